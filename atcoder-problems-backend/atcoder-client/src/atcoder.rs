@@ -1,8 +1,17 @@
+#[cfg(feature = "client")]
 mod client;
-mod contest;
-mod problem;
-mod submission;
+pub(crate) mod contest;
+#[cfg(feature = "client")]
+mod login;
+pub(crate) mod problem;
+pub(crate) mod submission;
 mod types;
+pub(crate) mod user;
 
+#[cfg(feature = "client")]
 pub use client::AtCoderClient;
-pub use types::{AtCoderContest, AtCoderProblem, AtCoderSubmission, AtCoderSubmissionListResponse, ContestTypeSpecifier};
+pub use types::{
+    AtCoderContest, AtCoderContestDetail, AtCoderProblem, AtCoderStandings, AtCoderStandingsEntry,
+    AtCoderStandingsResult, AtCoderSubmission, AtCoderSubmissionListResponse, AtCoderUserProfile,
+    ContestTypeSpecifier,
+};
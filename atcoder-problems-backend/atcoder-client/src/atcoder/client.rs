@@ -1,20 +1,65 @@
+use crate::parser::{self, ParseError};
 use crate::util;
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 
 use super::*;
-use surf::StatusCode;
+use surf::{Body, StatusCode};
 
 const ATCODER_PREFIX: &str = "https://atcoder.jp";
 
-pub struct AtCoderClient;
-
-impl Default for AtCoderClient {
-    fn default() -> Self {
-        Self
-    }
+#[derive(Clone, Default)]
+pub struct AtCoderClient {
+    session: Option<String>,
 }
 
 impl AtCoderClient {
+    /// Logs in to AtCoder, so that the client can fetch the pages which require login,
+    /// e.g. the standings.
+    pub async fn login(username: &str, password: &str) -> Result<Self> {
+        let url = format!("{}/login", ATCODER_PREFIX);
+        let mut response = surf::get(&url)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Connection error: {:?}", e))?;
+        let session = session_cookie(&response)
+            .ok_or_else(|| anyhow!("The login page does not give a session."))?;
+        let html = response
+            .body_string()
+            .await
+            .map_err(|e| anyhow!("Failed to parse HTTP body: {:?}", e))?;
+        let csrf_token = login::scrape_csrf_token(&html)?;
+
+        let form = Body::from_form(&[
+            ("username", username),
+            ("password", password),
+            ("csrf_token", &csrf_token),
+        ])
+        .map_err(|e| anyhow!("Failed to encode the login form: {:?}", e))?;
+        let response = surf::post(&url)
+            .header("cookie", session)
+            .body(form)
+            .send()
+            .await
+            .map_err(|e| anyhow!("Connection error: {:?}", e))?;
+
+        // AtCoder redirects to the login page again if the username or the password is wrong.
+        let location = response
+            .header("location")
+            .map(|location| location.as_str());
+        if !response.status().is_redirection() || location.map_or(true, |l| l.contains("/login")) {
+            bail!("Failed to log in to AtCoder as {}", username);
+        }
+        let session = session_cookie(&response)
+            .ok_or_else(|| anyhow!("AtCoder does not give a session after logging in."))?;
+        Ok(Self {
+            session: Some(session),
+        })
+    }
+
+    fn session(&self) -> Option<&str> {
+        self.session.as_deref()
+    }
+
     pub async fn fetch_atcoder_contests(
         &self,
         spf: ContestTypeSpecifier,
@@ -28,19 +73,19 @@ impl AtCoderClient {
 
     async fn fetch_atcoder_normal_contests(&self, page: u32) -> Result<Vec<AtCoderContest>> {
         let url = format!("{}/contests/archive?lang=ja&page={}", ATCODER_PREFIX, page);
-        let (html, _) = util::get_html(&url).await?;
-        contest::scrape_normal(&html)
+        let (html, _) = util::get_html(&url, self.session()).await?;
+        parser::parse_contest_list(&html).with_context(|| parse_error(&url))
     }
 
     async fn fetch_atcoder_permanent_contests(&self) -> Result<Vec<AtCoderContest>> {
         let url = format!("{}/contests/?lang=ja", ATCODER_PREFIX);
-        let (html, _) = util::get_html(&url).await?;
-        contest::scrape_permanent(&html)
+        let (html, _) = util::get_html(&url, self.session()).await?;
+        parser::parse_permanent_contest_list(&html).with_context(|| parse_error(&url))
     }
 
     async fn fetch_atcoder_hidden_contests(&self) -> Result<Vec<AtCoderContest>> {
         let uri = "https://kenkoooo.com/atcoder/static_data/backend/hidden_contests.json";
-        util::get_json(&uri).await
+        util::get_json(&uri, None).await
     }
 
     /// Fetch a list of submissions.
@@ -54,15 +99,29 @@ impl AtCoderClient {
             "{}/contests/{}/submissions?page={}",
             ATCODER_PREFIX, contest_id, page
         );
-        let (html, status) = util::get_html(&url).await?;
+        self.fetch_submission_page(&url, contest_id).await
+    }
+
+    /// Fetch a list of the submissions of the user in the contest.
+    pub async fn fetch_atcoder_user_submission_list(
+        &self,
+        contest_id: &str,
+        user_id: &str,
+        page: u32,
+    ) -> Result<AtCoderSubmissionListResponse> {
+        let url = user_submission_list_url(contest_id, user_id, page)?;
+        self.fetch_submission_page(&url, contest_id).await
+    }
+
+    async fn fetch_submission_page(
+        &self,
+        url: &str,
+        contest_id: &str,
+    ) -> Result<AtCoderSubmissionListResponse> {
+        let (html, status) = util::get_html(url, self.session()).await?;
 
         if status.is_success() {
-            let submissions = submission::scrape(&html, contest_id)?;
-            let max_page = submission::scrape_submission_page_count(&html)?;
-            Ok(AtCoderSubmissionListResponse {
-                max_page,
-                submissions,
-            })
+            parser::parse_submission_list(&html, contest_id).with_context(|| parse_error(url))
         } else if status == StatusCode::NotFound {
             log::warn!("404: {}", url);
             Ok(AtCoderSubmissionListResponse {
@@ -81,9 +140,65 @@ impl AtCoderClient {
 
     pub async fn fetch_problem_list(&self, contest_id: &str) -> Result<Vec<AtCoderProblem>> {
         let url = format!("{}/contests/{}/tasks", ATCODER_PREFIX, contest_id);
-        let (html, _) = util::get_html(&url).await?;
-        problem::scrape(&html, contest_id)
+        let (html, _) = util::get_html(&url, self.session()).await?;
+        parser::parse_problem_list(&html, contest_id).with_context(|| parse_error(&url))
+    }
+
+    pub async fn fetch_atcoder_contest_detail(
+        &self,
+        contest_id: &str,
+    ) -> Result<AtCoderContestDetail> {
+        let url = format!("{}/contests/{}?lang=en", ATCODER_PREFIX, contest_id);
+        let (html, _) = util::get_html(&url, self.session()).await?;
+        parser::parse_contest_detail(&html).with_context(|| parse_error(&url))
     }
+
+    /// Returns `None` if the user does not exist, e.g. the account is deleted.
+    pub async fn fetch_atcoder_user_profile(
+        &self,
+        user_id: &str,
+    ) -> Result<Option<AtCoderUserProfile>> {
+        let url = format!("{}/users/{}?lang=en", ATCODER_PREFIX, user_id);
+        let (html, status) = util::get_html(&url, self.session()).await?;
+        if status == StatusCode::NotFound {
+            log::warn!("404: {}", url);
+            return Ok(None);
+        }
+        if !status.is_success() {
+            bail!("Failed to fetch {}: status={}", url, status);
+        }
+        parser::parse_user_profile(&html, user_id)
+            .with_context(|| parse_error(&url))
+            .map(Some)
+    }
+
+    pub async fn fetch_atcoder_standings(&self, contest_id: &str) -> Result<AtCoderStandings> {
+        let url = format!("{}/contests/{}/standings/json", ATCODER_PREFIX, contest_id);
+        util::get_json(&url, self.session()).await
+    }
+}
+
+fn parse_error(url: &str) -> ParseError {
+    ParseError {
+        url: url.to_owned(),
+    }
+}
+
+/// The user id is percent-encoded, since it may come from the requests of the users.
+fn user_submission_list_url(contest_id: &str, user_id: &str, page: u32) -> Result<String> {
+    let mut url = surf::Url::parse(&format!(
+        "{}/contests/{}/submissions",
+        ATCODER_PREFIX, contest_id
+    ))?;
+    url.query_pairs_mut()
+        .append_pair("f.User", user_id)
+        .append_pair("page", &page.to_string());
+    Ok(url.into())
+}
+
+fn session_cookie(response: &surf::Response) -> Option<String> {
+    let set_cookies = response.header("set-cookie")?;
+    login::find_session_cookie(set_cookies.iter().map(|value| value.as_str()))
 }
 
 #[cfg(test)]
@@ -91,6 +206,31 @@ mod tests {
     use super::*;
     use futures::executor::block_on;
 
+    #[test]
+    fn test_parse_error() {
+        let e = parser::parse_problem_list("<html></html>", "abc001")
+            .with_context(|| parse_error("https://atcoder.jp/contests/abc001/tasks"))
+            .context("Failed to crawl abc001")
+            .unwrap_err();
+        assert_eq!(
+            e.downcast_ref::<ParseError>().unwrap().url,
+            "https://atcoder.jp/contests/abc001/tasks"
+        );
+        assert!(anyhow!("timeout").downcast_ref::<ParseError>().is_none());
+    }
+
+    #[test]
+    fn test_user_submission_list_url() {
+        assert_eq!(
+            user_submission_list_url("abc001", "user1", 2).unwrap(),
+            "https://atcoder.jp/contests/abc001/submissions?f.User=user1&page=2"
+        );
+        assert_eq!(
+            user_submission_list_url("abc001", "a&page=100#", 1).unwrap(),
+            "https://atcoder.jp/contests/abc001/submissions?f.User=a%26page%3D100%23&page=1"
+        );
+    }
+
     #[test]
     fn test_fetch_contest_list() {
         let client = AtCoderClient::default();
@@ -1,13 +1,13 @@
 use anyhow::{Result, anyhow};
 
-use super::AtCoderContest;
+use super::{AtCoderContest, AtCoderContestDetail};
 
 use chrono::DateTime;
 use scraper::{Html, Selector};
 
 const PERMANENT_CONTEST_DURATION_SECOND: u64 = 100 * 365 * 24 * 3600;
 
-pub(super) fn scrape_normal(html: &str) -> Result<Vec<AtCoderContest>> {
+pub(crate) fn scrape_normal(html: &str) -> Result<Vec<AtCoderContest>> {
     Html::parse_document(html)
         .select(&Selector::parse("tbody").unwrap())
         .next()
@@ -73,7 +73,7 @@ pub(super) fn scrape_normal(html: &str) -> Result<Vec<AtCoderContest>> {
         .collect()
 }
 
-pub(super) fn scrape_permanent(html: &str) -> Result<Vec<AtCoderContest>> {
+pub(crate) fn scrape_permanent(html: &str) -> Result<Vec<AtCoderContest>> {
     Html::parse_document(html)
         .select(&Selector::parse("#contest-table-permanent").unwrap())
         .next()
@@ -123,6 +123,44 @@ pub(super) fn scrape_permanent(html: &str) -> Result<Vec<AtCoderContest>> {
         .collect()
 }
 
+/// Scrapes the rules in the English top page of a contest, which are shown as
+/// `<span>Penalty: 5 minutes</span>` and so on. Late registration is allowed unless the page says
+/// otherwise, since it is allowed in most of the contests.
+pub(crate) fn scrape_detail(html: &str) -> Result<AtCoderContestDetail> {
+    let document = Html::parse_document(html);
+    let mut penalty_minutes = None;
+    let mut allows_late_join = true;
+    for span in document.select(&Selector::parse("span").unwrap()) {
+        let text = span.text().collect::<String>();
+        let mut pair = text.splitn(2, ':').map(|s| s.trim());
+        let (key, value) = match (pair.next(), pair.next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => continue,
+        };
+        match key {
+            "Penalty" => {
+                let minutes = if value == "None" {
+                    0
+                } else {
+                    value
+                        .trim_end_matches("minutes")
+                        .trim_end_matches("minute")
+                        .trim()
+                        .parse::<i64>()
+                        .map_err(|_| anyhow!("Invalid penalty: {}", value))?
+                };
+                penalty_minutes = Some(minutes);
+            }
+            "Late Registration" => allows_late_join = value != "Not Allowed",
+            _ => {}
+        }
+    }
+    Ok(AtCoderContestDetail {
+        penalty_minutes,
+        allows_late_join,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -148,4 +186,29 @@ mod tests {
         let contests = scrape_permanent(&contents).unwrap();
         assert_eq!(contests.len(), 4);
     }
+
+    #[test]
+    fn test_scrape_detail() {
+        let mut file = File::open("test_resources/abc200_top").unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+
+        let detail = scrape_detail(&contents).unwrap();
+        assert_eq!(
+            detail,
+            AtCoderContestDetail {
+                penalty_minutes: Some(5),
+                allows_late_join: true,
+            }
+        );
+
+        let html = "<span>Penalty: None</span><span>Late Registration: Not Allowed</span>";
+        let detail = scrape_detail(html).unwrap();
+        assert_eq!(detail.penalty_minutes, Some(0));
+        assert!(!detail.allows_late_join);
+
+        let detail = scrape_detail("<span>Can Participate: All</span>").unwrap();
+        assert_eq!(detail.penalty_minutes, None);
+        assert!(scrape_detail("<span>Penalty: forever</span>").is_err());
+    }
 }
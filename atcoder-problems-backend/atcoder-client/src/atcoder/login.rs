@@ -0,0 +1,55 @@
+use anyhow::{anyhow, Result};
+use scraper::{Html, Selector};
+
+const SESSION_COOKIE_NAME: &str = "REVEL_SESSION";
+
+pub(super) fn scrape_csrf_token(html: &str) -> Result<String> {
+    Html::parse_document(html)
+        .select(&Selector::parse("input[name=csrf_token]").unwrap())
+        .next()
+        .and_then(|input| input.value().attr("value"))
+        .map(|token| token.to_owned())
+        .ok_or_else(|| anyhow!("Failed to find csrf_token in the login page."))
+}
+
+/// Finds the session in the `Set-Cookie` headers, and returns it in the form of the `Cookie` header.
+pub(super) fn find_session_cookie<'a, I: IntoIterator<Item = &'a str>>(
+    set_cookies: I,
+) -> Option<String> {
+    set_cookies
+        .into_iter()
+        .filter_map(|set_cookie| set_cookie.split(';').next())
+        .map(|cookie| cookie.trim())
+        .find(|cookie| cookie.starts_with(&format!("{}=", SESSION_COOKIE_NAME)))
+        .map(|cookie| cookie.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrape_csrf_token() {
+        let html = r#"
+        <form action="" method="POST">
+            <input type="hidden" name="csrf_token" value="abc+def="/>
+            <input type="text" name="username"/>
+        </form>
+        "#;
+        assert_eq!(scrape_csrf_token(html).unwrap(), "abc+def=");
+        assert!(scrape_csrf_token("<form></form>").is_err());
+    }
+
+    #[test]
+    fn test_find_session_cookie() {
+        let set_cookies = vec![
+            "timeDelta=0; Path=/",
+            "REVEL_SESSION=abc%00def; Path=/; Expires=Sat, 01 Jan 2022 00:00:00 GMT; HttpOnly",
+        ];
+        assert_eq!(
+            find_session_cookie(set_cookies),
+            Some("REVEL_SESSION=abc%00def".to_owned())
+        );
+        assert_eq!(find_session_cookie(vec!["timeDelta=0; Path=/"]), None);
+    }
+}
@@ -4,7 +4,7 @@ use super::AtCoderProblem;
 
 use scraper::{Html, Selector};
 
-pub(super) fn scrape(html: &str, contest_id: &str) -> Result<Vec<AtCoderProblem>> {
+pub(crate) fn scrape(html: &str, contest_id: &str) -> Result<Vec<AtCoderProblem>> {
     Html::parse_document(html)
         .select(&Selector::parse("tbody").unwrap())
         .next()
@@ -6,7 +6,7 @@ use chrono::DateTime;
 use regex::Regex;
 use scraper::{Html, Selector};
 
-pub(super) fn scrape_submission_page_count(html: &str) -> Result<u32> {
+pub(crate) fn scrape_submission_page_count(html: &str) -> Result<u32> {
     let selector = Selector::parse("a").unwrap();
     let re = Regex::new(r"page=\d+$").unwrap();
     Html::parse_document(&html)
@@ -19,7 +19,7 @@ pub(super) fn scrape_submission_page_count(html: &str) -> Result<u32> {
         .ok_or_else(|| anyhow!("Failed to parse html."))
 }
 
-pub(super) fn scrape(html_text: &str, contest_id: &str) -> Result<Vec<AtCoderSubmission>> {
+pub(crate) fn scrape(html_text: &str, contest_id: &str) -> Result<Vec<AtCoderSubmission>> {
     let tbody_selector = Selector::parse("tbody").unwrap();
     let tr_selector = Selector::parse("tr").unwrap();
     let td_selector = Selector::parse("td").unwrap();
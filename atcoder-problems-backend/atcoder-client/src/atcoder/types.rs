@@ -1,12 +1,13 @@
 use crate::util::Problem;
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize};
 
 pub enum ContestTypeSpecifier {
-    Normal {page: u32},
+    Normal { page: u32 },
     Permanent,
-    Hidden
+    Hidden,
 }
 
+#[derive(Serialize)]
 pub struct AtCoderSubmissionListResponse {
     pub max_page: u32,
     pub submissions: Vec<AtCoderSubmission>,
@@ -21,7 +22,32 @@ pub struct AtCoderContest {
     pub rate_change: String,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// The rules shown on the top page of a contest, which are not in the contest list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AtCoderContestDetail {
+    /// The penalty minutes for each wrong submission, or 0 if there is no penalty.
+    /// `None` if the page does not show the penalty, e.g. for some of the old contests.
+    pub penalty_minutes: Option<i64>,
+    /// Whether users can register for the contest after it starts.
+    pub allows_late_join: bool,
+}
+
+/// The profile of a user, where the missing fields are `None`, e.g. the rating of a user who has
+/// never joined a rated contest.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AtCoderUserProfile {
+    pub user_id: String,
+    pub rating: Option<i64>,
+    pub highest_rating: Option<i64>,
+    pub affiliation: Option<String>,
+    /// The code of the country or the region, e.g. `JP`.
+    pub country: Option<String>,
+    pub birth_year: Option<i32>,
+    /// The number of the rated contests the user has joined.
+    pub rated_matches: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct AtCoderSubmission {
     pub id: u64,
     pub epoch_second: u64,
@@ -35,6 +61,32 @@ pub struct AtCoderSubmission {
     pub execution_time: Option<u64>,
 }
 
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AtCoderStandings {
+    pub standings_data: Vec<AtCoderStandingsEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AtCoderStandingsEntry {
+    pub rank: u64,
+    pub user_screen_name: String,
+    pub is_rated: bool,
+    pub old_rating: i64,
+    pub competitions: u64,
+    pub total_result: AtCoderStandingsResult,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AtCoderStandingsResult {
+    pub count: u64,
+    pub score: i64,
+    pub elapsed: i64,
+    pub penalty: u64,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AtCoderProblem {
     pub id: String,
@@ -68,4 +120,24 @@ mod tests {
             problem.url()
         );
     }
+
+    #[test]
+    fn test_deserialize_standings() {
+        let json = r#"{
+            "StandingsData": [
+                {
+                    "Rank": 1,
+                    "UserScreenName": "tourist",
+                    "IsRated": true,
+                    "OldRating": 3800,
+                    "Competitions": 50,
+                    "TotalResult": {"Count": 6, "Score": 210000, "Elapsed": 600000000000, "Penalty": 0}
+                }
+            ]
+        }"#;
+        let standings: AtCoderStandings = serde_json::from_str(json).unwrap();
+        assert_eq!(standings.standings_data.len(), 1);
+        assert_eq!(standings.standings_data[0].user_screen_name, "tourist");
+        assert_eq!(standings.standings_data[0].total_result.count, 6);
+    }
 }
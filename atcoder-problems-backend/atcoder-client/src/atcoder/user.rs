@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+
+use super::AtCoderUserProfile;
+
+use scraper::{ElementRef, Html, Selector};
+
+/// Scrapes the profile page of a user in English, where the profile and the rating are shown
+/// as the rows of `table.dl-table`, e.g. `<tr><th>Rating</th><td>...</td></tr>`.
+pub(crate) fn scrape_profile(html: &str, user_id: &str) -> Result<AtCoderUserProfile> {
+    let document = Html::parse_document(html);
+    let tables = document
+        .select(&Selector::parse("table.dl-table").unwrap())
+        .collect::<Vec<_>>();
+    if tables.is_empty() {
+        return Err(anyhow!("Failed to parse html."));
+    }
+
+    let mut profile = AtCoderUserProfile {
+        user_id: user_id.to_owned(),
+        ..Default::default()
+    };
+    let th = Selector::parse("th").unwrap();
+    let td = Selector::parse("td").unwrap();
+    let tr = Selector::parse("tr").unwrap();
+    for tr in tables.iter().flat_map(|table| table.select(&tr)) {
+        let (key, value) = match (tr.select(&th).next(), tr.select(&td).next()) {
+            (Some(key), Some(value)) => (key, value),
+            _ => continue,
+        };
+        let key = key.text().next().unwrap_or_default().trim();
+        match key {
+            "Country/Region" => profile.country = scrape_country(value),
+            "Birth Year" => profile.birth_year = text(value).and_then(|y| y.parse().ok()),
+            "Affiliation" => profile.affiliation = text(value),
+            "Rating" => profile.rating = Some(scrape_rating(value)?),
+            "Highest Rating" => profile.highest_rating = Some(scrape_rating(value)?),
+            "Rated Matches" => profile.rated_matches = text(value).and_then(|n| n.parse().ok()),
+            _ => {}
+        }
+    }
+    Ok(profile)
+}
+
+fn text(element: ElementRef) -> Option<String> {
+    let text = element.text().collect::<String>();
+    let text = text.trim();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_owned())
+    }
+}
+
+/// The code of the country, e.g. `JP` of `<img src="//img.atcoder.jp/assets/flag/JP.png">`.
+fn scrape_country(element: ElementRef) -> Option<String> {
+    element
+        .select(&Selector::parse("img").unwrap())
+        .next()
+        .and_then(|img| img.value().attr("src"))
+        .and_then(|src| src.rsplit('/').next())
+        .and_then(|name| name.strip_suffix(".png"))
+        .map(|code| code.to_owned())
+}
+
+/// The rating is the first `span`, which is followed by the rank and so on in "Highest Rating".
+fn scrape_rating(element: ElementRef) -> Result<i64> {
+    let rating = element
+        .select(&Selector::parse("span").unwrap())
+        .next()
+        .and_then(|span| span.text().next())
+        .ok_or_else(|| anyhow!("Failed to parse html."))?
+        .trim()
+        .parse::<i64>()?;
+    Ok(rating)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::Read;
+
+    #[test]
+    fn test_scrape_profile() {
+        let mut file = File::open("test_resources/user_profile").unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+
+        let profile = scrape_profile(&contents, "kenkoooo").unwrap();
+        assert_eq!(
+            profile,
+            AtCoderUserProfile {
+                user_id: "kenkoooo".to_owned(),
+                rating: Some(1832),
+                highest_rating: Some(2034),
+                affiliation: Some("AtCoder Problems".to_owned()),
+                country: Some("JP".to_owned()),
+                birth_year: Some(1993),
+                rated_matches: Some(42),
+            }
+        );
+    }
+
+    #[test]
+    fn test_scrape_unrated_profile() {
+        let html = r#"<table class="dl-table"><tr><th>Country/Region</th><td></td></tr>
+            <tr><th>Affiliation</th><td> </td></tr></table>"#;
+        let profile = scrape_profile(html, "newcomer").unwrap();
+        assert_eq!(
+            profile,
+            AtCoderUserProfile {
+                user_id: "newcomer".to_owned(),
+                ..Default::default()
+            }
+        );
+        assert!(scrape_profile("<html></html>", "newcomer").is_err());
+    }
+}
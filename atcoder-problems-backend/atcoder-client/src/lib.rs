@@ -1,6 +1,22 @@
+//! A client of AtCoder, which fetches and parses the pages of the contests, the submissions and the
+//! standings. It does not depend on the database, and the parsers in [`parser`] can be used without
+//! the network. The parsers and the rating helpers in [`rating`] are also built for wasm32 with the
+//! `wasm` feature, so that the frontend can share them, and the client is exposed to Python with the
+//! `python` feature.
+
 pub(crate) mod atcoder;
+#[cfg(feature = "client")]
+pub use atcoder::AtCoderClient;
 pub use atcoder::{
-    AtCoderClient, AtCoderContest, AtCoderProblem, AtCoderSubmission, AtCoderSubmissionListResponse, ContestTypeSpecifier
+    AtCoderContest, AtCoderContestDetail, AtCoderProblem, AtCoderStandings, AtCoderStandingsEntry,
+    AtCoderStandingsResult, AtCoderSubmission, AtCoderSubmissionListResponse, AtCoderUserProfile,
+    ContestTypeSpecifier,
 };
 
+pub mod parser;
+#[cfg(feature = "python")]
+mod python;
+pub mod rating;
 pub(crate) mod util;
+#[cfg(feature = "wasm")]
+mod wasm;
@@ -0,0 +1,93 @@
+//! Parsers of the pages of AtCoder, which take the fetched bodies and do no I/O, so that the tools
+//! which fetch the pages by themselves can reuse them. `AtCoderClient` fetches the pages and
+//! passes them to these parsers.
+
+use crate::atcoder::{contest, problem, submission, user};
+use crate::{
+    AtCoderContest, AtCoderContestDetail, AtCoderProblem, AtCoderStandings,
+    AtCoderSubmissionListResponse, AtCoderUserProfile,
+};
+use anyhow::Result;
+use std::fmt;
+
+/// The context of the errors of the parsers in `AtCoderClient`, which tells the pages changed by
+/// AtCoder from the network errors, e.g. by `error.downcast_ref::<ParseError>()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub url: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse {}", self.url)
+    }
+}
+
+/// Parses a page of `/contests/archive`.
+pub fn parse_contest_list(html: &str) -> Result<Vec<AtCoderContest>> {
+    contest::scrape_normal(html)
+}
+
+/// Parses the permanent contests in `/contests/`.
+pub fn parse_permanent_contest_list(html: &str) -> Result<Vec<AtCoderContest>> {
+    contest::scrape_permanent(html)
+}
+
+/// Parses the rules of a contest in `/contests/{contest_id}?lang=en`.
+pub fn parse_contest_detail(html: &str) -> Result<AtCoderContestDetail> {
+    contest::scrape_detail(html)
+}
+
+/// Parses a page of `/contests/{contest_id}/submissions`, with the number of the pages.
+pub fn parse_submission_list(
+    html: &str,
+    contest_id: &str,
+) -> Result<AtCoderSubmissionListResponse> {
+    let submissions = submission::scrape(html, contest_id)?;
+    let max_page = submission::scrape_submission_page_count(html)?;
+    Ok(AtCoderSubmissionListResponse {
+        max_page,
+        submissions,
+    })
+}
+
+/// Parses `/contests/{contest_id}/tasks`.
+pub fn parse_problem_list(html: &str, contest_id: &str) -> Result<Vec<AtCoderProblem>> {
+    problem::scrape(html, contest_id)
+}
+
+/// Parses `/contests/{contest_id}/standings/json`.
+pub fn parse_standings(json: &str) -> Result<AtCoderStandings> {
+    let standings = serde_json::from_str(json)?;
+    Ok(standings)
+}
+
+/// Parses `/users/{user_id}?lang=en`.
+pub fn parse_user_profile(html: &str, user_id: &str) -> Result<AtCoderUserProfile> {
+    user::scrape_profile(html, user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_submission_list() {
+        let html = fs::read_to_string("test_resources/abc107_submissions").unwrap();
+        let response = parse_submission_list(&html, "abc107").unwrap();
+        assert_eq!(response.submissions.len(), 20);
+        assert_eq!(response.max_page, 2208);
+        assert!(response
+            .submissions
+            .iter()
+            .all(|s| s.contest_id == "abc107"));
+    }
+
+    #[test]
+    fn test_parse_standings() {
+        let json = r#"{"StandingsData": []}"#;
+        assert!(parse_standings(json).unwrap().standings_data.is_empty());
+        assert!(parse_standings("<html></html>").is_err());
+    }
+}
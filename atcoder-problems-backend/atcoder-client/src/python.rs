@@ -0,0 +1,317 @@
+//! The Python module `atcoder_client`, which exposes the client and the parsers to the analysis
+//! scripts. The requests block the calling thread, but they release the GIL while waiting.
+
+use crate::{
+    parser, AtCoderClient, AtCoderContest, AtCoderProblem, AtCoderSubmission,
+    AtCoderSubmissionListResponse, AtCoderUserProfile, ContestTypeSpecifier,
+};
+use futures::executor::block_on;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    PyRuntimeError::new_err(format!("{:?}", e))
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Contest {
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub start_epoch_second: u64,
+    #[pyo3(get)]
+    pub duration_second: u64,
+    #[pyo3(get)]
+    pub title: String,
+    #[pyo3(get)]
+    pub rate_change: String,
+}
+
+impl From<AtCoderContest> for Contest {
+    fn from(contest: AtCoderContest) -> Self {
+        Self {
+            id: contest.id,
+            start_epoch_second: contest.start_epoch_second,
+            duration_second: contest.duration_second,
+            title: contest.title,
+            rate_change: contest.rate_change,
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Problem {
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub contest_id: String,
+    #[pyo3(get)]
+    pub title: String,
+    #[pyo3(get)]
+    pub position: String,
+}
+
+impl From<AtCoderProblem> for Problem {
+    fn from(problem: AtCoderProblem) -> Self {
+        Self {
+            id: problem.id,
+            contest_id: problem.contest_id,
+            title: problem.title,
+            position: problem.position,
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct Submission {
+    #[pyo3(get)]
+    pub id: u64,
+    #[pyo3(get)]
+    pub epoch_second: u64,
+    #[pyo3(get)]
+    pub problem_id: String,
+    #[pyo3(get)]
+    pub contest_id: String,
+    #[pyo3(get)]
+    pub user_id: String,
+    #[pyo3(get)]
+    pub language: String,
+    #[pyo3(get)]
+    pub point: f64,
+    #[pyo3(get)]
+    pub length: u64,
+    #[pyo3(get)]
+    pub result: String,
+    #[pyo3(get)]
+    pub execution_time: Option<u64>,
+}
+
+impl From<AtCoderSubmission> for Submission {
+    fn from(submission: AtCoderSubmission) -> Self {
+        Self {
+            id: submission.id,
+            epoch_second: submission.epoch_second,
+            problem_id: submission.problem_id,
+            contest_id: submission.contest_id,
+            user_id: submission.user_id,
+            language: submission.language,
+            point: submission.point,
+            length: submission.length,
+            result: submission.result,
+            execution_time: submission.execution_time,
+        }
+    }
+}
+
+/// A page of the submissions, with the number of the pages.
+#[pyclass]
+#[derive(Clone)]
+pub struct SubmissionList {
+    #[pyo3(get)]
+    pub max_page: u32,
+    #[pyo3(get)]
+    pub submissions: Vec<Submission>,
+}
+
+impl From<AtCoderSubmissionListResponse> for SubmissionList {
+    fn from(response: AtCoderSubmissionListResponse) -> Self {
+        Self {
+            max_page: response.max_page,
+            submissions: response.submissions.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+pub struct UserProfile {
+    #[pyo3(get)]
+    pub user_id: String,
+    #[pyo3(get)]
+    pub rating: Option<i64>,
+    #[pyo3(get)]
+    pub highest_rating: Option<i64>,
+    #[pyo3(get)]
+    pub affiliation: Option<String>,
+    #[pyo3(get)]
+    pub country: Option<String>,
+    #[pyo3(get)]
+    pub birth_year: Option<i32>,
+    #[pyo3(get)]
+    pub rated_matches: Option<i64>,
+}
+
+impl From<AtCoderUserProfile> for UserProfile {
+    fn from(profile: AtCoderUserProfile) -> Self {
+        Self {
+            user_id: profile.user_id,
+            rating: profile.rating,
+            highest_rating: profile.highest_rating,
+            affiliation: profile.affiliation,
+            country: profile.country,
+            birth_year: profile.birth_year,
+            rated_matches: profile.rated_matches,
+        }
+    }
+}
+
+fn convert<T, U: From<T>>(items: Vec<T>) -> Vec<U> {
+    items.into_iter().map(U::from).collect()
+}
+
+#[pyclass]
+pub struct Client {
+    inner: AtCoderClient,
+}
+
+#[pymethods]
+impl Client {
+    #[new]
+    fn new() -> Self {
+        Self {
+            inner: AtCoderClient::default(),
+        }
+    }
+
+    #[staticmethod]
+    fn login(py: Python, username: &str, password: &str) -> PyResult<Self> {
+        let inner = py
+            .allow_threads(|| block_on(AtCoderClient::login(username, password)))
+            .map_err(to_py_err)?;
+        Ok(Self { inner })
+    }
+
+    fn fetch_contests(&self, py: Python, page: u32) -> PyResult<Vec<Contest>> {
+        let spf = ContestTypeSpecifier::Normal { page };
+        let contests = py
+            .allow_threads(|| block_on(self.inner.fetch_atcoder_contests(spf)))
+            .map_err(to_py_err)?;
+        Ok(convert(contests))
+    }
+
+    fn fetch_permanent_contests(&self, py: Python) -> PyResult<Vec<Contest>> {
+        let spf = ContestTypeSpecifier::Permanent;
+        let contests = py
+            .allow_threads(|| block_on(self.inner.fetch_atcoder_contests(spf)))
+            .map_err(to_py_err)?;
+        Ok(convert(contests))
+    }
+
+    fn fetch_problems(&self, py: Python, contest_id: &str) -> PyResult<Vec<Problem>> {
+        let problems = py
+            .allow_threads(|| block_on(self.inner.fetch_problem_list(contest_id)))
+            .map_err(to_py_err)?;
+        Ok(convert(problems))
+    }
+
+    #[args(page = "None")]
+    fn fetch_submissions(
+        &self,
+        py: Python,
+        contest_id: &str,
+        page: Option<u32>,
+    ) -> PyResult<SubmissionList> {
+        let response = py
+            .allow_threads(|| block_on(self.inner.fetch_atcoder_submission_list(contest_id, page)))
+            .map_err(to_py_err)?;
+        Ok(response.into())
+    }
+
+    /// Returns `None` if the user does not exist.
+    fn fetch_user_profile(&self, py: Python, user_id: &str) -> PyResult<Option<UserProfile>> {
+        let profile = py
+            .allow_threads(|| block_on(self.inner.fetch_atcoder_user_profile(user_id)))
+            .map_err(to_py_err)?;
+        Ok(profile.map(Into::into))
+    }
+}
+
+#[pyfunction]
+fn parse_contest_list(html: &str) -> PyResult<Vec<Contest>> {
+    let contests = parser::parse_contest_list(html).map_err(to_py_err)?;
+    Ok(convert(contests))
+}
+
+#[pyfunction]
+fn parse_problem_list(html: &str, contest_id: &str) -> PyResult<Vec<Problem>> {
+    let problems = parser::parse_problem_list(html, contest_id).map_err(to_py_err)?;
+    Ok(convert(problems))
+}
+
+#[pyfunction]
+fn parse_submission_list(html: &str, contest_id: &str) -> PyResult<SubmissionList> {
+    let response = parser::parse_submission_list(html, contest_id).map_err(to_py_err)?;
+    Ok(response.into())
+}
+
+#[pyfunction]
+fn parse_user_profile(html: &str, user_id: &str) -> PyResult<UserProfile> {
+    let profile = parser::parse_user_profile(html, user_id).map_err(to_py_err)?;
+    Ok(profile.into())
+}
+
+#[pymodule]
+fn atcoder_client(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<Client>()?;
+    m.add_class::<Contest>()?;
+    m.add_class::<Problem>()?;
+    m.add_class::<Submission>()?;
+    m.add_class::<SubmissionList>()?;
+    m.add_class::<UserProfile>()?;
+    m.add_function(wrap_pyfunction!(parse_contest_list, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_problem_list, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_submission_list, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_user_profile, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_parse_user_profile() {
+        let html = fs::read_to_string("test_resources/user_profile").unwrap();
+        let profile = parse_user_profile(&html, "kenkoooo").unwrap();
+        assert_eq!(profile.user_id, "kenkoooo");
+        assert_eq!(profile.rating, Some(1832));
+        assert_eq!(profile.highest_rating, Some(2034));
+        assert_eq!(profile.affiliation.as_deref(), Some("AtCoder Problems"));
+        assert_eq!(profile.country.as_deref(), Some("JP"));
+        assert_eq!(profile.birth_year, Some(1993));
+        assert_eq!(profile.rated_matches, Some(42));
+
+        let profile = UserProfile::from(AtCoderUserProfile {
+            user_id: "newcomer".to_owned(),
+            ..Default::default()
+        });
+        assert_eq!(profile.rating, None);
+        assert_eq!(profile.rated_matches, None);
+    }
+
+    #[test]
+    fn test_parse_submission_list() {
+        let html = fs::read_to_string("test_resources/abc107_submissions").unwrap();
+        let expected = parser::parse_submission_list(&html, "abc107").unwrap();
+        let list = parse_submission_list(&html, "abc107").unwrap();
+        assert_eq!(list.max_page, 2208);
+        assert_eq!(list.submissions.len(), expected.submissions.len());
+        for (submission, expected) in list.submissions.iter().zip(expected.submissions) {
+            assert_eq!(submission.id, expected.id);
+            assert_eq!(submission.epoch_second, expected.epoch_second);
+            assert_eq!(submission.problem_id, expected.problem_id);
+            assert_eq!(submission.contest_id, expected.contest_id);
+            assert_eq!(submission.user_id, expected.user_id);
+            assert_eq!(submission.language, expected.language);
+            assert_eq!(submission.point, expected.point);
+            assert_eq!(submission.length, expected.length);
+            assert_eq!(submission.result, expected.result);
+            assert_eq!(submission.execution_time, expected.execution_time);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+//! The rating system of AtCoder and the difficulties of the problems, which are shared with the
+//! frontend through the `wasm` feature.
+
+use std::collections::BTreeMap;
+
+const PERFORMANCE_SEARCH_LOWER_BOUND: f64 = -10000.0;
+const PERFORMANCE_SEARCH_UPPER_BOUND: f64 = 10000.0;
+const RATING_WEIGHT_DECAY: f64 = 0.9;
+
+/// Parameters of the rating system which differ between contests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatingRule {
+    /// The highest rating which is rated in the contest, or `None` if there is no upper bound.
+    pub rated_upper_bound: Option<i64>,
+    /// The performance assumed for participants who have never joined a rated contest.
+    pub default_performance: f64,
+}
+
+impl RatingRule {
+    pub fn new(contest_id: &str, rated_upper_bound: Option<i64>) -> Self {
+        let default_performance = if contest_id.starts_with("agc") {
+            1600.0
+        } else if contest_id.starts_with("arc") {
+            1200.0
+        } else {
+            800.0
+        };
+        Self {
+            rated_upper_bound,
+            default_performance,
+        }
+    }
+
+    /// The highest performance which can be achieved in the contest.
+    pub fn performance_cap(&self) -> Option<f64> {
+        self.rated_upper_bound.map(|upper| (upper + 401) as f64)
+    }
+}
+
+/// Estimates the performances of the participants ranked at `ranks` (1-origin) among the
+/// participants whose average performances are `average_performances`.
+pub fn estimate_performances(
+    average_performances: &[f64],
+    ranks: &[f64],
+    rule: &RatingRule,
+) -> Vec<f64> {
+    // The binary searches for different ranks share most of their midpoints.
+    let mut expected_rank_cache = BTreeMap::new();
+    ranks
+        .iter()
+        .map(|&rank| {
+            let mut lower = PERFORMANCE_SEARCH_LOWER_BOUND;
+            let mut upper = PERFORMANCE_SEARCH_UPPER_BOUND;
+            while upper - lower > 0.5 {
+                let mid = (lower + upper) / 2.0;
+                let expected_rank =
+                    *expected_rank_cache.entry(mid.to_bits()).or_insert_with(|| {
+                        average_performances
+                            .iter()
+                            .map(|a| 1.0 / (1.0 + 6.0f64.powf((mid - a) / 400.0)))
+                            .sum::<f64>()
+                    });
+                if expected_rank < rank - 0.5 {
+                    upper = mid;
+                } else {
+                    lower = mid;
+                }
+            }
+
+            let performance = positivize_rating(lower);
+            match rule.performance_cap() {
+                Some(cap) => performance.min(cap),
+                None => performance,
+            }
+        })
+        .collect()
+}
+
+/// Calculates the rating after a contest, given the rating and the number of rated contests before it.
+pub fn next_rating(rating: i64, competitions: u64, performance: f64) -> f64 {
+    let old_weight = (1..=competitions)
+        .map(|i| RATING_WEIGHT_DECAY.powi(i as i32))
+        .sum::<f64>();
+    let old_exp_sum = if competitions == 0 {
+        0.0
+    } else {
+        2.0f64.powf(unadjust_rating(rating, competitions) / 800.0) * old_weight
+    };
+
+    let exp_sum = RATING_WEIGHT_DECAY * (2.0f64.powf(performance / 800.0) + old_exp_sum);
+    let weight = RATING_WEIGHT_DECAY * (1.0 + old_weight);
+    let raw_rating = (exp_sum / weight).log2() * 800.0;
+    adjust_rating(raw_rating, competitions + 1)
+}
+
+/// Converts a displayed rating to the raw rating, which is not discounted by the number of contests.
+pub fn unadjust_rating(rating: i64, competitions: u64) -> f64 {
+    let rating = rating as f64;
+    let rating = if rating <= 400.0 {
+        400.0 * (1.0 - (400.0 / rating.max(1.0)).ln())
+    } else {
+        rating
+    };
+    rating + competition_discount(competitions)
+}
+
+/// Converts a raw rating to the displayed rating.
+pub fn adjust_rating(raw_rating: f64, competitions: u64) -> f64 {
+    positivize_rating(raw_rating - competition_discount(competitions))
+}
+
+/// The difficulty shown to the users, which is positive like the ratings.
+pub fn clip_difficulty(difficulty: f64) -> f64 {
+    if difficulty >= 400.0 {
+        difficulty.round()
+    } else {
+        (400.0 / (1.0 - difficulty / 400.0).exp()).round()
+    }
+}
+
+/// The colors of the ratings and the difficulties, which change every 400.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RatingColor {
+    Black,
+    Grey,
+    Brown,
+    Green,
+    Cyan,
+    Blue,
+    Yellow,
+    Orange,
+    Red,
+}
+
+impl RatingColor {
+    pub fn from_rating(rating: f64) -> Self {
+        let index = (rating / 400.0).floor();
+        if index < 0.0 {
+            RatingColor::Black
+        } else if index < 1.0 {
+            RatingColor::Grey
+        } else if index < 2.0 {
+            RatingColor::Brown
+        } else if index < 3.0 {
+            RatingColor::Green
+        } else if index < 4.0 {
+            RatingColor::Cyan
+        } else if index < 5.0 {
+            RatingColor::Blue
+        } else if index < 6.0 {
+            RatingColor::Yellow
+        } else if index < 7.0 {
+            RatingColor::Orange
+        } else {
+            RatingColor::Red
+        }
+    }
+
+    /// The name used in the frontend, e.g. `Grey`.
+    pub fn name(self) -> &'static str {
+        match self {
+            RatingColor::Black => "Black",
+            RatingColor::Grey => "Grey",
+            RatingColor::Brown => "Brown",
+            RatingColor::Green => "Green",
+            RatingColor::Cyan => "Cyan",
+            RatingColor::Blue => "Blue",
+            RatingColor::Yellow => "Yellow",
+            RatingColor::Orange => "Orange",
+            RatingColor::Red => "Red",
+        }
+    }
+}
+
+fn competition_discount(competitions: u64) -> f64 {
+    if competitions == 0 {
+        return 0.0;
+    }
+    let n = competitions as i32;
+    let f = (1.0 - 0.81f64.powi(n)).sqrt() / (1.0 - 0.9f64.powi(n));
+    (f - 1.0) / (19.0f64.sqrt() - 1.0) * 1200.0
+}
+
+fn positivize_rating(rating: f64) -> f64 {
+    if rating >= 400.0 {
+        rating
+    } else {
+        400.0 / ((400.0 - rating) / 400.0).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rating_rule() {
+        let rule = RatingRule::new("abc200", Some(1999));
+        assert_eq!(rule.performance_cap(), Some(2400.0));
+        assert_eq!(rule.default_performance, 800.0);
+
+        let rule = RatingRule::new("agc050", None);
+        assert_eq!(rule.performance_cap(), None);
+        assert_eq!(rule.default_performance, 1600.0);
+
+        assert_eq!(RatingRule::new("arc110", None).default_performance, 1200.0);
+    }
+
+    #[test]
+    fn test_adjust_rating() {
+        assert!((adjust_rating(2000.0, 1) - 800.0).abs() < 1e-6);
+        for &(rating, competitions) in &[(1500, 3), (2800, 40), (300, 10)] {
+            let raw = unadjust_rating(rating, competitions);
+            let adjusted = adjust_rating(raw, competitions);
+            assert!((adjusted - rating as f64).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_estimate_performance() {
+        let rule = RatingRule::new("abc200", Some(1999));
+        let average_performances = vec![1200.0; 101];
+        let performances = estimate_performances(&average_performances, &[1.0, 51.0, 101.0], &rule);
+        assert!((performances[1] - 1200.0).abs() < 1.0);
+        assert!(performances[0] > performances[1]);
+        assert!(performances[2] < performances[1]);
+
+        let average_performances = vec![3000.0; 10];
+        let performances = estimate_performances(&average_performances, &[1.0], &rule);
+        assert_eq!(performances, vec![2400.0]);
+    }
+
+    #[test]
+    fn test_next_rating() {
+        assert!((next_rating(0, 0, 2000.0) - 800.0).abs() < 1e-6);
+
+        let rating = next_rating(1500, 20, 1500.0);
+        assert!((rating - 1500.0).abs() < 1.0);
+        assert!(next_rating(1500, 20, 2000.0) > 1500.0);
+        assert!(next_rating(1500, 20, 1000.0) < 1500.0);
+    }
+
+    #[test]
+    fn test_clip_difficulty() {
+        assert_eq!(clip_difficulty(1234.4), 1234.0);
+        assert_eq!(clip_difficulty(400.0), 400.0);
+        assert_eq!(clip_difficulty(0.0), 147.0);
+        assert!(clip_difficulty(-1000.0) > 0.0);
+    }
+
+    #[test]
+    fn test_rating_color() {
+        assert_eq!(RatingColor::from_rating(-1.0), RatingColor::Black);
+        assert_eq!(RatingColor::from_rating(0.0), RatingColor::Grey);
+        assert_eq!(RatingColor::from_rating(399.0), RatingColor::Grey);
+        assert_eq!(RatingColor::from_rating(400.0), RatingColor::Brown);
+        assert_eq!(RatingColor::from_rating(1999.0), RatingColor::Blue);
+        assert_eq!(RatingColor::from_rating(2800.0), RatingColor::Red);
+        assert_eq!(RatingColor::from_rating(4000.0).name(), "Red");
+    }
+}
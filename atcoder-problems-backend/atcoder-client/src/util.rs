@@ -1,9 +1,25 @@
+#[cfg(feature = "client")]
 use anyhow::{anyhow, Result};
 
+#[cfg(feature = "client")]
 use serde::de::DeserializeOwned;
 
-pub(crate) async fn get_html(url: &str) -> Result<(String, surf::StatusCode)> {
-    let mut response = surf::get(url)
+/// Sends a GET request with the session cookie if the client has logged in.
+#[cfg(feature = "client")]
+fn get(url: &str, session: Option<&str>) -> surf::RequestBuilder {
+    let request = surf::get(url);
+    match session {
+        Some(session) => request.header("cookie", session),
+        None => request,
+    }
+}
+
+#[cfg(feature = "client")]
+pub(crate) async fn get_html(
+    url: &str,
+    session: Option<&str>,
+) -> Result<(String, surf::StatusCode)> {
+    let mut response = get(url, session)
         .header("accept", "text/html")
         .header("accept-encoding", "gzip")
         .send()
@@ -20,8 +36,9 @@ pub(crate) async fn get_html(url: &str) -> Result<(String, surf::StatusCode)> {
     Ok((body, status))
 }
 
-pub(crate) async fn get_json<T: DeserializeOwned>(url: &str) -> Result<T> {
-    surf::get(url)
+#[cfg(feature = "client")]
+pub(crate) async fn get_json<T: DeserializeOwned>(url: &str, session: Option<&str>) -> Result<T> {
+    get(url, session)
         .header("accept", "application/json")
         .header("accept-encoding", "gzip")
         .recv_json()
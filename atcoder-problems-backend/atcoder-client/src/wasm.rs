@@ -0,0 +1,55 @@
+//! The bindings for the frontend, which return the parsed pages as JavaScript objects.
+
+use crate::parser;
+use crate::rating::{self, RatingColor, RatingRule};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(e: anyhow::Error) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// Converts the value to the same object as `JSON.parse`, e.g. `null` for `None`.
+fn to_js_value<T: Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    value.serialize(&serializer).map_err(JsValue::from)
+}
+
+#[wasm_bindgen(js_name = parseContestList)]
+pub fn parse_contest_list(html: &str) -> Result<JsValue, JsValue> {
+    let contests = parser::parse_contest_list(html).map_err(to_js_error)?;
+    to_js_value(&contests)
+}
+
+#[wasm_bindgen(js_name = parseSubmissionList)]
+pub fn parse_submission_list(html: &str, contest_id: &str) -> Result<JsValue, JsValue> {
+    let response = parser::parse_submission_list(html, contest_id).map_err(to_js_error)?;
+    to_js_value(&response)
+}
+
+#[wasm_bindgen(js_name = clipDifficulty)]
+pub fn clip_difficulty(difficulty: f64) -> f64 {
+    rating::clip_difficulty(difficulty)
+}
+
+#[wasm_bindgen(js_name = getRatingColor)]
+pub fn get_rating_color(rating: f64) -> String {
+    RatingColor::from_rating(rating).name().to_owned()
+}
+
+/// Returns the estimated performances in the same order as `ranks`.
+#[wasm_bindgen(js_name = estimatePerformances)]
+pub fn estimate_performances(
+    contest_id: &str,
+    rated_upper_bound: Option<i32>,
+    average_performances: &[f64],
+    ranks: &[f64],
+) -> Vec<f64> {
+    let rule = RatingRule::new(contest_id, rated_upper_bound.map(i64::from));
+    rating::estimate_performances(average_performances, ranks, &rule)
+}
+
+#[wasm_bindgen(js_name = nextRating)]
+pub fn next_rating(rating: i32, competitions: u32, performance: f64) -> f64 {
+    rating::next_rating(rating.into(), competitions.into(), performance)
+}
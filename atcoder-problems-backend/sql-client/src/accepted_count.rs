@@ -1,3 +1,4 @@
+use crate::ids::UserId;
 use crate::models::{Submission, UserProblemCount};
 use crate::{PgPool, MAX_INSERT_ROWS};
 use anyhow::Result;
@@ -14,7 +15,8 @@ pub trait AcceptedCountClient {
         &self,
         rank_range: Range<usize>,
     ) -> Result<Vec<UserProblemCount>>;
-    async fn get_users_accepted_count(&self, user_id: &str) -> Option<i32>;
+    /// Returns `None` if the user has no accepted submissions.
+    async fn get_users_accepted_count(&self, user_id: &UserId) -> Result<Option<i32>>;
     async fn get_accepted_count_rank(&self, accepted_count: i32) -> Result<i64>;
     async fn update_accepted_count(&self, submissions: &[Submission]) -> Result<()>;
 }
@@ -69,7 +71,7 @@ impl AcceptedCountClient for PgPool {
         Ok(count)
     }
 
-    async fn get_users_accepted_count(&self, user_id: &str) -> Option<i32> {
+    async fn get_users_accepted_count(&self, user_id: &UserId) -> Result<Option<i32>> {
         let count = sqlx::query(
             r"
             SELECT problem_count FROM accepted_count
@@ -78,11 +80,9 @@ impl AcceptedCountClient for PgPool {
         )
         .bind(user_id)
         .try_map(|row: PgRow| row.try_get::<i32, _>("problem_count"))
-        .fetch_one(self)
-        .await
-        .ok()?;
-
-        Some(count)
+        .fetch_optional(self)
+        .await?;
+        Ok(count)
     }
 
     async fn get_accepted_count_rank(&self, accepted_count: i32) -> Result<i64> {
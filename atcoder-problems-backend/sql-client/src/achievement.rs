@@ -0,0 +1,348 @@
+use crate::ids::UserId;
+use crate::models::{Achievement, ContestProblem, Submission};
+use crate::time::JstDay;
+use crate::{PgPool, MAX_INSERT_ROWS};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub enum AchievementCondition {
+    /// Solved the given number of distinct problems.
+    AcceptedCount(usize),
+    /// Solved a new problem on the given number of consecutive days in JST.
+    Streak(usize),
+    /// Solved all problems whose contest id starts with `contest_prefix`
+    /// and whose problem id ends with `problem_suffix`.
+    AllSolved {
+        contest_prefix: &'static str,
+        problem_suffix: &'static str,
+    },
+    /// Solved any problem in a contest whose id starts with `contest_prefix`.
+    FirstAccepted { contest_prefix: &'static str },
+}
+
+pub struct AchievementRule {
+    pub id: &'static str,
+    pub condition: AchievementCondition,
+}
+
+pub const ACHIEVEMENT_RULES: &[AchievementRule] = &[
+    AchievementRule {
+        id: "accepted_count_100",
+        condition: AchievementCondition::AcceptedCount(100),
+    },
+    AchievementRule {
+        id: "accepted_count_500",
+        condition: AchievementCondition::AcceptedCount(500),
+    },
+    AchievementRule {
+        id: "accepted_count_1000",
+        condition: AchievementCondition::AcceptedCount(1000),
+    },
+    AchievementRule {
+        id: "accepted_count_2000",
+        condition: AchievementCondition::AcceptedCount(2000),
+    },
+    AchievementRule {
+        id: "streak_7",
+        condition: AchievementCondition::Streak(7),
+    },
+    AchievementRule {
+        id: "streak_30",
+        condition: AchievementCondition::Streak(30),
+    },
+    AchievementRule {
+        id: "streak_100",
+        condition: AchievementCondition::Streak(100),
+    },
+    AchievementRule {
+        id: "streak_365",
+        condition: AchievementCondition::Streak(365),
+    },
+    AchievementRule {
+        id: "all_abc_a",
+        condition: AchievementCondition::AllSolved {
+            contest_prefix: "abc",
+            problem_suffix: "_a",
+        },
+    },
+    AchievementRule {
+        id: "all_abc_b",
+        condition: AchievementCondition::AllSolved {
+            contest_prefix: "abc",
+            problem_suffix: "_b",
+        },
+    },
+    AchievementRule {
+        id: "first_arc_accepted",
+        condition: AchievementCondition::FirstAccepted {
+            contest_prefix: "arc",
+        },
+    },
+    AchievementRule {
+        id: "first_agc_accepted",
+        condition: AchievementCondition::FirstAccepted {
+            contest_prefix: "agc",
+        },
+    },
+];
+
+#[async_trait]
+pub trait AchievementClient {
+    async fn update_achievements(
+        &self,
+        ac_submissions: &[Submission],
+        contest_problems: &[ContestProblem],
+    ) -> Result<()>;
+    async fn load_achievements(&self, user_id: &UserId) -> Result<Vec<Achievement>>;
+}
+
+#[async_trait]
+impl AchievementClient for PgPool {
+    async fn update_achievements(
+        &self,
+        ac_submissions: &[Submission],
+        contest_problems: &[ContestProblem],
+    ) -> Result<()> {
+        let achievements =
+            evaluate_achievements(ac_submissions, contest_problems, ACHIEVEMENT_RULES);
+
+        for chunk in achievements.chunks(MAX_INSERT_ROWS) {
+            let (user_ids, achievement_ids, achieved_epoch_seconds) = chunk.iter().fold(
+                (vec![], vec![], vec![]),
+                |(mut user_ids, mut achievement_ids, mut achieved_epoch_seconds), cur| {
+                    user_ids.push(cur.user_id.as_str());
+                    achievement_ids.push(cur.achievement_id.as_str());
+                    achieved_epoch_seconds.push(cur.achieved_epoch_second);
+                    (user_ids, achievement_ids, achieved_epoch_seconds)
+                },
+            );
+
+            // Once earned, an achievement keeps the time when it was earned first.
+            sqlx::query(
+                r"
+                INSERT INTO achievements (user_id, achievement_id, achieved_epoch_second)
+                VALUES (
+                    UNNEST($1::VARCHAR(255)[]),
+                    UNNEST($2::VARCHAR(255)[]),
+                    UNNEST($3::BIGINT[])
+                )
+                ON CONFLICT (user_id, achievement_id)
+                DO UPDATE SET achieved_epoch_second = LEAST(
+                    achievements.achieved_epoch_second,
+                    EXCLUDED.achieved_epoch_second
+                )
+                ",
+            )
+            .bind(user_ids)
+            .bind(achievement_ids)
+            .bind(achieved_epoch_seconds)
+            .execute(self)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn load_achievements(&self, user_id: &UserId) -> Result<Vec<Achievement>> {
+        let achievements = sqlx::query(
+            r"
+            SELECT user_id, achievement_id, achieved_epoch_second
+            FROM achievements
+            WHERE user_id = $1
+            ORDER BY achieved_epoch_second, achievement_id
+            ",
+        )
+        .bind(user_id)
+        .try_map(|row: PgRow| {
+            let user_id: String = row.try_get("user_id")?;
+            let achievement_id: String = row.try_get("achievement_id")?;
+            let achieved_epoch_second: i64 = row.try_get("achieved_epoch_second")?;
+            Ok(Achievement {
+                user_id,
+                achievement_id,
+                achieved_epoch_second,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(achievements)
+    }
+}
+
+fn evaluate_achievements(
+    ac_submissions: &[Submission],
+    contest_problems: &[ContestProblem],
+    rules: &[AchievementRule],
+) -> Vec<Achievement> {
+    let targets = rules
+        .iter()
+        .map(|rule| match rule.condition {
+            AchievementCondition::AllSolved {
+                contest_prefix,
+                problem_suffix,
+            } => contest_problems
+                .iter()
+                .filter(|p| {
+                    p.contest_id.as_str().starts_with(contest_prefix)
+                        && p.problem_id.as_str().ends_with(problem_suffix)
+                })
+                .map(|p| p.problem_id.as_str())
+                .collect::<BTreeSet<_>>(),
+            _ => BTreeSet::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut user_submissions = BTreeMap::new();
+    for submission in ac_submissions {
+        user_submissions
+            .entry(submission.user_id.as_str())
+            .or_insert_with(Vec::new)
+            .push(submission);
+    }
+
+    let mut achievements = Vec::new();
+    for (user_id, mut submissions) in user_submissions.into_iter() {
+        submissions.sort_by_key(|s| (s.epoch_second, s.id));
+
+        let mut achieved = vec![None; rules.len()];
+        let mut solved = BTreeSet::new();
+        let mut solved_targets = vec![0; rules.len()];
+        let mut last_day: Option<JstDay> = None;
+        let mut streak = 0;
+        for submission in submissions.into_iter() {
+            if !solved.insert(submission.problem_id.as_str()) {
+                continue;
+            }
+
+            let day = JstDay::from_epoch_second(submission.epoch_second);
+            streak = match last_day {
+                Some(last_day) if last_day == day => streak,
+                Some(last_day) if last_day.next() == day => streak + 1,
+                _ => 1,
+            };
+            last_day = Some(day);
+
+            for (i, rule) in rules.iter().enumerate() {
+                if targets[i].contains(submission.problem_id.as_str()) {
+                    solved_targets[i] += 1;
+                }
+                if achieved[i].is_some() {
+                    continue;
+                }
+                let satisfied = match rule.condition {
+                    AchievementCondition::AcceptedCount(count) => solved.len() >= count,
+                    AchievementCondition::Streak(days) => streak >= days,
+                    AchievementCondition::AllSolved { .. } => {
+                        !targets[i].is_empty() && solved_targets[i] == targets[i].len()
+                    }
+                    AchievementCondition::FirstAccepted { contest_prefix } => {
+                        submission.contest_id.as_str().starts_with(contest_prefix)
+                    }
+                };
+                if satisfied {
+                    achieved[i] = Some(submission.epoch_second);
+                }
+            }
+        }
+
+        for (rule, achieved_epoch_second) in rules.iter().zip(achieved) {
+            if let Some(achieved_epoch_second) = achieved_epoch_second {
+                achievements.push(Achievement {
+                    user_id: user_id.to_owned(),
+                    achievement_id: rule.id.to_owned(),
+                    achieved_epoch_second,
+                });
+            }
+        }
+    }
+    achievements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(id: i64, epoch_second: i64, problem_id: &str, contest_id: &str) -> Submission {
+        Submission {
+            id,
+            epoch_second,
+            problem_id: problem_id.into(),
+            contest_id: contest_id.into(),
+            user_id: "user".into(),
+            result: "AC".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_achievements() {
+        let rules = [
+            AchievementRule {
+                id: "accepted_count_2",
+                condition: AchievementCondition::AcceptedCount(2),
+            },
+            AchievementRule {
+                id: "streak_2",
+                condition: AchievementCondition::Streak(2),
+            },
+            AchievementRule {
+                id: "streak_3",
+                condition: AchievementCondition::Streak(3),
+            },
+            AchievementRule {
+                id: "all_abc_a",
+                condition: AchievementCondition::AllSolved {
+                    contest_prefix: "abc",
+                    problem_suffix: "_a",
+                },
+            },
+            AchievementRule {
+                id: "first_agc_accepted",
+                condition: AchievementCondition::FirstAccepted {
+                    contest_prefix: "agc",
+                },
+            },
+        ];
+        let contest_problems = [
+            ContestProblem {
+                contest_id: "abc001".into(),
+                problem_id: "abc001_a".into(),
+            },
+            ContestProblem {
+                contest_id: "abc002".into(),
+                problem_id: "abc002_a".into(),
+            },
+            ContestProblem {
+                contest_id: "abc002".into(),
+                problem_id: "abc002_b".into(),
+            },
+        ];
+        let submissions = [
+            // 2019-10-04T00:00:00+09:00
+            submission(1, 1570114800, "abc001_a", "abc001"),
+            // 2019-10-04T10:00:00+09:00, solved again
+            submission(2, 1570150800, "abc001_a", "abc001"),
+            // 2019-10-05T00:00:00+09:00
+            submission(3, 1570201200, "abc002_a", "abc002"),
+            // 2019-10-07T00:00:00+09:00
+            submission(4, 1570374000, "agc001_a", "agc001"),
+        ];
+
+        let achievements = evaluate_achievements(&submissions, &contest_problems, &rules);
+        let achievements = achievements
+            .iter()
+            .map(|a| (a.achievement_id.as_str(), a.achieved_epoch_second))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            achievements,
+            vec![
+                ("accepted_count_2", 1570201200),
+                ("streak_2", 1570201200),
+                ("all_abc_a", 1570201200),
+                ("first_agc_accepted", 1570374000),
+            ]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+/// A page of AtCoder which a crawler fetched but failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseFailure {
+    pub url: String,
+    pub error: String,
+    pub epoch_second: i64,
+}
+
+/// Statistics of the crawled data for the operations reports.
+#[async_trait]
+pub trait ActivityClient {
+    /// Returns the number of the submissions crawled for the first time in `[from_second, to_second)`.
+    async fn count_submissions_crawled_between(
+        &self,
+        from_second: i64,
+        to_second: i64,
+    ) -> Result<i64>;
+
+    async fn insert_parse_failures(&self, failures: &[ParseFailure]) -> Result<()>;
+
+    /// Returns the number of the parse failures in `[from_second, to_second)`.
+    async fn count_parse_failures_between(&self, from_second: i64, to_second: i64) -> Result<i64>;
+}
+
+#[async_trait]
+impl ActivityClient for PgPool {
+    async fn count_submissions_crawled_between(
+        &self,
+        from_second: i64,
+        to_second: i64,
+    ) -> Result<i64> {
+        let count = sqlx::query(
+            r"
+            SELECT COUNT(*) AS count FROM submissions
+            WHERE crawled_epoch_second >= $1 AND crawled_epoch_second < $2
+            ",
+        )
+        .bind(from_second)
+        .bind(to_second)
+        .try_map(|row: PgRow| row.try_get::<i64, _>("count"))
+        .fetch_one(self)
+        .await?;
+        Ok(count)
+    }
+
+    async fn insert_parse_failures(&self, failures: &[ParseFailure]) -> Result<()> {
+        if failures.is_empty() {
+            return Ok(());
+        }
+        let urls = failures.iter().map(|f| f.url.as_str()).collect::<Vec<_>>();
+        let errors = failures
+            .iter()
+            .map(|f| f.error.as_str())
+            .collect::<Vec<_>>();
+        let epoch_seconds = failures.iter().map(|f| f.epoch_second).collect::<Vec<_>>();
+        sqlx::query(
+            r"
+            INSERT INTO parse_failures (url, error, epoch_second)
+            VALUES (
+                UNNEST($1::TEXT[]),
+                UNNEST($2::TEXT[]),
+                UNNEST($3::BIGINT[])
+            )
+            ",
+        )
+        .bind(urls)
+        .bind(errors)
+        .bind(epoch_seconds)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn count_parse_failures_between(&self, from_second: i64, to_second: i64) -> Result<i64> {
+        let count = sqlx::query(
+            r"
+            SELECT COUNT(*) AS count FROM parse_failures
+            WHERE epoch_second >= $1 AND epoch_second < $2
+            ",
+        )
+        .bind(from_second)
+        .bind(to_second)
+        .try_map(|row: PgRow| row.try_get::<i64, _>("count"))
+        .fetch_one(self)
+        .await?;
+        Ok(count)
+    }
+}
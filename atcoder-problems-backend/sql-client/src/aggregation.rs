@@ -0,0 +1,356 @@
+//! Computes the rankings from all the submissions in a single scan, instead of a `GROUP BY` for
+//! each of them, and writes them back with `COPY`.
+//!
+//! The ids are interned while scanning, so that the aggregation keeps only small integers for each
+//! pair of a user and a problem.
+
+use crate::ids::ProblemId;
+use crate::internal::progress_reset_manager::{load_progress_resets, ProgressResets};
+use crate::rated_point_sum::load_rated_problem_ids;
+use crate::PgPool;
+use anyhow::{Context, Result};
+use futures::TryStreamExt;
+use postgres::Client;
+use sqlx::Row;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Display;
+use std::io::{BufWriter, Write};
+
+/// Assigns consecutive indices to strings.
+#[derive(Debug, Default)]
+pub struct Interner {
+    indices: HashMap<Box<str>, u32>,
+    values: Vec<Box<str>>,
+}
+
+impl Interner {
+    pub fn intern(&mut self, value: &str) -> u32 {
+        if let Some(&index) = self.indices.get(value) {
+            return index;
+        }
+        let index = self.values.len() as u32;
+        self.values.push(value.into());
+        self.indices.insert(value.into(), index);
+        index
+    }
+
+    pub fn get(&self, index: u32) -> &str {
+        &self.values[index as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// The rows of the ranking tables, in the order of the ids.
+#[derive(Debug, Default, PartialEq)]
+pub struct Rankings {
+    /// `accepted_count`: the number of the problems which each user has solved.
+    pub accepted_count: Vec<(String, i32)>,
+    /// `rated_point_sum`: the sum of the points of the rated problems which each user has solved.
+    pub rated_point_sum: Vec<(String, f64)>,
+    /// `submission_count`: the number of the submissions of each user.
+    pub submission_count: Vec<(String, i64)>,
+    /// `solver`: the number of the users who have solved each problem.
+    pub solver_count: Vec<(String, i32)>,
+    /// `last_accepted`: the last time when each problem was solved.
+    pub last_accepted: Vec<(String, i64)>,
+}
+
+/// Aggregates the submissions one by one.
+pub struct RankingAggregator {
+    users: Interner,
+    problems: Interner,
+    rated_problems: HashSet<u32>,
+    resets: ProgressResets,
+    submission_count: Vec<i64>,
+    /// The points of the last accepted submissions of the pairs of a user and a problem, which
+    /// are not reset.
+    accepted_points: HashMap<(u32, u32), u32>,
+    /// The pairs of a user and a problem which the user has solved, even if it is reset.
+    solved: HashSet<(u32, u32)>,
+    last_accepted: HashMap<u32, i64>,
+}
+
+impl RankingAggregator {
+    /// The accepted submissions before the users reset the problems are not counted in the
+    /// rankings of the users, but still in the solver counts and the last accepted times.
+    pub fn new(rated_problem_ids: &BTreeSet<ProblemId>, resets: ProgressResets) -> Self {
+        let mut problems = Interner::default();
+        let rated_problems = rated_problem_ids
+            .iter()
+            .map(|id| problems.intern(id.as_str()))
+            .collect();
+        Self {
+            users: Interner::default(),
+            problems,
+            rated_problems,
+            resets,
+            submission_count: Vec::new(),
+            accepted_points: HashMap::new(),
+            solved: HashSet::new(),
+            last_accepted: HashMap::new(),
+        }
+    }
+
+    /// Adds a submission, where the submissions have to be added in the order of their ids.
+    pub fn add(
+        &mut self,
+        user_id: &str,
+        problem_id: &str,
+        result: &str,
+        point: f64,
+        epoch_second: i64,
+    ) {
+        let user = self.users.intern(user_id);
+        if self.submission_count.len() <= user as usize {
+            self.submission_count.push(0);
+        }
+        self.submission_count[user as usize] += 1;
+        if result != "AC" {
+            return;
+        }
+
+        let problem = self.problems.intern(problem_id);
+        self.solved.insert((user, problem));
+        let last = self.last_accepted.entry(problem).or_insert(epoch_second);
+        *last = (*last).max(epoch_second);
+        if !self.resets.is_reset(user_id, problem_id, epoch_second) {
+            self.accepted_points.insert((user, problem), point as u32);
+        }
+    }
+
+    pub fn finish(self) -> Rankings {
+        let mut accepted_count = HashMap::new();
+        let mut rated_point_sum = HashMap::new();
+        let mut solver_count = HashMap::new();
+        for &(_, problem) in self.solved.iter() {
+            *solver_count.entry(problem).or_insert(0) += 1;
+        }
+        for (&(user, problem), &point) in self.accepted_points.iter() {
+            *accepted_count.entry(user).or_insert(0) += 1;
+            if self.rated_problems.contains(&problem) {
+                *rated_point_sum.entry(user).or_insert(0) += point;
+            }
+        }
+
+        let users = &self.users;
+        let problems = &self.problems;
+        Rankings {
+            accepted_count: sorted_rows(users, accepted_count),
+            rated_point_sum: sorted_rows(users, rated_point_sum)
+                .into_iter()
+                .map(|(user_id, sum)| (user_id, f64::from(sum)))
+                .collect(),
+            submission_count: sorted_rows(
+                users,
+                self.submission_count
+                    .iter()
+                    .enumerate()
+                    .map(|(user, &count)| (user as u32, count)),
+            ),
+            solver_count: sorted_rows(problems, solver_count),
+            last_accepted: sorted_rows(problems, self.last_accepted.iter().map(|(&p, &t)| (p, t))),
+        }
+    }
+}
+
+fn sorted_rows<T, I>(interner: &Interner, values: I) -> Vec<(String, T)>
+where
+    I: IntoIterator<Item = (u32, T)>,
+{
+    let mut rows = values
+        .into_iter()
+        .map(|(index, value)| (interner.get(index).to_owned(), value))
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    rows
+}
+
+/// Scans all the submissions in the order of their ids and computes the rankings.
+pub async fn load_rankings(pool: &PgPool) -> Result<Rankings> {
+    let rated_problem_ids = load_rated_problem_ids(pool).await?;
+    let resets = load_progress_resets(pool).await?;
+    let mut aggregator = RankingAggregator::new(&rated_problem_ids, resets);
+    let mut rows = sqlx::query(
+        r"
+        SELECT user_id, problem_id, result, point, epoch_second FROM submissions
+        ORDER BY id
+        ",
+    )
+    .fetch(pool);
+    while let Some(row) = rows.try_next().await? {
+        aggregator.add(
+            row.try_get("user_id")?,
+            row.try_get("problem_id")?,
+            row.try_get("result")?,
+            row.try_get("point")?,
+            row.try_get("epoch_second")?,
+        );
+    }
+    Ok(aggregator.finish())
+}
+
+/// Writes the rankings with `COPY`, each table in its own transaction, and returns the number of
+/// the written rows.
+///
+/// It uses the synchronous `postgres` driver, since sqlx does not support `COPY` yet.
+pub fn write_rankings(database_url: &str, rankings: &Rankings) -> Result<u64> {
+    let mut client = crate::connect_sync(database_url)?;
+    let rows = upsert(
+        &mut client,
+        "accepted_count",
+        "user_id",
+        "problem_count",
+        &rankings.accepted_count,
+    )? + upsert(
+        &mut client,
+        "rated_point_sum",
+        "user_id",
+        "point_sum",
+        &rankings.rated_point_sum,
+    )? + upsert(
+        &mut client,
+        "submission_count",
+        "user_id",
+        "count",
+        &rankings.submission_count,
+    )? + upsert(
+        &mut client,
+        "solver",
+        "problem_id",
+        "user_count",
+        &rankings.solver_count,
+    )? + upsert(
+        &mut client,
+        "last_accepted",
+        "problem_id",
+        "epoch_second",
+        &rankings.last_accepted,
+    )?;
+    Ok(rows)
+}
+
+/// Copies the rows to a temporary table, and then inserts them or updates the existing rows,
+/// since `COPY` can not update the conflicting rows.
+fn upsert<T: Display>(
+    client: &mut Client,
+    table: &str,
+    key: &str,
+    value: &str,
+    rows: &[(String, T)],
+) -> Result<u64> {
+    let mut transaction = client.transaction()?;
+    transaction.batch_execute(&format!(
+        "CREATE TEMPORARY TABLE ranking_staging (LIKE {} INCLUDING DEFAULTS) ON COMMIT DROP",
+        table
+    ))?;
+
+    let query = format!("COPY ranking_staging ({}, {}) FROM STDIN", key, value);
+    let mut writer = BufWriter::new(transaction.copy_in(query.as_str())?);
+    for (id, v) in rows.iter() {
+        writeln!(writer, "{}\t{}", escape_copy_text(id), v)
+            .with_context(|| format!("Failed to copy {}", table))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| anyhow::anyhow!("Failed to copy {}: {:?}", table, e.error()))?
+        .finish()?;
+
+    let query = format!(
+        r"
+        INSERT INTO {0} ({1}, {2}) SELECT {1}, {2} FROM ranking_staging
+        ON CONFLICT ({1}) DO UPDATE SET {2} = EXCLUDED.{2}
+        ",
+        table, key, value
+    );
+    let rows = transaction.execute(query.as_str(), &[])?;
+    transaction.commit()?;
+    Ok(rows)
+}
+
+/// Escapes a value for the text format of `COPY`.
+fn escape_copy_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::default();
+        assert_eq!(interner.intern("a"), 0);
+        assert_eq!(interner.intern("b"), 1);
+        assert_eq!(interner.intern("a"), 0);
+        assert_eq!(interner.get(1), "b");
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let rated_problem_ids = vec![ProblemId::from("abc001_a")].into_iter().collect();
+        let resets = ProgressResets::new(vec![("user2", "abc001_b", 600)]);
+        let mut aggregator = RankingAggregator::new(&rated_problem_ids, resets);
+        aggregator.add("user1", "abc001_a", "WA", 0.0, 100);
+        aggregator.add("user1", "abc001_a", "AC", 100.0, 200);
+        aggregator.add("user1", "abc001_a", "AC", 100.0, 300);
+        aggregator.add("user1", "practice_a", "AC", 0.0, 400);
+        aggregator.add("user2", "abc001_a", "AC", 100.0, 150);
+        aggregator.add("user3", "abc001_b", "WA", 0.0, 500);
+        aggregator.add("user2", "abc001_b", "AC", 100.0, 550);
+
+        let rankings = aggregator.finish();
+        let rows = |rows: &[(&str, i64)]| {
+            rows.iter()
+                .map(|&(id, v)| (id.to_owned(), v))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rankings.accepted_count,
+            vec![("user1".to_owned(), 2), ("user2".to_owned(), 1)]
+        );
+        assert_eq!(
+            rankings.rated_point_sum,
+            vec![("user1".to_owned(), 100.0), ("user2".to_owned(), 100.0)]
+        );
+        assert_eq!(
+            rankings.submission_count,
+            rows(&[("user1", 4), ("user2", 2), ("user3", 1)])
+        );
+        assert_eq!(
+            rankings.solver_count,
+            vec![
+                ("abc001_a".to_owned(), 2),
+                ("abc001_b".to_owned(), 1),
+                ("practice_a".to_owned(), 1)
+            ]
+        );
+        assert_eq!(
+            rankings.last_accepted,
+            rows(&[("abc001_a", 300), ("abc001_b", 550), ("practice_a", 400)])
+        );
+    }
+
+    #[test]
+    fn test_escape_copy_text() {
+        assert_eq!(escape_copy_text("user"), "user");
+        assert_eq!(escape_copy_text("a\tb\\c\n"), "a\\tb\\\\c\\n");
+    }
+}
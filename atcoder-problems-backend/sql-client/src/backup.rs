@@ -0,0 +1,408 @@
+use crate::quote_ident;
+use anyhow::{Context, Result};
+use postgres::{Client, Transaction};
+use std::io::{self, Read, Write};
+
+/// The tables which record the forgotten users themselves, so their rows are never filtered.
+const FORGETTING_TABLES: &[&str] = &["forgotten_users", "user_deletions"];
+
+/// Reads all the tables by `COPY TO` from a single snapshot of the database. The snapshot is
+/// taken by a read-only `REPEATABLE READ` transaction, which does not block the writers.
+///
+/// It uses the synchronous `postgres` driver, since sqlx does not support `COPY` yet.
+pub struct Snapshot {
+    client: Client,
+}
+
+impl Snapshot {
+    pub fn begin(database_url: &str) -> Result<Self> {
+        let mut client = crate::connect_sync(database_url)?;
+        client.batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ, READ ONLY")?;
+        Ok(Self { client })
+    }
+
+    /// Returns the tables in the order in which they can be restored,
+    /// i.e. each table comes after the tables which it references.
+    pub fn tables(&mut self) -> Result<Vec<String>> {
+        let rows = self.client.query(
+            r"
+            SELECT table_name::TEXT FROM information_schema.tables
+            WHERE table_schema = 'public' AND table_type = 'BASE TABLE'
+            ORDER BY table_name
+            ",
+            &[],
+        )?;
+        let tables = rows.iter().map(|row| row.get(0)).collect();
+        let foreign_keys = foreign_keys(&mut self.client)?;
+        Ok(sort_by_dependencies(tables, &foreign_keys))
+    }
+
+    pub fn schema_version(&mut self) -> Result<String> {
+        schema_version(&mut self.client)
+    }
+
+    /// Returns the names and the types of the columns of the table, in the order of `COPY`.
+    pub fn columns(&mut self, table: &str) -> Result<Vec<(String, String)>> {
+        columns(&mut self.client, table)
+    }
+
+    /// Returns the columns of the primary key of the table, in the order of the key.
+    pub fn primary_key(&mut self, table: &str) -> Result<Vec<String>> {
+        let rows = self.client.query(
+            r"
+            SELECT a.attname::TEXT FROM pg_index i
+            JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey)
+            WHERE i.indrelid = $1::TEXT::REGCLASS AND i.indisprimary
+            ORDER BY ARRAY_POSITION(i.indkey::SMALLINT[], a.attnum)
+            ",
+            &[&quote_ident(table)],
+        )?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    /// Writes the table in the text format of `COPY`, and returns the number of the rows.
+    /// Each row is a line, since the newlines in the values are escaped.
+    pub fn copy_table<W: Write>(&mut self, table: &str, writer: W) -> Result<u64> {
+        let query = format!("COPY {} TO STDOUT", quote_ident(table));
+        let mut reader = self.client.copy_out(query.as_str())?;
+        let mut counter = LineCounter { writer, lines: 0 };
+        io::copy(&mut reader, &mut counter).with_context(|| format!("Failed to copy {}", table))?;
+        Ok(counter.lines)
+    }
+}
+
+/// The format of the rows given to `Loader::import_table`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyFormat {
+    /// The text format of `COPY`, which is written by `Snapshot::copy_table`.
+    Text,
+    /// CSV without the header.
+    Csv,
+}
+
+/// Loads the tables written by `Snapshot` with `COPY FROM`, each in its own transaction.
+///
+/// The users in `forgotten_users` when it connects are kept forgotten, even if `forgotten_users`
+/// itself is replaced by an older backup.
+pub struct Loader {
+    client: Client,
+}
+
+impl Loader {
+    pub fn connect(database_url: &str) -> Result<Self> {
+        let mut client = crate::connect_sync(database_url)?;
+        client.batch_execute(
+            "CREATE TEMPORARY TABLE kept_forgotten_users AS SELECT * FROM forgotten_users",
+        )?;
+        Ok(Self { client })
+    }
+
+    pub fn schema_version(&mut self) -> Result<String> {
+        schema_version(&mut self.client)
+    }
+
+    /// Returns the pairs of the referencing table and the referenced table.
+    pub fn foreign_keys(&mut self) -> Result<Vec<(String, String)>> {
+        foreign_keys(&mut self.client)
+    }
+
+    /// Returns the names and the types of the columns of the table, in the order of `COPY`.
+    pub fn columns(&mut self, table: &str) -> Result<Vec<(String, String)>> {
+        columns(&mut self.client, table)
+    }
+
+    /// Returns whether the rows of the forgotten users have to be removed from the table.
+    fn has_user_id(&mut self, table: &str) -> Result<bool> {
+        let has_user_id = self
+            .columns(table)?
+            .iter()
+            .any(|(name, _)| name == "user_id");
+        Ok(has_user_id && !FORGETTING_TABLES.contains(&table))
+    }
+
+    /// Replaces the rows of the table, and returns the number of the loaded rows.
+    ///
+    /// `TRUNCATE CASCADE` also clears the tables which reference the table, so they have to be
+    /// loaded after it. The triggers, e.g. the notifications of the submissions, are disabled
+    /// while loading, so the rows of the forgotten users are deleted after `COPY` instead, and
+    /// the sequences of the `SERIAL` columns are moved past the loaded ids.
+    pub fn load_table<R: Read>(&mut self, table: &str, mut reader: R) -> Result<u64> {
+        let has_user_id = self.has_user_id(table)?;
+        let quoted = quote_ident(table);
+        let mut transaction = self.client.transaction()?;
+        transaction.batch_execute(&format!(
+            "ALTER TABLE {0} DISABLE TRIGGER USER; TRUNCATE {0} CASCADE",
+            quoted
+        ))?;
+        let query = format!("COPY {} FROM STDIN", quoted);
+        let mut writer = transaction.copy_in(query.as_str())?;
+        io::copy(&mut reader, &mut writer).with_context(|| format!("Failed to load {}", table))?;
+        let mut rows = writer.finish()?;
+        if table == "forgotten_users" {
+            transaction.batch_execute(
+                "INSERT INTO forgotten_users SELECT * FROM kept_forgotten_users ON CONFLICT DO NOTHING",
+            )?;
+        } else if has_user_id {
+            let query = format!(
+                r"
+                DELETE FROM {} WHERE LOWER(user_id) IN (
+                    SELECT LOWER(user_id) FROM forgotten_users
+                    UNION SELECT LOWER(user_id) FROM kept_forgotten_users
+                )
+                ",
+                quoted
+            );
+            rows -= transaction.execute(query.as_str(), &[])?;
+        }
+        reset_sequences(&mut transaction, table)?;
+        transaction.batch_execute(&format!("ALTER TABLE {} ENABLE TRIGGER USER", quoted))?;
+        transaction.commit()?;
+        Ok(rows)
+    }
+
+    /// Adds the rows to the table, skipping the ones whose primary keys already exist, and
+    /// returns the number of the added rows. `columns` are the columns of the rows, and the
+    /// others are filled with their defaults.
+    ///
+    /// The rows are copied to a temporary table first, since `COPY` can not skip the conflicts.
+    /// The triggers are disabled while adding them, as `load_table` does, so the rows of the
+    /// users in `forgotten_users` are removed from the temporary table instead of by the triggers.
+    pub fn import_table<R: Read>(
+        &mut self,
+        table: &str,
+        columns: &[String],
+        format: CopyFormat,
+        mut reader: R,
+    ) -> Result<u64> {
+        let has_user_id = self.has_user_id(table)?;
+        let quoted = quote_ident(table);
+        let columns = columns.iter().map(|c| quote_ident(c)).collect::<Vec<_>>();
+        let mut transaction = self.client.transaction()?;
+        transaction.batch_execute(&format!(
+            "CREATE TEMPORARY TABLE import_staging (LIKE {} INCLUDING DEFAULTS) ON COMMIT DROP",
+            quoted
+        ))?;
+        let options = match format {
+            CopyFormat::Text => "",
+            CopyFormat::Csv => " WITH (FORMAT csv)",
+        };
+        let query = format!(
+            "COPY import_staging ({}) FROM STDIN{}",
+            columns.join(", "),
+            options
+        );
+        let mut writer = transaction.copy_in(query.as_str())?;
+        io::copy(&mut reader, &mut writer)
+            .with_context(|| format!("Failed to import {}", table))?;
+        writer.finish()?;
+        if has_user_id {
+            transaction.batch_execute(
+                r"
+                DELETE FROM import_staging
+                WHERE LOWER(user_id) IN (SELECT LOWER(user_id) FROM forgotten_users)
+                ",
+            )?;
+        }
+
+        transaction.batch_execute(&format!("ALTER TABLE {} DISABLE TRIGGER USER", quoted))?;
+        let query = format!(
+            "INSERT INTO {} SELECT * FROM import_staging ON CONFLICT DO NOTHING",
+            quoted
+        );
+        let rows = transaction.execute(query.as_str(), &[])?;
+        reset_sequences(&mut transaction, table)?;
+        transaction.batch_execute(&format!("ALTER TABLE {} ENABLE TRIGGER USER", quoted))?;
+        transaction.commit()?;
+        Ok(rows)
+    }
+}
+
+/// Moves the non-AC submissions in a range of `epoch_second` out of the database. The rows are
+/// copied and deleted in a single `REPEATABLE READ` transaction, so exactly the copied rows are
+/// deleted, and nothing is deleted unless `commit` is called after the copy is saved.
+pub struct SubmissionArchiver {
+    client: Client,
+}
+
+impl SubmissionArchiver {
+    pub fn begin(database_url: &str) -> Result<Self> {
+        let mut client = crate::connect_sync(database_url)?;
+        client.batch_execute("BEGIN ISOLATION LEVEL REPEATABLE READ")?;
+        Ok(Self { client })
+    }
+
+    /// Returns the `epoch_second` of the oldest non-AC submission before `end`.
+    pub fn oldest_non_accepted(&mut self, end: i64) -> Result<Option<i64>> {
+        let row = self.client.query_one(
+            "SELECT MIN(epoch_second) FROM submissions WHERE result != 'AC' AND epoch_second < $1",
+            &[&end],
+        )?;
+        Ok(row.get(0))
+    }
+
+    /// Writes the non-AC submissions in `[start, end)` in the text format of `COPY` in the order
+    /// of their ids, and returns the number of the rows.
+    pub fn copy_non_accepted<W: Write>(&mut self, start: i64, end: i64, writer: W) -> Result<u64> {
+        let query = format!(
+            r"
+            COPY (
+                SELECT * FROM submissions
+                WHERE result != 'AC' AND epoch_second >= {} AND epoch_second < {}
+                ORDER BY id
+            ) TO STDOUT
+            ",
+            start, end
+        );
+        let mut reader = self.client.copy_out(query.as_str())?;
+        let mut counter = LineCounter { writer, lines: 0 };
+        io::copy(&mut reader, &mut counter).context("Failed to copy the submissions")?;
+        Ok(counter.lines)
+    }
+
+    /// Returns whether the submission exists, e.g. to tell whether the deletion of an archived
+    /// submission has been committed.
+    pub fn has_submission(&mut self, id: i64) -> Result<bool> {
+        let row = self.client.query_one(
+            "SELECT EXISTS (SELECT 1 FROM submissions WHERE id = $1)",
+            &[&id],
+        )?;
+        Ok(row.get(0))
+    }
+
+    /// Deletes the non-AC submissions in `[start, end)` which `copy_non_accepted` has copied.
+    pub fn delete_non_accepted(&mut self, start: i64, end: i64) -> Result<u64> {
+        let rows = self.client.execute(
+            r"
+            DELETE FROM submissions
+            WHERE result != 'AC' AND epoch_second >= $1 AND epoch_second < $2
+            ",
+            &[&start, &end],
+        )?;
+        Ok(rows)
+    }
+
+    pub fn commit(mut self) -> Result<()> {
+        self.client.batch_execute("COMMIT")?;
+        Ok(())
+    }
+}
+
+fn columns(client: &mut Client, table: &str) -> Result<Vec<(String, String)>> {
+    let rows = client.query(
+        r"
+        SELECT column_name::TEXT, data_type::TEXT FROM information_schema.columns
+        WHERE table_schema = 'public' AND table_name = $1
+        ORDER BY ordinal_position
+        ",
+        &[&table],
+    )?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// A hash of the columns of the tables, which tells whether a backup can be restored
+/// into a database with the current definition.
+fn schema_version(client: &mut Client) -> Result<String> {
+    let row = client.query_one(
+        r"
+        SELECT MD5(STRING_AGG(
+            table_name || '.' || column_name || ':' || data_type,
+            ',' ORDER BY table_name, ordinal_position
+        )) FROM information_schema.columns
+        WHERE table_schema = 'public'
+        ",
+        &[],
+    )?;
+    Ok(row.get(0))
+}
+
+/// Moves the sequences of the `SERIAL` columns of the table past the largest ids in it, since
+/// `COPY` writes the ids without taking them from the sequences.
+fn reset_sequences(transaction: &mut Transaction, table: &str) -> Result<()> {
+    let rows = transaction.query(
+        r"
+        SELECT column_name::TEXT, pg_get_serial_sequence($2, column_name)
+        FROM information_schema.columns
+        WHERE table_schema = 'public' AND table_name = $1
+        ",
+        &[&table, &quote_ident(table)],
+    )?;
+    for row in rows {
+        let column: String = row.get(0);
+        let sequence: Option<String> = row.get(1);
+        if let Some(sequence) = sequence {
+            let query = format!(
+                "SELECT setval($1::TEXT::REGCLASS, COALESCE(MAX({}), 0) + 1, false) FROM {}",
+                quote_ident(&column),
+                quote_ident(table)
+            );
+            transaction.query_one(query.as_str(), &[&sequence])?;
+        }
+    }
+    Ok(())
+}
+
+fn foreign_keys(client: &mut Client) -> Result<Vec<(String, String)>> {
+    let rows = client.query(
+        r"
+        SELECT conrelid::REGCLASS::TEXT, confrelid::REGCLASS::TEXT FROM pg_constraint
+        WHERE contype = 'f'
+        ",
+        &[],
+    )?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}
+
+/// Sorts the tables so that each table comes after the tables which it references,
+/// keeping the given order otherwise.
+fn sort_by_dependencies(mut tables: Vec<String>, foreign_keys: &[(String, String)]) -> Vec<String> {
+    let mut sorted = Vec::with_capacity(tables.len());
+    while !tables.is_empty() {
+        let is_ready = |table: &String| {
+            !foreign_keys
+                .iter()
+                .any(|(from, to)| from == table && to != table && tables.contains(to))
+        };
+        // Takes the first table if the references are cyclic, which never happens in this schema.
+        let index = tables.iter().position(is_ready).unwrap_or(0);
+        sorted.push(tables.remove(index));
+    }
+    sorted
+}
+
+struct LineCounter<W> {
+    writer: W,
+    lines: u64,
+}
+
+impl<W: Write> Write for LineCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.lines += buf[..written].iter().filter(|&&b| b == b'\n').count() as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_by_dependencies() {
+        let tables = ["list_items", "lists", "submissions", "users"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let foreign_keys = vec![
+            ("list_items".to_string(), "lists".to_string()),
+            ("lists".to_string(), "users".to_string()),
+        ];
+        assert_eq!(
+            sort_by_dependencies(tables, &foreign_keys),
+            vec!["submissions", "users", "lists", "list_items"]
+        );
+    }
+}
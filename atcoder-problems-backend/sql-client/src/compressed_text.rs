@@ -0,0 +1,362 @@
+//! Stores the large texts, the source codes and the problem statements, compressed by zstd.
+//!
+//! The texts of a table share a lot, e.g. the templates of the source codes, so they are
+//! compressed with a dictionary trained on the samples of the table. The latest dictionary of the
+//! table is used to compress the new texts, and the older ones are kept to decompress the texts
+//! compressed with them until they are recompressed.
+
+use crate::PgPool;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Read;
+use std::str::FromStr;
+
+const ZSTD_LEVEL: i32 = 3;
+/// The default maximum size of the dictionaries of the zstd command line.
+const MAX_DICTIONARY_SIZE: usize = 112_640;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextTable {
+    SourceCodes,
+    ProblemStatements,
+}
+
+impl TextTable {
+    pub fn name(self) -> &'static str {
+        match self {
+            TextTable::SourceCodes => "submission_source_codes",
+            TextTable::ProblemStatements => "problem_statements",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            TextTable::SourceCodes => "submission_id",
+            TextTable::ProblemStatements => "problem_id",
+        }
+    }
+
+    /// The type of the key, which the keys given as texts are cast to.
+    fn key_type(self) -> &'static str {
+        match self {
+            TextTable::SourceCodes => "BIGINT",
+            TextTable::ProblemStatements => "VARCHAR",
+        }
+    }
+}
+
+impl FromStr for TextTable {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "submission_source_codes" => Ok(TextTable::SourceCodes),
+            "problem_statements" => Ok(TextTable::ProblemStatements),
+            _ => Err(anyhow!("Unknown table of the texts: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dictionary {
+    pub id: i32,
+    pub data: Vec<u8>,
+}
+
+pub fn compress(text: &str, dictionary: Option<&[u8]>) -> Result<Vec<u8>> {
+    let compressed = match dictionary {
+        Some(dictionary) => zstd::block::Compressor::with_dict(dictionary.to_vec())
+            .compress(text.as_bytes(), ZSTD_LEVEL)?,
+        None => zstd::block::compress(text.as_bytes(), ZSTD_LEVEL)?,
+    };
+    Ok(compressed)
+}
+
+pub fn decompress(compressed: &[u8], dictionary: Option<&[u8]>) -> Result<String> {
+    let mut text = String::new();
+    match dictionary {
+        Some(dictionary) => {
+            zstd::stream::read::Decoder::with_dictionary(compressed, dictionary)?
+                .read_to_string(&mut text)?;
+        }
+        None => {
+            zstd::stream::read::Decoder::new(compressed)?.read_to_string(&mut text)?;
+        }
+    }
+    Ok(text)
+}
+
+/// Trains a dictionary on the samples. zstd fails if the samples are too few or too small.
+pub fn train_dictionary(samples: &[String]) -> Result<Vec<u8>> {
+    let dictionary = zstd::dict::from_samples(samples, MAX_DICTIONARY_SIZE)
+        .context("Failed to train the dictionary")?;
+    Ok(dictionary)
+}
+
+#[async_trait]
+pub trait CompressedTextClient {
+    /// Inserts or replaces the texts, which are the pairs of the key and the text, compressing
+    /// them with the latest dictionary of the table.
+    async fn save_texts(&self, table: TextTable, texts: &[(String, String)]) -> Result<usize>;
+
+    /// Returns the texts of the keys which exist in the table.
+    async fn load_texts(&self, table: TextTable, keys: &[String]) -> Result<Vec<(String, String)>>;
+
+    async fn load_latest_dictionary(&self, table: TextTable) -> Result<Option<Dictionary>>;
+
+    /// Trains a dictionary on at most `sample_count` random texts of the table, and returns its
+    /// id. Returns `None` if the table is empty.
+    async fn train_text_dictionary(
+        &self,
+        table: TextTable,
+        sample_count: i64,
+    ) -> Result<Option<i32>>;
+
+    /// Recompresses the texts which are not compressed with the latest dictionary, `batch_size`
+    /// texts at a time, and returns the number of the recompressed texts.
+    async fn recompress_texts(&self, table: TextTable, batch_size: i64) -> Result<usize>;
+}
+
+#[async_trait]
+impl CompressedTextClient for PgPool {
+    async fn save_texts(&self, table: TextTable, texts: &[(String, String)]) -> Result<usize> {
+        let dictionary = self.load_latest_dictionary(table).await?;
+        let data = dictionary.as_ref().map(|d| d.data.as_slice());
+        let keys = texts
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        let compressed = texts
+            .iter()
+            .map(|(_, text)| compress(text, data))
+            .collect::<Result<Vec<_>>>()?;
+        write_compressed(self, table, &keys, dictionary.map(|d| d.id), compressed).await
+    }
+
+    async fn load_texts(&self, table: TextTable, keys: &[String]) -> Result<Vec<(String, String)>> {
+        let rows: Vec<(String, Option<i32>, Vec<u8>)> = sqlx::query(&format!(
+            r"
+            SELECT {key}::TEXT, dictionary_id, compressed FROM {table}
+            WHERE {key} = ANY($1::TEXT[]::{key_type}[])
+            ",
+            key = table.key(),
+            table = table.name(),
+            key_type = table.key_type(),
+        ))
+        .bind(keys)
+        .try_map(|row: PgRow| Ok((row.try_get(0)?, row.try_get(1)?, row.try_get(2)?)))
+        .fetch_all(self)
+        .await?;
+
+        let dictionaries = load_dictionaries(self, rows.iter().filter_map(|row| row.1)).await?;
+        rows.into_iter()
+            .map(|(key, dictionary_id, compressed)| {
+                let dictionary = dictionary_id.map(|id| dictionaries[&id].as_slice());
+                let text = decompress(&compressed, dictionary)
+                    .with_context(|| format!("Failed to decompress {}", key))?;
+                Ok((key, text))
+            })
+            .collect()
+    }
+
+    async fn load_latest_dictionary(&self, table: TextTable) -> Result<Option<Dictionary>> {
+        let dictionary = sqlx::query(
+            r"
+            SELECT id, dictionary FROM compression_dictionaries
+            WHERE table_name = $1
+            ORDER BY id DESC LIMIT 1
+            ",
+        )
+        .bind(table.name())
+        .try_map(|row: PgRow| {
+            Ok(Dictionary {
+                id: row.try_get(0)?,
+                data: row.try_get(1)?,
+            })
+        })
+        .fetch_optional(self)
+        .await?;
+        Ok(dictionary)
+    }
+
+    async fn train_text_dictionary(
+        &self,
+        table: TextTable,
+        sample_count: i64,
+    ) -> Result<Option<i32>> {
+        let rows: Vec<(Option<i32>, Vec<u8>)> = sqlx::query(&format!(
+            "SELECT dictionary_id, compressed FROM {} ORDER BY RANDOM() LIMIT $1",
+            table.name()
+        ))
+        .bind(sample_count)
+        .try_map(|row: PgRow| Ok((row.try_get(0)?, row.try_get(1)?)))
+        .fetch_all(self)
+        .await?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        let dictionaries = load_dictionaries(self, rows.iter().filter_map(|row| row.0)).await?;
+        let samples = rows
+            .iter()
+            .map(|(dictionary_id, compressed)| {
+                let dictionary = dictionary_id.map(|id| dictionaries[&id].as_slice());
+                decompress(compressed, dictionary)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let dictionary = train_dictionary(&samples)?;
+
+        let id = sqlx::query(
+            r"
+            INSERT INTO compression_dictionaries (table_name, dictionary) VALUES ($1, $2)
+            RETURNING id
+            ",
+        )
+        .bind(table.name())
+        .bind(dictionary)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_one(self)
+        .await?;
+        Ok(Some(id))
+    }
+
+    async fn recompress_texts(&self, table: TextTable, batch_size: i64) -> Result<usize> {
+        let latest = match self.load_latest_dictionary(table).await? {
+            Some(dictionary) => dictionary,
+            None => return Ok(0),
+        };
+        let mut recompressed = 0;
+        loop {
+            let rows: Vec<(String, Option<i32>, Vec<u8>)> = sqlx::query(&format!(
+                r"
+                SELECT {key}::TEXT, dictionary_id, compressed FROM {table}
+                WHERE dictionary_id IS DISTINCT FROM $1
+                ORDER BY {key} LIMIT $2
+                ",
+                key = table.key(),
+                table = table.name(),
+            ))
+            .bind(latest.id)
+            .bind(batch_size)
+            .try_map(|row: PgRow| Ok((row.try_get(0)?, row.try_get(1)?, row.try_get(2)?)))
+            .fetch_all(self)
+            .await?;
+            if rows.is_empty() {
+                return Ok(recompressed);
+            }
+
+            let dictionaries = load_dictionaries(self, rows.iter().filter_map(|row| row.1)).await?;
+            let keys = rows.iter().map(|row| row.0.as_str()).collect::<Vec<_>>();
+            let compressed = rows
+                .iter()
+                .map(|(_, dictionary_id, compressed)| {
+                    let dictionary = dictionary_id.map(|id| dictionaries[&id].as_slice());
+                    let text = decompress(compressed, dictionary)?;
+                    compress(&text, Some(&latest.data))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            recompressed +=
+                write_compressed(self, table, &keys, Some(latest.id), compressed).await?;
+        }
+    }
+}
+
+async fn write_compressed(
+    pool: &PgPool,
+    table: TextTable,
+    keys: &[&str],
+    dictionary_id: Option<i32>,
+    compressed: Vec<Vec<u8>>,
+) -> Result<usize> {
+    let result = sqlx::query(&format!(
+        r"
+        INSERT INTO {table} ({key}, dictionary_id, compressed)
+        SELECT key::{key_type}, $2, compressed FROM UNNEST($1::TEXT[], $3::BYTEA[])
+        AS t(key, compressed)
+        ON CONFLICT ({key}) DO UPDATE SET
+            dictionary_id = EXCLUDED.dictionary_id,
+            compressed = EXCLUDED.compressed
+        ",
+        table = table.name(),
+        key = table.key(),
+        key_type = table.key_type(),
+    ))
+    .bind(keys)
+    .bind(dictionary_id)
+    .bind(compressed)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() as usize)
+}
+
+async fn load_dictionaries<I>(pool: &PgPool, ids: I) -> Result<HashMap<i32, Vec<u8>>>
+where
+    I: Iterator<Item = i32>,
+{
+    let ids = ids.collect::<BTreeSet<_>>().into_iter().collect::<Vec<_>>();
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let dictionaries =
+        sqlx::query("SELECT id, dictionary FROM compression_dictionaries WHERE id = ANY($1)")
+            .bind(&ids)
+            .try_map(|row: PgRow| Ok((row.try_get(0)?, row.try_get(1)?)))
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+    if let Some(id) = ids.iter().find(|id| !dictionaries.contains_key(*id)) {
+        return Err(anyhow!("The dictionary {} does not exist", id));
+    }
+    Ok(dictionaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_code(i: usize) -> String {
+        format!(
+            r#"use std::io::*;
+
+fn main() {{
+    let mut s = String::new();
+    stdin().read_to_string(&mut s).unwrap();
+    let n: usize = s.trim().parse().unwrap();
+    println!("{{}}", n * {});
+}}
+"#,
+            i
+        )
+    }
+
+    #[test]
+    fn test_compress() {
+        let text = source_code(1);
+        let compressed = compress(&text, None).unwrap();
+        assert_eq!(decompress(&compressed, None).unwrap(), text);
+        assert!(decompress(b"not compressed", None).is_err());
+    }
+
+    #[test]
+    fn test_compress_with_dictionary() {
+        let samples = (0..1000).map(source_code).collect::<Vec<_>>();
+        let dictionary = train_dictionary(&samples).unwrap();
+
+        let text = source_code(1000);
+        let compressed = compress(&text, Some(&dictionary)).unwrap();
+        assert_eq!(decompress(&compressed, Some(&dictionary)).unwrap(), text);
+        assert!(compressed.len() < compress(&text, None).unwrap().len());
+    }
+
+    #[test]
+    fn test_parse_text_table() {
+        for &table in [TextTable::SourceCodes, TextTable::ProblemStatements].iter() {
+            assert_eq!(table.name().parse::<TextTable>().unwrap(), table);
+        }
+        assert!("submissions".parse::<TextTable>().is_err());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{FIRST_AGC_EPOCH_SECOND, UNRATED_STATE};
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// The categories of the contests, which are the tabs of the table page of the frontend.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ContestCategory {
+    #[serde(rename = "ABC")]
+    Abc,
+    #[serde(rename = "ARC")]
+    Arc,
+    #[serde(rename = "AGC")]
+    Agc,
+    #[serde(rename = "ABC-Like")]
+    AbcLike,
+    #[serde(rename = "ARC-Like")]
+    ArcLike,
+    #[serde(rename = "AGC-Like")]
+    AgcLike,
+    #[serde(rename = "PAST")]
+    Past,
+    #[serde(rename = "JOI")]
+    Joi,
+    #[serde(rename = "JAG")]
+    Jag,
+    #[serde(rename = "AHC")]
+    Ahc,
+    #[serde(rename = "Marathon")]
+    Marathon,
+    #[serde(rename = "Other Sponsored")]
+    OtherSponsored,
+    #[serde(rename = "Other Contests")]
+    OtherContests,
+}
+
+const CATEGORIES: [ContestCategory; 13] = [
+    ContestCategory::Abc,
+    ContestCategory::Arc,
+    ContestCategory::Agc,
+    ContestCategory::AbcLike,
+    ContestCategory::ArcLike,
+    ContestCategory::AgcLike,
+    ContestCategory::Past,
+    ContestCategory::Joi,
+    ContestCategory::Jag,
+    ContestCategory::Ahc,
+    ContestCategory::Marathon,
+    ContestCategory::OtherSponsored,
+    ContestCategory::OtherContests,
+];
+
+const MARATHON_CONTEST_IDS: [&str; 4] = [
+    "caddi2019",
+    "pakencamp-2019-day2",
+    "kuronekoyamato-contest2019",
+    "wn2017_1",
+];
+
+lazy_static! {
+    static ref MARATHON_TITLE: Regex = Regex::new(
+        "(^Chokudai Contest|ハーフマラソン|^HACK TO THE FUTURE|Asprova|Heuristics Contest)",
+    )
+    .unwrap();
+    static ref SPONSORED_TITLE: Regex = Regex::new(concat!(
+        "(ドワンゴ|^Mujin|SoundHound|^codeFlyer|^COLOCON|みんなのプロコン|CODE THANKS FESTIVAL",
+        "|CODE FESTIVAL|^DISCO|日本最強プログラマー学生選手権|全国統一プログラミング王|Indeed",
+        "|^Donuts|^dwango|^DigitalArts|^Code Formula|天下一プログラマーコンテスト)",
+    ))
+    .unwrap();
+}
+
+impl Default for ContestCategory {
+    fn default() -> Self {
+        ContestCategory::OtherContests
+    }
+}
+
+impl ContestCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContestCategory::Abc => "ABC",
+            ContestCategory::Arc => "ARC",
+            ContestCategory::Agc => "AGC",
+            ContestCategory::AbcLike => "ABC-Like",
+            ContestCategory::ArcLike => "ARC-Like",
+            ContestCategory::AgcLike => "AGC-Like",
+            ContestCategory::Past => "PAST",
+            ContestCategory::Joi => "JOI",
+            ContestCategory::Jag => "JAG",
+            ContestCategory::Ahc => "AHC",
+            ContestCategory::Marathon => "Marathon",
+            ContestCategory::OtherSponsored => "Other Sponsored",
+            ContestCategory::OtherContests => "Other Contests",
+        }
+    }
+
+    /// Classifies the contest in the same way as the frontend did.
+    pub fn classify(id: &str, title: &str, rated_range: Option<(i64, Option<i64>)>) -> Self {
+        let numbered = |prefix: &str| {
+            id.strip_prefix(prefix).map_or(false, |n| {
+                n.len() == 3 && n.bytes().all(|b| b.is_ascii_digit())
+            })
+        };
+        if numbered("abc") {
+            return ContestCategory::Abc;
+        }
+        if numbered("arc") {
+            return ContestCategory::Arc;
+        }
+        if numbered("agc") {
+            return ContestCategory::Agc;
+        }
+        match rated_range {
+            Some((_, None)) => return ContestCategory::AgcLike,
+            Some((_, Some(upper))) if upper < 2000 => return ContestCategory::AbcLike,
+            Some(_) => return ContestCategory::ArcLike,
+            None => {}
+        }
+
+        if id.starts_with("past") {
+            return ContestCategory::Past;
+        }
+        if id.starts_with("joi") {
+            return ContestCategory::Joi;
+        }
+        if id.starts_with("jag") || id.starts_with("JAG") {
+            return ContestCategory::Jag;
+        }
+        if numbered("ahc") {
+            return ContestCategory::Ahc;
+        }
+
+        if MARATHON_TITLE.is_match(title)
+            || id.starts_with("future-meets-you-contest")
+            || id.starts_with("hokudai-hitachi")
+            || MARATHON_CONTEST_IDS.contains(&id)
+        {
+            return ContestCategory::Marathon;
+        }
+        if SPONSORED_TITLE.is_match(title) {
+            return ContestCategory::OtherSponsored;
+        }
+        ContestCategory::OtherContests
+    }
+}
+
+impl FromStr for ContestCategory {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        CATEGORIES
+            .iter()
+            .find(|category| category.as_str() == s)
+            .copied()
+            .ok_or_else(|| anyhow!("Unknown contest category: {}", s))
+    }
+}
+
+/// Parses `rate_change` of the contest, e.g. ` ~ 1999`, `1200 ~ 2799`, `1200 ~ ` or `All`, into
+/// the lowest and the highest ratings which are rated, or `None` if the contest is unrated.
+/// The contests before AGC 001 are unrated, since the current rating system started with it.
+pub fn parse_rated_range(start_epoch_second: i64, rate_change: &str) -> Option<(i64, Option<i64>)> {
+    let rate_change = rate_change.trim();
+    if start_epoch_second < FIRST_AGC_EPOCH_SECOND || rate_change == UNRATED_STATE {
+        return None;
+    }
+    if rate_change == "All" {
+        return Some((0, None));
+    }
+    let mut bounds = rate_change.split('~').map(|bound| bound.trim());
+    let (lower, upper) = match (bounds.next(), bounds.next(), bounds.next()) {
+        (Some(lower), Some(upper), None) => (lower, upper),
+        _ => return None,
+    };
+    let upper = upper.parse::<i64>().ok();
+    let lower = lower.parse::<i64>().ok();
+    match (lower, upper) {
+        (None, None) => None,
+        (lower, upper) => Some((lower.unwrap_or(0), upper)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFTER_AGC001: i64 = FIRST_AGC_EPOCH_SECOND + 1;
+
+    #[test]
+    fn test_parse_rated_range() {
+        assert_eq!(
+            parse_rated_range(AFTER_AGC001, " ~ 1999"),
+            Some((0, Some(1999)))
+        );
+        assert_eq!(
+            parse_rated_range(AFTER_AGC001, "1200 ~ 2799"),
+            Some((1200, Some(2799)))
+        );
+        assert_eq!(
+            parse_rated_range(AFTER_AGC001, "1200 ~ "),
+            Some((1200, None))
+        );
+        assert_eq!(parse_rated_range(AFTER_AGC001, "All"), Some((0, None)));
+        assert_eq!(parse_rated_range(AFTER_AGC001, "-"), None);
+        assert_eq!(parse_rated_range(AFTER_AGC001, " ~ "), None);
+        assert_eq!(parse_rated_range(0, " ~ 1999"), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let classify = |id: &str, title: &str, rate_change: &str| {
+            ContestCategory::classify(id, title, parse_rated_range(AFTER_AGC001, rate_change))
+        };
+        assert_eq!(classify("abc200", "", " ~ 1999"), ContestCategory::Abc);
+        assert_eq!(classify("arc110", "", " ~ 2799"), ContestCategory::Arc);
+        assert_eq!(classify("agc050", "", "All"), ContestCategory::Agc);
+        assert_eq!(
+            classify("abc200_old", "", " ~ 1999"),
+            ContestCategory::AbcLike
+        );
+        assert_eq!(
+            classify("keyence2021", "", " ~ 2799"),
+            ContestCategory::ArcLike
+        );
+        assert_eq!(classify("wtf22", "", "1200 ~ "), ContestCategory::AgcLike);
+        assert_eq!(classify("past202012-open", "", "-"), ContestCategory::Past);
+        assert_eq!(classify("joi2021yo1a", "", "-"), ContestCategory::Joi);
+        assert_eq!(classify("jag2017summer", "", "-"), ContestCategory::Jag);
+        assert_eq!(classify("ahc001", "", "-"), ContestCategory::Ahc);
+        assert_eq!(
+            classify("chokudai005", "Chokudai Contest 005", "-"),
+            ContestCategory::Marathon
+        );
+        assert_eq!(classify("caddi2019", "", "-"), ContestCategory::Marathon);
+        assert_eq!(
+            classify("code-festival-2017-qualc", "CODE FESTIVAL 2017 qual C", "-"),
+            ContestCategory::OtherSponsored
+        );
+        assert_eq!(
+            classify("practice", "", "-"),
+            ContestCategory::OtherContests
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        for category in CATEGORIES.iter() {
+            assert_eq!(
+                category.as_str().parse::<ContestCategory>().unwrap(),
+                *category
+            );
+            assert_eq!(
+                serde_json::to_value(category).unwrap(),
+                serde_json::json!(category.as_str())
+            );
+        }
+        assert!("abc".parse::<ContestCategory>().is_err());
+    }
+}
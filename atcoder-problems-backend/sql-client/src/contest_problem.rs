@@ -1,3 +1,4 @@
+use crate::ids::{ContestId, ProblemId};
 use crate::models::ContestProblem;
 use crate::PgPool;
 use anyhow::Result;
@@ -40,8 +41,8 @@ impl ContestProblemClient for PgPool {
     async fn load_contest_problem(&self) -> Result<Vec<ContestProblem>> {
         let problems = sqlx::query("SELECT contest_id, problem_id FROM contest_problem")
             .try_map(|row: PgRow| {
-                let contest_id: String = row.try_get("contest_id")?;
-                let problem_id: String = row.try_get("problem_id")?;
+                let contest_id: ContestId = row.try_get("contest_id")?;
+                let problem_id: ProblemId = row.try_get("problem_id")?;
                 Ok(ContestProblem {
                     contest_id,
                     problem_id,
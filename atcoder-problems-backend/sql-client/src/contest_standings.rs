@@ -0,0 +1,157 @@
+use crate::ids::ContestId;
+use crate::models::{ContestStandings, StandingsEntry};
+use crate::{PgPool, MAX_INSERT_ROWS};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+#[async_trait]
+pub trait ContestStandingsClient {
+    /// Replaces the standings of the contest, and records when they are fetched.
+    async fn update_contest_standings(
+        &self,
+        contest_id: &ContestId,
+        standings: &[StandingsEntry],
+        updated_epoch_second: i64,
+    ) -> Result<()>;
+    async fn load_contest_standings(
+        &self,
+        contest_id: &ContestId,
+    ) -> Result<Option<ContestStandings>>;
+
+    /// Returns when the standings of each contest are fetched last time.
+    async fn load_standings_updated_epoch_seconds(&self) -> Result<Vec<(String, i64)>>;
+}
+
+#[async_trait]
+impl ContestStandingsClient for PgPool {
+    async fn update_contest_standings(
+        &self,
+        contest_id: &ContestId,
+        standings: &[StandingsEntry],
+        updated_epoch_second: i64,
+    ) -> Result<()> {
+        let mut tx = self.begin().await?;
+        sqlx::query("DELETE FROM contest_standings WHERE contest_id = $1")
+            .bind(contest_id)
+            .execute(&mut tx)
+            .await?;
+
+        for chunk in standings.chunks(MAX_INSERT_ROWS) {
+            let user_ids = chunk.iter().map(|e| e.user_id.as_str()).collect::<Vec<_>>();
+            let ranks = chunk.iter().map(|e| e.rank).collect::<Vec<_>>();
+            let is_rated = chunk.iter().map(|e| e.is_rated).collect::<Vec<_>>();
+            let old_ratings = chunk.iter().map(|e| e.old_rating).collect::<Vec<_>>();
+            let solved_counts = chunk.iter().map(|e| e.solved_count).collect::<Vec<_>>();
+            let points = chunk.iter().map(|e| e.point).collect::<Vec<_>>();
+            let elapsed_seconds = chunk.iter().map(|e| e.elapsed_second).collect::<Vec<_>>();
+            let penalties = chunk.iter().map(|e| e.penalty).collect::<Vec<_>>();
+
+            sqlx::query(
+                r"
+                INSERT INTO contest_standings
+                (contest_id, user_id, rank, is_rated, old_rating,
+                 solved_count, point, elapsed_second, penalty)
+                VALUES (
+                    $1,
+                    UNNEST($2::VARCHAR(255)[]),
+                    UNNEST($3::BIGINT[]),
+                    UNNEST($4::BOOLEAN[]),
+                    UNNEST($5::BIGINT[]),
+                    UNNEST($6::BIGINT[]),
+                    UNNEST($7::DOUBLE PRECISION[]),
+                    UNNEST($8::BIGINT[]),
+                    UNNEST($9::BIGINT[])
+                )
+                ",
+            )
+            .bind(contest_id)
+            .bind(user_ids)
+            .bind(ranks)
+            .bind(is_rated)
+            .bind(old_ratings)
+            .bind(solved_counts)
+            .bind(points)
+            .bind(elapsed_seconds)
+            .bind(penalties)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        sqlx::query(
+            r"
+            INSERT INTO contest_standings_updates (contest_id, updated_epoch_second)
+            VALUES ($1, $2)
+            ON CONFLICT (contest_id)
+            DO UPDATE SET updated_epoch_second = EXCLUDED.updated_epoch_second
+            ",
+        )
+        .bind(contest_id)
+        .bind(updated_epoch_second)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn load_contest_standings(
+        &self,
+        contest_id: &ContestId,
+    ) -> Result<Option<ContestStandings>> {
+        let updated_epoch_second = sqlx::query(
+            "SELECT updated_epoch_second FROM contest_standings_updates WHERE contest_id = $1",
+        )
+        .bind(contest_id)
+        .try_map(|row: PgRow| row.try_get::<i64, _>("updated_epoch_second"))
+        .fetch_optional(self)
+        .await?;
+        let updated_epoch_second = match updated_epoch_second {
+            Some(updated_epoch_second) => updated_epoch_second,
+            None => return Ok(None),
+        };
+
+        let standings = sqlx::query(
+            r"
+            SELECT user_id, rank, is_rated, old_rating, solved_count, point, elapsed_second, penalty
+            FROM contest_standings
+            WHERE contest_id = $1
+            ORDER BY rank, user_id
+            ",
+        )
+        .bind(contest_id)
+        .try_map(|row: PgRow| {
+            Ok(StandingsEntry {
+                user_id: row.try_get("user_id")?,
+                rank: row.try_get("rank")?,
+                is_rated: row.try_get("is_rated")?,
+                old_rating: row.try_get("old_rating")?,
+                solved_count: row.try_get("solved_count")?,
+                point: row.try_get("point")?,
+                elapsed_second: row.try_get("elapsed_second")?,
+                penalty: row.try_get("penalty")?,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(Some(ContestStandings {
+            contest_id: contest_id.to_string(),
+            updated_epoch_second,
+            standings,
+        }))
+    }
+
+    async fn load_standings_updated_epoch_seconds(&self) -> Result<Vec<(String, i64)>> {
+        let updates =
+            sqlx::query("SELECT contest_id, updated_epoch_second FROM contest_standings_updates")
+                .try_map(|row: PgRow| {
+                    let contest_id: String = row.try_get("contest_id")?;
+                    let updated_epoch_second: i64 = row.try_get("updated_epoch_second")?;
+                    Ok((contest_id, updated_epoch_second))
+                })
+                .fetch_all(self)
+                .await?;
+        Ok(updates)
+    }
+}
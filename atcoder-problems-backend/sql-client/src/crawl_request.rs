@@ -0,0 +1,157 @@
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::{PgListener, PgRow};
+use sqlx::Row;
+
+/// The channel which the trigger on the `crawl_requests` table notifies.
+pub const CRAWL_REQUEST_CHANNEL: &str = "crawl_requests";
+
+/// What `crawl requests` crawls. It is stored as `kind` and `target_id` in `crawl_requests`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CrawlRequest {
+    Contest(String),
+    User(String),
+}
+
+impl CrawlRequest {
+    fn kind(&self) -> &'static str {
+        match self {
+            CrawlRequest::Contest(_) => "contest",
+            CrawlRequest::User(_) => "user",
+        }
+    }
+
+    fn target_id(&self) -> &str {
+        match self {
+            CrawlRequest::Contest(id) | CrawlRequest::User(id) => id,
+        }
+    }
+}
+
+/// A queue of the contests and the users to be re-crawled, which the crawler daemon listens to.
+/// Each of them is queued at most once, with the highest priority it has been requested with.
+#[async_trait]
+pub trait CrawlRequestClient {
+    async fn enqueue_crawl(
+        &self,
+        request: &CrawlRequest,
+        priority: i32,
+        requested_epoch_second: i64,
+    ) -> Result<()>;
+
+    /// Queues a request again after it failed `failed_attempts` times, behind the other requests.
+    /// Nothing is changed if the same crawl has been requested again in the meantime.
+    async fn retry_crawl(
+        &self,
+        request: &CrawlRequest,
+        failed_attempts: i32,
+        requested_epoch_second: i64,
+    ) -> Result<()>;
+
+    /// Removes and returns the request with the highest priority,
+    /// or the oldest one among the requests with the same priority,
+    /// with the number of the times it has failed.
+    async fn pop_crawl(&self) -> Result<Option<(CrawlRequest, i32)>>;
+}
+
+#[async_trait]
+impl CrawlRequestClient for PgPool {
+    async fn enqueue_crawl(
+        &self,
+        request: &CrawlRequest,
+        priority: i32,
+        requested_epoch_second: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO crawl_requests (kind, target_id, priority, requested_epoch_second)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (kind, target_id)
+            DO UPDATE SET priority = GREATEST(crawl_requests.priority, EXCLUDED.priority)
+            ",
+        )
+        .bind(request.kind())
+        .bind(request.target_id())
+        .bind(priority)
+        .bind(requested_epoch_second)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn retry_crawl(
+        &self,
+        request: &CrawlRequest,
+        failed_attempts: i32,
+        requested_epoch_second: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO crawl_requests
+            (kind, target_id, priority, requested_epoch_second, failed_attempts)
+            VALUES ($1, $2, 0, $3, $4)
+            ON CONFLICT (kind, target_id) DO NOTHING
+            ",
+        )
+        .bind(request.kind())
+        .bind(request.target_id())
+        .bind(requested_epoch_second)
+        .bind(failed_attempts)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn pop_crawl(&self) -> Result<Option<(CrawlRequest, i32)>> {
+        let request = sqlx::query(
+            r"
+            DELETE FROM crawl_requests
+            WHERE (kind, target_id) = (
+                SELECT kind, target_id FROM crawl_requests
+                ORDER BY priority DESC, requested_epoch_second ASC
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING kind, target_id, failed_attempts
+            ",
+        )
+        .try_map(|row: PgRow| {
+            let kind: String = row.try_get("kind")?;
+            let target_id: String = row.try_get("target_id")?;
+            let failed_attempts: i32 = row.try_get("failed_attempts")?;
+            let request = match kind.as_str() {
+                "contest" => CrawlRequest::Contest(target_id),
+                "user" => CrawlRequest::User(target_id),
+                _ => {
+                    return Err(sqlx::Error::Decode(
+                        format!("Unknown crawl request: {}", kind).into(),
+                    ))
+                }
+            };
+            Ok((request, failed_attempts))
+        })
+        .fetch_optional(self)
+        .await?;
+        Ok(request)
+    }
+}
+
+/// Wakes up the crawler daemon when a crawl is requested, including by `INSERT` in psql.
+pub struct CrawlRequestListener {
+    listener: PgListener,
+}
+
+impl CrawlRequestListener {
+    pub async fn connect(pool: &PgPool) -> Result<Self> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(CRAWL_REQUEST_CHANNEL).await?;
+        Ok(Self { listener })
+    }
+
+    /// Waits until a crawl is requested. The request itself has to be taken by `pop_crawl`.
+    pub async fn recv(&mut self) -> Result<()> {
+        self.listener.recv().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::ids::ContestId;
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+/// A run of a job, which is recorded in `crawler_runs` when the job finishes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CrawlerRun {
+    pub run_id: String,
+    pub job: String,
+    /// The contests which the run has finished, e.g. crawled the submissions of.
+    pub contest_ids: Vec<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub started_epoch_second: i64,
+    pub finished_epoch_second: i64,
+    pub rows_written: i64,
+    pub failures: i64,
+}
+
+#[async_trait]
+pub trait CrawlerRunClient {
+    async fn insert_crawler_run(&self, run: &CrawlerRun) -> Result<()>;
+
+    /// Loads the latest `limit` runs, of the job and of the runs which processed the contest
+    /// if they are specified.
+    async fn load_crawler_runs(
+        &self,
+        job: Option<&str>,
+        contest_id: Option<&ContestId>,
+        limit: i64,
+    ) -> Result<Vec<CrawlerRun>>;
+}
+
+#[async_trait]
+impl CrawlerRunClient for PgPool {
+    async fn insert_crawler_run(&self, run: &CrawlerRun) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO crawler_runs (
+                run_id, job, contest_ids, status, error,
+                started_epoch_second, finished_epoch_second, rows_written, failures
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ",
+        )
+        .bind(&run.run_id)
+        .bind(&run.job)
+        .bind(&run.contest_ids)
+        .bind(&run.status)
+        .bind(&run.error)
+        .bind(run.started_epoch_second)
+        .bind(run.finished_epoch_second)
+        .bind(run.rows_written)
+        .bind(run.failures)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_crawler_runs(
+        &self,
+        job: Option<&str>,
+        contest_id: Option<&ContestId>,
+        limit: i64,
+    ) -> Result<Vec<CrawlerRun>> {
+        let runs = sqlx::query(
+            r"
+            SELECT * FROM crawler_runs
+            WHERE ($1::VARCHAR IS NULL OR job = $1)
+            AND ($2::VARCHAR IS NULL OR $2 = ANY(contest_ids))
+            ORDER BY finished_epoch_second DESC
+            LIMIT $3
+            ",
+        )
+        .bind(job)
+        .bind(contest_id)
+        .bind(limit)
+        .try_map(|row: PgRow| {
+            Ok(CrawlerRun {
+                run_id: row.try_get("run_id")?,
+                job: row.try_get("job")?,
+                contest_ids: row.try_get("contest_ids")?,
+                status: row.try_get("status")?,
+                error: row.try_get("error")?,
+                started_epoch_second: row.try_get("started_epoch_second")?,
+                finished_epoch_second: row.try_get("finished_epoch_second")?,
+                rows_written: row.try_get("rows_written")?,
+                failures: row.try_get("failures")?,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(runs)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+pub const CONTESTS_DATA: &str = "contests";
+pub const PROBLEMS_DATA: &str = "problems";
+pub const MERGED_PROBLEMS_DATA: &str = "merged-problems";
+pub const RANKINGS_DATA: &str = "rankings";
+
+/// All the data which have the versions.
+pub const ALL_DATA: &[&str] = &[
+    CONTESTS_DATA,
+    PROBLEMS_DATA,
+    MERGED_PROBLEMS_DATA,
+    RANKINGS_DATA,
+];
+
+/// Counters which are incremented every time the corresponding data is modified,
+/// so that the API server can tell whether the clients have the latest data.
+#[async_trait]
+pub trait DataVersionClient {
+    async fn increment_data_version(&self, name: &str) -> Result<()>;
+    async fn load_data_version(&self, name: &str) -> Result<i64>;
+}
+
+#[async_trait]
+impl DataVersionClient for PgPool {
+    async fn increment_data_version(&self, name: &str) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO data_versions (name, version)
+            VALUES ($1, 1)
+            ON CONFLICT (name)
+            DO UPDATE SET version = data_versions.version + 1
+            ",
+        )
+        .bind(name)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_data_version(&self, name: &str) -> Result<i64> {
+        let version = sqlx::query("SELECT version FROM data_versions WHERE name = $1")
+            .bind(name)
+            .try_map(|row: PgRow| row.try_get::<i64, _>("version"))
+            .fetch_optional(self)
+            .await?;
+        Ok(version.unwrap_or(0))
+    }
+}
@@ -0,0 +1,218 @@
+//! Removes a user for the takedown requests. The user is added to `forgotten_users`, whose
+//! submissions and profile are skipped by the triggers when the crawlers insert them again.
+//! The user ids are compared case-insensitively, as AtCoder does.
+
+use crate::ids::UserId;
+use crate::PgPool;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::str::FromStr;
+
+/// The tables of the aggregated values and the crawled data of each user, which are deleted in
+/// both of the modes.
+const USER_TABLES: [&str; 14] = [
+    "accepted_count",
+    "rated_point_sum",
+    "language_count",
+    "predicted_rating",
+    "max_streaks",
+    "current_streaks",
+    "submission_count",
+    "achievements",
+    "training_velocity",
+    "recent_difficulty_count",
+    "live_performances",
+    "contest_standings",
+    "users",
+    "internal_webhooks",
+];
+
+/// The tables which refer to the submissions by `submission_id`.
+const SUBMISSION_TABLES: [&str; 4] = ["first", "fastest", "shortest", "submission_source_codes"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForgetMode {
+    /// Deletes the submissions of the user.
+    Delete,
+    /// Renames the user of the submissions to a pseudonym, keeping the statistics of the problems.
+    Anonymize,
+}
+
+impl ForgetMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ForgetMode::Delete => "delete",
+            ForgetMode::Anonymize => "anonymize",
+        }
+    }
+}
+
+impl FromStr for ForgetMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "delete" => Ok(ForgetMode::Delete),
+            "anonymize" => Ok(ForgetMode::Anonymize),
+            _ => Err(anyhow!("Unknown mode: {}", s)),
+        }
+    }
+}
+
+/// A row of `user_deletions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserDeletion {
+    pub id: i32,
+    pub user_id: String,
+    pub mode: ForgetMode,
+    pub pseudonym: Option<String>,
+    pub rows_affected: i64,
+}
+
+/// The pseudonym of an anonymized user, which never collides with the ids of AtCoder since they
+/// can not contain `-`.
+pub fn pseudonym(deletion_id: i32) -> String {
+    format!("deleted-user-{}", deletion_id)
+}
+
+#[async_trait]
+pub trait ForgetUserClient {
+    /// Forgets the user in a single transaction, and records it in `user_deletions`. The writes to
+    /// the submissions and the profiles are blocked until it finishes, so that a crawler can not
+    /// insert them in the meantime.
+    async fn forget_user(
+        &self,
+        user_id: &UserId,
+        mode: ForgetMode,
+        reason: Option<&str>,
+    ) -> Result<UserDeletion>;
+
+    async fn is_forgotten_user(&self, user_id: &UserId) -> Result<bool>;
+}
+
+#[async_trait]
+impl ForgetUserClient for PgPool {
+    async fn forget_user(
+        &self,
+        user_id: &UserId,
+        mode: ForgetMode,
+        reason: Option<&str>,
+    ) -> Result<UserDeletion> {
+        let mut tx = self.begin().await?;
+        sqlx::query("LOCK TABLE submissions, users IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut tx)
+            .await?;
+        sqlx::query("INSERT INTO forgotten_users (user_id) VALUES ($1) ON CONFLICT DO NOTHING")
+            .bind(user_id)
+            .execute(&mut tx)
+            .await?;
+        let id: i32 = sqlx::query(
+            "INSERT INTO user_deletions (user_id, mode, reason) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(user_id)
+        .bind(mode.as_str())
+        .bind(reason)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_one(&mut tx)
+        .await?;
+
+        let mut rows_affected = 0;
+        let pseudonym = match mode {
+            ForgetMode::Delete => {
+                for table in SUBMISSION_TABLES.iter() {
+                    rows_affected += sqlx::query(&format!(
+                        r"
+                        DELETE FROM {} WHERE submission_id IN (
+                            SELECT id FROM submissions WHERE LOWER(user_id) = LOWER($1)
+                        )
+                        ",
+                        table
+                    ))
+                    .bind(user_id)
+                    .execute(&mut tx)
+                    .await?
+                    .rows_affected();
+                }
+                rows_affected +=
+                    sqlx::query("DELETE FROM submissions WHERE LOWER(user_id) = LOWER($1)")
+                        .bind(user_id)
+                        .execute(&mut tx)
+                        .await?
+                        .rows_affected();
+                None
+            }
+            ForgetMode::Anonymize => {
+                let pseudonym = pseudonym(id);
+                rows_affected += sqlx::query(
+                    "UPDATE submissions SET user_id = $2 WHERE LOWER(user_id) = LOWER($1)",
+                )
+                .bind(user_id)
+                .bind(&pseudonym)
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+                Some(pseudonym)
+            }
+        };
+
+        for table in USER_TABLES.iter() {
+            rows_affected += sqlx::query(&format!(
+                "DELETE FROM {} WHERE LOWER(user_id) = LOWER($1)",
+                table
+            ))
+            .bind(user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        }
+        rows_affected += sqlx::query(
+            "UPDATE internal_users SET atcoder_user_id = NULL WHERE LOWER(atcoder_user_id) = LOWER($1)",
+        )
+        .bind(user_id)
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query("UPDATE user_deletions SET pseudonym = $2, rows_affected = $3 WHERE id = $1")
+            .bind(id)
+            .bind(&pseudonym)
+            .bind(rows_affected as i64)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(UserDeletion {
+            id,
+            user_id: user_id.to_string(),
+            mode,
+            pseudonym,
+            rows_affected: rows_affected as i64,
+        })
+    }
+
+    async fn is_forgotten_user(&self, user_id: &UserId) -> Result<bool> {
+        let count: i64 =
+            sqlx::query("SELECT COUNT(*) FROM forgotten_users WHERE LOWER(user_id) = LOWER($1)")
+                .bind(user_id)
+                .try_map(|row: PgRow| row.try_get(0))
+                .fetch_one(self)
+                .await?;
+        Ok(count > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forget_mode() {
+        for &mode in [ForgetMode::Delete, ForgetMode::Anonymize].iter() {
+            assert_eq!(mode.as_str().parse::<ForgetMode>().unwrap(), mode);
+        }
+        assert!("drop".parse::<ForgetMode>().is_err());
+        assert_eq!(pseudonym(3), "deleted-user-3");
+    }
+}
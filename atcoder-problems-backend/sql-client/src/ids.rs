@@ -0,0 +1,120 @@
+//! The ids of the problems, the contests and the users, which are distinct types so that one of
+//! them cannot be passed where another is expected. They are stored and serialized as plain strings,
+//! and they do not dereference to `str`, so `as_str()` has to be called explicitly.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+
+macro_rules! define_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            Debug,
+            Clone,
+            Default,
+            Eq,
+            PartialEq,
+            Ord,
+            PartialOrd,
+            Hash,
+            Serialize,
+            Deserialize,
+            sqlx::Type,
+        )]
+        #[serde(transparent)]
+        #[sqlx(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new<S: Into<String>>(id: S) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_string(self) -> String {
+                self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self(id.to_owned())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+    };
+}
+
+define_id!(
+    /// The id of a problem, e.g. `abc001_a`.
+    ProblemId
+);
+define_id!(
+    /// The id of a contest, e.g. `abc001`.
+    ContestId
+);
+define_id!(
+    /// The id of a user on AtCoder.
+    UserId
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_serde_as_string() {
+        let id = ProblemId::from("abc001_a");
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, r#""abc001_a""#);
+        let id: ProblemId = serde_json::from_str(&json).unwrap();
+        assert_eq!(id, ProblemId::from("abc001_a"));
+    }
+
+    #[test]
+    fn test_as_str() {
+        let id = ContestId::new("abc001");
+        assert_eq!(id.as_str(), "abc001");
+        assert_eq!(id.to_string(), "abc001");
+        assert!(id.as_str().starts_with("abc"));
+        assert_eq!(String::from(id), "abc001".to_owned());
+
+        let mut map = BTreeMap::new();
+        map.insert(UserId::from("user"), 1);
+        assert_eq!(map.get("user"), Some(&1));
+    }
+}
@@ -0,0 +1,254 @@
+//! Checks the consistency of the tables which the schema can not enforce, e.g. the ids of the
+//! submissions duplicated across the partitions, and the references without foreign keys.
+
+use crate::{quote_ident, PgPool};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+/// The number of the offending ids included in the report for each check.
+const SAMPLE_SIZE: i32 = 10;
+
+/// The columns which refer to the ids of the other tables, as the tuples of the table, the column,
+/// the referenced table and the referenced column.
+pub const REFERENCES: [(&str, &str, &str, &str); 9] = [
+    ("submissions", "problem_id", "problems", "id"),
+    ("submissions", "contest_id", "contests", "id"),
+    ("problems", "contest_id", "contests", "id"),
+    ("contest_problem", "problem_id", "problems", "id"),
+    ("contest_problem", "contest_id", "contests", "id"),
+    ("first", "submission_id", "submissions", "id"),
+    ("fastest", "submission_id", "submissions", "id"),
+    ("shortest", "submission_id", "submissions", "id"),
+    (
+        "submission_source_codes",
+        "submission_id",
+        "submissions",
+        "id",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableReport {
+    pub table: String,
+    pub row_count: i64,
+    /// The range of `id`, which is `None` if the table has no `id` or no rows.
+    pub min_id: Option<String>,
+    pub max_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DuplicateReport {
+    pub count: i64,
+    pub sample: Vec<i64>,
+}
+
+/// The rows whose values of `column` do not exist in `referenced_table`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrphanReport {
+    pub table: String,
+    pub column: String,
+    pub referenced_table: String,
+    pub count: i64,
+    pub sample: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    pub tables: Vec<TableReport>,
+    /// The ids shared by two or more submissions, which can happen only if the submissions are
+    /// partitioned, since the primary keys of the partitions include the partition key.
+    pub duplicate_submission_ids: DuplicateReport,
+    pub orphans: Vec<OrphanReport>,
+}
+
+impl IntegrityReport {
+    pub fn has_issues(&self) -> bool {
+        self.duplicate_submission_ids.count > 0 || self.orphans.iter().any(|o| o.count > 0)
+    }
+}
+
+#[async_trait]
+pub trait IntegrityClient {
+    /// Returns the tables of the public schema except the partitions, in the order of the names.
+    async fn load_table_names(&self) -> Result<Vec<String>>;
+    async fn check_table(&self, table: &str) -> Result<TableReport>;
+    async fn check_duplicate_submission_ids(&self) -> Result<DuplicateReport>;
+    async fn check_orphans(
+        &self,
+        table: &str,
+        column: &str,
+        referenced_table: &str,
+        referenced_column: &str,
+    ) -> Result<OrphanReport>;
+
+    /// Checks all the tables and all the `REFERENCES` of the existing tables.
+    async fn check_integrity(&self) -> Result<IntegrityReport> {
+        let names = self.load_table_names().await?;
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names.iter() {
+            tables.push(self.check_table(name).await?);
+        }
+        let duplicate_submission_ids = self.check_duplicate_submission_ids().await?;
+
+        let mut orphans = Vec::new();
+        for &(table, column, referenced_table, referenced_column) in REFERENCES.iter() {
+            let exists = |t: &str| names.iter().any(|name| name == t);
+            if exists(table) && exists(referenced_table) {
+                let report = self
+                    .check_orphans(table, column, referenced_table, referenced_column)
+                    .await?;
+                orphans.push(report);
+            }
+        }
+        Ok(IntegrityReport {
+            tables,
+            duplicate_submission_ids,
+            orphans,
+        })
+    }
+}
+
+#[async_trait]
+impl IntegrityClient for PgPool {
+    async fn load_table_names(&self) -> Result<Vec<String>> {
+        let names = sqlx::query(
+            r"
+            SELECT c.relname::TEXT FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = 'public' AND c.relkind IN ('r', 'p') AND NOT c.relispartition
+            ORDER BY c.relname
+            ",
+        )
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_all(self)
+        .await?;
+        Ok(names)
+    }
+
+    async fn check_table(&self, table: &str) -> Result<TableReport> {
+        let has_id: bool = sqlx::query(
+            r"
+            SELECT EXISTS(
+                SELECT 1 FROM information_schema.columns
+                WHERE table_schema = 'public' AND table_name = $1 AND column_name = 'id'
+            )
+            ",
+        )
+        .bind(table)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_one(self)
+        .await?;
+        let query = if has_id {
+            format!(
+                "SELECT COUNT(*), MIN(id)::TEXT, MAX(id)::TEXT FROM {}",
+                quote_ident(table)
+            )
+        } else {
+            format!(
+                "SELECT COUNT(*), NULL::TEXT, NULL::TEXT FROM {}",
+                quote_ident(table)
+            )
+        };
+        let report = sqlx::query(&query)
+            .try_map(|row: PgRow| {
+                Ok(TableReport {
+                    table: table.to_owned(),
+                    row_count: row.try_get(0)?,
+                    min_id: row.try_get(1)?,
+                    max_id: row.try_get(2)?,
+                })
+            })
+            .fetch_one(self)
+            .await?;
+        Ok(report)
+    }
+
+    async fn check_duplicate_submission_ids(&self) -> Result<DuplicateReport> {
+        let count: i64 = sqlx::query(
+            r"
+            SELECT COUNT(*) FROM (
+                SELECT id FROM submissions
+                GROUP BY id HAVING COUNT(*) > 1
+            ) AS duplicates
+            ",
+        )
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_one(self)
+        .await?;
+        let sample: Vec<i64> = sqlx::query(
+            r"
+            SELECT id FROM submissions
+            GROUP BY id HAVING COUNT(*) > 1
+            ORDER BY id
+            LIMIT $1
+            ",
+        )
+        .bind(SAMPLE_SIZE)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_all(self)
+        .await?;
+        Ok(DuplicateReport { count, sample })
+    }
+
+    async fn check_orphans(
+        &self,
+        table: &str,
+        column: &str,
+        referenced_table: &str,
+        referenced_column: &str,
+    ) -> Result<OrphanReport> {
+        let (count, sample): (i64, Option<Vec<String>>) = sqlx::query(&format!(
+            r"
+            SELECT COUNT(*), (ARRAY_AGG(DISTINCT t.{column}::TEXT))[1:$1] FROM {table} t
+            WHERE NOT EXISTS (
+                SELECT 1 FROM {referenced_table} r WHERE r.{referenced_column} = t.{column}
+            )
+            ",
+            table = quote_ident(table),
+            column = quote_ident(column),
+            referenced_table = quote_ident(referenced_table),
+            referenced_column = quote_ident(referenced_column),
+        ))
+        .bind(SAMPLE_SIZE)
+        .try_map(|row: PgRow| Ok((row.try_get(0)?, row.try_get(1)?)))
+        .fetch_one(self)
+        .await?;
+        Ok(OrphanReport {
+            table: table.to_owned(),
+            column: column.to_owned(),
+            referenced_table: referenced_table.to_owned(),
+            count,
+            sample: sample.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_issues() {
+        let orphans = |count| OrphanReport {
+            table: "submissions".to_owned(),
+            column: "problem_id".to_owned(),
+            referenced_table: "problems".to_owned(),
+            count,
+            sample: Vec::new(),
+        };
+        let mut report = IntegrityReport {
+            tables: Vec::new(),
+            duplicate_submission_ids: DuplicateReport {
+                count: 0,
+                sample: Vec::new(),
+            },
+            orphans: vec![orphans(0)],
+        };
+        assert!(!report.has_issues());
+        report.orphans.push(orphans(1));
+        assert!(report.has_issues());
+    }
+}
@@ -1,10 +1,11 @@
+use crate::ids::ProblemId;
 use crate::PgPool;
 use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::Row;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 const MAX_LIST_NUM: usize = 256;
 const MAX_ITEM_NUM: usize = 1024;
@@ -23,6 +24,66 @@ pub struct ListItem {
     pub memo: String,
 }
 
+/// The version of `ProblemListDocument`, which is raised on incompatible changes.
+pub const PROBLEM_LIST_FORMAT_VERSION: u32 = 1;
+
+/// The interchange format of a problem list, which is exported and imported by the API and the
+/// CLI. The lists returned by the API of AtCoder Problems can be imported as they are, since
+/// `internal_list_name` and `memo` are accepted as `name` and `note`.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ProblemListDocument {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(alias = "internal_list_name")]
+    pub name: String,
+    pub items: Vec<ProblemListEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct ProblemListEntry {
+    pub problem_id: String,
+    /// The 1-based position in the list. The entries without it follow the others in the order
+    /// of the document.
+    #[serde(default)]
+    pub order: Option<u32>,
+    #[serde(default, alias = "memo")]
+    pub note: String,
+}
+
+fn default_version() -> u32 {
+    PROBLEM_LIST_FORMAT_VERSION
+}
+
+impl ProblemListDocument {
+    pub fn from_list(list: ProblemList) -> Self {
+        let items = list
+            .items
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| ProblemListEntry {
+                problem_id: item.problem_id,
+                order: Some(i as u32 + 1),
+                note: item.memo,
+            })
+            .collect();
+        Self {
+            version: PROBLEM_LIST_FORMAT_VERSION,
+            name: list.internal_list_name,
+            items,
+        }
+    }
+
+    /// Returns the entries in the order of the list, where only the first entry of each problem
+    /// is kept.
+    pub fn ordered_items(&self) -> Vec<&ProblemListEntry> {
+        let mut items = self.items.iter().collect::<Vec<_>>();
+        items.sort_by_key(|item| item.order.unwrap_or(u32::MAX));
+        let mut seen = BTreeSet::new();
+        items.retain(|item| seen.insert(item.problem_id.as_str()));
+        items
+    }
+}
+
 #[async_trait]
 pub trait ProblemListManager {
     async fn get_list(&self, internal_user_id: &str) -> Result<Vec<ProblemList>>;
@@ -30,10 +91,21 @@ pub trait ProblemListManager {
     async fn create_list(&self, internal_user_id: &str, name: &str) -> Result<String>;
     async fn update_list(&self, internal_list_id: &str, name: &str) -> Result<()>;
     async fn delete_list(&self, internal_list_id: &str) -> Result<()>;
-    async fn add_item(&self, internal_list_id: &str, problem_id: &str) -> Result<()>;
-    async fn update_item(&self, internal_list_id: &str, problem_id: &str, memo: &str)
-        -> Result<()>;
-    async fn delete_item(&self, internal_list_id: &str, problem_id: &str) -> Result<()>;
+    async fn add_item(&self, internal_list_id: &str, problem_id: &ProblemId) -> Result<()>;
+    async fn update_item(
+        &self,
+        internal_list_id: &str,
+        problem_id: &ProblemId,
+        memo: &str,
+    ) -> Result<()>;
+    async fn delete_item(&self, internal_list_id: &str, problem_id: &ProblemId) -> Result<()>;
+    /// Returns `false` if the list is another user's or does not exist.
+    async fn is_list_owner(&self, internal_list_id: &str, internal_user_id: &str) -> Result<bool>;
+    async fn import_list(
+        &self,
+        internal_user_id: &str,
+        document: &ProblemListDocument,
+    ) -> Result<String>;
 }
 
 #[async_trait]
@@ -51,6 +123,7 @@ impl ProblemListManager for PgPool {
         LEFT JOIN internal_problem_list_items AS b
         ON a.internal_list_id = b.internal_list_id
         WHERE a.internal_user_id = $1
+        ORDER BY b.item_order, b.problem_id
             ",
         )
         .bind(internal_user_id)
@@ -106,6 +179,7 @@ impl ProblemListManager for PgPool {
         LEFT JOIN internal_problem_list_items AS b
         ON a.internal_list_id = b.internal_list_id
         WHERE a.internal_list_id = $1
+        ORDER BY b.item_order, b.problem_id
             ",
         )
         .bind(internal_list_id)
@@ -195,7 +269,7 @@ impl ProblemListManager for PgPool {
         Ok(())
     }
 
-    async fn add_item(&self, internal_list_id: &str, problem_id: &str) -> Result<()> {
+    async fn add_item(&self, internal_list_id: &str, problem_id: &ProblemId) -> Result<()> {
         let problems = sqlx::query(
             "SELECT problem_id FROM internal_problem_list_items WHERE internal_list_id = $1",
         )
@@ -209,8 +283,10 @@ impl ProblemListManager for PgPool {
 
         sqlx::query(
             r"
-            INSERT INTO internal_problem_list_items (internal_list_id, problem_id)
-            VALUES ($1, $2)
+            INSERT INTO internal_problem_list_items (internal_list_id, problem_id, item_order)
+            SELECT $1, $2, COALESCE(MAX(item_order), 0) + 1
+            FROM internal_problem_list_items
+            WHERE internal_list_id = $1
             ",
         )
         .bind(internal_list_id)
@@ -223,7 +299,7 @@ impl ProblemListManager for PgPool {
     async fn update_item(
         &self,
         internal_list_id: &str,
-        problem_id: &str,
+        problem_id: &ProblemId,
         memo: &str,
     ) -> Result<()> {
         sqlx::query(
@@ -241,7 +317,7 @@ impl ProblemListManager for PgPool {
         Ok(())
     }
 
-    async fn delete_item(&self, internal_list_id: &str, problem_id: &str) -> Result<()> {
+    async fn delete_item(&self, internal_list_id: &str, problem_id: &ProblemId) -> Result<()> {
         sqlx::query(
             r"
             DELETE FROM internal_problem_list_items
@@ -254,4 +330,71 @@ impl ProblemListManager for PgPool {
         .await?;
         Ok(())
     }
+    async fn is_list_owner(&self, internal_list_id: &str, internal_user_id: &str) -> Result<bool> {
+        let list = sqlx::query(
+            r"
+            SELECT internal_list_id
+            FROM internal_problem_lists
+            WHERE internal_user_id = $1
+            AND internal_list_id = $2
+            ",
+        )
+        .bind(internal_user_id)
+        .bind(internal_list_id)
+        .try_map(|row: PgRow| row.try_get::<String, _>("internal_list_id"))
+        .fetch_optional(self)
+        .await?;
+        Ok(list.is_some())
+    }
+
+    /// Creates a new list of the user with the items of the document in a transaction.
+    async fn import_list(
+        &self,
+        internal_user_id: &str,
+        document: &ProblemListDocument,
+    ) -> Result<String> {
+        if document.version > PROBLEM_LIST_FORMAT_VERSION {
+            bail!("Unsupported problem list version: {}", document.version);
+        }
+        let items = document.ordered_items();
+        if items.len() > MAX_ITEM_NUM {
+            bail!("Cannot create a list item anymore");
+        }
+        let list = self.get_list(internal_user_id).await?;
+        if list.len() >= MAX_LIST_NUM {
+            bail!("Cannot create a list anymore");
+        }
+
+        let new_list_id = uuid::Uuid::new_v4().to_string();
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            r"
+            INSERT INTO internal_problem_lists
+            (internal_user_id, internal_list_id, internal_list_name)
+            VALUES ($1, $2, $3)
+            ",
+        )
+        .bind(internal_user_id)
+        .bind(new_list_id.as_str())
+        .bind(document.name.as_str())
+        .execute(&mut tx)
+        .await?;
+        for (i, item) in items.into_iter().enumerate() {
+            sqlx::query(
+                r"
+                INSERT INTO internal_problem_list_items
+                (internal_list_id, problem_id, memo, item_order)
+                VALUES ($1, $2, $3, $4)
+                ",
+            )
+            .bind(new_list_id.as_str())
+            .bind(item.problem_id.as_str())
+            .bind(item.note.as_str())
+            .bind(i as i32 + 1)
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(new_list_id)
+    }
 }
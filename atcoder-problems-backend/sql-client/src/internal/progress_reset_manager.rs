@@ -1,9 +1,12 @@
+use crate::ids::ProblemId;
+use crate::models::Submission;
 use crate::PgPool;
 use anyhow::Result;
 use async_trait::async_trait;
 use serde::Serialize;
 use sqlx::postgres::PgRow;
 use sqlx::Row;
+use std::collections::HashMap;
 
 #[derive(Serialize, Debug, PartialEq, Eq)]
 pub struct ProgressResetList {
@@ -21,11 +24,15 @@ pub trait ProgressResetManager {
     async fn add_item(
         &self,
         internal_user_id: &str,
-        problem_id: &str,
+        problem_id: &ProblemId,
         reset_epoch_second: i64,
     ) -> Result<()>;
-    async fn remove_item(&self, internal_user_id: &str, problem_id: &str) -> Result<()>;
+    async fn remove_item(&self, internal_user_id: &str, problem_id: &ProblemId) -> Result<()>;
     async fn get_progress_reset_list(&self, internal_user_id: &str) -> Result<ProgressResetList>;
+
+    /// Returns the problems which the AtCoder account of the user has solved,
+    /// ignoring the accepted submissions before the problems were reset.
+    async fn get_solved_problems_after_reset(&self, internal_user_id: &str) -> Result<Vec<String>>;
 }
 
 #[async_trait]
@@ -33,7 +40,7 @@ impl ProgressResetManager for PgPool {
     async fn add_item(
         &self,
         internal_user_id: &str,
-        problem_id: &str,
+        problem_id: &ProblemId,
         reset_epoch_second: i64,
     ) -> Result<()> {
         sqlx::query(
@@ -52,7 +59,7 @@ impl ProgressResetManager for PgPool {
         Ok(())
     }
 
-    async fn remove_item(&self, internal_user_id: &str, problem_id: &str) -> Result<()> {
+    async fn remove_item(&self, internal_user_id: &str, problem_id: &ProblemId) -> Result<()> {
         sqlx::query(
             r"
             DELETE FROM internal_progress_reset
@@ -88,4 +95,123 @@ impl ProgressResetManager for PgPool {
         .await?;
         Ok(ProgressResetList { items })
     }
+
+    async fn get_solved_problems_after_reset(&self, internal_user_id: &str) -> Result<Vec<String>> {
+        let problem_ids = sqlx::query(
+            r"
+            SELECT DISTINCT s.problem_id
+            FROM internal_users AS u
+            JOIN submissions AS s
+            ON LOWER(s.user_id) = LOWER(u.atcoder_user_id)
+            LEFT JOIN internal_progress_reset AS r
+            ON r.internal_user_id = u.internal_user_id
+            AND r.problem_id = s.problem_id
+            WHERE u.internal_user_id = $1
+            AND s.result = 'AC'
+            AND (r.reset_epoch_second IS NULL OR s.epoch_second > r.reset_epoch_second)
+            ORDER BY s.problem_id
+            ",
+        )
+        .bind(internal_user_id)
+        .try_map(|row: PgRow| row.try_get::<String, _>("problem_id"))
+        .fetch_all(self)
+        .await?;
+        Ok(problem_ids)
+    }
+}
+
+/// The times when the users reset the problems, keyed by the AtCoder ids of the users in lower
+/// case and the problems. The accepted submissions before them are not counted as solved in the
+/// aggregations, as `get_solved_problems_after_reset` does not.
+#[derive(Debug, Default)]
+pub struct ProgressResets(HashMap<String, HashMap<String, i64>>);
+
+impl ProgressResets {
+    pub fn new<'a, I: IntoIterator<Item = (&'a str, &'a str, i64)>>(resets: I) -> Self {
+        let mut map = HashMap::new();
+        for (user_id, problem_id, reset_epoch_second) in resets {
+            map.entry(user_id.to_lowercase())
+                .or_insert_with(HashMap::new)
+                .insert(problem_id.to_owned(), reset_epoch_second);
+        }
+        Self(map)
+    }
+
+    /// Returns true if the user got accepted for the problem at `epoch_second` before they
+    /// reset it.
+    pub fn is_reset(&self, user_id: &str, problem_id: &str, epoch_second: i64) -> bool {
+        if self.0.is_empty() {
+            return false;
+        }
+        self.0
+            .get(&user_id.to_lowercase())
+            .and_then(|problems| problems.get(problem_id))
+            .map_or(false, |&reset_epoch_second| {
+                epoch_second <= reset_epoch_second
+            })
+    }
+
+    /// Removes the accepted submissions made before the users reset the problems.
+    pub fn exclude(&self, submissions: &mut Vec<Submission>) {
+        submissions
+            .retain(|s| !self.is_reset(s.user_id.as_str(), s.problem_id.as_str(), s.epoch_second));
+    }
+}
+
+/// Loads the resets of all the users who have linked their AtCoder accounts.
+pub async fn load_progress_resets(pool: &PgPool) -> Result<ProgressResets> {
+    let resets = sqlx::query(
+        r"
+        SELECT u.atcoder_user_id, r.problem_id, r.reset_epoch_second
+        FROM internal_progress_reset AS r
+        JOIN internal_users AS u
+        ON u.internal_user_id = r.internal_user_id
+        WHERE u.atcoder_user_id IS NOT NULL
+        ",
+    )
+    .try_map(|row: PgRow| {
+        let user_id: String = row.try_get("atcoder_user_id")?;
+        let problem_id: String = row.try_get("problem_id")?;
+        let reset_epoch_second: i64 = row.try_get("reset_epoch_second")?;
+        Ok((user_id, problem_id, reset_epoch_second))
+    })
+    .fetch_all(pool)
+    .await?;
+    Ok(ProgressResets::new(resets.iter().map(
+        |(user_id, problem_id, reset_epoch_second)| {
+            (user_id.as_str(), problem_id.as_str(), *reset_epoch_second)
+        },
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_resets() {
+        let resets = ProgressResets::new(vec![("User1", "abc001_a", 200)]);
+        assert!(resets.is_reset("user1", "abc001_a", 100));
+        assert!(resets.is_reset("USER1", "abc001_a", 200));
+        assert!(!resets.is_reset("user1", "abc001_a", 201));
+        assert!(!resets.is_reset("user1", "abc001_b", 100));
+        assert!(!resets.is_reset("user2", "abc001_a", 100));
+
+        let submission = |user_id: &str, epoch_second| Submission {
+            user_id: user_id.into(),
+            problem_id: "abc001_a".into(),
+            epoch_second,
+            ..Default::default()
+        };
+        let mut submissions = vec![
+            submission("user1", 100),
+            submission("user1", 300),
+            submission("user2", 100),
+        ];
+        resets.exclude(&mut submissions);
+        assert_eq!(
+            submissions,
+            vec![submission("user1", 300), submission("user2", 100)]
+        );
+    }
 }
@@ -0,0 +1,138 @@
+use crate::models::Submission;
+use crate::PgPool;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+const MAX_WEBHOOK_NUM: usize = 16;
+
+/// The conditions of the submissions to be delivered. `None` matches any submission.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+pub struct WebhookFilter {
+    pub user_id: Option<String>,
+    pub result: Option<String>,
+    pub contest_id: Option<String>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq, Clone)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// The key to sign the payloads, so that the receivers can verify them.
+    pub secret: String,
+    #[serde(flatten)]
+    pub filter: WebhookFilter,
+}
+
+#[async_trait]
+pub trait WebhookManager {
+    async fn get_webhooks(&self, internal_user_id: &str) -> Result<Vec<Webhook>>;
+    async fn add_webhook(
+        &self,
+        internal_user_id: &str,
+        url: &str,
+        filter: &WebhookFilter,
+    ) -> Result<Webhook>;
+    async fn delete_webhook(&self, internal_user_id: &str, id: &str) -> Result<()>;
+
+    /// Returns the webhooks whose filter matches the submission.
+    async fn load_matching_webhooks(&self, submission: &Submission) -> Result<Vec<Webhook>>;
+}
+
+fn map_webhook(row: PgRow) -> sqlx::Result<Webhook> {
+    Ok(Webhook {
+        id: row.try_get("id")?,
+        url: row.try_get("url")?,
+        secret: row.try_get("secret")?,
+        filter: WebhookFilter {
+            user_id: row.try_get("user_id")?,
+            result: row.try_get("result")?,
+            contest_id: row.try_get("contest_id")?,
+        },
+    })
+}
+
+#[async_trait]
+impl WebhookManager for PgPool {
+    async fn get_webhooks(&self, internal_user_id: &str) -> Result<Vec<Webhook>> {
+        let webhooks = sqlx::query(
+            r"
+            SELECT id, url, secret, user_id, result, contest_id
+            FROM internal_webhooks
+            WHERE internal_user_id = $1
+            ORDER BY id
+            ",
+        )
+        .bind(internal_user_id)
+        .try_map(map_webhook)
+        .fetch_all(self)
+        .await?;
+        Ok(webhooks)
+    }
+
+    async fn add_webhook(
+        &self,
+        internal_user_id: &str,
+        url: &str,
+        filter: &WebhookFilter,
+    ) -> Result<Webhook> {
+        let webhooks = self.get_webhooks(internal_user_id).await?;
+        if webhooks.len() >= MAX_WEBHOOK_NUM {
+            bail!("Cannot create a webhook anymore");
+        }
+
+        let webhook = Webhook {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: url.to_owned(),
+            secret: uuid::Uuid::new_v4().to_simple().to_string(),
+            filter: filter.clone(),
+        };
+        sqlx::query(
+            r"
+            INSERT INTO internal_webhooks
+            (id, internal_user_id, url, secret, user_id, result, contest_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ",
+        )
+        .bind(webhook.id.as_str())
+        .bind(internal_user_id)
+        .bind(webhook.url.as_str())
+        .bind(webhook.secret.as_str())
+        .bind(webhook.filter.user_id.as_deref())
+        .bind(webhook.filter.result.as_deref())
+        .bind(webhook.filter.contest_id.as_deref())
+        .execute(self)
+        .await?;
+        Ok(webhook)
+    }
+
+    async fn delete_webhook(&self, internal_user_id: &str, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM internal_webhooks WHERE id = $1 AND internal_user_id = $2")
+            .bind(id)
+            .bind(internal_user_id)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn load_matching_webhooks(&self, submission: &Submission) -> Result<Vec<Webhook>> {
+        let webhooks = sqlx::query(
+            r"
+            SELECT id, url, secret, user_id, result, contest_id
+            FROM internal_webhooks
+            WHERE (user_id IS NULL OR user_id = $1)
+            AND (result IS NULL OR result = $2)
+            AND (contest_id IS NULL OR contest_id = $3)
+            ",
+        )
+        .bind(submission.user_id.as_str())
+        .bind(submission.result.as_str())
+        .bind(submission.contest_id.as_str())
+        .try_map(map_webhook)
+        .fetch_all(self)
+        .await?;
+        Ok(webhooks)
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Result;
+use sqlx::postgres::{PgConnection, PgRow};
+use sqlx::{Connection, Row};
+
+/// A Postgres advisory lock which prevents the same job from running concurrently.
+///
+/// The lock is held by a dedicated connection, not by a pooled one, so that it is released
+/// by the server as soon as the connection is closed, even if the process dies.
+pub struct JobLock {
+    conn: PgConnection,
+    name: String,
+}
+
+impl JobLock {
+    /// Returns `None` if another process holds the lock of `name`.
+    pub async fn try_acquire(database_url: &str, name: &str) -> Result<Option<Self>> {
+        let mut conn = PgConnection::connect(database_url).await?;
+        let acquired = sqlx::query("SELECT pg_try_advisory_lock(hashtext($1)) AS acquired")
+            .bind(name)
+            .try_map(|row: PgRow| row.try_get::<bool, _>("acquired"))
+            .fetch_one(&mut conn)
+            .await?;
+        if acquired {
+            Ok(Some(Self {
+                conn,
+                name: name.to_owned(),
+            }))
+        } else {
+            conn.close().await?;
+            Ok(None)
+        }
+    }
+
+    /// Waits until the process which holds the lock of `name` releases it.
+    pub async fn acquire(database_url: &str, name: &str) -> Result<Self> {
+        let mut conn = PgConnection::connect(database_url).await?;
+        sqlx::query("SELECT pg_advisory_lock(hashtext($1))")
+            .bind(name)
+            .execute(&mut conn)
+            .await?;
+        Ok(Self {
+            conn,
+            name: name.to_owned(),
+        })
+    }
+
+    pub async fn release(mut self) -> Result<()> {
+        sqlx::query("SELECT pg_advisory_unlock(hashtext($1))")
+            .bind(&self.name)
+            .execute(&mut self.conn)
+            .await?;
+        self.conn.close().await?;
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
-use crate::models::{Submission, UserLanguageCount};
+use crate::ids::UserId;
+use crate::models::{LanguageUserCount, Submission, UserLanguageCount};
 use crate::{PgPool, MAX_INSERT_ROWS};
 use anyhow::Result;
 use async_trait::async_trait;
@@ -15,6 +16,10 @@ pub trait LanguageCountClient {
         current_counts: &[UserLanguageCount],
     ) -> Result<()>;
     async fn load_language_count(&self) -> Result<Vec<UserLanguageCount>>;
+    async fn load_users_language_count(&self, user_id: &UserId) -> Result<Vec<UserLanguageCount>>;
+
+    /// Returns the simplified languages, which are the canonical names in the language ranking.
+    async fn load_languages(&self) -> Result<Vec<LanguageUserCount>>;
 }
 
 #[async_trait]
@@ -120,6 +125,90 @@ impl LanguageCountClient for PgPool {
         .await?;
         Ok(count)
     }
+
+    async fn load_users_language_count(&self, user_id: &UserId) -> Result<Vec<UserLanguageCount>> {
+        let count = sqlx::query(
+            r"
+            SELECT
+                user_id,
+                simplified_language,
+                problem_count
+            FROM language_count
+            WHERE user_id = $1
+            ORDER BY simplified_language
+            ",
+        )
+        .bind(user_id)
+        .try_map(|row: PgRow| {
+            let user_id: String = row.try_get("user_id")?;
+            let simplified_language: String = row.try_get("simplified_language")?;
+            let problem_count: i32 = row.try_get("problem_count")?;
+            Ok(UserLanguageCount {
+                user_id,
+                simplified_language,
+                problem_count,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(count)
+    }
+
+    async fn load_languages(&self) -> Result<Vec<LanguageUserCount>> {
+        let languages = sqlx::query(
+            r"
+            SELECT simplified_language, COUNT(*) AS user_count
+            FROM language_count
+            GROUP BY simplified_language
+            ORDER BY simplified_language
+            ",
+        )
+        .try_map(|row: PgRow| {
+            let language: String = row.try_get("simplified_language")?;
+            let user_count: i64 = row.try_get("user_count")?;
+            Ok(LanguageUserCount {
+                language,
+                user_count,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(languages)
+    }
+}
+
+/// Common abbreviations and the names without versions, which are not simplified by `simplify_language`.
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("cpp", "C++"),
+    ("cs", "C#"),
+    ("csharp", "C#"),
+    ("js", "JavaScript"),
+    ("kt", "Kotlin"),
+    ("perl6", "Raku"),
+    ("py", "Python"),
+    ("pypy2", "PyPy"),
+    ("pypy3", "PyPy"),
+    ("python2", "Python"),
+    ("python3", "Python"),
+    ("rb", "Ruby"),
+    ("rs", "Rust"),
+    ("ts", "TypeScript"),
+];
+
+/// Finds the canonical name of `language` from `languages` ignoring case.
+/// `language` can be an alias or a language name of AtCoder with its version, e.g. `C++ (GCC 9.2.1)`.
+pub fn resolve_language<'a, S: AsRef<str>>(language: &str, languages: &'a [S]) -> Option<&'a str> {
+    let simplified = simplify_language(language.trim());
+    let lower = simplified.trim().to_lowercase();
+    let name = LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == lower)
+        .map(|(_, name)| name.to_lowercase())
+        .unwrap_or(lower);
+    languages
+        .iter()
+        .map(|l| l.as_ref())
+        .find(|l| l.to_lowercase() == name)
 }
 
 fn simplify_language(lang: &str) -> String {
@@ -145,4 +234,22 @@ mod tests {
         assert_eq!(simplify_language("PyPy2 (7.3.0)"), "PyPy");
         assert_eq!(simplify_language("Haxe (4.0.3); js"), "Haxe; js");
     }
+
+    #[test]
+    fn test_resolve_language() {
+        let languages = ["C++", "Python", "PyPy", "Rust", "Raku"];
+        assert_eq!(resolve_language("Rust", &languages), Some("Rust"));
+        assert_eq!(resolve_language("rust", &languages), Some("Rust"));
+        assert_eq!(resolve_language("Rust (1.42.0)", &languages), Some("Rust"));
+        assert_eq!(
+            resolve_language("C++14 (GCC 5.4.1)", &languages),
+            Some("C++")
+        );
+        assert_eq!(resolve_language("cpp", &languages), Some("C++"));
+        assert_eq!(resolve_language("Python3", &languages), Some("Python"));
+        assert_eq!(resolve_language("pypy3", &languages), Some("PyPy"));
+        assert_eq!(resolve_language("Perl6", &languages), Some("Raku"));
+        assert_eq!(resolve_language("Go", &languages), None);
+        assert_eq!(resolve_language("", &languages), None);
+    }
 }
@@ -1,30 +1,100 @@
 use anyhow::Result;
+use native_tls::TlsConnector;
+use postgres::config::SslMode;
+use postgres::NoTls;
+use postgres_native_tls::MakeTlsConnector;
+use sqlx::Executor;
 use std::time::Duration;
 
 pub mod accepted_count;
+pub mod achievement;
+pub mod activity;
+pub mod aggregation;
+pub mod backup;
+pub mod compressed_text;
+pub mod contest_category;
 pub mod contest_problem;
+pub mod contest_standings;
+pub mod crawl_request;
+pub mod crawler_run;
+pub mod data_version;
+pub mod forget_user;
+pub mod ids;
+pub mod integrity;
 pub mod internal;
+pub mod job_lock;
 pub mod language_count;
+pub mod live_performance;
+pub mod merge_user;
+pub mod merged_problem;
 pub mod models;
+pub mod partition;
+pub mod pgoutput;
+pub mod problem_difficulty;
 pub mod problem_info;
+pub mod problem_staleness;
 pub mod problems_submissions;
+pub mod published_contest;
+pub mod ranking;
 pub mod rated_point_sum;
+pub mod replication;
 pub mod simple_client;
+pub mod sink_cursor;
 pub mod streak;
 pub mod submission_client;
+pub mod submission_listener;
+pub mod time;
+pub mod training_velocity;
+pub mod user_profile;
+pub mod user_summary;
 
 pub use sqlx::postgres::{PgPool, PgPoolOptions, PgRow};
-pub use sqlx::{query, Row};
+pub use sqlx::{query, Error as DatabaseError, Row};
 
 const FIRST_AGC_EPOCH_SECOND: i64 = 1_468_670_400;
 const UNRATED_STATE: &str = "-";
 const MAX_INSERT_ROWS: usize = 10_000;
 
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 15;
+
 pub async fn initialize_pool<S: AsRef<str>>(database_url: S) -> Result<PgPool> {
+    initialize_pool_with_max_connections(database_url, DEFAULT_MAX_CONNECTIONS).await
+}
+
+/// Same as `initialize_pool`, but limits how many queries can run concurrently.
+pub async fn initialize_pool_with_max_connections<S: AsRef<str>>(
+    database_url: S,
+    max_connections: u32,
+) -> Result<PgPool> {
     let pool = PgPoolOptions::new()
         .max_lifetime(Some(Duration::from_secs(60 * 5)))
-        .max_connections(15)
+        .max_connections(max_connections)
         .connect(database_url.as_ref())
         .await?;
     Ok(pool)
 }
+
+/// Connects with the synchronous `postgres` driver, which is used for `COPY` since sqlx does not
+/// support it yet. TLS is used as `sslmode` in the URL tells, which is `prefer` by default, and
+/// the certificate of the server is verified.
+pub fn connect_sync(database_url: &str) -> Result<postgres::Client> {
+    let config: postgres::Config = database_url.parse()?;
+    let client = if config.get_ssl_mode() == SslMode::Disable {
+        config.connect(NoTls)?
+    } else {
+        config.connect(MakeTlsConnector::new(TlsConnector::new()?))?
+    };
+    Ok(client)
+}
+
+/// Quotes the name of a table or a column, which can not be bound as a parameter.
+pub(crate) fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Executes the SQL statements separated by semicolons, e.g. the database definition.
+pub async fn execute_script(pool: &PgPool, script: &str) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    conn.execute(script).await?;
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use crate::ids::{ContestId, UserId};
+use crate::models::LivePerformance;
+use crate::{PgPool, MAX_INSERT_ROWS};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+#[async_trait]
+pub trait LivePerformanceClient {
+    async fn load_predicted_ratings(&self, user_ids: &[UserId]) -> Result<Vec<(String, f64)>>;
+    async fn update_live_performances(
+        &self,
+        contest_id: &ContestId,
+        performances: &[LivePerformance],
+    ) -> Result<()>;
+    async fn load_live_performances(&self, contest_id: &ContestId) -> Result<Vec<LivePerformance>>;
+    async fn load_users_live_performances(&self, user_id: &UserId) -> Result<Vec<LivePerformance>>;
+}
+
+#[async_trait]
+impl LivePerformanceClient for PgPool {
+    async fn load_predicted_ratings(&self, user_ids: &[UserId]) -> Result<Vec<(String, f64)>> {
+        let ratings = sqlx::query(
+            r"
+            SELECT user_id, rating FROM predicted_rating
+            WHERE user_id = ANY($1)
+            AND rating IS NOT NULL
+            ",
+        )
+        .bind(user_ids.iter().map(UserId::as_str).collect::<Vec<_>>())
+        .try_map(|row: PgRow| {
+            let user_id: String = row.try_get("user_id")?;
+            let rating: f64 = row.try_get("rating")?;
+            Ok((user_id, rating))
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(ratings)
+    }
+
+    async fn update_live_performances(
+        &self,
+        contest_id: &ContestId,
+        performances: &[LivePerformance],
+    ) -> Result<()> {
+        let mut tx = self.begin().await?;
+        sqlx::query("DELETE FROM live_performances WHERE contest_id = $1")
+            .bind(contest_id)
+            .execute(&mut tx)
+            .await?;
+
+        for chunk in performances.chunks(MAX_INSERT_ROWS) {
+            let (user_ids, ranks, performances, old_ratings, new_ratings) = chunk.iter().fold(
+                (vec![], vec![], vec![], vec![], vec![]),
+                |(mut user_ids, mut ranks, mut performances, mut old_ratings, mut new_ratings),
+                 cur| {
+                    user_ids.push(cur.user_id.as_str());
+                    ranks.push(cur.rank);
+                    performances.push(cur.performance);
+                    old_ratings.push(cur.old_rating);
+                    new_ratings.push(cur.new_rating);
+                    (user_ids, ranks, performances, old_ratings, new_ratings)
+                },
+            );
+
+            sqlx::query(
+                r"
+                INSERT INTO live_performances
+                (contest_id, user_id, rank, performance, old_rating, new_rating)
+                VALUES (
+                    $1,
+                    UNNEST($2::VARCHAR(255)[]),
+                    UNNEST($3::BIGINT[]),
+                    UNNEST($4::BIGINT[]),
+                    UNNEST($5::BIGINT[]),
+                    UNNEST($6::BIGINT[])
+                )
+                ",
+            )
+            .bind(contest_id)
+            .bind(user_ids)
+            .bind(ranks)
+            .bind(performances)
+            .bind(old_ratings)
+            .bind(new_ratings)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn load_live_performances(&self, contest_id: &ContestId) -> Result<Vec<LivePerformance>> {
+        let performances = sqlx::query(
+            r"
+            SELECT contest_id, user_id, rank, performance, old_rating, new_rating
+            FROM live_performances
+            WHERE contest_id = $1
+            ORDER BY rank, user_id
+            ",
+        )
+        .bind(contest_id)
+        .try_map(live_performance_from_row)
+        .fetch_all(self)
+        .await?;
+        Ok(performances)
+    }
+
+    async fn load_users_live_performances(&self, user_id: &UserId) -> Result<Vec<LivePerformance>> {
+        let performances = sqlx::query(
+            r"
+            SELECT contest_id, user_id, rank, performance, old_rating, new_rating
+            FROM live_performances
+            WHERE user_id = $1
+            ORDER BY contest_id
+            ",
+        )
+        .bind(user_id)
+        .try_map(live_performance_from_row)
+        .fetch_all(self)
+        .await?;
+        Ok(performances)
+    }
+}
+
+fn live_performance_from_row(row: PgRow) -> sqlx::Result<LivePerformance> {
+    Ok(LivePerformance {
+        contest_id: row.try_get("contest_id")?,
+        user_id: row.try_get("user_id")?,
+        rank: row.try_get("rank")?,
+        performance: row.try_get("performance")?,
+        old_rating: row.try_get("old_rating")?,
+        new_rating: row.try_get("new_rating")?,
+    })
+}
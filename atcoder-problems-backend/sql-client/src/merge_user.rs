@@ -0,0 +1,185 @@
+//! Merges the rows of an old handle into the new one after the user is renamed on AtCoder, since
+//! the crawlers store the submissions under the handle at the time they are crawled.
+
+use crate::ids::UserId;
+use crate::PgPool;
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+/// The tables of each user with the columns of the primary key other than `user_id`. When both of
+/// the handles have a row of the same key, the row of the new handle is kept, since it is newer.
+const USER_TABLES: [(&str, &[&str]); 13] = [
+    ("accepted_count", &[]),
+    ("rated_point_sum", &[]),
+    ("language_count", &["simplified_language"]),
+    ("predicted_rating", &[]),
+    ("max_streaks", &[]),
+    ("current_streaks", &[]),
+    ("submission_count", &[]),
+    ("achievements", &["achievement_id"]),
+    ("training_velocity", &["period_days"]),
+    (
+        "recent_difficulty_count",
+        &["period_days", "difficulty_level"],
+    ),
+    ("live_performances", &["contest_id"]),
+    ("contest_standings", &["contest_id"]),
+    ("users", &[]),
+];
+
+/// The rows of a table changed by the merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableMerge {
+    pub table: String,
+    /// The rows renamed to the new handle.
+    pub moved: u64,
+    /// The rows of the old handle discarded since the new handle has a row of the same key.
+    pub discarded: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserMerge {
+    pub old_user_id: String,
+    pub new_user_id: String,
+    pub submissions: u64,
+    pub tables: Vec<TableMerge>,
+    /// The rows of `internal_users` and `internal_webhooks` following the old handle.
+    pub internal_rows: u64,
+}
+
+impl UserMerge {
+    pub fn rows_affected(&self) -> u64 {
+        self.submissions
+            + self.internal_rows
+            + self
+                .tables
+                .iter()
+                .map(|t| t.moved + t.discarded)
+                .sum::<u64>()
+    }
+}
+
+fn discard_conflicts_query(table: &str, key_columns: &[&str]) -> String {
+    let conditions = key_columns
+        .iter()
+        .map(|c| format!(" AND n.{c} = o.{c}", c = c))
+        .collect::<String>();
+    format!(
+        "DELETE FROM {t} o WHERE o.user_id = $1 AND EXISTS \
+         (SELECT 1 FROM {t} n WHERE n.user_id = $2{conditions})",
+        t = table,
+        conditions = conditions
+    )
+}
+
+#[async_trait]
+pub trait MergeUserClient {
+    /// Renames the submissions and the rows of the old handle to the new one in a single
+    /// transaction. The aggregated values are not recalculated, so `aggregate` should run after
+    /// it.
+    async fn merge_user(&self, old_user_id: &UserId, new_user_id: &UserId) -> Result<UserMerge>;
+}
+
+#[async_trait]
+impl MergeUserClient for PgPool {
+    async fn merge_user(&self, old_user_id: &UserId, new_user_id: &UserId) -> Result<UserMerge> {
+        if old_user_id == new_user_id {
+            bail!("The old and the new user ids are the same: {}", old_user_id);
+        }
+        let mut tx = self.begin().await?;
+        sqlx::query("LOCK TABLE submissions, users IN SHARE ROW EXCLUSIVE MODE")
+            .execute(&mut tx)
+            .await?;
+        // The triggers would silently skip the renamed rows of a forgotten user, whose ids are
+        // compared case-insensitively.
+        let forgotten: Vec<String> = sqlx::query(
+            "SELECT user_id FROM forgotten_users WHERE LOWER(user_id) IN (LOWER($1), LOWER($2))",
+        )
+        .bind(old_user_id)
+        .bind(new_user_id)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_all(&mut tx)
+        .await?;
+        if !forgotten.is_empty() {
+            bail!("{} has been forgotten by forget-user", forgotten.join(", "));
+        }
+
+        let submissions = sqlx::query("UPDATE submissions SET user_id = $2 WHERE user_id = $1")
+            .bind(old_user_id)
+            .bind(new_user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+
+        let mut tables = Vec::with_capacity(USER_TABLES.len());
+        for &(table, key_columns) in USER_TABLES.iter() {
+            let discarded = sqlx::query(&discard_conflicts_query(table, key_columns))
+                .bind(old_user_id)
+                .bind(new_user_id)
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+            let moved = sqlx::query(&format!(
+                "UPDATE {} SET user_id = $2 WHERE user_id = $1",
+                table
+            ))
+            .bind(old_user_id)
+            .bind(new_user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+            tables.push(TableMerge {
+                table: table.to_owned(),
+                moved,
+                discarded,
+            });
+        }
+
+        let mut internal_rows = sqlx::query(
+            "UPDATE internal_users SET atcoder_user_id = $2 WHERE atcoder_user_id = $1",
+        )
+        .bind(old_user_id)
+        .bind(new_user_id)
+        .execute(&mut tx)
+        .await?
+        .rows_affected();
+        internal_rows +=
+            sqlx::query("UPDATE internal_webhooks SET user_id = $2 WHERE user_id = $1")
+                .bind(old_user_id)
+                .bind(new_user_id)
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+        tx.commit().await?;
+
+        Ok(UserMerge {
+            old_user_id: old_user_id.to_string(),
+            new_user_id: new_user_id.to_string(),
+            submissions,
+            tables,
+            internal_rows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discard_conflicts_query() {
+        assert_eq!(
+            discard_conflicts_query("users", &[]),
+            "DELETE FROM users o WHERE o.user_id = $1 AND EXISTS \
+             (SELECT 1 FROM users n WHERE n.user_id = $2)"
+        );
+        assert_eq!(
+            discard_conflicts_query("live_performances", &["contest_id"]),
+            "DELETE FROM live_performances o WHERE o.user_id = $1 AND EXISTS \
+             (SELECT 1 FROM live_performances n WHERE n.user_id = $2 \
+             AND n.contest_id = o.contest_id)"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::contest_category::ContestCategory;
+use crate::ids::{ContestId, ProblemId, UserId};
+use crate::models::MergedProblem;
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+const SELECT_MERGED_PROBLEMS: &str = r"
+    SELECT
+        problems.id AS merged_problem_id,
+        problems.contest_id AS merged_contest_id,
+        problems.title AS merged_problem_title,
+        problems.problem_index AS merged_problem_index,
+        problems.name AS merged_problem_name,
+
+        shortest.submission_id AS shortest_submission_id,
+        shortest.contest_id AS shortest_contest_id,
+        shortest_submissions.user_id AS shortest_user_id,
+
+        fastest.submission_id AS fastest_submission_id,
+        fastest.contest_id AS fastest_contest_id,
+        fastest_submissions.user_id AS fastest_user_id,
+
+        first.submission_id AS first_submission_id,
+        first.contest_id AS first_contest_id,
+        first_submissions.user_id AS first_user_id,
+
+        shortest_submissions.length AS source_code_length,
+        fastest_submissions.execution_time AS execution_time,
+        points.point,
+        solver.user_count AS solver_count
+    FROM
+        problems
+        LEFT JOIN shortest ON shortest.problem_id = problems.id
+        LEFT JOIN fastest ON fastest.problem_id = problems.id
+        LEFT JOIN first ON first.problem_id = problems.id
+        LEFT JOIN submissions AS shortest_submissions ON shortest.submission_id = shortest_submissions.id
+        LEFT JOIN submissions AS fastest_submissions ON fastest.submission_id = fastest_submissions.id
+        LEFT JOIN submissions AS first_submissions ON first.submission_id = first_submissions.id
+        LEFT JOIN points ON points.problem_id = problems.id
+        LEFT JOIN solver ON solver.problem_id = problems.id
+        LEFT JOIN problem_difficulties ON problem_difficulties.problem_id = problems.id
+";
+
+fn map_merged_problem(row: PgRow) -> sqlx::Result<MergedProblem> {
+    let id: ProblemId = row.try_get("merged_problem_id")?;
+    let contest_id: ContestId = row.try_get("merged_contest_id")?;
+    let title: String = row.try_get("merged_problem_title")?;
+    let problem_index: String = row.try_get("merged_problem_index")?;
+    let name: String = row.try_get("merged_problem_name")?;
+
+    let shortest_submission_id: Option<i64> = row.try_get("shortest_submission_id")?;
+    let shortest_contest_id: Option<ContestId> = row.try_get("shortest_contest_id")?;
+    let shortest_user_id: Option<UserId> = row.try_get("shortest_user_id")?;
+
+    let fastest_submission_id: Option<i64> = row.try_get("fastest_submission_id")?;
+    let fastest_contest_id: Option<ContestId> = row.try_get("fastest_contest_id")?;
+    let fastest_user_id: Option<UserId> = row.try_get("fastest_user_id")?;
+
+    let first_submission_id: Option<i64> = row.try_get("first_submission_id")?;
+    let first_contest_id: Option<ContestId> = row.try_get("first_contest_id")?;
+    let first_user_id: Option<UserId> = row.try_get("first_user_id")?;
+
+    let source_code_length: Option<i32> = row.try_get("source_code_length")?;
+    let execution_time: Option<i32> = row.try_get("execution_time")?;
+    let point: Option<f64> = row.try_get("point")?;
+    let solver_count: Option<i32> = row.try_get("solver_count")?;
+
+    Ok(MergedProblem {
+        id,
+        contest_id,
+        title,
+        problem_index,
+        name,
+        shortest_submission_id,
+        shortest_contest_id,
+        shortest_user_id,
+        fastest_submission_id,
+        fastest_contest_id,
+        fastest_user_id,
+        first_submission_id,
+        first_contest_id,
+        first_user_id,
+        source_code_length,
+        execution_time,
+        point,
+        solver_count,
+    })
+}
+
+#[async_trait]
+pub trait MergedProblemClient {
+    async fn load_merged_problems(&self) -> Result<Vec<MergedProblem>>;
+
+    /// Returns up to `limit` problems whose title or id contains `text`, whose difficulty is in
+    /// the range, and whose contest is in `category`. The problems without difficulty are
+    /// excluded if the range is specified.
+    async fn search_merged_problems(
+        &self,
+        text: Option<&str>,
+        difficulty_from: Option<f64>,
+        difficulty_to: Option<f64>,
+        category: Option<ContestCategory>,
+        limit: usize,
+    ) -> Result<Vec<MergedProblem>>;
+}
+
+#[async_trait]
+impl MergedProblemClient for PgPool {
+    async fn load_merged_problems(&self) -> Result<Vec<MergedProblem>> {
+        let sql = format!("{} ORDER BY problems.id", SELECT_MERGED_PROBLEMS);
+        let merged_problems = sqlx::query(&sql)
+            .try_map(map_merged_problem)
+            .fetch_all(self)
+            .await?;
+        Ok(merged_problems)
+    }
+
+    async fn search_merged_problems(
+        &self,
+        text: Option<&str>,
+        difficulty_from: Option<f64>,
+        difficulty_to: Option<f64>,
+        category: Option<ContestCategory>,
+        limit: usize,
+    ) -> Result<Vec<MergedProblem>> {
+        // The conditions are built only for the given parameters, so that the trigram indexes
+        // on the titles and the ids can be used. Both columns need one for the OR.
+        let mut conditions = vec![];
+        let mut params = 1;
+        if text.is_some() {
+            params += 1;
+            conditions.push(format!(
+                "(problems.title ILIKE ${0} OR problems.id ILIKE ${0})",
+                params
+            ));
+        }
+        if difficulty_from.is_some() {
+            params += 1;
+            conditions.push(format!("problem_difficulties.difficulty >= ${}", params));
+        }
+        if difficulty_to.is_some() {
+            params += 1;
+            conditions.push(format!("problem_difficulties.difficulty <= ${}", params));
+        }
+        if category.is_some() {
+            params += 1;
+            conditions.push(format!(
+                "problems.contest_id IN (SELECT id FROM contests WHERE category = ${})",
+                params
+            ));
+        }
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let sql = format!(
+            "{} {} ORDER BY problems.id LIMIT $1",
+            SELECT_MERGED_PROBLEMS, filter
+        );
+
+        let mut query = sqlx::query(&sql).bind(limit as i64);
+        if let Some(text) = text {
+            let escaped = text
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            query = query.bind(format!("%{}%", escaped));
+        }
+        if let Some(difficulty_from) = difficulty_from {
+            query = query.bind(difficulty_from);
+        }
+        if let Some(difficulty_to) = difficulty_to {
+            query = query.bind(difficulty_to);
+        }
+        if let Some(category) = category {
+            query = query.bind(category.as_str());
+        }
+        let merged_problems = query.try_map(map_merged_problem).fetch_all(self).await?;
+        Ok(merged_problems)
+    }
+}
@@ -1,42 +1,140 @@
-use crate::{FIRST_AGC_EPOCH_SECOND, UNRATED_STATE};
+use crate::contest_category::{parse_rated_range, ContestCategory};
+use crate::ids::{ContestId, ProblemId, UserId};
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
 use sqlx::FromRow;
 use sqlx::Row;
 
-#[derive(Default, Debug, Eq, PartialEq, Serialize)]
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ContestFields")]
 pub struct Contest {
-    pub id: String,
+    pub id: ContestId,
     pub start_epoch_second: i64,
     pub duration_second: i64,
     pub title: String,
     pub rate_change: String,
+    /// Derived from the other fields by `Contest::new`.
+    pub category: ContestCategory,
+    /// The lowest and the highest ratings which are rated, where `None` of the highest one
+    /// means no upper bound, or `None` if the contest is unrated. Derived by `Contest::new`.
+    pub rated_range: Option<(i64, Option<i64>)>,
+    /// The penalty minutes for each wrong submission, or `None` if it is not crawled yet.
+    pub penalty_minutes: Option<i64>,
+    /// Whether users can register after the contest starts, or `None` if it is not crawled yet.
+    pub allows_late_join: Option<bool>,
+}
+
+/// The fields of `Contest` which are not derived. The derived ones in the input are ignored,
+/// so that the dumps written before they were added or changed are read correctly.
+#[derive(Deserialize)]
+struct ContestFields {
+    id: ContestId,
+    start_epoch_second: i64,
+    duration_second: i64,
+    title: String,
+    rate_change: String,
+    #[serde(default)]
+    penalty_minutes: Option<i64>,
+    #[serde(default)]
+    allows_late_join: Option<bool>,
+}
+
+impl From<ContestFields> for Contest {
+    fn from(fields: ContestFields) -> Self {
+        Self {
+            penalty_minutes: fields.penalty_minutes,
+            allows_late_join: fields.allows_late_join,
+            ..Contest::new(
+                fields.id,
+                fields.start_epoch_second,
+                fields.duration_second,
+                fields.title,
+                fields.rate_change,
+            )
+        }
+    }
 }
 
 impl Contest {
+    /// Creates a contest with `category` and `rated_range` derived from the other fields.
+    pub fn new<I: Into<ContestId>>(
+        id: I,
+        start_epoch_second: i64,
+        duration_second: i64,
+        title: String,
+        rate_change: String,
+    ) -> Self {
+        let id = id.into();
+        let rated_range = parse_rated_range(start_epoch_second, &rate_change);
+        let category = ContestCategory::classify(id.as_str(), &title, rated_range);
+        Self {
+            id,
+            start_epoch_second,
+            duration_second,
+            title,
+            rate_change,
+            category,
+            rated_range,
+            penalty_minutes: None,
+            allows_late_join: None,
+        }
+    }
+
     pub fn is_rated(&self) -> bool {
-        self.start_epoch_second >= FIRST_AGC_EPOCH_SECOND && self.rate_change != UNRATED_STATE
+        self.rated_range.is_some()
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
+#[derive(Default, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Problem {
-    pub id: String,
-    pub contest_id: String,
+    pub id: ProblemId,
+    pub contest_id: ContestId,
+    /// The full title, e.g. `A. Train`.
+    pub title: String,
+    /// The index in the contest, e.g. `A` or `Ex`.
+    #[serde(default)]
+    pub problem_index: String,
+    /// The title without the index, e.g. `Train`.
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
+pub struct MergedProblem {
+    pub id: ProblemId,
+    pub contest_id: ContestId,
     pub title: String,
+    pub problem_index: String,
+    pub name: String,
+    pub shortest_submission_id: Option<i64>,
+    pub shortest_contest_id: Option<ContestId>,
+    pub shortest_user_id: Option<UserId>,
+    pub fastest_submission_id: Option<i64>,
+    pub fastest_contest_id: Option<ContestId>,
+    pub fastest_user_id: Option<UserId>,
+    pub first_submission_id: Option<i64>,
+    pub first_contest_id: Option<ContestId>,
+    pub first_user_id: Option<UserId>,
+    pub source_code_length: Option<i32>,
+    pub execution_time: Option<i32>,
+    pub point: Option<f64>,
+    pub solver_count: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Default, Deserialize)]
+/// A submission, which is serialized in the same format by the crawlers, the dumps and the API.
+#[derive(Debug, Clone, PartialEq, Serialize, Default, Deserialize)]
 pub struct Submission {
     pub id: i64,
     pub epoch_second: i64,
-    pub problem_id: String,
-    pub contest_id: String,
-    pub user_id: String,
+    pub problem_id: ProblemId,
+    pub contest_id: ContestId,
+    pub user_id: UserId,
     pub language: String,
     pub point: f64,
     pub length: i32,
     pub result: String,
+    /// `null` while the submission is being judged, or if it is not executed, e.g. `CE`.
+    #[serde(default)]
     pub execution_time: Option<i32>,
 }
 
@@ -44,9 +142,9 @@ impl FromRow<'_, PgRow> for Submission {
     fn from_row(row: &PgRow) -> sqlx::Result<Self> {
         let id: i64 = row.try_get("id")?;
         let epoch_second: i64 = row.try_get("epoch_second")?;
-        let problem_id: String = row.try_get("problem_id")?;
-        let contest_id: String = row.try_get("contest_id")?;
-        let user_id: String = row.try_get("user_id")?;
+        let problem_id: ProblemId = row.try_get("problem_id")?;
+        let contest_id: ContestId = row.try_get("contest_id")?;
+        let user_id: UserId = row.try_get("user_id")?;
         let language: String = row.try_get("language")?;
         let point: f64 = row.try_get("point")?;
         let length: i32 = row.try_get("length")?;
@@ -78,6 +176,13 @@ pub struct UserLanguageCount {
     pub problem_count: i32,
 }
 
+/// A language in the language ranking, with the number of users who solved problems in it.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct LanguageUserCount {
+    pub language: String,
+    pub user_count: i64,
+}
+
 #[derive(Debug, Eq, PartialEq, Serialize)]
 pub struct UserProblemCount {
     pub user_id: String,
@@ -90,10 +195,54 @@ pub struct UserSum {
     pub point_sum: f64,
 }
 
+/// The aggregated values of a user, where the ranks are 0-indexed, i.e. the number of the users
+/// with greater values.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct UserSummary {
+    pub user_id: String,
+    pub accepted_count: i32,
+    pub accepted_count_rank: i64,
+    pub rated_point_sum: f64,
+    pub rated_point_sum_rank: i64,
+    pub max_streak: i64,
+    pub max_streak_rank: i64,
+}
+
 #[derive(PartialEq, Debug, Serialize)]
+pub struct RankingEntry {
+    /// 1-indexed rank, which is shared by the users with the same value.
+    pub rank: i64,
+    pub user_id: String,
+    pub value: f64,
+}
+
+/// Points to the last entry of a page of a ranking, so that the next page can be loaded
+/// without scanning the entries before it.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct RankingCursor {
+    /// The number of the entries up to the last entry.
+    pub position: usize,
+    pub rank: i64,
+    pub user_id: String,
+    pub value: f64,
+}
+
+impl RankingCursor {
+    /// Returns the cursor pointing to the last entry of the page which starts at `offset`.
+    pub fn after(offset: usize, entries: &[RankingEntry]) -> Option<Self> {
+        entries.last().map(|last| Self {
+            position: offset + entries.len(),
+            rank: last.rank,
+            user_id: last.user_id.clone(),
+            value: last.value,
+        })
+    }
+}
+
+#[derive(PartialEq, Debug, Serialize, Deserialize)]
 pub struct ContestProblem {
-    pub contest_id: String,
-    pub problem_id: String,
+    pub contest_id: ContestId,
+    pub problem_id: ProblemId,
 }
 
 #[derive(PartialEq, Debug, Serialize)]
@@ -101,3 +250,275 @@ pub struct UserStreak {
     pub user_id: String,
     pub streak: i64,
 }
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct LivePerformance {
+    pub contest_id: String,
+    pub user_id: String,
+    pub rank: i64,
+    pub performance: i64,
+    pub old_rating: i64,
+    pub new_rating: i64,
+}
+
+/// An entry of the standings of a contest, which is crawled from AtCoder.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct StandingsEntry {
+    pub user_id: String,
+    pub rank: i64,
+    pub is_rated: bool,
+    pub old_rating: i64,
+    pub solved_count: i64,
+    pub point: f64,
+    pub elapsed_second: i64,
+    pub penalty: i64,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct ContestStandings {
+    pub contest_id: String,
+    pub updated_epoch_second: i64,
+    pub standings: Vec<StandingsEntry>,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct Achievement {
+    pub user_id: String,
+    pub achievement_id: String,
+    pub achieved_epoch_second: i64,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct TrainingVelocity {
+    pub user_id: String,
+    pub period_days: i64,
+    pub accepted_count: i64,
+    pub rated_point_sum: f64,
+    /// The number of solved problems at each difficulty level.
+    pub difficulty_counts: Vec<i64>,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct StaleProblem {
+    pub problem_id: String,
+    pub contest_id: String,
+    pub last_accepted_epoch_second: i64,
+}
+
+#[derive(PartialEq, Debug, Serialize)]
+pub struct UnsolvedAttempt {
+    pub problem_id: String,
+    pub contest_id: String,
+    pub first_attempt_epoch_second: i64,
+    pub last_attempt_epoch_second: i64,
+    pub attempt_count: i64,
+}
+
+/// The profile of a user crawled from AtCoder, where the missing fields are `None`.
+#[derive(PartialEq, Debug, Clone, Default, Serialize)]
+pub struct UserProfile {
+    pub user_id: String,
+    pub rating: Option<i64>,
+    pub highest_rating: Option<i64>,
+    pub affiliation: Option<String>,
+    /// The code of the country or the region, e.g. `JP`.
+    pub country: Option<String>,
+    /// The first year of the decade of the birth year, e.g. 1990 for 1995, so that the exact
+    /// birth year is not stored.
+    pub birth_year_bucket: Option<i32>,
+    /// The number of the rated contests the user has joined.
+    pub rated_matches: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::DeserializeOwned;
+    use serde_json::{json, Value};
+    use std::fmt::Debug;
+
+    fn assert_round_trip<T>(value: &T, expected: Value)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + Debug,
+    {
+        let serialized = serde_json::to_value(value).unwrap();
+        assert_eq!(serialized, expected);
+        let deserialized = serde_json::from_value::<T>(serialized).unwrap();
+        assert_eq!(&deserialized, value);
+    }
+
+    #[test]
+    fn test_submission_json() {
+        let submission = Submission {
+            id: 5870123,
+            epoch_second: 1558181760,
+            problem_id: "abc127_a".into(),
+            contest_id: "abc127".into(),
+            user_id: "kenkoooo".into(),
+            language: "Rust (1.15.1)".to_owned(),
+            point: 100.0,
+            length: 318,
+            result: "AC".to_owned(),
+            execution_time: Some(2),
+        };
+        assert_round_trip(
+            &submission,
+            json!({
+                "id": 5870123,
+                "epoch_second": 1558181760,
+                "problem_id": "abc127_a",
+                "contest_id": "abc127",
+                "user_id": "kenkoooo",
+                "language": "Rust (1.15.1)",
+                "point": 100.0,
+                "length": 318,
+                "result": "AC",
+                "execution_time": 2
+            }),
+        );
+
+        let submission = Submission {
+            result: "CE".to_owned(),
+            execution_time: None,
+            ..submission
+        };
+        let serialized = serde_json::to_value(&submission).unwrap();
+        assert_eq!(serialized["execution_time"], Value::Null);
+
+        let mut missing = serialized.clone();
+        missing.as_object_mut().unwrap().remove("execution_time");
+        assert_eq!(
+            serde_json::from_value::<Submission>(missing).unwrap(),
+            submission
+        );
+    }
+
+    #[test]
+    fn test_problem_json() {
+        let problem = Problem {
+            id: "abc127_a".into(),
+            contest_id: "abc127".into(),
+            title: "A. Ferris Wheel".to_owned(),
+            problem_index: "A".to_owned(),
+            name: "Ferris Wheel".to_owned(),
+        };
+        assert_round_trip(
+            &problem,
+            json!({
+                "id": "abc127_a",
+                "contest_id": "abc127",
+                "title": "A. Ferris Wheel",
+                "problem_index": "A",
+                "name": "Ferris Wheel"
+            }),
+        );
+
+        // The dumps before the index and the name were added.
+        let problem = serde_json::from_value::<Problem>(json!({
+            "id": "abc127_a",
+            "contest_id": "abc127",
+            "title": "A. Ferris Wheel"
+        }))
+        .unwrap();
+        assert_eq!(problem.problem_index, "");
+        assert_eq!(problem.name, "");
+    }
+
+    #[test]
+    fn test_contest_json() {
+        let contest = Contest::new(
+            "abc127",
+            1558182000,
+            6000,
+            "AtCoder Beginner Contest 127".to_owned(),
+            " ~ 1199".to_owned(),
+        );
+        assert_round_trip(
+            &contest,
+            json!({
+                "id": "abc127",
+                "start_epoch_second": 1558182000,
+                "duration_second": 6000,
+                "title": "AtCoder Beginner Contest 127",
+                "rate_change": " ~ 1199",
+                "category": "ABC",
+                "rated_range": [0, 1199],
+                "penalty_minutes": null,
+                "allows_late_join": null
+            }),
+        );
+
+        let contest = Contest {
+            penalty_minutes: Some(5),
+            allows_late_join: Some(true),
+            ..Contest::new(
+                "agc001",
+                1468670400,
+                6600,
+                "AtCoder Grand Contest 001".to_owned(),
+                "All".to_owned(),
+            )
+        };
+        let serialized = serde_json::to_value(&contest).unwrap();
+        assert_eq!(serialized["rated_range"], json!([0, null]));
+        assert_eq!(serialized["penalty_minutes"], json!(5));
+        assert_eq!(serialized["allows_late_join"], json!(true));
+        assert_round_trip(&contest, serialized);
+
+        // The dumps before the derived fields and the rules were added.
+        let contest = serde_json::from_value::<Contest>(json!({
+            "id": "abc127",
+            "start_epoch_second": 1558182000,
+            "duration_second": 6000,
+            "title": "AtCoder Beginner Contest 127",
+            "rate_change": " ~ 1199"
+        }))
+        .unwrap();
+        assert_eq!(contest.category, ContestCategory::Abc);
+        assert_eq!(contest.rated_range, Some((0, Some(1199))));
+        assert_eq!(contest.penalty_minutes, None);
+        assert_eq!(contest.allows_late_join, None);
+
+        // The derived fields are never taken from the input.
+        let contest = serde_json::from_value::<Contest>(json!({
+            "id": "abc127",
+            "start_epoch_second": 1558182000,
+            "duration_second": 6000,
+            "title": "AtCoder Beginner Contest 127",
+            "rate_change": " ~ 1199",
+            "category": "Other Contests",
+            "rated_range": null
+        }))
+        .unwrap();
+        assert_eq!(contest.category, ContestCategory::Abc);
+        assert_eq!(contest.rated_range, Some((0, Some(1199))));
+    }
+
+    #[test]
+    fn test_merged_problem_json() {
+        let problem = MergedProblem {
+            id: "abc127_a".into(),
+            contest_id: "abc127".into(),
+            title: "A. Ferris Wheel".to_owned(),
+            problem_index: "A".to_owned(),
+            name: "Ferris Wheel".to_owned(),
+            shortest_submission_id: Some(1),
+            shortest_contest_id: Some("abc127".into()),
+            shortest_user_id: Some("user".into()),
+            fastest_submission_id: None,
+            fastest_contest_id: None,
+            fastest_user_id: None,
+            first_submission_id: None,
+            first_contest_id: None,
+            first_user_id: None,
+            source_code_length: Some(10),
+            execution_time: None,
+            point: Some(100.0),
+            solver_count: Some(3),
+        };
+        let serialized = serde_json::to_value(&problem).unwrap();
+        assert_eq!(serialized["shortest_user_id"], json!("user"));
+        assert_eq!(serialized["fastest_user_id"], Value::Null);
+        assert_round_trip(&problem, serialized);
+    }
+}
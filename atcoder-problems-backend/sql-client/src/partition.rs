@@ -0,0 +1,187 @@
+//! The partitions of the tables partitioned by `epoch_second` for each year in JST, which are named
+//! like `submissions_y2021`.
+
+use crate::time::JstDay;
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Datelike, NaiveDate};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct YearlyPartition {
+    pub year: i32,
+}
+
+impl YearlyPartition {
+    pub fn of_epoch_second(epoch_second: i64) -> Self {
+        let year = JstDay::from_epoch_second(epoch_second).date().year();
+        Self { year }
+    }
+
+    /// Parses the name of a partition of `table`.
+    pub fn from_name(table: &str, name: &str) -> Option<Self> {
+        let year = name.strip_prefix(table)?.strip_prefix("_y")?;
+        if year.len() != 4 {
+            return None;
+        }
+        year.parse().ok().map(|year| Self { year })
+    }
+
+    pub fn name(&self, table: &str) -> String {
+        format!("{}_y{}", table, self.year)
+    }
+
+    pub fn start_epoch_second(&self) -> i64 {
+        JstDay::from_date(NaiveDate::from_ymd(self.year, 1, 1)).start_epoch_second()
+    }
+
+    /// The end of the partition, which is excluded from it.
+    pub fn end_epoch_second(&self) -> i64 {
+        self.next().start_epoch_second()
+    }
+
+    pub fn next(&self) -> Self {
+        Self {
+            year: self.year + 1,
+        }
+    }
+}
+
+#[async_trait]
+pub trait PartitionClient {
+    async fn is_partitioned(&self, table: &str) -> Result<bool>;
+
+    /// Returns the yearly partitions attached to the table in the order of the years.
+    async fn load_yearly_partitions(&self, table: &str) -> Result<Vec<YearlyPartition>>;
+
+    /// Creates the partition and attaches it to the table. The partition may have been created but
+    /// not attached yet, e.g. if the previous run failed.
+    async fn attach_yearly_partition(&self, table: &str, partition: YearlyPartition) -> Result<()>;
+
+    /// Detaches the partition from the table, leaving it as a standalone table.
+    async fn detach_yearly_partition(&self, table: &str, partition: YearlyPartition) -> Result<()>;
+
+    async fn drop_detached_partition(&self, table: &str, partition: YearlyPartition) -> Result<()>;
+}
+
+#[async_trait]
+impl PartitionClient for PgPool {
+    async fn is_partitioned(&self, table: &str) -> Result<bool> {
+        let count: i64 = sqlx::query(
+            r"
+            SELECT COUNT(*) FROM pg_partitioned_table
+            WHERE partrelid = $1::TEXT::REGCLASS
+            ",
+        )
+        .bind(table)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_one(self)
+        .await?;
+        Ok(count > 0)
+    }
+
+    async fn load_yearly_partitions(&self, table: &str) -> Result<Vec<YearlyPartition>> {
+        let names: Vec<String> = sqlx::query(
+            r"
+            SELECT c.relname::TEXT FROM pg_inherits i
+            JOIN pg_class c ON c.oid = i.inhrelid
+            WHERE i.inhparent = $1::TEXT::REGCLASS
+            ",
+        )
+        .bind(table)
+        .try_map(|row: PgRow| row.try_get(0))
+        .fetch_all(self)
+        .await?;
+        let mut partitions = names
+            .iter()
+            .filter_map(|name| YearlyPartition::from_name(table, name))
+            .collect::<Vec<_>>();
+        partitions.sort();
+        Ok(partitions)
+    }
+
+    async fn attach_yearly_partition(&self, table: &str, partition: YearlyPartition) -> Result<()> {
+        let name = partition.name(table);
+        let mut tx = self.begin().await?;
+        sqlx::query(&format!(
+            "CREATE TABLE IF NOT EXISTS {} (LIKE {} INCLUDING DEFAULTS INCLUDING CONSTRAINTS)",
+            name, table
+        ))
+        .execute(&mut tx)
+        .await?;
+        sqlx::query(&format!(
+            "ALTER TABLE {} ATTACH PARTITION {} FOR VALUES FROM ({}) TO ({})",
+            table,
+            name,
+            partition.start_epoch_second(),
+            partition.end_epoch_second()
+        ))
+        .execute(&mut tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn detach_yearly_partition(&self, table: &str, partition: YearlyPartition) -> Result<()> {
+        sqlx::query(&format!(
+            "ALTER TABLE {} DETACH PARTITION {}",
+            table,
+            partition.name(table)
+        ))
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn drop_detached_partition(&self, table: &str, partition: YearlyPartition) -> Result<()> {
+        sqlx::query(&format!("DROP TABLE {}", partition.name(table)))
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yearly_partition() {
+        let partition = YearlyPartition { year: 2021 };
+        assert_eq!(partition.name("submissions"), "submissions_y2021");
+        // 2021-01-01T00:00:00+09:00
+        assert_eq!(partition.start_epoch_second(), 1_609_426_800);
+        assert_eq!(partition.end_epoch_second(), 1_640_962_800);
+        assert_eq!(
+            YearlyPartition::of_epoch_second(1_609_426_799),
+            YearlyPartition { year: 2020 }
+        );
+        assert_eq!(YearlyPartition::of_epoch_second(1_609_426_800), partition);
+        assert_eq!(
+            YearlyPartition::of_epoch_second(partition.end_epoch_second()),
+            partition.next()
+        );
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            YearlyPartition::from_name("submissions", "submissions_y2021"),
+            Some(YearlyPartition { year: 2021 })
+        );
+        assert_eq!(
+            YearlyPartition::from_name("submissions", "submissions_default"),
+            None
+        );
+        assert_eq!(
+            YearlyPartition::from_name("submissions", "submissions_y21"),
+            None
+        );
+        assert_eq!(
+            YearlyPartition::from_name("contests", "submissions_y2021"),
+            None
+        );
+    }
+}
@@ -0,0 +1,542 @@
+//! A decoder of the messages of `pgoutput`, the logical decoding plugin of PostgreSQL, in the
+//! version 1 of the protocol. The values of the columns are in the text format.
+//!
+//! See <https://www.postgresql.org/docs/current/protocol-logicalrep-message-formats.html>.
+
+use crate::models::Submission;
+use crate::partition::YearlyPartition;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const SUBMISSIONS_TABLE: &str = "submissions";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    /// A TOASTed value which is not changed by the update, and so is not sent.
+    Unchanged,
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation {
+    pub id: u32,
+    pub namespace: String,
+    pub name: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Begin,
+    /// The end of a transaction, where `end_lsn` is the LSN after the commit record.
+    Commit {
+        end_lsn: u64,
+    },
+    /// Describes a table, which is sent before the first change of the table in the session.
+    Relation(Relation),
+    Insert {
+        relation_id: u32,
+        new: Vec<Value>,
+    },
+    /// `old` is given only if the key is changed, or the replica identity is `FULL`.
+    Update {
+        relation_id: u32,
+        old: Option<Vec<Value>>,
+        new: Vec<Value>,
+    },
+    /// `old` has only the key columns unless the replica identity is `FULL`.
+    Delete {
+        relation_id: u32,
+        old: Vec<Value>,
+    },
+    Truncate {
+        relation_ids: Vec<u32>,
+    },
+    /// The other messages, e.g. `Type` and `Origin`, which do not change the rows.
+    Other(u8),
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            bail!("The message is truncated");
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    /// A string terminated by a null byte.
+    fn string(&mut self) -> Result<String> {
+        let len = self
+            .data
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| anyhow!("The string is not terminated"))?;
+        let string = String::from_utf8(self.bytes(len)?.to_vec())?;
+        self.bytes(1)?;
+        Ok(string)
+    }
+
+    fn tuple(&mut self) -> Result<Vec<Value>> {
+        let count = self.u16()?;
+        (0..count)
+            .map(|_| match self.u8()? {
+                b'n' => Ok(Value::Null),
+                b'u' => Ok(Value::Unchanged),
+                b't' => {
+                    let len = self.u32()? as usize;
+                    let text = String::from_utf8(self.bytes(len)?.to_vec())?;
+                    Ok(Value::Text(text))
+                }
+                kind => Err(anyhow!("Unknown kind of a column: {}", kind as char)),
+            })
+            .collect()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<()> {
+        let actual = self.u8()?;
+        if actual != expected {
+            bail!("Expected {} but got {}", expected as char, actual as char);
+        }
+        Ok(())
+    }
+}
+
+pub fn parse_message(data: &[u8]) -> Result<Message> {
+    let mut reader = Reader { data };
+    let message = match reader.u8()? {
+        b'B' => Message::Begin,
+        b'C' => {
+            let _flags = reader.u8()?;
+            let _commit_lsn = reader.u64()?;
+            let end_lsn = reader.u64()?;
+            Message::Commit { end_lsn }
+        }
+        b'R' => {
+            let id = reader.u32()?;
+            let namespace = reader.string()?;
+            let name = reader.string()?;
+            let _replica_identity = reader.u8()?;
+            let count = reader.u16()?;
+            let mut columns = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                let _flags = reader.u8()?;
+                columns.push(reader.string()?);
+                let _type_id = reader.u32()?;
+                let _type_modifier = reader.u32()?;
+            }
+            Message::Relation(Relation {
+                id,
+                namespace,
+                name,
+                columns,
+            })
+        }
+        b'I' => {
+            let relation_id = reader.u32()?;
+            reader.expect(b'N')?;
+            let new = reader.tuple()?;
+            Message::Insert { relation_id, new }
+        }
+        b'U' => {
+            let relation_id = reader.u32()?;
+            let (old, new) = match reader.u8()? {
+                b'K' | b'O' => {
+                    let old = reader.tuple()?;
+                    reader.expect(b'N')?;
+                    (Some(old), reader.tuple()?)
+                }
+                b'N' => (None, reader.tuple()?),
+                kind => bail!("Unknown kind of a tuple: {}", kind as char),
+            };
+            Message::Update {
+                relation_id,
+                old,
+                new,
+            }
+        }
+        b'D' => {
+            let relation_id = reader.u32()?;
+            match reader.u8()? {
+                b'K' | b'O' => {}
+                kind => bail!("Unknown kind of a tuple: {}", kind as char),
+            }
+            let old = reader.tuple()?;
+            Message::Delete { relation_id, old }
+        }
+        b'T' => {
+            let count = reader.u32()?;
+            let _options = reader.u8()?;
+            let relation_ids = (0..count)
+                .map(|_| reader.u32())
+                .collect::<Result<Vec<_>>>()?;
+            Message::Truncate { relation_ids }
+        }
+        kind => Message::Other(kind),
+    };
+    Ok(message)
+}
+
+/// A change of the `submissions` table, which is serialized as a JSON object with `type`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubmissionChange {
+    Upsert { submission: Submission },
+    Delete { id: i64 },
+    Truncate,
+}
+
+/// Decodes the messages into the changes of the submissions, remembering the relations.
+#[derive(Default)]
+pub struct SubmissionDecoder {
+    relations: HashMap<u32, Relation>,
+}
+
+impl SubmissionDecoder {
+    /// Returns the changes of the submissions in the message, which are empty for the messages
+    /// which do not change them. An update which changes the id is a deletion of the old id and
+    /// an upsert of the new one.
+    pub fn decode(&mut self, message: Message) -> Result<Vec<SubmissionChange>> {
+        let changes = match message {
+            Message::Relation(relation) => {
+                self.relations.insert(relation.id, relation);
+                vec![]
+            }
+            Message::Insert { relation_id, new } => match self.submissions(relation_id)? {
+                Some(relation) => vec![SubmissionChange::Upsert {
+                    submission: to_submission(relation, &new)?,
+                }],
+                None => vec![],
+            },
+            Message::Update {
+                relation_id,
+                old,
+                new,
+            } => match self.submissions(relation_id)? {
+                Some(relation) => {
+                    let submission = to_submission(relation, &new)?;
+                    let mut changes = vec![];
+                    if let Some(old) = old {
+                        let id = column(relation, &old, "id")?.context("id is null")?;
+                        if id != submission.id {
+                            changes.push(SubmissionChange::Delete { id });
+                        }
+                    }
+                    changes.push(SubmissionChange::Upsert { submission });
+                    changes
+                }
+                None => vec![],
+            },
+            Message::Delete { relation_id, old } => match self.submissions(relation_id)? {
+                Some(relation) => {
+                    let id = column(relation, &old, "id")?.context("id is null")?;
+                    vec![SubmissionChange::Delete { id }]
+                }
+                None => vec![],
+            },
+            Message::Truncate { relation_ids } => {
+                let mut truncated = false;
+                for &relation_id in relation_ids.iter() {
+                    truncated |= self.submissions(relation_id)?.is_some();
+                }
+                if truncated {
+                    vec![SubmissionChange::Truncate]
+                } else {
+                    vec![]
+                }
+            }
+            Message::Begin | Message::Commit { .. } | Message::Other(_) => vec![],
+        };
+        Ok(changes)
+    }
+
+    /// Returns the relation if it is the submissions or one of their partitions, which is sent
+    /// if the publication does not publish the changes via the root.
+    fn submissions(&self, relation_id: u32) -> Result<Option<&Relation>> {
+        let relation = self
+            .relations
+            .get(&relation_id)
+            .ok_or_else(|| anyhow!("Unknown relation: {}", relation_id))?;
+        Ok(Some(relation).filter(|r| {
+            r.name == SUBMISSIONS_TABLE
+                || YearlyPartition::from_name(SUBMISSIONS_TABLE, &r.name).is_some()
+        }))
+    }
+}
+
+fn column<T>(relation: &Relation, tuple: &[Value], name: &str) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let index = relation
+        .columns
+        .iter()
+        .position(|c| c == name)
+        .ok_or_else(|| anyhow!("{} is not in {}", name, relation.name))?;
+    match tuple.get(index) {
+        Some(Value::Text(text)) => {
+            let value = text
+                .parse()
+                .with_context(|| format!("Failed to parse {}: {}", name, text))?;
+            Ok(Some(value))
+        }
+        Some(Value::Null) => Ok(None),
+        Some(Value::Unchanged) => bail!("{} is not sent", name),
+        None => bail!("{} is missing in the tuple", name),
+    }
+}
+
+fn required<T>(relation: &Relation, tuple: &[Value], name: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    column(relation, tuple, name)?.ok_or_else(|| anyhow!("{} is null", name))
+}
+
+fn to_submission(relation: &Relation, tuple: &[Value]) -> Result<Submission> {
+    Ok(Submission {
+        id: required(relation, tuple, "id")?,
+        epoch_second: required(relation, tuple, "epoch_second")?,
+        problem_id: required::<String>(relation, tuple, "problem_id")?.into(),
+        contest_id: required::<String>(relation, tuple, "contest_id")?.into(),
+        user_id: required::<String>(relation, tuple, "user_id")?.into(),
+        language: required(relation, tuple, "language")?,
+        point: required(relation, tuple, "point")?,
+        length: required(relation, tuple, "length")?,
+        result: required(relation, tuple, "result")?,
+        execution_time: column(relation, tuple, "execution_time")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: [&str; 10] = [
+        "id",
+        "epoch_second",
+        "problem_id",
+        "contest_id",
+        "user_id",
+        "language",
+        "point",
+        "length",
+        "result",
+        "execution_time",
+    ];
+
+    fn relation_message(id: u32, name: &str, columns: &[&str]) -> Vec<u8> {
+        let mut data = vec![b'R'];
+        data.extend_from_slice(&id.to_be_bytes());
+        data.extend_from_slice(b"public\0");
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        data.push(b'd');
+        data.extend_from_slice(&(columns.len() as u16).to_be_bytes());
+        for column in columns.iter() {
+            data.push(0);
+            data.extend_from_slice(column.as_bytes());
+            data.push(0);
+            data.extend_from_slice(&25u32.to_be_bytes());
+            data.extend_from_slice(&u32::MAX.to_be_bytes());
+        }
+        data
+    }
+
+    fn tuple(values: &[Option<&str>]) -> Vec<u8> {
+        let mut data = (values.len() as u16).to_be_bytes().to_vec();
+        for value in values.iter() {
+            match value {
+                Some(value) => {
+                    data.push(b't');
+                    data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+                    data.extend_from_slice(value.as_bytes());
+                }
+                None => data.push(b'n'),
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse_relation() {
+        let message = parse_message(&relation_message(16384, "submissions", &COLUMNS)).unwrap();
+        match message {
+            Message::Relation(relation) => {
+                assert_eq!(relation.id, 16384);
+                assert_eq!(relation.namespace, "public");
+                assert_eq!(relation.name, "submissions");
+                assert_eq!(relation.columns, COLUMNS);
+            }
+            message => panic!("{:?}", message),
+        }
+    }
+
+    #[test]
+    fn test_parse_truncated_message() {
+        let data = relation_message(16384, "submissions", &COLUMNS);
+        assert!(parse_message(&data[..data.len() - 1]).is_err());
+        assert!(parse_message(&[]).is_err());
+    }
+
+    #[test]
+    fn test_decode_submissions() {
+        let mut decoder = SubmissionDecoder::default();
+        for data in [
+            relation_message(1, "submissions", &COLUMNS),
+            relation_message(2, "contests", &["id"]),
+        ]
+        .iter()
+        {
+            let changes = decoder.decode(parse_message(data).unwrap()).unwrap();
+            assert!(changes.is_empty());
+        }
+
+        let mut insert = vec![b'I'];
+        insert.extend_from_slice(&1u32.to_be_bytes());
+        insert.push(b'N');
+        insert.extend(tuple(&[
+            Some("1"),
+            Some("1600000000"),
+            Some("abc001_a"),
+            Some("abc001"),
+            Some("user"),
+            Some("Rust"),
+            Some("100"),
+            Some("42"),
+            Some("AC"),
+            None,
+        ]));
+        let changes = decoder.decode(parse_message(&insert).unwrap()).unwrap();
+        assert_eq!(
+            changes,
+            vec![SubmissionChange::Upsert {
+                submission: Submission {
+                    id: 1,
+                    epoch_second: 1_600_000_000,
+                    problem_id: "abc001_a".into(),
+                    contest_id: "abc001".into(),
+                    user_id: "user".into(),
+                    language: "Rust".to_owned(),
+                    point: 100.0,
+                    length: 42,
+                    result: "AC".to_owned(),
+                    execution_time: None,
+                }
+            }]
+        );
+
+        let mut delete = vec![b'D'];
+        delete.extend_from_slice(&1u32.to_be_bytes());
+        delete.push(b'K');
+        delete.extend(tuple(&[
+            Some("1"),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ]));
+        let changes = decoder.decode(parse_message(&delete).unwrap()).unwrap();
+        assert_eq!(changes, vec![SubmissionChange::Delete { id: 1 }]);
+
+        let mut insert = vec![b'I'];
+        insert.extend_from_slice(&2u32.to_be_bytes());
+        insert.push(b'N');
+        insert.extend(tuple(&[Some("abc001")]));
+        let changes = decoder.decode(parse_message(&insert).unwrap()).unwrap();
+        assert!(changes.is_empty());
+
+        let mut truncate = vec![b'T'];
+        truncate.extend_from_slice(&2u32.to_be_bytes());
+        truncate.push(0);
+        truncate.extend_from_slice(&2u32.to_be_bytes());
+        truncate.extend_from_slice(&1u32.to_be_bytes());
+        let changes = decoder.decode(parse_message(&truncate).unwrap()).unwrap();
+        assert_eq!(changes, vec![SubmissionChange::Truncate]);
+    }
+
+    #[test]
+    fn test_decode_updated_id() {
+        let mut decoder = SubmissionDecoder::default();
+        let relation = relation_message(3, "submissions_y2021", &COLUMNS);
+        let changes = decoder.decode(parse_message(&relation).unwrap()).unwrap();
+        assert!(changes.is_empty());
+
+        let row = |id| {
+            tuple(&[
+                Some(id),
+                Some("1600000000"),
+                Some("abc001_a"),
+                Some("abc001"),
+                Some("user"),
+                Some("Rust"),
+                Some("100"),
+                Some("42"),
+                Some("AC"),
+                None,
+            ])
+        };
+        let mut update = vec![b'U'];
+        update.extend_from_slice(&3u32.to_be_bytes());
+        update.push(b'K');
+        update.extend(row("1"));
+        update.push(b'N');
+        update.extend(row("2"));
+        let changes = decoder.decode(parse_message(&update).unwrap()).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0], SubmissionChange::Delete { id: 1 });
+        match &changes[1] {
+            SubmissionChange::Upsert { submission } => assert_eq!(submission.id, 2),
+            change => panic!("{:?}", change),
+        }
+    }
+
+    #[test]
+    fn test_serialize_change() {
+        let change = SubmissionChange::Delete { id: 1 };
+        assert_eq!(
+            serde_json::to_string(&change).unwrap(),
+            r#"{"type":"delete","id":1}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&SubmissionChange::Truncate).unwrap(),
+            r#"{"type":"truncate"}"#
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{PgPool, MAX_INSERT_ROWS};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::BTreeMap;
+
+#[async_trait]
+pub trait ProblemDifficultyClient {
+    async fn update_problem_difficulties(&self, difficulties: &BTreeMap<String, f64>)
+        -> Result<()>;
+    async fn load_problem_difficulties(&self) -> Result<BTreeMap<String, f64>>;
+}
+
+#[async_trait]
+impl ProblemDifficultyClient for PgPool {
+    async fn update_problem_difficulties(
+        &self,
+        difficulties: &BTreeMap<String, f64>,
+    ) -> Result<()> {
+        let difficulties = difficulties
+            .iter()
+            .map(|(problem_id, &difficulty)| (problem_id.as_str(), difficulty))
+            .collect::<Vec<_>>();
+        for chunk in difficulties.chunks(MAX_INSERT_ROWS) {
+            let (problem_ids, difficulties): (Vec<&str>, Vec<f64>) = chunk.iter().copied().unzip();
+            sqlx::query(
+                r"
+                INSERT INTO problem_difficulties (problem_id, difficulty)
+                VALUES (
+                    UNNEST($1::VARCHAR(255)[]),
+                    UNNEST($2::FLOAT8[])
+                )
+                ON CONFLICT (problem_id)
+                DO UPDATE SET difficulty = EXCLUDED.difficulty
+                ",
+            )
+            .bind(problem_ids)
+            .bind(difficulties)
+            .execute(self)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn load_problem_difficulties(&self) -> Result<BTreeMap<String, f64>> {
+        let difficulties = sqlx::query("SELECT problem_id, difficulty FROM problem_difficulties")
+            .try_map(|row: PgRow| {
+                let problem_id: String = row.try_get("problem_id")?;
+                let difficulty: f64 = row.try_get("difficulty")?;
+                Ok((problem_id, difficulty))
+            })
+            .fetch_all(self)
+            .await?;
+        Ok(difficulties.into_iter().collect())
+    }
+}
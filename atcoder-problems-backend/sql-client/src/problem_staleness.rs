@@ -0,0 +1,164 @@
+use crate::ids::{ContestId, ProblemId, UserId};
+use crate::models::{Problem, StaleProblem, UnsolvedAttempt};
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+#[async_trait]
+pub trait ProblemStalenessClient {
+    async fn update_last_accepted(&self) -> Result<()>;
+    async fn load_never_solved_problems(&self) -> Result<Vec<Problem>>;
+    async fn load_stale_problems(&self, count: i64) -> Result<Vec<StaleProblem>>;
+    async fn load_unsolved_attempts(&self, user_id: &UserId) -> Result<Vec<UnsolvedAttempt>>;
+    /// Returns the problems which the user solved last before `before_epoch_second`, in the order
+    /// of the last accepted submissions.
+    async fn load_users_stale_problems(
+        &self,
+        user_id: &UserId,
+        before_epoch_second: i64,
+    ) -> Result<Vec<StaleProblem>>;
+}
+
+#[async_trait]
+impl ProblemStalenessClient for PgPool {
+    async fn update_last_accepted(&self) -> Result<()> {
+        sqlx::query(
+            r"
+                INSERT INTO last_accepted (problem_id, epoch_second)
+                    SELECT problem_id, MAX(epoch_second)
+                    FROM submissions
+                    WHERE result = 'AC'
+                    GROUP BY problem_id
+                ON CONFLICT (problem_id) DO UPDATE
+                SET epoch_second = EXCLUDED.epoch_second;
+            ",
+        )
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn load_never_solved_problems(&self) -> Result<Vec<Problem>> {
+        let problems = sqlx::query(
+            r"
+            SELECT
+                problems.id, problems.contest_id, problems.title,
+                problems.problem_index, problems.name
+            FROM problems
+            LEFT JOIN last_accepted ON last_accepted.problem_id = problems.id
+            WHERE last_accepted.problem_id IS NULL
+            ORDER BY problems.id
+            ",
+        )
+        .try_map(|row: PgRow| {
+            let id: ProblemId = row.try_get("id")?;
+            let contest_id: ContestId = row.try_get("contest_id")?;
+            let title: String = row.try_get("title")?;
+            let problem_index: String = row.try_get("problem_index")?;
+            let name: String = row.try_get("name")?;
+            Ok(Problem {
+                id,
+                contest_id,
+                title,
+                problem_index,
+                name,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(problems)
+    }
+
+    async fn load_stale_problems(&self, count: i64) -> Result<Vec<StaleProblem>> {
+        let problems = sqlx::query(
+            r"
+            SELECT problems.id, problems.contest_id, last_accepted.epoch_second
+            FROM problems
+            INNER JOIN last_accepted ON last_accepted.problem_id = problems.id
+            ORDER BY last_accepted.epoch_second, problems.id
+            LIMIT $1
+            ",
+        )
+        .bind(count)
+        .try_map(|row: PgRow| {
+            let problem_id: String = row.try_get("id")?;
+            let contest_id: String = row.try_get("contest_id")?;
+            let last_accepted_epoch_second: i64 = row.try_get("epoch_second")?;
+            Ok(StaleProblem {
+                problem_id,
+                contest_id,
+                last_accepted_epoch_second,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(problems)
+    }
+
+    async fn load_unsolved_attempts(&self, user_id: &UserId) -> Result<Vec<UnsolvedAttempt>> {
+        let attempts = sqlx::query(
+            r"
+            SELECT
+                problem_id,
+                MIN(contest_id) AS contest_id,
+                MIN(epoch_second) AS first_epoch_second,
+                MAX(epoch_second) AS last_epoch_second,
+                COUNT(*) AS attempt_count
+            FROM submissions
+            WHERE user_id = $1
+            GROUP BY problem_id
+            HAVING COUNT(*) FILTER (WHERE result = 'AC') = 0
+            ORDER BY first_epoch_second, problem_id
+            ",
+        )
+        .bind(user_id)
+        .try_map(|row: PgRow| {
+            let problem_id: String = row.try_get("problem_id")?;
+            let contest_id: String = row.try_get("contest_id")?;
+            let first_attempt_epoch_second: i64 = row.try_get("first_epoch_second")?;
+            let last_attempt_epoch_second: i64 = row.try_get("last_epoch_second")?;
+            let attempt_count: i64 = row.try_get("attempt_count")?;
+            Ok(UnsolvedAttempt {
+                problem_id,
+                contest_id,
+                first_attempt_epoch_second,
+                last_attempt_epoch_second,
+                attempt_count,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(attempts)
+    }
+
+    async fn load_users_stale_problems(
+        &self,
+        user_id: &UserId,
+        before_epoch_second: i64,
+    ) -> Result<Vec<StaleProblem>> {
+        let problems = sqlx::query(
+            r"
+            SELECT problem_id, MIN(contest_id) AS contest_id, MAX(epoch_second) AS epoch_second
+            FROM submissions
+            WHERE user_id = $1 AND result = 'AC'
+            GROUP BY problem_id
+            HAVING MAX(epoch_second) < $2
+            ORDER BY epoch_second, problem_id
+            ",
+        )
+        .bind(user_id)
+        .bind(before_epoch_second)
+        .try_map(|row: PgRow| {
+            Ok(StaleProblem {
+                problem_id: row.try_get("problem_id")?,
+                contest_id: row.try_get("contest_id")?,
+                last_accepted_epoch_second: row.try_get("epoch_second")?,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(problems)
+    }
+}
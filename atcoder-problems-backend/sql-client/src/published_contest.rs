@@ -0,0 +1,45 @@
+use crate::ids::ContestId;
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::BTreeSet;
+
+/// The contests whose discoveries `publish-events` has published, so that the contests found
+/// while it is stopped are published after restarting.
+#[async_trait]
+pub trait PublishedContestClient {
+    async fn load_published_contest_ids(&self) -> Result<BTreeSet<ContestId>>;
+    async fn insert_published_contest_ids(&self, contest_ids: &[ContestId]) -> Result<()>;
+}
+
+#[async_trait]
+impl PublishedContestClient for PgPool {
+    async fn load_published_contest_ids(&self) -> Result<BTreeSet<ContestId>> {
+        let contest_ids = sqlx::query("SELECT contest_id FROM published_contests")
+            .try_map(|row: PgRow| row.try_get::<String, _>("contest_id"))
+            .fetch_all(self)
+            .await?;
+        Ok(contest_ids.into_iter().map(ContestId::from).collect())
+    }
+
+    async fn insert_published_contest_ids(&self, contest_ids: &[ContestId]) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO published_contests (contest_id)
+            VALUES (UNNEST($1::VARCHAR(255)[]))
+            ON CONFLICT DO NOTHING
+            ",
+        )
+        .bind(
+            contest_ids
+                .iter()
+                .map(ContestId::as_str)
+                .collect::<Vec<_>>(),
+        )
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
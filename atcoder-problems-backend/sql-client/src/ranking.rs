@@ -0,0 +1,279 @@
+use crate::ids::UserId;
+use crate::models::{RankingCursor, RankingEntry};
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::{PgArguments, PgRow};
+use sqlx::query::Query;
+use sqlx::{Postgres, Row};
+
+/// The rankings built from the aggregation tables.
+/// Users are ordered by the value in descending order, and the ties are broken by the user id.
+#[derive(Debug, Clone, Copy)]
+pub enum RankingKind<'a> {
+    AcceptedCount,
+    RatedPointSum,
+    Streak,
+    LanguageCount {
+        language: &'a str,
+    },
+    /// The last streaks which are still alive, i.e. extended at or after `alive_since`.
+    CurrentStreak {
+        alive_since: i64,
+    },
+}
+
+/// The value of the condition which narrows down the users in a ranking.
+#[derive(Debug, Clone, Copy)]
+enum FilterValue<'a> {
+    Text(&'a str),
+    Integer(i64),
+}
+
+fn bind_filter<'q>(
+    query: Query<'q, Postgres, PgArguments>,
+    value: FilterValue<'q>,
+) -> Query<'q, Postgres, PgArguments> {
+    match value {
+        FilterValue::Text(value) => query.bind(value),
+        FilterValue::Integer(value) => query.bind(value),
+    }
+}
+
+impl<'a> RankingKind<'a> {
+    fn table(&self) -> &'static str {
+        match self {
+            RankingKind::AcceptedCount => "accepted_count",
+            RankingKind::RatedPointSum => "rated_point_sum",
+            RankingKind::Streak => "max_streaks",
+            RankingKind::LanguageCount { .. } => "language_count",
+            RankingKind::CurrentStreak { .. } => "current_streaks",
+        }
+    }
+
+    fn value_column(&self) -> &'static str {
+        match self {
+            RankingKind::AcceptedCount => "problem_count",
+            RankingKind::RatedPointSum => "point_sum",
+            RankingKind::Streak => "streak",
+            RankingKind::LanguageCount { .. } => "problem_count",
+            RankingKind::CurrentStreak { .. } => "streak",
+        }
+    }
+
+    fn value_type(&self) -> &'static str {
+        match self {
+            RankingKind::AcceptedCount => "INT",
+            RankingKind::RatedPointSum => "DOUBLE PRECISION",
+            RankingKind::Streak => "BIGINT",
+            RankingKind::LanguageCount { .. } => "INT",
+            RankingKind::CurrentStreak { .. } => "BIGINT",
+        }
+    }
+
+    /// Returns the condition on a column, which is followed by the parameter of the value.
+    fn filter(&self) -> Option<(&'static str, FilterValue<'a>)> {
+        match *self {
+            RankingKind::LanguageCount { language } => {
+                Some(("simplified_language =", FilterValue::Text(language)))
+            }
+            RankingKind::CurrentStreak { alive_since } => {
+                Some(("last_epoch_second >=", FilterValue::Integer(alive_since)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+pub trait RankingClient {
+    async fn load_ranking(
+        &self,
+        kind: RankingKind<'_>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RankingEntry>>;
+
+    /// Loads the entries after the cursor, or from the top of the ranking if `cursor` is `None`.
+    async fn load_ranking_after(
+        &self,
+        kind: RankingKind<'_>,
+        cursor: Option<&RankingCursor>,
+        limit: usize,
+    ) -> Result<Vec<RankingEntry>>;
+
+    /// Returns the 0-indexed position of the user in the ranking, or `None` if the user is not in it.
+    async fn get_ranking_position(
+        &self,
+        kind: RankingKind<'_>,
+        user_id: &UserId,
+    ) -> Result<Option<usize>>;
+}
+
+#[async_trait]
+impl RankingClient for PgPool {
+    async fn load_ranking(
+        &self,
+        kind: RankingKind<'_>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<RankingEntry>> {
+        let filter = match kind.filter() {
+            Some((condition, _)) => format!("WHERE {} $3", condition),
+            None => String::new(),
+        };
+        let sql = format!(
+            r"
+            SELECT
+                user_id,
+                {value}::DOUBLE PRECISION AS value,
+                RANK() OVER (ORDER BY {value} DESC) AS rank
+            FROM {table}
+            {filter}
+            ORDER BY {value} DESC, user_id ASC
+            OFFSET $1 LIMIT $2
+            ",
+            value = kind.value_column(),
+            table = kind.table(),
+            filter = filter,
+        );
+        let mut query = sqlx::query(&sql).bind(offset as i64).bind(limit as i64);
+        if let Some((_, value)) = kind.filter() {
+            query = bind_filter(query, value);
+        }
+        let ranking = query
+            .try_map(|row: PgRow| {
+                let rank: i64 = row.try_get("rank")?;
+                let user_id: String = row.try_get("user_id")?;
+                let value: f64 = row.try_get("value")?;
+                Ok(RankingEntry {
+                    rank,
+                    user_id,
+                    value,
+                })
+            })
+            .fetch_all(self)
+            .await?;
+        Ok(ranking)
+    }
+
+    async fn load_ranking_after(
+        &self,
+        kind: RankingKind<'_>,
+        cursor: Option<&RankingCursor>,
+        limit: usize,
+    ) -> Result<Vec<RankingEntry>> {
+        // The ranks are derived from the cursor, because the users before it are not scanned.
+        // The users with the same value as the last entry share its rank, and the rank of the others
+        // is the position of the cursor plus their rank in the page.
+        let (cursor_filter, rank, filter_param) = match cursor {
+            Some(_) => (
+                format!(
+                    "WHERE ({value} < $2::{value_type} OR ({value} = $2::{value_type} AND user_id > $3))",
+                    value = kind.value_column(),
+                    value_type = kind.value_type(),
+                ),
+                format!(
+                    "CASE WHEN value = $2::{value_type} THEN $4 ELSE $5 + RANK() OVER (ORDER BY value DESC) END",
+                    value_type = kind.value_type(),
+                ),
+                "$6",
+            ),
+            None => (
+                "WHERE TRUE".to_owned(),
+                "RANK() OVER (ORDER BY value DESC)".to_owned(),
+                "$2",
+            ),
+        };
+        let filter = match kind.filter() {
+            Some((condition, _)) => format!("AND {} {}", condition, filter_param),
+            None => String::new(),
+        };
+        let sql = format!(
+            r"
+            SELECT
+                user_id,
+                value::DOUBLE PRECISION AS value,
+                {rank} AS rank
+            FROM (
+                SELECT user_id, {value} AS value
+                FROM {table}
+                {cursor_filter}
+                {filter}
+                ORDER BY {value} DESC, user_id ASC
+                LIMIT $1
+            ) AS page
+            ORDER BY value DESC, user_id ASC
+            ",
+            rank = rank,
+            cursor_filter = cursor_filter,
+            filter = filter,
+            value = kind.value_column(),
+            table = kind.table(),
+        );
+        let mut query = sqlx::query(&sql).bind(limit as i64);
+        if let Some(cursor) = cursor {
+            query = query
+                .bind(cursor.value)
+                .bind(cursor.user_id.as_str())
+                .bind(cursor.rank)
+                .bind(cursor.position as i64);
+        }
+        if let Some((_, value)) = kind.filter() {
+            query = bind_filter(query, value);
+        }
+        let ranking = query
+            .try_map(|row: PgRow| {
+                let rank: i64 = row.try_get("rank")?;
+                let user_id: String = row.try_get("user_id")?;
+                let value: f64 = row.try_get("value")?;
+                Ok(RankingEntry {
+                    rank,
+                    user_id,
+                    value,
+                })
+            })
+            .fetch_all(self)
+            .await?;
+        Ok(ranking)
+    }
+
+    async fn get_ranking_position(
+        &self,
+        kind: RankingKind<'_>,
+        user_id: &UserId,
+    ) -> Result<Option<usize>> {
+        let (user_filter, others_filter) = match kind.filter() {
+            Some((condition, _)) => (
+                format!("AND u.{} $2", condition),
+                format!("AND t.{} $2", condition),
+            ),
+            None => (String::new(), String::new()),
+        };
+        let sql = format!(
+            r"
+            SELECT (
+                SELECT COUNT(*) FROM {table} AS t
+                WHERE (t.{value} > u.{value} OR (t.{value} = u.{value} AND t.user_id < u.user_id))
+                {others_filter}
+            ) AS position
+            FROM {table} AS u
+            WHERE u.user_id = $1
+            {user_filter}
+            ",
+            value = kind.value_column(),
+            table = kind.table(),
+            user_filter = user_filter,
+            others_filter = others_filter,
+        );
+        let mut query = sqlx::query(&sql).bind(user_id);
+        if let Some((_, value)) = kind.filter() {
+            query = bind_filter(query, value);
+        }
+        let position = query
+            .try_map(|row: PgRow| row.try_get::<i64, _>("position"))
+            .fetch_optional(self)
+            .await?;
+        Ok(position.map(|position| position as usize))
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ids::{ContestId, ProblemId, UserId};
 use crate::models::{ContestProblem, Submission, UserSum};
 use crate::{PgPool, FIRST_AGC_EPOCH_SECOND, MAX_INSERT_ROWS, UNRATED_STATE};
 use anyhow::Result;
@@ -11,47 +12,17 @@ use std::ops::Range;
 #[async_trait]
 pub trait RatedPointSumClient {
     async fn update_rated_point_sum(&self, ac_submissions: &[Submission]) -> Result<()>;
-    async fn get_users_rated_point_sum(&self, user_id: &str) -> Option<f64>;
+    /// Returns `None` if the user has no accepted submissions of the rated problems.
+    async fn get_users_rated_point_sum(&self, user_id: &UserId) -> Result<Option<f64>>;
     async fn get_rated_point_sum_rank(&self, point: f64) -> Result<i64>;
-    async fn load_rated_point_sum_in_range(&self, rank_range: Range<usize>) -> Result<Vec<UserSum>>;
+    async fn load_rated_point_sum_in_range(&self, rank_range: Range<usize>)
+        -> Result<Vec<UserSum>>;
 }
 
 #[async_trait]
 impl RatedPointSumClient for PgPool {
     async fn update_rated_point_sum(&self, ac_submissions: &[Submission]) -> Result<()> {
-        let rated_contest_ids_fut = sqlx::query(
-            r"
-            SELECT id FROM contests
-            WHERE start_epoch_second >= $1
-            AND rate_change != $2
-            ",
-        )
-        .bind(FIRST_AGC_EPOCH_SECOND)
-        .bind(UNRATED_STATE)
-        .try_map(|row: PgRow| row.try_get::<String, _>("id"))
-        .fetch_all(self);
-
-        let rated_problem_ids_fut =
-            sqlx::query("SELECT contest_id, problem_id FROM contest_problem")
-                .try_map(|row: PgRow| {
-                    let contest_id: String = row.try_get("contest_id")?;
-                    let problem_id: String = row.try_get("problem_id")?;
-                    Ok(ContestProblem {
-                        contest_id,
-                        problem_id,
-                    })
-                })
-                .fetch_all(self);
-
-        let (rated_contest_ids, rated_problem_ids) =
-            try_join!(rated_contest_ids_fut, rated_problem_ids_fut)?;
-
-        let rated_contest_ids = rated_contest_ids.into_iter().collect::<BTreeSet<_>>();
-        let rated_problem_ids = rated_problem_ids
-            .into_iter()
-            .filter(|p| rated_contest_ids.contains(&p.contest_id))
-            .map(|p| p.problem_id)
-            .collect::<BTreeSet<_>>();
+        let rated_problem_ids = load_rated_problem_ids(self).await?;
         let rated_point_sum = ac_submissions
             .iter()
             .filter(|s| rated_problem_ids.contains(&s.problem_id))
@@ -90,14 +61,13 @@ impl RatedPointSumClient for PgPool {
         Ok(())
     }
 
-    async fn get_users_rated_point_sum(&self, user_id: &str) -> Option<f64> {
+    async fn get_users_rated_point_sum(&self, user_id: &UserId) -> Result<Option<f64>> {
         let sum = sqlx::query("SELECT point_sum FROM rated_point_sum WHERE user_id = $1")
             .bind(user_id)
             .try_map(|row: PgRow| row.try_get::<f64, _>("point_sum"))
-            .fetch_one(self)
-            .await
-            .ok()?;
-        Some(sum)
+            .fetch_optional(self)
+            .await?;
+        Ok(sum)
     }
 
     async fn get_rated_point_sum_rank(&self, rated_point_sum: f64) -> Result<i64> {
@@ -109,7 +79,10 @@ impl RatedPointSumClient for PgPool {
         Ok(rank)
     }
 
-    async fn load_rated_point_sum_in_range(&self, rank_range: Range<usize>) -> Result<Vec<UserSum>> {
+    async fn load_rated_point_sum_in_range(
+        &self,
+        rank_range: Range<usize>,
+    ) -> Result<Vec<UserSum>> {
         let list = sqlx::query(
             r"
             SELECT * FROM rated_point_sum
@@ -129,3 +102,39 @@ impl RatedPointSumClient for PgPool {
         Ok(list)
     }
 }
+
+pub(crate) async fn load_rated_problem_ids(pool: &PgPool) -> Result<BTreeSet<ProblemId>> {
+    let rated_contest_ids_fut = sqlx::query(
+        r"
+        SELECT id FROM contests
+        WHERE start_epoch_second >= $1
+        AND rate_change != $2
+        ",
+    )
+    .bind(FIRST_AGC_EPOCH_SECOND)
+    .bind(UNRATED_STATE)
+    .try_map(|row: PgRow| row.try_get::<ContestId, _>("id"))
+    .fetch_all(pool);
+
+    let rated_problem_ids_fut = sqlx::query("SELECT contest_id, problem_id FROM contest_problem")
+        .try_map(|row: PgRow| {
+            let contest_id: ContestId = row.try_get("contest_id")?;
+            let problem_id: ProblemId = row.try_get("problem_id")?;
+            Ok(ContestProblem {
+                contest_id,
+                problem_id,
+            })
+        })
+        .fetch_all(pool);
+
+    let (rated_contest_ids, rated_problem_ids) =
+        try_join!(rated_contest_ids_fut, rated_problem_ids_fut)?;
+
+    let rated_contest_ids = rated_contest_ids.into_iter().collect::<BTreeSet<_>>();
+    let rated_problem_ids = rated_problem_ids
+        .into_iter()
+        .filter(|p| rated_contest_ids.contains(&p.contest_id))
+        .map(|p| p.problem_id)
+        .collect::<BTreeSet<_>>();
+    Ok(rated_problem_ids)
+}
@@ -0,0 +1,142 @@
+//! Reads the logical replication of the `submissions` table through the SQL functions of the
+//! replication slots, which does not need a connection in the replication mode.
+//!
+//! The changes are peeked and the slot is advanced only after they are applied, so that they are
+//! applied at least once even if the consumer fails.
+
+use crate::pgoutput::SubmissionChange;
+use crate::submission_client::SubmissionClient;
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+/// A message of `pgoutput` with the LSN of its record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationChange {
+    pub lsn: String,
+    pub data: Vec<u8>,
+}
+
+#[async_trait]
+pub trait ReplicationClient {
+    /// Creates the publication of the submissions and the slot if they do not exist. The changes of
+    /// the partitions are published as the changes of `submissions`.
+    async fn create_replication_slot(&self, slot: &str, publication: &str) -> Result<()>;
+
+    /// Returns at most `limit` changes from the slot without consuming them. The transactions are
+    /// not split, so it may return more changes than `limit`.
+    async fn peek_replication_changes(
+        &self,
+        slot: &str,
+        publication: &str,
+        limit: i32,
+    ) -> Result<Vec<ReplicationChange>>;
+
+    /// Consumes the changes up to the LSN.
+    async fn advance_replication_slot(&self, slot: &str, lsn: &str) -> Result<()>;
+
+    /// Applies the changes to the submissions of this database, in order.
+    async fn apply_submission_changes(&self, changes: &[SubmissionChange]) -> Result<()>;
+}
+
+#[async_trait]
+impl ReplicationClient for PgPool {
+    async fn create_replication_slot(&self, slot: &str, publication: &str) -> Result<()> {
+        let publications: i64 =
+            sqlx::query("SELECT COUNT(*) FROM pg_publication WHERE pubname = $1")
+                .bind(publication)
+                .try_map(|row: PgRow| row.try_get(0))
+                .fetch_one(self)
+                .await?;
+        let query = if publications == 0 {
+            format!(
+                "CREATE PUBLICATION {} FOR TABLE submissions WITH (publish_via_partition_root = true)",
+                publication
+            )
+        } else {
+            format!(
+                "ALTER PUBLICATION {} SET (publish_via_partition_root = true)",
+                publication
+            )
+        };
+        sqlx::query(&query).execute(self).await?;
+
+        let slots: i64 =
+            sqlx::query("SELECT COUNT(*) FROM pg_replication_slots WHERE slot_name = $1")
+                .bind(slot)
+                .try_map(|row: PgRow| row.try_get(0))
+                .fetch_one(self)
+                .await?;
+        if slots == 0 {
+            sqlx::query("SELECT pg_create_logical_replication_slot($1, 'pgoutput')")
+                .bind(slot)
+                .execute(self)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn peek_replication_changes(
+        &self,
+        slot: &str,
+        publication: &str,
+        limit: i32,
+    ) -> Result<Vec<ReplicationChange>> {
+        let changes = sqlx::query(
+            r"
+            SELECT lsn::TEXT, data FROM pg_logical_slot_peek_binary_changes(
+                $1, NULL, $2, 'proto_version', '1', 'publication_names', $3
+            )
+            ",
+        )
+        .bind(slot)
+        .bind(limit)
+        .bind(publication)
+        .try_map(|row: PgRow| {
+            Ok(ReplicationChange {
+                lsn: row.try_get(0)?,
+                data: row.try_get(1)?,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(changes)
+    }
+
+    async fn advance_replication_slot(&self, slot: &str, lsn: &str) -> Result<()> {
+        sqlx::query("SELECT pg_replication_slot_advance($1, $2::TEXT::PG_LSN)")
+            .bind(slot)
+            .bind(lsn)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn apply_submission_changes(&self, changes: &[SubmissionChange]) -> Result<()> {
+        // The consecutive upserts are written at once. The other changes are applied one by one
+        // to keep the order.
+        let mut submissions = Vec::new();
+        for change in changes.iter() {
+            match change {
+                SubmissionChange::Upsert { submission } => submissions.push(submission.clone()),
+                SubmissionChange::Delete { id } => {
+                    self.update_submissions(&submissions).await?;
+                    submissions.clear();
+                    sqlx::query("DELETE FROM submissions WHERE id = $1")
+                        .bind(id)
+                        .execute(self)
+                        .await?;
+                }
+                SubmissionChange::Truncate => {
+                    self.update_submissions(&submissions).await?;
+                    submissions.clear();
+                    sqlx::query("TRUNCATE submissions").execute(self).await?;
+                }
+            }
+        }
+        self.update_submissions(&submissions).await?;
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use crate::data_version::{DataVersionClient, CONTESTS_DATA, MERGED_PROBLEMS_DATA, PROBLEMS_DATA};
+use crate::ids::{ContestId, ProblemId};
 use crate::models::{Contest, Problem};
 use crate::PgPool;
 use anyhow::Result;
@@ -10,50 +12,112 @@ pub trait SimpleClient {
     async fn insert_contests(&self, values: &[Contest]) -> Result<usize>;
     async fn insert_problems(&self, values: &[Problem]) -> Result<usize>;
     async fn load_problems(&self) -> Result<Vec<Problem>>;
+    /// Loads the contests with `category` and `rated_range` derived from the other columns,
+    /// since the stored ones are stale in the rows written by COPY or before they were added.
     async fn load_contests(&self) -> Result<Vec<Contest>>;
+
+    /// Writes the derived columns of all the contests again, e.g. after they are imported.
+    async fn update_derived_contest_columns(&self) -> Result<usize>;
+
+    /// Sets the rules of the contest which are crawled separately from the contest list, so they
+    /// are not overwritten by `insert_contests`.
+    async fn update_contest_rules(
+        &self,
+        contest_id: &ContestId,
+        penalty_minutes: i64,
+        allows_late_join: bool,
+    ) -> Result<()>;
 }
 
 #[async_trait]
 impl SimpleClient for PgPool {
     async fn insert_contests(&self, values: &[Contest]) -> Result<usize> {
-        let (ids, start_epoch_seconds, duration_seconds, titles, rate_changes) =
-            values.iter().fold(
-                (vec![], vec![], vec![], vec![], vec![]),
-                |(
-                    mut ids,
-                    mut start_epoch_seconds,
-                    mut duration_seconds,
-                    mut titles,
-                    mut rate_changes,
-                ),
-                 cur| {
-                    ids.push(cur.id.clone());
-                    start_epoch_seconds.push(cur.start_epoch_second);
-                    duration_seconds.push(cur.duration_second);
-                    titles.push(cur.title.clone());
-                    rate_changes.push(cur.rate_change.clone());
-                    (
-                        ids,
-                        start_epoch_seconds,
-                        duration_seconds,
-                        titles,
-                        rate_changes,
-                    )
-                },
-            );
+        let (
+            ids,
+            start_epoch_seconds,
+            duration_seconds,
+            titles,
+            rate_changes,
+            categories,
+            rated_lower_bounds,
+            rated_upper_bounds,
+        ) = values.iter().fold(
+            (
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+            ),
+            |(
+                mut ids,
+                mut start_epoch_seconds,
+                mut duration_seconds,
+                mut titles,
+                mut rate_changes,
+                mut categories,
+                mut rated_lower_bounds,
+                mut rated_upper_bounds,
+            ),
+             cur| {
+                ids.push(cur.id.as_str());
+                start_epoch_seconds.push(cur.start_epoch_second);
+                duration_seconds.push(cur.duration_second);
+                titles.push(cur.title.clone());
+                rate_changes.push(cur.rate_change.clone());
+                categories.push(cur.category.as_str());
+                rated_lower_bounds.push(cur.rated_range.map(|(lower, _)| lower));
+                rated_upper_bounds.push(cur.rated_range.and_then(|(_, upper)| upper));
+                (
+                    ids,
+                    start_epoch_seconds,
+                    duration_seconds,
+                    titles,
+                    rate_changes,
+                    categories,
+                    rated_lower_bounds,
+                    rated_upper_bounds,
+                )
+            },
+        );
 
+        // The derived columns of the existing contests are updated, so that they follow the
+        // changes of the classification.
         let result = sqlx::query(
             r"
             INSERT INTO contests
-            (id, start_epoch_second, duration_second, title, rate_change)
+            (
+                id,
+                start_epoch_second,
+                duration_second,
+                title,
+                rate_change,
+                category,
+                rated_lower_bound,
+                rated_upper_bound
+            )
             VALUES (
                 UNNEST($1::VARCHAR(255)[]),
                 UNNEST($2::BIGINT[]),
                 UNNEST($3::BIGINT[]),
                 UNNEST($4::VARCHAR(255)[]),
-                UNNEST($5::VARCHAR(255)[])
+                UNNEST($5::VARCHAR(255)[]),
+                UNNEST($6::VARCHAR(255)[]),
+                UNNEST($7::BIGINT[]),
+                UNNEST($8::BIGINT[])
             )
-            ON CONFLICT DO NOTHING
+            ON CONFLICT (id)
+            DO UPDATE SET
+                category = EXCLUDED.category,
+                rated_lower_bound = EXCLUDED.rated_lower_bound,
+                rated_upper_bound = EXCLUDED.rated_upper_bound
+            WHERE
+                (contests.category, contests.rated_lower_bound, contests.rated_upper_bound)
+                IS DISTINCT FROM
+                (EXCLUDED.category, EXCLUDED.rated_lower_bound, EXCLUDED.rated_upper_bound)
             ",
         )
         .bind(ids)
@@ -61,58 +125,86 @@ impl SimpleClient for PgPool {
         .bind(duration_seconds)
         .bind(titles)
         .bind(rate_changes)
+        .bind(categories)
+        .bind(rated_lower_bounds)
+        .bind(rated_upper_bounds)
         .execute(self)
         .await?;
 
+        if result.rows_affected() > 0 {
+            self.increment_data_version(CONTESTS_DATA).await?;
+        }
         Ok(result.rows_affected() as usize)
     }
 
     async fn insert_problems(&self, values: &[Problem]) -> Result<usize> {
-        let (ids, contest_ids, titles) = values.iter().fold(
-            (vec![], vec![], vec![]),
-            |(mut ids, mut contest_ids, mut titles), cur| {
-                ids.push(cur.id.clone());
-                contest_ids.push(cur.contest_id.clone());
+        let (ids, contest_ids, titles, problem_indexes, names) = values.iter().fold(
+            (vec![], vec![], vec![], vec![], vec![]),
+            |(mut ids, mut contest_ids, mut titles, mut problem_indexes, mut names), cur| {
+                ids.push(cur.id.as_str());
+                contest_ids.push(cur.contest_id.as_str());
                 titles.push(cur.title.clone());
-                (ids, contest_ids, titles)
+                problem_indexes.push(cur.problem_index.clone());
+                names.push(cur.name.clone());
+                (ids, contest_ids, titles, problem_indexes, names)
             },
         );
 
+        // The index and the name of the existing problems are filled in, but never cleared by
+        // the sources which don't have them.
         let result = sqlx::query(
             r"
             INSERT INTO problems
-            (id, contest_id, title)
+            (id, contest_id, title, problem_index, name)
             VALUES (
                 UNNEST($1::VARCHAR(255)[]),
                 UNNEST($2::VARCHAR(255)[]),
-                UNNEST($3::VARCHAR(255)[])
+                UNNEST($3::VARCHAR(255)[]),
+                UNNEST($4::VARCHAR(255)[]),
+                UNNEST($5::VARCHAR(255)[])
             )
-            ON CONFLICT DO NOTHING
+            ON CONFLICT (id) DO UPDATE SET
+                problem_index = EXCLUDED.problem_index,
+                name = EXCLUDED.name
+            WHERE EXCLUDED.problem_index <> ''
+            AND (problems.problem_index, problems.name)
+                IS DISTINCT FROM (EXCLUDED.problem_index, EXCLUDED.name)
             ",
         )
         .bind(ids)
         .bind(contest_ids)
         .bind(titles)
+        .bind(problem_indexes)
+        .bind(names)
         .execute(self)
         .await?;
 
+        if result.rows_affected() > 0 {
+            self.increment_data_version(PROBLEMS_DATA).await?;
+            self.increment_data_version(MERGED_PROBLEMS_DATA).await?;
+        }
         Ok(result.rows_affected() as usize)
     }
 
     async fn load_problems(&self) -> Result<Vec<Problem>> {
-        let problems = sqlx::query("SELECT id, contest_id, title FROM problems")
-            .try_map(|row: PgRow| {
-                let id: String = row.try_get("id")?;
-                let contest_id: String = row.try_get("contest_id")?;
-                let title: String = row.try_get("title")?;
-                Ok(Problem {
-                    id,
-                    contest_id,
-                    title,
+        let problems =
+            sqlx::query("SELECT id, contest_id, title, problem_index, name FROM problems")
+                .try_map(|row: PgRow| {
+                    let id: ProblemId = row.try_get("id")?;
+                    let contest_id: ContestId = row.try_get("contest_id")?;
+                    let title: String = row.try_get("title")?;
+                    let problem_index: String = row.try_get("problem_index")?;
+                    let name: String = row.try_get("name")?;
+                    Ok(Problem {
+                        id,
+                        contest_id,
+                        title,
+                        problem_index,
+                        name,
+                    })
                 })
-            })
-            .fetch_all(self)
-            .await?;
+                .fetch_all(self)
+                .await?;
         Ok(problems)
     }
 
@@ -124,26 +216,59 @@ impl SimpleClient for PgPool {
                     start_epoch_second,
                     duration_second,
                     title,
-                    rate_change
+                    rate_change,
+                    penalty_minutes,
+                    allows_late_join
                  FROM contests
                  ",
         )
         .try_map(|row: PgRow| {
-            let id: String = row.try_get("id")?;
+            let id: ContestId = row.try_get("id")?;
             let start_epoch_second: i64 = row.try_get("start_epoch_second")?;
             let duration_second: i64 = row.try_get("duration_second")?;
             let title: String = row.try_get("title")?;
             let rate_change: String = row.try_get("rate_change")?;
+            let penalty_minutes: Option<i64> = row.try_get("penalty_minutes")?;
+            let allows_late_join: Option<bool> = row.try_get("allows_late_join")?;
             Ok(Contest {
-                id,
-                start_epoch_second,
-                duration_second,
-                title,
-                rate_change,
+                penalty_minutes,
+                allows_late_join,
+                ..Contest::new(id, start_epoch_second, duration_second, title, rate_change)
             })
         })
         .fetch_all(self)
         .await?;
         Ok(contests)
     }
+
+    async fn update_derived_contest_columns(&self) -> Result<usize> {
+        let contests = self.load_contests().await?;
+        self.insert_contests(&contests).await
+    }
+
+    async fn update_contest_rules(
+        &self,
+        contest_id: &ContestId,
+        penalty_minutes: i64,
+        allows_late_join: bool,
+    ) -> Result<()> {
+        let result = sqlx::query(
+            r"
+            UPDATE contests
+            SET penalty_minutes = $2, allows_late_join = $3
+            WHERE id = $1
+            AND (penalty_minutes, allows_late_join) IS DISTINCT FROM ($2, $3)
+            ",
+        )
+        .bind(contest_id)
+        .bind(penalty_minutes)
+        .bind(allows_late_join)
+        .execute(self)
+        .await?;
+
+        if result.rows_affected() > 0 {
+            self.increment_data_version(CONTESTS_DATA).await?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,42 @@
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+/// The last submissions which the sinks sent in the order of `(epoch_second, id)`, so that they
+/// can send the submissions inserted while they were stopped after restarting.
+#[async_trait]
+pub trait SinkCursorClient {
+    async fn load_sink_cursor(&self, name: &str) -> Result<Option<(i64, i64)>>;
+    async fn save_sink_cursor(&self, name: &str, cursor: (i64, i64)) -> Result<()>;
+}
+
+#[async_trait]
+impl SinkCursorClient for PgPool {
+    async fn load_sink_cursor(&self, name: &str) -> Result<Option<(i64, i64)>> {
+        let cursor = sqlx::query("SELECT epoch_second, id FROM sink_cursors WHERE name = $1")
+            .bind(name)
+            .try_map(|row: PgRow| Ok((row.try_get("epoch_second")?, row.try_get("id")?)))
+            .fetch_optional(self)
+            .await?;
+        Ok(cursor)
+    }
+
+    async fn save_sink_cursor(&self, name: &str, (epoch_second, id): (i64, i64)) -> Result<()> {
+        sqlx::query(
+            r"
+            INSERT INTO sink_cursors (name, epoch_second, id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (name)
+            DO UPDATE SET epoch_second = EXCLUDED.epoch_second, id = EXCLUDED.id
+            ",
+        )
+        .bind(name)
+        .bind(epoch_second)
+        .bind(id)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+}
@@ -1,16 +1,27 @@
+use crate::ids::UserId;
 use crate::models::Submission;
 use crate::{PgPool, MAX_INSERT_ROWS};
 use anyhow::Result;
 use async_trait::async_trait;
 
-use chrono::Duration;
-use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Utc};
+use crate::time::JstDay;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
 use std::cmp;
 use std::collections::BTreeMap;
 
 #[async_trait]
 pub trait StreakUpdater {
     async fn update_streak_count(&self, submissions: &[Submission]) -> Result<()>;
+    /// Returns `None` if the user has no accepted submissions.
+    async fn get_users_max_streak(&self, user_id: &UserId) -> Result<Option<i64>>;
+    /// Returns the current streak of the user, or `None` if it is not alive since `alive_since`.
+    async fn get_users_current_streak(
+        &self,
+        user_id: &UserId,
+        alive_since: i64,
+    ) -> Result<Option<i64>>;
+    async fn get_max_streak_rank(&self, streak: i64) -> Result<i64>;
 }
 
 #[async_trait]
@@ -18,13 +29,7 @@ impl StreakUpdater for PgPool {
     async fn update_streak_count(&self, ac_submissions: &[Submission]) -> Result<()> {
         let mut submissions = ac_submissions
             .iter()
-            .map(|s| {
-                (
-                    Utc.timestamp(s.epoch_second, 0),
-                    s.user_id.as_str(),
-                    s.problem_id.as_str(),
-                )
-            })
+            .map(|s| (s.epoch_second, s.user_id.as_str(), s.problem_id.as_str()))
             .collect::<Vec<_>>();
         submissions.sort_by_key(|&(timestamp, _, _)| timestamp);
         let first_ac_map = submissions.into_iter().fold(
@@ -38,13 +43,22 @@ impl StreakUpdater for PgPool {
             },
         );
 
-        let user_max_streak = first_ac_map
+        let user_streaks = first_ac_map
             .into_iter()
             .map(|(user_id, m)| {
-                let max_streak = get_max_streak(m.into_iter().map(|(_, utc)| utc).collect());
-                (user_id, max_streak)
+                let first_acs = m
+                    .into_iter()
+                    .map(|(_, epoch_second)| epoch_second)
+                    .collect::<Vec<_>>();
+                let max_streak = get_max_streak(first_acs.clone());
+                let (current_streak, last_epoch_second) = get_current_streak(first_acs);
+                (user_id, max_streak, current_streak, last_epoch_second)
             })
             .collect::<Vec<_>>();
+        let user_max_streak = user_streaks
+            .iter()
+            .map(|&(user_id, max_streak, _, _)| (user_id, max_streak))
+            .collect::<Vec<_>>();
 
         for chunk in user_max_streak.chunks(MAX_INSERT_ROWS) {
             let (user_ids, max_streaks): (Vec<&str>, Vec<i64>) = chunk.iter().copied().unzip();
@@ -65,57 +79,126 @@ impl StreakUpdater for PgPool {
             .await?;
         }
 
+        for chunk in user_streaks.chunks(MAX_INSERT_ROWS) {
+            let user_ids = chunk.iter().map(|s| s.0).collect::<Vec<_>>();
+            let streaks = chunk.iter().map(|s| s.2).collect::<Vec<_>>();
+            let last_epoch_seconds = chunk.iter().map(|s| s.3).collect::<Vec<_>>();
+            sqlx::query(
+                r"
+                INSERT INTO current_streaks (user_id, streak, last_epoch_second)
+                VALUES (
+                    UNNEST($1::VARCHAR(255)[]),
+                    UNNEST($2::BIGINT[]),
+                    UNNEST($3::BIGINT[])
+                )
+                ON CONFLICT (user_id)
+                DO UPDATE SET streak = EXCLUDED.streak, last_epoch_second = EXCLUDED.last_epoch_second
+                ",
+            )
+            .bind(user_ids)
+            .bind(streaks)
+            .bind(last_epoch_seconds)
+            .execute(self)
+            .await?;
+        }
+
         Ok(())
     }
+
+    async fn get_users_max_streak(&self, user_id: &UserId) -> Result<Option<i64>> {
+        let streak = sqlx::query("SELECT streak FROM max_streaks WHERE user_id = $1")
+            .bind(user_id)
+            .try_map(|row: PgRow| row.try_get::<i64, _>("streak"))
+            .fetch_optional(self)
+            .await?;
+        Ok(streak)
+    }
+
+    async fn get_users_current_streak(
+        &self,
+        user_id: &UserId,
+        alive_since: i64,
+    ) -> Result<Option<i64>> {
+        let streak = sqlx::query(
+            "SELECT streak FROM current_streaks WHERE user_id = $1 AND last_epoch_second >= $2",
+        )
+        .bind(user_id)
+        .bind(alive_since)
+        .try_map(|row: PgRow| row.try_get::<i64, _>("streak"))
+        .fetch_optional(self)
+        .await?;
+        Ok(streak)
+    }
+
+    async fn get_max_streak_rank(&self, streak: i64) -> Result<i64> {
+        let rank = sqlx::query("SELECT COUNT(*) AS rank FROM max_streaks WHERE streak > $1")
+            .bind(streak)
+            .try_map(|row: PgRow| row.try_get::<i64, _>("rank"))
+            .fetch_one(self)
+            .await?;
+        Ok(rank)
+    }
 }
 
-fn get_max_streak<Tz: TimeZone>(mut v: Vec<DateTime<Tz>>) -> i64 {
-    v.sort();
-    let (_, max_streak) = (1..v.len()).fold((1, 1), |(current_streak, max_streak), i| {
-        if v[i - 1].is_same_day_in_jst(&v[i]) {
-            (current_streak, max_streak)
-        } else if (v[i - 1].clone() + Duration::days(1)).is_same_day_in_jst(&v[i]) {
-            (current_streak + 1, cmp::max(max_streak, current_streak + 1))
-        } else {
-            (1, max_streak)
+fn get_max_streak(mut v: Vec<i64>) -> i64 {
+    v.sort_unstable();
+    let mut current_streak = 1;
+    let mut max_streak = 1;
+    for i in 1..v.len() {
+        match days_between(v[i - 1], v[i]) {
+            0 => continue,
+            1 => current_streak += 1,
+            _ => current_streak = 1,
         }
-    });
+        max_streak = cmp::max(max_streak, current_streak);
+    }
     max_streak
 }
 
-trait AsJst {
-    fn as_jst(&self) -> DateTime<FixedOffset>;
-    fn is_same_day_in_jst<T: TimeZone>(&self, rhs: &DateTime<T>) -> bool {
-        let d1 = self.as_jst();
-        let d2 = rhs.as_jst();
-        d1.day() == d2.day() && d1.month() == d2.month() && d1.year() == d2.year()
+/// Returns the length of the last streak, and the epoch second of the last accepted submission in it.
+fn get_current_streak(mut v: Vec<i64>) -> (i64, i64) {
+    v.sort_unstable();
+    let last_epoch_second = v.last().copied().unwrap_or(0);
+    let mut streak = 1;
+    for i in (1..v.len()).rev() {
+        match days_between(v[i - 1], v[i]) {
+            0 => continue,
+            1 => streak += 1,
+            _ => break,
+        }
     }
+    (streak, last_epoch_second)
 }
 
-impl<Tz> AsJst for DateTime<Tz>
-where
-    Tz: TimeZone,
-{
-    fn as_jst(&self) -> DateTime<FixedOffset> {
-        self.with_timezone(&FixedOffset::east(9 * 3600))
-    }
+fn days_between(from_epoch_second: i64, to_epoch_second: i64) -> i64 {
+    JstDay::from_epoch_second(from_epoch_second)
+        .days_until(JstDay::from_epoch_second(to_epoch_second))
+}
+
+/// Returns the beginning of yesterday in JST. The last streak of a user is still alive at `now`
+/// if they got accepted after it, because they can extend the streak by solving a new problem today.
+pub fn current_streak_alive_since(now_epoch_second: i64) -> i64 {
+    JstDay::from_epoch_second(now_epoch_second)
+        .prev()
+        .start_epoch_second()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Datelike;
+    use chrono::{DateTime, Utc};
 
     #[test]
-    fn test_to_jst() {
-        let dt_10_04 = Utc.timestamp(1570114800, 0); //2019-10-04T00:00:00+09:00
-        assert_eq!(dt_10_04.as_jst().day(), 04);
-
-        let dt_10_03 = Utc.timestamp(1570114799, 0); //2019-10-03T23:59:59+09:00
-        assert_eq!(dt_10_03.as_jst().day(), 03);
-
-        let tomorrow = dt_10_03 + Duration::days(1);
-        assert!((tomorrow).is_same_day_in_jst(&dt_10_04));
+    fn test_day_boundary() {
+        // 2019-10-03T23:59:59+09:00 and 2019-10-04T00:00:00+09:00
+        assert_eq!(get_max_streak(vec![1570114799, 1570114800]), 2);
+        // 2019-10-04T00:00:00+09:00 and 2019-10-04T23:59:59+09:00
+        assert_eq!(get_max_streak(vec![1570114800, 1570201199]), 1);
+        // 2019-10-03T23:59:59+09:00 and 2019-10-05T00:00:00+09:00
+        assert_eq!(
+            get_current_streak(vec![1570114799, 1570201200]),
+            (1, 1570201200)
+        );
     }
 
     #[test]
@@ -135,9 +218,34 @@ mod tests {
             "2014-12-01T23:59:59+09:00",
         ]
         .into_iter()
-        .map(|s| s.parse::<DateTime<Utc>>().unwrap())
+        .map(|s| s.parse::<DateTime<Utc>>().unwrap().timestamp())
         .collect::<Vec<_>>();
-        let streak = get_max_streak(v);
+        let streak = get_max_streak(v.clone());
+        assert_eq!(streak, 4);
+
+        let (streak, last_epoch_second) = get_current_streak(v);
         assert_eq!(streak, 4);
+        assert_eq!(last_epoch_second, 1417705199); // 2014-12-04T23:59:59+09:00
+    }
+
+    #[test]
+    fn test_get_current_streak() {
+        let v = vec![
+            "2014-11-28T23:00:00+09:00",
+            "2014-11-29T00:00:00+09:00",
+            "2014-12-01T10:00:00+09:00",
+            "2014-12-01T12:00:00+09:00",
+        ]
+        .into_iter()
+        .map(|s| s.parse::<DateTime<Utc>>().unwrap().timestamp())
+        .collect::<Vec<_>>();
+        assert_eq!(get_current_streak(v), (1, 1417402800));
+    }
+
+    #[test]
+    fn test_current_streak_alive_since() {
+        // 2019-10-04T00:00:00+09:00 is 2019-10-03T15:00:00Z, when it is still 10-03 in UTC.
+        assert_eq!(current_streak_alive_since(1570114800), 1570028400);
+        assert_eq!(current_streak_alive_since(1570114799), 1569942000);
     }
 }
@@ -1,7 +1,9 @@
+use crate::ids::{ContestId, ProblemId, UserId};
 use crate::models::Submission;
 use crate::PgPool;
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
 use sqlx::postgres::PgRow;
 use sqlx::Row;
 use std::collections::BTreeMap;
@@ -10,19 +12,34 @@ const SUBMISSION_LIMIT: i64 = 10000;
 
 pub enum SubmissionRequest<'a> {
     UserAll {
-        user_id: &'a str,
+        user_id: &'a UserId,
     },
     UsersAccepted {
-        user_ids: &'a [&'a str],
+        user_ids: &'a [UserId],
     },
     FromTime {
         from_second: i64,
         count: i64,
     },
+    /// Submissions after the submission which has `from_second` and `from_id`,
+    /// in the order of `(epoch_second, id)`.
+    FromCursor {
+        from_second: i64,
+        from_id: i64,
+        count: i64,
+    },
     FromUserAndTime {
-        user_id: &'a str,
+        user_id: &'a UserId,
+        from_second: i64,
+        count: i64,
+    },
+    /// Submissions of the user after the submission which has `from_second` and `from_id`,
+    /// in the order of `(epoch_second, id)`.
+    FromUserAndCursor {
+        user_id: &'a UserId,
         from_second: i64,
-        count: usize,
+        from_id: i64,
+        count: i64,
     },
     RecentAccepted {
         count: i64,
@@ -38,8 +55,8 @@ pub enum SubmissionRequest<'a> {
         ids: &'a [i64],
     },
     UsersProblemsTime {
-        user_ids: &'a [&'a str],
-        problem_ids: &'a [&'a str],
+        user_ids: &'a [UserId],
+        problem_ids: &'a [ProblemId],
         from_second: i64,
         to_second: i64,
     },
@@ -48,10 +65,15 @@ pub enum SubmissionRequest<'a> {
 #[async_trait]
 pub trait SubmissionClient {
     async fn get_submissions<'a>(&self, request: SubmissionRequest<'a>) -> Result<Vec<Submission>>;
-    async fn get_user_submission_count(&self, user_id: &str) -> Result<i64>;
+    async fn get_user_submission_count(&self, user_id: &UserId) -> Result<i64>;
+    /// Returns the number of the stored submissions in the contest and the latest id of them.
+    async fn get_contest_submission_stats(
+        &self,
+        contest_id: &ContestId,
+    ) -> Result<(i64, Option<i64>)>;
     async fn update_submissions(&self, values: &[Submission]) -> Result<usize>;
     async fn update_submission_count(&self) -> Result<()>;
-    async fn update_user_submission_count(&self, user_id: &str) -> Result<()>;
+    async fn update_user_submission_count(&self, user_id: &UserId) -> Result<()>;
     async fn update_delta_submission_count(&self, values: &[Submission]) -> Result<()>;
 
     async fn count_stored_submissions(&self, ids: &[i64]) -> Result<usize> {
@@ -78,13 +100,29 @@ impl SubmissionClient for PgPool {
                 r"
                          SELECT * FROM submissions
                          WHERE epoch_second >= $1
-                         ORDER BY epoch_second ASC
+                         ORDER BY epoch_second ASC, id ASC
                          LIMIT $2
                          ",
             )
             .bind(from_second)
             .bind(count)
             .fetch_all(self),
+            SubmissionRequest::FromCursor {
+                from_second,
+                from_id,
+                count,
+            } => sqlx::query_as(
+                r"
+                         SELECT * FROM submissions
+                         WHERE (epoch_second, id) > ($1, $2)
+                         ORDER BY epoch_second ASC, id ASC
+                         LIMIT $3
+                         ",
+            )
+            .bind(from_second)
+            .bind(from_id)
+            .bind(count)
+            .fetch_all(self),
             SubmissionRequest::FromUserAndTime {
                 user_id,
                 from_second,
@@ -94,13 +132,32 @@ impl SubmissionClient for PgPool {
                          SELECT * FROM submissions
                          WHERE LOWER(user_id) = LOWER($1)
                          AND epoch_second >= $2
-                         ORDER BY epoch_second ASC
+                         ORDER BY epoch_second ASC, id ASC
                          LIMIT $3
                          ",
             )
             .bind(user_id)
             .bind(from_second)
-            .bind(count as i64)
+            .bind(count)
+            .fetch_all(self),
+            SubmissionRequest::FromUserAndCursor {
+                user_id,
+                from_second,
+                from_id,
+                count,
+            } => sqlx::query_as(
+                r"
+                         SELECT * FROM submissions
+                         WHERE LOWER(user_id) = LOWER($1)
+                         AND (epoch_second, id) > ($2, $3)
+                         ORDER BY epoch_second ASC, id ASC
+                         LIMIT $4
+                         ",
+            )
+            .bind(user_id)
+            .bind(from_second)
+            .bind(from_id)
+            .bind(count)
             .fetch_all(self),
             SubmissionRequest::RecentAccepted { count } => sqlx::query_as(
                 r"
@@ -128,7 +185,7 @@ impl SubmissionClient for PgPool {
                     AND user_id = ANY($1)
                     ",
             )
-            .bind(user_ids)
+            .bind(user_ids.iter().map(UserId::as_str).collect::<Vec<_>>())
             .fetch_all(self),
             SubmissionRequest::AllAccepted => sqlx::query_as(
                 r"
@@ -174,8 +231,13 @@ impl SubmissionClient for PgPool {
                     LIMIT $5
                     ",
             )
-            .bind(user_ids)
-            .bind(problem_ids)
+            .bind(user_ids.iter().map(UserId::as_str).collect::<Vec<_>>())
+            .bind(
+                problem_ids
+                    .iter()
+                    .map(ProblemId::as_str)
+                    .collect::<Vec<_>>(),
+            )
             .bind(from_second)
             .bind(to_second)
             .bind(SUBMISSION_LIMIT)
@@ -185,7 +247,7 @@ impl SubmissionClient for PgPool {
         Ok(submissions)
     }
 
-    async fn get_user_submission_count(&self, user_id: &str) -> Result<i64> {
+    async fn get_user_submission_count(&self, user_id: &UserId) -> Result<i64> {
         let count = sqlx::query(
             r"
             SELECT count FROM submission_count
@@ -199,6 +261,27 @@ impl SubmissionClient for PgPool {
         Ok(count)
     }
 
+    async fn get_contest_submission_stats(
+        &self,
+        contest_id: &ContestId,
+    ) -> Result<(i64, Option<i64>)> {
+        let stats = sqlx::query(
+            r"
+            SELECT COUNT(*) AS count, MAX(id) AS latest_id FROM submissions
+            WHERE contest_id = $1
+            ",
+        )
+        .bind(contest_id)
+        .try_map(|row: PgRow| {
+            let count: i64 = row.try_get("count")?;
+            let latest_id: Option<i64> = row.try_get("latest_id")?;
+            Ok((count, latest_id))
+        })
+        .fetch_one(self)
+        .await?;
+        Ok(stats)
+    }
+
     async fn update_submissions(&self, values: &[Submission]) -> Result<usize> {
         let (
             ids,
@@ -239,9 +322,9 @@ impl SubmissionClient for PgPool {
              cur| {
                 ids.push(cur.id);
                 epoch_seconds.push(cur.epoch_second);
-                problem_ids.push(cur.problem_id.clone());
-                contest_ids.push(cur.contest_id.clone());
-                user_ids.push(cur.user_id.clone());
+                problem_ids.push(cur.problem_id.as_str());
+                contest_ids.push(cur.contest_id.as_str());
+                user_ids.push(cur.user_id.as_str());
                 languages.push(cur.language.clone());
                 points.push(cur.point);
                 lengths.push(cur.length);
@@ -324,7 +407,7 @@ impl SubmissionClient for PgPool {
         Ok(())
     }
 
-    async fn update_user_submission_count(&self, user_id: &str) -> Result<()> {
+    async fn update_user_submission_count(&self, user_id: &UserId) -> Result<()> {
         sqlx::query(
             r"
                 INSERT INTO submission_count (user_id, count)
@@ -363,3 +446,30 @@ impl SubmissionClient for PgPool {
         Ok(())
     }
 }
+
+/// Streams all the submissions in the order of their ids. The rows are decoded as they arrive from
+/// the server, so the memory usage does not depend on the number of the submissions.
+pub fn stream_all_submissions(pool: &PgPool) -> BoxStream<'_, Result<Submission>> {
+    stream_submissions_after(pool, i64::MIN)
+}
+
+/// Streams the submissions of the user, the same ones as `SubmissionRequest::UserAll`.
+pub fn stream_user_submissions<'a>(
+    pool: &'a PgPool,
+    user_id: &'a UserId,
+) -> BoxStream<'a, Result<Submission>> {
+    sqlx::query_as::<_, Submission>("SELECT * FROM submissions WHERE LOWER(user_id) = LOWER($1)")
+        .bind(user_id)
+        .fetch(pool)
+        .map_err(anyhow::Error::from)
+        .boxed()
+}
+
+/// Streams the submissions whose ids are greater than `after_id` in the order of their ids.
+pub fn stream_submissions_after(pool: &PgPool, after_id: i64) -> BoxStream<'_, Result<Submission>> {
+    sqlx::query_as::<_, Submission>("SELECT * FROM submissions WHERE id > $1 ORDER BY id")
+        .bind(after_id)
+        .fetch(pool)
+        .map_err(anyhow::Error::from)
+        .boxed()
+}
@@ -0,0 +1,44 @@
+use crate::models::Submission;
+use crate::PgPool;
+use anyhow::Result;
+use async_std::channel::{self, Receiver};
+use async_std::task::{self, JoinHandle};
+use sqlx::postgres::PgListener;
+
+/// The channel which the trigger on the `submissions` table notifies.
+pub const SUBMISSION_CHANNEL: &str = "submissions";
+
+/// Receives the submissions inserted or updated by the crawlers.
+pub struct SubmissionListener {
+    listener: PgListener,
+}
+
+impl SubmissionListener {
+    pub async fn connect(pool: &PgPool) -> Result<Self> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(SUBMISSION_CHANNEL).await?;
+        Ok(Self { listener })
+    }
+
+    pub async fn recv(&mut self) -> Result<Submission> {
+        let notification = self.listener.recv().await?;
+        let submission = serde_json::from_str(notification.payload())?;
+        Ok(submission)
+    }
+
+    /// Receives the submissions on another task, so that they can be received with a timeout
+    /// without losing the notification being read. The task finishes with the error of the
+    /// listener, or when the receiver is dropped.
+    pub fn spawn(mut self) -> (Receiver<Submission>, JoinHandle<Result<()>>) {
+        let (sender, receiver) = channel::unbounded();
+        let handle = task::spawn(async move {
+            loop {
+                let submission = self.recv().await?;
+                if sender.send(submission).await.is_err() {
+                    return Ok(());
+                }
+            }
+        });
+        (receiver, handle)
+    }
+}
@@ -0,0 +1,168 @@
+//! Conversions between the epoch seconds and the days in JST, where the days of AtCoder begin,
+//! e.g. for the streaks, and the windows of the contests.
+
+use crate::models::Contest;
+use chrono::{Duration, NaiveDate};
+use std::fmt;
+
+pub const JST_OFFSET_SECOND: i64 = 9 * 3600;
+pub const DAY_SECOND: i64 = 24 * 3600;
+
+/// A day in JST, which begins at 15:00 UTC of the previous day.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct JstDay(i64);
+
+impl JstDay {
+    pub fn from_epoch_second(epoch_second: i64) -> Self {
+        Self((epoch_second + JST_OFFSET_SECOND).div_euclid(DAY_SECOND))
+    }
+
+    pub fn from_date(date: NaiveDate) -> Self {
+        Self((date - NaiveDate::from_ymd(1970, 1, 1)).num_days())
+    }
+
+    pub fn date(self) -> NaiveDate {
+        NaiveDate::from_ymd(1970, 1, 1) + Duration::days(self.0)
+    }
+
+    /// The epoch second of 00:00 of the day in JST.
+    pub fn start_epoch_second(self) -> i64 {
+        self.0 * DAY_SECOND - JST_OFFSET_SECOND
+    }
+
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+
+    pub fn prev(self) -> Self {
+        Self(self.0 - 1)
+    }
+
+    /// The number of the days from `self` to `other`, which is negative if `other` is earlier.
+    pub fn days_until(self, other: Self) -> i64 {
+        other.0 - self.0
+    }
+}
+
+impl fmt::Display for JstDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.date())
+    }
+}
+
+/// The time when a contest is held, which includes the start and excludes the end.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ContestWindow {
+    pub start_epoch_second: i64,
+    pub end_epoch_second: i64,
+}
+
+impl ContestWindow {
+    pub fn new(start_epoch_second: i64, duration_second: i64) -> Self {
+        Self {
+            start_epoch_second,
+            end_epoch_second: start_epoch_second + duration_second,
+        }
+    }
+
+    pub fn of(contest: &Contest) -> Self {
+        Self::new(contest.start_epoch_second, contest.duration_second)
+    }
+
+    pub fn has_started(&self, epoch_second: i64) -> bool {
+        self.start_epoch_second <= epoch_second
+    }
+
+    pub fn has_ended(&self, epoch_second: i64) -> bool {
+        self.end_epoch_second <= epoch_second
+    }
+
+    pub fn is_running(&self, epoch_second: i64) -> bool {
+        self.has_started(epoch_second) && !self.has_ended(epoch_second)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn epoch_second(rfc3339: &str) -> i64 {
+        rfc3339.parse::<DateTime<Utc>>().unwrap().timestamp()
+    }
+
+    fn day(date: &str) -> JstDay {
+        JstDay::from_date(date.parse().unwrap())
+    }
+
+    #[test]
+    fn test_day_boundary() {
+        let cases = [
+            ("2019-10-03T23:59:59+09:00", "2019-10-03"),
+            ("2019-10-04T00:00:00+09:00", "2019-10-04"),
+            ("2019-10-04T08:59:59+09:00", "2019-10-04"),
+            ("2019-10-04T09:00:00+09:00", "2019-10-04"),
+            ("2019-10-04T23:59:59+09:00", "2019-10-04"),
+            // The midnight in JST is 15:00 in UTC of the previous day.
+            ("2019-10-03T14:59:59Z", "2019-10-03"),
+            ("2019-10-03T15:00:00Z", "2019-10-04"),
+            // The year and the leap day.
+            ("2019-12-31T23:59:59+09:00", "2019-12-31"),
+            ("2020-01-01T00:00:00+09:00", "2020-01-01"),
+            ("2020-02-29T00:00:00+09:00", "2020-02-29"),
+            ("2020-03-01T00:00:00+09:00", "2020-03-01"),
+            // Before the epoch in JST.
+            ("1970-01-01T00:00:00Z", "1970-01-01"),
+            ("1969-12-31T14:59:59Z", "1969-12-31"),
+            ("1969-12-31T15:00:00Z", "1970-01-01"),
+        ];
+        for &(time, date) in cases.iter() {
+            let actual = JstDay::from_epoch_second(epoch_second(time));
+            assert_eq!(actual, day(date), "{}", time);
+            assert_eq!(actual.to_string(), date);
+        }
+    }
+
+    #[test]
+    fn test_start_epoch_second() {
+        for &date in ["1969-12-31", "1970-01-01", "2019-10-04", "2020-02-29"].iter() {
+            let start = day(date).start_epoch_second();
+            assert_eq!(start, epoch_second(&format!("{}T00:00:00+09:00", date)));
+            assert_eq!(JstDay::from_epoch_second(start), day(date));
+            assert_eq!(JstDay::from_epoch_second(start - 1), day(date).prev());
+            assert_eq!(JstDay::from_epoch_second(start + DAY_SECOND - 1), day(date));
+            assert_eq!(
+                JstDay::from_epoch_second(start + DAY_SECOND),
+                day(date).next()
+            );
+        }
+    }
+
+    #[test]
+    fn test_days_until() {
+        assert_eq!(day("2020-02-28").days_until(day("2020-03-01")), 2);
+        assert_eq!(day("2021-02-28").days_until(day("2021-03-01")), 1);
+        assert_eq!(day("2020-01-01").days_until(day("2019-12-31")), -1);
+        assert_eq!(day("2020-01-01").next().prev(), day("2020-01-01"));
+        assert!(day("2019-12-31") < day("2020-01-01"));
+    }
+
+    #[test]
+    fn test_contest_window() {
+        let window = ContestWindow::new(1000, 100);
+        assert_eq!(window.end_epoch_second, 1100);
+
+        assert!(!window.has_started(999));
+        assert!(!window.is_running(999));
+        assert!(window.has_started(1000));
+        assert!(window.is_running(1000));
+        assert!(window.is_running(1099));
+        assert!(!window.has_ended(1099));
+        assert!(window.has_ended(1100));
+        assert!(!window.is_running(1100));
+
+        let window = ContestWindow::new(1000, 0);
+        assert!(!window.is_running(1000));
+        assert!(window.has_ended(1000));
+    }
+}
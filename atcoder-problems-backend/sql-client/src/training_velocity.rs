@@ -0,0 +1,319 @@
+use crate::ids::{ProblemId, UserId};
+use crate::models::{Submission, TrainingVelocity};
+use crate::rated_point_sum::load_rated_problem_ids;
+use crate::time::DAY_SECOND;
+use crate::{PgPool, MAX_INSERT_ROWS};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::collections::{BTreeMap, BTreeSet};
+
+pub const TRAINING_VELOCITY_PERIOD_DAYS: [i64; 3] = [7, 30, 90];
+
+/// Solved problems are classified by their difficulties in steps of 400, the same as the rating colors.
+pub const DIFFICULTY_LEVEL_WIDTH: f64 = 400.0;
+pub const DIFFICULTY_LEVEL_COUNT: usize = 8;
+/// The names of the difficulty levels, which are the colors of the ratings.
+pub const DIFFICULTY_LEVEL_NAMES: [&str; DIFFICULTY_LEVEL_COUNT] = [
+    "Gray", "Brown", "Green", "Cyan", "Blue", "Yellow", "Orange", "Red",
+];
+
+#[async_trait]
+pub trait TrainingVelocityClient {
+    async fn update_training_velocity(
+        &self,
+        ac_submissions: &[Submission],
+        difficulties: &BTreeMap<String, f64>,
+        current_time_second: i64,
+    ) -> Result<()>;
+    async fn load_training_velocity(&self, user_id: &UserId) -> Result<Vec<TrainingVelocity>>;
+}
+
+#[async_trait]
+impl TrainingVelocityClient for PgPool {
+    async fn update_training_velocity(
+        &self,
+        ac_submissions: &[Submission],
+        difficulties: &BTreeMap<String, f64>,
+        current_time_second: i64,
+    ) -> Result<()> {
+        let rated_problem_ids = load_rated_problem_ids(self).await?;
+        let user_ids = ac_submissions
+            .iter()
+            .map(|s| s.user_id.as_str())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let velocities = compute_training_velocity(
+            ac_submissions,
+            &rated_problem_ids,
+            difficulties,
+            current_time_second,
+        );
+        let difficulty_counts = velocities
+            .iter()
+            .flat_map(|v| {
+                v.difficulty_counts
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &count)| count > 0)
+                    .map(move |(level, &count)| (v, level as i32, count))
+            })
+            .collect::<Vec<_>>();
+
+        let mut tx = self.begin().await?;
+        for chunk in user_ids.chunks(MAX_INSERT_ROWS) {
+            sqlx::query("DELETE FROM training_velocity WHERE user_id = ANY($1)")
+                .bind(chunk)
+                .execute(&mut tx)
+                .await?;
+            sqlx::query("DELETE FROM recent_difficulty_count WHERE user_id = ANY($1)")
+                .bind(chunk)
+                .execute(&mut tx)
+                .await?;
+        }
+
+        for chunk in velocities.chunks(MAX_INSERT_ROWS) {
+            let (user_ids, period_days, accepted_counts, rated_point_sums) = chunk.iter().fold(
+                (vec![], vec![], vec![], vec![]),
+                |(mut user_ids, mut period_days, mut accepted_counts, mut rated_point_sums),
+                 cur| {
+                    user_ids.push(cur.user_id.as_str());
+                    period_days.push(cur.period_days);
+                    accepted_counts.push(cur.accepted_count);
+                    rated_point_sums.push(cur.rated_point_sum);
+                    (user_ids, period_days, accepted_counts, rated_point_sums)
+                },
+            );
+            sqlx::query(
+                r"
+                INSERT INTO training_velocity (user_id, period_days, accepted_count, rated_point_sum)
+                VALUES (
+                    UNNEST($1::VARCHAR(255)[]),
+                    UNNEST($2::BIGINT[]),
+                    UNNEST($3::BIGINT[]),
+                    UNNEST($4::FLOAT8[])
+                )
+                ",
+            )
+            .bind(user_ids)
+            .bind(period_days)
+            .bind(accepted_counts)
+            .bind(rated_point_sums)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        for chunk in difficulty_counts.chunks(MAX_INSERT_ROWS) {
+            let (user_ids, period_days, difficulty_levels, problem_counts) = chunk.iter().fold(
+                (vec![], vec![], vec![], vec![]),
+                |(mut user_ids, mut period_days, mut difficulty_levels, mut problem_counts),
+                 &(velocity, level, count)| {
+                    user_ids.push(velocity.user_id.as_str());
+                    period_days.push(velocity.period_days);
+                    difficulty_levels.push(level);
+                    problem_counts.push(count);
+                    (user_ids, period_days, difficulty_levels, problem_counts)
+                },
+            );
+            sqlx::query(
+                r"
+                INSERT INTO recent_difficulty_count
+                (user_id, period_days, difficulty_level, problem_count)
+                VALUES (
+                    UNNEST($1::VARCHAR(255)[]),
+                    UNNEST($2::BIGINT[]),
+                    UNNEST($3::INTEGER[]),
+                    UNNEST($4::BIGINT[])
+                )
+                ",
+            )
+            .bind(user_ids)
+            .bind(period_days)
+            .bind(difficulty_levels)
+            .bind(problem_counts)
+            .execute(&mut tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn load_training_velocity(&self, user_id: &UserId) -> Result<Vec<TrainingVelocity>> {
+        let mut velocities = sqlx::query(
+            r"
+            SELECT user_id, period_days, accepted_count, rated_point_sum
+            FROM training_velocity
+            WHERE user_id = $1
+            ORDER BY period_days
+            ",
+        )
+        .bind(user_id)
+        .try_map(|row: PgRow| {
+            let user_id: String = row.try_get("user_id")?;
+            let period_days: i64 = row.try_get("period_days")?;
+            let accepted_count: i64 = row.try_get("accepted_count")?;
+            let rated_point_sum: f64 = row.try_get("rated_point_sum")?;
+            Ok(TrainingVelocity {
+                user_id,
+                period_days,
+                accepted_count,
+                rated_point_sum,
+                difficulty_counts: vec![0; DIFFICULTY_LEVEL_COUNT],
+            })
+        })
+        .fetch_all(self)
+        .await?;
+
+        let difficulty_counts = sqlx::query(
+            r"
+            SELECT period_days, difficulty_level, problem_count
+            FROM recent_difficulty_count
+            WHERE user_id = $1
+            ",
+        )
+        .bind(user_id)
+        .try_map(|row: PgRow| {
+            let period_days: i64 = row.try_get("period_days")?;
+            let difficulty_level: i32 = row.try_get("difficulty_level")?;
+            let problem_count: i64 = row.try_get("problem_count")?;
+            Ok((period_days, difficulty_level as usize, problem_count))
+        })
+        .fetch_all(self)
+        .await?;
+
+        for (period_days, level, count) in difficulty_counts.into_iter() {
+            if let Some(velocity) = velocities.iter_mut().find(|v| v.period_days == period_days) {
+                if let Some(c) = velocity.difficulty_counts.get_mut(level) {
+                    *c = count;
+                }
+            }
+        }
+        Ok(velocities)
+    }
+}
+
+fn compute_training_velocity(
+    ac_submissions: &[Submission],
+    rated_problem_ids: &BTreeSet<ProblemId>,
+    difficulties: &BTreeMap<String, f64>,
+    current_time_second: i64,
+) -> Vec<TrainingVelocity> {
+    let mut first_ac_map = BTreeMap::new();
+    for s in ac_submissions {
+        let first_ac = first_ac_map
+            .entry(s.user_id.as_str())
+            .or_insert_with(BTreeMap::new)
+            .entry(s.problem_id.as_str())
+            .or_insert(s);
+        if (s.epoch_second, s.id) < (first_ac.epoch_second, first_ac.id) {
+            *first_ac = s;
+        }
+    }
+
+    let mut velocities = Vec::new();
+    for (user_id, first_acs) in first_ac_map.into_iter() {
+        for &period_days in TRAINING_VELOCITY_PERIOD_DAYS.iter() {
+            let from_second = current_time_second - period_days * DAY_SECOND;
+            let recent_acs = first_acs
+                .values()
+                .filter(|s| from_second <= s.epoch_second && s.epoch_second <= current_time_second)
+                .collect::<Vec<_>>();
+            if recent_acs.is_empty() {
+                continue;
+            }
+
+            let rated_point_sum = recent_acs
+                .iter()
+                .filter(|s| rated_problem_ids.contains(&s.problem_id))
+                .map(|s| s.point)
+                .sum::<f64>();
+            let mut difficulty_counts = vec![0; DIFFICULTY_LEVEL_COUNT];
+            for s in recent_acs.iter() {
+                if let Some(&difficulty) = difficulties.get(s.problem_id.as_str()) {
+                    difficulty_counts[difficulty_level(difficulty)] += 1;
+                }
+            }
+            velocities.push(TrainingVelocity {
+                user_id: user_id.to_owned(),
+                period_days,
+                accepted_count: recent_acs.len() as i64,
+                rated_point_sum,
+                difficulty_counts,
+            });
+        }
+    }
+    velocities
+}
+
+pub fn difficulty_level(difficulty: f64) -> usize {
+    let level = (difficulty / DIFFICULTY_LEVEL_WIDTH).floor().max(0.0) as usize;
+    level.min(DIFFICULTY_LEVEL_COUNT - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(id: i64, epoch_second: i64, problem_id: &str, point: f64) -> Submission {
+        Submission {
+            id,
+            epoch_second,
+            problem_id: problem_id.into(),
+            user_id: "user".into(),
+            point,
+            result: "AC".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_difficulty_level() {
+        assert_eq!(difficulty_level(-500.0), 0);
+        assert_eq!(difficulty_level(399.9), 0);
+        assert_eq!(difficulty_level(400.0), 1);
+        assert_eq!(difficulty_level(2799.0), 6);
+        assert_eq!(difficulty_level(4000.0), 7);
+    }
+
+    #[test]
+    fn test_compute_training_velocity() {
+        let now = 100 * DAY_SECOND;
+        let submissions = [
+            submission(1, now - DAY_SECOND, "abc001_a", 100.0),
+            // solved before, so it is not a new AC in the recent days.
+            submission(2, now - 100 * DAY_SECOND, "abc001_b", 200.0),
+            submission(3, now - 2 * DAY_SECOND, "abc001_b", 200.0),
+            submission(4, now - 10 * DAY_SECOND, "abc002_c", 300.0),
+            submission(5, now - 60 * DAY_SECOND, "arc001_d", 400.0),
+        ];
+        let rated_problem_ids = ["abc001_a", "abc001_b", "abc002_c"]
+            .iter()
+            .map(|&s| ProblemId::from(s))
+            .collect::<BTreeSet<_>>();
+        let difficulties = [("abc001_a", -100.0), ("abc002_c", 1300.0)]
+            .iter()
+            .map(|&(problem_id, difficulty)| (problem_id.to_owned(), difficulty))
+            .collect::<BTreeMap<_, _>>();
+
+        let velocities =
+            compute_training_velocity(&submissions, &rated_problem_ids, &difficulties, now);
+        assert_eq!(
+            velocities
+                .iter()
+                .map(|v| (v.period_days, v.accepted_count, v.rated_point_sum))
+                .collect::<Vec<_>>(),
+            vec![(7, 1, 100.0), (30, 2, 400.0), (90, 3, 400.0)]
+        );
+        assert_eq!(
+            velocities[0].difficulty_counts,
+            vec![1, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            velocities[1].difficulty_counts,
+            vec![1, 0, 0, 1, 0, 0, 0, 0]
+        );
+    }
+}
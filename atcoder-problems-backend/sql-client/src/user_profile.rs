@@ -0,0 +1,179 @@
+use crate::ids::UserId;
+use crate::models::UserProfile;
+use crate::{PgPool, MAX_INSERT_ROWS};
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+#[async_trait]
+pub trait UserProfileClient {
+    async fn insert_user_profiles(&self, profiles: &[UserProfile]) -> Result<usize>;
+    async fn get_user_profile(&self, user_id: &UserId) -> Result<Option<UserProfile>>;
+
+    /// Returns the ratings and the numbers of the rated contests of the users in their crawled
+    /// profiles, where the users who have never joined a rated contest have `(0, 0)`. The users
+    /// whose profiles or numbers of the rated contests are not crawled yet are omitted.
+    async fn load_ratings_with_competitions(
+        &self,
+        user_ids: &[UserId],
+    ) -> Result<Vec<(String, i64, i64)>>;
+
+    /// Returns up to `count` users who have accepted submissions, whose profiles have never been
+    /// crawled first, and then whose profiles were crawled the longest ago.
+    async fn load_user_ids_to_crawl_profiles(&self, count: i64) -> Result<Vec<String>>;
+}
+
+#[async_trait]
+impl UserProfileClient for PgPool {
+    async fn insert_user_profiles(&self, profiles: &[UserProfile]) -> Result<usize> {
+        let mut rows = 0;
+        for chunk in profiles.chunks(MAX_INSERT_ROWS) {
+            let (
+                user_ids,
+                ratings,
+                highest_ratings,
+                affiliations,
+                countries,
+                birth_year_buckets,
+                rated_matches,
+            ) = chunk.iter().fold(
+                (vec![], vec![], vec![], vec![], vec![], vec![], vec![]),
+                |(
+                    mut user_ids,
+                    mut ratings,
+                    mut highest_ratings,
+                    mut affiliations,
+                    mut countries,
+                    mut birth_year_buckets,
+                    mut rated_matches,
+                ),
+                 cur| {
+                    user_ids.push(cur.user_id.as_str());
+                    ratings.push(cur.rating);
+                    highest_ratings.push(cur.highest_rating);
+                    affiliations.push(cur.affiliation.as_deref());
+                    countries.push(cur.country.as_deref());
+                    birth_year_buckets.push(cur.birth_year_bucket);
+                    rated_matches.push(cur.rated_matches);
+                    (
+                        user_ids,
+                        ratings,
+                        highest_ratings,
+                        affiliations,
+                        countries,
+                        birth_year_buckets,
+                        rated_matches,
+                    )
+                },
+            );
+            let result = sqlx::query(
+                r"
+                INSERT INTO users
+                (user_id, rating, highest_rating, affiliation, country, birth_year_bucket,
+                 rated_matches)
+                VALUES (
+                    UNNEST($1::VARCHAR(255)[]),
+                    UNNEST($2::BIGINT[]),
+                    UNNEST($3::BIGINT[]),
+                    UNNEST($4::VARCHAR(255)[]),
+                    UNNEST($5::VARCHAR(255)[]),
+                    UNNEST($6::INT[]),
+                    UNNEST($7::BIGINT[])
+                )
+                ON CONFLICT (user_id)
+                DO UPDATE SET
+                    rating = EXCLUDED.rating,
+                    highest_rating = EXCLUDED.highest_rating,
+                    affiliation = EXCLUDED.affiliation,
+                    country = EXCLUDED.country,
+                    birth_year_bucket = EXCLUDED.birth_year_bucket,
+                    rated_matches = EXCLUDED.rated_matches,
+                    updated_epoch_second = EXTRACT(EPOCH FROM NOW())
+                ",
+            )
+            .bind(user_ids)
+            .bind(ratings)
+            .bind(highest_ratings)
+            .bind(affiliations)
+            .bind(countries)
+            .bind(birth_year_buckets)
+            .bind(rated_matches)
+            .execute(self)
+            .await?;
+            rows += result.rows_affected() as usize;
+        }
+        Ok(rows)
+    }
+
+    async fn get_user_profile(&self, user_id: &UserId) -> Result<Option<UserProfile>> {
+        let profile = sqlx::query(
+            r"
+            SELECT user_id, rating, highest_rating, affiliation, country, birth_year_bucket,
+                rated_matches
+            FROM users
+            WHERE user_id = $1
+            ",
+        )
+        .bind(user_id)
+        .try_map(|row: PgRow| {
+            Ok(UserProfile {
+                user_id: row.try_get("user_id")?,
+                rating: row.try_get("rating")?,
+                highest_rating: row.try_get("highest_rating")?,
+                affiliation: row.try_get("affiliation")?,
+                country: row.try_get("country")?,
+                birth_year_bucket: row.try_get("birth_year_bucket")?,
+                rated_matches: row.try_get("rated_matches")?,
+            })
+        })
+        .fetch_optional(self)
+        .await?;
+        Ok(profile)
+    }
+
+    async fn load_ratings_with_competitions(
+        &self,
+        user_ids: &[UserId],
+    ) -> Result<Vec<(String, i64, i64)>> {
+        let ratings = sqlx::query(
+            r"
+            SELECT
+                user_id,
+                COALESCE(rating, 0) AS rating,
+                COALESCE(rated_matches, 0) AS competitions
+            FROM users
+            WHERE user_id = ANY($1)
+            AND (rating IS NULL OR rated_matches IS NOT NULL)
+            ORDER BY user_id
+            ",
+        )
+        .bind(user_ids.iter().map(UserId::as_str).collect::<Vec<_>>())
+        .try_map(|row: PgRow| {
+            let user_id: String = row.try_get("user_id")?;
+            let rating: i64 = row.try_get("rating")?;
+            let competitions: i64 = row.try_get("competitions")?;
+            Ok((user_id, rating, competitions))
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(ratings)
+    }
+
+    async fn load_user_ids_to_crawl_profiles(&self, count: i64) -> Result<Vec<String>> {
+        let user_ids = sqlx::query(
+            r"
+            SELECT accepted_count.user_id
+            FROM accepted_count
+            LEFT JOIN users ON users.user_id = accepted_count.user_id
+            ORDER BY users.updated_epoch_second NULLS FIRST, accepted_count.problem_count DESC
+            LIMIT $1
+            ",
+        )
+        .bind(count)
+        .try_map(|row: PgRow| row.try_get::<String, _>("user_id"))
+        .fetch_all(self)
+        .await?;
+        Ok(user_ids)
+    }
+}
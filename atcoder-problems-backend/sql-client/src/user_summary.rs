@@ -0,0 +1,51 @@
+use crate::models::UserSummary;
+use crate::PgPool;
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+
+#[async_trait]
+pub trait UserSummaryClient {
+    /// Loads the summaries of all the users who have got accepted, in the order of the accepted
+    /// count.
+    async fn load_user_summaries(&self) -> Result<Vec<UserSummary>>;
+}
+
+#[async_trait]
+impl UserSummaryClient for PgPool {
+    async fn load_user_summaries(&self) -> Result<Vec<UserSummary>> {
+        // Every user in rated_point_sum and max_streaks is in accepted_count, so the ranks are
+        // the same as the ones counted in each table.
+        let summaries = sqlx::query(
+            r"
+            SELECT
+                a.user_id,
+                a.problem_count AS accepted_count,
+                RANK() OVER (ORDER BY a.problem_count DESC) - 1 AS accepted_count_rank,
+                COALESCE(r.point_sum, 0) AS rated_point_sum,
+                RANK() OVER (ORDER BY COALESCE(r.point_sum, 0) DESC) - 1 AS rated_point_sum_rank,
+                COALESCE(s.streak, 0) AS max_streak,
+                RANK() OVER (ORDER BY COALESCE(s.streak, 0) DESC) - 1 AS max_streak_rank
+            FROM accepted_count AS a
+            LEFT JOIN rated_point_sum AS r ON r.user_id = a.user_id
+            LEFT JOIN max_streaks AS s ON s.user_id = a.user_id
+            ORDER BY a.problem_count DESC, a.user_id ASC
+            ",
+        )
+        .try_map(|row: PgRow| {
+            Ok(UserSummary {
+                user_id: row.try_get("user_id")?,
+                accepted_count: row.try_get("accepted_count")?,
+                accepted_count_rank: row.try_get("accepted_count_rank")?,
+                rated_point_sum: row.try_get("rated_point_sum")?,
+                rated_point_sum_rank: row.try_get("rated_point_sum_rank")?,
+                max_streak: row.try_get("max_streak")?,
+                max_streak_rank: row.try_get("max_streak_rank")?,
+            })
+        })
+        .fetch_all(self)
+        .await?;
+        Ok(summaries)
+    }
+}
@@ -1,4 +1,5 @@
 use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::ids::UserId;
 use sql_client::models::{Submission, UserProblemCount};
 
 mod utils;
@@ -9,38 +10,38 @@ async fn test_accepted_count() {
     let submissions = [
         Submission {
             id: 1,
-            user_id: "user1".to_owned(),
-            problem_id: "problem1".to_owned(),
+            user_id: "user1".into(),
+            problem_id: "problem1".into(),
             ..Default::default()
         },
         Submission {
             id: 2,
-            user_id: "user1".to_owned(),
-            problem_id: "problem1".to_owned(),
+            user_id: "user1".into(),
+            problem_id: "problem1".into(),
             ..Default::default()
         },
         Submission {
             id: 3,
-            user_id: "user1".to_owned(),
-            problem_id: "problem2".to_owned(),
+            user_id: "user1".into(),
+            problem_id: "problem2".into(),
             ..Default::default()
         },
         Submission {
             id: 4,
-            user_id: "user2".to_owned(),
-            problem_id: "problem1".to_owned(),
+            user_id: "user2".into(),
+            problem_id: "problem1".into(),
             ..Default::default()
         },
         Submission {
             id: 5,
-            user_id: "user2".to_owned(),
-            problem_id: "problem2".to_owned(),
+            user_id: "user2".into(),
+            problem_id: "problem2".into(),
             ..Default::default()
         },
         Submission {
             id: 6,
-            user_id: "user2".to_owned(),
-            problem_id: "problem3".to_owned(),
+            user_id: "user2".into(),
+            problem_id: "problem3".into(),
             ..Default::default()
         },
     ];
@@ -93,13 +94,24 @@ async fn test_accepted_count() {
         2
     );
 
-    assert_eq!(pool.get_users_accepted_count("user1").await.unwrap(), 2);
-    assert_eq!(pool.get_users_accepted_count("user2").await.unwrap(), 3);
+    assert_eq!(
+        pool.get_users_accepted_count(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        Some(2)
+    );
+    assert_eq!(
+        pool.get_users_accepted_count(&UserId::from("user2"))
+            .await
+            .unwrap(),
+        Some(3)
+    );
     assert_eq!(pool.get_accepted_count_rank(3).await.unwrap(), 0);
     assert_eq!(pool.get_accepted_count_rank(2).await.unwrap(), 1);
 
     assert!(pool
-        .get_users_accepted_count("non_existing_user")
+        .get_users_accepted_count(&UserId::from("non_existing_user"))
         .await
+        .unwrap()
         .is_none());
 }
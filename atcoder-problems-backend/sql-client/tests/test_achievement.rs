@@ -0,0 +1,63 @@
+use sql_client::achievement::AchievementClient;
+use sql_client::ids::UserId;
+use sql_client::models::{Achievement, ContestProblem, Submission};
+
+mod utils;
+
+#[async_std::test]
+async fn test_update_achievements() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let contest_problems = [ContestProblem {
+        contest_id: "agc001".into(),
+        problem_id: "agc001_a".into(),
+    }];
+    let submissions = [
+        Submission {
+            id: 1,
+            epoch_second: 200,
+            user_id: "user1".into(),
+            problem_id: "agc001_a".into(),
+            contest_id: "agc001".into(),
+            ..Default::default()
+        },
+        Submission {
+            id: 2,
+            epoch_second: 100,
+            user_id: "user1".into(),
+            problem_id: "agc001_a".into(),
+            contest_id: "agc001".into(),
+            ..Default::default()
+        },
+    ];
+    pool.update_achievements(&submissions, &contest_problems)
+        .await
+        .unwrap();
+
+    let expected = vec![Achievement {
+        user_id: "user1".to_owned(),
+        achievement_id: "first_agc_accepted".to_owned(),
+        achieved_epoch_second: 100,
+    }];
+    assert_eq!(
+        pool.load_achievements(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        expected
+    );
+
+    // The time of an achievement is never overwritten by a later one.
+    pool.update_achievements(&submissions[..1], &contest_problems)
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.load_achievements(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        expected
+    );
+    assert!(pool
+        .load_achievements(&UserId::from("user2"))
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,64 @@
+use sql_client::activity::{ActivityClient, ParseFailure};
+
+mod utils;
+
+#[async_std::test]
+async fn test_count_submissions_crawled_between() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"
+        INSERT INTO submissions
+            (id, epoch_second, problem_id, contest_id, user_id, language, point, length, result,
+             crawled_epoch_second)
+        VALUES
+            (1, 100, 'problem1', 'contest1', 'user1', 'language1', 1.0, 1, 'AC', 1000),
+            (2, 200, 'problem1', 'contest1', 'user2', 'language1', 1.0, 1, 'AC', 1000),
+            (3, 300, 'problem1', 'contest1', 'user1', 'language1', 1.0, 1, 'WA', 1100);
+    ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    // The submissions are counted when they are crawled, not when they are submitted.
+    assert_eq!(
+        pool.count_submissions_crawled_between(0, 500)
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        pool.count_submissions_crawled_between(1000, 1100)
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        pool.count_submissions_crawled_between(1000, 2000)
+            .await
+            .unwrap(),
+        3
+    );
+}
+
+#[async_std::test]
+async fn test_parse_failures() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_parse_failures(&[]).await.unwrap();
+    let failure = |epoch_second: i64| ParseFailure {
+        url: "https://atcoder.jp/contests/abc001/tasks".to_owned(),
+        error: "Failed to parse html.".to_owned(),
+        epoch_second,
+    };
+    pool.insert_parse_failures(&[failure(100), failure(200), failure(300)])
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.count_parse_failures_between(100, 300).await.unwrap(),
+        2
+    );
+    assert_eq!(
+        pool.count_parse_failures_between(400, 500).await.unwrap(),
+        0
+    );
+}
@@ -0,0 +1,78 @@
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::aggregation::{load_rankings, write_rankings};
+use sql_client::ids::UserId;
+use sql_client::models::{Submission, UserProblemCount};
+use sql_client::submission_client::SubmissionClient;
+
+mod utils;
+
+fn submission(id: i64, user_id: &str, problem_id: &str, result: &str) -> Submission {
+    Submission {
+        id,
+        epoch_second: id * 100,
+        user_id: user_id.into(),
+        problem_id: problem_id.into(),
+        contest_id: "contest".into(),
+        result: result.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn test_load_and_write_rankings() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.update_submissions(&[
+        submission(1, "user1", "problem1", "WA"),
+        submission(2, "user1", "problem1", "AC"),
+        submission(3, "user1", "problem2", "AC"),
+        submission(4, "user2", "problem1", "AC"),
+        submission(5, "user3", "problem1", "TLE"),
+    ])
+    .await
+    .unwrap();
+
+    let rankings = load_rankings(&pool).await.unwrap();
+    assert_eq!(
+        rankings.submission_count,
+        vec![
+            ("user1".to_owned(), 3),
+            ("user2".to_owned(), 1),
+            ("user3".to_owned(), 1)
+        ]
+    );
+    assert_eq!(
+        rankings.last_accepted,
+        vec![("problem1".to_owned(), 400), ("problem2".to_owned(), 300)]
+    );
+
+    let url = std::env::var("SQL_URL").unwrap();
+    let rows = write_rankings(&url, &rankings).unwrap();
+    // There is no rated problem, so rated_point_sum is empty.
+    assert_eq!(rows, 9);
+    assert_eq!(
+        pool.load_accepted_count().await.unwrap(),
+        vec![
+            UserProblemCount {
+                user_id: "user1".to_owned(),
+                problem_count: 2,
+            },
+            UserProblemCount {
+                user_id: "user2".to_owned(),
+                problem_count: 1,
+            },
+        ]
+    );
+
+    // The existing rows are updated.
+    pool.update_submissions(&[submission(6, "user2", "problem2", "AC")])
+        .await
+        .unwrap();
+    let rankings = load_rankings(&pool).await.unwrap();
+    write_rankings(&url, &rankings).unwrap();
+    assert_eq!(
+        pool.get_users_accepted_count(&UserId::from("user2"))
+            .await
+            .unwrap(),
+        Some(2)
+    );
+}
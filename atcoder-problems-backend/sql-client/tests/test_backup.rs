@@ -0,0 +1,237 @@
+use sql_client::backup::{CopyFormat, Loader, Snapshot, SubmissionArchiver};
+use sql_client::models::{Contest, Submission};
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::SubmissionClient;
+use sqlx::Row;
+
+mod utils;
+
+fn contest(id: &str) -> Contest {
+    Contest {
+        id: id.into(),
+        start_epoch_second: 100,
+        duration_second: 200,
+        title: "title\nwith a newline".to_owned(),
+        rate_change: "-".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn test_snapshot() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_contests(&[contest("abc001"), contest("abc002")])
+        .await
+        .unwrap();
+
+    let url = std::env::var("SQL_URL").unwrap();
+    let mut snapshot = Snapshot::begin(&url).unwrap();
+    let tables = snapshot.tables().unwrap();
+    assert!(tables.contains(&"contests".to_owned()));
+    assert!(tables.contains(&"submissions".to_owned()));
+    assert_eq!(snapshot.schema_version().unwrap().len(), 32);
+    assert_eq!(
+        snapshot.columns("contests").unwrap()[..2],
+        [
+            ("id".to_owned(), "character varying".to_owned()),
+            ("start_epoch_second".to_owned(), "bigint".to_owned()),
+        ]
+    );
+    assert_eq!(
+        snapshot.primary_key("language_count").unwrap(),
+        vec!["user_id", "simplified_language"]
+    );
+
+    // The rows written after the snapshot is taken are not copied.
+    pool.insert_contests(&[contest("abc003")]).await.unwrap();
+
+    let mut copied = Vec::new();
+    assert_eq!(snapshot.copy_table("contests", &mut copied).unwrap(), 2);
+    let copied = String::from_utf8(copied).unwrap();
+    assert!(copied.contains(
+        "abc001\t100\t200\ttitle\\nwith a newline\t-\tOther Contests\t\\N\t\\N\t\\N\t\\N\n"
+    ));
+
+    // The snapshot is closed before loading, since `TRUNCATE` waits for its lock on the table.
+    let schema_version = snapshot.schema_version().unwrap();
+    drop(snapshot);
+
+    let mut loader = Loader::connect(&url).unwrap();
+    assert_eq!(loader.schema_version().unwrap(), schema_version);
+    assert_eq!(loader.load_table("contests", copied.as_bytes()).unwrap(), 2);
+    let mut contests = pool.load_contests().await.unwrap();
+    contests.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(contests.len(), 2);
+    assert_eq!(contests[1].id.as_str(), "abc002");
+    assert_eq!(contests[1].title, "title\nwith a newline");
+}
+
+#[async_std::test]
+async fn test_import_table() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_contests(&[contest("abc001")]).await.unwrap();
+
+    let url = std::env::var("SQL_URL").unwrap();
+    let mut loader = Loader::connect(&url).unwrap();
+    let columns = vec![
+        "id".to_owned(),
+        "title".to_owned(),
+        "start_epoch_second".to_owned(),
+        "duration_second".to_owned(),
+        "rate_change".to_owned(),
+    ];
+    let csv = "abc001,\"Existing\",0,0,-\nabc002,\"AtCoder Beginner Contest 002, with a comma\",100,6000,-\n";
+    assert_eq!(
+        loader
+            .import_table("contests", &columns, CopyFormat::Csv, csv.as_bytes())
+            .unwrap(),
+        1
+    );
+    let text = "abc003\tARC\t200\t6000\t-\n";
+    assert_eq!(
+        loader
+            .import_table("contests", &columns, CopyFormat::Text, text.as_bytes())
+            .unwrap(),
+        1
+    );
+
+    let mut contests = pool.load_contests().await.unwrap();
+    contests.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(contests.len(), 3);
+    assert_eq!(contests[0].title, "title\nwith a newline");
+    assert_eq!(
+        contests[1].title,
+        "AtCoder Beginner Contest 002, with a comma"
+    );
+    assert_eq!(contests[2].start_epoch_second, 200);
+}
+
+#[async_std::test]
+async fn test_import_table_skips_forgotten_users() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query("INSERT INTO forgotten_users (user_id) VALUES ('Forgotten')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let url = std::env::var("SQL_URL").unwrap();
+    let mut loader = Loader::connect(&url).unwrap();
+    let columns = [
+        "id",
+        "epoch_second",
+        "problem_id",
+        "contest_id",
+        "user_id",
+        "language",
+        "point",
+        "length",
+        "result",
+    ]
+    .iter()
+    .map(|c| c.to_string())
+    .collect::<Vec<_>>();
+    let csv = "1,0,abc001_a,abc001,forgotten,Rust,0,0,AC\n2,0,abc001_a,abc001,user,Rust,0,0,AC\n";
+    assert_eq!(
+        loader
+            .import_table("submissions", &columns, CopyFormat::Csv, csv.as_bytes())
+            .unwrap(),
+        1
+    );
+    assert_eq!(pool.count_stored_submissions(&[1, 2]).await.unwrap(), 1);
+}
+
+#[async_std::test]
+async fn test_archive_submissions() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let submission = |id: i64, epoch_second: i64, result: &str| Submission {
+        id,
+        epoch_second,
+        result: result.to_owned(),
+        ..Default::default()
+    };
+    pool.update_submissions(&[
+        submission(1, 100, "WA"),
+        submission(2, 100, "AC"),
+        submission(3, 200, "TLE"),
+        submission(4, 300, "WA"),
+    ])
+    .await
+    .unwrap();
+
+    let url = std::env::var("SQL_URL").unwrap();
+    let mut archiver = SubmissionArchiver::begin(&url).unwrap();
+    assert_eq!(archiver.oldest_non_accepted(300).unwrap(), Some(100));
+    assert_eq!(archiver.oldest_non_accepted(100).unwrap(), None);
+
+    let mut copied = Vec::new();
+    assert_eq!(archiver.copy_non_accepted(0, 300, &mut copied).unwrap(), 2);
+    let copied = String::from_utf8(copied).unwrap();
+    assert!(copied.starts_with("1\t100\t"));
+    assert_eq!(archiver.delete_non_accepted(0, 300).unwrap(), 2);
+
+    // Nothing is deleted until it is committed.
+    assert_eq!(
+        pool.count_stored_submissions(&[1, 2, 3, 4]).await.unwrap(),
+        4
+    );
+    archiver.commit().unwrap();
+    assert_eq!(
+        pool.count_stored_submissions(&[1, 2, 3, 4]).await.unwrap(),
+        2
+    );
+
+    let mut archiver = SubmissionArchiver::begin(&url).unwrap();
+    assert!(!archiver.has_submission(1).unwrap());
+    assert!(archiver.has_submission(2).unwrap());
+}
+
+#[async_std::test]
+async fn test_load_table_fixes_up_rows() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query("INSERT INTO forgotten_users (user_id) VALUES ('Forgotten')")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let url = std::env::var("SQL_URL").unwrap();
+    let mut loader = Loader::connect(&url).unwrap();
+
+    // The forgotten users are kept even if `forgotten_users` is replaced by an older backup.
+    assert_eq!(
+        loader
+            .load_table("forgotten_users", "other\t100\n".as_bytes())
+            .unwrap(),
+        1
+    );
+    let forgotten = sqlx::query("SELECT user_id FROM forgotten_users ORDER BY user_id")
+        .try_map(|row: sqlx::postgres::PgRow| row.try_get::<String, _>("user_id"))
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(forgotten, vec!["Forgotten", "other"]);
+
+    let rows = "1\t0\tabc001_a\tabc001\tforgotten\tRust\t0\t0\tAC\t\\N\t100\n\
+                2\t0\tabc001_a\tabc001\tuser\tRust\t0\t0\tAC\t\\N\t100\n";
+    assert_eq!(
+        loader.load_table("submissions", rows.as_bytes()).unwrap(),
+        1
+    );
+    assert_eq!(pool.count_stored_submissions(&[1, 2]).await.unwrap(), 1);
+
+    // The audit log of the forgotten users is restored as it is, and the next id follows it.
+    let rows = "7\tforgotten\tdelete\t\\N\t\\N\t1\t100\n";
+    assert_eq!(
+        loader
+            .load_table("user_deletions", rows.as_bytes())
+            .unwrap(),
+        1
+    );
+    let id = sqlx::query(
+        "INSERT INTO user_deletions (user_id, mode) VALUES ('user', 'delete') RETURNING id",
+    )
+    .try_map(|row: sqlx::postgres::PgRow| row.try_get::<i32, _>("id"))
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(id, 8);
+}
@@ -0,0 +1,69 @@
+use sql_client::compressed_text::{CompressedTextClient, TextTable};
+
+mod utils;
+
+fn statement(i: usize) -> String {
+    format!(
+        "<h3>Problem Statement</h3>\
+         <p>Given are {} integers A_1, ..., A_N. Print the sum of them.</p>\
+         <h3>Constraints</h3>\
+         <ul><li>1 \\leq N \\leq {}</li><li>All values in input are integers.</li></ul>",
+        i,
+        i * 100
+    )
+}
+
+#[async_std::test]
+async fn test_compressed_text() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let table = TextTable::ProblemStatements;
+    let texts = (0..500)
+        .map(|i| (format!("abc{:03}_a", i), statement(i)))
+        .collect::<Vec<_>>();
+    assert_eq!(pool.save_texts(table, &texts).await.unwrap(), 500);
+    assert!(pool.load_latest_dictionary(table).await.unwrap().is_none());
+
+    let keys = vec!["abc001_a".to_owned(), "abc999_a".to_owned()];
+    assert_eq!(
+        pool.load_texts(table, &keys).await.unwrap(),
+        vec![("abc001_a".to_owned(), statement(1))]
+    );
+
+    let id = pool
+        .train_text_dictionary(table, 500)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        pool.load_latest_dictionary(table)
+            .await
+            .unwrap()
+            .unwrap()
+            .id,
+        id
+    );
+    assert_eq!(pool.recompress_texts(table, 100).await.unwrap(), 500);
+    assert_eq!(pool.recompress_texts(table, 100).await.unwrap(), 0);
+
+    // The texts saved after the training are compressed with the dictionary.
+    let texts = vec![("abc999_a".to_owned(), statement(999))];
+    assert_eq!(pool.save_texts(table, &texts).await.unwrap(), 1);
+    let mut loaded = pool.load_texts(table, &keys).await.unwrap();
+    loaded.sort();
+    assert_eq!(
+        loaded,
+        vec![
+            ("abc001_a".to_owned(), statement(1)),
+            ("abc999_a".to_owned(), statement(999)),
+        ]
+    );
+
+    let table = TextTable::SourceCodes;
+    assert_eq!(pool.train_text_dictionary(table, 100).await.unwrap(), None);
+    let texts = vec![("1".to_owned(), "fn main() {}".to_owned())];
+    assert_eq!(pool.save_texts(table, &texts).await.unwrap(), 1);
+    assert_eq!(
+        pool.load_texts(table, &["1".to_owned()]).await.unwrap(),
+        texts
+    );
+}
@@ -5,8 +5,8 @@ mod utils;
 
 fn create_problem(id: i32) -> ContestProblem {
     ContestProblem {
-        contest_id: format!("contest{}", id),
-        problem_id: format!("problem{}", id),
+        contest_id: format!("contest{}", id).into(),
+        problem_id: format!("problem{}", id).into(),
     }
 }
 
@@ -0,0 +1,70 @@
+use sql_client::contest_standings::ContestStandingsClient;
+use sql_client::ids::ContestId;
+use sql_client::models::StandingsEntry;
+
+mod utils;
+
+fn entry(user_id: &str, rank: i64, solved_count: i64) -> StandingsEntry {
+    StandingsEntry {
+        user_id: user_id.to_owned(),
+        rank,
+        is_rated: true,
+        old_rating: 1200,
+        solved_count,
+        point: solved_count as f64 * 100.0,
+        elapsed_second: 600,
+        penalty: 0,
+    }
+}
+
+#[async_std::test]
+async fn test_contest_standings() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert_eq!(
+        pool.load_contest_standings(&ContestId::from("contest1"))
+            .await
+            .unwrap(),
+        None
+    );
+
+    pool.update_contest_standings(
+        &ContestId::from("contest1"),
+        &[entry("user2", 2, 1), entry("user1", 1, 2)],
+        100,
+    )
+    .await
+    .unwrap();
+    pool.update_contest_standings(&ContestId::from("contest2"), &[entry("user1", 1, 3)], 200)
+        .await
+        .unwrap();
+
+    let standings = pool
+        .load_contest_standings(&ContestId::from("contest1"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(standings.contest_id, "contest1");
+    assert_eq!(standings.updated_epoch_second, 100);
+    assert_eq!(
+        standings.standings,
+        vec![entry("user1", 1, 2), entry("user2", 2, 1)]
+    );
+
+    pool.update_contest_standings(&ContestId::from("contest1"), &[entry("user3", 1, 4)], 300)
+        .await
+        .unwrap();
+    let standings = pool
+        .load_contest_standings(&ContestId::from("contest1"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(standings.updated_epoch_second, 300);
+    assert_eq!(standings.standings, vec![entry("user3", 1, 4)]);
+
+    let mut updates = pool.load_standings_updated_epoch_seconds().await.unwrap();
+    updates.sort();
+    assert_eq!(
+        updates,
+        vec![("contest1".to_owned(), 300), ("contest2".to_owned(), 200)]
+    );
+}
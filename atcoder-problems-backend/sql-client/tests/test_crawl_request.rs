@@ -0,0 +1,106 @@
+use async_std::future::timeout;
+use sql_client::crawl_request::{CrawlRequest, CrawlRequestClient, CrawlRequestListener};
+use std::time::Duration;
+
+mod utils;
+
+fn contest(contest_id: &str) -> CrawlRequest {
+    CrawlRequest::Contest(contest_id.to_owned())
+}
+
+#[async_std::test]
+async fn test_crawl_request() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert_eq!(pool.pop_crawl().await.unwrap(), None);
+
+    pool.enqueue_crawl(&contest("abc001"), 0, 100)
+        .await
+        .unwrap();
+    pool.enqueue_crawl(&contest("abc002"), 0, 200)
+        .await
+        .unwrap();
+    pool.enqueue_crawl(&contest("abc003"), 1, 300)
+        .await
+        .unwrap();
+    let user = CrawlRequest::User("abc002".to_owned());
+    pool.enqueue_crawl(&user, 0, 150).await.unwrap();
+
+    // Requesting a queued contest again raises its priority, but doesn't queue it twice.
+    pool.enqueue_crawl(&contest("abc002"), 2, 400)
+        .await
+        .unwrap();
+    pool.enqueue_crawl(&contest("abc003"), 0, 500)
+        .await
+        .unwrap();
+
+    let mut requests = vec![];
+    while let Some((request, failed_attempts)) = pool.pop_crawl().await.unwrap() {
+        assert_eq!(failed_attempts, 0);
+        requests.push(request);
+    }
+    assert_eq!(
+        requests,
+        vec![
+            contest("abc002"),
+            contest("abc003"),
+            contest("abc001"),
+            user
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_retry_crawl() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+
+    pool.enqueue_crawl(&contest("abc001"), 1, 100)
+        .await
+        .unwrap();
+    pool.retry_crawl(&contest("abc002"), 2, 200).await.unwrap();
+    assert_eq!(
+        pool.pop_crawl().await.unwrap(),
+        Some((contest("abc001"), 0))
+    );
+    assert_eq!(
+        pool.pop_crawl().await.unwrap(),
+        Some((contest("abc002"), 2))
+    );
+
+    // A new request while the failed one is crawled is kept as it is.
+    pool.enqueue_crawl(&contest("abc003"), 1, 300)
+        .await
+        .unwrap();
+    pool.retry_crawl(&contest("abc003"), 1, 400).await.unwrap();
+    assert_eq!(
+        pool.pop_crawl().await.unwrap(),
+        Some((contest("abc003"), 0))
+    );
+    assert_eq!(pool.pop_crawl().await.unwrap(), None);
+}
+
+#[async_std::test]
+async fn test_crawl_request_listener() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let mut listener = CrawlRequestListener::connect(&pool).await.unwrap();
+
+    // The operators can request a crawl by SQL.
+    sqlx::query("INSERT INTO crawl_requests (kind, target_id) VALUES ('user', 'user1')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    timeout(Duration::from_secs(5), listener.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        pool.pop_crawl().await.unwrap(),
+        Some((CrawlRequest::User("user1".to_owned()), 0))
+    );
+
+    assert!(sqlx::query(
+        "INSERT INTO crawl_requests (kind, target_id) VALUES ('problem', 'abc001_a')"
+    )
+    .execute(&pool)
+    .await
+    .is_err());
+}
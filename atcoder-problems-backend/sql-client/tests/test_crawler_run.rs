@@ -0,0 +1,58 @@
+use sql_client::crawler_run::{CrawlerRun, CrawlerRunClient};
+use sql_client::ids::ContestId;
+
+mod utils;
+
+fn run(run_id: &str, job: &str, contest_ids: &[&str], finished_epoch_second: i64) -> CrawlerRun {
+    CrawlerRun {
+        run_id: run_id.to_owned(),
+        job: job.to_owned(),
+        contest_ids: contest_ids.iter().map(|id| id.to_string()).collect(),
+        status: "success".to_owned(),
+        error: None,
+        started_epoch_second: finished_epoch_second - 10,
+        finished_epoch_second,
+        rows_written: 100,
+        failures: 0,
+    }
+}
+
+#[async_std::test]
+async fn test_crawler_run() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let runs = vec![
+        run("1", "crawl_contests_new", &["abc001", "abc002"], 100),
+        run("2", "crawl_contests_new", &["abc002"], 200),
+        run("3", "crawl_problems", &[], 300),
+    ];
+    for run in runs.iter() {
+        pool.insert_crawler_run(run).await.unwrap();
+    }
+
+    let loaded = pool.load_crawler_runs(None, None, 10).await.unwrap();
+    assert_eq!(
+        loaded,
+        vec![runs[2].clone(), runs[1].clone(), runs[0].clone()]
+    );
+
+    let loaded = pool.load_crawler_runs(None, None, 1).await.unwrap();
+    assert_eq!(loaded, vec![runs[2].clone()]);
+
+    let loaded = pool
+        .load_crawler_runs(Some("crawl_contests_new"), None, 10)
+        .await
+        .unwrap();
+    assert_eq!(loaded, vec![runs[1].clone(), runs[0].clone()]);
+
+    let loaded = pool
+        .load_crawler_runs(None, Some(&ContestId::from("abc001")), 10)
+        .await
+        .unwrap();
+    assert_eq!(loaded, vec![runs[0].clone()]);
+
+    let loaded = pool
+        .load_crawler_runs(Some("crawl_problems"), Some(&ContestId::from("abc001")), 10)
+        .await
+        .unwrap();
+    assert!(loaded.is_empty());
+}
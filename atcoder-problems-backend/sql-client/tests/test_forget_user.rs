@@ -0,0 +1,150 @@
+use sql_client::forget_user::{ForgetMode, ForgetUserClient};
+use sql_client::ids::UserId;
+use sql_client::models::{Submission, UserProfile};
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::user_profile::UserProfileClient;
+
+mod utils;
+
+fn submission(id: i64, user_id: &str) -> Submission {
+    Submission {
+        id,
+        user_id: user_id.into(),
+        result: "AC".to_owned(),
+        ..Default::default()
+    }
+}
+
+async fn setup(pool: &sql_client::PgPool) {
+    pool.update_submissions(&[
+        submission(1, "forgotten"),
+        submission(2, "forgotten"),
+        submission(3, "kept"),
+    ])
+    .await
+    .unwrap();
+    for query in [
+        "INSERT INTO accepted_count VALUES ('forgotten', 2), ('kept', 1)",
+        "INSERT INTO first VALUES ('abc001', 'abc001_a', 1)",
+        "INSERT INTO submission_source_codes (submission_id, compressed) VALUES (1, '')",
+    ]
+    .iter()
+    {
+        sql_client::query(query).execute(pool).await.unwrap();
+    }
+    pool.insert_user_profiles(&[UserProfile {
+        user_id: "forgotten".to_owned(),
+        ..Default::default()
+    }])
+    .await
+    .unwrap();
+}
+
+async fn count_rows(pool: &sql_client::PgPool, query: &str) -> i64 {
+    use sql_client::Row;
+    sql_client::query(query)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+        .get(0)
+}
+
+#[async_std::test]
+async fn test_delete_user() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    setup(&pool).await;
+
+    // The user ids are case-insensitive.
+    let deletion = pool
+        .forget_user(
+            &UserId::from("Forgotten"),
+            ForgetMode::Delete,
+            Some("takedown"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(deletion.pseudonym, None);
+    // 2 submissions, 1 row of first, 1 source code, 1 row of accepted_count and the profile.
+    assert_eq!(deletion.rows_affected, 6);
+    assert!(pool
+        .is_forgotten_user(&UserId::from("forgotten"))
+        .await
+        .unwrap());
+    assert!(!pool.is_forgotten_user(&UserId::from("kept")).await.unwrap());
+    assert_eq!(pool.count_stored_submissions(&[1, 2, 3]).await.unwrap(), 1);
+    assert_eq!(
+        count_rows(&pool, "SELECT COUNT(*) FROM accepted_count").await,
+        1
+    );
+    assert_eq!(count_rows(&pool, "SELECT COUNT(*) FROM first").await, 0);
+    assert_eq!(
+        count_rows(&pool, "SELECT COUNT(*) FROM submission_source_codes").await,
+        0
+    );
+    assert_eq!(
+        count_rows(
+            &pool,
+            "SELECT rows_affected FROM user_deletions WHERE user_id = 'Forgotten'"
+        )
+        .await,
+        6
+    );
+
+    // The submissions and the profile crawled again are not inserted.
+    pool.update_submissions(&[submission(1, "forgotten"), submission(4, "forgotten")])
+        .await
+        .unwrap();
+    assert_eq!(pool.count_stored_submissions(&[1, 4]).await.unwrap(), 0);
+    pool.insert_user_profiles(&[UserProfile {
+        user_id: "forgotten".to_owned(),
+        ..Default::default()
+    }])
+    .await
+    .unwrap();
+    assert_eq!(
+        pool.get_user_profile(&UserId::from("forgotten"))
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[async_std::test]
+async fn test_anonymize_user() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    setup(&pool).await;
+
+    let deletion = pool
+        .forget_user(&UserId::from("forgotten"), ForgetMode::Anonymize, None)
+        .await
+        .unwrap();
+    let pseudonym = deletion.pseudonym.unwrap();
+    assert_eq!(pseudonym, format!("deleted-user-{}", deletion.id));
+
+    let submissions = pool
+        .get_submissions(SubmissionRequest::ByIds { ids: &[1, 2, 3] })
+        .await
+        .unwrap();
+    assert_eq!(submissions.len(), 3);
+    assert!(submissions
+        .iter()
+        .all(|s| s.user_id.as_str() != "forgotten"));
+    assert_eq!(
+        submissions
+            .iter()
+            .filter(|s| s.user_id.as_str() == pseudonym)
+            .count(),
+        2
+    );
+    assert_eq!(count_rows(&pool, "SELECT COUNT(*) FROM first").await, 1);
+
+    // The anonymized submissions are not renamed back when they are crawled again.
+    pool.update_submissions(&[submission(1, "forgotten")])
+        .await
+        .unwrap();
+    let submissions = pool
+        .get_submissions(SubmissionRequest::ByIds { ids: &[1] })
+        .await
+        .unwrap();
+    assert_eq!(submissions[0].user_id.as_str(), pseudonym);
+}
@@ -0,0 +1,71 @@
+use sql_client::integrity::IntegrityClient;
+use sql_client::models::{Contest, Problem, Submission};
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::SubmissionClient;
+
+mod utils;
+
+#[async_std::test]
+async fn test_check_integrity() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_contests(&[Contest {
+        id: "abc001".into(),
+        ..Default::default()
+    }])
+    .await
+    .unwrap();
+    pool.insert_problems(&[Problem {
+        id: "abc001_a".into(),
+        contest_id: "abc001".into(),
+        ..Default::default()
+    }])
+    .await
+    .unwrap();
+    let submission = |id: i64, problem_id: &str| Submission {
+        id,
+        problem_id: problem_id.into(),
+        contest_id: "abc001".into(),
+        ..Default::default()
+    };
+    pool.update_submissions(&[
+        submission(1, "abc001_a"),
+        submission(2, "abc001_b"),
+        submission(3, "abc001_b"),
+    ])
+    .await
+    .unwrap();
+
+    let report = pool.check_integrity().await.unwrap();
+    assert!(report.has_issues());
+
+    let submissions = report
+        .tables
+        .iter()
+        .find(|t| t.table == "submissions")
+        .unwrap();
+    assert_eq!(submissions.row_count, 3);
+    assert_eq!(submissions.min_id.as_deref(), Some("1"));
+    assert_eq!(submissions.max_id.as_deref(), Some("3"));
+    let accepted_count = report
+        .tables
+        .iter()
+        .find(|t| t.table == "accepted_count")
+        .unwrap();
+    assert_eq!(accepted_count.min_id, None);
+
+    assert_eq!(report.duplicate_submission_ids.count, 0);
+    let orphans = |table: &str, column: &str| {
+        report
+            .orphans
+            .iter()
+            .find(|o| o.table == table && o.column == column)
+            .unwrap()
+    };
+    assert_eq!(orphans("submissions", "problem_id").count, 2);
+    assert_eq!(
+        orphans("submissions", "problem_id").sample,
+        vec!["abc001_b"]
+    );
+    assert_eq!(orphans("submissions", "contest_id").count, 0);
+    assert_eq!(orphans("problems", "contest_id").count, 0);
+}
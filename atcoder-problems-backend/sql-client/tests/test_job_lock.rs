@@ -0,0 +1,41 @@
+use sql_client::job_lock::JobLock;
+
+mod utils;
+
+#[async_std::test]
+async fn test_job_lock() {
+    utils::initialize_and_connect_to_test_sql().await;
+    let url = std::env::var("SQL_URL").unwrap();
+
+    let lock = JobLock::try_acquire(&url, "crawl_problems")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(JobLock::try_acquire(&url, "crawl_problems")
+        .await
+        .unwrap()
+        .is_none());
+
+    // The locks of the other jobs are independent.
+    let other = JobLock::try_acquire(&url, "aggregate")
+        .await
+        .unwrap()
+        .unwrap();
+    other.release().await.unwrap();
+
+    lock.release().await.unwrap();
+    let lock = JobLock::acquire(&url, "crawl_problems").await.unwrap();
+    lock.release().await.unwrap();
+
+    // The lock is released when the connection is closed without unlocking.
+    let lock = JobLock::try_acquire(&url, "crawl_problems")
+        .await
+        .unwrap()
+        .unwrap();
+    drop(lock);
+    async_std::task::sleep(std::time::Duration::from_millis(500)).await;
+    assert!(JobLock::try_acquire(&url, "crawl_problems")
+        .await
+        .unwrap()
+        .is_some());
+}
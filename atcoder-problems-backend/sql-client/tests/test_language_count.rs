@@ -1,5 +1,6 @@
+use sql_client::ids::UserId;
 use sql_client::language_count::LanguageCountClient;
-use sql_client::models::{Submission, UserLanguageCount};
+use sql_client::models::{LanguageUserCount, Submission, UserLanguageCount};
 
 mod utils;
 
@@ -9,50 +10,50 @@ async fn test_language_count() {
     let mut submissions = vec![
         Submission {
             id: 1,
-            problem_id: "problem1".to_owned(),
-            user_id: "user1".to_owned(),
+            problem_id: "problem1".into(),
+            user_id: "user1".into(),
             language: "language1".to_owned(),
             ..Default::default()
         },
         Submission {
             id: 2,
-            problem_id: "problem2".to_owned(),
-            user_id: "user1".to_owned(),
+            problem_id: "problem2".into(),
+            user_id: "user1".into(),
             language: "language1".to_owned(),
             ..Default::default()
         },
         Submission {
             id: 3,
-            problem_id: "problem1".to_owned(),
-            user_id: "user1".to_owned(),
+            problem_id: "problem1".into(),
+            user_id: "user1".into(),
             language: "language1".to_owned(),
             ..Default::default()
         },
         Submission {
             id: 4,
-            problem_id: "problem1".to_owned(),
-            user_id: "user1".to_owned(),
+            problem_id: "problem1".into(),
+            user_id: "user1".into(),
             language: "language2".to_owned(),
             ..Default::default()
         },
         Submission {
             id: 5,
-            problem_id: "problem1".to_owned(),
-            user_id: "user2".to_owned(),
+            problem_id: "problem1".into(),
+            user_id: "user2".into(),
             language: "language1".to_owned(),
             ..Default::default()
         },
         Submission {
             id: 6,
-            problem_id: "problem1".to_owned(),
-            user_id: "user3".to_owned(),
+            problem_id: "problem1".into(),
+            user_id: "user3".into(),
             language: "Perl (5)".to_owned(),
             ..Default::default()
         },
         Submission {
             id: 7,
-            problem_id: "problem1".to_owned(),
-            user_id: "user3".to_owned(),
+            problem_id: "problem1".into(),
+            user_id: "user3".into(),
             language: "Perl6".to_owned(),
             ..Default::default()
         },
@@ -92,8 +93,8 @@ async fn test_language_count() {
     );
     submissions.push(Submission {
         id: 8,
-        problem_id: "problem4".to_owned(),
-        user_id: "user3".to_owned(),
+        problem_id: "problem4".into(),
+        user_id: "user3".into(),
         language: "Perl6".to_owned(),
         ..Default::default()
     });
@@ -131,4 +132,53 @@ async fn test_language_count() {
             }
         ]
     );
+
+    let language_count = pool
+        .load_users_language_count(&UserId::from("user3"))
+        .await
+        .unwrap();
+    assert_eq!(
+        language_count,
+        vec![
+            UserLanguageCount {
+                user_id: "user3".to_owned(),
+                simplified_language: "Perl".to_owned(),
+                problem_count: 1
+            },
+            UserLanguageCount {
+                user_id: "user3".to_owned(),
+                simplified_language: "Raku".to_owned(),
+                problem_count: 2
+            }
+        ]
+    );
+    let language_count = pool
+        .load_users_language_count(&UserId::from("user4"))
+        .await
+        .unwrap();
+    assert!(language_count.is_empty());
+
+    let mut languages = pool.load_languages().await.unwrap();
+    languages.sort_by(|a, b| a.language.cmp(&b.language));
+    assert_eq!(
+        languages,
+        vec![
+            LanguageUserCount {
+                language: "Perl".to_owned(),
+                user_count: 1
+            },
+            LanguageUserCount {
+                language: "Raku".to_owned(),
+                user_count: 1
+            },
+            LanguageUserCount {
+                language: "language1".to_owned(),
+                user_count: 2
+            },
+            LanguageUserCount {
+                language: "language2".to_owned(),
+                user_count: 1
+            }
+        ]
+    );
 }
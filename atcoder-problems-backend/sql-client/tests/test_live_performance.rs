@@ -0,0 +1,98 @@
+use sql_client::ids::{ContestId, UserId};
+use sql_client::live_performance::LivePerformanceClient;
+use sql_client::models::LivePerformance;
+
+mod utils;
+
+fn performance(contest_id: &str, user_id: &str, rank: i64, performance: i64) -> LivePerformance {
+    LivePerformance {
+        contest_id: contest_id.to_owned(),
+        user_id: user_id.to_owned(),
+        rank,
+        performance,
+        old_rating: 1000,
+        new_rating: 1000,
+    }
+}
+
+#[async_std::test]
+async fn test_live_performances() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.update_live_performances(
+        &ContestId::from("contest1"),
+        &[
+            performance("contest1", "user2", 2, 1000),
+            performance("contest1", "user1", 1, 2000),
+        ],
+    )
+    .await
+    .unwrap();
+    pool.update_live_performances(
+        &ContestId::from("contest2"),
+        &[performance("contest2", "user1", 1, 1500)],
+    )
+    .await
+    .unwrap();
+
+    let performances = pool
+        .load_live_performances(&ContestId::from("contest1"))
+        .await
+        .unwrap();
+    assert_eq!(
+        performances,
+        vec![
+            performance("contest1", "user1", 1, 2000),
+            performance("contest1", "user2", 2, 1000),
+        ]
+    );
+
+    pool.update_live_performances(
+        &ContestId::from("contest1"),
+        &[performance("contest1", "user3", 1, 3000)],
+    )
+    .await
+    .unwrap();
+    let performances = pool
+        .load_live_performances(&ContestId::from("contest1"))
+        .await
+        .unwrap();
+    assert_eq!(
+        performances,
+        vec![performance("contest1", "user3", 1, 3000)]
+    );
+    assert_eq!(
+        pool.load_live_performances(&ContestId::from("contest2"))
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        pool.load_users_live_performances(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        vec![performance("contest2", "user1", 1, 1500)]
+    );
+}
+
+#[async_std::test]
+async fn test_load_predicted_ratings() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"INSERT INTO predicted_rating (user_id, rating) VALUES ('user1', 1200.0), ('user2', NULL), ('user3', 800.0)",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let mut ratings = pool
+        .load_predicted_ratings(&[
+            UserId::from("user1"),
+            UserId::from("user2"),
+            UserId::from("user4"),
+        ])
+        .await
+        .unwrap();
+    ratings.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(ratings, vec![("user1".to_owned(), 1200.0)]);
+}
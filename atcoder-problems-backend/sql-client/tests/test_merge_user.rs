@@ -0,0 +1,118 @@
+use sql_client::ids::UserId;
+use sql_client::merge_user::MergeUserClient;
+use sql_client::models::{Submission, UserProfile};
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::user_profile::UserProfileClient;
+use sql_client::Row;
+
+mod utils;
+
+fn submission(id: i64, user_id: &str) -> Submission {
+    Submission {
+        id,
+        user_id: user_id.into(),
+        result: "AC".to_owned(),
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn test_merge_user() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.update_submissions(&[
+        submission(1, "old_name"),
+        submission(2, "old_name"),
+        submission(3, "new_name"),
+    ])
+    .await
+    .unwrap();
+    for query in [
+        "INSERT INTO accepted_count VALUES ('old_name', 2), ('new_name', 1)",
+        "INSERT INTO achievements VALUES ('old_name', 'a', 1), ('old_name', 'b', 2)",
+        "INSERT INTO achievements VALUES ('new_name', 'a', 3)",
+    ]
+    .iter()
+    {
+        sql_client::query(query).execute(&pool).await.unwrap();
+    }
+    pool.insert_user_profiles(&[UserProfile {
+        user_id: "old_name".to_owned(),
+        ..Default::default()
+    }])
+    .await
+    .unwrap();
+
+    let merge = pool
+        .merge_user(&UserId::from("old_name"), &UserId::from("new_name"))
+        .await
+        .unwrap();
+    assert_eq!(merge.submissions, 2);
+    let table = |name: &str| merge.tables.iter().find(|t| t.table == name).unwrap();
+    assert_eq!(
+        (
+            table("accepted_count").moved,
+            table("accepted_count").discarded
+        ),
+        (0, 1)
+    );
+    assert_eq!(
+        (table("achievements").moved, table("achievements").discarded),
+        (1, 1)
+    );
+    assert_eq!((table("users").moved, table("users").discarded), (1, 0));
+    assert_eq!(merge.rows_affected(), 6);
+
+    let submissions = pool
+        .get_submissions(SubmissionRequest::UserAll {
+            user_id: &UserId::from("new_name"),
+        })
+        .await
+        .unwrap();
+    assert_eq!(submissions.len(), 3);
+
+    // The rows of the new handle are kept on the conflicts.
+    let mut achievements: Vec<(String, i64)> =
+        sql_client::query("SELECT achievement_id, achieved_epoch_second FROM achievements")
+            .fetch_all(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect();
+    achievements.sort();
+    assert_eq!(achievements, vec![("a".to_owned(), 3), ("b".to_owned(), 2)]);
+    assert!(pool
+        .get_user_profile(&UserId::from("new_name"))
+        .await
+        .unwrap()
+        .is_some());
+    assert!(pool
+        .get_user_profile(&UserId::from("old_name"))
+        .await
+        .unwrap()
+        .is_none());
+
+    assert!(pool
+        .merge_user(&UserId::from("new_name"), &UserId::from("new_name"))
+        .await
+        .is_err());
+
+    // The forgotten users are matched case-insensitively, as the triggers do.
+    sql_client::query("INSERT INTO forgotten_users (user_id) VALUES ('forgotten')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(pool
+        .merge_user(&UserId::from("new_name"), &UserId::from("Forgotten"))
+        .await
+        .is_err());
+    assert_eq!(
+        pool.get_submissions(SubmissionRequest::UserAll {
+            user_id: &UserId::from("new_name"),
+        })
+        .await
+        .unwrap()
+        .len(),
+        3
+    );
+}
@@ -0,0 +1,57 @@
+use sql_client::partition::{PartitionClient, YearlyPartition};
+use sqlx::Row;
+
+mod utils;
+
+#[async_std::test]
+async fn test_yearly_partitions() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert!(!pool.is_partitioned("submissions").await.unwrap());
+
+    sql_client::execute_script(
+        &pool,
+        r"
+        DROP TABLE IF EXISTS partitioned_submissions CASCADE;
+        DROP TABLE IF EXISTS partitioned_submissions_y2020;
+        DROP TABLE IF EXISTS partitioned_submissions_y2021;
+        CREATE TABLE partitioned_submissions (
+          id            BIGINT NOT NULL,
+          epoch_second  BIGINT NOT NULL
+        ) PARTITION BY RANGE (epoch_second);
+        ",
+    )
+    .await
+    .unwrap();
+    let table = "partitioned_submissions";
+    assert!(pool.is_partitioned(table).await.unwrap());
+    assert!(pool.load_yearly_partitions(table).await.unwrap().is_empty());
+
+    let y2020 = YearlyPartition { year: 2020 };
+    let y2021 = YearlyPartition { year: 2021 };
+    pool.attach_yearly_partition(table, y2021).await.unwrap();
+    pool.attach_yearly_partition(table, y2020).await.unwrap();
+    assert_eq!(
+        pool.load_yearly_partitions(table).await.unwrap(),
+        vec![y2020, y2021]
+    );
+
+    // The rows are routed to the partition of the year.
+    sqlx::query("INSERT INTO partitioned_submissions VALUES (1, $1)")
+        .bind(y2021.start_epoch_second())
+        .execute(&pool)
+        .await
+        .unwrap();
+    let count: i64 = sqlx::query("SELECT COUNT(*) FROM partitioned_submissions_y2021")
+        .fetch_one(&pool)
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 1);
+
+    pool.detach_yearly_partition(table, y2020).await.unwrap();
+    assert_eq!(
+        pool.load_yearly_partitions(table).await.unwrap(),
+        vec![y2021]
+    );
+    pool.drop_detached_partition(table, y2020).await.unwrap();
+}
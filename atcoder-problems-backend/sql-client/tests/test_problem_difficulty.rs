@@ -0,0 +1,30 @@
+use sql_client::problem_difficulty::ProblemDifficultyClient;
+use std::collections::BTreeMap;
+
+mod utils;
+
+#[async_std::test]
+async fn test_problem_difficulty() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert!(pool.load_problem_difficulties().await.unwrap().is_empty());
+
+    let mut difficulties = BTreeMap::new();
+    difficulties.insert("problem1".to_owned(), 100.0);
+    difficulties.insert("problem2".to_owned(), -200.0);
+    pool.update_problem_difficulties(&difficulties)
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.load_problem_difficulties().await.unwrap(),
+        difficulties
+    );
+
+    let mut updated = BTreeMap::new();
+    updated.insert("problem1".to_owned(), 300.0);
+    pool.update_problem_difficulties(&updated).await.unwrap();
+    difficulties.insert("problem1".to_owned(), 300.0);
+    assert_eq!(
+        pool.load_problem_difficulties().await.unwrap(),
+        difficulties
+    );
+}
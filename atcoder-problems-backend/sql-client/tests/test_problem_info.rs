@@ -41,23 +41,23 @@ async fn test_update_problem_solver_count() {
     pool.update_submissions(&[
         Submission {
             id: 0,
-            user_id: "user1".to_string(),
+            user_id: "user1".into(),
             result: "AC".to_string(),
-            problem_id: "problem".to_string(),
+            problem_id: "problem".into(),
             ..Default::default()
         },
         Submission {
             id: 1,
-            user_id: "user2".to_string(),
+            user_id: "user2".into(),
             result: "AC".to_string(),
-            problem_id: "problem".to_string(),
+            problem_id: "problem".into(),
             ..Default::default()
         },
         Submission {
             id: 2,
-            user_id: "user3".to_string(),
+            user_id: "user3".into(),
             result: "WA".to_string(),
-            problem_id: "problem".to_string(),
+            problem_id: "problem".into(),
             ..Default::default()
         },
     ])
@@ -68,9 +68,9 @@ async fn test_update_problem_solver_count() {
 
     pool.update_submissions(&[Submission {
         id: 3,
-        user_id: "user3".to_string(),
+        user_id: "user3".into(),
         result: "AC".to_string(),
-        problem_id: "problem".to_string(),
+        problem_id: "problem".into(),
         ..Default::default()
     }])
     .await
@@ -85,14 +85,13 @@ async fn test_update_problem_points() {
     let problem_id = "problem";
 
     let pool = utils::initialize_and_connect_to_test_sql().await;
-    pool.insert_contests(&[Contest {
-        id: contest_id.to_string(),
-        start_epoch_second: 1468670400,
-        rate_change: "All".to_string(),
-
-        duration_second: 0,
-        title: "".to_string(),
-    }])
+    pool.insert_contests(&[Contest::new(
+        contest_id.to_string(),
+        1468670400,
+        0,
+        "".to_string(),
+        "All".to_string(),
+    )])
     .await
     .unwrap();
 
@@ -101,8 +100,8 @@ async fn test_update_problem_points() {
     pool.update_submissions(&[Submission {
         id: 0,
         point: 0.0,
-        problem_id: problem_id.to_string(),
-        contest_id: contest_id.to_string(),
+        problem_id: problem_id.into(),
+        contest_id: contest_id.into(),
         ..Default::default()
     }])
     .await
@@ -116,8 +115,8 @@ async fn test_update_problem_points() {
     pool.update_submissions(&[Submission {
         id: 1,
         point: 100.0,
-        problem_id: problem_id.to_string(),
-        contest_id: contest_id.to_string(),
+        problem_id: problem_id.into(),
+        contest_id: contest_id.into(),
         ..Default::default()
     }])
     .await
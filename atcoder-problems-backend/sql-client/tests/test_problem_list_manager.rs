@@ -1,4 +1,7 @@
-use sql_client::internal::problem_list_manager::{ListItem, ProblemList, ProblemListManager};
+use sql_client::ids::ProblemId;
+use sql_client::internal::problem_list_manager::{
+    ListItem, ProblemList, ProblemListDocument, ProblemListEntry, ProblemListManager,
+};
 
 mod utils;
 
@@ -7,7 +10,7 @@ async fn test_problem_list_manager() {
     let internal_user_id = "user_id";
     let atcoder_user_id = "atcoder_id";
     let list_name = "list_name";
-    let problem_id = "problem_id";
+    let problem_id = &ProblemId::from("problem_id");
     let pool = utils::initialize_and_connect_to_test_sql().await;
     utils::setup_internal_user(&pool, internal_user_id, atcoder_user_id).await;
 
@@ -30,6 +33,25 @@ async fn test_problem_list_manager() {
         "`get_single_list` returned an unexpected value."
     );
 
+    assert!(pool
+        .is_list_owner(&list_id, internal_user_id)
+        .await
+        .unwrap());
+    assert!(
+        !pool
+            .is_list_owner(&list_id, "another_user_id")
+            .await
+            .unwrap(),
+        "`is_list_owner` should be false for a user who does not own the list."
+    );
+    assert!(
+        !pool
+            .is_list_owner("unknown_list_id", internal_user_id)
+            .await
+            .unwrap(),
+        "`is_list_owner` should be false for an unknown list."
+    );
+
     pool.update_list(&list_id, "list_name_updated")
         .await
         .unwrap();
@@ -76,3 +98,74 @@ async fn test_problem_list_manager() {
         "The list should be deleted, but still exists."
     );
 }
+
+#[async_std::test]
+async fn test_import_and_export_list() {
+    let internal_user_id = "user_id";
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    utils::setup_internal_user(&pool, internal_user_id, "atcoder_id").await;
+
+    let document: ProblemListDocument = serde_json::from_str(
+        r#"{
+            "name": "imported",
+            "items": [
+                {"problem_id": "problem_b", "order": 2},
+                {"problem_id": "problem_c"},
+                {"problem_id": "problem_a", "order": 1, "note": "first"},
+                {"problem_id": "problem_b", "order": 3}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let list_id = pool.import_list(internal_user_id, &document).await.unwrap();
+
+    let list = pool.get_single_list(&list_id).await.unwrap();
+    let exported = ProblemListDocument::from_list(list);
+    assert_eq!(exported.name, "imported");
+    assert_eq!(
+        exported.items,
+        vec![
+            ProblemListEntry {
+                problem_id: "problem_a".to_string(),
+                order: Some(1),
+                note: "first".to_string(),
+            },
+            ProblemListEntry {
+                problem_id: "problem_b".to_string(),
+                order: Some(2),
+                note: "".to_string(),
+            },
+            ProblemListEntry {
+                problem_id: "problem_c".to_string(),
+                order: Some(3),
+                note: "".to_string(),
+            },
+        ]
+    );
+
+    // The items added later follow the imported ones.
+    pool.add_item(&list_id, &ProblemId::from("problem_0"))
+        .await
+        .unwrap();
+    let list = pool.get_single_list(&list_id).await.unwrap();
+    assert_eq!(list.items.last().unwrap().problem_id, "problem_0");
+
+    // A list of the API of AtCoder Problems can be imported as it is.
+    let upstream: ProblemListDocument = serde_json::from_str(
+        r#"{
+            "internal_list_id": "list_id",
+            "internal_list_name": "upstream",
+            "internal_user_id": "upstream_user",
+            "items": [{"problem_id": "problem_a", "memo": "memo"}]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(upstream.name, "upstream");
+    assert_eq!(upstream.items[0].note, "memo");
+    let list_id = pool.import_list(internal_user_id, &upstream).await.unwrap();
+    assert_eq!(pool.get_single_list(&list_id).await.unwrap().items.len(), 1);
+
+    let mut document = upstream;
+    document.version = 2;
+    assert!(pool.import_list(internal_user_id, &document).await.is_err());
+}
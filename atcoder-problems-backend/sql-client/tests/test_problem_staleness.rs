@@ -0,0 +1,115 @@
+use sql_client::ids::UserId;
+use sql_client::models::{Problem, StaleProblem, Submission, UnsolvedAttempt};
+use sql_client::problem_staleness::ProblemStalenessClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::SubmissionClient;
+
+mod utils;
+
+fn problem(id: &str) -> Problem {
+    Problem {
+        id: id.into(),
+        contest_id: "contest".into(),
+        title: id.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn submission(
+    id: i64,
+    epoch_second: i64,
+    user_id: &str,
+    problem_id: &str,
+    result: &str,
+) -> Submission {
+    Submission {
+        id,
+        epoch_second,
+        user_id: user_id.into(),
+        problem_id: problem_id.into(),
+        contest_id: "contest".into(),
+        result: result.to_owned(),
+        ..Default::default()
+    }
+}
+
+#[async_std::test]
+async fn test_problem_staleness() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_problems(&[
+        problem("problem1"),
+        problem("problem2"),
+        problem("problem3"),
+    ])
+    .await
+    .unwrap();
+    pool.update_submissions(&[
+        submission(1, 100, "user1", "problem1", "AC"),
+        submission(2, 300, "user2", "problem1", "AC"),
+        submission(3, 200, "user1", "problem2", "AC"),
+        submission(4, 50, "user1", "problem3", "WA"),
+        submission(5, 400, "user1", "problem3", "TLE"),
+        submission(6, 10, "user2", "problem2", "WA"),
+        submission(7, 20, "user2", "problem2", "AC"),
+    ])
+    .await
+    .unwrap();
+    pool.update_last_accepted().await.unwrap();
+
+    assert_eq!(
+        pool.load_never_solved_problems().await.unwrap(),
+        vec![problem("problem3")]
+    );
+    assert_eq!(
+        pool.load_stale_problems(10).await.unwrap(),
+        vec![
+            StaleProblem {
+                problem_id: "problem2".to_owned(),
+                contest_id: "contest".to_owned(),
+                last_accepted_epoch_second: 200,
+            },
+            StaleProblem {
+                problem_id: "problem1".to_owned(),
+                contest_id: "contest".to_owned(),
+                last_accepted_epoch_second: 300,
+            },
+        ]
+    );
+    assert_eq!(pool.load_stale_problems(1).await.unwrap().len(), 1);
+
+    assert_eq!(
+        pool.load_unsolved_attempts(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        vec![UnsolvedAttempt {
+            problem_id: "problem3".to_owned(),
+            contest_id: "contest".to_owned(),
+            first_attempt_epoch_second: 50,
+            last_attempt_epoch_second: 400,
+            attempt_count: 2,
+        }]
+    );
+    assert!(pool
+        .load_unsolved_attempts(&UserId::from("user2"))
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        pool.load_users_stale_problems(&UserId::from("user1"), 200)
+            .await
+            .unwrap(),
+        vec![StaleProblem {
+            problem_id: "problem1".to_owned(),
+            contest_id: "contest".to_owned(),
+            last_accepted_epoch_second: 100,
+        }]
+    );
+    assert_eq!(
+        pool.load_users_stale_problems(&UserId::from("user1"), 201)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+}
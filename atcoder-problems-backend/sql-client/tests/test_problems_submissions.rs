@@ -56,8 +56,8 @@ async fn setup_contests() -> PgPool {
 async fn test_problem_info_aggrefator() {
     let ignored_submission = vec![Submission {
         id: 0,
-        problem_id: "problem1".to_owned(),
-        contest_id: "contest1".to_owned(),
+        problem_id: "problem1".into(),
+        contest_id: "contest1".into(),
         epoch_second: 0,
         length: 1,
         execution_time: Some(1),
@@ -66,8 +66,8 @@ async fn test_problem_info_aggrefator() {
     }];
     let submissions1 = vec![Submission {
         id: 1,
-        problem_id: "problem1".to_owned(),
-        contest_id: "contest1".to_owned(),
+        problem_id: "problem1".into(),
+        contest_id: "contest1".into(),
         epoch_second: 10,
         length: 20,
         execution_time: Some(10),
@@ -76,8 +76,8 @@ async fn test_problem_info_aggrefator() {
     }];
     let submissions2 = vec![Submission {
         id: 2,
-        problem_id: "problem1".to_owned(),
-        contest_id: "contest2".to_owned(),
+        problem_id: "problem1".into(),
+        contest_id: "contest2".into(),
         epoch_second: 10,
         length: 10,
         execution_time: Some(10),
@@ -97,16 +97,16 @@ async fn test_problem_info_aggrefator() {
         pool.update_submissions_of_problems().await.unwrap();
         let first = get_from(&pool, Table::First).await;
         assert_eq!(first.len(), 1);
-        assert_eq!(first[0].0, submissions1[0].contest_id);
-        assert_eq!(first[0].1, submissions1[0].problem_id);
+        assert_eq!(first[0].0, submissions1[0].contest_id.as_str());
+        assert_eq!(first[0].1, submissions1[0].problem_id.as_str());
         assert_eq!(first[0].2, submissions1[0].id);
 
         pool.update_submissions(&submissions2).await.unwrap();
         pool.update_submissions_of_problems().await.unwrap();
         let first = get_from(&pool, Table::First).await;
         assert_eq!(first.len(), 1);
-        assert_eq!(first[0].0, submissions1[0].contest_id);
-        assert_eq!(first[0].1, submissions1[0].problem_id);
+        assert_eq!(first[0].0, submissions1[0].contest_id.as_str());
+        assert_eq!(first[0].1, submissions1[0].problem_id.as_str());
         assert_eq!(first[0].2, submissions1[0].id);
     }
 
@@ -117,16 +117,16 @@ async fn test_problem_info_aggrefator() {
         pool.update_submissions_of_problems().await.unwrap();
         let first = get_from(&pool, Table::First).await;
         assert_eq!(first.len(), 1);
-        assert_eq!(first[0].0, submissions2[0].contest_id);
-        assert_eq!(first[0].1, submissions2[0].problem_id);
+        assert_eq!(first[0].0, submissions2[0].contest_id.as_str());
+        assert_eq!(first[0].1, submissions2[0].problem_id.as_str());
         assert_eq!(first[0].2, submissions2[0].id);
 
         pool.update_submissions(&submissions1).await.unwrap();
         pool.update_submissions_of_problems().await.unwrap();
         let first = get_from(&pool, Table::First).await;
         assert_eq!(first.len(), 1);
-        assert_eq!(first[0].0, submissions1[0].contest_id);
-        assert_eq!(first[0].1, submissions1[0].problem_id);
+        assert_eq!(first[0].0, submissions1[0].contest_id.as_str());
+        assert_eq!(first[0].1, submissions1[0].problem_id.as_str());
         assert_eq!(first[0].2, submissions1[0].id);
     }
 
@@ -137,16 +137,16 @@ async fn test_problem_info_aggrefator() {
         pool.update_submissions_of_problems().await.unwrap();
         let shortest = get_from(&pool, Table::Shortest).await;
         assert_eq!(shortest.len(), 1);
-        assert_eq!(shortest[0].0, submissions1[0].contest_id);
-        assert_eq!(shortest[0].1, submissions1[0].problem_id);
+        assert_eq!(shortest[0].0, submissions1[0].contest_id.as_str());
+        assert_eq!(shortest[0].1, submissions1[0].problem_id.as_str());
         assert_eq!(shortest[0].2, submissions1[0].id);
 
         pool.update_submissions(&submissions2).await.unwrap();
         pool.update_submissions_of_problems().await.unwrap();
         let shortest = get_from(&pool, Table::Shortest).await;
         assert_eq!(shortest.len(), 1);
-        assert_eq!(shortest[0].0, submissions2[0].contest_id);
-        assert_eq!(shortest[0].1, submissions2[0].problem_id);
+        assert_eq!(shortest[0].0, submissions2[0].contest_id.as_str());
+        assert_eq!(shortest[0].1, submissions2[0].problem_id.as_str());
         assert_eq!(shortest[0].2, submissions2[0].id);
     }
 
@@ -157,16 +157,16 @@ async fn test_problem_info_aggrefator() {
         pool.update_submissions_of_problems().await.unwrap();
         let fastest = get_from(&pool, Table::Fastest).await;
         assert_eq!(fastest.len(), 1);
-        assert_eq!(fastest[0].0, submissions2[0].contest_id);
-        assert_eq!(fastest[0].1, submissions2[0].problem_id);
+        assert_eq!(fastest[0].0, submissions2[0].contest_id.as_str());
+        assert_eq!(fastest[0].1, submissions2[0].problem_id.as_str());
         assert_eq!(fastest[0].2, submissions2[0].id);
 
         pool.update_submissions(&submissions1).await.unwrap();
         pool.update_submissions_of_problems().await.unwrap();
         let fastest = get_from(&pool, Table::Fastest).await;
         assert_eq!(fastest.len(), 1);
-        assert_eq!(fastest[0].0, submissions1[0].contest_id);
-        assert_eq!(fastest[0].1, submissions1[0].problem_id);
+        assert_eq!(fastest[0].0, submissions1[0].contest_id.as_str());
+        assert_eq!(fastest[0].1, submissions1[0].problem_id.as_str());
         assert_eq!(fastest[0].2, submissions1[0].id);
     }
 }
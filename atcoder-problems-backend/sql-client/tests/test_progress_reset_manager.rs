@@ -1,5 +1,6 @@
+use sql_client::ids::ProblemId;
 use sql_client::internal::progress_reset_manager::{
-    ProgressResetItem, ProgressResetList, ProgressResetManager,
+    load_progress_resets, ProgressResetItem, ProgressResetList, ProgressResetManager,
 };
 
 mod utils;
@@ -8,7 +9,7 @@ mod utils;
 async fn test_progress_reset_manager() {
     let internal_user_id = "user_id";
     let atcoder_user_id = "atcoder_id";
-    let problem_id = "problem_id";
+    let problem_id = &ProblemId::from("problem_id");
     let reset_epoch_second = 42;
     let pool = utils::initialize_and_connect_to_test_sql().await;
     utils::setup_internal_user(&pool, internal_user_id, atcoder_user_id).await;
@@ -71,3 +72,59 @@ async fn test_progress_reset_manager() {
         "The list should not have any items, but still has."
     );
 }
+
+#[async_std::test]
+async fn test_solved_problems_after_reset() {
+    let internal_user_id = "user_id";
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    utils::setup_internal_user(&pool, internal_user_id, "AtCoder_ID").await;
+    sqlx::query(
+        r"
+        INSERT INTO submissions
+            (id, epoch_second, problem_id, contest_id, user_id, language, point, length, result)
+        VALUES
+            (1, 100, 'problem1', 'contest1', 'atcoder_id', 'language1', 1.0, 1, 'AC'),
+            (2, 100, 'problem2', 'contest1', 'atcoder_id', 'language1', 1.0, 1, 'AC'),
+            (3, 300, 'problem2', 'contest1', 'atcoder_id', 'language1', 1.0, 1, 'AC'),
+            (4, 100, 'problem3', 'contest1', 'atcoder_id', 'language1', 1.0, 1, 'AC'),
+            (5, 300, 'problem3', 'contest1', 'atcoder_id', 'language1', 1.0, 1, 'WA'),
+            (6, 300, 'problem4', 'contest1', 'atcoder_id', 'language1', 1.0, 1, 'WA'),
+            (7, 300, 'problem4', 'contest1', 'another_id', 'language1', 1.0, 1, 'AC');
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let solved = pool
+        .get_solved_problems_after_reset(internal_user_id)
+        .await
+        .unwrap();
+    assert_eq!(solved, vec!["problem1", "problem2", "problem3"]);
+
+    pool.add_item(internal_user_id, &ProblemId::from("problem2"), 200)
+        .await
+        .unwrap();
+    pool.add_item(internal_user_id, &ProblemId::from("problem3"), 200)
+        .await
+        .unwrap();
+    let solved = pool
+        .get_solved_problems_after_reset(internal_user_id)
+        .await
+        .unwrap();
+    assert_eq!(solved, vec!["problem1", "problem2"]);
+}
+
+#[async_std::test]
+async fn test_load_progress_resets() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    utils::setup_internal_user(&pool, "user_id", "AtCoder_ID").await;
+    pool.add_item("user_id", &ProblemId::from("problem1"), 200)
+        .await
+        .unwrap();
+
+    let resets = load_progress_resets(&pool).await.unwrap();
+    assert!(resets.is_reset("atcoder_id", "problem1", 200));
+    assert!(!resets.is_reset("atcoder_id", "problem1", 300));
+    assert!(!resets.is_reset("atcoder_id", "problem2", 100));
+}
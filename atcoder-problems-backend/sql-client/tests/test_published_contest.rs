@@ -0,0 +1,29 @@
+use sql_client::ids::ContestId;
+use sql_client::published_contest::PublishedContestClient;
+
+mod utils;
+
+#[async_std::test]
+async fn test_published_contests() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert!(pool.load_published_contest_ids().await.unwrap().is_empty());
+
+    pool.insert_published_contest_ids(&[ContestId::from("abc001"), ContestId::from("abc002")])
+        .await
+        .unwrap();
+    pool.insert_published_contest_ids(&[ContestId::from("abc002"), ContestId::from("abc003")])
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.load_published_contest_ids()
+            .await
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec![
+            ContestId::from("abc001"),
+            ContestId::from("abc002"),
+            ContestId::from("abc003")
+        ]
+    );
+}
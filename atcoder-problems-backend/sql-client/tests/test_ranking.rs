@@ -0,0 +1,230 @@
+use sql_client::ids::UserId;
+use sql_client::models::{RankingCursor, RankingEntry};
+use sql_client::ranking::{RankingClient, RankingKind};
+
+mod utils;
+
+fn entry(rank: i64, user_id: &str, value: f64) -> RankingEntry {
+    RankingEntry {
+        rank,
+        user_id: user_id.to_owned(),
+        value,
+    }
+}
+
+#[async_std::test]
+async fn test_ranking() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"
+        INSERT INTO accepted_count (user_id, problem_count) VALUES
+        ('u1', 10),
+        ('u4', 20),
+        ('u3', 10),
+        ('u2', 30)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r"
+        INSERT INTO language_count (user_id, simplified_language, problem_count) VALUES
+        ('u1', 'Rust', 5),
+        ('u2', 'Rust', 5),
+        ('u2', 'C++', 100)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let ranking = pool
+        .load_ranking(RankingKind::AcceptedCount, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(
+        ranking,
+        vec![
+            entry(1, "u2", 30.0),
+            entry(2, "u4", 20.0),
+            entry(3, "u1", 10.0),
+            entry(3, "u3", 10.0),
+        ]
+    );
+
+    // The users with the same value keep the rank even when the page is split between them.
+    let ranking = pool
+        .load_ranking(RankingKind::AcceptedCount, 3, 10)
+        .await
+        .unwrap();
+    assert_eq!(ranking, vec![entry(3, "u3", 10.0)]);
+
+    let ranking = pool
+        .load_ranking(RankingKind::LanguageCount { language: "Rust" }, 0, 10)
+        .await
+        .unwrap();
+    assert_eq!(ranking, vec![entry(1, "u1", 5.0), entry(1, "u2", 5.0)]);
+
+    let ranking = pool.load_ranking(RankingKind::Streak, 0, 10).await.unwrap();
+    assert!(ranking.is_empty());
+
+    let kind = RankingKind::AcceptedCount;
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u2"))
+        .await
+        .unwrap();
+    assert_eq!(position, Some(0));
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u1"))
+        .await
+        .unwrap();
+    assert_eq!(position, Some(2));
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u3"))
+        .await
+        .unwrap();
+    assert_eq!(position, Some(3));
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u5"))
+        .await
+        .unwrap();
+    assert_eq!(position, None);
+
+    let kind = RankingKind::LanguageCount { language: "Rust" };
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u2"))
+        .await
+        .unwrap();
+    assert_eq!(position, Some(1));
+    let kind = RankingKind::LanguageCount { language: "C++" };
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u2"))
+        .await
+        .unwrap();
+    assert_eq!(position, Some(0));
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u1"))
+        .await
+        .unwrap();
+    assert_eq!(position, None);
+}
+
+#[async_std::test]
+async fn test_current_streak_ranking() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"
+        INSERT INTO current_streaks (user_id, streak, last_epoch_second) VALUES
+        ('u1', 10, 100),
+        ('u2', 20, 200),
+        ('u3', 30, 99),
+        ('u4', 10, 300)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let kind = RankingKind::CurrentStreak { alive_since: 100 };
+    let ranking = pool.load_ranking(kind, 0, 10).await.unwrap();
+    assert_eq!(
+        ranking,
+        vec![
+            entry(1, "u2", 20.0),
+            entry(2, "u1", 10.0),
+            entry(2, "u4", 10.0),
+        ]
+    );
+
+    let ranking = pool.load_ranking_after(kind, None, 2).await.unwrap();
+    assert_eq!(ranking, vec![entry(1, "u2", 20.0), entry(2, "u1", 10.0)]);
+
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u4"))
+        .await
+        .unwrap();
+    assert_eq!(position, Some(2));
+    let position = pool
+        .get_ranking_position(kind, &UserId::from("u3"))
+        .await
+        .unwrap();
+    assert_eq!(position, None);
+}
+
+#[async_std::test]
+async fn test_ranking_cursor() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"
+        INSERT INTO rated_point_sum (user_id, point_sum) VALUES
+        ('u1', 100.5),
+        ('u2', 300),
+        ('u3', 100.5),
+        ('u4', 200),
+        ('u5', 100.5)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r"
+        INSERT INTO language_count (user_id, simplified_language, problem_count) VALUES
+        ('u1', 'Rust', 5),
+        ('u2', 'Rust', 5),
+        ('u3', 'Rust', 3),
+        ('u2', 'C++', 100)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let kind = RankingKind::RatedPointSum;
+    let ranking = pool.load_ranking_after(kind, None, 3).await.unwrap();
+    assert_eq!(
+        ranking,
+        vec![
+            entry(1, "u2", 300.0),
+            entry(2, "u4", 200.0),
+            entry(3, "u1", 100.5),
+        ]
+    );
+
+    // The users with the same value as the last entry keep its rank on the next page.
+    let cursor = RankingCursor::after(0, &ranking).unwrap();
+    assert_eq!(
+        cursor,
+        RankingCursor {
+            position: 3,
+            rank: 3,
+            user_id: "u1".to_owned(),
+            value: 100.5,
+        }
+    );
+    let ranking = pool
+        .load_ranking_after(kind, Some(&cursor), 3)
+        .await
+        .unwrap();
+    assert_eq!(ranking, vec![entry(3, "u3", 100.5), entry(3, "u5", 100.5)]);
+
+    let cursor = RankingCursor::after(cursor.position, &ranking).unwrap();
+    assert_eq!(cursor.position, 5);
+    let ranking = pool
+        .load_ranking_after(kind, Some(&cursor), 3)
+        .await
+        .unwrap();
+    assert!(ranking.is_empty());
+    assert_eq!(RankingCursor::after(cursor.position, &ranking), None);
+
+    let kind = RankingKind::LanguageCount { language: "Rust" };
+    let ranking = pool.load_ranking_after(kind, None, 1).await.unwrap();
+    assert_eq!(ranking, vec![entry(1, "u1", 5.0)]);
+    let cursor = RankingCursor::after(0, &ranking).unwrap();
+    let ranking = pool
+        .load_ranking_after(kind, Some(&cursor), 10)
+        .await
+        .unwrap();
+    assert_eq!(ranking, vec![entry(1, "u2", 5.0), entry(3, "u3", 3.0)]);
+}
@@ -1,3 +1,4 @@
+use sql_client::ids::UserId;
 use sql_client::models::{Contest, ContestProblem, Submission, UserSum};
 use sql_client::rated_point_sum::RatedPointSumClient;
 use sql_client::PgPool;
@@ -21,41 +22,41 @@ const SAME_CONTEST_RATED: &str = "same_contest_rated";
 
 async fn setup_contests(pool: &PgPool) {
     let contests = vec![
-        Contest {
-            id: RATED_CONTEST.to_string(),
-            start_epoch_second: FIRST_AGC_EPOCH_SECOND,
-            duration_second: 1000,
-            title: "Rated Contest".to_string(),
-            rate_change: "All".to_string(),
-        },
-        Contest {
-            id: UNRATED_CONTEST1.to_string(),
-            start_epoch_second: 0,
-            duration_second: 1000,
-            title: "Unrated Old Contest".to_string(),
-            rate_change: "All".to_string(),
-        },
-        Contest {
-            id: UNRATED_CONTEST2.to_string(),
-            start_epoch_second: FIRST_AGC_EPOCH_SECOND,
-            duration_second: 1000,
-            title: "Unrated New Contest".to_string(),
-            rate_change: UNRATED_STATE.to_string(),
-        },
-        Contest {
-            id: SAME_CONTEST_RATED.to_string(),
-            start_epoch_second: FIRST_AGC_EPOCH_SECOND,
-            duration_second: 1000,
-            title: "Unrated New Contest".to_string(),
-            rate_change: "All".to_string(),
-        },
-        Contest {
-            id: SAME_CONTEST_UNRATED.to_string(),
-            start_epoch_second: FIRST_AGC_EPOCH_SECOND,
-            duration_second: 1000,
-            title: "Unrated New Contest".to_string(),
-            rate_change: UNRATED_STATE.to_string(),
-        },
+        Contest::new(
+            RATED_CONTEST.to_string(),
+            FIRST_AGC_EPOCH_SECOND,
+            1000,
+            "Rated Contest".to_string(),
+            "All".to_string(),
+        ),
+        Contest::new(
+            UNRATED_CONTEST1.to_string(),
+            0,
+            1000,
+            "Unrated Old Contest".to_string(),
+            "All".to_string(),
+        ),
+        Contest::new(
+            UNRATED_CONTEST2.to_string(),
+            FIRST_AGC_EPOCH_SECOND,
+            1000,
+            "Unrated New Contest".to_string(),
+            UNRATED_STATE.to_string(),
+        ),
+        Contest::new(
+            SAME_CONTEST_RATED.to_string(),
+            FIRST_AGC_EPOCH_SECOND,
+            1000,
+            "Unrated New Contest".to_string(),
+            "All".to_string(),
+        ),
+        Contest::new(
+            SAME_CONTEST_UNRATED.to_string(),
+            FIRST_AGC_EPOCH_SECOND,
+            1000,
+            "Unrated New Contest".to_string(),
+            UNRATED_STATE.to_string(),
+        ),
     ];
     for contest in contests {
         sqlx::query(
@@ -79,28 +80,28 @@ async fn setup_contests(pool: &PgPool) {
 async fn setup_contest_problems(pool: &PgPool) {
     let problems = vec![
         ContestProblem {
-            problem_id: "problem1".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem1".into(),
+            contest_id: RATED_CONTEST.into(),
         },
         ContestProblem {
-            problem_id: "problem2".to_string(),
-            contest_id: UNRATED_CONTEST1.to_string(),
+            problem_id: "problem2".into(),
+            contest_id: UNRATED_CONTEST1.into(),
         },
         ContestProblem {
-            problem_id: "problem3".to_string(),
-            contest_id: UNRATED_CONTEST1.to_string(),
+            problem_id: "problem3".into(),
+            contest_id: UNRATED_CONTEST1.into(),
         },
         ContestProblem {
-            problem_id: "problem4".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem4".into(),
+            contest_id: RATED_CONTEST.into(),
         },
         ContestProblem {
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_RATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_RATED.into(),
         },
         ContestProblem {
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_UNRATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_UNRATED.into(),
         },
     ];
 
@@ -129,50 +130,50 @@ async fn test_update_rated_point_sum() {
     let submissions = vec![
         Submission {
             id: 0,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem1".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem1".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 1,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem1".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem1".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 2,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem2".to_string(),
-            contest_id: UNRATED_CONTEST1.to_string(),
+            problem_id: "problem2".into(),
+            contest_id: UNRATED_CONTEST1.into(),
             ..Default::default()
         },
         Submission {
             id: 3,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem3".to_string(),
-            contest_id: UNRATED_CONTEST2.to_string(),
+            problem_id: "problem3".into(),
+            contest_id: UNRATED_CONTEST2.into(),
             ..Default::default()
         },
         Submission {
             id: 4,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem4".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem4".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 5,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_UNRATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_UNRATED.into(),
             ..Default::default()
         },
     ];
@@ -191,14 +192,17 @@ async fn test_update_rated_point_sum() {
     assert_eq!(sums[0].user_id, USER_ID.to_string());
     assert_eq!(sums[0].point_sum, 300.0);
     assert_eq!(
-        pool.get_users_rated_point_sum(USER_ID).await.unwrap(),
-        300.0
+        pool.get_users_rated_point_sum(&UserId::from(USER_ID))
+            .await
+            .unwrap(),
+        Some(300.0)
     );
     assert_eq!(pool.get_rated_point_sum_rank(300.0).await.unwrap(), 0);
 
     assert!(pool
-        .get_users_rated_point_sum("non_existing_user")
+        .get_users_rated_point_sum(&UserId::from("non_existing_user"))
         .await
+        .unwrap()
         .is_none());
 }
 
@@ -212,66 +216,66 @@ async fn test_load_rated_point_sum_in_range() {
     let submissions = vec![
         Submission {
             id: 0,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem1".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem1".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 1,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem4".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem4".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 2,
-            user_id: USER_ID.to_string(),
+            user_id: USER_ID.into(),
             point: 100.0,
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_UNRATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_UNRATED.into(),
             ..Default::default()
         },
         Submission {
             id: 3,
-            user_id: USER_ID2.to_string(),
+            user_id: USER_ID2.into(),
             point: 100.0,
-            problem_id: "problem4".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem4".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 4,
-            user_id: USER_ID2.to_string(),
+            user_id: USER_ID2.into(),
             point: 100.0,
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_UNRATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_UNRATED.into(),
             ..Default::default()
         },
         Submission {
             id: 5,
-            user_id: USER_ID3.to_string(),
+            user_id: USER_ID3.into(),
             point: 100.0,
-            problem_id: "problem4".to_string(),
-            contest_id: RATED_CONTEST.to_string(),
+            problem_id: "problem4".into(),
+            contest_id: RATED_CONTEST.into(),
             ..Default::default()
         },
         Submission {
             id: 6,
-            user_id: USER_ID3.to_string(),
+            user_id: USER_ID3.into(),
             point: 100.0,
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_UNRATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_UNRATED.into(),
             ..Default::default()
         },
         Submission {
             id: 7,
-            user_id: USER_ID4.to_string(),
+            user_id: USER_ID4.into(),
             point: 100.0,
-            problem_id: "problem5".to_string(),
-            contest_id: SAME_CONTEST_UNRATED.to_string(),
+            problem_id: "problem5".into(),
+            contest_id: SAME_CONTEST_UNRATED.into(),
             ..Default::default()
         },
     ];
@@ -1,3 +1,5 @@
+use sql_client::contest_category::ContestCategory;
+use sql_client::ids::ContestId;
 use sql_client::models::{Contest, Problem};
 use sql_client::simple_client::SimpleClient;
 
@@ -8,11 +10,12 @@ async fn test_insert_contests() {
     let pool = utils::initialize_and_connect_to_test_sql().await;
     assert!(pool.load_contests().await.unwrap().is_empty());
     pool.insert_contests(&vec![Contest {
-        id: "contest1".to_string(),
+        id: "contest1".into(),
         start_epoch_second: 0,
         duration_second: 0,
         title: "".to_string(),
         rate_change: "".to_string(),
+        ..Default::default()
     }])
     .await
     .unwrap();
@@ -21,36 +24,140 @@ async fn test_insert_contests() {
     assert_eq!(contests[0].id.as_str(), "contest1");
 
     pool.insert_contests(&vec![Contest {
-        id: "contest1".to_string(),
+        id: "contest1".into(),
         start_epoch_second: 0,
         duration_second: 0,
         title: "".to_string(),
         rate_change: "".to_string(),
+        ..Default::default()
     }])
     .await
     .unwrap();
 }
 
+#[async_std::test]
+async fn test_contest_derived_fields() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_contests(&[
+        Contest::new(
+            "abc200".to_owned(),
+            1_620_475_200,
+            6000,
+            "AtCoder Beginner Contest 200".to_owned(),
+            " ~ 1999".to_owned(),
+        ),
+        Contest::new(
+            "practice".to_owned(),
+            0,
+            6000,
+            "Practice".to_owned(),
+            "-".to_owned(),
+        ),
+    ])
+    .await
+    .unwrap();
+
+    let mut contests = pool.load_contests().await.unwrap();
+    contests.sort_by(|a, b| a.id.cmp(&b.id));
+    assert_eq!(contests[0].category, ContestCategory::Abc);
+    assert_eq!(contests[0].rated_range, Some((0, Some(1999))));
+    assert_eq!(contests[1].category, ContestCategory::OtherContests);
+    assert_eq!(contests[1].rated_range, None);
+}
+
+#[async_std::test]
+async fn test_update_derived_contest_columns() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"
+        INSERT INTO contests (id, start_epoch_second, duration_second, title, rate_change)
+        VALUES ('arc100', 1530403200, 6000, 'AtCoder Regular Contest 100', ' ~ 2799')
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let contests = pool.load_contests().await.unwrap();
+    assert_eq!(contests[0].category, ContestCategory::Arc);
+    assert_eq!(contests[0].rated_range, Some((0, Some(2799))));
+
+    pool.update_derived_contest_columns().await.unwrap();
+    let (category, rated_upper_bound): (String, Option<i64>) =
+        sqlx::query_as("SELECT category, rated_upper_bound FROM contests WHERE id = 'arc100'")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(category, ContestCategory::Arc.as_str());
+    assert_eq!(rated_upper_bound, Some(2799));
+}
+
+#[async_std::test]
+async fn test_update_contest_rules() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let contest = Contest::new(
+        "abc200".to_owned(),
+        1_620_475_200,
+        6000,
+        "AtCoder Beginner Contest 200".to_owned(),
+        " ~ 1999".to_owned(),
+    );
+    pool.insert_contests(&[contest.clone()]).await.unwrap();
+    let contests = pool.load_contests().await.unwrap();
+    assert_eq!(contests[0].penalty_minutes, None);
+    assert_eq!(contests[0].allows_late_join, None);
+
+    pool.update_contest_rules(&ContestId::from("abc200"), 5, true)
+        .await
+        .unwrap();
+    let contests = pool.load_contests().await.unwrap();
+    assert_eq!(contests[0].penalty_minutes, Some(5));
+    assert_eq!(contests[0].allows_late_join, Some(true));
+
+    // The rules are kept when the contest list is crawled again.
+    pool.insert_contests(&[contest]).await.unwrap();
+    let contests = pool.load_contests().await.unwrap();
+    assert_eq!(contests[0].penalty_minutes, Some(5));
+}
+
 #[async_std::test]
 async fn test_insert_problems() {
     let pool = utils::initialize_and_connect_to_test_sql().await;
     assert!(pool.load_problems().await.unwrap().is_empty());
     pool.insert_problems(&vec![Problem {
-        id: "problem1".to_string(),
-        contest_id: "".to_string(),
+        id: "problem1".into(),
+        contest_id: "".into(),
         title: "".to_string(),
+        ..Default::default()
     }])
     .await
     .unwrap();
 
     let problems = pool.load_problems().await.unwrap();
     assert_eq!(problems[0].id.as_str(), "problem1");
+    assert_eq!(problems[0].problem_index.as_str(), "");
 
+    let problem = Problem {
+        id: "problem1".into(),
+        contest_id: "".into(),
+        title: "Ex. Name".to_string(),
+        problem_index: "Ex".to_string(),
+        name: "Name".to_string(),
+    };
+    pool.insert_problems(&[problem]).await.unwrap();
+    let problems = pool.load_problems().await.unwrap();
+    assert_eq!(problems[0].problem_index.as_str(), "Ex");
+    assert_eq!(problems[0].name.as_str(), "Name");
+
+    // The sources without the index don't clear it.
     pool.insert_problems(&vec![Problem {
-        id: "problem1".to_string(),
-        contest_id: "".to_string(),
+        id: "problem1".into(),
+        contest_id: "".into(),
         title: "".to_string(),
+        ..Default::default()
     }])
     .await
     .unwrap();
+    let problems = pool.load_problems().await.unwrap();
+    assert_eq!(problems[0].problem_index.as_str(), "Ex");
 }
@@ -0,0 +1,21 @@
+use sql_client::sink_cursor::SinkCursorClient;
+
+mod utils;
+
+#[async_std::test]
+async fn test_sink_cursor() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert_eq!(pool.load_sink_cursor("bigquery").await.unwrap(), None);
+
+    pool.save_sink_cursor("bigquery", (100, 1)).await.unwrap();
+    pool.save_sink_cursor("bigquery", (200, 2)).await.unwrap();
+    pool.save_sink_cursor("other", (300, 3)).await.unwrap();
+    assert_eq!(
+        pool.load_sink_cursor("bigquery").await.unwrap(),
+        Some((200, 2))
+    );
+    assert_eq!(
+        pool.load_sink_cursor("other").await.unwrap(),
+        Some((300, 3))
+    );
+}
@@ -1,3 +1,4 @@
+use sql_client::ids::UserId;
 use sql_client::models::UserStreak;
 use sql_client::streak::StreakUpdater;
 use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
@@ -40,5 +41,45 @@ async fn test_update_streak_ranking() {
 
     assert_eq!(v.len(), 1);
     assert_eq!(v[0].streak, 2);
-}
 
+    assert_eq!(
+        pool.get_users_max_streak(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        Some(2)
+    );
+    assert_eq!(
+        pool.get_users_max_streak(&UserId::from("user2"))
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(pool.get_max_streak_rank(2).await.unwrap(), 0);
+    assert_eq!(pool.get_max_streak_rank(1).await.unwrap(), 1);
+
+    let (streak, last_epoch_second) = sqlx::query(
+        "SELECT streak, last_epoch_second FROM current_streaks WHERE user_id = 'user1'",
+    )
+    .map(|row: PgRow| {
+        let streak: i64 = row.get("streak");
+        let last_epoch_second: i64 = row.get("last_epoch_second");
+        (streak, last_epoch_second)
+    })
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(streak, 2);
+    assert_eq!(last_epoch_second, 1570201200);
+    assert_eq!(
+        pool.get_users_current_streak(&UserId::from("user1"), last_epoch_second)
+            .await
+            .unwrap(),
+        Some(2)
+    );
+    assert_eq!(
+        pool.get_users_current_streak(&UserId::from("user1"), last_epoch_second + 1)
+            .await
+            .unwrap(),
+        None
+    );
+}
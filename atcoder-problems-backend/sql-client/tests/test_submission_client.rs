@@ -1,3 +1,4 @@
+use sql_client::ids::{ContestId, UserId};
 use sql_client::models::Submission;
 use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
 
@@ -23,15 +24,21 @@ async fn test_submission_client() {
     .await
     .unwrap();
 
-    let request = SubmissionRequest::UserAll { user_id: "usEr1" };
+    let request = SubmissionRequest::UserAll {
+        user_id: &UserId::from("usEr1"),
+    };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 3);
 
-    let request = SubmissionRequest::UserAll { user_id: "user2" };
+    let request = SubmissionRequest::UserAll {
+        user_id: &UserId::from("user2"),
+    };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 1);
 
-    let request = SubmissionRequest::UserAll { user_id: "user3" };
+    let request = SubmissionRequest::UserAll {
+        user_id: &UserId::from("user3"),
+    };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 0);
 
@@ -72,8 +79,27 @@ async fn test_submission_client() {
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 1);
 
+    let request = SubmissionRequest::FromCursor {
+        from_second: 200,
+        from_id: 2,
+        count: 10,
+    };
+    let submissions = pool.get_submissions(request).await.unwrap();
+    assert_eq!(submissions.len(), 2);
+    assert_eq!(submissions[0].id, 3);
+    assert_eq!(submissions[1].id, 4);
+
+    let request = SubmissionRequest::FromCursor {
+        from_second: 200,
+        from_id: 1,
+        count: 1,
+    };
+    let submissions = pool.get_submissions(request).await.unwrap();
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].id, 2);
+
     let request = SubmissionRequest::FromUserAndTime {
-        user_id: "usEr1",
+        user_id: &UserId::from("usEr1"),
         from_second: 300,
         count: 1000,
     };
@@ -83,7 +109,7 @@ async fn test_submission_client() {
     assert_eq!(submissions[1].result, "AC".to_owned());
 
     let request = SubmissionRequest::FromUserAndTime {
-        user_id: "usEr1",
+        user_id: &UserId::from("usEr1"),
         from_second: 300,
         count: 1,
     };
@@ -91,28 +117,58 @@ async fn test_submission_client() {
     assert_eq!(submissions.len(), 1);
 
     let request = SubmissionRequest::FromUserAndTime {
-        user_id: "user3",
+        user_id: &UserId::from("user3"),
         from_second: 300,
         count: 1000,
     };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 0);
 
+    let request = SubmissionRequest::FromUserAndCursor {
+        user_id: &UserId::from("usEr1"),
+        from_second: 300,
+        from_id: 3,
+        count: 1000,
+    };
+    let submissions = pool.get_submissions(request).await.unwrap();
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].id, 4);
+
+    let request = SubmissionRequest::FromUserAndCursor {
+        user_id: &UserId::from("user1"),
+        from_second: 300,
+        from_id: 2,
+        count: 1,
+    };
+    let submissions = pool.get_submissions(request).await.unwrap();
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].id, 3);
+
     let request = SubmissionRequest::UsersAccepted {
-        user_ids: &["user1", "user2"],
+        user_ids: &[UserId::from("user1"), UserId::from("user2")],
     };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 3);
 
     let request = SubmissionRequest::UsersAccepted {
-        user_ids: &["user1"],
+        user_ids: &[UserId::from("user1")],
     };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 2);
 
     pool.update_submission_count().await.unwrap();
-    assert_eq!(pool.get_user_submission_count("user1").await.unwrap(), 3);
-    assert_eq!(pool.get_user_submission_count("user2").await.unwrap(), 1);
+    assert_eq!(
+        pool.get_user_submission_count(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        pool.get_user_submission_count(&UserId::from("user2"))
+            .await
+            .unwrap(),
+        1
+    );
 
     let submissions = pool
         .get_submissions(SubmissionRequest::AllAccepted)
@@ -123,6 +179,17 @@ async fn test_submission_client() {
     assert_eq!(pool.count_stored_submissions(&[1]).await.unwrap(), 1);
     assert_eq!(pool.count_stored_submissions(&[9]).await.unwrap(), 0);
 
+    let stats = pool
+        .get_contest_submission_stats(&ContestId::from("contest1"))
+        .await
+        .unwrap();
+    assert_eq!(stats, (6, Some(6)));
+    let stats = pool
+        .get_contest_submission_stats(&ContestId::from("contest2"))
+        .await
+        .unwrap();
+    assert_eq!(stats, (0, None));
+
     let request = SubmissionRequest::InvalidResult { from_second: 1 };
     let submissions = pool.get_submissions(request).await.unwrap();
     assert_eq!(submissions.len(), 2);
@@ -147,9 +214,19 @@ async fn test_update_submission_count() {
     .await
     .unwrap();
 
-    assert!(pool.get_user_submission_count("user1").await.is_err());
-    pool.update_user_submission_count("user1").await.unwrap();
-    assert_eq!(pool.get_user_submission_count("user1").await.unwrap(), 1);
+    assert!(pool
+        .get_user_submission_count(&UserId::from("user1"))
+        .await
+        .is_err());
+    pool.update_user_submission_count(&UserId::from("user1"))
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.get_user_submission_count(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        1
+    );
 }
 
 #[async_std::test]
@@ -157,7 +234,7 @@ async fn test_update_submissions() {
     let pool = utils::initialize_and_connect_to_test_sql().await;
     pool.update_submissions(&[Submission {
         id: 0,
-        user_id: "old_user_name".to_owned(),
+        user_id: "old_user_name".into(),
         result: "WJ".to_owned(),
         point: 0.0,
         execution_time: None,
@@ -168,19 +245,19 @@ async fn test_update_submissions() {
 
     let submissions = pool
         .get_submissions(SubmissionRequest::UserAll {
-            user_id: "old_user_name",
+            user_id: &UserId::from("old_user_name"),
         })
         .await
         .unwrap();
     assert_eq!(submissions.len(), 1);
-    assert_eq!(submissions[0].user_id, "old_user_name".to_owned());
+    assert_eq!(submissions[0].user_id.as_str(), "old_user_name");
     assert_eq!(submissions[0].result, "WJ".to_owned());
     assert_eq!(submissions[0].point, 0.0);
     assert_eq!(submissions[0].execution_time, None);
 
     let submissions = pool
         .get_submissions(SubmissionRequest::UserAll {
-            user_id: "new_user_name",
+            user_id: &UserId::from("new_user_name"),
         })
         .await
         .unwrap();
@@ -188,7 +265,7 @@ async fn test_update_submissions() {
 
     pool.update_submissions(&[Submission {
         id: 0,
-        user_id: "new_user_name".to_owned(),
+        user_id: "new_user_name".into(),
         result: "AC".to_owned(),
         point: 100.0,
         execution_time: Some(1),
@@ -199,7 +276,7 @@ async fn test_update_submissions() {
 
     let submissions = pool
         .get_submissions(SubmissionRequest::UserAll {
-            user_id: "old_user_name",
+            user_id: &UserId::from("old_user_name"),
         })
         .await
         .unwrap();
@@ -207,13 +284,43 @@ async fn test_update_submissions() {
 
     let submissions = pool
         .get_submissions(SubmissionRequest::UserAll {
-            user_id: "new_user_name",
+            user_id: &UserId::from("new_user_name"),
         })
         .await
         .unwrap();
     assert_eq!(submissions.len(), 1);
-    assert_eq!(submissions[0].user_id, "new_user_name".to_owned());
+    assert_eq!(submissions[0].user_id.as_str(), "new_user_name");
     assert_eq!(submissions[0].result, "AC".to_owned());
     assert_eq!(submissions[0].point, 100.0);
     assert_eq!(submissions[0].execution_time, Some(1));
 }
+
+#[async_std::test]
+async fn test_stream_all_submissions() {
+    use futures::TryStreamExt;
+    use sql_client::submission_client::{stream_all_submissions, stream_submissions_after};
+
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let submissions = [3, 1, 2]
+        .iter()
+        .map(|&id| Submission {
+            id,
+            ..Default::default()
+        })
+        .collect::<Vec<_>>();
+    pool.update_submissions(&submissions).await.unwrap();
+
+    let ids = stream_all_submissions(&pool)
+        .map_ok(|s| s.id)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    let ids = stream_submissions_after(&pool, 1)
+        .map_ok(|s| s.id)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![2, 3]);
+}
@@ -0,0 +1,58 @@
+use sql_client::models::Submission;
+use sql_client::submission_client::SubmissionClient;
+use sql_client::submission_listener::SubmissionListener;
+
+mod utils;
+
+#[async_std::test]
+async fn test_submission_listener() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    let mut listener = SubmissionListener::connect(&pool).await.unwrap();
+
+    let submission = Submission {
+        id: 1,
+        epoch_second: 100,
+        problem_id: "problem1".into(),
+        contest_id: "contest1".into(),
+        user_id: "user1".into(),
+        language: "language1".to_owned(),
+        point: 100.0,
+        length: 10,
+        result: "WJ".to_owned(),
+        execution_time: None,
+    };
+    pool.update_submissions(&[submission.clone()])
+        .await
+        .unwrap();
+    let received = listener.recv().await.unwrap();
+    assert_eq!(received.id, 1);
+    assert_eq!(received.user_id.as_str(), "user1");
+    assert_eq!(received.result, "WJ");
+    assert_eq!(received.execution_time, None);
+
+    // Submissions which are crawled again without any change are not notified.
+    pool.update_submissions(&[submission.clone()])
+        .await
+        .unwrap();
+    pool.update_submissions(&[Submission {
+        result: "AC".to_owned(),
+        execution_time: Some(20),
+        ..submission.clone()
+    }])
+    .await
+    .unwrap();
+    let received = listener.recv().await.unwrap();
+    assert_eq!(received.id, 1);
+    assert_eq!(received.result, "AC");
+    assert_eq!(received.execution_time, Some(20));
+
+    let (receiver, _) = listener.spawn();
+    pool.update_submissions(&[Submission {
+        id: 2,
+        ..submission
+    }])
+    .await
+    .unwrap();
+    let received = receiver.recv().await.unwrap();
+    assert_eq!(received.id, 2);
+}
@@ -0,0 +1,98 @@
+use sql_client::contest_problem::ContestProblemClient;
+use sql_client::ids::UserId;
+use sql_client::models::{Contest, ContestProblem, Submission, TrainingVelocity};
+use sql_client::simple_client::SimpleClient;
+use sql_client::training_velocity::TrainingVelocityClient;
+use std::collections::BTreeMap;
+
+mod utils;
+
+const FIRST_AGC_EPOCH_SECOND: i64 = 1_468_670_400;
+const ONE_DAY_SECOND: i64 = 24 * 3600;
+
+#[async_std::test]
+async fn test_training_velocity() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    pool.insert_contests(&[Contest::new(
+        "abc100".to_owned(),
+        FIRST_AGC_EPOCH_SECOND,
+        6000,
+        "ABC 100".to_owned(),
+        " ~ 1199".to_owned(),
+    )])
+    .await
+    .unwrap();
+    pool.insert_contest_problem(&[ContestProblem {
+        contest_id: "abc100".into(),
+        problem_id: "abc100_a".into(),
+    }])
+    .await
+    .unwrap();
+
+    let now = FIRST_AGC_EPOCH_SECOND + 100 * ONE_DAY_SECOND;
+    let submissions = [
+        Submission {
+            id: 1,
+            epoch_second: now - ONE_DAY_SECOND,
+            problem_id: "abc100_a".into(),
+            user_id: "user1".into(),
+            point: 100.0,
+            ..Default::default()
+        },
+        Submission {
+            id: 2,
+            epoch_second: now - 20 * ONE_DAY_SECOND,
+            problem_id: "practice_a".into(),
+            user_id: "user1".into(),
+            point: 100.0,
+            ..Default::default()
+        },
+    ];
+    let mut difficulties = BTreeMap::new();
+    difficulties.insert("abc100_a".to_owned(), 500.0);
+
+    pool.update_training_velocity(&submissions, &difficulties, now)
+        .await
+        .unwrap();
+    assert_eq!(
+        pool.load_training_velocity(&UserId::from("user1"))
+            .await
+            .unwrap(),
+        vec![
+            TrainingVelocity {
+                user_id: "user1".to_owned(),
+                period_days: 7,
+                accepted_count: 1,
+                rated_point_sum: 100.0,
+                difficulty_counts: vec![0, 1, 0, 0, 0, 0, 0, 0],
+            },
+            TrainingVelocity {
+                user_id: "user1".to_owned(),
+                period_days: 30,
+                accepted_count: 2,
+                rated_point_sum: 100.0,
+                difficulty_counts: vec![0, 1, 0, 0, 0, 0, 0, 0],
+            },
+            TrainingVelocity {
+                user_id: "user1".to_owned(),
+                period_days: 90,
+                accepted_count: 2,
+                rated_point_sum: 100.0,
+                difficulty_counts: vec![0, 1, 0, 0, 0, 0, 0, 0],
+            },
+        ]
+    );
+
+    // The statistics of the user are recomputed from scratch.
+    pool.update_training_velocity(&submissions[1..], &difficulties, now)
+        .await
+        .unwrap();
+    let velocities = pool
+        .load_training_velocity(&UserId::from("user1"))
+        .await
+        .unwrap();
+    assert_eq!(velocities.len(), 2);
+    assert_eq!(velocities[0].period_days, 30);
+    assert_eq!(velocities[0].rated_point_sum, 0.0);
+    assert_eq!(velocities[0].difficulty_counts, vec![0; 8]);
+}
@@ -0,0 +1,110 @@
+use sql_client::ids::UserId;
+use sql_client::models::UserProfile;
+use sql_client::user_profile::UserProfileClient;
+
+mod utils;
+
+#[async_std::test]
+async fn test_user_profiles() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    assert_eq!(
+        pool.get_user_profile(&UserId::from("user1")).await.unwrap(),
+        None
+    );
+
+    let profile = UserProfile {
+        user_id: "user1".to_owned(),
+        rating: Some(1200),
+        highest_rating: Some(1600),
+        affiliation: Some("University".to_owned()),
+        country: Some("JP".to_owned()),
+        birth_year_bucket: Some(1990),
+        rated_matches: Some(10),
+    };
+    let unrated = UserProfile {
+        user_id: "user2".to_owned(),
+        ..Default::default()
+    };
+    assert_eq!(
+        pool.insert_user_profiles(&[profile.clone(), unrated.clone()])
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        pool.get_user_profile(&UserId::from("user1")).await.unwrap(),
+        Some(profile.clone())
+    );
+    assert_eq!(
+        pool.get_user_profile(&UserId::from("user2")).await.unwrap(),
+        Some(unrated)
+    );
+
+    let updated = UserProfile {
+        rating: Some(1300),
+        ..profile
+    };
+    pool.insert_user_profiles(&[updated.clone()]).await.unwrap();
+    assert_eq!(
+        pool.get_user_profile(&UserId::from("user1")).await.unwrap(),
+        Some(updated)
+    );
+}
+
+#[async_std::test]
+async fn test_load_user_ids_to_crawl_profiles() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sql_client::query(
+        r"
+        INSERT INTO accepted_count (user_id, problem_count) VALUES
+        ('user1', 10), ('user2', 20), ('user3', 30)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"
+        INSERT INTO users (user_id, updated_epoch_second) VALUES
+        ('user1', 200), ('user3', 100)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        pool.load_user_ids_to_crawl_profiles(10).await.unwrap(),
+        vec!["user2", "user3", "user1"]
+    );
+    assert_eq!(
+        pool.load_user_ids_to_crawl_profiles(1).await.unwrap(),
+        vec!["user2"]
+    );
+}
+
+#[async_std::test]
+async fn test_load_ratings_with_competitions() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sql_client::query(
+        r"
+        INSERT INTO users (user_id, rating, rated_matches) VALUES
+        ('user1', 1200, 2), ('not_crawled', 800, NULL), ('unrated', NULL, NULL)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(
+        pool.load_ratings_with_competitions(&[
+            UserId::from("user1"),
+            UserId::from("not_crawled"),
+            UserId::from("unrated"),
+            UserId::from("unknown")
+        ])
+        .await
+        .unwrap(),
+        vec![("unrated".to_owned(), 0, 0), ("user1".to_owned(), 1200, 2)]
+    );
+}
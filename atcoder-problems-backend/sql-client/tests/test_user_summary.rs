@@ -0,0 +1,60 @@
+use sql_client::models::UserSummary;
+use sql_client::user_summary::UserSummaryClient;
+
+mod utils;
+
+#[async_std::test]
+async fn test_load_user_summaries() {
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    sqlx::query(
+        r"
+        INSERT INTO accepted_count (user_id, problem_count)
+        VALUES ('user1', 10), ('user2', 20), ('user3', 10)
+        ",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO rated_point_sum (user_id, point_sum) VALUES ('user1', 300.0)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO max_streaks (user_id, streak) VALUES ('user1', 3), ('user2', 5)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let summaries = pool.load_user_summaries().await.unwrap();
+    assert_eq!(
+        summaries,
+        vec![
+            UserSummary {
+                user_id: "user2".to_owned(),
+                accepted_count: 20,
+                accepted_count_rank: 0,
+                rated_point_sum: 0.0,
+                rated_point_sum_rank: 1,
+                max_streak: 5,
+                max_streak_rank: 0,
+            },
+            UserSummary {
+                user_id: "user1".to_owned(),
+                accepted_count: 10,
+                accepted_count_rank: 1,
+                rated_point_sum: 300.0,
+                rated_point_sum_rank: 0,
+                max_streak: 3,
+                max_streak_rank: 1,
+            },
+            UserSummary {
+                user_id: "user3".to_owned(),
+                accepted_count: 10,
+                accepted_count_rank: 1,
+                rated_point_sum: 0.0,
+                rated_point_sum_rank: 1,
+                max_streak: 0,
+                max_streak_rank: 2,
+            },
+        ]
+    );
+}
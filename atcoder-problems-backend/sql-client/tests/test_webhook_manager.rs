@@ -0,0 +1,65 @@
+use sql_client::internal::webhook_manager::{WebhookFilter, WebhookManager};
+use sql_client::models::Submission;
+
+mod utils;
+
+#[async_std::test]
+async fn test_webhook_manager() {
+    let internal_user_id = "user_id";
+    let pool = utils::initialize_and_connect_to_test_sql().await;
+    utils::setup_internal_user(&pool, internal_user_id, "atcoder_id").await;
+
+    assert!(pool
+        .get_webhooks(internal_user_id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let filter = WebhookFilter {
+        user_id: Some("chokudai".to_owned()),
+        result: Some("AC".to_owned()),
+        contest_id: None,
+    };
+    let webhook = pool
+        .add_webhook(internal_user_id, "https://example.com/hook", &filter)
+        .await
+        .unwrap();
+    assert_eq!(webhook.url, "https://example.com/hook");
+    assert_eq!(webhook.filter, filter);
+    assert!(!webhook.secret.is_empty());
+    assert_eq!(
+        pool.get_webhooks(internal_user_id).await.unwrap(),
+        vec![webhook.clone()]
+    );
+
+    let mut submission = Submission {
+        user_id: "chokudai".into(),
+        result: "AC".to_owned(),
+        contest_id: "abc001".into(),
+        ..Default::default()
+    };
+    assert_eq!(
+        pool.load_matching_webhooks(&submission).await.unwrap(),
+        vec![webhook.clone()]
+    );
+    submission.result = "WA".to_owned();
+    assert!(pool
+        .load_matching_webhooks(&submission)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Only the owner can delete the webhook.
+    pool.delete_webhook("other_user_id", &webhook.id)
+        .await
+        .unwrap();
+    assert_eq!(pool.get_webhooks(internal_user_id).await.unwrap().len(), 1);
+    pool.delete_webhook(internal_user_id, &webhook.id)
+        .await
+        .unwrap();
+    assert!(pool
+        .get_webhooks(internal_user_id)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,383 @@
+use anyhow::{anyhow, bail, Result};
+use async_std::future::timeout;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sql_client::models::Submission;
+use sql_client::sink_cursor::SinkCursorClient;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::submission_listener::SubmissionListener;
+use sql_client::PgPool;
+use std::time::{Duration, Instant};
+
+const API_URL: &str = "https://bigquery.googleapis.com/bigquery/v2";
+/// The name of the cursor of the last submission sent to BigQuery.
+const CURSOR_NAME: &str = "bigquery";
+/// The submissions are buffered up to this number of batches while BigQuery fails, after which
+/// the sink restarts from the cursor instead.
+const MAX_BUFFERED_BATCHES: usize = 100;
+/// Issues the access tokens of the service account of the instance on Google Cloud.
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// The columns of the table, which are added to the existing table if they are missing.
+const SCHEMA: [(&str, &str, &str); 10] = [
+    ("id", "INTEGER", "REQUIRED"),
+    ("epoch_second", "INTEGER", "REQUIRED"),
+    ("problem_id", "STRING", "REQUIRED"),
+    ("contest_id", "STRING", "REQUIRED"),
+    ("user_id", "STRING", "REQUIRED"),
+    ("language", "STRING", "REQUIRED"),
+    ("point", "FLOAT", "REQUIRED"),
+    ("length", "INTEGER", "REQUIRED"),
+    ("result", "STRING", "REQUIRED"),
+    ("execution_time", "INTEGER", "NULLABLE"),
+];
+
+#[derive(Deserialize)]
+struct AccessToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Inserts the submissions into a BigQuery table with the streaming API.
+pub struct BigQuerySink {
+    table_url: String,
+    dataset_url: String,
+    table: String,
+    /// Used instead of the token of the instance if it is set.
+    access_token: Option<String>,
+    cached_token: Option<(String, Instant)>,
+}
+
+impl BigQuerySink {
+    pub fn new(project_id: &str, dataset: &str, table: &str, access_token: Option<&str>) -> Self {
+        let dataset_url = format!("{}/projects/{}/datasets/{}", API_URL, project_id, dataset);
+        Self {
+            table_url: format!("{}/tables/{}", dataset_url, table),
+            dataset_url,
+            table: table.to_owned(),
+            access_token: access_token.map(|token| token.to_owned()),
+            cached_token: None,
+        }
+    }
+
+    async fn token(&mut self) -> Result<String> {
+        if let Some(token) = &self.access_token {
+            return Ok(token.clone());
+        }
+        match &self.cached_token {
+            Some((token, expires)) if Instant::now() < *expires => return Ok(token.clone()),
+            _ => {}
+        }
+        let mut response = surf::get(METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .await
+            .map_err(|e| anyhow!("Failed to get the access token: {:?}", e))?;
+        if !response.status().is_success() {
+            bail!("The metadata server returned {}", response.status());
+        }
+        let token: AccessToken = response
+            .body_json()
+            .await
+            .map_err(|e| anyhow!("Failed to parse the access token: {:?}", e))?;
+        // Refreshes the token a minute before it expires.
+        let expires = Instant::now() + Duration::from_secs(token.expires_in.saturating_sub(60));
+        self.cached_token = Some((token.access_token.clone(), expires));
+        Ok(token.access_token)
+    }
+
+    async fn request(
+        &mut self,
+        method: surf::http::Method,
+        url: &str,
+        body: Option<Value>,
+    ) -> Result<Option<Value>> {
+        let token = self.token().await?;
+        let mut request = surf::RequestBuilder::new(method, surf::Url::parse(url)?)
+            .header("Authorization", format!("Bearer {}", token));
+        if let Some(body) = body {
+            request = request
+                .content_type(surf::http::mime::JSON)
+                .body(serde_json::to_vec(&body)?);
+        }
+        let mut response = request
+            .await
+            .map_err(|e| anyhow!("Failed to request {}: {:?}", url, e))?;
+        if response.status() == surf::StatusCode::NotFound {
+            return Ok(None);
+        }
+        let body = response.body_string().await.unwrap_or_default();
+        if !response.status().is_success() {
+            bail!("BigQuery returned {}: {}", response.status(), body);
+        }
+        Ok(Some(serde_json::from_str(&body)?))
+    }
+
+    /// Creates the table if it does not exist, or adds the missing columns to it.
+    pub async fn ensure_table(&mut self) -> Result<()> {
+        let table_url = self.table_url.clone();
+        let table = self
+            .request(surf::http::Method::Get, &table_url, None)
+            .await?;
+        match table {
+            None => {
+                let body = json!({
+                    "tableReference": { "tableId": self.table },
+                    "schema": { "fields": schema_fields(&[]) },
+                });
+                let url = format!("{}/tables", self.dataset_url);
+                self.request(surf::http::Method::Post, &url, Some(body))
+                    .await?;
+                log::info!("Created {}", self.table);
+            }
+            Some(table) => {
+                let existing = table["schema"]["fields"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                let fields = schema_fields(&existing);
+                if fields.len() > existing.len() {
+                    let body = json!({ "schema": { "fields": fields } });
+                    self.request(surf::http::Method::Patch, &table_url, Some(body))
+                        .await?;
+                    log::info!(
+                        "Added {} columns to {}",
+                        fields.len() - existing.len(),
+                        self.table
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the indexes of the rows rejected by BigQuery which should be retried.
+    pub async fn insert(&mut self, submissions: &[Submission]) -> Result<Vec<usize>> {
+        let url = format!("{}/insertAll", self.table_url);
+        let response = self
+            .request(
+                surf::http::Method::Post,
+                &url,
+                Some(insert_body(submissions)),
+            )
+            .await?
+            .ok_or_else(|| anyhow!("{} does not exist", self.table))?;
+        Ok(retried_indexes(&response))
+    }
+}
+
+/// The indexes of the rows in `insertErrors` of the response of `insertAll`. The rows which are
+/// invalid themselves are not retried since they are rejected again, but the other ones, e.g.
+/// the valid rows `stopped` by them, are retried.
+fn retried_indexes(response: &Value) -> Vec<usize> {
+    let errors = match response["insertErrors"].as_array() {
+        Some(errors) => errors,
+        None => return Vec::new(),
+    };
+    let mut indexes = Vec::new();
+    for error in errors {
+        let index = match error["index"].as_u64() {
+            Some(index) => index as usize,
+            None => continue,
+        };
+        let is_invalid = error["errors"]
+            .as_array()
+            .map_or(false, |e| e.iter().any(|e| e["reason"] == "invalid"));
+        if is_invalid {
+            log::error!("BigQuery rejected an invalid row: {}", error);
+        } else {
+            indexes.push(index);
+        }
+    }
+    indexes
+}
+
+/// The fields of the existing table followed by the missing ones, which are nullable since
+/// BigQuery adds only nullable columns to a table.
+fn schema_fields(existing: &[Value]) -> Vec<Value> {
+    let mut fields = existing.to_vec();
+    for (name, field_type, mode) in SCHEMA.iter() {
+        if existing.iter().any(|field| field["name"] == *name) {
+            continue;
+        }
+        let mode = if existing.is_empty() {
+            *mode
+        } else {
+            "NULLABLE"
+        };
+        fields.push(json!({ "name": name, "type": field_type, "mode": mode }));
+    }
+    fields
+}
+
+/// The body of `insertAll`. BigQuery drops the rows with the same `insertId` which are inserted
+/// within a minute, so a submission is inserted again when its result changes.
+fn insert_body(submissions: &[Submission]) -> Value {
+    let rows = submissions
+        .iter()
+        .map(|s| {
+            json!({
+                "insertId": format!("{}-{}", s.id, s.result),
+                "json": s,
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "rows": rows })
+}
+
+/// Inserts the buffered submissions by `batch_size`, and keeps the ones to be retried.
+async fn flush(sink: &mut BigQuerySink, buffer: &mut Vec<Submission>, batch_size: usize) {
+    let mut retried = Vec::new();
+    for chunk in buffer.chunks(batch_size) {
+        match sink.insert(chunk).await {
+            Ok(indexes) => {
+                retried.extend(indexes.into_iter().filter_map(|i| chunk.get(i).cloned()))
+            }
+            Err(e) => {
+                log::error!("Failed to insert {} submissions: {:?}", chunk.len(), e);
+                retried.extend_from_slice(chunk);
+            }
+        }
+    }
+    log::info!(
+        "Inserted {} submissions into BigQuery",
+        buffer.len() - retried.len()
+    );
+    *buffer = retried;
+}
+
+/// Inserts the submissions inserted or updated by the crawlers, by `batch_size` or every
+/// `flush_interval`, whichever comes first.
+///
+/// The failed submissions are kept and retried at the next flush. The last submission sent is
+/// saved as the cursor whenever all the buffered ones are sent, and the submissions after it are
+/// sent first after restarting, so that the new ones are not lost while the sink is stopped.
+/// The updates of the older submissions in the meantime, e.g. the rejudges, are not sent again.
+pub async fn stream_submissions(
+    pg_pool: PgPool,
+    sink: &mut BigQuerySink,
+    batch_size: usize,
+    flush_interval: Duration,
+) -> Result<()> {
+    sink.ensure_table().await?;
+    let (receiver, listening) = SubmissionListener::connect(&pg_pool).await?.spawn();
+
+    let mut cursor = pg_pool.load_sink_cursor(CURSOR_NAME).await?;
+    if let Some(mut from) = cursor {
+        loop {
+            let submissions = pg_pool
+                .get_submissions(SubmissionRequest::FromCursor {
+                    from_second: from.0,
+                    from_id: from.1,
+                    count: batch_size as i64,
+                })
+                .await?;
+            let last = match submissions.last() {
+                Some(last) => (last.epoch_second, last.id),
+                None => break,
+            };
+            let retried = sink.insert(&submissions).await?;
+            if !retried.is_empty() {
+                bail!("BigQuery rejected {} submissions", retried.len());
+            }
+            from = last;
+            pg_pool.save_sink_cursor(CURSOR_NAME, from).await?;
+            log::info!("Inserted the submissions until {} into BigQuery", from.0);
+        }
+        cursor = Some(from);
+    }
+
+    let mut buffer = Vec::<Submission>::new();
+    let mut flushed = Instant::now();
+    loop {
+        let wait = flush_interval
+            .checked_sub(flushed.elapsed())
+            .unwrap_or_default();
+        match timeout(wait, receiver.recv()).await {
+            Ok(Ok(submission)) => {
+                cursor = cursor.max(Some((submission.epoch_second, submission.id)));
+                buffer.push(submission);
+            }
+            Ok(Err(_)) => return listening.await,
+            Err(_) => {}
+        }
+        if buffer.len() >= batch_size || flushed.elapsed() >= flush_interval {
+            if !buffer.is_empty() {
+                flush(sink, &mut buffer, batch_size).await;
+                if buffer.is_empty() {
+                    if let Some(cursor) = cursor {
+                        pg_pool.save_sink_cursor(CURSOR_NAME, cursor).await?;
+                    }
+                } else if buffer.len() > batch_size * MAX_BUFFERED_BATCHES {
+                    bail!(
+                        "{} submissions are not inserted into BigQuery",
+                        buffer.len()
+                    );
+                }
+            }
+            flushed = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_fields() {
+        let created = schema_fields(&[]);
+        assert_eq!(created.len(), SCHEMA.len());
+        assert_eq!(
+            created[0],
+            json!({"name": "id", "type": "INTEGER", "mode": "REQUIRED"})
+        );
+
+        let existing = created[..8].to_vec();
+        let patched = schema_fields(&existing);
+        assert_eq!(patched[..8], existing[..]);
+        assert_eq!(
+            patched[8..],
+            [
+                json!({"name": "result", "type": "STRING", "mode": "NULLABLE"}),
+                json!({"name": "execution_time", "type": "INTEGER", "mode": "NULLABLE"}),
+            ]
+        );
+        assert_eq!(schema_fields(&created), created);
+    }
+
+    #[test]
+    fn test_retried_indexes() {
+        assert!(
+            retried_indexes(&json!({"kind": "bigquery#tableDataInsertAllResponse"})).is_empty()
+        );
+        let response = json!({
+            "insertErrors": [
+                {"index": 0, "errors": [{"reason": "stopped"}]},
+                {"index": 1, "errors": [{"reason": "invalid", "message": "no such field"}]},
+                {"index": 3, "errors": [{"reason": "backendError"}]},
+            ]
+        });
+        assert_eq!(retried_indexes(&response), vec![0, 3]);
+    }
+
+    #[test]
+    fn test_insert_body() {
+        let submission = Submission {
+            id: 1,
+            epoch_second: 1_600_000_000,
+            problem_id: "abc001_a".into(),
+            contest_id: "abc001".into(),
+            user_id: "kenkoooo".into(),
+            language: "Rust".to_owned(),
+            point: 100.0,
+            length: 42,
+            result: "AC".to_owned(),
+            execution_time: None,
+        };
+        let body = insert_body(&[submission]);
+        assert_eq!(body["rows"][0]["insertId"], "1-AC");
+        assert_eq!(body["rows"][0]["json"]["user_id"], "kenkoooo");
+        assert_eq!(body["rows"][0]["json"]["execution_time"], Value::Null);
+    }
+}
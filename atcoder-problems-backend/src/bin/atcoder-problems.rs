@@ -0,0 +1,23 @@
+use atcoder_problems_backend::cli::{Cli, ExitStatus};
+use atcoder_problems_backend::utils::init_log_config;
+use std::process;
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+
+fn main() {
+    let cli = match Cli::from_args_safe() {
+        Ok(cli) => cli,
+        Err(e) if e.kind == ErrorKind::HelpDisplayed || e.kind == ErrorKind::VersionDisplayed => {
+            e.exit()
+        }
+        Err(e) => {
+            eprintln!("{}", e.message);
+            process::exit(ExitStatus::UsageError.code());
+        }
+    };
+    if let Err(e) = init_log_config(cli.log_level(), cli.log_format) {
+        eprintln!("Failed to initialize the logger: {:?}", e);
+        process::exit(ExitStatus::Failure.code());
+    }
+    process::exit(cli.run().code());
+}
@@ -0,0 +1,65 @@
+use atcoder_problems_backend::mirror::{mirror_submissions, Mirror, MirrorTarget};
+use atcoder_problems_backend::utils::{init_log_config, LogFormat};
+use log::{error, LevelFilter};
+use std::process;
+use std::time::Duration;
+use structopt::StructOpt;
+
+/// Mirrors the submissions to another database, a file or a message broker, by consuming the
+/// logical replication of the `submissions` table.
+#[derive(StructOpt)]
+struct Args {
+    /// The database to read the replication from, which needs `wal_level = logical`.
+    #[structopt(long)]
+    database_url: String,
+    /// The replication slot, which is created if it does not exist.
+    #[structopt(long, default_value = "submissions_mirror")]
+    slot: String,
+    /// The publication of the submissions, which is created if it does not exist.
+    #[structopt(long, default_value = "submissions_mirror")]
+    publication: String,
+    /// `postgres://...`, `file:<path>`, `kafka://<host>,<host>` or `nats://<host>,<host>`.
+    #[structopt(long)]
+    target: MirrorTarget,
+    /// The changes are published to `<prefix>.submissions` for Kafka and NATS.
+    #[structopt(long, default_value = "atcoder-problems")]
+    topic_prefix: String,
+    /// The number of the changes read at once.
+    #[structopt(long, default_value = "1000")]
+    batch_size: i32,
+    /// How long to wait when there are no changes.
+    #[structopt(long, default_value = "1000")]
+    polling_interval_ms: u64,
+    /// One of off, error, warn, info, debug and trace.
+    #[structopt(long, default_value = "info")]
+    log_level: LevelFilter,
+    #[structopt(long, default_value = "text", possible_values = &["text", "json"])]
+    log_format: LogFormat,
+}
+
+async fn run(args: Args) -> anyhow::Result<()> {
+    let pool = sql_client::initialize_pool(&args.database_url).await?;
+    let mut mirror = Mirror::connect(&args.target, &args.topic_prefix).await?;
+    mirror_submissions(
+        &pool,
+        &args.slot,
+        &args.publication,
+        &mut mirror,
+        args.batch_size,
+        Duration::from_millis(args.polling_interval_ms),
+    )
+    .await
+}
+
+#[async_std::main]
+async fn main() {
+    let args = Args::from_args();
+    if let Err(e) = init_log_config(args.log_level, args.log_format) {
+        eprintln!("Failed to initialize the logger: {:?}", e);
+        process::exit(1);
+    }
+    if let Err(e) = run(args).await {
+        error!("{:?}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::difficulty::fetch_problem_difficulties;
+use anyhow::Result;
+use chrono::Utc;
+use log::info;
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::achievement::AchievementClient;
+use sql_client::aggregation::{load_rankings, write_rankings};
+use sql_client::contest_problem::ContestProblemClient;
+use sql_client::data_version::{DataVersionClient, MERGED_PROBLEMS_DATA, RANKINGS_DATA};
+use sql_client::internal::progress_reset_manager::load_progress_resets;
+use sql_client::language_count::LanguageCountClient;
+use sql_client::models::Submission;
+use sql_client::problem_difficulty::ProblemDifficultyClient;
+use sql_client::problem_info::ProblemInfoUpdater;
+use sql_client::problems_submissions::ProblemsSubmissionUpdater;
+use sql_client::rated_point_sum::RatedPointSumClient;
+use sql_client::streak::StreakUpdater;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::training_velocity::TrainingVelocityClient;
+use sql_client::PgPool;
+use std::collections::BTreeSet;
+
+/// Updates all the aggregation tables from all the accepted submissions. The rankings are computed
+/// by scanning the submissions once and written with `COPY`.
+pub(crate) async fn batch_update(conn: &PgPool, database_url: &str) -> Result<()> {
+    info!("Loading submissions ...");
+    let mut all_accepted_submissions: Vec<Submission> =
+        conn.get_submissions(SubmissionRequest::AllAccepted).await?;
+
+    info!(
+        "There are {} AC submissions.",
+        all_accepted_submissions.len()
+    );
+
+    info!("Excluding the submissions before the progress resets ...");
+    let resets = load_progress_resets(conn).await?;
+    resets.exclude(&mut all_accepted_submissions);
+
+    info!("Sorting by id ...");
+    all_accepted_submissions.sort_by_key(|s| s.id);
+
+    info!("Aggregating the rankings ...");
+    let rankings = load_rankings(conn).await?;
+    let rows = write_rankings(database_url, &rankings)?;
+    info!("Wrote {} rows of the rankings.", rows);
+
+    let current_count = conn.load_language_count().await?;
+    info!("Executing update_language_count...");
+    conn.update_language_count(&all_accepted_submissions, &current_count)
+        .await?;
+
+    info!("Executing update_submissions_of_problems...");
+    conn.update_submissions_of_problems().await?;
+
+    info!("Executing update_problem_points...");
+    conn.update_problem_points().await?;
+    conn.increment_data_version(MERGED_PROBLEMS_DATA).await?;
+
+    info!("Executing update_streak_count...");
+    conn.update_streak_count(&all_accepted_submissions).await?;
+    conn.increment_data_version(RANKINGS_DATA).await?;
+
+    info!("Executing update_achievements...");
+    let contest_problems = conn.load_contest_problem().await?;
+    conn.update_achievements(&all_accepted_submissions, &contest_problems)
+        .await?;
+
+    info!("Executing update_problem_difficulties...");
+    let difficulties = fetch_problem_difficulties().await?;
+    conn.update_problem_difficulties(&difficulties).await?;
+
+    info!("Executing update_training_velocity...");
+    conn.update_training_velocity(
+        &all_accepted_submissions,
+        &difficulties,
+        Utc::now().timestamp(),
+    )
+    .await?;
+
+    info!("Finished");
+    Ok(())
+}
+
+/// Updates the aggregation tables of the users who have got accepted recently.
+pub(crate) async fn delta_update(conn: &PgPool) -> Result<()> {
+    info!("Loading submissions ...");
+    let request = SubmissionRequest::RecentAccepted { count: 200 };
+    let recent_submissions = conn.get_submissions(request).await?;
+
+    let user_ids = recent_submissions
+        .into_iter()
+        .map(|s| s.user_id)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    info!("Loading submissions of {} users ...", user_ids.len());
+    let request = SubmissionRequest::UsersAccepted {
+        user_ids: &user_ids,
+    };
+    let mut user_accepted_submissions = conn.get_submissions(request).await?;
+    info!("There are {} submissions.", user_accepted_submissions.len());
+
+    info!("Excluding the submissions before the progress resets ...");
+    let resets = load_progress_resets(conn).await?;
+    resets.exclude(&mut user_accepted_submissions);
+
+    info!("Sorting by id ...");
+    user_accepted_submissions.sort_by_key(|s| s.id);
+
+    info!("Executing update_rated_point_sum...");
+    conn.update_rated_point_sum(&user_accepted_submissions)
+        .await?;
+
+    info!("Executing update_accepted_count...");
+    conn.update_accepted_count(&user_accepted_submissions)
+        .await?;
+
+    info!("Executing update_language_count...");
+    conn.update_language_count(&user_accepted_submissions, &[])
+        .await?;
+
+    info!("Executing update_streak_count...");
+    conn.update_streak_count(&user_accepted_submissions).await?;
+    conn.increment_data_version(RANKINGS_DATA).await?;
+
+    info!("Executing update_achievements...");
+    let contest_problems = conn.load_contest_problem().await?;
+    conn.update_achievements(&user_accepted_submissions, &contest_problems)
+        .await?;
+
+    info!("Executing update_training_velocity...");
+    let difficulties = conn.load_problem_difficulties().await?;
+    conn.update_training_velocity(
+        &user_accepted_submissions,
+        &difficulties,
+        Utc::now().timestamp(),
+    )
+    .await?;
+
+    info!("Executing update_submission_count...");
+    conn.update_submission_count().await?;
+
+    info!("Finished");
+    Ok(())
+}
@@ -0,0 +1,386 @@
+use crate::cli::config::Config;
+use crate::compression::{open_decompressed, Compression};
+use crate::metrics;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sql_client::backup::{Loader, Snapshot};
+use sql_client::data_version::{DataVersionClient, ALL_DATA};
+use sql_client::simple_client::SimpleClient;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// The versions in the backup are older than the ones which the clients may have cached, so they
+/// are never restored, and all the versions are incremented after the restore instead.
+const DATA_VERSIONS_TABLE: &str = "data_versions";
+
+/// The size of each file of a table in the archive. The size of a file is written before its
+/// content, so each part of a table is buffered in memory before being appended.
+const PART_SIZE: usize = 64 << 20;
+
+/// Describes the backup, so that it can be checked before being restored.
+/// The tables are in the order in which they can be restored.
+#[derive(Serialize, Deserialize, Debug)]
+struct Manifest {
+    schema_version: String,
+    created_epoch_second: i64,
+    tables: Vec<TableManifest>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct TableManifest {
+    name: String,
+    /// The file in the archive, which is in the text format of `COPY` and can be restored by
+    /// `COPY {name} FROM STDIN`. The table is split into `parts` files of up to `PART_SIZE`
+    /// bytes, which are `{file}`, `{file}.1`, `{file}.2`, ... in this order.
+    file: String,
+    #[serde(default = "default_parts")]
+    parts: u64,
+    rows: u64,
+}
+
+fn default_parts() -> u64 {
+    1
+}
+
+fn part_name(file: &str, part: u64) -> String {
+    if part == 0 {
+        file.to_owned()
+    } else {
+        format!("{}.{}", file, part)
+    }
+}
+
+fn append_file<W: Write>(archive: &mut tar::Builder<W>, name: &str, data: &[u8]) -> io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)
+}
+
+/// Appends the written table to the archive every `part_size` bytes.
+struct PartWriter<'a, W: Write> {
+    archive: &'a mut tar::Builder<W>,
+    file: &'a str,
+    part_size: usize,
+    buffer: Vec<u8>,
+    parts: u64,
+}
+
+impl<'a, W: Write> PartWriter<'a, W> {
+    fn append_part(&mut self) -> io::Result<()> {
+        append_file(
+            self.archive,
+            &part_name(self.file, self.parts),
+            &self.buffer,
+        )?;
+        self.buffer.clear();
+        self.parts += 1;
+        Ok(())
+    }
+
+    /// Appends the rest of the table, and returns the number of the parts.
+    /// An empty table is an empty file.
+    fn finish(mut self) -> io::Result<u64> {
+        if !self.buffer.is_empty() || self.parts == 0 {
+            self.append_part()?;
+        }
+        Ok(self.parts)
+    }
+}
+
+impl<'a, W: Write> Write for PartWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() >= self.part_size {
+            self.append_part()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the parts of a table in the archive as a single file. The parts are consecutive
+/// entries of the archive, since they are appended in order.
+struct PartReader<'a, 'b> {
+    entries: &'b mut tar::Entries<'a, Box<dyn Read>>,
+    current: tar::Entry<'a, Box<dyn Read>>,
+    file: &'b str,
+    next: u64,
+    parts: u64,
+}
+
+impl<'a, 'b> Read for PartReader<'a, 'b> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() || self.next == self.parts {
+                return Ok(read);
+            }
+            let name = part_name(self.file, self.next);
+            let entry = self.entries.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, format!("{} is missing", name))
+            })??;
+            if entry.path()? != Path::new(&name) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} is not followed by {}", self.file, name),
+                ));
+            }
+            self.current = entry;
+            self.next += 1;
+        }
+    }
+}
+
+/// Writes all the tables and the manifest to a tar archive compressed by `compression`, which is
+/// zstd by default. The tables are streamed into the archive in parts, and the archive is written
+/// to a temporary file first, so that the output is never a half-written backup.
+pub(crate) fn backup(config: &Config, output: &Path, compression: Compression) -> Result<()> {
+    let output = &compression.with_extension(output);
+    let mut temporary = output.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let file = File::create(&temporary)
+        .with_context(|| format!("Failed to create {}", Path::new(&temporary).display()))?;
+    let mut archive = tar::Builder::new(compression.writer(BufWriter::new(file))?);
+
+    let mut snapshot = Snapshot::begin(config.database.url()?)?;
+    let mut manifest = Manifest {
+        schema_version: snapshot.schema_version()?,
+        created_epoch_second: Utc::now().timestamp(),
+        tables: Vec::new(),
+    };
+    for table in snapshot.tables()? {
+        log::info!("Copying {}", table);
+        let file_name = format!("{}.tsv", table);
+        let mut writer = PartWriter {
+            archive: &mut archive,
+            file: &file_name,
+            part_size: PART_SIZE,
+            buffer: Vec::new(),
+            parts: 0,
+        };
+        let rows = snapshot.copy_table(&table, &mut writer)?;
+        let parts = writer.finish()?;
+        manifest.tables.push(TableManifest {
+            name: table,
+            file: file_name,
+            parts,
+            rows,
+        });
+    }
+
+    let manifest = serde_json::to_vec_pretty(&manifest)?;
+    append_file(&mut archive, MANIFEST_NAME, &manifest)?;
+    archive.into_inner()?.finish()?.flush()?;
+
+    fs::rename(&temporary, output)?;
+    log::info!("Wrote {}", output.display());
+    Ok(())
+}
+
+type Archive = tar::Archive<Box<dyn Read>>;
+
+/// Opens the archive, which is decompressed by the format of the backup whatever its name is.
+fn open_archive(input: &Path) -> Result<Archive> {
+    let reader =
+        open_decompressed(input).with_context(|| format!("Failed to open {}", input.display()))?;
+    Ok(tar::Archive::new(reader))
+}
+
+/// Reads the manifest, which is at the end of the archive.
+fn read_manifest(input: &Path) -> Result<Manifest> {
+    let mut archive = open_archive(input)?;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.path()? == Path::new(MANIFEST_NAME) {
+            return Ok(serde_json::from_reader(entry)?);
+        }
+    }
+    bail!("{} has no {}", input.display(), MANIFEST_NAME)
+}
+
+/// Returns the tables which are not given, but are cleared by `TRUNCATE CASCADE` of the given
+/// tables because they reference the given tables directly or indirectly.
+fn cascaded_tables(tables: &[&str], foreign_keys: &[(String, String)]) -> BTreeSet<String> {
+    let mut cleared = tables
+        .iter()
+        .map(|t| t.to_string())
+        .collect::<BTreeSet<_>>();
+    loop {
+        let referencing = foreign_keys
+            .iter()
+            .filter(|(from, to)| cleared.contains(to) && !cleared.contains(from))
+            .map(|(from, _)| from.clone())
+            .collect::<Vec<_>>();
+        if referencing.is_empty() {
+            break;
+        }
+        cleared.extend(referencing);
+    }
+    for table in tables {
+        cleared.remove(*table);
+    }
+    cleared
+}
+
+/// Replaces the tables with the ones in the backup written by `backup`. All the tables are
+/// restored if `tables` is empty, except for `data_versions`.
+pub(crate) async fn restore(config: &Config, input: &Path, tables: &[String]) -> Result<()> {
+    let manifest = read_manifest(input)?;
+    let mut loader = Loader::connect(config.database.url()?)?;
+    let schema_version = loader.schema_version()?;
+    if manifest.schema_version != schema_version {
+        bail!(
+            "The schema of the backup is {}, but the database is {}",
+            manifest.schema_version,
+            schema_version
+        );
+    }
+    if let Some(table) = tables
+        .iter()
+        .find(|table| !manifest.tables.iter().any(|t| &t.name == *table))
+    {
+        bail!("{} is not in the backup", table);
+    }
+    if tables.iter().any(|table| table == DATA_VERSIONS_TABLE) {
+        bail!("{} is not restored", DATA_VERSIONS_TABLE);
+    }
+
+    let selected = manifest
+        .tables
+        .iter()
+        .filter(|t| t.name != DATA_VERSIONS_TABLE)
+        .filter(|t| tables.is_empty() || tables.contains(&t.name))
+        .collect::<Vec<_>>();
+    let names = selected.iter().map(|t| t.name.as_str()).collect::<Vec<_>>();
+    let cascaded = cascaded_tables(&names, &loader.foreign_keys()?);
+    if !cascaded.is_empty() {
+        let cascaded = cascaded.into_iter().collect::<Vec<_>>();
+        bail!(
+            "Restoring the tables also clears {}, so add them to --tables",
+            cascaded.join(", ")
+        );
+    }
+
+    let mut archive = open_archive(input)?;
+    let mut entries = archive.entries()?;
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let path = entry.path()?.into_owned();
+        let table = match selected.iter().find(|t| Path::new(&t.file) == path) {
+            Some(table) => table,
+            None => continue,
+        };
+        log::info!("Restoring {}", table.name);
+        let reader = PartReader {
+            entries: &mut entries,
+            current: entry,
+            file: &table.file,
+            next: 1,
+            parts: table.parts,
+        };
+        let rows = loader.load_table(&table.name, reader)?;
+        if rows != table.rows {
+            log::warn!(
+                "Restored {} rows of {}, but the manifest says {}",
+                rows,
+                table.name,
+                table.rows
+            );
+        }
+        metrics::add_rows_written(rows as usize);
+    }
+    let pg_pool = config.database.connect().await?;
+    if names.contains(&"contests") {
+        // The derived columns in the backup follow the rules when it was written.
+        let rows = pg_pool.update_derived_contest_columns().await?;
+        log::info!("Updated the derived columns of {} contests", rows);
+    }
+    // The restored tables may change any of the data which the clients have cached.
+    for name in ALL_DATA {
+        pg_pool.increment_data_version(name).await?;
+    }
+    log::info!(
+        "Restored {} tables from {}",
+        selected.len(),
+        input.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cascaded_tables() {
+        let foreign_keys = vec![
+            ("list_items".to_string(), "lists".to_string()),
+            ("lists".to_string(), "users".to_string()),
+            ("progress".to_string(), "users".to_string()),
+        ];
+        assert_eq!(
+            cascaded_tables(&["users", "lists"], &foreign_keys),
+            vec!["list_items".to_string(), "progress".to_string()]
+                .into_iter()
+                .collect()
+        );
+        assert!(cascaded_tables(&["list_items", "submissions"], &foreign_keys).is_empty());
+    }
+
+    #[test]
+    fn test_parts() {
+        let mut archive = tar::Builder::new(Vec::new());
+        for (file, content) in [("a.tsv", "1\n2\n3\n"), ("b.tsv", "")].iter() {
+            let mut writer = PartWriter {
+                archive: &mut archive,
+                file,
+                part_size: 4,
+                buffer: Vec::new(),
+                parts: 0,
+            };
+            for line in content.split_inclusive('\n') {
+                writer.write_all(line.as_bytes()).unwrap();
+            }
+            let parts = writer.finish().unwrap();
+            assert_eq!(parts, if content.is_empty() { 1 } else { 2 });
+        }
+        let data = archive.into_inner().unwrap();
+
+        let reader: Box<dyn Read> = Box::new(io::Cursor::new(data));
+        let mut archive = tar::Archive::new(reader);
+        let mut entries = archive.entries().unwrap();
+        let mut read = Vec::new();
+        while let Some(entry) = entries.next() {
+            let entry = entry.unwrap();
+            let file = entry.path().unwrap().to_str().unwrap().to_owned();
+            let mut content = String::new();
+            let mut reader = PartReader {
+                entries: &mut entries,
+                current: entry,
+                file: &file,
+                next: 1,
+                parts: if file == "a.tsv" { 2 } else { 1 },
+            };
+            reader.read_to_string(&mut content).unwrap();
+            read.push((file, content));
+        }
+        assert_eq!(
+            read,
+            vec![
+                ("a.tsv".to_owned(), "1\n2\n3\n".to_owned()),
+                ("b.tsv".to_owned(), String::new()),
+            ]
+        );
+    }
+}
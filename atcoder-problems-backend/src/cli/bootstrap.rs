@@ -0,0 +1,386 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use futures::AsyncReadExt;
+use serde::de::DeserializeOwned;
+use sql_client::contest_problem::ContestProblemClient;
+use sql_client::models::{Contest, ContestProblem, Problem, Submission};
+use sql_client::problem_difficulty::ProblemDifficultyClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::SubmissionClient;
+use sql_client::PgPool;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use surf::Url;
+
+use crate::cli::dump::{Segment, SegmentManifest};
+use crate::compression::open_decompressed;
+use crate::difficulty::{self, ProblemModel};
+use crate::metrics;
+use crate::utils::HashingWriter;
+
+const BASE_URL: &str = "https://kenkoooo.com/atcoder";
+const USER_AGENT: &str = concat!("atcoder-problems-backend/", env!("CARGO_PKG_VERSION"));
+/// The maximum number of the submissions which `/atcoder-api/v3/from/{second}` returns.
+const SUBMISSIONS_PER_REQUEST: usize = 1000;
+/// The number of the submissions of a segment inserted at once.
+const SEGMENT_INSERT_BATCH_SIZE: usize = 1000;
+/// The number of the attempts to download a segment in a run, each of which resumes the previous
+/// one. The next run resumes it again.
+const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+const DOWNLOAD_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+const DOWNLOAD_BUFFER_SIZE: usize = 1 << 16;
+
+/// Requests the public API of AtCoder Problems, waiting for `interval` between the requests.
+struct UpstreamClient {
+    interval: Duration,
+    last_requested: Option<Instant>,
+}
+
+impl UpstreamClient {
+    async fn get<T: DeserializeOwned>(&mut self, path: &str) -> Result<T> {
+        if let Some(wait) = self
+            .last_requested
+            .and_then(|last| self.interval.checked_sub(last.elapsed()))
+        {
+            async_std::task::sleep(wait).await;
+        }
+        self.last_requested = Some(Instant::now());
+
+        let url = format!("{}{}", BASE_URL, path);
+        let value = surf::get(&url)
+            .header("accept-encoding", "gzip")
+            .header("user-agent", USER_AGENT)
+            .recv_json()
+            .await
+            .map_err(|e| anyhow!("Failed to get json from {}: {:?}", url, e))?;
+        metrics::add_page_fetched();
+        Ok(value)
+    }
+}
+
+/// The second to request the submissions from after `submissions`, or `None` if they are the
+/// last ones. The submissions in the same second as the last one are requested again unless
+/// the response is full of them, since they may not fit in the response.
+fn next_from_second(from_second: i64, submissions: &[Submission]) -> Option<i64> {
+    if submissions.len() < SUBMISSIONS_PER_REQUEST {
+        return None;
+    }
+    let last_second = submissions.iter().map(|s| s.epoch_second).max()?;
+    if last_second > from_second {
+        Some(last_second)
+    } else {
+        Some(from_second + 1)
+    }
+}
+
+/// Where `bootstrap` downloads the segments written by `dump --submission-segments`.
+pub(crate) struct SegmentSource {
+    /// The directory of the segments and `manifest.json`.
+    pub(crate) url: Url,
+    /// The local directory, where the downloaded segments are kept so that the next run skips
+    /// them, and the partial downloads are resumed.
+    pub(crate) download_dir: PathBuf,
+}
+
+/// Inserts the contests, the problems, the difficulties and the submissions of the last `days`
+/// days from the public API of AtCoder Problems into an empty database, so that a local instance
+/// works without crawling AtCoder for days. All the submissions are inserted from the segments
+/// instead if `segments` is given.
+pub(crate) async fn bootstrap(
+    pg_pool: &PgPool,
+    days: i64,
+    interval: Duration,
+    segments: Option<SegmentSource>,
+) -> Result<()> {
+    if !pg_pool.load_contests().await?.is_empty() {
+        bail!("The database already has contests. Run `migrate --reset` first");
+    }
+    let mut client = UpstreamClient {
+        interval,
+        last_requested: None,
+    };
+
+    // The upstream contests have no derived fields, which are derived here as the crawler does.
+    let contests = client
+        .get::<Vec<Contest>>("/resources/contests.json")
+        .await?
+        .into_iter()
+        .map(|c| {
+            Contest::new(
+                c.id,
+                c.start_epoch_second,
+                c.duration_second,
+                c.title,
+                c.rate_change,
+            )
+        })
+        .collect::<Vec<_>>();
+    metrics::add_rows_written(pg_pool.insert_contests(&contests).await?);
+    let problems: Vec<Problem> = client.get("/resources/problems.json").await?;
+    metrics::add_rows_written(pg_pool.insert_problems(&problems).await?);
+    let contest_problems: Vec<ContestProblem> =
+        client.get("/resources/contest-problem.json").await?;
+    pg_pool.insert_contest_problem(&contest_problems).await?;
+    let models: BTreeMap<String, ProblemModel> =
+        client.get("/resources/problem-models.json").await?;
+    let difficulties = difficulty::difficulties(models);
+    pg_pool.update_problem_difficulties(&difficulties).await?;
+    log::info!(
+        "Inserted {} contests, {} problems and {} difficulties",
+        contests.len(),
+        problems.len(),
+        difficulties.len()
+    );
+
+    match segments {
+        Some(segments) => insert_segments(pg_pool, &segments).await?,
+        None => insert_recent_submissions(pg_pool, &mut client, days).await?,
+    }
+    pg_pool.update_submission_count().await?;
+    Ok(())
+}
+
+async fn insert_recent_submissions(
+    pg_pool: &PgPool,
+    client: &mut UpstreamClient,
+    days: i64,
+) -> Result<()> {
+    let mut from_second = Some(Utc::now().timestamp() - days * 24 * 3600);
+    let mut submission_count = 0;
+    while let Some(second) = from_second {
+        let submissions: Vec<Submission> = client
+            .get(&format!("/atcoder-api/v3/from/{}", second))
+            .await?;
+        if !submissions.is_empty() {
+            metrics::add_rows_written(pg_pool.update_submissions(&submissions).await?);
+            submission_count += submissions.len();
+            log::info!("Inserted {} submissions", submission_count);
+        }
+        from_second = next_from_second(second, &submissions);
+    }
+    Ok(())
+}
+
+/// Downloads the segments listed in the manifest, and inserts their submissions. A segment is
+/// inserted only after its checksum is verified, and inserting it again after an interrupted run
+/// is harmless, since the submissions are upserted.
+async fn insert_segments(pg_pool: &PgPool, source: &SegmentSource) -> Result<()> {
+    fs::create_dir_all(&source.download_dir)
+        .with_context(|| format!("Failed to create {}", source.download_dir.display()))?;
+    let mut base_url = source.url.clone();
+    if !base_url.path().ends_with('/') {
+        let path = format!("{}/", base_url.path());
+        base_url.set_path(&path);
+    }
+    let manifest_url = base_url.join("manifest.json")?;
+    let manifest: SegmentManifest = surf::get(manifest_url.as_str())
+        .header("user-agent", USER_AGENT)
+        .recv_json()
+        .await
+        .map_err(|e| anyhow!("Failed to get json from {}: {:?}", manifest_url, e))?;
+    log::info!(
+        "{} lists {} segments",
+        manifest_url,
+        manifest.segments.len()
+    );
+
+    let mut submission_count = 0;
+    for segment in manifest.segments.iter() {
+        // The names come from the remote manifest, so they must not point outside the directory.
+        if Path::new(&segment.file).file_name() != Some(OsStr::new(&segment.file)) {
+            bail!("Invalid segment name: {}", segment.file);
+        }
+        let path = source.download_dir.join(&segment.file);
+        if path.exists() {
+            log::info!("{} is already downloaded", path.display());
+        } else {
+            download_segment(&base_url.join(&segment.file)?, &path, segment).await?;
+        }
+        submission_count += insert_segment(pg_pool, &path).await?;
+        log::info!("Inserted {} submissions", submission_count);
+    }
+    Ok(())
+}
+
+/// Downloads the segment to `{path}.part` and renames it to `path` after its checksum is verified.
+/// The download is resumed by a `Range` request from the end of the partial file, which the
+/// previous attempts or runs have left, so a dropped connection loses nothing downloaded.
+async fn download_segment(url: &Url, path: &Path, segment: &Segment) -> Result<()> {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match download_rest(url, &partial).await {
+            Ok(()) => break,
+            Err(e) if attempts < MAX_DOWNLOAD_ATTEMPTS => {
+                log::warn!("Retrying to download {}: {:?}", url, e);
+                async_std::task::sleep(DOWNLOAD_RETRY_INTERVAL * attempts).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    match &segment.sha256 {
+        Some(expected) => {
+            let mut hashing = HashingWriter::new(io::sink());
+            io::copy(&mut File::open(&partial)?, &mut hashing)?;
+            let (_, actual) = hashing.finish();
+            if &actual != expected {
+                fs::remove_file(&partial)?;
+                bail!(
+                    "The SHA-256 of {} is {}, but the manifest says {}",
+                    segment.file,
+                    actual,
+                    expected
+                );
+            }
+        }
+        None => log::warn!("{} has no checksum to verify", segment.file),
+    }
+    fs::rename(&partial, path)?;
+    log::info!("Downloaded {}", path.display());
+    Ok(())
+}
+
+/// Appends the rest of the file after the partial file, and fails if the connection is closed
+/// before the end of the file.
+async fn download_rest(url: &Url, partial: &Path) -> Result<()> {
+    let offset = fs::metadata(partial).map_or(0, |metadata| metadata.len());
+    let mut response = surf::get(url.as_str())
+        .header("range", format!("bytes={}-", offset))
+        // The ranges are of the file itself, not of a compressed response.
+        .header("accept-encoding", "identity")
+        .header("user-agent", USER_AGENT)
+        .await
+        .map_err(|e| anyhow!("Failed to get {}: {:?}", url, e))?;
+    let (mut file, mut written, total) = match u16::from(response.status()) {
+        206 => {
+            let content_range = response
+                .header("content-range")
+                .map(|range| range.as_str().to_owned())
+                .unwrap_or_default();
+            let (start, total) = parse_content_range(&content_range)
+                .ok_or_else(|| anyhow!("Invalid Content-Range: {}", content_range))?;
+            if start != offset {
+                bail!("Requested from {}, but got {}", offset, content_range);
+            }
+            let file = OpenOptions::new().create(true).append(true).open(partial)?;
+            (file, offset, total)
+        }
+        // The server does not support the ranges, so the file is downloaded from the beginning.
+        200 => {
+            let total = response.len().map(|len| len as u64);
+            (File::create(partial)?, 0, total)
+        }
+        // The partial file is already the whole file.
+        416 if offset > 0 => return Ok(()),
+        status => bail!("Failed to get {}: {}", url, status),
+    };
+    metrics::add_page_fetched();
+
+    let mut buffer = vec![0; DOWNLOAD_BUFFER_SIZE];
+    loop {
+        let size = response
+            .read(&mut buffer)
+            .await
+            .with_context(|| format!("Disconnected at {} bytes of {}", written, url))?;
+        if size == 0 {
+            break;
+        }
+        file.write_all(&buffer[..size])?;
+        written += size as u64;
+    }
+    file.flush()?;
+    match total {
+        Some(total) if written < total => {
+            bail!("Disconnected at {} of {} bytes of {}", written, total, url)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The first byte and the length of the whole file in `Content-Range`, e.g. `bytes 100-199/200`.
+/// The length is `None` if it is unknown.
+fn parse_content_range(content_range: &str) -> Option<(u64, Option<u64>)> {
+    let range = content_range.trim().strip_prefix("bytes ")?;
+    let mut parts = range.splitn(2, '/');
+    let start = parts.next()?.splitn(2, '-').next()?.parse().ok()?;
+    let total = match parts.next()? {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start, total))
+}
+
+/// Inserts the submissions of the segment, which may be compressed, and returns their number.
+async fn insert_segment(pg_pool: &PgPool, path: &Path) -> Result<usize> {
+    let reader = BufReader::new(
+        open_decompressed(path).with_context(|| format!("Failed to open {}", path.display()))?,
+    );
+    let mut count = 0;
+    let mut batch = Vec::with_capacity(SEGMENT_INSERT_BATCH_SIZE);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let submission: Submission = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid JSON at line {} of {}", i + 1, path.display()))?;
+        batch.push(submission);
+        if batch.len() == SEGMENT_INSERT_BATCH_SIZE {
+            metrics::add_rows_written(pg_pool.update_submissions(&batch).await?);
+            count += batch.len();
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        metrics::add_rows_written(pg_pool.update_submissions(&batch).await?);
+        count += batch.len();
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submissions(seconds: &[i64]) -> Vec<Submission> {
+        seconds
+            .iter()
+            .map(|&epoch_second| Submission {
+                epoch_second,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_next_from_second() {
+        assert_eq!(next_from_second(100, &submissions(&[100, 150])), None);
+
+        let mut full = submissions(&[100; SUBMISSIONS_PER_REQUEST - 1]);
+        full.extend(submissions(&[150]));
+        assert_eq!(next_from_second(100, &full), Some(150));
+
+        let full = submissions(&[100; SUBMISSIONS_PER_REQUEST]);
+        assert_eq!(next_from_second(100, &full), Some(101));
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 0-99/*"), Some((0, None)));
+        assert_eq!(parse_content_range("bytes */200"), None);
+        assert_eq!(parse_content_range(""), None);
+    }
+}
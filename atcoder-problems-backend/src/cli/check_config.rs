@@ -0,0 +1,151 @@
+use anyhow::{anyhow, bail, Context, Result};
+use sentry::types::Dsn;
+use std::str::FromStr;
+use surf::Url;
+
+use crate::cli::config::{Config, ReportConfig, RetentionConfig};
+use crate::cli::daemon;
+
+/// Prints the effective config with the secrets masked, and fails if any setting is invalid or
+/// the database is not reachable, so that a deployment fails before the jobs run.
+pub(crate) async fn check_config(config: &Config) -> Result<()> {
+    println!("{}", toml::to_string(config)?);
+
+    let mut problems = validate(config);
+    match config.database.connect().await {
+        Ok(_) => log::info!("Connected to the database"),
+        Err(e) => problems.push(format!("Failed to connect to the database: {:#}", e)),
+    }
+    for problem in problems.iter() {
+        log::error!("{}", problem);
+    }
+    if !problems.is_empty() {
+        bail!("{} problems are found in the config", problems.len());
+    }
+    Ok(())
+}
+
+/// Returns the problems of the settings which can be checked offline.
+fn validate(config: &Config) -> Vec<String> {
+    let server = &config.server;
+    let results = vec![
+        config
+            .database
+            .url()
+            .and_then(|url| check_url("the database URL", url, &["postgres", "postgresql"])),
+        check_atcoder_account(config),
+        check_optional_url("REDIS_URL", &server.redis_url, &["redis", "rediss"]),
+        check_positive("RATE_LIMIT_CAPACITY", server.rate_limit.capacity),
+        check_positive("RATE_LIMIT_PER_SECOND", server.rate_limit.per_second),
+        check_optional_url(
+            "PUSHGATEWAY_URL",
+            &config.metrics.pushgateway_url,
+            &["http", "https"],
+        ),
+        check_sentry_dsn(&config.error_report.sentry_dsn),
+        check_optional_url(
+            "NOTIFY_WEBHOOK_URL",
+            &config.notify.webhook_url,
+            &["http", "https"],
+        ),
+        check_report(&config.report),
+        check_retention(&config.retention),
+        check_optional_url(
+            "MATERIALIZE_REDIS_URL",
+            &config.materialize.redis_url,
+            &["redis", "rediss"],
+        ),
+        check_optional_url("CLICKHOUSE_URL", &config.clickhouse.url, &["http", "https"]),
+        check_optional_url(
+            "STORAGE_ENDPOINT",
+            &config.storage.endpoint,
+            &["http", "https"],
+        ),
+        daemon::validate_jobs(config),
+    ];
+    results
+        .into_iter()
+        .filter_map(|result| result.err())
+        .map(|e| format!("{:#}", e))
+        .collect()
+}
+
+fn check_atcoder_account(config: &Config) -> Result<()> {
+    if config.atcoder.user.is_some() != config.atcoder.password.is_some() {
+        bail!("Specify both ATCODER_USER and ATCODER_PASS, or neither");
+    }
+    Ok(())
+}
+
+fn check_report(report: &ReportConfig) -> Result<()> {
+    if report.smtp_host.is_some() && (report.from.is_none() || report.to.is_empty()) {
+        bail!("Specify REPORT_FROM and REPORT_TO with REPORT_SMTP_HOST");
+    }
+    Ok(())
+}
+
+fn check_retention(retention: &RetentionConfig) -> Result<()> {
+    match retention.years {
+        Some(years) if years < 0 => bail!("RETENTION_YEARS must not be negative"),
+        Some(_) if retention.archive_dir.is_none() => {
+            bail!("Specify RETENTION_ARCHIVE_DIR with RETENTION_YEARS")
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_url(name: &str, url: &str, schemes: &[&str]) -> Result<()> {
+    let parsed = Url::parse(url).with_context(|| format!("Invalid {}", name))?;
+    if !schemes.contains(&parsed.scheme()) {
+        bail!("{} must start with {}://", name, schemes.join(":// or "));
+    }
+    Ok(())
+}
+
+fn check_optional_url(name: &str, url: &Option<String>, schemes: &[&str]) -> Result<()> {
+    match url {
+        Some(url) => check_url(name, url, schemes),
+        None => Ok(()),
+    }
+}
+
+fn check_sentry_dsn(dsn: &Option<String>) -> Result<()> {
+    if let Some(dsn) = dsn {
+        Dsn::from_str(dsn).map_err(|e| anyhow!("Invalid SENTRY_DSN: {}", e))?;
+    }
+    Ok(())
+}
+
+fn check_positive(name: &str, value: Option<f64>) -> Result<()> {
+    match value {
+        Some(value) if value.is_nan() || value <= 0.0 => {
+            bail!("{} must be positive: {}", name, value)
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::config::ScheduledJobConfig;
+
+    #[test]
+    fn test_validate() {
+        let mut config = Config::default();
+        config.database.url = Some("postgres://localhost/test".to_owned());
+        assert!(validate(&config).is_empty());
+
+        config.database.url = Some("mysql://localhost/test".to_owned());
+        config.atcoder.user = Some("kenkoooo".to_owned());
+        config.server.redis_url = Some("localhost:6379".to_owned());
+        config.server.rate_limit.per_second = Some(0.0);
+        config.daemon.jobs.push(ScheduledJobConfig {
+            command: "aggregate".to_owned(),
+            schedule: "*/10 * *".to_owned(),
+            jitter_second: 0,
+        });
+        config.retention.years = Some(5);
+        assert_eq!(validate(&config).len(), 6);
+    }
+}
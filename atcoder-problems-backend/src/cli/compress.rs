@@ -0,0 +1,33 @@
+use crate::metrics;
+use anyhow::Result;
+use sql_client::compressed_text::{CompressedTextClient, TextTable};
+use sql_client::PgPool;
+
+const RECOMPRESS_BATCH_SIZE: i64 = 1000;
+
+/// Trains a new dictionary of the table on the random samples of it, and recompresses the texts
+/// with it if `recompress` is set. The new texts are compressed with it either way.
+pub(crate) async fn train_dictionary(
+    pg_pool: &PgPool,
+    table: TextTable,
+    samples: i64,
+    recompress: bool,
+) -> Result<()> {
+    let id = match pg_pool.train_text_dictionary(table, samples).await? {
+        Some(id) => id,
+        None => {
+            log::info!("{} is empty", table.name());
+            return Ok(());
+        }
+    };
+    log::info!("Trained the dictionary {} of {}", id, table.name());
+
+    if recompress {
+        let rows = pg_pool
+            .recompress_texts(table, RECOMPRESS_BATCH_SIZE)
+            .await?;
+        metrics::add_rows_written(rows);
+        log::info!("Recompressed {} texts of {}", rows, table.name());
+    }
+    Ok(())
+}
@@ -0,0 +1,459 @@
+use anyhow::{bail, Context, Result};
+use async_std::future::timeout;
+use async_std::prelude::FutureExt;
+use async_std::task;
+use atcoder_client::AtCoderClient;
+use chrono::Utc;
+use log::{error, info, warn};
+use rand::thread_rng;
+use sql_client::activity::ActivityClient;
+use sql_client::crawl_request::{CrawlRequest, CrawlRequestClient, CrawlRequestListener};
+use sql_client::ids::UserId;
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::PgPool;
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+use std::thread;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+use crate::cli::config::Config;
+use crate::crawler::{
+    take_parse_failures, FixCrawler, LivePerformanceCrawler, ProblemCrawler, ProfileCrawler,
+    RecentCrawler, StandingsCrawler, UserCrawler, VirtualContestCrawler, WholeContestCrawler,
+};
+use crate::error_report;
+use crate::metrics;
+use crate::systemd;
+
+/// How many times `crawl requests` tries a request before giving it up, e.g. a deleted contest.
+const MAX_CRAWL_ATTEMPTS: i32 = 3;
+/// How often the long-running crawlers save the parse failures for the daily report.
+const PARSE_FAILURES_SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(StructOpt, Debug, Clone)]
+pub enum CrawlCommand {
+    /// Crawls the list of the contests, and the problems of the contests which have no problems.
+    Problems,
+    /// Crawls all the submissions of the contests.
+    Contests {
+        /// Crawls all the contests repeatedly.
+        #[structopt(long, conflicts_with_all = &["new", "requested", "contest-ids"])]
+        all: bool,
+        /// Crawls the newest contests repeatedly.
+        #[structopt(long, conflicts_with_all = &["requested", "contest-ids"])]
+        new: bool,
+        /// The same as `crawl requests`, which also crawls the requested users.
+        #[structopt(long, conflicts_with = "contest-ids")]
+        requested: bool,
+        /// The contests to crawl once.
+        contest_ids: Vec<String>,
+    },
+    /// Crawls the contests and the users queued in `crawl_requests`, e.g. by /internal/crawl,
+    /// as soon as they are queued.
+    Requests,
+    /// Crawls the recent submissions repeatedly.
+    Submissions {
+        /// Crawls the submissions of the participants of the running virtual contests instead.
+        #[structopt(long, conflicts_with = "fix")]
+        virtual_contests: bool,
+        /// Re-crawls the submissions in the last day which are still being judged, and exits.
+        #[structopt(long)]
+        fix: bool,
+    },
+    /// Estimates the performances in the running contests every minute.
+    LivePerformances,
+    /// Caches the standings of the running contests every minute.
+    Standings,
+    /// Crawls the profiles of the users whose profiles are missing or the oldest, e.g. daily.
+    Profiles {
+        /// The number of the users to crawl.
+        #[structopt(long, default_value = "1000")]
+        count: i64,
+    },
+}
+
+impl CrawlCommand {
+    pub(crate) fn job_name(&self) -> &'static str {
+        match self {
+            CrawlCommand::Problems => "crawl_problems",
+            CrawlCommand::Contests { all: true, .. } => "crawl_contests_all",
+            CrawlCommand::Contests { new: true, .. } => "crawl_contests_new",
+            CrawlCommand::Contests {
+                requested: true, ..
+            } => "crawl_contests_requested",
+            CrawlCommand::Contests { .. } => "crawl_contests",
+            CrawlCommand::Requests => "crawl_requests",
+            CrawlCommand::Submissions { fix: true, .. } => "crawl_submissions_fix",
+            CrawlCommand::Submissions {
+                virtual_contests: true,
+                ..
+            } => "crawl_submissions_virtual_contests",
+            CrawlCommand::Submissions { .. } => "crawl_submissions",
+            CrawlCommand::LivePerformances => "crawl_live_performances",
+            CrawlCommand::Standings => "crawl_standings",
+            CrawlCommand::Profiles { .. } => "crawl_profiles",
+        }
+    }
+}
+
+pub(crate) async fn run(command: CrawlCommand, config: &Config) -> Result<()> {
+    let save_periodically = async {
+        loop {
+            task::sleep(PARSE_FAILURES_SAVE_INTERVAL).await;
+            save_parse_failures(config).await;
+        }
+    };
+    let result = run_command(command, config).race(save_periodically).await;
+    save_parse_failures(config).await;
+    result
+}
+
+/// Saves the pages which the crawlers failed to parse since the last save.
+async fn save_parse_failures(config: &Config) {
+    let failures = take_parse_failures();
+    if failures.is_empty() {
+        return;
+    }
+    let result = match config.database.connect().await {
+        Ok(db) => db.insert_parse_failures(&failures).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        error!("Failed to save {} parse failures: {:?}", failures.len(), e);
+        error_report::report(&e, &[]);
+    }
+}
+
+async fn run_command(command: CrawlCommand, config: &Config) -> Result<()> {
+    let client = config.atcoder.client().await?;
+    systemd::notify_ready();
+    match command {
+        CrawlCommand::Problems => {
+            let db = config.database.connect().await?;
+            ProblemCrawler::new(db, client).crawl().await
+        }
+        CrawlCommand::Contests { all: true, .. } => crawl_all_contests(config, &client).await,
+        CrawlCommand::Contests { new: true, .. } => crawl_new_contests(config, &client).await,
+        CrawlCommand::Contests {
+            requested: true, ..
+        } => crawl_requests(config, &client).await,
+        CrawlCommand::Contests { contest_ids, .. } => {
+            if contest_ids.is_empty() {
+                bail!("Specify --all, --new, --requested or the contest ids to crawl");
+            }
+            let db = config.database.connect().await?;
+            let mut failed = Vec::new();
+            for contest_id in contest_ids.iter() {
+                info!("Starting {}", contest_id);
+                let crawler = WholeContestCrawler::new(db.clone(), client.clone(), contest_id);
+                if let Err(e) = crawler.crawl().await {
+                    error!("Error while crawling {}: {:?}", contest_id, e);
+                    error_report::report(&e, &[("contest_id", contest_id)]);
+                    metrics::add_failure();
+                    failed.push(contest_id.as_str());
+                }
+            }
+            // The run partially succeeds if some of the contests are crawled.
+            if failed.len() == contest_ids.len() {
+                bail!("Failed to crawl {}", failed.join(", "));
+            }
+            Ok(())
+        }
+        CrawlCommand::Requests => crawl_requests(config, &client).await,
+        CrawlCommand::Submissions { fix: true, .. } => {
+            let db = config.database.connect().await?;
+            let now = Utc::now().timestamp();
+            FixCrawler::new(db, client, now - config.crawl.fix_range_second)
+                .crawl()
+                .await?;
+            info!("Finished fixing.");
+            Ok(())
+        }
+        CrawlCommand::Submissions {
+            virtual_contests: true,
+            ..
+        } => crawl_virtual_contests(config, &client).await,
+        CrawlCommand::Submissions { .. } => crawl_recent_submissions(config, &client).await,
+        CrawlCommand::LivePerformances => {
+            crawl_every_interval(config, &client, |db, client, now| async move {
+                LivePerformanceCrawler::new(db, client, now).crawl().await
+            })
+            .await
+        }
+        CrawlCommand::Standings => {
+            crawl_every_interval(config, &client, |db, client, now| async move {
+                StandingsCrawler::new(db, client, now).crawl().await
+            })
+            .await
+        }
+        CrawlCommand::Profiles { count } => {
+            let db = config.database.connect().await?;
+            ProfileCrawler::new(db, client, count).crawl().await
+        }
+    }
+}
+
+async fn crawl_all_contests(config: &Config, client: &AtCoderClient) -> Result<()> {
+    loop {
+        info!("Start new loop");
+        systemd::notify_watchdog();
+        let contests = match config.database.connect().await {
+            Ok(db) => db.load_contests().await,
+            Err(e) => Err(e),
+        };
+        match contests {
+            Ok(contests) => {
+                for contest in contests.into_iter() {
+                    finish_one_contest(config, client, contest.id.as_str()).await;
+                }
+            }
+            Err(e) => {
+                error!("Failed to load the contests: {:?}", e);
+                error_report::report(&e, &[]);
+                sleep_1sec();
+            }
+        }
+    }
+}
+
+async fn finish_one_contest(config: &Config, client: &AtCoderClient, contest_id: &str) {
+    loop {
+        info!("Starting {}", contest_id);
+        systemd::notify_watchdog();
+        let result = match config.database.connect().await {
+            Ok(db) => {
+                WholeContestCrawler::new(db, client.clone(), contest_id)
+                    .crawl()
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => {
+                info!("Finished {}", contest_id);
+                return;
+            }
+            Err(e) => {
+                error!("Error while crawling {}: {:?}", contest_id, e);
+                error_report::report(&e, &[("contest_id", contest_id)]);
+                sleep_1sec();
+            }
+        }
+    }
+}
+
+async fn crawl_new_contests(config: &Config, client: &AtCoderClient) -> Result<()> {
+    async fn iteration(config: &Config, client: &AtCoderClient) -> Result<()> {
+        let db = config.database.connect().await?;
+        let mut contests = db.load_contests().await?;
+        contests.sort_by_key(|c| c.start_epoch_second);
+        contests.reverse();
+
+        for contest in contests.iter().take(config.crawl.new_contest_count) {
+            info!("Starting {}", contest.id);
+            let crawler = WholeContestCrawler::new(db.clone(), client.clone(), &contest.id);
+            crawler
+                .crawl()
+                .await
+                .with_context(|| format!("Failed to crawl {}", contest.id))?;
+        }
+        Ok(())
+    }
+
+    loop {
+        info!("Start new loop");
+        systemd::notify_watchdog();
+        if let Err(e) = iteration(config, client).await {
+            error!("{:?}", e);
+            error_report::report(&e, &[]);
+            sleep_1sec();
+        }
+    }
+}
+
+/// Crawls the request at the head of the queue, and returns false if the queue is empty.
+async fn crawl_request(config: &Config, db: &PgPool, client: &AtCoderClient) -> Result<bool> {
+    let (request, failed_attempts) = match db.pop_crawl().await? {
+        Some(popped) => popped,
+        None => return Ok(false),
+    };
+    info!("Crawling the requested {:?}", request);
+    let result = match &request {
+        CrawlRequest::Contest(contest_id) => {
+            WholeContestCrawler::new(db.clone(), client.clone(), contest_id)
+                .crawl()
+                .await
+        }
+        CrawlRequest::User(user_id) if !config.features.user_crawler => {
+            warn!(
+                "Dropped the request of {} since the user crawler is disabled",
+                user_id
+            );
+            Ok(())
+        }
+        CrawlRequest::User(user_id) => crawl_user(config, db, client, user_id).await,
+    };
+    if let Err(e) = result {
+        let failed_attempts = failed_attempts + 1;
+        if failed_attempts < MAX_CRAWL_ATTEMPTS {
+            db.retry_crawl(&request, failed_attempts, Utc::now().timestamp())
+                .await?;
+        } else {
+            warn!(
+                "Gave up {:?} after {} attempts",
+                request, MAX_CRAWL_ATTEMPTS
+            );
+        }
+        return Err(e.context(format!(
+            "Failed to crawl {:?} ({} attempts)",
+            request, failed_attempts
+        )));
+    }
+    Ok(true)
+}
+
+/// Crawls the submissions of the user in the contests where the user has submitted before,
+/// and in the newest contests.
+async fn crawl_user(
+    config: &Config,
+    db: &PgPool,
+    client: &AtCoderClient,
+    user_id: &str,
+) -> Result<()> {
+    let mut contest_ids = db
+        .get_submissions(SubmissionRequest::UserAll {
+            user_id: &UserId::from(user_id),
+        })
+        .await?
+        .into_iter()
+        .map(|submission| submission.contest_id.into_string())
+        .collect::<BTreeSet<_>>();
+    let mut contests = db.load_contests().await?;
+    contests.sort_by_key(|contest| Reverse(contest.start_epoch_second));
+    contest_ids.extend(
+        contests
+            .into_iter()
+            .take(config.crawl.new_contest_count)
+            .map(|contest| contest.id.into_string()),
+    );
+    let contest_ids = contest_ids.into_iter().collect();
+    UserCrawler::new(db.clone(), client.clone(), user_id, contest_ids)
+        .crawl()
+        .await
+}
+
+async fn crawl_requests(config: &Config, client: &AtCoderClient) -> Result<()> {
+    let db = config.database.connect().await?;
+    let mut listener = CrawlRequestListener::connect(&db).await?;
+    // The queue is also polled, since the notifications are lost while the listener reconnects.
+    let polling_interval = Duration::from_secs(config.crawl.request_polling_interval_second);
+    loop {
+        systemd::notify_watchdog();
+        match crawl_request(config, &db, client).await {
+            Ok(true) => {}
+            Ok(false) => {
+                if let Ok(Err(e)) = timeout(polling_interval, listener.recv()).await {
+                    error!("Failed to listen to the crawl requests: {:?}", e);
+                    sleep_1sec();
+                }
+            }
+            Err(e) => {
+                error!("{:?}", e);
+                error_report::report(&e, &[]);
+                sleep_1sec();
+            }
+        }
+    }
+}
+
+async fn crawl_recent_submissions(config: &Config, client: &AtCoderClient) -> Result<()> {
+    loop {
+        info!("Start new loop");
+        systemd::notify_watchdog();
+        let result = match config.database.connect().await {
+            Ok(db) => RecentCrawler::new(db, client.clone()).crawl().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("{:?}", e);
+            error_report::report(&e, &[]);
+            sleep_1sec();
+        }
+    }
+}
+
+async fn crawl_virtual_contests(config: &Config, client: &AtCoderClient) -> Result<()> {
+    async fn iteration(config: &Config, client: &AtCoderClient) -> Result<()> {
+        info!("Start crawling...");
+        let db = config.database.connect().await?;
+        let mut rng = thread_rng();
+        let mut crawler = VirtualContestCrawler::new(db.clone(), client.clone(), &mut rng);
+        crawler.crawl().await?;
+        info!("Finished crawling");
+
+        info!("Starting fixing...");
+        let cur = Utc::now().timestamp();
+        let crawler = FixCrawler::new(
+            db,
+            client.clone(),
+            cur - config.crawl.virtual_contest_fix_range_second,
+        );
+        crawler.crawl().await?;
+        info!("Finished fixing");
+        Ok(())
+    }
+
+    loop {
+        info!("Start new loop...");
+        systemd::notify_watchdog();
+        let now = Instant::now();
+        if let Err(e) = iteration(config, client).await {
+            error!("{:?}", e);
+            error_report::report(&e, &[]);
+        }
+        sleep_until_interval(now, config.crawl.virtual_contest_interval_second);
+        info!("Finished a loop");
+    }
+}
+
+/// Runs the crawler every `live_interval_second` with a new connection pool and the current time.
+async fn crawl_every_interval<F, Fut>(
+    config: &Config,
+    client: &AtCoderClient,
+    crawl: F,
+) -> Result<()>
+where
+    F: Fn(PgPool, AtCoderClient, i64) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    loop {
+        info!("Start new loop");
+        systemd::notify_watchdog();
+        let now = Instant::now();
+        let result = match config.database.connect().await {
+            Ok(db) => crawl(db, client.clone(), Utc::now().timestamp()).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            error!("{:?}", e);
+            error_report::report(&e, &[]);
+        }
+        sleep_until_interval(now, config.crawl.live_interval_second);
+    }
+}
+
+fn sleep_until_interval(started: Instant, interval_second: u64) {
+    let elapsed_secs = started.elapsed().as_secs();
+    info!("Elapsed {} sec.", elapsed_secs);
+    if elapsed_secs < interval_second {
+        let sleep_seconds = interval_second - elapsed_secs;
+        info!("Sleeping {} sec.", sleep_seconds);
+        thread::sleep(Duration::from_secs(sleep_seconds));
+    }
+}
+
+fn sleep_1sec() {
+    thread::sleep(Duration::from_millis(1000));
+}
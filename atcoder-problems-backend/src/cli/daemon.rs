@@ -0,0 +1,144 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use rand::{thread_rng, Rng};
+use std::iter;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use structopt::StructOpt;
+
+use crate::cli::config::{Config, ScheduledJobConfig};
+use crate::cli::status::generate_run_id;
+use crate::cli::{execute, Command};
+use crate::cron::CronSchedule;
+use crate::metrics;
+use crate::systemd;
+
+struct ScheduledJob {
+    job: &'static str,
+    command: Command,
+    schedule: CronSchedule,
+    jitter_second: u64,
+}
+
+impl ScheduledJob {
+    fn parse(config: &ScheduledJobConfig) -> Result<Self> {
+        let args = iter::once("atcoder-problems").chain(config.command.split_whitespace());
+        let command = Command::from_iter_safe(args)
+            .map_err(|e| anyhow!("Invalid command `{}`: {}", config.command, e.message))?;
+        // The servers and the daemon itself never finish, so they would block the schedule.
+        if let Command::Serve { .. } | Command::ServeGrpc { .. } | Command::Daemon = command {
+            bail!("`{}` runs forever and cannot be scheduled", config.command);
+        }
+        let job = command
+            .job_name()
+            .ok_or_else(|| anyhow!("`{}` cannot be scheduled", config.command))?;
+        let schedule = config.schedule.parse()?;
+        Ok(Self {
+            job,
+            command,
+            schedule,
+            jitter_second: config.jitter_second,
+        })
+    }
+
+    /// Runs the job at the scheduled times until the schedule ends. The runs scheduled while the
+    /// job is running are skipped, so that the job does not overlap with itself.
+    fn run_forever(&self, config: &Arc<Config>) {
+        loop {
+            let now = Utc::now();
+            let next = match self.schedule.next_after(now) {
+                Some(next) => next,
+                None => {
+                    log::warn!("{} is never scheduled again", self.job);
+                    return;
+                }
+            };
+            let jitter_second = thread_rng().gen_range(0, self.jitter_second + 1);
+            log::info!(
+                "{} is scheduled at {} + {} sec.",
+                self.job,
+                next,
+                jitter_second
+            );
+            let delay = (next - now).to_std().unwrap_or_default();
+            thread::sleep(delay + Duration::from_secs(jitter_second));
+
+            metrics::reset();
+            let (status, _) = execute(self.command.clone(), config, &generate_run_id());
+            log::info!("{} finished: {:?}", self.job, status);
+            if let Some(following) = self.schedule.next_after(next) {
+                if following < Utc::now() {
+                    log::warn!("Skipped the runs of {} while it was running", self.job);
+                }
+            }
+        }
+    }
+}
+
+fn parse_jobs(config: &Config) -> Result<Vec<ScheduledJob>> {
+    config
+        .daemon
+        .jobs
+        .iter()
+        .map(|job| ScheduledJob::parse(job).with_context(|| format!("Invalid schedule: {:?}", job)))
+        .collect()
+}
+
+/// Checks the commands and the schedules of the jobs without running them.
+pub(crate) fn validate_jobs(config: &Config) -> Result<()> {
+    parse_jobs(config).map(|_| ())
+}
+
+/// Runs each of the scheduled jobs on its own thread.
+pub(crate) fn run(config: &Arc<Config>) -> Result<()> {
+    let jobs = parse_jobs(config)?;
+    if jobs.is_empty() {
+        bail!("Specify the jobs in [[daemon.jobs]] of the config file");
+    }
+
+    let handles = jobs
+        .into_iter()
+        .map(|job| {
+            let config = Arc::clone(config);
+            thread::Builder::new()
+                .name(job.job.to_owned())
+                .spawn(move || job.run_forever(&config))
+        })
+        .collect::<std::io::Result<Vec<_>>>()?;
+    systemd::notify_ready();
+    for handle in handles {
+        if handle.join().is_err() {
+            log::error!("A scheduled job panicked");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(command: &str) -> ScheduledJobConfig {
+        ScheduledJobConfig {
+            command: command.to_owned(),
+            schedule: "@hourly".to_owned(),
+            jitter_second: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let scheduled = ScheduledJob::parse(&job("crawl submissions --fix")).unwrap();
+        assert_eq!(scheduled.job, "crawl_submissions_fix");
+
+        assert!(ScheduledJob::parse(&job("serve")).is_err());
+        assert!(ScheduledJob::parse(&job("daemon")).is_err());
+        assert!(ScheduledJob::parse(&job("crawl unknown")).is_err());
+        let invalid_schedule = ScheduledJobConfig {
+            schedule: "@yearly".to_owned(),
+            ..job("aggregate")
+        };
+        assert!(ScheduledJob::parse(&invalid_schedule).is_err());
+    }
+}
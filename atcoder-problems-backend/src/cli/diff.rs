@@ -0,0 +1,261 @@
+use crate::compression::{open_decompressed, Compression, FORMATS};
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sql_client::models::{Contest, Problem};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The files written by `snapshot` which are compared.
+struct Snapshot {
+    problems: BTreeMap<String, Problem>,
+    contests: BTreeMap<String, Contest>,
+    difficulties: BTreeMap<String, f64>,
+}
+
+impl Snapshot {
+    fn load(directory: &Path) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct ProblemModel {
+            difficulty: Option<f64>,
+        }
+
+        let problems = read_json::<Vec<Problem>>(&snapshot_file(directory, "problems.json"))?;
+        let contests = read_json::<Vec<Contest>>(&snapshot_file(directory, "contests.json"))?;
+        // The snapshots of the resources uploaded by `dump` have no models.
+        let models_path = snapshot_file(directory, "problem-models.json");
+        let models = if models_path.exists() {
+            read_json::<BTreeMap<String, ProblemModel>>(&models_path)?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self {
+            problems: problems
+                .into_iter()
+                .map(|p| (p.id.to_string(), p))
+                .collect(),
+            contests: contests
+                .into_iter()
+                .map(|c| (c.id.to_string(), c))
+                .collect(),
+            difficulties: models
+                .into_iter()
+                .filter_map(|(id, model)| model.difficulty.map(|d| (id, d)))
+                .collect(),
+        })
+    }
+}
+
+/// The file of the snapshot, which is followed by `.gz` or `.zst` if it is compressed.
+/// The latest one is read if the files of several formats are left, e.g. by an older `snapshot`.
+fn snapshot_file(directory: &Path, name: &str) -> PathBuf {
+    let path = directory.join(name);
+    FORMATS
+        .iter()
+        .map(|&format| Compression::of(format).with_extension(&path))
+        .filter_map(|file| {
+            let modified = fs::metadata(&file).and_then(|m| m.modified()).ok()?;
+            Some((modified, file))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map_or(path, |(_, file)| file)
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let reader =
+        open_decompressed(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_reader(reader).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+trait Item {
+    fn summary(&self) -> String;
+}
+
+impl Item for Problem {
+    fn summary(&self) -> String {
+        format!("{} in {}", self.title, self.contest_id)
+    }
+}
+
+impl Item for Contest {
+    fn summary(&self) -> String {
+        format!(
+            "{} at {} for {} sec. ({})",
+            self.title, self.start_epoch_second, self.duration_second, self.rate_change
+        )
+    }
+}
+
+struct Changes<'a, T> {
+    added: Vec<&'a T>,
+    removed: Vec<&'a T>,
+    changed: Vec<(&'a T, &'a T)>,
+}
+
+impl<'a, T: PartialEq> Changes<'a, T> {
+    fn compare(old: &'a BTreeMap<String, T>, new: &'a BTreeMap<String, T>) -> Self {
+        let added = new
+            .iter()
+            .filter(|(id, _)| !old.contains_key(*id))
+            .map(|(_, item)| item)
+            .collect();
+        let mut removed = Vec::new();
+        let mut changed = Vec::new();
+        for (id, old_item) in old {
+            match new.get(id) {
+                None => removed.push(old_item),
+                Some(new_item) if new_item != old_item => changed.push((old_item, new_item)),
+                Some(_) => {}
+            }
+        }
+        Self {
+            added,
+            removed,
+            changed,
+        }
+    }
+}
+
+impl<T: Item> Changes<'_, T> {
+    fn write(&self, f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} added, {} removed, {} changed",
+            name,
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        )?;
+        for item in &self.added {
+            writeln!(f, "+ {}", item.summary())?;
+        }
+        for item in &self.removed {
+            writeln!(f, "- {}", item.summary())?;
+        }
+        for (old, new) in &self.changed {
+            writeln!(f, "~ {} -> {}", old.summary(), new.summary())?;
+        }
+        Ok(())
+    }
+}
+
+struct SnapshotDiff<'a> {
+    problems: Changes<'a, Problem>,
+    contests: Changes<'a, Contest>,
+    /// The problems whose difficulties changed by the threshold or more.
+    difficulty_shifts: Vec<(&'a str, f64, f64)>,
+    threshold: f64,
+}
+
+impl<'a> SnapshotDiff<'a> {
+    fn new(old: &'a Snapshot, new: &'a Snapshot, threshold: f64) -> Self {
+        let difficulty_shifts = old
+            .difficulties
+            .iter()
+            .filter_map(|(id, &old)| {
+                let new = *new.difficulties.get(id)?;
+                Some((id.as_str(), old, new))
+            })
+            .filter(|(_, old, new)| (new - old).abs() >= threshold)
+            .collect();
+        Self {
+            problems: Changes::compare(&old.problems, &new.problems),
+            contests: Changes::compare(&old.contests, &new.contests),
+            difficulty_shifts,
+            threshold,
+        }
+    }
+
+    fn removed_count(&self) -> usize {
+        self.problems.removed.len() + self.contests.removed.len()
+    }
+}
+
+impl fmt::Display for SnapshotDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.problems.write(f, "Problems")?;
+        self.contests.write(f, "Contests")?;
+        writeln!(
+            f,
+            "Difficulties: {} shifted by {} or more",
+            self.difficulty_shifts.len(),
+            self.threshold
+        )?;
+        for (id, old, new) in &self.difficulty_shifts {
+            writeln!(f, "~ {}: {:.0} -> {:.0} ({:+.0})", id, old, new, new - old)?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the differences between the directories written by `snapshot`, e.g. before publishing
+/// the new one. Fails if `deny_removals` is set and some problems or contests are removed,
+/// which usually means that the crawler failed to parse AtCoder.
+pub(crate) fn diff_snapshots(
+    old: &Path,
+    new: &Path,
+    difficulty_threshold: f64,
+    deny_removals: bool,
+) -> Result<()> {
+    let old = Snapshot::load(old)?;
+    let new = Snapshot::load(new)?;
+    let diff = SnapshotDiff::new(&old, &new, difficulty_threshold);
+    print!("{}", diff);
+    if deny_removals && diff.removed_count() > 0 {
+        bail!("{} problems or contests are removed", diff.removed_count());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(id: &str, title: &str) -> (String, Problem) {
+        let problem = Problem {
+            id: id.into(),
+            contest_id: "abc001".into(),
+            title: title.to_owned(),
+            ..Default::default()
+        };
+        (id.to_owned(), problem)
+    }
+
+    fn snapshot(problems: Vec<(String, Problem)>, difficulties: &[(&str, f64)]) -> Snapshot {
+        Snapshot {
+            problems: problems.into_iter().collect(),
+            contests: BTreeMap::new(),
+            difficulties: difficulties
+                .iter()
+                .map(|(id, d)| (id.to_string(), *d))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let old = snapshot(
+            vec![problem("abc001_a", "A. a"), problem("abc001_b", "B. b")],
+            &[("abc001_a", 100.0), ("abc001_b", 800.0)],
+        );
+        let new = snapshot(
+            vec![problem("abc001_b", "B. bb"), problem("abc001_c", "C. c")],
+            &[("abc001_a", 150.0), ("abc001_b", 1000.0)],
+        );
+        let diff = SnapshotDiff::new(&old, &new, 100.0);
+        assert_eq!(diff.removed_count(), 1);
+        assert_eq!(
+            diff.to_string(),
+            r"Problems: 1 added, 1 removed, 1 changed
++ C. c in abc001
+- A. a in abc001
+~ B. b in abc001 -> B. bb in abc001
+Contests: 0 added, 0 removed, 0 changed
+Difficulties: 1 shifted by 100 or more
+~ abc001_b: 800 -> 1000 (+200)
+"
+        );
+    }
+}
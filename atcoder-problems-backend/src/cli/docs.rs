@@ -0,0 +1,108 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use std::io;
+use std::path::Path;
+use structopt::clap::{ErrorKind, Shell};
+use structopt::StructOpt;
+
+use crate::cli::Cli;
+use crate::utils::write_atomically;
+
+const BIN_NAME: &str = "atcoder-problems";
+
+/// Prints the completion script of the shell to stdout.
+pub(crate) fn completions(shell: Shell) {
+    Cli::clap().gen_completions_to(BIN_NAME, shell, &mut io::stdout());
+}
+
+/// Writes `atcoder-problems.1`, which has the help of all the subcommands, to the directory.
+pub(crate) fn mangen(output_dir: &Path) -> Result<()> {
+    let mut page = format!(
+        ".TH {} 1 {} \"{}\"\n",
+        BIN_NAME.to_uppercase(),
+        Utc::now().format("%Y-%m-%d"),
+        env!("CARGO_PKG_VERSION")
+    );
+    let mut commands = vec![vec![]];
+    while let Some(command) = commands.pop() {
+        let help = long_help(&command)?;
+        let name = std::iter::once(BIN_NAME)
+            .chain(command.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        page.push_str(&format!(".SH \"{}\"\n.nf\n", roff_escape(&name)));
+        for line in help.lines() {
+            page.push_str(&roff_escape(line));
+            page.push('\n');
+        }
+        page.push_str(".fi\n");
+        // In the reverse order, so that the subcommands are popped in the order of the help.
+        for subcommand in subcommands(&help).into_iter().rev() {
+            let mut path = command.clone();
+            path.push(subcommand);
+            commands.push(path);
+        }
+    }
+
+    let path = output_dir.join(format!("{}.1", BIN_NAME));
+    write_atomically(&path, page.as_bytes())?;
+    log::info!("Wrote {}", path.display());
+    Ok(())
+}
+
+/// The help which `--help` prints for the subcommand.
+fn long_help(command: &[String]) -> Result<String> {
+    let args = std::iter::once(BIN_NAME)
+        .chain(command.iter().map(String::as_str))
+        .chain(std::iter::once("--help"));
+    match Cli::clap().get_matches_from_safe(args) {
+        Err(e) if e.kind == ErrorKind::HelpDisplayed => Ok(e.message),
+        _ => Err(anyhow!("Failed to get the help of {:?}", command)),
+    }
+}
+
+/// The names in the `SUBCOMMANDS:` section of the help, except for `help`.
+fn subcommands(help: &str) -> Vec<String> {
+    help.lines()
+        .skip_while(|line| *line != "SUBCOMMANDS:")
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter(|line| line.starts_with("    ") && !line.starts_with("     "))
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != "help")
+        .map(|name| name.to_owned())
+        .collect()
+}
+
+fn roff_escape(line: &str) -> String {
+    let line = line.replace('\\', "\\e");
+    if line.starts_with('.') || line.starts_with('\'') {
+        format!("\\&{}", line)
+    } else {
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subcommands() {
+        let help = long_help(&[]).unwrap();
+        let names = subcommands(&help);
+        assert!(names.contains(&"crawl".to_owned()));
+        assert!(names.contains(&"mangen".to_owned()));
+        assert!(!names.contains(&"help".to_owned()));
+
+        let help = long_help(&["crawl".to_owned()]).unwrap();
+        assert!(subcommands(&help).contains(&"live-performances".to_owned()));
+    }
+
+    #[test]
+    fn test_roff_escape() {
+        assert_eq!(roff_escape(".hidden"), "\\&.hidden");
+        assert_eq!(roff_escape("a\\b"), "a\\eb");
+        assert_eq!(roff_escape("plain"), "plain");
+    }
+}
@@ -0,0 +1,459 @@
+use crate::compression::{open_decompressed, Compression};
+use crate::config::{BLOCKED_CONTESTS, BLOCKED_PROBLEMS};
+use crate::metrics;
+use crate::s3::S3Client;
+use crate::utils::{write_atomically, HashingWriter};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::contest_problem::ContestProblemClient;
+use sql_client::language_count::LanguageCountClient;
+use sql_client::merged_problem::MergedProblemClient;
+use sql_client::models::{Submission, UserSum};
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::{stream_all_submissions, stream_submissions_after};
+use sql_client::time::JstDay;
+use sql_client::{query, PgPool, PgRow, Row};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const LANGUAGE_COUNT_LIMIT: usize = 1000;
+/// The number of the values serialized by a task, which is small enough to balance the tasks and
+/// large enough to make the overhead of the tasks negligible.
+const SERIALIZE_CHUNK_SIZE: usize = 10_000;
+const MANIFEST_FILE: &str = "manifest.json";
+/// The number of the ids below the high-water mark which are checked again for the submissions
+/// crawled after the segments were written, which is a few days of submissions.
+const SEGMENT_RESCAN_IDS: i64 = 200_000;
+
+/// Uploads the resources, which are too large to be served by the API server, to S3 as JSON files.
+pub(crate) async fn dump(pg_pool: &PgPool, client: &S3Client) -> Result<()> {
+    for (name, data) in render_resources(pg_pool).await? {
+        client.update(data, &format!("/resources/{}", name))?;
+    }
+    log::info!("Done.");
+    Ok(())
+}
+
+/// Renders the resources into the JSON files, which are returned with their names.
+/// The data is loaded from the database first, and then the files are serialized in parallel,
+/// where the large arrays are also split into the chunks serialized in parallel.
+/// The files are the same as the ones serialized one by one.
+pub(crate) async fn render_resources(pg_pool: &PgPool) -> Result<Vec<(&'static str, Vec<u8>)>> {
+    let mut contests = pg_pool
+        .load_contests()
+        .await?
+        .into_iter()
+        .filter(|c| !BLOCKED_CONTESTS.contains(&c.id.as_str()))
+        .collect::<Vec<_>>();
+    contests.sort_by_key(|c| c.id.clone());
+
+    let mut accepted_count = pg_pool.load_accepted_count().await?;
+    accepted_count.sort_by_key(|c| c.user_id.clone());
+
+    let mut problems = pg_pool
+        .load_problems()
+        .await?
+        .into_iter()
+        .filter(|c| !BLOCKED_PROBLEMS.contains(&c.id.as_str()))
+        .collect::<Vec<_>>();
+    problems.sort_by_key(|p| p.id.clone());
+
+    let sums: Vec<UserSum> =
+        query("SELECT user_id, point_sum FROM rated_point_sum ORDER BY user_id")
+            .map(|row: PgRow| {
+                let user_id: String = row.get("user_id");
+                let point_sum: f64 = row.get("point_sum");
+                UserSum { user_id, point_sum }
+            })
+            .fetch_all(pg_pool)
+            .await?;
+
+    let language_count = pg_pool.load_language_count().await?;
+    let mut reduced_language_count = BTreeMap::new();
+    for entry in &language_count {
+        reduced_language_count
+            .entry(entry.simplified_language.as_str())
+            .or_insert_with(Vec::new)
+            .push((entry.problem_count, entry));
+    }
+
+    for vec in reduced_language_count.values_mut() {
+        vec.sort_by_key(|e| Reverse(e.0));
+        vec.truncate(LANGUAGE_COUNT_LIMIT);
+    }
+
+    let mut language_count = reduced_language_count
+        .into_iter()
+        .flat_map(|(_, v)| v.into_iter())
+        .map(|(_, e)| e)
+        .collect::<Vec<_>>();
+    language_count.sort_by(|a, b| {
+        a.user_id
+            .cmp(&b.user_id)
+            .then_with(|| a.simplified_language.cmp(&b.simplified_language))
+    });
+
+    let mut contest_problem = pg_pool.load_contest_problem().await?;
+    contest_problem.sort_by_key(|c| (c.contest_id.clone(), c.problem_id.clone()));
+
+    let max_streaks: Vec<UserStreak> =
+        query("SELECT user_id, streak FROM max_streaks ORDER BY user_id")
+            .map(|row: PgRow| {
+                let user_id: String = row.get("user_id");
+                let streak: i64 = row.get("streak");
+                UserStreak { user_id, streak }
+            })
+            .fetch_all(pg_pool)
+            .await?;
+
+    let merged_problems = pg_pool
+        .load_merged_problems()
+        .await?
+        .into_iter()
+        .filter(|c| !BLOCKED_PROBLEMS.contains(&c.id.as_str()))
+        .collect::<Vec<_>>();
+
+    let files: Vec<(&'static str, SerializeTask)> = vec![
+        ("contests.json", Box::new(|| serialize_array(&contests))),
+        ("ac.json", Box::new(|| serialize_array(&accepted_count))),
+        ("problems.json", Box::new(|| serialize_array(&problems))),
+        ("sums.json", Box::new(|| serialize_array(&sums))),
+        ("lang.json", Box::new(|| serialize_array(&language_count))),
+        (
+            "contest-problem.json",
+            Box::new(|| serialize_array(&contest_problem)),
+        ),
+        ("streaks.json", Box::new(|| serialize_array(&max_streaks))),
+        (
+            "merged-problems.json",
+            Box::new(|| serialize_array(&merged_problems)),
+        ),
+    ];
+    files
+        .into_par_iter()
+        .map(|(name, serialize)| Ok((name, serialize()?)))
+        .collect()
+}
+
+type SerializeTask<'a> = Box<dyn Fn() -> Result<Vec<u8>> + Send + Sync + 'a>;
+
+/// Serializes the values into the same bytes as `serde_json::to_vec`, where the chunks of
+/// `SERIALIZE_CHUNK_SIZE` values are serialized in parallel and concatenated in order.
+fn serialize_array<T: Serialize + Sync>(values: &[T]) -> Result<Vec<u8>> {
+    let chunks = values
+        .par_chunks(SERIALIZE_CHUNK_SIZE)
+        .map(|chunk| {
+            let mut bytes = Vec::new();
+            for (i, value) in chunk.iter().enumerate() {
+                if i > 0 {
+                    bytes.push(b',');
+                }
+                serde_json::to_writer(&mut bytes, value)?;
+            }
+            Ok(bytes)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let length = chunks.iter().map(|chunk| chunk.len() + 1).sum::<usize>() + 1;
+    let mut bytes = Vec::with_capacity(length);
+    bytes.push(b'[');
+    for (i, chunk) in chunks.iter().enumerate() {
+        if i > 0 {
+            bytes.push(b',');
+        }
+        bytes.extend_from_slice(chunk);
+    }
+    bytes.push(b']');
+    Ok(bytes)
+}
+
+/// Writes all the submissions to a file as JSON lines like the dumps of `import`, in the order of
+/// their ids. The submissions are streamed from the database to the file one by one, and the file
+/// is compressed by `compression`, which is chosen by the extension of the file by default. It is
+/// written to a temporary file first, so that the output is never a half-written dump.
+pub(crate) async fn dump_submissions(
+    pg_pool: &PgPool,
+    output: &Path,
+    compression: Compression,
+) -> Result<()> {
+    let output = &compression.with_extension(output);
+    let written = write_submissions(stream_all_submissions(pg_pool), output, compression).await?;
+    metrics::add_rows_written(written.count);
+    log::info!(
+        "Wrote {} submissions to {}",
+        written.count,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Appends the submissions newer than the high-water mark of the previous run to the directory as
+/// a new segment of JSON lines, and records it in `manifest.json`, which the consumers follow to
+/// download only the new segments. The segments are named by the day in JST and the sequence
+/// number, e.g. `submissions-2021-05-01-00012.jsonl`, followed by `.gz` or `.zst` if compressed.
+///
+/// The mark is the largest id, but the submissions are not always crawled in the order of their
+/// ids, e.g. when a contest is crawled late. So the last `SEGMENT_RESCAN_IDS` ids below the mark
+/// are checked again, and the submissions which are not in the segments are added to the new
+/// segment. The ones further below the mark are not dumped.
+///
+/// The segments are never rewritten, so the submissions updated after they were dumped, e.g. by
+/// the rejudges, keep the old values, and the submissions of the users removed by `forget-user`
+/// stay in them. The consumers have to apply those changes by themselves.
+pub(crate) async fn dump_submission_segments(
+    pg_pool: &PgPool,
+    directory: &Path,
+    compression: Compression,
+) -> Result<()> {
+    fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create {}", directory.display()))?;
+    let manifest_path = directory.join(MANIFEST_FILE);
+    let mut manifest = match fs::read(&manifest_path) {
+        Ok(manifest) => serde_json::from_slice::<SegmentManifest>(&manifest)
+            .with_context(|| format!("Failed to parse {}", manifest_path.display()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => SegmentManifest::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let now = Utc::now().timestamp();
+    let mut file = format!(
+        "submissions-{}-{:05}.jsonl",
+        JstDay::from_epoch_second(now),
+        manifest.segments.len() + 1
+    );
+    if let Some(extension) = compression.format.extension() {
+        file = format!("{}.{}", file, extension);
+    }
+    let path = directory.join(&file);
+    let after_id = manifest
+        .high_water_id
+        .map_or(i64::MIN, |id| id.saturating_sub(SEGMENT_RESCAN_IDS));
+    let dumped_ids = load_dumped_ids(directory, &manifest, after_id)?;
+    let submissions = stream_submissions_after(pg_pool, after_id)
+        .try_filter(|s| futures::future::ready(!dumped_ids.contains(&s.id)))
+        .boxed();
+    let written = write_submissions(submissions, &path, compression).await?;
+    let (first_id, last_id) = match written.id_range {
+        Some(range) => range,
+        None => {
+            fs::remove_file(&path)?;
+            log::info!("No submissions after {}", after_id);
+            return Ok(());
+        }
+    };
+
+    manifest.high_water_id = manifest.high_water_id.max(Some(last_id));
+    manifest.segments.push(Segment {
+        file,
+        first_id,
+        last_id,
+        count: written.count,
+        created_epoch_second: now,
+        sha256: Some(written.sha256),
+    });
+    write_atomically(&manifest_path, &serde_json::to_vec_pretty(&manifest)?)?;
+    metrics::add_rows_written(written.count);
+    log::info!(
+        "Wrote {} submissions from {} to {} to {}",
+        written.count,
+        first_id,
+        last_id,
+        path.display()
+    );
+    Ok(())
+}
+
+/// The ids after `after_id` in the segments.
+fn load_dumped_ids(
+    directory: &Path,
+    manifest: &SegmentManifest,
+    after_id: i64,
+) -> Result<HashSet<i64>> {
+    let mut ids = HashSet::new();
+    for segment in manifest.segments.iter().filter(|s| s.last_id > after_id) {
+        let path = directory.join(&segment.file);
+        let reader = open_decompressed(&path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        for line in BufReader::new(reader).lines() {
+            let submission: Submission = serde_json::from_str(&line?)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            if submission.id > after_id {
+                ids.insert(submission.id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// The list of the segments written by `dump_submission_segments` in the order in which they are
+/// written, which `bootstrap --segments-url` also downloads.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SegmentManifest {
+    /// The largest id of the dumped submissions.
+    pub(crate) high_water_id: Option<i64>,
+    pub(crate) segments: Vec<Segment>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Segment {
+    pub(crate) file: String,
+    pub(crate) first_id: i64,
+    pub(crate) last_id: i64,
+    pub(crate) count: usize,
+    pub(crate) created_epoch_second: i64,
+    /// The SHA-256 of the file in lowercase hex, which the segments written before it was
+    /// recorded do not have.
+    #[serde(default)]
+    pub(crate) sha256: Option<String>,
+}
+
+struct WrittenSubmissions {
+    count: usize,
+    /// The ids of the first and the last submissions, or `None` if there are no submissions.
+    id_range: Option<(i64, i64)>,
+    /// The SHA-256 of the written file.
+    sha256: String,
+}
+
+/// Writes the submissions to the file as compressed JSON lines through a temporary file.
+async fn write_submissions(
+    mut submissions: BoxStream<'_, Result<Submission>>,
+    output: &Path,
+    compression: Compression,
+) -> Result<WrittenSubmissions> {
+    let mut temporary = output.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let file = File::create(&temporary)
+        .with_context(|| format!("Failed to create {}", Path::new(&temporary).display()))?;
+    let mut writer = compression.writer(HashingWriter::new(BufWriter::new(file)))?;
+
+    let mut count = 0;
+    let mut id_range = None;
+    while let Some(submission) = submissions.try_next().await? {
+        serde_json::to_writer(&mut writer, &submission)?;
+        writer.write_all(b"\n")?;
+        let first_id = id_range.map_or(submission.id, |(first_id, _)| first_id);
+        id_range = Some((first_id, submission.id));
+        count += 1;
+        if count % 1_000_000 == 0 {
+            log::info!("Wrote {} submissions", count);
+        }
+    }
+    let (mut file, sha256) = writer.finish()?.finish();
+    file.flush()?;
+
+    fs::rename(&temporary, output)?;
+    Ok(WrittenSubmissions {
+        count,
+        id_range,
+        sha256,
+    })
+}
+
+#[derive(Serialize)]
+struct UserStreak {
+    user_id: String,
+    streak: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_serialize_array() {
+        for &length in &[0, 1, SERIALIZE_CHUNK_SIZE, SERIALIZE_CHUNK_SIZE * 2 + 1] {
+            let values = (0..length)
+                .map(|i| UserStreak {
+                    user_id: format!("user{}", i),
+                    streak: i as i64,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(
+                serialize_array(&values).unwrap(),
+                serde_json::to_vec(&values).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_segment_manifest() {
+        let manifest = SegmentManifest {
+            high_water_id: Some(200),
+            segments: vec![Segment {
+                file: "submissions-2021-05-01-00001.jsonl".to_owned(),
+                first_id: 1,
+                last_id: 200,
+                count: 150,
+                created_epoch_second: 1_619_827_200,
+                sha256: Some("0".repeat(64)),
+            }],
+        };
+        let json = serde_json::to_value(&manifest).unwrap();
+        assert_eq!(json["high_water_id"], 200);
+        assert_eq!(
+            json["segments"][0]["file"],
+            "submissions-2021-05-01-00001.jsonl"
+        );
+        assert_eq!(
+            serde_json::from_value::<SegmentManifest>(json).unwrap(),
+            manifest
+        );
+
+        let without_sha256 = serde_json::json!({
+            "high_water_id": 1,
+            "segments": [{
+                "file": "submissions-2021-05-01-00001.jsonl",
+                "first_id": 1,
+                "last_id": 1,
+                "count": 1,
+                "created_epoch_second": 1_619_827_200
+            }]
+        });
+        let manifest = serde_json::from_value::<SegmentManifest>(without_sha256).unwrap();
+        assert_eq!(manifest.segments[0].sha256, None);
+    }
+
+    #[test]
+    fn test_load_dumped_ids() {
+        let directory = std::env::temp_dir().join("atcoder-problems-test-segments");
+        fs::create_dir_all(&directory).unwrap();
+        let lines = [1, 5, 3]
+            .iter()
+            .map(|&id| {
+                let submission = Submission {
+                    id,
+                    ..Default::default()
+                };
+                format!("{}\n", serde_json::to_string(&submission).unwrap())
+            })
+            .collect::<String>();
+        let file = "submissions-2021-05-01-00001.jsonl";
+        fs::write(directory.join(file), lines).unwrap();
+
+        let manifest = SegmentManifest {
+            high_water_id: Some(5),
+            segments: vec![Segment {
+                file: file.to_owned(),
+                first_id: 1,
+                last_id: 5,
+                count: 3,
+                created_epoch_second: 0,
+                sha256: None,
+            }],
+        };
+        let ids = load_dumped_ids(&directory, &manifest, 2).unwrap();
+        assert_eq!(ids, vec![3, 5].into_iter().collect());
+        assert!(load_dumped_ids(&directory, &manifest, 5)
+            .unwrap()
+            .is_empty());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,499 @@
+use anyhow::{anyhow, bail, Context, Result};
+use arrow::array::{ArrayRef, Float64Array, Int32Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use chrono::{Datelike, TimeZone, Timelike, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression as ParquetCompression;
+use parquet::file::properties::WriterProperties;
+use sql_client::models::{Problem, Submission};
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::PgPool;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::cli::config::Config;
+use crate::cli::sqlite;
+use crate::compression::{Compression, CompressionFormat};
+
+/// The submissions are loaded and written as a row group or a record batch by this many.
+const BATCH_SIZE: usize = 10_000;
+const FILE_NAME: &str = "submissions.parquet";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ExportFormat {
+    /// The files partitioned by the month in a directory, for Spark and Polars.
+    Parquet,
+    /// An Arrow IPC stream in a file, which pyarrow and the arrow package of R read without copying.
+    Arrow,
+    /// A single SQLite file with the public tables and their indexes.
+    Sqlite,
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "parquet" => Ok(ExportFormat::Parquet),
+            "arrow" => Ok(ExportFormat::Arrow),
+            "sqlite" => Ok(ExportFormat::Sqlite),
+            _ => Err(anyhow!("Unknown export format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ExportTable {
+    Submissions,
+    Problems,
+}
+
+impl FromStr for ExportTable {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "submissions" => Ok(ExportTable::Submissions),
+            "problems" => Ok(ExportTable::Problems),
+            _ => Err(anyhow!("Unknown table to export: {}", s)),
+        }
+    }
+}
+
+/// Exports the table for the analyses outside of the database. The submissions are limited to
+/// the ones whose `epoch_second` is in `[from_second, to_second)`.
+/// The Parquet files compress their pages by the codec of `compression`, which is Snappy by
+/// default, and the other formats are compressed as a whole, which they are not by default.
+pub(crate) async fn export(
+    config: &Config,
+    format: ExportFormat,
+    table: Option<ExportTable>,
+    output: &Path,
+    from_second: Option<i64>,
+    to_second: Option<i64>,
+    compression: Option<Compression>,
+) -> Result<()> {
+    match (format, table) {
+        (ExportFormat::Sqlite, None) => {
+            if from_second.is_some() || to_second.is_some() {
+                bail!("The SQLite file has all the submissions");
+            }
+            let compression = compression.unwrap_or(Compression::NONE);
+            sqlite::export_sqlite(config.database.url()?, output, compression)
+        }
+        (ExportFormat::Sqlite, Some(_)) => bail!("The SQLite file has all the tables"),
+        (ExportFormat::Parquet, None) | (ExportFormat::Parquet, Some(ExportTable::Submissions)) => {
+            // A file of a partial month would replace the file of the whole month.
+            if let Some(second) = from_second
+                .into_iter()
+                .chain(to_second)
+                .find(|&s| !is_month_start(s))
+            {
+                bail!("The range has to be of whole months in UTC: {}", second);
+            }
+            let codec = parquet_codec(compression)?;
+            let pg_pool = config.database.connect().await?;
+            export_parquet(&pg_pool, output, from_second, to_second, codec).await
+        }
+        (ExportFormat::Parquet, _) => bail!("Only the submissions can be exported as Parquet"),
+        (ExportFormat::Arrow, table) => {
+            let pg_pool = config.database.connect().await?;
+            let table = table.unwrap_or(ExportTable::Submissions);
+            let compression = compression.unwrap_or(Compression::NONE);
+            export_arrow(&pg_pool, table, output, from_second, to_second, compression).await
+        }
+    }
+}
+
+fn parquet_codec(compression: Option<Compression>) -> Result<ParquetCompression> {
+    match compression {
+        None => Ok(ParquetCompression::SNAPPY),
+        Some(Compression { level: Some(_), .. }) => {
+            bail!("The codecs of Parquet can not be given the level")
+        }
+        Some(compression) => Ok(match compression.format {
+            CompressionFormat::None => ParquetCompression::UNCOMPRESSED,
+            CompressionFormat::Gzip => ParquetCompression::GZIP,
+            CompressionFormat::Zstd => ParquetCompression::ZSTD,
+        }),
+    }
+}
+
+/// Loads the submissions in `[from_second, to_second)` by `BATCH_SIZE`,
+/// in the order of `epoch_second`.
+struct SubmissionBatches<'a> {
+    pg_pool: &'a PgPool,
+    cursor: (i64, i64),
+    to_second: Option<i64>,
+    finished: bool,
+}
+
+impl<'a> SubmissionBatches<'a> {
+    fn new(pg_pool: &'a PgPool, from_second: Option<i64>, to_second: Option<i64>) -> Self {
+        Self {
+            pg_pool,
+            cursor: (from_second.unwrap_or(0), -1),
+            to_second,
+            finished: false,
+        }
+    }
+
+    async fn next(&mut self) -> Result<Option<Vec<Submission>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let submissions = self
+            .pg_pool
+            .get_submissions(SubmissionRequest::FromCursor {
+                from_second: self.cursor.0,
+                from_id: self.cursor.1,
+                count: BATCH_SIZE as i64,
+            })
+            .await?;
+        if let Some(last) = submissions.last() {
+            self.cursor = (last.epoch_second, last.id);
+        }
+        let to_second = self.to_second;
+        let submissions = submissions
+            .into_iter()
+            .take_while(|s| to_second.map_or(true, |to_second| s.epoch_second < to_second))
+            .collect::<Vec<_>>();
+        self.finished = submissions.len() < BATCH_SIZE;
+        if submissions.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(submissions))
+        }
+    }
+}
+
+/// Writes the submissions to the Parquet files partitioned by the month, e.g.
+/// `month=2021-01/submissions.parquet`, which Spark and Polars read as a partitioned dataset.
+/// Each file is replaced atomically.
+async fn export_parquet(
+    pg_pool: &PgPool,
+    output_dir: &Path,
+    from_second: Option<i64>,
+    to_second: Option<i64>,
+    codec: ParquetCompression,
+) -> Result<()> {
+    let schema = submission_schema();
+    let mut batches = SubmissionBatches::new(pg_pool, from_second, to_second);
+    let mut partition: Option<PartitionWriter> = None;
+    while let Some(submissions) = batches.next().await? {
+        // The submissions are sorted by `epoch_second`, so each month is written at once.
+        let mut rest = submissions.as_slice();
+        while let Some(first) = rest.first() {
+            let month = month_of(first.epoch_second);
+            let count = rest
+                .iter()
+                .take_while(|s| month_of(s.epoch_second) == month)
+                .count();
+            let (current, next) = rest.split_at(count);
+            let writer = match partition.take() {
+                Some(writer) if writer.month == month => writer,
+                previous => {
+                    if let Some(previous) = previous {
+                        previous.finish()?;
+                    }
+                    PartitionWriter::create(output_dir, &month, schema.clone(), codec)?
+                }
+            };
+            partition = Some(writer.write(current)?);
+            rest = next;
+        }
+    }
+    if let Some(writer) = partition {
+        writer.finish()?;
+    }
+    Ok(())
+}
+
+/// Writes the table to the file as an Arrow IPC stream. The file is written from the beginning,
+/// so that it can be a named pipe which an analysis reads while it is exported. It is named with
+/// the extension of the compression, e.g. `submissions.arrow.gz`.
+async fn export_arrow(
+    pg_pool: &PgPool,
+    table: ExportTable,
+    output: &Path,
+    from_second: Option<i64>,
+    to_second: Option<i64>,
+    compression: Compression,
+) -> Result<()> {
+    let output = &compression.with_extension(output);
+    let file =
+        File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut file = compression.writer(BufWriter::new(file))?;
+    let rows = match table {
+        ExportTable::Submissions => {
+            let schema = submission_schema();
+            let mut writer = StreamWriter::try_new(&mut file, &schema)?;
+            let mut batches = SubmissionBatches::new(pg_pool, from_second, to_second);
+            let mut rows = 0;
+            while let Some(submissions) = batches.next().await? {
+                writer.write(&to_record_batch(schema.clone(), &submissions)?)?;
+                rows += submissions.len();
+            }
+            writer.finish()?;
+            rows
+        }
+        ExportTable::Problems => {
+            let problems = pg_pool.load_problems().await?;
+            write_problems(&mut file, &problems)?;
+            problems.len()
+        }
+    };
+    file.finish()?.flush()?;
+    log::info!("Wrote {} rows to {}", rows, output.display());
+    Ok(())
+}
+
+fn write_problems<W: Write>(writer: W, problems: &[Problem]) -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("contest_id", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, false),
+        Field::new("problem_index", DataType::Utf8, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let strings = |f: fn(&Problem) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from(
+            problems.iter().map(f).collect::<Vec<_>>(),
+        ))
+    };
+    let columns = vec![
+        strings(|p| p.id.as_str()),
+        strings(|p| p.contest_id.as_str()),
+        strings(|p| p.title.as_str()),
+        strings(|p| p.problem_index.as_str()),
+        strings(|p| p.name.as_str()),
+    ];
+    let mut writer = StreamWriter::try_new(writer, &schema)?;
+    writer.write(&RecordBatch::try_new(schema.clone(), columns)?)?;
+    writer.finish()?;
+    Ok(())
+}
+
+fn submission_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("epoch_second", DataType::Int64, false),
+        Field::new("problem_id", DataType::Utf8, false),
+        Field::new("contest_id", DataType::Utf8, false),
+        Field::new("user_id", DataType::Utf8, false),
+        Field::new("language", DataType::Utf8, false),
+        Field::new("point", DataType::Float64, false),
+        Field::new("length", DataType::Int32, false),
+        Field::new("result", DataType::Utf8, false),
+        Field::new("execution_time", DataType::Int32, true),
+    ]))
+}
+
+fn to_record_batch(schema: SchemaRef, submissions: &[Submission]) -> Result<RecordBatch> {
+    let strings = |f: fn(&Submission) -> &str| -> ArrayRef {
+        Arc::new(StringArray::from(
+            submissions.iter().map(f).collect::<Vec<_>>(),
+        ))
+    };
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from(
+            submissions.iter().map(|s| s.id).collect::<Vec<_>>(),
+        )),
+        Arc::new(Int64Array::from(
+            submissions
+                .iter()
+                .map(|s| s.epoch_second)
+                .collect::<Vec<_>>(),
+        )),
+        strings(|s| s.problem_id.as_str()),
+        strings(|s| s.contest_id.as_str()),
+        strings(|s| s.user_id.as_str()),
+        strings(|s| s.language.as_str()),
+        Arc::new(Float64Array::from(
+            submissions.iter().map(|s| s.point).collect::<Vec<_>>(),
+        )),
+        Arc::new(Int32Array::from(
+            submissions.iter().map(|s| s.length).collect::<Vec<_>>(),
+        )),
+        strings(|s| s.result.as_str()),
+        Arc::new(Int32Array::from(
+            submissions
+                .iter()
+                .map(|s| s.execution_time)
+                .collect::<Vec<_>>(),
+        )),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+/// The name of the partition directory, in the Hive style.
+fn month_of(epoch_second: i64) -> String {
+    Utc.timestamp(epoch_second, 0)
+        .format("month=%Y-%m")
+        .to_string()
+}
+
+fn is_month_start(epoch_second: i64) -> bool {
+    let time = Utc.timestamp(epoch_second, 0);
+    time.day() == 1 && time.num_seconds_from_midnight() == 0
+}
+
+/// Writes a partition to a temporary file, which replaces the file of the partition when finished.
+struct PartitionWriter {
+    month: String,
+    schema: SchemaRef,
+    path: PathBuf,
+    temporary: PathBuf,
+    writer: ArrowWriter<File>,
+    rows: usize,
+}
+
+impl PartitionWriter {
+    fn create(
+        output_dir: &Path,
+        month: &str,
+        schema: SchemaRef,
+        codec: ParquetCompression,
+    ) -> Result<Self> {
+        let directory = output_dir.join(month);
+        fs::create_dir_all(&directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+        let path = directory.join(FILE_NAME);
+        let temporary = directory.join(format!("{}.tmp", FILE_NAME));
+        let file = File::create(&temporary)
+            .with_context(|| format!("Failed to create {}", temporary.display()))?;
+        let properties = WriterProperties::builder().set_compression(codec).build();
+        let writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))?;
+        Ok(Self {
+            month: month.to_owned(),
+            schema,
+            path,
+            temporary,
+            writer,
+            rows: 0,
+        })
+    }
+
+    fn write(mut self, submissions: &[Submission]) -> Result<Self> {
+        let batch = to_record_batch(self.schema.clone(), submissions)?;
+        self.writer.write(&batch)?;
+        self.rows += submissions.len();
+        Ok(self)
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.writer.close()?;
+        fs::rename(&self.temporary, &self.path)?;
+        log::info!("Wrote {} submissions to {}", self.rows, self.path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::ipc::reader::StreamReader;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use std::io::Cursor;
+
+    fn submission(id: i64, epoch_second: i64) -> Submission {
+        Submission {
+            id,
+            epoch_second,
+            problem_id: "abc001_a".into(),
+            contest_id: "abc001".into(),
+            user_id: "kenkoooo".into(),
+            language: "Rust".to_owned(),
+            point: 100.0,
+            length: 42,
+            result: "AC".to_owned(),
+            execution_time: if id % 2 == 0 { Some(10) } else { None },
+        }
+    }
+
+    #[test]
+    fn test_month_of() {
+        assert_eq!(month_of(1_600_000_000), "month=2020-09");
+        assert_eq!(month_of(0), "month=1970-01");
+    }
+
+    #[test]
+    fn test_is_month_start() {
+        // 2020-09-01T00:00:00Z
+        assert!(is_month_start(1_598_918_400));
+        assert!(!is_month_start(1_598_918_401));
+        assert!(!is_month_start(1_600_000_000));
+    }
+
+    #[test]
+    fn test_partition_writer() {
+        let dir =
+            std::env::temp_dir().join(format!("atcoder-problems-export-{}", std::process::id()));
+        let writer = PartitionWriter::create(
+            &dir,
+            "month=2020-09",
+            submission_schema(),
+            ParquetCompression::SNAPPY,
+        )
+        .unwrap();
+        let writer = writer
+            .write(&[submission(1, 1_600_000_000), submission(2, 1_600_000_001)])
+            .unwrap();
+        let writer = writer.write(&[submission(3, 1_600_000_002)]).unwrap();
+        writer.finish().unwrap();
+
+        let path = dir.join("month=2020-09").join(FILE_NAME);
+        let reader = SerializedFileReader::new(File::open(&path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_problems() {
+        let problems = vec![
+            Problem {
+                id: "abc001_a".into(),
+                contest_id: "abc001".into(),
+                title: "A. 積雪深差".to_owned(),
+                problem_index: "A".to_owned(),
+                name: "積雪深差".to_owned(),
+            },
+            Problem {
+                id: "abc001_b".into(),
+                contest_id: "abc001".into(),
+                title: "B. 視程の通報".to_owned(),
+                problem_index: "B".to_owned(),
+                name: "視程の通報".to_owned(),
+            },
+        ];
+        let mut stream = Vec::new();
+        write_problems(&mut stream, &problems).unwrap();
+
+        let reader = StreamReader::try_new(Cursor::new(stream)).unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 2);
+        let titles = batches[0]
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(titles.value(1), "B. 視程の通報");
+    }
+
+    #[test]
+    fn test_parquet_codec() {
+        assert_eq!(parquet_codec(None).unwrap(), ParquetCompression::SNAPPY);
+        let zstd = Compression::of(CompressionFormat::Zstd);
+        assert_eq!(parquet_codec(Some(zstd)).unwrap(), ParquetCompression::ZSTD);
+        let gzip = Compression::new(CompressionFormat::Gzip, Some(9)).unwrap();
+        assert!(parquet_codec(Some(gzip)).is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::PgPool;
+
+/// Fails if the latest submission is older than `max_data_age_second`, which means the crawlers
+/// have stopped. Scheduled with the notifications, it tells the operators about the stale data.
+pub(crate) async fn check_freshness(pg_pool: &PgPool, max_data_age_second: i64) -> Result<()> {
+    let latest = pg_pool
+        .get_submissions(SubmissionRequest::RecentAll { count: 1 })
+        .await?;
+    let latest_epoch_second = match latest.first() {
+        Some(submission) => submission.epoch_second,
+        None => bail!("There are no submissions"),
+    };
+    let data_age_second = Utc::now().timestamp() - latest_epoch_second;
+    log::info!("The latest submission is {} sec. old", data_age_second);
+    if data_age_second > max_data_age_second {
+        bail!(
+            "The latest submission is {} sec. old, older than {} sec.",
+            data_age_second,
+            max_data_age_second
+        );
+    }
+    Ok(())
+}
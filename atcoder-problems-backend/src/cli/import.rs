@@ -0,0 +1,263 @@
+use crate::cli::config::Config;
+use crate::metrics;
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::MultiGzDecoder;
+use serde_json::Value;
+use sql_client::backup::{CopyFormat, Loader};
+use sql_client::simple_client::SimpleClient;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The tables which can be imported, in the order in which they are imported.
+const TABLES: [&str; 3] = ["contests", "problems", "submissions"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DumpFormat {
+    /// CSV whose first line is the header of the column names.
+    Csv,
+    /// A JSON object per line, whose keys are the column names.
+    Jsonl,
+}
+
+/// A dump file, e.g. `submissions.csv.gz`, whose name tells the table and the format.
+#[derive(Debug, PartialEq)]
+struct DumpFile {
+    path: PathBuf,
+    table: &'static str,
+    format: DumpFormat,
+    gzip: bool,
+}
+
+impl DumpFile {
+    fn parse(path: &Path) -> Result<Self> {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| anyhow!("Invalid file name: {}", path.display()))?;
+        let (name, gzip) = match name.strip_suffix(".gz") {
+            Some(name) => (name, true),
+            None => (name, false),
+        };
+        let (name, format) = if let Some(name) = name.strip_suffix(".csv") {
+            (name, DumpFormat::Csv)
+        } else if let Some(name) = name.strip_suffix(".jsonl") {
+            (name, DumpFormat::Jsonl)
+        } else {
+            bail!("{} is neither .csv nor .jsonl", path.display());
+        };
+        let table = TABLES
+            .iter()
+            .find(|table| **table == name)
+            .ok_or_else(|| anyhow!("{} is not one of {}", name, TABLES.join(", ")))?;
+        Ok(Self {
+            path: path.to_owned(),
+            table,
+            format,
+            gzip,
+        })
+    }
+
+    fn open(&self) -> Result<Box<dyn BufRead>> {
+        let file = File::open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        if self.gzip {
+            Ok(Box::new(BufReader::new(MultiGzDecoder::new(
+                BufReader::new(file),
+            ))))
+        } else {
+            Ok(Box::new(BufReader::new(file)))
+        }
+    }
+}
+
+/// Adds the rows of the dump files to the tables with `COPY`, skipping the rows which already
+/// exist, so that a new database is bootstrapped without crawling the history. The aggregated
+/// tables are not updated, so `aggregate` should be run after this. The derived columns of the
+/// contests, e.g. the categories, are written after the contests are imported.
+pub(crate) async fn import(config: &Config, inputs: &[PathBuf]) -> Result<()> {
+    let mut dumps = inputs
+        .iter()
+        .map(|input| DumpFile::parse(input))
+        .collect::<Result<Vec<_>>>()?;
+    dumps.sort_by_key(|dump| TABLES.iter().position(|table| *table == dump.table));
+
+    let mut loader = Loader::connect(config.database.url()?)?;
+    for dump in dumps.iter() {
+        log::info!("Importing {} into {}", dump.path.display(), dump.table);
+        let mut reader = dump.open()?;
+        let rows = match dump.format {
+            DumpFormat::Csv => {
+                let mut header = String::new();
+                reader.read_line(&mut header)?;
+                let columns = parse_csv_header(&header);
+                loader.import_table(dump.table, &columns, CopyFormat::Csv, reader)?
+            }
+            DumpFormat::Jsonl => {
+                let columns = loader
+                    .columns(dump.table)?
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>();
+                let rows = JsonlRows::new(reader, columns.clone());
+                loader.import_table(dump.table, &columns, CopyFormat::Text, rows)?
+            }
+        };
+        log::info!("Imported {} rows into {}", rows, dump.table);
+        metrics::add_rows_written(rows as usize);
+    }
+
+    if dumps.iter().any(|dump| dump.table == "contests") {
+        let pg_pool = config.database.connect().await?;
+        let rows = pg_pool.update_derived_contest_columns().await?;
+        log::info!("Updated the derived columns of {} contests", rows);
+    }
+    Ok(())
+}
+
+/// The column names in the header, which may be quoted.
+fn parse_csv_header(header: &str) -> Vec<String> {
+    header
+        .trim_end_matches(&['\r', '\n'][..])
+        .split(',')
+        .map(|name| name.trim().trim_matches('"').to_owned())
+        .collect()
+}
+
+/// Converts the JSON objects to the rows in the text format of `COPY`. The columns which are
+/// missing in an object are NULL.
+struct JsonlRows<R> {
+    reader: R,
+    columns: Vec<String>,
+    line_number: usize,
+    buffer: Vec<u8>,
+    position: usize,
+}
+
+impl<R: BufRead> JsonlRows<R> {
+    fn new(reader: R, columns: Vec<String>) -> Self {
+        Self {
+            reader,
+            columns,
+            line_number: 0,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Fills the buffer with the next row, and returns false at the end of the input.
+    fn fill(&mut self) -> io::Result<bool> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(false);
+            }
+            self.line_number += 1;
+            if !line.trim().is_empty() {
+                break;
+            }
+        }
+        let object: Value = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid JSON at line {}: {}", self.line_number, e),
+            )
+        })?;
+        self.buffer = copy_row(&object, &self.columns).into_bytes();
+        self.position = 0;
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Read for JsonlRows<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.buffer.len() && !self.fill()? {
+            return Ok(0);
+        }
+        let size = buf.len().min(self.buffer.len() - self.position);
+        buf[..size].copy_from_slice(&self.buffer[self.position..self.position + size]);
+        self.position += size;
+        Ok(size)
+    }
+}
+
+/// A line in the text format of `COPY`, where the columns are separated by tabs, the special
+/// characters are escaped by backslashes, and `\N` is NULL.
+fn copy_row(object: &Value, columns: &[String]) -> String {
+    let fields = columns
+        .iter()
+        .map(|column| match &object[column] {
+            Value::Null => "\\N".to_owned(),
+            Value::String(value) => escape_copy_field(value),
+            value => escape_copy_field(&value.to_string()),
+        })
+        .collect::<Vec<_>>();
+    format!("{}\n", fields.join("\t"))
+}
+
+fn escape_copy_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dump_file() {
+        let dump = DumpFile::parse(Path::new("dumps/submissions.csv.gz")).unwrap();
+        assert_eq!(dump.table, "submissions");
+        assert_eq!(dump.format, DumpFormat::Csv);
+        assert!(dump.gzip);
+
+        let dump = DumpFile::parse(Path::new("problems.jsonl")).unwrap();
+        assert_eq!(dump.table, "problems");
+        assert_eq!(dump.format, DumpFormat::Jsonl);
+        assert!(!dump.gzip);
+
+        assert!(DumpFile::parse(Path::new("contests.json.gz")).is_err());
+        assert!(DumpFile::parse(Path::new("accepted_count.csv")).is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_header() {
+        assert_eq!(
+            parse_csv_header("\"id\",epoch_second, user_id\r\n"),
+            vec!["id", "epoch_second", "user_id"]
+        );
+    }
+
+    #[test]
+    fn test_jsonl_rows() {
+        let input = r#"{"id": "abc001_a", "title": "A.\tTab", "point": 100.0}
+
+{"id": "abc001_b", "title": "B. Back\\slash"}
+"#;
+        let columns = vec!["id".to_owned(), "title".to_owned(), "point".to_owned()];
+        let mut rows = String::new();
+        JsonlRows::new(input.as_bytes(), columns)
+            .read_to_string(&mut rows)
+            .unwrap();
+        assert_eq!(
+            rows,
+            "abc001_a\tA.\\tTab\t100.0\nabc001_b\tB. Back\\\\slash\t\\N\n"
+        );
+
+        let mut rows = String::new();
+        let error = JsonlRows::new("{}\nnot json\n".as_bytes(), vec!["id".to_owned()])
+            .read_to_string(&mut rows)
+            .unwrap_err();
+        assert!(error.to_string().contains("line 2"));
+    }
+}
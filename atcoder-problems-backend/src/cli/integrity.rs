@@ -0,0 +1,55 @@
+use anyhow::{bail, Context, Result};
+use sql_client::integrity::IntegrityClient;
+use sql_client::PgPool;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Writes the integrity report as JSON to `output`, or to stdout if it is not given. Fails if
+/// there are duplicate ids or orphans and `fail_on_issues` is set, so that they are notified.
+pub(crate) async fn integrity_check(
+    pg_pool: &PgPool,
+    output: Option<&Path>,
+    fail_on_issues: bool,
+) -> Result<()> {
+    let report = pg_pool.check_integrity().await?;
+    match output {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?;
+            let mut writer = BufWriter::new(file);
+            serde_json::to_writer_pretty(&mut writer, &report)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut writer = stdout.lock();
+            serde_json::to_writer_pretty(&mut writer, &report)?;
+            writeln!(writer)?;
+        }
+    }
+
+    for orphans in report.orphans.iter().filter(|o| o.count > 0) {
+        log::warn!(
+            "{} rows of {} refer to unknown {} by {}, e.g. {:?}",
+            orphans.count,
+            orphans.table,
+            orphans.referenced_table,
+            orphans.column,
+            orphans.sample
+        );
+    }
+    let duplicates = &report.duplicate_submission_ids;
+    if duplicates.count > 0 {
+        log::warn!(
+            "{} ids are shared by multiple submissions, e.g. {:?}",
+            duplicates.count,
+            duplicates.sample
+        );
+    }
+    if fail_on_issues && report.has_issues() {
+        bail!("The integrity check found issues");
+    }
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use crate::cli::config::Config;
+use anyhow::{bail, Context, Result};
+use sql_client::execute_script;
+use std::fs;
+use std::path::Path;
+
+/// Creates the tables from the database definition. The definition drops the existing tables,
+/// so it runs only when `reset` confirms that all the data in the database will be lost.
+pub(crate) async fn migrate(config: &Config, schema: &Path, reset: bool) -> Result<()> {
+    if !reset {
+        bail!("Pass --reset to confirm that the existing tables are dropped");
+    }
+    let script = fs::read_to_string(schema)
+        .with_context(|| format!("Failed to read {}", schema.display()))?;
+    let pg_pool = config.database.connect().await?;
+    log::info!("Executing {}", schema.display());
+    execute_script(&pg_pool, &script).await?;
+    log::info!("Finished");
+    Ok(())
+}
@@ -0,0 +1,1072 @@
+mod aggregate;
+mod backup;
+mod bootstrap;
+mod check_config;
+mod compress;
+pub mod config;
+mod crawl;
+mod daemon;
+mod diff;
+mod docs;
+mod dump;
+mod export;
+mod freshness;
+mod import;
+mod integrity;
+mod migrate;
+mod partition;
+mod problem_list;
+mod report;
+mod retention;
+mod review;
+mod seed;
+mod serve;
+mod snapshot;
+mod sqlite;
+mod status;
+mod user_report;
+mod verify;
+
+pub use crawl::CrawlCommand;
+pub use status::ExitStatus;
+
+use crate::bigquery::stream_submissions;
+use crate::clickhouse::replicate_submissions;
+use crate::compression::{Compression, CompressionFormat};
+use crate::error_report;
+use crate::events::publish_events;
+use crate::metrics::{self, JobMetrics};
+use crate::progress::{self, BarReporter, QuietReporter};
+use crate::utils::LogFormat;
+use crate::webhook::deliver_submissions;
+use anyhow::Result;
+use bootstrap::SegmentSource;
+use chrono::{NaiveDate, Utc};
+use config::Config;
+use export::{ExportFormat, ExportTable};
+use log::LevelFilter;
+use review::ReviewFormat;
+use sql_client::compressed_text::TextTable;
+use sql_client::crawler_run::{CrawlerRun, CrawlerRunClient};
+use sql_client::forget_user::{ForgetMode, ForgetUserClient};
+use sql_client::ids::UserId;
+use sql_client::merge_user::MergeUserClient;
+use status::{generate_run_id, RunStatus};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use std::{thread, time};
+use structopt::clap::Shell;
+use structopt::StructOpt;
+use surf::Url;
+use user_report::ReportFormat;
+
+const DEFAULT_SCHEMA_PATH: &str = "../config/database-definition.sql";
+
+/// The backend of AtCoder Problems.
+#[derive(StructOpt, Debug)]
+#[structopt(name = "atcoder-problems")]
+pub struct Cli {
+    /// The config file. `atcoder-problems.toml` in the working directory is loaded if it exists.
+    #[structopt(long, global = true, parse(from_os_str))]
+    config: Option<PathBuf>,
+    /// Connection URL of PostgreSQL, which overrides DATABASE_URL and the config file.
+    /// Prefer DATABASE_URL or DATABASE_URL_FILE if it contains a password, which `ps` shows.
+    #[structopt(long, global = true)]
+    database_url: Option<String>,
+    /// One of off, error, warn, info, debug and trace.
+    #[structopt(long, default_value = "info", global = true)]
+    log_level: LevelFilter,
+    /// `json` prints one JSON object per line for the log collectors.
+    #[structopt(long, default_value = "text", possible_values = &["text", "json"], global = true)]
+    pub log_format: LogFormat,
+    /// Shows the contests processed, the pages fetched and the rows written on stderr,
+    /// and logs only the warnings and the errors.
+    #[structopt(long, global = true, conflicts_with = "quiet")]
+    progress: bool,
+    /// Prints only the errors and the summary of the run, e.g. for cron.
+    #[structopt(long, short, global = true)]
+    quiet: bool,
+    /// Writes the summary of the run as JSON to this file when the command finishes.
+    #[structopt(long, global = true, parse(from_os_str))]
+    status_file: Option<PathBuf>,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+enum Command {
+    /// Runs the crawlers.
+    Crawl(CrawlCommand),
+    /// Updates the rankings and the other aggregations of the submissions.
+    Aggregate {
+        /// Updates only the users who have got accepted recently.
+        #[structopt(long)]
+        delta: bool,
+    },
+    /// Runs the API server.
+    Serve {
+        /// Overrides PORT and the config file. 8080 by default.
+        #[structopt(long)]
+        port: Option<u16>,
+    },
+    /// Runs the gRPC server for the internal services.
+    ServeGrpc {
+        /// Overrides GRPC_PORT and the config file. 50051 by default.
+        #[structopt(long)]
+        port: Option<u16>,
+    },
+    /// Posts the new submissions to the webhooks registered by the users.
+    DeliverWebhooks,
+    /// Streams the new submissions to BigQuery, creating the table or adding the missing columns.
+    SinkBigquery,
+    /// Replicates the new and the updated submissions to ClickHouse, creating the table.
+    SinkClickhouse {
+        /// Copies the existing submissions since this epoch second first, e.g. 0 for all of them.
+        #[structopt(long)]
+        backfill_from_second: Option<i64>,
+    },
+    /// Publishes the updated submissions and the new contests to Kafka or NATS.
+    PublishEvents,
+    /// Uploads the large resources to S3, compressed by gzip with the best level by default.
+    Dump {
+        /// Backs up all the tables to this file, e.g. `backup.tar.zst`, instead, which is
+        /// compressed by zstd by default.
+        #[structopt(long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Streams all the submissions as JSON lines to this file instead, which is compressed
+        /// by gzip for `.gz` or by zstd for `.zst` by default.
+        #[structopt(long, parse(from_os_str), conflicts_with = "output")]
+        submissions_jsonl: Option<PathBuf>,
+        /// Appends only the submissions newer than the previous run to this directory instead, as
+        /// a new segment of JSON lines listed in its `manifest.json`.
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with_all = &["output", "submissions-jsonl"]
+        )]
+        submission_segments: Option<PathBuf>,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Writes the resources uploaded by `dump` and `problem-models.json` to a directory.
+    Snapshot {
+        /// The directory, whose files are replaced atomically.
+        #[structopt(long, parse(from_os_str))]
+        output_dir: PathBuf,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Exports a table for the analyses with Spark, Polars, pandas or R. `--compress` chooses the
+    /// codec of the pages of Parquet, which is Snappy by default, and compresses the other
+    /// formats as a whole.
+    Export {
+        /// `parquet` writes the files partitioned by the month to the output directory,
+        /// `arrow` writes an Arrow IPC stream to the output file, and `sqlite` writes all the
+        /// public tables to the output file.
+        #[structopt(long, default_value = "parquet")]
+        format: ExportFormat,
+        /// `submissions` (default) or `problems`, which can be exported only as `arrow`.
+        #[structopt(long)]
+        table: Option<ExportTable>,
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+        /// Exports only the submissions at or after this time.
+        #[structopt(long)]
+        from_second: Option<i64>,
+        /// Exports only the submissions before this time.
+        #[structopt(long)]
+        to_second: Option<i64>,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// The same as `export --format parquet`, under its former name for the existing deployments.
+    ExportParquet {
+        #[structopt(long, parse(from_os_str))]
+        output_dir: PathBuf,
+        #[structopt(long)]
+        from_second: Option<i64>,
+        #[structopt(long)]
+        to_second: Option<i64>,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Compares the directories written by `snapshot`, e.g. before publishing the new one.
+    DiffSnapshots {
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        #[structopt(parse(from_os_str))]
+        new: PathBuf,
+        /// Reports the problems whose difficulties changed by this or more.
+        #[structopt(long, default_value = "100")]
+        difficulty_threshold: f64,
+        /// Fails if some problems or contests are removed.
+        #[structopt(long)]
+        deny_removals: bool,
+    },
+    /// Restores the tables from the backup written by `dump --output`.
+    Restore {
+        /// The backup, e.g. `backup.tar.zst`.
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+        /// Comma-separated tables to restore. All the tables are restored by default.
+        #[structopt(long, use_delimiter = true)]
+        tables: Vec<String>,
+    },
+    /// Adds the rows of the dump files to the tables, skipping the existing rows. Each file is
+    /// named after its table, e.g. `submissions.csv.gz` or `problems.jsonl`, which is
+    /// `contests`, `problems` or `submissions`, and may be compressed by gzip.
+    Import {
+        #[structopt(parse(from_os_str), required = true)]
+        inputs: Vec<PathBuf>,
+    },
+    /// Creates the tables. All the existing data will be lost.
+    Migrate {
+        /// The database definition to execute.
+        #[structopt(long, default_value = DEFAULT_SCHEMA_PATH, parse(from_os_str))]
+        schema: PathBuf,
+        /// Confirms that the existing tables are dropped.
+        #[structopt(long)]
+        reset: bool,
+    },
+    /// Compares the submissions of the contests with AtCoder, and reports the contests which
+    /// should be re-crawled.
+    Verify {
+        /// The number of the finished contests chosen at random.
+        #[structopt(long, default_value = "20")]
+        sample: usize,
+        /// Queues the drifted contests in `crawl_requests` instead of failing.
+        #[structopt(long)]
+        enqueue: bool,
+        /// The contests to verify instead of the sampled ones.
+        contest_ids: Vec<String>,
+    },
+    /// Inserts a small fixture of contests, problems and submissions into an empty database
+    /// for the local development.
+    Seed {
+        /// The fixture is the same for the same seed.
+        #[structopt(long, default_value = "1")]
+        seed: u64,
+        /// The number of the submissions.
+        #[structopt(long, default_value = "5000")]
+        submissions: usize,
+    },
+    /// Inserts the contests, the problems, the difficulties and the recent submissions from the
+    /// public API of AtCoder Problems into an empty database.
+    Bootstrap {
+        /// The submissions of the last days are inserted.
+        #[structopt(long, default_value = "7")]
+        days: i64,
+        /// The interval between the requests to the API.
+        #[structopt(long, default_value = "1000")]
+        interval_ms: u64,
+        /// Inserts all the submissions from the segments written by `dump --submission-segments`
+        /// under this URL instead. The segments are verified by their checksums, and the
+        /// interrupted downloads are resumed by the retries and the next runs.
+        #[structopt(long, conflicts_with = "days")]
+        segments_url: Option<Url>,
+        /// Where the segments are downloaded and kept.
+        #[structopt(long, default_value = "bootstrap-segments", parse(from_os_str))]
+        download_dir: PathBuf,
+    },
+    /// Prints the completion script of the shell.
+    Completions {
+        #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+        shell: Shell,
+    },
+    /// Writes the man page of all the subcommands.
+    Mangen {
+        #[structopt(long, default_value = ".", parse(from_os_str))]
+        output_dir: PathBuf,
+    },
+    /// Fails if the latest submission is too old, e.g. to notify that the crawlers have stopped.
+    CheckFreshness {
+        #[structopt(long, default_value = "3600")]
+        max_data_age_second: i64,
+    },
+    /// Attaches the yearly partitions of the submissions of this year and the next year, and
+    /// optionally detaches and archives the old ones. The submissions have to be partitioned.
+    MaintainPartitions {
+        /// Detaches the partitions older than this many years before this year.
+        #[structopt(long)]
+        archive_after_years: Option<i32>,
+        /// Writes the detached partitions to this directory and drops them. They are compressed
+        /// by zstd by default.
+        #[structopt(long, parse(from_os_str), requires = "archive-after-years")]
+        archive_dir: Option<PathBuf>,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Archives the non-AC submissions older than `[retention] years` to files compressed by
+    /// zstd by default, and optionally deletes them from the database.
+    ArchiveSubmissions {
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Trains the zstd dictionary of the source codes or the problem statements on the samples
+    /// of the table, which the texts saved later are compressed with.
+    TrainDictionary {
+        #[structopt(possible_values = &["submission_source_codes", "problem_statements"])]
+        table: TextTable,
+        /// The number of the random texts to train the dictionary on.
+        #[structopt(long, default_value = "1000")]
+        samples: i64,
+        /// Also recompresses the existing texts with the new dictionary.
+        #[structopt(long)]
+        recompress: bool,
+    },
+    /// Writes a JSON report of the row counts and the id ranges of the tables, the duplicate ids of
+    /// the submissions across the partitions, and the rows referring to unknown problems, contests
+    /// or submissions.
+    IntegrityCheck {
+        /// Writes the report to this file instead of stdout.
+        #[structopt(long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        /// Fails if there are duplicate ids or orphan rows.
+        #[structopt(long)]
+        fail_on_issues: bool,
+    },
+    /// Deletes or anonymizes all the rows of the user for a takedown request, and prevents the
+    /// crawlers from inserting them again.
+    ForgetUser {
+        user_id: String,
+        /// Renames the user of the submissions to a pseudonym instead of deleting them, so that
+        /// the statistics of the problems do not change.
+        #[structopt(long)]
+        anonymize: bool,
+        /// Recorded in `user_deletions`, e.g. the id of the request.
+        #[structopt(long)]
+        reason: Option<String>,
+    },
+    /// Moves the submissions and the rows of a user from the old handle to the new one after the
+    /// user is renamed on AtCoder. The rows of the new handle win on the conflicts.
+    MergeUser {
+        old_user_id: String,
+        new_user_id: String,
+    },
+    /// Sends the daily report of the crawled data and the jobs by email, or writes the training
+    /// report of a user with `--user`.
+    Report {
+        /// The directory of the files written by `--status-file` of the jobs.
+        #[structopt(long, parse(from_os_str))]
+        status_dir: Option<PathBuf>,
+        /// Writes the report of this user from the aggregation tables instead.
+        #[structopt(long, conflicts_with = "status-dir")]
+        user: Option<String>,
+        /// The first day in JST of the user's report, e.g. `2021-05-01`, 30 days ago by default.
+        #[structopt(long, requires = "user")]
+        since: Option<NaiveDate>,
+        /// `markdown` or `html`.
+        #[structopt(long, default_value = "markdown")]
+        format: ReportFormat,
+        /// Writes the user's report to this file instead of the standard output.
+        #[structopt(long, parse(from_os_str), requires = "user")]
+        output: Option<PathBuf>,
+    },
+    /// Writes the problems for a user to revisit: the attempted but unsolved ones and the ones
+    /// solved long ago, with their difficulties and links.
+    ExportReview {
+        user_id: String,
+        #[structopt(parse(from_os_str))]
+        output: PathBuf,
+        /// `csv`, or `anki` for a tab-separated file which Anki imports as notes.
+        #[structopt(long, default_value = "csv")]
+        format: ReviewFormat,
+        /// Includes the solved problems whose last accepted submission is at least this many
+        /// days old.
+        #[structopt(long, default_value = "365")]
+        solved_days_ago: i64,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Exports a problem list in the JSON interchange format, or as CSV if the output ends with
+    /// `.csv`.
+    ExportProblemList {
+        list_id: String,
+        /// Prints the list as JSON if not given.
+        #[structopt(long, parse(from_os_str))]
+        output: Option<PathBuf>,
+        #[structopt(flatten)]
+        compress: CompressOptions,
+    },
+    /// Creates a problem list of the internal user from a file exported by
+    /// `export-problem-list`, a list of the API of AtCoder Problems, or CSV with the columns
+    /// `problem_id`, `order` and `note`.
+    ImportProblemList {
+        internal_user_id: String,
+        #[structopt(parse(from_os_str))]
+        input: PathBuf,
+        /// The name of the new list instead of the one in the file.
+        #[structopt(long)]
+        name: Option<String>,
+    },
+    /// Prints the effective config, and checks the settings and the connection to the database.
+    CheckConfig,
+    /// Runs the jobs at the times scheduled in the config file.
+    Daemon,
+}
+
+/// The compression of the files written by the export and the dump commands, whose default
+/// depends on the command.
+#[derive(StructOpt, Debug, Clone)]
+struct CompressOptions {
+    /// `none`, `gzip` or `zstd`.
+    #[structopt(long, possible_values = &["none", "gzip", "zstd"])]
+    compress: Option<CompressionFormat>,
+    /// 0 to 9 for gzip and 1 to 22 for zstd, which are 6 and 3 by default.
+    #[structopt(long)]
+    compress_level: Option<i32>,
+}
+
+impl CompressOptions {
+    /// The compression given by the options, or `default` if neither of them is given.
+    /// The level alone changes the level of the default format.
+    fn or(&self, default: Compression) -> Result<Compression> {
+        match (self.compress, self.compress_level) {
+            (None, None) => Ok(default),
+            (format, level) => Compression::new(format.unwrap_or(default.format), level),
+        }
+    }
+
+    /// The compression given by the options, or `None` for the default of the format.
+    fn given(&self) -> Result<Option<Compression>> {
+        match (self.compress, self.compress_level) {
+            (None, None) => Ok(None),
+            _ => self.or(Compression::NONE).map(Some),
+        }
+    }
+}
+
+impl Command {
+    /// The name of the batch job in the metrics. The servers do not push the metrics.
+    fn job_name(&self) -> Option<&'static str> {
+        match self {
+            Command::Crawl(command) => Some(command.job_name()),
+            Command::Aggregate { delta: true } => Some("aggregate_delta"),
+            Command::Aggregate { delta: false } => Some("aggregate"),
+            Command::Dump {
+                submissions_jsonl: Some(_),
+                ..
+            } => Some("dump_submissions"),
+            Command::Dump {
+                submission_segments: Some(_),
+                ..
+            } => Some("dump_submission_segments"),
+            Command::Dump {
+                output: Some(_), ..
+            } => Some("backup"),
+            Command::Dump { .. } => Some("dump"),
+            Command::Snapshot { .. } => Some("snapshot"),
+            // The Parquet export keeps the job name from before the other formats.
+            Command::Export {
+                format: ExportFormat::Parquet,
+                ..
+            }
+            | Command::ExportParquet { .. } => Some("export_parquet"),
+            Command::Export { .. } => Some("export"),
+            Command::Restore { .. } => Some("restore"),
+            Command::Import { .. } => Some("import"),
+            Command::Migrate { .. } => Some("migrate"),
+            Command::Verify { .. } => Some("verify"),
+            Command::CheckFreshness { .. } => Some("check_freshness"),
+            Command::Report { user: Some(_), .. } => Some("user_report"),
+            Command::ExportReview { .. } => Some("export_review"),
+            Command::ExportProblemList { .. } => Some("export_problem_list"),
+            Command::ImportProblemList { .. } => Some("import_problem_list"),
+            Command::Report { .. } => Some("report"),
+            Command::MaintainPartitions { .. } => Some("maintain_partitions"),
+            Command::ArchiveSubmissions { .. } => Some("archive_submissions"),
+            Command::TrainDictionary { .. } => Some("train_dictionary"),
+            Command::IntegrityCheck { .. } => Some("integrity_check"),
+            Command::ForgetUser { .. } => Some("forget_user"),
+            Command::MergeUser { .. } => Some("merge_user"),
+            Command::Serve { .. }
+            | Command::ServeGrpc { .. }
+            | Command::DeliverWebhooks
+            | Command::SinkBigquery
+            | Command::SinkClickhouse { .. }
+            | Command::PublishEvents
+            | Command::DiffSnapshots { .. }
+            | Command::Seed { .. }
+            | Command::Bootstrap { .. }
+            | Command::CheckConfig
+            | Command::Completions { .. }
+            | Command::Mangen { .. }
+            | Command::Daemon => None,
+        }
+    }
+}
+
+impl Cli {
+    /// The level of the logs, which `--progress` and `--quiet` lower not to mess up the output.
+    pub fn log_level(&self) -> LevelFilter {
+        if self.quiet {
+            self.log_level.min(LevelFilter::Error)
+        } else if self.progress {
+            self.log_level.min(LevelFilter::Warn)
+        } else {
+            self.log_level
+        }
+    }
+
+    pub fn run(self) -> ExitStatus {
+        // The documents are generated without the config, and nothing else is printed.
+        match &self.command {
+            Command::Completions { shell } => {
+                docs::completions(*shell);
+                return ExitStatus::Success;
+            }
+            Command::Mangen { output_dir } => {
+                return match docs::mangen(output_dir) {
+                    Ok(()) => ExitStatus::Success,
+                    Err(e) => {
+                        log::error!("{:?}", e);
+                        ExitStatus::Failure
+                    }
+                };
+            }
+            _ => {}
+        }
+
+        let started_epoch_second = Utc::now().timestamp();
+        let job = self.command.job_name();
+        let run_id = generate_run_id();
+        let status_file = self.status_file;
+        let write_status = |status: ExitStatus, error: Option<&anyhow::Error>| {
+            if let Some(path) = &status_file {
+                let run_status = RunStatus {
+                    job,
+                    run_id: run_id.clone(),
+                    status,
+                    exit_code: status.code(),
+                    error: error.map(|e| format!("{:#}", e)),
+                    started_epoch_second,
+                    finished_epoch_second: Utc::now().timestamp(),
+                    rows_written: metrics::rows_written(),
+                    failures: metrics::failures(),
+                };
+                if let Err(e) = run_status.write(path) {
+                    log::error!("Failed to write {}: {:?}", path.display(), e);
+                }
+            }
+        };
+
+        let mut config = match Config::load(self.config.as_deref()) {
+            Ok(config) => config,
+            Err(e) => {
+                log::error!("{:?}", e);
+                write_status(ExitStatus::UsageError, Some(&e));
+                return ExitStatus::UsageError;
+            }
+        };
+        if let Some(database_url) = self.database_url {
+            config.database.url = Some(database_url);
+        }
+        let _error_report =
+            config.error_report.sentry_dsn.as_deref().map(|dsn| {
+                error_report::init(dsn, config.error_report.sentry_environment.as_deref())
+            });
+
+        if self.quiet {
+            progress::set_reporter(Box::new(QuietReporter));
+        } else if self.progress {
+            progress::set_reporter(Box::new(BarReporter::new()));
+        }
+
+        log::info!("Started");
+        let started = Instant::now();
+        let (status, error) = execute(self.command, &Arc::new(config), &run_id);
+        write_status(status, error.as_ref());
+        progress::finish(&format!(
+            "{} finished in {:.1} sec.: {:?}, {}",
+            job.unwrap_or("The command"),
+            started.elapsed().as_secs_f64(),
+            status,
+            metrics::counts()
+        ));
+        status
+    }
+}
+
+/// Runs the command holding the lock of the job, and pushes the metrics and records the run in
+/// `crawler_runs` when it finishes. The failures of the jobs are notified if the webhook is
+/// configured.
+fn execute(
+    command: Command,
+    config: &Arc<Config>,
+    run_id: &str,
+) -> (ExitStatus, Option<anyhow::Error>) {
+    let job = command.job_name();
+    if let Some(job) = job {
+        error_report::set_tag("job", job);
+        error_report::set_tag("run_id", run_id);
+        log::info!("Running {} as {}", job, run_id);
+        if !config.features.is_job_enabled(job) {
+            log::info!("Skipped because {} is disabled by the config", job);
+            return (ExitStatus::Skipped, None);
+        }
+    }
+    let lock = match job.filter(|_| config.job_lock.enabled) {
+        Some(job) => match async_std::task::block_on(config.job_lock.lock(&config.database, job)) {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                log::info!("Skipped because another process is running {}", job);
+                return (ExitStatus::Skipped, None);
+            }
+            Err(e) => {
+                log::error!("Failed to lock {}: {:?}", job, e);
+                return (ExitStatus::of(&Err(e), 0), None);
+            }
+        },
+        None => None,
+    };
+
+    let started = Instant::now();
+    let started_epoch_second = Utc::now().timestamp();
+    let result = async_std::task::block_on(run(command, config));
+    if let Some(lock) = lock {
+        if let Err(e) = async_std::task::block_on(lock.release()) {
+            log::error!("Failed to unlock: {:?}", e);
+        }
+    }
+    if let Err(e) = &result {
+        log::error!("{:?}", e);
+        error_report::report(e, &[]);
+        if let (Some(job), Some(notifier)) = (job, config.notify.notifier()) {
+            let environment = config.error_report.sentry_environment.as_deref();
+            let message = format!(
+                "{}{} failed (run {}): {:#}",
+                environment.map_or(String::new(), |e| format!("[{}] ", e)),
+                job,
+                run_id,
+                e
+            );
+            if let Err(e) = async_std::task::block_on(notifier.send(&message)) {
+                log::error!("Failed to notify the failure of {}: {:?}", job, e);
+            }
+        }
+    }
+
+    if let Some(job) = job {
+        let job_metrics = JobMetrics {
+            job: job.to_owned(),
+            duration: started.elapsed(),
+            rows_written: metrics::rows_written(),
+            failed: result.is_err(),
+            finished_epoch_second: Utc::now().timestamp(),
+        };
+        let pushed = async_std::task::block_on(config.metrics.push(&job_metrics));
+        if let Err(e) = pushed {
+            log::error!("Failed to push the metrics of {}: {:?}", job, e);
+        }
+    }
+
+    let status = ExitStatus::of(&result, metrics::failures());
+    if let Some(job) = job {
+        let run = CrawlerRun {
+            run_id: run_id.to_owned(),
+            job: job.to_owned(),
+            contest_ids: metrics::contests_processed(),
+            status: status.as_str().to_owned(),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            started_epoch_second,
+            finished_epoch_second: Utc::now().timestamp(),
+            rows_written: metrics::rows_written() as i64,
+            failures: metrics::failures() as i64,
+        };
+        if let Err(e) = async_std::task::block_on(record_run(config, &run)) {
+            log::error!("Failed to record the run of {}: {:?}", job, e);
+        }
+    }
+    (status, result.err())
+}
+
+async fn record_run(config: &Config, run: &CrawlerRun) -> Result<()> {
+    let pg_pool = config.database.connect().await?;
+    pg_pool.insert_crawler_run(run).await
+}
+
+async fn run(command: Command, config: &Arc<Config>) -> Result<()> {
+    match command {
+        Command::Crawl(command) => crawl::run(command, config).await,
+        Command::Aggregate { delta } => {
+            let pg_pool = config.database.connect().await?;
+            if delta {
+                aggregate::delta_update(&pg_pool).await?;
+            } else {
+                aggregate::batch_update(&pg_pool, config.database.url()?).await?;
+            }
+            // Redis is only a cache of the aggregated tables, which are already written.
+            if let Some(writer) = config.materialize.writer()? {
+                if let Err(e) = writer.write(&pg_pool).await {
+                    log::error!("Failed to write to Redis: {:?}", e);
+                    error_report::report(&e, &[]);
+                    metrics::add_failure();
+                }
+            }
+            Ok(())
+        }
+        Command::Serve { port } => {
+            config.server.export_env();
+            let port = port.unwrap_or(config.server.port);
+            serve::serve(config, port).await
+        }
+        Command::ServeGrpc { port } => {
+            let port = port.unwrap_or(config.server.grpc_port);
+            serve::serve_grpc(config, port)
+        }
+        Command::DeliverWebhooks => loop {
+            let result = match config.database.connect().await {
+                Ok(pg_pool) => deliver_submissions(pg_pool).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log::error!("{:?}", e);
+                error_report::report(&e, &[]);
+                thread::sleep(time::Duration::from_millis(1000));
+            }
+        },
+        Command::SinkBigquery => {
+            let mut sink = config.bigquery.sink()?;
+            let batch_size = config.bigquery.batch_size.max(1);
+            let flush_interval = time::Duration::from_secs(config.bigquery.flush_interval_second);
+            loop {
+                let result = match config.database.connect().await {
+                    Ok(pg_pool) => {
+                        stream_submissions(pg_pool, &mut sink, batch_size, flush_interval).await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    log::error!("{:?}", e);
+                    error_report::report(&e, &[]);
+                    thread::sleep(time::Duration::from_millis(1000));
+                }
+            }
+        }
+        Command::SinkClickhouse {
+            mut backfill_from_second,
+        } => {
+            let sink = config.clickhouse.sink()?;
+            let batch_size = config.clickhouse.batch_size.max(1);
+            let flush_interval = time::Duration::from_secs(config.clickhouse.flush_interval_second);
+            loop {
+                let result = match config.database.connect().await {
+                    Ok(pg_pool) => {
+                        replicate_submissions(
+                            pg_pool,
+                            &sink,
+                            &mut backfill_from_second,
+                            batch_size,
+                            flush_interval,
+                        )
+                        .await
+                    }
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    log::error!("{:?}", e);
+                    error_report::report(&e, &[]);
+                    thread::sleep(time::Duration::from_millis(1000));
+                }
+            }
+        }
+        Command::PublishEvents => {
+            let events = &config.events;
+            let polling_interval =
+                time::Duration::from_secs(events.contest_polling_interval_second);
+            let mut pending = VecDeque::new();
+            loop {
+                let result = match (config.database.connect().await, events.publisher()) {
+                    (Ok(pg_pool), Ok(publisher)) => {
+                        publish_events(
+                            pg_pool,
+                            &publisher,
+                            &events.topic_prefix,
+                            polling_interval,
+                            &mut pending,
+                        )
+                        .await
+                    }
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                };
+                if let Err(e) = result {
+                    log::error!("{:?}", e);
+                    error_report::report(&e, &[]);
+                    thread::sleep(time::Duration::from_millis(1000));
+                }
+            }
+        }
+        Command::Dump {
+            submissions_jsonl: Some(output),
+            compress,
+            ..
+        } => {
+            let by_extension = Compression::of(CompressionFormat::from_extension(&output));
+            let compression = compress.or(by_extension)?;
+            let pg_pool = config.database.connect().await?;
+            dump::dump_submissions(&pg_pool, &output, compression).await
+        }
+        Command::Dump {
+            submission_segments: Some(directory),
+            compress,
+            ..
+        } => {
+            let compression = compress.or(Compression::NONE)?;
+            let pg_pool = config.database.connect().await?;
+            dump::dump_submission_segments(&pg_pool, &directory, compression).await
+        }
+        Command::Dump {
+            output: Some(output),
+            compress,
+            ..
+        } => {
+            let compression = compress.or(Compression::of(CompressionFormat::Zstd))?;
+            backup::backup(config, &output, compression)
+        }
+        Command::Snapshot {
+            output_dir,
+            compress,
+        } => {
+            let compression = compress.or(Compression::NONE)?;
+            let pg_pool = config.database.connect().await?;
+            snapshot::snapshot(&pg_pool, &output_dir, compression).await
+        }
+        Command::Export {
+            format,
+            table,
+            output,
+            from_second,
+            to_second,
+            compress,
+        } => {
+            let compression = compress.given()?;
+            export::export(
+                config,
+                format,
+                table,
+                &output,
+                from_second,
+                to_second,
+                compression,
+            )
+            .await
+        }
+        Command::ExportParquet {
+            output_dir,
+            from_second,
+            to_second,
+            compress,
+        } => {
+            let compression = compress.given()?;
+            export::export(
+                config,
+                ExportFormat::Parquet,
+                None,
+                &output_dir,
+                from_second,
+                to_second,
+                compression,
+            )
+            .await
+        }
+        Command::DiffSnapshots {
+            old,
+            new,
+            difficulty_threshold,
+            deny_removals,
+        } => diff::diff_snapshots(&old, &new, difficulty_threshold, deny_removals),
+        Command::Restore { input, tables } => backup::restore(config, &input, &tables).await,
+        Command::Import { inputs } => import::import(config, &inputs).await,
+        Command::Dump { compress, .. } => {
+            let compression = compress.or(crate::s3::DEFAULT_COMPRESSION)?;
+            let client = config.storage.client(compression)?;
+            let pg_pool = config.database.connect().await?;
+            dump::dump(&pg_pool, &client).await
+        }
+        Command::Migrate { schema, reset } => migrate::migrate(config, &schema, reset).await,
+        Command::Verify {
+            sample,
+            enqueue,
+            contest_ids,
+        } => verify::verify(config, contest_ids, sample, enqueue).await,
+        Command::Seed { seed, submissions } => {
+            let pg_pool = config.database.connect().await?;
+            seed::seed(&pg_pool, seed, submissions).await
+        }
+        Command::Bootstrap {
+            days,
+            interval_ms,
+            segments_url,
+            download_dir,
+        } => {
+            let pg_pool = config.database.connect().await?;
+            let interval = time::Duration::from_millis(interval_ms);
+            let segments = segments_url.map(|url| SegmentSource { url, download_dir });
+            bootstrap::bootstrap(&pg_pool, days, interval, segments).await
+        }
+        Command::CheckConfig => check_config::check_config(config).await,
+        Command::MaintainPartitions {
+            archive_after_years,
+            archive_dir,
+            compress,
+        } => {
+            let compression = compress.or(Compression::of(CompressionFormat::Zstd))?;
+            partition::maintain_partitions(
+                config,
+                archive_after_years,
+                archive_dir.as_deref(),
+                compression,
+            )
+            .await
+        }
+        Command::ArchiveSubmissions { compress } => {
+            let compression = compress.or(Compression::of(CompressionFormat::Zstd))?;
+            retention::archive_submissions(config, compression)
+        }
+        Command::TrainDictionary {
+            table,
+            samples,
+            recompress,
+        } => {
+            let pg_pool = config.database.connect().await?;
+            compress::train_dictionary(&pg_pool, table, samples, recompress).await
+        }
+        Command::IntegrityCheck {
+            output,
+            fail_on_issues,
+        } => {
+            let pg_pool = config.database.connect().await?;
+            integrity::integrity_check(&pg_pool, output.as_deref(), fail_on_issues).await
+        }
+        Command::ForgetUser {
+            user_id,
+            anonymize,
+            reason,
+        } => {
+            let mode = if anonymize {
+                ForgetMode::Anonymize
+            } else {
+                ForgetMode::Delete
+            };
+            let pg_pool = config.database.connect().await?;
+            let deletion = pg_pool
+                .forget_user(&UserId::from(user_id.as_str()), mode, reason.as_deref())
+                .await?;
+            metrics::add_rows_written(deletion.rows_affected as usize);
+            if let Some(writer) = config.materialize.writer()? {
+                writer.forget_user(&user_id).await?;
+            }
+            log::info!(
+                "Forgot {} by {} ({} rows), recorded as {} in user_deletions",
+                user_id,
+                mode.as_str(),
+                deletion.rows_affected,
+                deletion.id
+            );
+            Ok(())
+        }
+        Command::MergeUser {
+            old_user_id,
+            new_user_id,
+        } => {
+            let pg_pool = config.database.connect().await?;
+            let merge = pg_pool
+                .merge_user(
+                    &UserId::from(old_user_id.as_str()),
+                    &UserId::from(new_user_id.as_str()),
+                )
+                .await?;
+            log::info!(
+                "Moved {} submissions from {} to {}",
+                merge.submissions,
+                old_user_id,
+                new_user_id
+            );
+            for table in merge.tables.iter() {
+                if table.moved > 0 || table.discarded > 0 {
+                    log::info!(
+                        "{}: moved {} rows, discarded {} rows of {}",
+                        table.table,
+                        table.moved,
+                        table.discarded,
+                        old_user_id
+                    );
+                }
+            }
+            log::info!("Moved {} internal rows", merge.internal_rows);
+            metrics::add_rows_written(merge.rows_affected() as usize);
+            Ok(())
+        }
+        Command::Report {
+            user: Some(user_id),
+            since,
+            format,
+            output,
+            ..
+        } => {
+            let pg_pool = config.database.connect().await?;
+            user_report::user_report(
+                &pg_pool,
+                &UserId::from(user_id),
+                since,
+                format,
+                output.as_deref(),
+            )
+            .await
+        }
+        Command::ExportReview {
+            user_id,
+            output,
+            format,
+            solved_days_ago,
+            compress,
+        } => {
+            let compression = compress.or(Compression::NONE)?;
+            let pg_pool = config.database.connect().await?;
+            review::export_review(
+                &pg_pool,
+                &UserId::from(user_id),
+                &output,
+                format,
+                solved_days_ago,
+                compression,
+            )
+            .await
+        }
+        Command::ExportProblemList {
+            list_id,
+            output,
+            compress,
+        } => {
+            let compression = compress.or(Compression::NONE)?;
+            let pg_pool = config.database.connect().await?;
+            problem_list::export_problem_list(&pg_pool, &list_id, output.as_deref(), compression)
+                .await
+        }
+        Command::ImportProblemList {
+            internal_user_id,
+            input,
+            name,
+        } => {
+            let pg_pool = config.database.connect().await?;
+            problem_list::import_problem_list(&pg_pool, &internal_user_id, &input, name).await?;
+            Ok(())
+        }
+        Command::Report { status_dir, .. } => {
+            let pg_pool = config.database.connect().await?;
+            report::report(&pg_pool, &config.report, status_dir.as_deref()).await
+        }
+        Command::CheckFreshness {
+            max_data_age_second,
+        } => {
+            let pg_pool = config.database.connect().await?;
+            freshness::check_freshness(&pg_pool, max_data_age_second).await
+        }
+        Command::Daemon => daemon::run(config),
+        Command::Completions { .. } | Command::Mangen { .. } => {
+            unreachable!("The documents are generated by Cli::run")
+        }
+    }
+}
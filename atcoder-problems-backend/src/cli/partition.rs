@@ -0,0 +1,87 @@
+use crate::cli::config::Config;
+use crate::compression::Compression;
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use sql_client::backup::Snapshot;
+use sql_client::partition::{PartitionClient, YearlyPartition};
+use std::fs::{self, File};
+use std::path::Path;
+
+const SUBMISSIONS_TABLE: &str = "submissions";
+
+/// Attaches the partitions of the submissions of this year and the next year if they are missing,
+/// and detaches the partitions older than `archive_after_years`.
+///
+/// The detached partitions are written to `archive_dir` in the text format of `COPY` compressed
+/// by `compression`, and dropped. They are left as standalone tables if `archive_dir` is not given.
+pub(crate) async fn maintain_partitions(
+    config: &Config,
+    archive_after_years: Option<i32>,
+    archive_dir: Option<&Path>,
+    compression: Compression,
+) -> Result<()> {
+    if let Some(years) = archive_after_years.filter(|&years| years < 0) {
+        bail!("--archive-after-years has to be 0 or more: {}", years);
+    }
+    let pg_pool = config.database.connect().await?;
+    if !pg_pool.is_partitioned(SUBMISSIONS_TABLE).await? {
+        bail!("{} is not partitioned", SUBMISSIONS_TABLE);
+    }
+
+    let attached = pg_pool.load_yearly_partitions(SUBMISSIONS_TABLE).await?;
+    let current = YearlyPartition::of_epoch_second(Utc::now().timestamp());
+    for &partition in [current, current.next()].iter() {
+        if !attached.contains(&partition) {
+            log::info!("Attaching {}", partition.name(SUBMISSIONS_TABLE));
+            pg_pool
+                .attach_yearly_partition(SUBMISSIONS_TABLE, partition)
+                .await?;
+        }
+    }
+
+    let archive_after_years = match archive_after_years {
+        Some(years) => years,
+        None => return Ok(()),
+    };
+    let expired = attached
+        .into_iter()
+        .filter(|p| p.year < current.year - archive_after_years);
+    for partition in expired {
+        let name = partition.name(SUBMISSIONS_TABLE);
+        log::info!("Detaching {}", name);
+        pg_pool
+            .detach_yearly_partition(SUBMISSIONS_TABLE, partition)
+            .await?;
+        if let Some(archive_dir) = archive_dir {
+            let rows = archive(config, &name, archive_dir, compression)?;
+            log::info!("Archived {} rows of {}", rows, name);
+            pg_pool
+                .drop_detached_partition(SUBMISSIONS_TABLE, partition)
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the table to `{archive_dir}/{table}.tsv` followed by the extension of `compression`,
+/// e.g. `.zst`, which can be loaded by `COPY {table} FROM STDIN` after being decompressed.
+fn archive(
+    config: &Config,
+    table: &str,
+    archive_dir: &Path,
+    compression: Compression,
+) -> Result<u64> {
+    fs::create_dir_all(archive_dir)?;
+    let path = compression.with_extension(&archive_dir.join(format!("{}.tsv", table)));
+    let file =
+        File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = compression.writer(file)?;
+    let mut snapshot = Snapshot::begin(config.database.url()?)?;
+    let rows = snapshot.copy_table(table, &mut writer)?;
+    // The partition is dropped after this, so the archive has to be on the disk.
+    writer
+        .finish()?
+        .sync_all()
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(rows)
+}
@@ -0,0 +1,93 @@
+use crate::compression::{open_decompressed, Compression, CompressionFormat};
+use crate::server::problem_list::{list_from_csv, list_to_csv};
+use anyhow::{Context, Result};
+use sql_client::internal::problem_list_manager::{ProblemListDocument, ProblemListManager};
+use sql_client::PgPool;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the file without `.gz` or `.zst`, e.g. `list.csv` for `list.csv.gz`.
+fn uncompressed_name(path: &Path) -> PathBuf {
+    match CompressionFormat::from_extension(path) {
+        CompressionFormat::None => path.to_owned(),
+        _ => path.with_extension(""),
+    }
+}
+
+fn is_csv(path: &Path) -> bool {
+    uncompressed_name(path)
+        .extension()
+        .map_or(false, |extension| extension == "csv")
+}
+
+/// Writes the list in the interchange format, or its items as CSV if the output ends with `.csv`
+/// or `.csv` followed by the extension of the compression. The output is named with the extension
+/// of the compression. The list is printed as JSON if the output is not given.
+pub(crate) async fn export_problem_list(
+    pg_pool: &PgPool,
+    list_id: &str,
+    output: Option<&Path>,
+    compression: Compression,
+) -> Result<()> {
+    let list = pg_pool.get_single_list(list_id).await?;
+    let document = ProblemListDocument::from_list(list);
+    match output {
+        Some(output) => {
+            let output = &compression.with_extension(output);
+            let exported = if is_csv(output) {
+                list_to_csv(&document)?
+            } else {
+                serde_json::to_string_pretty(&document)?
+            };
+            fs::write(output, compression.compress(exported.as_bytes())?)
+                .with_context(|| format!("Failed to write {}", output.display()))?;
+            log::info!(
+                "Exported {} problems of {} to {}",
+                document.items.len(),
+                list_id,
+                output.display()
+            );
+        }
+        None => {
+            let mut exported = serde_json::to_string_pretty(&document)?;
+            exported.push('\n');
+            io::stdout().write_all(&compression.compress(exported.as_bytes())?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Creates a new list of the user from a file in the interchange format, or from CSV if the input
+/// ends with `.csv`. The list is named `name`, or the file name of CSV if not given.
+/// The file may be compressed by gzip or zstd, which is detected from its content.
+pub(crate) async fn import_problem_list(
+    pg_pool: &PgPool,
+    internal_user_id: &str,
+    input: &Path,
+    name: Option<String>,
+) -> Result<String> {
+    let mut content = String::new();
+    open_decompressed(input)
+        .and_then(|mut reader| reader.read_to_string(&mut content))
+        .with_context(|| format!("Failed to read {}", input.display()))?;
+    let mut document = if is_csv(input) {
+        let name = uncompressed_name(input);
+        let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+        list_from_csv(&stem, &content)?
+    } else {
+        serde_json::from_str::<ProblemListDocument>(&content)
+            .with_context(|| format!("Failed to parse {}", input.display()))?
+    };
+    if let Some(name) = name {
+        document.name = name;
+    }
+    let list_id = pg_pool.import_list(internal_user_id, &document).await?;
+    log::info!(
+        "Imported {} problems into {} ({})",
+        document.ordered_items().len(),
+        document.name,
+        list_id
+    );
+    Ok(list_id)
+}
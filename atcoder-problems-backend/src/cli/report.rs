@@ -0,0 +1,265 @@
+use anyhow::{bail, Context, Result};
+use chrono::{TimeZone, Utc};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::Deserialize;
+use sql_client::activity::ActivityClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::PgPool;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::cli::config::ReportConfig;
+
+const DAY_SECOND: i64 = 24 * 3600;
+const SLOWEST_JOB_COUNT: usize = 5;
+
+/// The fields of the files written by `--status-file` which the report shows.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+struct JobStatus {
+    job: Option<String>,
+    status: String,
+    started_epoch_second: i64,
+    finished_epoch_second: i64,
+    rows_written: u64,
+    failures: u64,
+}
+
+impl JobStatus {
+    fn duration_second(&self) -> i64 {
+        self.finished_epoch_second - self.started_epoch_second
+    }
+}
+
+struct DailyReport {
+    until_epoch_second: i64,
+    submissions: i64,
+    parse_failures: i64,
+    new_contests: Vec<String>,
+    /// The latest runs of the jobs which finished in the period.
+    jobs: Vec<JobStatus>,
+}
+
+impl fmt::Display for DailyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let until = Utc.timestamp(self.until_epoch_second, 0);
+        writeln!(f, "AtCoder Problems report for the day until {}", until)?;
+        writeln!(f)?;
+        writeln!(f, "Submissions crawled: {}", self.submissions)?;
+        writeln!(f, "Pages failed to parse: {}", self.parse_failures)?;
+        writeln!(
+            f,
+            "New contests: {}",
+            if self.new_contests.is_empty() {
+                "none".to_owned()
+            } else {
+                self.new_contests.join(", ")
+            }
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "Jobs with failures:")?;
+        let failed = self
+            .jobs
+            .iter()
+            .filter(|job| job.failures > 0 || (job.status != "success" && job.status != "skipped"))
+            .collect::<Vec<_>>();
+        if failed.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        for job in failed {
+            writeln!(
+                f,
+                "  {}: {}, {} failures",
+                job.job.as_deref().unwrap_or("-"),
+                job.status,
+                job.failures
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Slowest jobs:")?;
+        let mut jobs = self.jobs.iter().collect::<Vec<_>>();
+        jobs.sort_by_key(|job| -job.duration_second());
+        for job in jobs.into_iter().take(SLOWEST_JOB_COUNT) {
+            writeln!(
+                f,
+                "  {}: {} sec., {} rows written",
+                job.job.as_deref().unwrap_or("-"),
+                job.duration_second(),
+                job.rows_written
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the status files in the directory, skipping the ones which cannot be parsed,
+/// e.g. the temporary files being written.
+fn load_job_statuses(status_dir: &Path, since_epoch_second: i64) -> Result<Vec<JobStatus>> {
+    let mut statuses = Vec::new();
+    let entries = fs::read_dir(status_dir)
+        .with_context(|| format!("Failed to read {}", status_dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+        match serde_json::from_slice::<JobStatus>(&fs::read(&path)?) {
+            Ok(status) if status.finished_epoch_second >= since_epoch_second => {
+                statuses.push(status)
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Skipped {}: {:?}", path.display(), e),
+        }
+    }
+    statuses.sort_by(|a, b| a.job.cmp(&b.job));
+    Ok(statuses)
+}
+
+/// Sends the digest of the last day by email, so that the operators notice a gradual
+/// degradation without watching the dashboards. The jobs are read from the files written by
+/// `--status-file`, e.g. one file per job in `status_dir`.
+pub(crate) async fn report(
+    pg_pool: &PgPool,
+    config: &ReportConfig,
+    status_dir: Option<&Path>,
+) -> Result<()> {
+    let until_epoch_second = Utc::now().timestamp();
+    let since_epoch_second = until_epoch_second - DAY_SECOND;
+    let submissions = pg_pool
+        .count_submissions_crawled_between(since_epoch_second, until_epoch_second)
+        .await?;
+    let parse_failures = pg_pool
+        .count_parse_failures_between(since_epoch_second, until_epoch_second)
+        .await?;
+    let new_contests = pg_pool
+        .load_contests()
+        .await?
+        .into_iter()
+        .filter(|c| {
+            since_epoch_second <= c.start_epoch_second && c.start_epoch_second < until_epoch_second
+        })
+        .map(|c| c.id.into_string())
+        .collect();
+    let jobs = match status_dir {
+        Some(status_dir) => load_job_statuses(status_dir, since_epoch_second)?,
+        None => Vec::new(),
+    };
+    let report = DailyReport {
+        until_epoch_second,
+        submissions,
+        parse_failures,
+        new_contests,
+        jobs,
+    };
+    send(config, &report.to_string())
+}
+
+fn send(config: &ReportConfig, body: &str) -> Result<()> {
+    let (host, from) = match (&config.smtp_host, &config.from) {
+        (Some(host), Some(from)) if !config.to.is_empty() => (host, from),
+        _ => bail!("Specify smtp_host, from and to in [report] of the config file"),
+    };
+    let mut builder = Message::builder()
+        .from(from.parse()?)
+        .subject("AtCoder Problems daily report");
+    for to in config.to.iter() {
+        builder = builder.to(to.parse()?);
+    }
+    let message = builder.body(body.to_owned())?;
+
+    let mut transport = SmtpTransport::starttls_relay(host)?.port(config.smtp_port);
+    if let (Some(user), Some(password)) = (&config.smtp_user, &config.smtp_password) {
+        transport = transport.credentials(Credentials::new(user.clone(), password.clone()));
+    }
+    transport.build().send(&message)?;
+    log::info!("Sent the report to {}", config.to.join(", "));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(job: &str, status: &str, duration_second: i64, failures: u64) -> JobStatus {
+        JobStatus {
+            job: Some(job.to_owned()),
+            status: status.to_owned(),
+            started_epoch_second: 1_600_000_000,
+            finished_epoch_second: 1_600_000_000 + duration_second,
+            rows_written: 10,
+            failures,
+        }
+    }
+
+    #[test]
+    fn test_daily_report() {
+        let report = DailyReport {
+            until_epoch_second: 1_600_000_000,
+            submissions: 1234,
+            parse_failures: 3,
+            new_contests: vec!["abc180".to_owned()],
+            jobs: vec![
+                status("aggregate", "success", 60, 0),
+                status("crawl_contests_new", "partial_success", 300, 2),
+                status("crawl_problems", "failure", 5, 0),
+            ],
+        };
+        assert_eq!(
+            report.to_string(),
+            r"AtCoder Problems report for the day until 2020-09-13 12:26:40 UTC
+
+Submissions crawled: 1234
+Pages failed to parse: 3
+New contests: abc180
+
+Jobs with failures:
+  crawl_contests_new: partial_success, 2 failures
+  crawl_problems: failure, 0 failures
+
+Slowest jobs:
+  crawl_contests_new: 300 sec., 10 rows written
+  aggregate: 60 sec., 10 rows written
+  crawl_problems: 5 sec., 10 rows written
+"
+        );
+    }
+
+    #[test]
+    fn test_load_job_statuses() {
+        let dir =
+            std::env::temp_dir().join(format!("atcoder-problems-report-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, job: &str, finished_epoch_second: i64| {
+            let json = serde_json::json!({
+                "job": job,
+                "run_id": "0123456789abcdef",
+                "status": "success",
+                "exit_code": 0,
+                "error": null,
+                "started_epoch_second": finished_epoch_second - 10,
+                "finished_epoch_second": finished_epoch_second,
+                "rows_written": 1,
+                "failures": 0,
+            });
+            fs::write(dir.join(name), json.to_string()).unwrap();
+        };
+        write("b.json", "crawl_problems", 200);
+        write("a.json", "aggregate", 300);
+        write("old.json", "dump", 50);
+        fs::write(dir.join("c.json.tmp"), "{").unwrap();
+
+        let statuses = load_job_statuses(&dir, 100).unwrap();
+        let jobs = statuses
+            .iter()
+            .map(|s| s.job.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(jobs, vec!["aggregate", "crawl_problems"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
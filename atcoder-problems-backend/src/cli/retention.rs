@@ -0,0 +1,150 @@
+use crate::cli::config::Config;
+use crate::compression::{open_decompressed, Compression};
+use crate::metrics;
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use sql_client::backup::SubmissionArchiver;
+use sql_client::partition::YearlyPartition;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Archives the non-AC submissions older than `[retention] years`, and deletes them from the
+/// database if `[retention] delete` is set.
+///
+/// The submissions of each year in JST are written to
+/// `{archive_dir}/submissions_non_ac_y{year}.tsv` in the text format of `COPY`, followed by the
+/// extension of `compression`, e.g. `.zst`. When they are deleted, the submissions archived later,
+/// e.g. the ones crawled late, are appended to the file as another gzip member or zstd frame,
+/// which is decompressed as one stream. Otherwise the file is rewritten with all of them.
+///
+/// The frame is written to `{file}.pending` first, and moved to the file only after the deletion
+/// is committed, so that a failed run neither loses the rows nor archives them twice. It is
+/// written to `{file}.pending.partial` and renamed when it is finished, since an uncompressed
+/// frame can be read even if it is cut off.
+///
+/// The submission counts of the users are computed from the remaining submissions, so they
+/// decrease after the deletion.
+pub(crate) fn archive_submissions(config: &Config, compression: Compression) -> Result<()> {
+    let retention = &config.retention;
+    let years = match retention.years {
+        Some(years) => years,
+        None => {
+            log::info!("RETENTION_YEARS is not set");
+            return Ok(());
+        }
+    };
+    let archive_dir = retention
+        .archive_dir
+        .as_deref()
+        .ok_or_else(|| anyhow!("Specify RETENTION_ARCHIVE_DIR to archive the submissions"))?;
+    fs::create_dir_all(archive_dir)?;
+
+    let current = YearlyPartition::of_epoch_second(Utc::now().timestamp());
+    let cutoff = YearlyPartition {
+        year: current.year - years,
+    };
+    let database_url = config.database.url()?;
+    let oldest = SubmissionArchiver::begin(database_url)?
+        .oldest_non_accepted(cutoff.start_epoch_second())?;
+    let mut year = match oldest {
+        Some(epoch_second) => YearlyPartition::of_epoch_second(epoch_second),
+        None => return Ok(()),
+    };
+
+    while year < cutoff {
+        let rows = archive_year(
+            database_url,
+            year,
+            archive_dir,
+            retention.delete,
+            compression,
+        )?;
+        metrics::add_rows_written(rows as usize);
+        year = year.next();
+    }
+    Ok(())
+}
+
+fn archive_year(
+    database_url: &str,
+    year: YearlyPartition,
+    archive_dir: &Path,
+    delete: bool,
+    compression: Compression,
+) -> Result<u64> {
+    let (start, end) = (year.start_epoch_second(), year.end_epoch_second());
+    let name = format!("{}.tsv", year.name("submissions_non_ac"));
+    let path = compression.with_extension(&archive_dir.join(name));
+    let pending = with_suffix(&path, ".pending");
+    let partial = with_suffix(&pending, ".partial");
+
+    let mut archiver = SubmissionArchiver::begin(database_url)?;
+    if pending.exists() {
+        recover_pending(&mut archiver, &pending, &path)?;
+    }
+
+    let file = File::create(&partial)
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+    let mut writer = compression.writer(file)?;
+    let rows = archiver.copy_non_accepted(start, end, &mut writer)?;
+    writer.finish()?.sync_all()?;
+    fs::rename(&partial, &pending)
+        .with_context(|| format!("Failed to rename {}", partial.display()))?;
+
+    if delete {
+        let deleted = archiver.delete_non_accepted(start, end)?;
+        archiver.commit()?;
+        log::info!("Deleted {} submissions of {}", deleted, year.year);
+        append_pending(&pending, &path)?;
+    } else {
+        fs::rename(&pending, &path)
+            .with_context(|| format!("Failed to rename {}", pending.display()))?;
+    }
+    log::info!("Archived {} submissions to {}", rows, path.display());
+    Ok(rows)
+}
+
+/// Handles the frame left by a failed run. It is appended to the archive if its rows have been
+/// deleted, and is discarded if they are still in the database, since they are copied again.
+fn recover_pending(archiver: &mut SubmissionArchiver, pending: &Path, path: &Path) -> Result<()> {
+    match first_id(pending) {
+        Ok(Some(id)) if !archiver.has_submission(id)? => {
+            log::warn!("Appending {} left by a failed run", pending.display());
+            append_pending(pending, path)
+        }
+        _ => {
+            log::warn!("Discarding {} left by a failed run", pending.display());
+            fs::remove_file(pending)?;
+            Ok(())
+        }
+    }
+}
+
+/// The id of the first row of the frame, or `None` if it is empty.
+fn first_id(pending: &Path) -> Result<Option<i64>> {
+    let mut first_line = String::new();
+    BufReader::new(open_decompressed(pending)?).read_line(&mut first_line)?;
+    match first_line.split('\t').next().filter(|id| !id.is_empty()) {
+        Some(id) => Ok(Some(id.parse()?)),
+        None => Ok(None),
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn append_pending(pending: &Path, path: &Path) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    io::copy(&mut File::open(pending)?, &mut file)?;
+    file.sync_all()?;
+    fs::remove_file(pending)?;
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use crate::compression::Compression;
+use crate::server::csv::to_csv;
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use sql_client::ids::UserId;
+use sql_client::models::{Problem, StaleProblem, UnsolvedAttempt};
+use sql_client::problem_difficulty::ProblemDifficultyClient;
+use sql_client::problem_staleness::ProblemStalenessClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::{JstDay, DAY_SECOND};
+use sql_client::training_velocity::{difficulty_level, DIFFICULTY_LEVEL_NAMES};
+use sql_client::PgPool;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const CSV_FIELDS: &str =
+    "problem_id,contest_id,title,difficulty,color,status,attempts,last_date,url";
+/// The header lines which tell Anki how to import the file.
+const ANKI_HEADER: &str = "#separator:tab\n#html:true\n#tags column:3\n";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ReviewFormat {
+    Csv,
+    /// A tab-separated file, whose columns are the front, the back and the tags of the notes.
+    Anki,
+}
+
+impl FromStr for ReviewFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(ReviewFormat::Csv),
+            "anki" => Ok(ReviewFormat::Anki),
+            _ => Err(anyhow!("Unknown review format: {}", s)),
+        }
+    }
+}
+
+/// A problem to review, which the user has attempted but not solved, or solved long ago.
+#[derive(Debug, PartialEq, Serialize)]
+struct ReviewItem {
+    problem_id: String,
+    contest_id: String,
+    title: String,
+    /// The difficulty clipped in the same way as the frontend, so that it is not negative.
+    difficulty: Option<i64>,
+    color: Option<&'static str>,
+    status: &'static str,
+    /// The number of the submissions, which is only counted for the unsolved problems.
+    attempts: Option<i64>,
+    /// The day in JST of the last attempt of the unsolved problem, or of the last accepted
+    /// submission of the solved problem.
+    last_date: String,
+    url: String,
+}
+
+fn clip_difficulty(difficulty: f64) -> f64 {
+    if difficulty >= 400.0 {
+        difficulty
+    } else {
+        400.0 / (1.0 - difficulty / 400.0).exp()
+    }
+}
+
+fn review_items(
+    unsolved: &[UnsolvedAttempt],
+    stale: &[StaleProblem],
+    problems: &[Problem],
+    difficulties: &BTreeMap<String, f64>,
+) -> Vec<ReviewItem> {
+    let titles = problems
+        .iter()
+        .map(|p| (p.id.as_str(), p.title.as_str()))
+        .collect::<BTreeMap<_, _>>();
+    let item = |problem_id: &str, contest_id: &str, status, attempts, last_epoch_second| {
+        let difficulty = difficulties.get(problem_id).copied();
+        ReviewItem {
+            problem_id: problem_id.to_owned(),
+            contest_id: contest_id.to_owned(),
+            title: titles
+                .get(problem_id)
+                .copied()
+                .unwrap_or(problem_id)
+                .to_owned(),
+            difficulty: difficulty.map(|d| clip_difficulty(d).round() as i64),
+            color: difficulty.map(|d| DIFFICULTY_LEVEL_NAMES[difficulty_level(d)]),
+            status,
+            attempts,
+            last_date: JstDay::from_epoch_second(last_epoch_second).to_string(),
+            url: format!(
+                "https://atcoder.jp/contests/{}/tasks/{}",
+                contest_id, problem_id
+            ),
+        }
+    };
+
+    let mut unsolved = unsolved.iter().collect::<Vec<_>>();
+    unsolved.sort_by_key(|a| (a.last_attempt_epoch_second, a.problem_id.as_str()));
+    let unsolved = unsolved.into_iter().map(|a| {
+        item(
+            &a.problem_id,
+            &a.contest_id,
+            "unsolved",
+            Some(a.attempt_count),
+            a.last_attempt_epoch_second,
+        )
+    });
+    let stale = stale.iter().map(|p| {
+        item(
+            &p.problem_id,
+            &p.contest_id,
+            "solved",
+            None,
+            p.last_accepted_epoch_second,
+        )
+    });
+    unsolved.chain(stale).collect()
+}
+
+/// Removes the tabs and the line breaks, which separate the fields and the notes of Anki.
+fn anki_field(text: &str) -> String {
+    text.replace(|c| c == '\t' || c == '\r' || c == '\n', " ")
+}
+
+fn render_anki(items: &[ReviewItem]) -> String {
+    let mut deck = ANKI_HEADER.to_owned();
+    for item in items.iter() {
+        let front = format!(
+            "<a href=\"{}\">{}</a>",
+            item.url,
+            anki_field(&item.title)
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+        );
+        let mut back = vec![format!("Contest: {}", item.contest_id)];
+        if let Some(difficulty) = item.difficulty {
+            back.push(format!("Difficulty: {}", difficulty));
+        }
+        match item.attempts {
+            Some(attempts) => back.push(format!(
+                "Unsolved after {} attempts, last on {}",
+                attempts, item.last_date
+            )),
+            None => back.push(format!("Solved last on {}", item.last_date)),
+        }
+        let mut tags = vec!["atcoder".to_owned(), item.status.to_owned()];
+        if let Some(color) = item.color {
+            tags.push(format!("difficulty::{}", color.to_lowercase()));
+        }
+        deck.push_str(&format!(
+            "{}\t{}\t{}\n",
+            front,
+            back.join("<br>"),
+            tags.join(" ")
+        ));
+    }
+    deck
+}
+
+/// Writes the problems to review of the user: the ones attempted but not solved, and the ones
+/// solved last `solved_days_ago` or more days ago, the oldest first in each group.
+pub(crate) async fn export_review(
+    pg_pool: &PgPool,
+    user_id: &UserId,
+    output: &Path,
+    format: ReviewFormat,
+    solved_days_ago: i64,
+    compression: Compression,
+) -> Result<()> {
+    let output = &compression.with_extension(output);
+    let before_epoch_second = Utc::now().timestamp() - solved_days_ago * DAY_SECOND;
+    let unsolved = pg_pool.load_unsolved_attempts(user_id).await?;
+    let stale = pg_pool
+        .load_users_stale_problems(user_id, before_epoch_second)
+        .await?;
+    let problems = pg_pool.load_problems().await?;
+    let difficulties = pg_pool.load_problem_difficulties().await?;
+    let items = review_items(&unsolved, &stale, &problems, &difficulties);
+
+    let rendered = match format {
+        ReviewFormat::Csv => to_csv(&serde_json::to_value(&items)?, Some(CSV_FIELDS)),
+        ReviewFormat::Anki => render_anki(&items),
+    };
+    fs::write(output, compression.compress(rendered.as_bytes())?)
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+    log::info!(
+        "Wrote {} unsolved and {} solved problems of {} to {}",
+        unsolved.len(),
+        stale.len(),
+        user_id,
+        output.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items() -> Vec<ReviewItem> {
+        let unsolved = vec![UnsolvedAttempt {
+            problem_id: "abc100_d".to_owned(),
+            contest_id: "abc100".to_owned(),
+            first_attempt_epoch_second: 0,
+            last_attempt_epoch_second: 1_600_000_000,
+            attempt_count: 3,
+        }];
+        let stale = vec![StaleProblem {
+            problem_id: "abc001_a".to_owned(),
+            contest_id: "abc001".to_owned(),
+            last_accepted_epoch_second: 1_500_000_000,
+        }];
+        let problems = vec![Problem {
+            id: "abc100_d".into(),
+            contest_id: "abc100".into(),
+            title: "D. Patisserie, ABC".to_owned(),
+            ..Default::default()
+        }];
+        let difficulties = vec![
+            ("abc100_d".to_owned(), 1234.4),
+            ("abc001_a".to_owned(), -500.0),
+        ]
+        .into_iter()
+        .collect();
+        review_items(&unsolved, &stale, &problems, &difficulties)
+    }
+
+    #[test]
+    fn test_review_items() {
+        let items = items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].status, "unsolved");
+        assert_eq!(items[0].difficulty, Some(1234));
+        assert_eq!(items[0].color, Some("Cyan"));
+        assert_eq!(items[0].last_date, "2020-09-13");
+        assert_eq!(items[1].status, "solved");
+        assert_eq!(items[1].title, "abc001_a");
+        assert_eq!(items[1].difficulty, Some(42));
+        assert_eq!(items[1].color, Some("Gray"));
+        assert_eq!(items[1].attempts, None);
+    }
+
+    #[test]
+    fn test_render() {
+        let items = items();
+        let csv = to_csv(&serde_json::to_value(&items).unwrap(), Some(CSV_FIELDS));
+        let lines = csv.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines[0], CSV_FIELDS);
+        assert_eq!(
+            lines[1],
+            "abc100_d,abc100,\"D. Patisserie, ABC\",1234,Cyan,unsolved,3,2020-09-13,\
+             https://atcoder.jp/contests/abc100/tasks/abc100_d"
+        );
+
+        let deck = render_anki(&items);
+        let lines = deck.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[3],
+            "<a href=\"https://atcoder.jp/contests/abc100/tasks/abc100_d\">D. Patisserie, ABC</a>\t\
+             Contest: abc100<br>Difficulty: 1234<br>Unsolved after 3 attempts, last on 2020-09-13\t\
+             atcoder unsolved difficulty::cyan"
+        );
+    }
+}
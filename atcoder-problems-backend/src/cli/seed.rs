@@ -0,0 +1,201 @@
+use anyhow::{bail, Result};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use sql_client::contest_problem::ContestProblemClient;
+use sql_client::models::{Contest, ContestProblem, Problem, Submission};
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::SubmissionClient;
+use sql_client::PgPool;
+
+use crate::metrics;
+
+/// 2021-01-02 21:00 JST, a Saturday.
+const FIRST_START_EPOCH_SECOND: i64 = 1_609_588_800;
+const WEEK_SECOND: i64 = 7 * 24 * 3600;
+const USER_COUNT: usize = 50;
+const CHUNK_SIZE: usize = 1000;
+
+/// `(contest prefix, rate change, duration, points of the problems)`
+const SERIES: &[(&str, &str, i64, &[f64])] = &[
+    (
+        "abc",
+        " ~ 1999",
+        100 * 60,
+        &[100.0, 200.0, 300.0, 400.0, 500.0, 600.0],
+    ),
+    (
+        "arc",
+        "1200 ~ 2799",
+        120 * 60,
+        &[300.0, 400.0, 500.0, 600.0],
+    ),
+    (
+        "agc",
+        "1200 ~ ",
+        180 * 60,
+        &[400.0, 700.0, 1000.0, 1400.0, 1800.0],
+    ),
+];
+const CONTESTS_PER_SERIES: usize = 3;
+
+const LANGUAGES: &[&str] = &[
+    "C++ (GCC 9.2.1)",
+    "Python (3.8.2)",
+    "PyPy3 (7.3.0)",
+    "Rust (1.42.0)",
+    "Java (OpenJDK 11.0.6)",
+];
+const RESULTS: &[&str] = &["AC", "AC", "AC", "WA", "WA", "TLE", "RE", "CE"];
+
+struct Fixture {
+    contests: Vec<Contest>,
+    problems: Vec<Problem>,
+    contest_problems: Vec<ContestProblem>,
+    submissions: Vec<Submission>,
+}
+
+/// Generates the same fixture for the same seed.
+fn fixture(seed: u64, submission_count: usize) -> Fixture {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut contests = Vec::new();
+    let mut problems = Vec::new();
+    let mut points = Vec::new();
+    for (series_index, (prefix, rate_change, duration_second, problem_points)) in
+        SERIES.iter().enumerate()
+    {
+        for number in 1..=CONTESTS_PER_SERIES {
+            let contest_id = format!("{}{:03}", prefix, number);
+            let week = (number - 1) * SERIES.len() + series_index;
+            contests.push(Contest::new(
+                contest_id.clone(),
+                FIRST_START_EPOCH_SECOND + week as i64 * WEEK_SECOND,
+                *duration_second,
+                format!("AtCoder {} {:03}", prefix.to_uppercase(), number),
+                rate_change.to_string(),
+            ));
+            for (index, point) in problem_points.iter().enumerate() {
+                let label = (b'A' + index as u8) as char;
+                problems.push(Problem {
+                    id: format!("{}_{}", contest_id, label.to_ascii_lowercase()).into(),
+                    contest_id: contest_id.clone().into(),
+                    title: format!("{}. Problem {}{}", label, contest_id, label),
+                    problem_index: label.to_string(),
+                    name: format!("Problem {}{}", contest_id, label),
+                });
+                points.push(*point);
+            }
+        }
+    }
+    let contest_problems = problems
+        .iter()
+        .map(|p| ContestProblem {
+            contest_id: p.contest_id.clone(),
+            problem_id: p.id.clone(),
+        })
+        .collect();
+
+    let users = (1..=USER_COUNT)
+        .map(|i| format!("user{:02}", i))
+        .collect::<Vec<_>>();
+    let mut submissions = (0..submission_count)
+        .map(|_| {
+            let index = rng.gen_range(0, problems.len());
+            let problem = &problems[index];
+            let contest = contests
+                .iter()
+                .find(|c| c.id == problem.contest_id)
+                .unwrap();
+            // Most of the submissions are in the contest, and the others are after it.
+            let epoch_second = if rng.gen_bool(0.7) {
+                contest.start_epoch_second + rng.gen_range(0, contest.duration_second)
+            } else {
+                contest.start_epoch_second + rng.gen_range(contest.duration_second, WEEK_SECOND * 8)
+            };
+            let result = *RESULTS.choose(&mut rng).unwrap();
+            Submission {
+                id: 0,
+                epoch_second,
+                problem_id: problem.id.clone(),
+                contest_id: problem.contest_id.clone(),
+                user_id: users.choose(&mut rng).unwrap().clone().into(),
+                language: LANGUAGES.choose(&mut rng).unwrap().to_string(),
+                point: if result == "AC" { points[index] } else { 0.0 },
+                length: rng.gen_range(100, 5000),
+                result: result.to_owned(),
+                execution_time: if result == "CE" {
+                    None
+                } else {
+                    Some(rng.gen_range(1, 2000))
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+    // The ids increase with the submission times as on AtCoder.
+    submissions.sort_by_key(|s| s.epoch_second);
+    for (id, submission) in submissions.iter_mut().enumerate() {
+        submission.id = id as i64 + 1;
+    }
+
+    Fixture {
+        contests,
+        problems,
+        contest_problems,
+        submissions,
+    }
+}
+
+/// Inserts the fixture into an empty database, so that the servers and the aggregations can run
+/// without crawling AtCoder.
+pub(crate) async fn seed(pg_pool: &PgPool, seed: u64, submission_count: usize) -> Result<()> {
+    if !pg_pool.load_contests().await?.is_empty() {
+        bail!("The database already has contests. Run `migrate --reset` first");
+    }
+    let seeded = fixture(seed, submission_count);
+    metrics::add_rows_written(pg_pool.insert_contests(&seeded.contests).await?);
+    metrics::add_rows_written(pg_pool.insert_problems(&seeded.problems).await?);
+    pg_pool
+        .insert_contest_problem(&seeded.contest_problems)
+        .await?;
+    for chunk in seeded.submissions.chunks(CHUNK_SIZE) {
+        metrics::add_rows_written(pg_pool.update_submissions(chunk).await?);
+    }
+    pg_pool.update_submission_count().await?;
+    log::info!(
+        "Inserted {} contests, {} problems and {} submissions",
+        seeded.contests.len(),
+        seeded.problems.len(),
+        seeded.submissions.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture() {
+        let seeded = fixture(1, 1000);
+        assert_eq!(seeded.contests.len(), 9);
+        assert_eq!(seeded.problems.len(), 45);
+        assert_eq!(seeded.contest_problems.len(), 45);
+        assert_eq!(seeded.submissions.len(), 1000);
+        assert!(seeded.submissions.iter().all(|s| seeded
+            .problems
+            .iter()
+            .any(|p| p.id == s.problem_id && p.contest_id == s.contest_id)));
+        assert!(seeded
+            .submissions
+            .windows(2)
+            .all(|w| w[0].id < w[1].id && w[0].epoch_second <= w[1].epoch_second));
+
+        let user_ids = |f: &Fixture| {
+            f.submissions
+                .iter()
+                .map(|s| s.user_id.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(user_ids(&seeded), user_ids(&fixture(1, 1000)));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::cli::config::Config;
+use crate::grpc::{ApiKeyInterceptor, AtCoderProblemsApiServer, GrpcService};
+use crate::server::{run_server_with_shutdown, GitHubAuthentication};
+use anyhow::{bail, Result};
+use futures::StreamExt;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook_async_std::Signals;
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+use tonic::transport::Server;
+
+/// Runs the API server until it receives SIGTERM or SIGINT.
+pub(crate) async fn serve(config: &Config, port: u16) -> Result<()> {
+    let client_id = env::var("CLIENT_ID").unwrap_or_else(|_| String::new());
+    let client_secret = env::var("CLIENT_SECRET").unwrap_or_else(|_| String::new());
+
+    let auth = GitHubAuthentication::new(&client_id, &client_secret);
+    let pg_pool = config.database.connect().await?;
+
+    let mut signals = Signals::new(&[SIGTERM, SIGINT])?;
+    let shutdown = async move {
+        if let Some(signal) = signals.next().await {
+            log::info!("Received signal {}", signal);
+        }
+    };
+    run_server_with_shutdown(pg_pool, auth, port, shutdown)
+        .await
+        .map_err(|e| e.into_inner())?;
+    Ok(())
+}
+
+/// Runs the gRPC server for the internal services on a tokio runtime, which tonic requires.
+pub(crate) fn serve_grpc(config: &Config, port: u16) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(run_grpc_server(config, port))
+}
+
+async fn run_grpc_server(config: &Config, port: u16) -> Result<()> {
+    let address = grpc_address(config.server.grpc_host, port, &config.server.admin_api_keys)?;
+    let pg_pool = config.database.connect().await?;
+    let interceptor = ApiKeyInterceptor::new(config.server.admin_api_keys.clone());
+    log::info!("Listening on {}", address);
+    Server::builder()
+        .add_service(AtCoderProblemsApiServer::with_interceptor(
+            GrpcService::new(pg_pool),
+            move |request| interceptor.check(request),
+        ))
+        .serve(address)
+        .await?;
+    Ok(())
+}
+
+/// The gRPC API has no authentication but the API keys, so it is not exposed without them.
+fn grpc_address(host: IpAddr, port: u16, api_keys: &[String]) -> Result<SocketAddr> {
+    if !host.is_loopback() && api_keys.is_empty() {
+        bail!(
+            "Set ADMIN_API_KEYS to listen on {} for the gRPC server, or GRPC_HOST=127.0.0.1",
+            host
+        );
+    }
+    Ok(SocketAddr::new(host, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_grpc_address() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert_eq!(
+            grpc_address(localhost, 50051, &[]).unwrap(),
+            "127.0.0.1:50051".parse().unwrap()
+        );
+
+        let any = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        assert!(grpc_address(any, 50051, &[]).is_err());
+        assert_eq!(
+            grpc_address(any, 50051, &["key".to_owned()]).unwrap(),
+            "0.0.0.0:50051".parse().unwrap()
+        );
+    }
+}
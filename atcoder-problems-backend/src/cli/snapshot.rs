@@ -0,0 +1,64 @@
+use crate::cli::dump::render_resources;
+use crate::compression::{Compression, FORMATS};
+use crate::server::feed::load_feed;
+use crate::utils::write_atomically;
+use anyhow::{Context, Result};
+use rayon::prelude::*;
+use serde::Serialize;
+use sql_client::problem_difficulty::ProblemDifficultyClient;
+use sql_client::PgPool;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// The subset of the models published by the time-estimator, which is stored in the database.
+#[derive(Serialize)]
+struct ProblemModel {
+    difficulty: f64,
+}
+
+/// Writes the resources to the directory as the static JSON files, which are the same as
+/// the ones `dump` uploads, `problem-models.json` with the difficulties and `feed.atom` of the new
+/// contests and problems. The files are compressed by `compression` and named with its extension,
+/// e.g. `problems.json.gz`, which `diff-snapshots` also reads.
+/// Each file is replaced atomically, and only if it has changed. The files are compared and
+/// written in parallel. The files written with the other compressions before are removed.
+pub(crate) async fn snapshot(
+    pg_pool: &PgPool,
+    directory: &Path,
+    compression: Compression,
+) -> Result<()> {
+    fs::create_dir_all(directory)
+        .with_context(|| format!("Failed to create {}", directory.display()))?;
+
+    let mut resources = render_resources(pg_pool).await?;
+    let problem_models = pg_pool
+        .load_problem_difficulties()
+        .await?
+        .into_iter()
+        .map(|(problem_id, difficulty)| (problem_id, ProblemModel { difficulty }))
+        .collect::<BTreeMap<_, _>>();
+    resources.push(("problem-models.json", serde_json::to_vec(&problem_models)?));
+    resources.push(("feed.atom", load_feed(pg_pool).await?.into_bytes()));
+
+    resources.into_par_iter().try_for_each(|(name, data)| {
+        let path = compression.with_extension(&directory.join(name));
+        for &format in FORMATS.iter().filter(|&&f| f != compression.format) {
+            let stale = Compression::of(format).with_extension(&directory.join(name));
+            if stale.exists() {
+                fs::remove_file(&stale)
+                    .with_context(|| format!("Failed to remove {}", stale.display()))?;
+                log::info!("Removed {}", stale.display());
+            }
+        }
+        let data = compression.compress(&data)?;
+        if fs::read(&path).ok().as_deref() == Some(data.as_slice()) {
+            log::info!("No update on {}", path.display());
+            return Ok(());
+        }
+        write_atomically(&path, &data)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        log::info!("Wrote {}", path.display());
+        Ok(())
+    })
+}
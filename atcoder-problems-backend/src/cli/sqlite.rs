@@ -0,0 +1,236 @@
+use crate::compression::{Compression, CompressionFormat};
+use anyhow::{bail, Context, Result};
+use rusqlite::{params_from_iter, Connection};
+use sql_client::backup::Snapshot;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The public tables, i.e. without the lists of the users and the states of the crawlers.
+const TABLES: [&str; 16] = [
+    "contests",
+    "problems",
+    "contest_problem",
+    "submissions",
+    "points",
+    "problem_difficulties",
+    "solver",
+    "first",
+    "fastest",
+    "shortest",
+    "accepted_count",
+    "rated_point_sum",
+    "language_count",
+    "max_streaks",
+    "submission_count",
+    "predicted_rating",
+];
+
+/// The indexes for the typical analyses, in addition to the primary keys.
+const INDEXES: [(&str, &str); 4] = [
+    ("submissions", "user_id, epoch_second"),
+    ("submissions", "problem_id"),
+    ("submissions", "contest_id"),
+    ("problems", "contest_id"),
+];
+
+/// Writes the public tables to a single SQLite file, all from the same snapshot of the database.
+/// The file is written to a temporary file first, so that the output is never a half-written one.
+/// It is compressed by `compression` after it is written, since SQLite writes the pages randomly,
+/// and the output is named with the extension of the compression, e.g. `atcoder.sqlite.zst`.
+pub(crate) fn export_sqlite(
+    database_url: &str,
+    output: &Path,
+    compression: Compression,
+) -> Result<()> {
+    let output = &compression.with_extension(output);
+    let mut temporary = output.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = Path::new(&temporary);
+    if temporary.exists() {
+        fs::remove_file(temporary)?;
+    }
+    let mut conn = Connection::open(temporary)
+        .with_context(|| format!("Failed to create {}", temporary.display()))?;
+    conn.execute_batch("PRAGMA journal_mode = OFF; PRAGMA synchronous = OFF;")?;
+
+    let mut snapshot = Snapshot::begin(database_url)?;
+    for table in TABLES.iter() {
+        log::info!("Copying {}", table);
+        let columns = snapshot.columns(table)?;
+        if columns.is_empty() {
+            bail!("{} does not exist", table);
+        }
+        let primary_key = snapshot.primary_key(table)?;
+        conn.execute_batch(&create_table(table, &columns, &primary_key))?;
+
+        // The rows are copied to a local file first, since the snapshot can not be read while
+        // the rows are inserted.
+        let local = std::env::temp_dir().join(format!(
+            "atcoder-problems-export-{}-{}.tsv",
+            std::process::id(),
+            table
+        ));
+        let rows = copy_table(&mut snapshot, &mut conn, table, columns.len(), &local);
+        // The error of the copy, if any, is more useful than the error of the cleanup.
+        if let Err(e) = fs::remove_file(&local) {
+            if e.kind() != io::ErrorKind::NotFound {
+                log::warn!("Failed to remove {}: {:?}", local.display(), e);
+            }
+        }
+        log::info!("Copied {} rows of {}", rows?, table);
+    }
+    for (table, columns) in INDEXES.iter() {
+        let name = format!("{}_{}", table, columns.replace(", ", "_"));
+        conn.execute_batch(&format!("CREATE INDEX {} ON {} ({})", name, table, columns))?;
+    }
+    conn.execute_batch("ANALYZE")?;
+    conn.close().map_err(|(_, e)| e)?;
+
+    if compression.format == CompressionFormat::None {
+        fs::rename(temporary, output)?;
+    } else {
+        let compressed = compression.with_extension(temporary);
+        let mut writer = compression.writer(BufWriter::new(File::create(&compressed)?))?;
+        io::copy(&mut File::open(temporary)?, &mut writer)?;
+        writer.finish()?.flush()?;
+        fs::remove_file(temporary)?;
+        fs::rename(compressed, output)?;
+    }
+    log::info!("Wrote {}", output.display());
+    Ok(())
+}
+
+fn copy_table(
+    snapshot: &mut Snapshot,
+    conn: &mut Connection,
+    table: &str,
+    column_count: usize,
+    local: &Path,
+) -> Result<u64> {
+    let mut writer = BufWriter::new(File::create(local)?);
+    snapshot.copy_table(table, &mut writer)?;
+    writer.flush()?;
+
+    let placeholders = vec!["?"; column_count].join(", ");
+    let transaction = conn.transaction()?;
+    let mut rows = 0;
+    {
+        let mut statement = transaction.prepare(&format!(
+            "INSERT INTO \"{}\" VALUES ({})",
+            table, placeholders
+        ))?;
+        for line in BufReader::new(File::open(local)?).lines() {
+            statement.execute(params_from_iter(parse_copy_line(&line?)))?;
+            rows += 1;
+        }
+    }
+    transaction.commit()?;
+    Ok(rows)
+}
+
+/// The SQLite table with the same columns, whose types are the affinities of the Postgres types.
+/// The names are quoted, since some of them are keywords in SQLite, e.g. `first`.
+fn create_table(table: &str, columns: &[(String, String)], primary_key: &[String]) -> String {
+    let mut definitions = columns
+        .iter()
+        .map(|(name, data_type)| format!("\"{}\" {}", name, affinity(data_type)))
+        .collect::<Vec<_>>();
+    if !primary_key.is_empty() {
+        let primary_key = primary_key
+            .iter()
+            .map(|name| format!("\"{}\"", name))
+            .collect::<Vec<_>>();
+        definitions.push(format!("PRIMARY KEY ({})", primary_key.join(", ")));
+    }
+    format!("CREATE TABLE \"{}\" ({})", table, definitions.join(", "))
+}
+
+fn affinity(data_type: &str) -> &'static str {
+    match data_type {
+        "smallint" | "integer" | "bigint" => "INTEGER",
+        "real" | "double precision" | "numeric" => "REAL",
+        _ => "TEXT",
+    }
+}
+
+/// Parses a line in the text format of `COPY`, where the columns are separated by tabs,
+/// the special characters are escaped by backslashes, and `\N` is NULL.
+fn parse_copy_line(line: &str) -> Vec<Option<String>> {
+    line.split('\t')
+        .map(|field| {
+            if field == "\\N" {
+                return None;
+            }
+            let mut value = String::with_capacity(field.len());
+            let mut chars = field.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    value.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('b') => value.push('\u{8}'),
+                    Some('f') => value.push('\u{c}'),
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some('v') => value.push('\u{b}'),
+                    Some(c) => value.push(c),
+                    None => value.push('\\'),
+                }
+            }
+            Some(value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_copy_line() {
+        assert_eq!(
+            parse_copy_line("abc001\t100\ttitle\\nwith a\\ttab\\\\\t\\N"),
+            vec![
+                Some("abc001".to_owned()),
+                Some("100".to_owned()),
+                Some("title\nwith a\ttab\\".to_owned()),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create_table() {
+        let columns = vec![
+            ("user_id".to_owned(), "character varying".to_owned()),
+            (
+                "simplified_language".to_owned(),
+                "character varying".to_owned(),
+            ),
+            ("problem_count".to_owned(), "integer".to_owned()),
+        ];
+        let primary_key = vec!["user_id".to_owned(), "simplified_language".to_owned()];
+        let sql = create_table("language_count", &columns, &primary_key);
+        assert_eq!(
+            sql,
+            r#"CREATE TABLE "language_count" ("user_id" TEXT, "simplified_language" TEXT, "problem_count" INTEGER, PRIMARY KEY ("user_id", "simplified_language"))"#
+        );
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&sql).unwrap();
+        conn.execute(
+            "INSERT INTO language_count VALUES (?, ?, ?)",
+            params_from_iter(parse_copy_line("kenkoooo\tRust\t42")),
+        )
+        .unwrap();
+        let count: i64 = conn
+            .query_row("SELECT problem_count FROM language_count", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 42);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::utils::write_atomically;
+use anyhow::{Error, Result};
+use serde::Serialize;
+use std::path::Path;
+
+/// The outcome of a run, which is told to the wrapper scripts by the exit code.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitStatus {
+    Success,
+    /// Something else failed, e.g. fetching from AtCoder.
+    Failure,
+    /// The command-line options or the config are invalid.
+    UsageError,
+    /// The database is not reachable, or a query failed.
+    DatabaseError,
+    /// The job finished, but some of the items failed, e.g. some of the contests to crawl.
+    PartialSuccess,
+    /// Another process is running the same job.
+    Skipped,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success | ExitStatus::Skipped => 0,
+            ExitStatus::Failure => 1,
+            ExitStatus::UsageError => 2,
+            ExitStatus::DatabaseError => 3,
+            ExitStatus::PartialSuccess => 4,
+        }
+    }
+
+    /// The name in `crawler_runs` and in the status file.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            ExitStatus::Success => "success",
+            ExitStatus::Failure => "failure",
+            ExitStatus::UsageError => "usage_error",
+            ExitStatus::DatabaseError => "database_error",
+            ExitStatus::PartialSuccess => "partial_success",
+            ExitStatus::Skipped => "skipped",
+        }
+    }
+
+    pub(crate) fn of(result: &Result<()>, failures: u64) -> Self {
+        match result {
+            Ok(()) if failures > 0 => ExitStatus::PartialSuccess,
+            Ok(()) => ExitStatus::Success,
+            Err(e) if is_database_error(e) => ExitStatus::DatabaseError,
+            Err(_) => ExitStatus::Failure,
+        }
+    }
+}
+
+fn is_database_error(error: &Error) -> bool {
+    error
+        .chain()
+        .any(|cause| cause.is::<sql_client::DatabaseError>())
+}
+
+/// Identifies a run in the logs, the error reports and the notifications.
+pub(crate) fn generate_run_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Summary of a run, which is written to `--status-file`.
+#[derive(Serialize, Debug)]
+pub(crate) struct RunStatus {
+    pub(crate) job: Option<&'static str>,
+    pub(crate) run_id: String,
+    pub(crate) status: ExitStatus,
+    pub(crate) exit_code: i32,
+    pub(crate) error: Option<String>,
+    pub(crate) started_epoch_second: i64,
+    pub(crate) finished_epoch_second: i64,
+    pub(crate) rows_written: u64,
+    pub(crate) failures: u64,
+}
+
+impl RunStatus {
+    pub(crate) fn write(&self, path: &Path) -> Result<()> {
+        write_atomically(path, &serde_json::to_vec_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::fs;
+
+    #[test]
+    fn test_exit_status() {
+        assert_eq!(ExitStatus::of(&Ok(()), 0), ExitStatus::Success);
+        assert_eq!(ExitStatus::of(&Ok(()), 2), ExitStatus::PartialSuccess);
+        assert_eq!(
+            ExitStatus::of(&Err(anyhow!("Failed to parse html.")), 0),
+            ExitStatus::Failure
+        );
+
+        let error =
+            Error::new(sql_client::DatabaseError::PoolTimedOut).context("Failed to connect");
+        assert_eq!(ExitStatus::of(&Err(error), 0), ExitStatus::DatabaseError);
+
+        let serialized = serde_json::to_value(ExitStatus::PartialSuccess).unwrap();
+        assert_eq!(serialized, ExitStatus::PartialSuccess.as_str());
+    }
+
+    #[test]
+    fn test_write() {
+        let path = std::env::temp_dir().join(format!(
+            "atcoder-problems-status-{}.json",
+            std::process::id()
+        ));
+        let status = RunStatus {
+            job: Some("crawl_contests"),
+            run_id: "0123456789abcdef".to_owned(),
+            status: ExitStatus::PartialSuccess,
+            exit_code: ExitStatus::PartialSuccess.code(),
+            error: None,
+            started_epoch_second: 1_600_000_000,
+            finished_epoch_second: 1_600_000_060,
+            rows_written: 100,
+            failures: 1,
+        };
+        status.write(&path).unwrap();
+
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({
+                "job": "crawl_contests",
+                "run_id": "0123456789abcdef",
+                "status": "partial_success",
+                "exit_code": 4,
+                "error": null,
+                "started_epoch_second": 1_600_000_000,
+                "finished_epoch_second": 1_600_000_060,
+                "rows_written": 100,
+                "failures": 1,
+            })
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
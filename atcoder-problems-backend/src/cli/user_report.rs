@@ -0,0 +1,452 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{NaiveDate, Utc};
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::achievement::AchievementClient;
+use sql_client::ids::UserId;
+use sql_client::live_performance::LivePerformanceClient;
+use sql_client::models::{Achievement, Contest, LivePerformance, TrainingVelocity};
+use sql_client::rated_point_sum::RatedPointSumClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::streak::{current_streak_alive_since, StreakUpdater};
+use sql_client::time::JstDay;
+use sql_client::training_velocity::{
+    TrainingVelocityClient, DIFFICULTY_LEVEL_NAMES, TRAINING_VELOCITY_PERIOD_DAYS,
+};
+use sql_client::PgPool;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const DEFAULT_PERIOD_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "markdown" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(anyhow!("Unknown report format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Table {
+    title: String,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+#[derive(Debug, PartialEq)]
+struct UserReport {
+    title: String,
+    summary: Vec<(&'static str, String)>,
+    tables: Vec<Table>,
+}
+
+/// The values read from the aggregation tables for the report.
+struct UserStats {
+    accepted_count: i32,
+    rated_point_sum: f64,
+    max_streak: i64,
+    current_streak: i64,
+    velocities: Vec<TrainingVelocity>,
+    performances: Vec<LivePerformance>,
+    achievements: Vec<Achievement>,
+}
+
+/// Returns the shortest period of `training_velocity` which covers the days, or the longest one.
+fn select_period_days(days: i64) -> i64 {
+    TRAINING_VELOCITY_PERIOD_DAYS
+        .iter()
+        .copied()
+        .find(|&period_days| period_days >= days)
+        .unwrap_or(TRAINING_VELOCITY_PERIOD_DAYS[TRAINING_VELOCITY_PERIOD_DAYS.len() - 1])
+}
+
+/// Builds the report from `since` to `today`. The solved problems are counted in the shortest
+/// period of `training_velocity` which covers them, since it has no other periods, and the period
+/// is shown with them.
+fn build_report(
+    user_id: &str,
+    since: JstDay,
+    today: JstDay,
+    stats: &UserStats,
+    contests: &[Contest],
+) -> UserReport {
+    let since_epoch_second = since.start_epoch_second();
+    let period_days = select_period_days(since.days_until(today) + 1);
+    let velocity = stats
+        .velocities
+        .iter()
+        .find(|v| v.period_days == period_days);
+
+    let summary = vec![
+        ("Accepted", stats.accepted_count.to_string()),
+        ("Rated point sum", format!("{:.0}", stats.rated_point_sum)),
+        ("Longest streak", format!("{} days", stats.max_streak)),
+        ("Current streak", format!("{} days", stats.current_streak)),
+        (
+            "Recently solved",
+            format!(
+                "{} problems, {:.0} points in {} days",
+                velocity.map_or(0, |v| v.accepted_count),
+                velocity.map_or(0.0, |v| v.rated_point_sum),
+                period_days
+            ),
+        ),
+    ];
+
+    let difficulty_rows = DIFFICULTY_LEVEL_NAMES
+        .iter()
+        .enumerate()
+        .map(|(level, color)| {
+            let count = velocity
+                .and_then(|v| v.difficulty_counts.get(level))
+                .copied()
+                .unwrap_or(0);
+            vec![color.to_string(), count.to_string()]
+        })
+        .collect();
+
+    let contests = contests
+        .iter()
+        .map(|c| (c.id.as_str(), c))
+        .collect::<BTreeMap<_, _>>();
+    let mut performances = stats
+        .performances
+        .iter()
+        .filter_map(|p| contests.get(p.contest_id.as_str()).map(|c| (*c, p)))
+        .filter(|(c, _)| c.start_epoch_second >= since_epoch_second)
+        .collect::<Vec<_>>();
+    performances.sort_by_key(|(c, _)| c.start_epoch_second);
+    let performance_rows = performances
+        .iter()
+        .map(|(c, p)| {
+            vec![
+                c.title.clone(),
+                JstDay::from_epoch_second(c.start_epoch_second).to_string(),
+                p.rank.to_string(),
+                p.performance.to_string(),
+                format!("{} → {}", p.old_rating, p.new_rating),
+            ]
+        })
+        .collect();
+
+    // The categories of the contests stand in for the tags of the problems, which are not stored.
+    let mut categories = BTreeMap::new();
+    for (c, p) in performances.iter() {
+        let (count, sum) = categories.entry(c.category.as_str()).or_insert((0, 0));
+        *count += 1;
+        *sum += p.performance;
+    }
+    let mut categories = categories
+        .into_iter()
+        .map(|(category, (count, sum))| (category, count, sum as f64 / count as f64))
+        .collect::<Vec<_>>();
+    categories.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap().then_with(|| a.0.cmp(b.0)));
+    let category_rows = categories
+        .into_iter()
+        .map(|(category, count, mean)| {
+            vec![
+                category.to_owned(),
+                count.to_string(),
+                format!("{:.0}", mean),
+            ]
+        })
+        .collect();
+
+    let mut achievements = stats
+        .achievements
+        .iter()
+        .filter(|a| a.achieved_epoch_second >= since_epoch_second)
+        .collect::<Vec<_>>();
+    achievements.sort_by_key(|a| a.achieved_epoch_second);
+    let achievement_rows = achievements
+        .iter()
+        .map(|a| {
+            vec![
+                a.achievement_id.clone(),
+                JstDay::from_epoch_second(a.achieved_epoch_second).to_string(),
+            ]
+        })
+        .collect();
+
+    UserReport {
+        title: format!("Training report of {} since {}", user_id, since),
+        summary,
+        tables: vec![
+            Table {
+                title: format!(
+                    "Solved problems by difficulty in the last {} days",
+                    period_days
+                ),
+                headers: vec!["Color", "Problems"],
+                rows: difficulty_rows,
+            },
+            Table {
+                title: "Performances".to_owned(),
+                headers: vec!["Contest", "Date", "Rank", "Performance", "Rating"],
+                rows: performance_rows,
+            },
+            Table {
+                title: "Weakest contest categories".to_owned(),
+                headers: vec!["Category", "Contests", "Mean performance"],
+                rows: category_rows,
+            },
+            Table {
+                title: "Achievements".to_owned(),
+                headers: vec!["Achievement", "Date"],
+                rows: achievement_rows,
+            },
+        ],
+    }
+}
+
+fn render_markdown(report: &UserReport) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut markdown = format!("# {}\n\n", report.title);
+    for (name, value) in report.summary.iter() {
+        markdown.push_str(&format!("- {}: {}\n", name, value));
+    }
+    for table in report.tables.iter() {
+        markdown.push_str(&format!("\n## {}\n\n", table.title));
+        if table.rows.is_empty() {
+            markdown.push_str("None.\n");
+            continue;
+        }
+        markdown.push_str(&format!("| {} |\n", table.headers.join(" | ")));
+        markdown.push_str(&format!("|{}\n", " --- |".repeat(table.headers.len())));
+        for row in table.rows.iter() {
+            let row = row.iter().map(|c| cell(c)).collect::<Vec<_>>();
+            markdown.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+    }
+    markdown
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_html(report: &UserReport) -> String {
+    let title = escape_html(&report.title);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n\
+         <body>\n<h1>{}</h1>\n<ul>\n",
+        title, title
+    );
+    for (name, value) in report.summary.iter() {
+        html.push_str(&format!("<li>{}: {}</li>\n", name, escape_html(value)));
+    }
+    html.push_str("</ul>\n");
+    for table in report.tables.iter() {
+        html.push_str(&format!("<h2>{}</h2>\n", escape_html(&table.title)));
+        if table.rows.is_empty() {
+            html.push_str("<p>None.</p>\n");
+            continue;
+        }
+        html.push_str("<table>\n<tr>");
+        for header in table.headers.iter() {
+            html.push_str(&format!("<th>{}</th>", header));
+        }
+        html.push_str("</tr>\n");
+        for row in table.rows.iter() {
+            html.push_str("<tr>");
+            for cell in row.iter() {
+                html.push_str(&format!("<td>{}</td>", escape_html(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Writes the report of the user since the day in JST, 30 days ago by default, to the file or
+/// the standard output. It reads only the aggregation tables and the contests, so it does not
+/// scan the submissions.
+pub(crate) async fn user_report(
+    pg_pool: &PgPool,
+    user_id: &UserId,
+    since: Option<NaiveDate>,
+    format: ReportFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let now = Utc::now().timestamp();
+    let today = JstDay::from_epoch_second(now);
+    let since = match since {
+        Some(date) => JstDay::from_date(date),
+        None => JstDay::from_epoch_second(now - DEFAULT_PERIOD_DAYS * 24 * 3600),
+    };
+
+    let stats = UserStats {
+        accepted_count: pg_pool
+            .get_users_accepted_count(user_id)
+            .await?
+            .unwrap_or(0),
+        rated_point_sum: pg_pool
+            .get_users_rated_point_sum(user_id)
+            .await?
+            .unwrap_or(0.0),
+        max_streak: pg_pool.get_users_max_streak(user_id).await?.unwrap_or(0),
+        current_streak: pg_pool
+            .get_users_current_streak(user_id, current_streak_alive_since(now))
+            .await?
+            .unwrap_or(0),
+        velocities: pg_pool.load_training_velocity(user_id).await?,
+        performances: pg_pool.load_users_live_performances(user_id).await?,
+        achievements: pg_pool.load_achievements(user_id).await?,
+    };
+    let contests = pg_pool.load_contests().await?;
+    let report = build_report(user_id.as_str(), since, today, &stats, &contests);
+    let rendered = match format {
+        ReportFormat::Markdown => render_markdown(&report),
+        ReportFormat::Html => render_html(&report),
+    };
+    match output {
+        Some(output) => fs::write(output, rendered)
+            .with_context(|| format!("Failed to write {}", output.display()))?,
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str) -> JstDay {
+        JstDay::from_date(date.parse().unwrap())
+    }
+
+    fn stats() -> UserStats {
+        let performance = |contest_id: &str, performance| LivePerformance {
+            contest_id: contest_id.to_owned(),
+            user_id: "user".to_owned(),
+            rank: 10,
+            performance,
+            old_rating: 1000,
+            new_rating: 1100,
+        };
+        UserStats {
+            accepted_count: 100,
+            rated_point_sum: 12345.0,
+            max_streak: 20,
+            current_streak: 3,
+            velocities: vec![TrainingVelocity {
+                user_id: "user".to_owned(),
+                period_days: 30,
+                accepted_count: 5,
+                rated_point_sum: 1500.0,
+                difficulty_counts: vec![1, 0, 4],
+            }],
+            performances: vec![
+                performance("abc200", 1200),
+                performance("arc120", 900),
+                performance("abc100", 2000),
+            ],
+            achievements: vec![Achievement {
+                user_id: "user".to_owned(),
+                achievement_id: "ac_100".to_owned(),
+                achieved_epoch_second: day("2021-05-10").start_epoch_second(),
+            }],
+        }
+    }
+
+    fn contests() -> Vec<Contest> {
+        let contest = |id: &str, title: &str, date: &str| {
+            Contest::new(
+                id,
+                day(date).start_epoch_second() + 21 * 3600,
+                6000,
+                title.to_owned(),
+                "All".to_owned(),
+            )
+        };
+        vec![
+            contest("abc200", "AtCoder Beginner Contest 200", "2021-05-08"),
+            contest("arc120", "AtCoder Regular Contest 120", "2021-05-23"),
+            contest("abc100", "AtCoder Beginner Contest 100", "2018-06-16"),
+        ]
+    }
+
+    #[test]
+    fn test_select_period_days() {
+        assert_eq!(select_period_days(1), 7);
+        assert_eq!(select_period_days(7), 7);
+        assert_eq!(select_period_days(8), 30);
+        assert_eq!(select_period_days(365), 90);
+    }
+
+    #[test]
+    fn test_build_report() {
+        let report = build_report(
+            "user",
+            day("2021-05-01"),
+            day("2021-05-30"),
+            &stats(),
+            &contests(),
+        );
+        assert_eq!(report.title, "Training report of user since 2021-05-01");
+        assert_eq!(report.summary[4].1, "5 problems, 1500 points in 30 days");
+
+        let difficulties = &report.tables[0];
+        assert_eq!(difficulties.rows.len(), DIFFICULTY_LEVEL_NAMES.len());
+        assert_eq!(difficulties.rows[2], vec!["Green", "4"]);
+        assert_eq!(difficulties.rows[3], vec!["Cyan", "0"]);
+
+        let performances = &report.tables[1];
+        assert_eq!(performances.rows.len(), 2);
+        assert_eq!(performances.rows[0][0], "AtCoder Beginner Contest 200");
+        assert_eq!(performances.rows[0][1], "2021-05-08");
+
+        let categories = &report.tables[2];
+        assert_eq!(
+            categories.rows,
+            vec![vec!["ARC", "1", "900"], vec!["ABC", "1", "1200"]]
+        );
+        assert_eq!(report.tables[3].rows, vec![vec!["ac_100", "2021-05-10"]]);
+    }
+
+    #[test]
+    fn test_render() {
+        let report = UserReport {
+            title: "Report <user>".to_owned(),
+            summary: vec![("Accepted", "1".to_owned())],
+            tables: vec![
+                Table {
+                    title: "Empty".to_owned(),
+                    headers: vec!["A"],
+                    rows: vec![],
+                },
+                Table {
+                    title: "Rows".to_owned(),
+                    headers: vec!["A", "B"],
+                    rows: vec![vec!["a|b".to_owned(), "<c>".to_owned()]],
+                },
+            ],
+        };
+        assert_eq!(
+            render_markdown(&report),
+            "# Report <user>\n\n- Accepted: 1\n\n## Empty\n\nNone.\n\n## Rows\n\n\
+             | A | B |\n| --- | --- |\n| a\\|b | <c> |\n"
+        );
+        let html = render_html(&report);
+        assert!(html.contains("<h1>Report &lt;user&gt;</h1>"));
+        assert!(html.contains("<p>None.</p>"));
+        assert!(html.contains("<tr><td>a|b</td><td>&lt;c&gt;</td></tr>"));
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use log::{error, info, warn};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use sql_client::crawl_request::{CrawlRequest, CrawlRequestClient};
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::ContestWindow;
+use std::{thread, time};
+
+use crate::cli::config::Config;
+use crate::crawler::Verifier;
+use crate::error_report;
+use crate::metrics;
+
+/// Verifies the given contests, or `sample` contests chosen at random among the finished ones,
+/// against AtCoder. The drifted contests are queued in `crawl_requests` if `enqueue` is set,
+/// otherwise the run fails so that they are noticed.
+pub(crate) async fn verify(
+    config: &Config,
+    contest_ids: Vec<String>,
+    sample: usize,
+    enqueue: bool,
+) -> Result<()> {
+    let db = config.database.connect().await?;
+    let client = config.atcoder.client().await?;
+    let contest_ids = if contest_ids.is_empty() {
+        let now = Utc::now().timestamp();
+        let finished = db
+            .load_contests()
+            .await?
+            .into_iter()
+            .filter(|c| ContestWindow::of(c).has_ended(now))
+            .map(|c| c.id.into_string())
+            .collect::<Vec<_>>();
+        finished
+            .choose_multiple(&mut thread_rng(), sample)
+            .cloned()
+            .collect()
+    } else {
+        contest_ids
+    };
+
+    let verifier = Verifier::new(db.clone(), client);
+    let mut drifts = Vec::new();
+    for contest_id in contest_ids.iter() {
+        match verifier.verify(contest_id).await {
+            Ok(Some(drift)) => {
+                warn!("{}", drift);
+                drifts.push(drift);
+            }
+            Ok(None) => info!("{} is up to date", contest_id),
+            Err(e) => {
+                error!("Failed to verify {}: {:?}", contest_id, e);
+                error_report::report(&e, &[("contest_id", contest_id)]);
+                metrics::add_failure();
+            }
+        }
+        metrics::add_contest_processed(contest_id);
+        thread::sleep(time::Duration::from_millis(200));
+    }
+
+    info!(
+        "{} of {} contests have drifted",
+        drifts.len(),
+        contest_ids.len()
+    );
+    for drift in drifts.iter() {
+        println!("{}", drift);
+    }
+    if drifts.is_empty() {
+        return Ok(());
+    }
+    if !enqueue {
+        bail!("{} contests should be re-crawled", drifts.len());
+    }
+    let now = Utc::now().timestamp();
+    for drift in drifts {
+        db.enqueue_crawl(&CrawlRequest::Contest(drift.contest_id), 0, now)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+use anyhow::{anyhow, bail, Result};
+use async_std::future::timeout;
+use serde_json::Value;
+use sql_client::models::Submission;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::submission_listener::SubmissionListener;
+use sql_client::PgPool;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The number of the submissions copied at once by the backfill.
+const BACKFILL_BATCH_SIZE: i64 = 10000;
+/// The submissions are buffered up to this number of batches while ClickHouse fails, after which
+/// the error is returned to restart the sink.
+const MAX_BUFFERED_BATCHES: usize = 100;
+
+/// Inserts the submissions into a ClickHouse table with the HTTP interface.
+///
+/// The table is a `ReplacingMergeTree` ordered by `id`, so a submission inserted more than once,
+/// e.g. when its result is updated, is deduplicated to the one with the latest `version` by the
+/// merges. The queries which need the exact rows read the table with `FINAL`.
+pub struct ClickHouseSink {
+    url: String,
+    database: String,
+    table: String,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+impl ClickHouseSink {
+    pub fn new(
+        url: &str,
+        database: &str,
+        table: &str,
+        user: Option<&str>,
+        password: Option<&str>,
+    ) -> Result<Self> {
+        check_identifier(database)?;
+        check_identifier(table)?;
+        Ok(Self {
+            url: url.to_owned(),
+            database: database.to_owned(),
+            table: table.to_owned(),
+            user: user.map(|user| user.to_owned()),
+            password: password.map(|password| password.to_owned()),
+        })
+    }
+
+    async fn query(&self, query: &str, body: Vec<u8>) -> Result<()> {
+        let mut url = surf::Url::parse(&self.url)?;
+        url.query_pairs_mut().append_pair("query", query);
+        let mut request = surf::post(url).body(body);
+        if let Some(user) = &self.user {
+            request = request.header("X-ClickHouse-User", user.as_str());
+        }
+        if let Some(password) = &self.password {
+            request = request.header("X-ClickHouse-Key", password.as_str());
+        }
+        let mut response = request
+            .await
+            .map_err(|e| anyhow!("Failed to request ClickHouse: {:?}", e))?;
+        if !response.status().is_success() {
+            let body = response.body_string().await.unwrap_or_default();
+            bail!("ClickHouse returned {}: {}", response.status(), body);
+        }
+        Ok(())
+    }
+
+    pub async fn ensure_table(&self) -> Result<()> {
+        self.query(&create_table_query(&self.database, &self.table), Vec::new())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn insert(&self, submissions: &[Submission]) -> Result<()> {
+        let query = format!(
+            "INSERT INTO {} FORMAT JSONEachRow",
+            table_name(&self.database, &self.table)
+        );
+        self.query(&query, insert_body(submissions, version()?)?)
+            .await
+    }
+}
+
+/// Accepts only the names which need no escaping, since they are embedded in the queries.
+fn check_identifier(name: &str) -> Result<()> {
+    let is_valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid {
+        bail!("Invalid ClickHouse database or table name: {:?}", name);
+    }
+    Ok(())
+}
+
+fn table_name(database: &str, table: &str) -> String {
+    format!("`{}`.`{}`", database, table)
+}
+
+fn create_table_query(database: &str, table: &str) -> String {
+    format!(
+        r"
+        CREATE TABLE IF NOT EXISTS {} (
+            id Int64,
+            epoch_second Int64,
+            problem_id String,
+            contest_id LowCardinality(String),
+            user_id String,
+            language LowCardinality(String),
+            point Float64,
+            length Int32,
+            result LowCardinality(String),
+            execution_time Nullable(Int32),
+            version UInt64
+        )
+        ENGINE = ReplacingMergeTree(version)
+        PARTITION BY toYYYYMM(toDateTime(epoch_second))
+        ORDER BY id
+        ",
+        table_name(database, table)
+    )
+}
+
+/// The milliseconds since the epoch, which orders the insertions of the same submission.
+fn version() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64)
+}
+
+/// The rows in `JSONEachRow`, i.e. a JSON object per line.
+fn insert_body(submissions: &[Submission], version: u64) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    for submission in submissions {
+        let mut row = serde_json::to_value(submission)?;
+        if let Value::Object(columns) = &mut row {
+            columns.insert("version".to_owned(), version.into());
+        }
+        serde_json::to_writer(&mut body, &row)?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
+
+/// Inserts the submissions inserted or updated by the crawlers, by `batch_size` or every
+/// `flush_interval`, whichever comes first. The submissions are kept and retried at the next
+/// flush if ClickHouse fails.
+///
+/// If `backfill_from_second` is given, the existing submissions since then are copied first,
+/// while the new ones are buffered. It is advanced as they are copied and cleared when they are
+/// all copied, so that a retry after an error resumes the backfill.
+pub async fn replicate_submissions(
+    pg_pool: PgPool,
+    sink: &ClickHouseSink,
+    backfill_from_second: &mut Option<i64>,
+    batch_size: usize,
+    flush_interval: Duration,
+) -> Result<()> {
+    sink.ensure_table().await?;
+    let (receiver, listening) = SubmissionListener::connect(&pg_pool).await?.spawn();
+
+    if let Some(from_second) = *backfill_from_second {
+        let mut cursor = (from_second, -1);
+        loop {
+            let submissions = pg_pool
+                .get_submissions(SubmissionRequest::FromCursor {
+                    from_second: cursor.0,
+                    from_id: cursor.1,
+                    count: BACKFILL_BATCH_SIZE,
+                })
+                .await?;
+            let last = match submissions.last() {
+                Some(last) => last,
+                None => break,
+            };
+            cursor = (last.epoch_second, last.id);
+            sink.insert(&submissions).await?;
+            *backfill_from_second = Some(cursor.0);
+            log::info!("Copied the submissions until {} to ClickHouse", cursor.0);
+        }
+        *backfill_from_second = None;
+    }
+
+    let mut buffer = Vec::new();
+    let mut flushed = Instant::now();
+    loop {
+        let wait = flush_interval
+            .checked_sub(flushed.elapsed())
+            .unwrap_or_default();
+        match timeout(wait, receiver.recv()).await {
+            Ok(Ok(submission)) => buffer.push(submission),
+            Ok(Err(_)) => {
+                // Sends the buffered submissions before returning the error of the listener.
+                for chunk in buffer.chunks(batch_size) {
+                    sink.insert(chunk).await?;
+                }
+                return listening.await;
+            }
+            Err(_) => {}
+        }
+        if buffer.len() >= batch_size || flushed.elapsed() >= flush_interval {
+            if !buffer.is_empty() {
+                let mut inserted = 0;
+                for chunk in buffer.chunks(batch_size) {
+                    match sink.insert(chunk).await {
+                        Ok(()) => inserted += chunk.len(),
+                        Err(e) if buffer.len() > batch_size * MAX_BUFFERED_BATCHES => {
+                            return Err(e)
+                        }
+                        Err(e) => {
+                            log::error!("Failed to insert {} submissions: {:?}", chunk.len(), e);
+                            break;
+                        }
+                    }
+                }
+                log::info!("Inserted {} submissions into ClickHouse", inserted);
+                buffer.drain(..inserted);
+            }
+            flushed = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_body() {
+        let submissions = vec![
+            Submission {
+                id: 1,
+                user_id: "kenkoooo".into(),
+                result: "WJ".to_owned(),
+                ..Default::default()
+            },
+            Submission {
+                id: 2,
+                execution_time: Some(10),
+                ..Default::default()
+            },
+        ];
+        let body = insert_body(&submissions, 42).unwrap();
+        let rows = String::from_utf8(body).unwrap();
+        let rows = rows
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["id"], 1);
+        assert_eq!(rows[0]["user_id"], "kenkoooo");
+        assert_eq!(rows[0]["execution_time"], Value::Null);
+        assert_eq!(rows[0]["version"], 42);
+        assert_eq!(rows[1]["execution_time"], 10);
+    }
+
+    #[test]
+    fn test_create_table_query() {
+        let query = create_table_query("atcoder", "submissions");
+        assert!(query.contains("CREATE TABLE IF NOT EXISTS `atcoder`.`submissions`"));
+        assert!(query.contains("ENGINE = ReplacingMergeTree(version)"));
+    }
+
+    #[test]
+    fn test_check_identifier() {
+        assert!(check_identifier("atcoder_2021").is_ok());
+        assert!(check_identifier("").is_err());
+        assert!(check_identifier("atcoder.submissions").is_err());
+        assert!(check_identifier("submissions; DROP TABLE submissions").is_err());
+        assert!(check_identifier("`submissions`").is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! The compression of the files written by the export and the dump commands, which is chosen by
+//! `--compress`, since some consumers prefer the fast decompression and others the small files.
+
+use anyhow::{anyhow, bail, Result};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const DEFAULT_GZIP_LEVEL: i32 = 6;
+const DEFAULT_ZSTD_LEVEL: i32 = 3;
+const MAX_GZIP_LEVEL: i32 = 9;
+const MAX_ZSTD_LEVEL: i32 = 22;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressionFormat {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// All the formats, e.g. to find the file written with any of them.
+pub const FORMATS: [CompressionFormat; 3] = [
+    CompressionFormat::None,
+    CompressionFormat::Gzip,
+    CompressionFormat::Zstd,
+];
+
+impl FromStr for CompressionFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "none" => Ok(CompressionFormat::None),
+            "gzip" => Ok(CompressionFormat::Gzip),
+            "zstd" => Ok(CompressionFormat::Zstd),
+            _ => Err(anyhow!("Unknown compression: {}", s)),
+        }
+    }
+}
+
+impl CompressionFormat {
+    /// The format of the file name ending with `.gz` or `.zst`.
+    pub fn from_extension(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gz") => CompressionFormat::Gzip,
+            Some("zst") => CompressionFormat::Zstd,
+            _ => CompressionFormat::None,
+        }
+    }
+
+    /// The format of the data starting with `head`, which is detected by the magic number.
+    pub fn detect(head: &[u8]) -> Self {
+        if head.starts_with(&GZIP_MAGIC) {
+            CompressionFormat::Gzip
+        } else if head.starts_with(&ZSTD_MAGIC) {
+            CompressionFormat::Zstd
+        } else {
+            CompressionFormat::None
+        }
+    }
+
+    /// The extension appended to the names of the compressed files, e.g. `a.json.gz`.
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            CompressionFormat::None => None,
+            CompressionFormat::Gzip => Some("gz"),
+            CompressionFormat::Zstd => Some("zst"),
+        }
+    }
+
+    /// The value of `Content-Encoding` of the compressed data.
+    pub fn content_encoding(self) -> Option<&'static str> {
+        match self {
+            CompressionFormat::None => None,
+            CompressionFormat::Gzip => Some("gzip"),
+            CompressionFormat::Zstd => Some("zstd"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compression {
+    pub format: CompressionFormat,
+    /// 0 to 9 for gzip and 1 to 22 for zstd, or `None` for the default of the format.
+    pub level: Option<i32>,
+}
+
+impl Compression {
+    pub const NONE: Compression = Compression {
+        format: CompressionFormat::None,
+        level: None,
+    };
+
+    pub fn new(format: CompressionFormat, level: Option<i32>) -> Result<Self> {
+        let levels = match format {
+            CompressionFormat::None => None,
+            CompressionFormat::Gzip => Some((0, MAX_GZIP_LEVEL)),
+            CompressionFormat::Zstd => Some((1, MAX_ZSTD_LEVEL)),
+        };
+        match (level, levels) {
+            (Some(_), None) => bail!("The compression level requires gzip or zstd"),
+            (Some(level), Some((min, max))) if level < min || level > max => bail!(
+                "The level of {:?} must be from {} to {}: {}",
+                format,
+                min,
+                max,
+                level
+            ),
+            _ => Ok(Self { format, level }),
+        }
+    }
+
+    /// The format with its default level.
+    pub fn of(format: CompressionFormat) -> Self {
+        Self {
+            format,
+            level: None,
+        }
+    }
+
+    /// Appends the extension of the format to the path unless it already ends with it, e.g.
+    /// `problems.json.gz` for both `problems.json` and `problems.json.gz`.
+    pub fn with_extension(self, path: &Path) -> PathBuf {
+        match self.format.extension() {
+            Some(_) if CompressionFormat::from_extension(path) == self.format => path.to_owned(),
+            Some(extension) => {
+                let mut path = path.as_os_str().to_owned();
+                path.push(".");
+                path.push(extension);
+                PathBuf::from(path)
+            }
+            None => path.to_owned(),
+        }
+    }
+
+    pub fn writer<W: Write>(self, writer: W) -> io::Result<CompressedWriter<W>> {
+        let writer = match self.format {
+            CompressionFormat::None => CompressedWriter::Plain(writer),
+            CompressionFormat::Gzip => {
+                let level = self.level.unwrap_or(DEFAULT_GZIP_LEVEL) as u32;
+                CompressedWriter::Gzip(GzEncoder::new(writer, flate2::Compression::new(level)))
+            }
+            CompressionFormat::Zstd => {
+                let level = self.level.unwrap_or(DEFAULT_ZSTD_LEVEL);
+                CompressedWriter::Zstd(zstd::Encoder::new(writer, level)?)
+            }
+        };
+        Ok(writer)
+    }
+
+    /// Compresses the data. The output is the same for the same data, since the gzip header has
+    /// no timestamp, so it can be compared with the previous output.
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        if self.format == CompressionFormat::None {
+            return Ok(data.to_vec());
+        }
+        let mut writer = self.writer(Vec::new())?;
+        writer.write_all(data)?;
+        writer.finish()
+    }
+}
+
+/// A writer compressed by one of the formats.
+pub enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Writes the end of the compressed stream, and returns the inner writer.
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            CompressedWriter::Plain(writer) => Ok(writer),
+            CompressedWriter::Gzip(encoder) => encoder.finish(),
+            CompressedWriter::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(writer) => writer.write(buf),
+            CompressedWriter::Gzip(encoder) => encoder.write(buf),
+            CompressedWriter::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            CompressedWriter::Plain(writer) => writer.flush(),
+            CompressedWriter::Gzip(encoder) => encoder.flush(),
+            CompressedWriter::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Opens the file decompressed by the format detected from its content, so that the files written
+/// with any `--compress` can be read regardless of their names.
+pub fn open_decompressed(path: &Path) -> io::Result<Box<dyn Read>> {
+    let reader = BufReader::new(File::open(path)?);
+    decompressed(reader)
+}
+
+fn decompressed<R: BufRead + 'static>(mut reader: R) -> io::Result<Box<dyn Read>> {
+    let reader: Box<dyn Read> = match CompressionFormat::detect(reader.fill_buf()?) {
+        CompressionFormat::None => Box::new(reader),
+        CompressionFormat::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        CompressionFormat::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+    };
+    Ok(reader)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const DATA: &[u8] = b"{\"id\":1}\n{\"id\":2}\n";
+
+    fn round_trip(compression: Compression) -> Vec<u8> {
+        let compressed = compression.compress(DATA).unwrap();
+        let mut output = Vec::new();
+        decompressed(Cursor::new(compressed))
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_eq!(round_trip(Compression::NONE), DATA);
+        for &format in &[CompressionFormat::Gzip, CompressionFormat::Zstd] {
+            assert_eq!(round_trip(Compression::of(format)), DATA);
+            let best = Compression::new(format, Some(9)).unwrap();
+            assert_eq!(round_trip(best), DATA);
+            assert_eq!(best.compress(DATA).unwrap(), best.compress(DATA).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Compression::new(CompressionFormat::Gzip, Some(10)).is_err());
+        assert!(Compression::new(CompressionFormat::Zstd, Some(0)).is_err());
+        assert!(Compression::new(CompressionFormat::Zstd, Some(22)).is_ok());
+        assert!(Compression::new(CompressionFormat::None, Some(1)).is_err());
+        assert_eq!(
+            Compression::new(CompressionFormat::None, None).unwrap(),
+            Compression::NONE
+        );
+    }
+
+    #[test]
+    fn test_extension() {
+        let path = Path::new("out/problems.json");
+        assert_eq!(
+            Compression::of(CompressionFormat::Gzip).with_extension(path),
+            Path::new("out/problems.json.gz")
+        );
+        assert_eq!(Compression::NONE.with_extension(path), path);
+        assert_eq!(
+            Compression::of(CompressionFormat::Zstd).with_extension(Path::new("backup.tar.zst")),
+            Path::new("backup.tar.zst")
+        );
+        assert_eq!(
+            CompressionFormat::from_extension(Path::new("submissions.jsonl.zst")),
+            CompressionFormat::Zstd
+        );
+        assert_eq!(
+            CompressionFormat::from_extension(path),
+            CompressionFormat::None
+        );
+    }
+}
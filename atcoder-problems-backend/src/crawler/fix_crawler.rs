@@ -1,4 +1,5 @@
 use crate::crawler::AtCoderFetcher;
+use crate::metrics;
 use anyhow::Result;
 use log::info;
 use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
@@ -49,9 +50,12 @@ where
         for (contest_id, minimum_id) in contests.into_iter() {
             for page in 1.. {
                 info!("Fetching from {}-{}", contest_id, page);
-                let (submissions, max_page) =
-                    self.fetcher.fetch_submissions(&contest_id, page).await;
-                self.db.update_submissions(&submissions).await?;
+                let (submissions, max_page) = self
+                    .fetcher
+                    .fetch_submissions(contest_id.as_str(), page)
+                    .await;
+                let rows = self.db.update_submissions(&submissions).await?;
+                metrics::add_rows_written(rows);
                 let all_old = submissions.iter().all(|s| s.id <= minimum_id);
                 if all_old || max_page == page {
                     break;
@@ -69,6 +73,7 @@ mod tests {
     use crate::crawler::utils::MockFetcher;
     use async_std::task::block_on;
     use async_trait::async_trait;
+    use sql_client::ids::{ContestId, UserId};
     use sql_client::models::Submission;
 
     const CURRENT_TIME: i64 = 100;
@@ -86,17 +91,17 @@ mod tests {
                     assert_eq!(from_second, CURRENT_TIME);
                     Ok(vec![
                         Submission {
-                            contest_id: "contest1".to_string(),
+                            contest_id: "contest1".into(),
                             id: 100,
                             ..Default::default()
                         },
                         Submission {
-                            contest_id: "contest1".to_string(),
+                            contest_id: "contest1".into(),
                             id: 200,
                             ..Default::default()
                         },
                         Submission {
-                            contest_id: "contest1".to_string(),
+                            contest_id: "contest1".into(),
                             id: 50,
                             ..Default::default()
                         },
@@ -105,7 +110,10 @@ mod tests {
                 _ => unreachable!(),
             }
         }
-        async fn get_user_submission_count(&self, _: &str) -> Result<i64> {
+        async fn get_user_submission_count(&self, _: &UserId) -> Result<i64> {
+            unimplemented!()
+        }
+        async fn get_contest_submission_stats(&self, _: &ContestId) -> Result<(i64, Option<i64>)> {
             unimplemented!()
         }
         async fn update_submissions(&self, _: &[Submission]) -> Result<usize> {
@@ -114,7 +122,7 @@ mod tests {
         async fn update_submission_count(&self) -> Result<()> {
             unimplemented!()
         }
-        async fn update_user_submission_count(&self, _: &str) -> Result<()> {
+        async fn update_user_submission_count(&self, _: &UserId) -> Result<()> {
             unimplemented!()
         }
         async fn update_delta_submission_count(&self, _: &[Submission]) -> Result<()> {
@@ -0,0 +1,187 @@
+use crate::crawler::AtCoderFetcher;
+use crate::rating::{
+    estimate_performances, next_rating, rated_ranks, rating_rule, unadjust_rating, RatingRule,
+};
+use anyhow::Result;
+use atcoder_client::AtCoderStandings;
+use sql_client::ids::UserId;
+use sql_client::live_performance::LivePerformanceClient;
+use sql_client::models::LivePerformance;
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::ContestWindow;
+use std::collections::BTreeMap;
+use std::{thread, time};
+
+const MAX_LIVE_CONTEST_DURATION_SECOND: i64 = 24 * 3600;
+
+pub struct LivePerformanceCrawler<C, F> {
+    db: C,
+    fetcher: F,
+    current_time_second: i64,
+}
+
+impl<C, F> LivePerformanceCrawler<C, F>
+where
+    C: SimpleClient + LivePerformanceClient + Sync,
+    F: AtCoderFetcher,
+{
+    pub fn new(db: C, fetcher: F, current_time_second: i64) -> Self {
+        Self {
+            db,
+            fetcher,
+            current_time_second,
+        }
+    }
+
+    pub async fn crawl(&self) -> Result<()> {
+        let contests = self.db.load_contests().await?;
+        let running_contests = contests
+            .into_iter()
+            .filter(|c| {
+                c.is_rated()
+                    && c.duration_second <= MAX_LIVE_CONTEST_DURATION_SECOND
+                    && ContestWindow::of(c).is_running(self.current_time_second)
+            })
+            .collect::<Vec<_>>();
+        log::info!(
+            "There are {} running rated contests.",
+            running_contests.len()
+        );
+
+        for contest in running_contests.into_iter() {
+            let rule = match rating_rule(&contest) {
+                Some(rule) => rule,
+                None => continue,
+            };
+            let standings = match self.fetcher.fetch_standings(contest.id.as_str()).await {
+                Ok(standings) => standings,
+                Err(e) => {
+                    log::error!("Failed to fetch standings of {}: {:?}", contest.id, e);
+                    continue;
+                }
+            };
+
+            let user_ids = standings
+                .standings_data
+                .iter()
+                .map(|e| UserId::from(e.user_screen_name.as_str()))
+                .collect::<Vec<_>>();
+            let predicted_ratings = self
+                .db
+                .load_predicted_ratings(&user_ids)
+                .await?
+                .into_iter()
+                .collect::<BTreeMap<_, _>>();
+
+            let performances = estimate_live_performances(
+                contest.id.as_str(),
+                &rule,
+                &standings,
+                &predicted_ratings,
+            );
+            log::info!(
+                "Estimated performances of {} participants in {}",
+                performances.len(),
+                contest.id
+            );
+            self.db
+                .update_live_performances(&contest.id, &performances)
+                .await?;
+            thread::sleep(time::Duration::from_millis(500));
+        }
+        Ok(())
+    }
+}
+
+fn estimate_live_performances(
+    contest_id: &str,
+    rule: &RatingRule,
+    standings: &AtCoderStandings,
+    predicted_ratings: &BTreeMap<String, f64>,
+) -> Vec<LivePerformance> {
+    let mut participants = standings
+        .standings_data
+        .iter()
+        .filter(|e| e.is_rated && e.total_result.count > 0)
+        .collect::<Vec<_>>();
+    participants.sort_by_key(|e| e.rank);
+
+    let average_performances = participants
+        .iter()
+        .map(|e| match predicted_ratings.get(&e.user_screen_name) {
+            Some(&rating) => rating,
+            None if e.competitions == 0 => rule.default_performance,
+            None => unadjust_rating(e.old_rating, e.competitions),
+        })
+        .collect::<Vec<_>>();
+
+    let ranks = rated_ranks(&participants.iter().map(|e| e.rank).collect::<Vec<_>>());
+
+    let performances = estimate_performances(&average_performances, &ranks, rule);
+    participants
+        .into_iter()
+        .zip(ranks)
+        .zip(performances)
+        .map(|((e, rank), performance)| LivePerformance {
+            contest_id: contest_id.to_owned(),
+            user_id: e.user_screen_name.clone(),
+            rank: rank as i64,
+            performance: performance.round() as i64,
+            old_rating: e.old_rating,
+            new_rating: next_rating(e.old_rating, e.competitions, performance).round() as i64,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atcoder_client::{AtCoderStandingsEntry, AtCoderStandingsResult};
+    use sql_client::models::Contest;
+
+    fn entry(user_id: &str, rank: u64, old_rating: i64, count: u64) -> AtCoderStandingsEntry {
+        AtCoderStandingsEntry {
+            rank,
+            user_screen_name: user_id.to_owned(),
+            is_rated: true,
+            old_rating,
+            competitions: 10,
+            total_result: AtCoderStandingsResult {
+                count,
+                score: 0,
+                elapsed: 0,
+                penalty: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn test_estimate_live_performances() {
+        let contest = Contest::new(
+            "abc200".to_owned(),
+            1_620_475_200,
+            6000,
+            String::new(),
+            " ~ 1999".to_owned(),
+        );
+        let rule = rating_rule(&contest).unwrap();
+        let standings = AtCoderStandings {
+            standings_data: vec![
+                entry("user3", 3, 1200, 1),
+                entry("user1", 1, 1200, 1),
+                entry("user2", 1, 1200, 1),
+                entry("retreated", 4, 1200, 0),
+            ],
+        };
+        let performances =
+            estimate_live_performances("abc200", &rule, &standings, &BTreeMap::new());
+        assert_eq!(performances.len(), 3);
+        assert_eq!(performances[0].rank, 1);
+        assert_eq!(performances[1].rank, 1);
+        assert_eq!(performances[2].rank, 3);
+        assert_eq!(performances[0].performance, performances[1].performance);
+        assert!(performances[0].performance > performances[2].performance);
+        assert!(performances[0].new_rating > performances[0].old_rating);
+        assert!(performances[2].new_rating < performances[2].old_rating);
+    }
+}
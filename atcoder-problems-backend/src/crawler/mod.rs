@@ -1,50 +1,141 @@
 mod fix_crawler;
+mod live_performance_crawler;
 mod problem_crawler;
+mod profile_crawler;
 mod recent_crawler;
+mod standings_crawler;
+mod user_crawler;
 pub(crate) mod utils;
+mod verifier;
 mod virtual_contest_crawler;
 mod whole_contest_crawler;
 
 pub use fix_crawler::FixCrawler;
+pub use live_performance_crawler::LivePerformanceCrawler;
 pub use problem_crawler::ProblemCrawler;
+pub use profile_crawler::ProfileCrawler;
 pub use recent_crawler::RecentCrawler;
+pub use standings_crawler::StandingsCrawler;
+pub use user_crawler::UserCrawler;
+pub use verifier::Verifier;
 pub use virtual_contest_crawler::VirtualContestCrawler;
 pub use whole_contest_crawler::WholeContestCrawler;
 
 use anyhow::Result;
 use async_trait::async_trait;
-use atcoder_client::{AtCoderClient, AtCoderProblem, AtCoderSubmission, ContestTypeSpecifier};
+use atcoder_client::parser::ParseError;
+use atcoder_client::{
+    AtCoderClient, AtCoderContestDetail, AtCoderProblem, AtCoderStandings, AtCoderSubmission,
+    AtCoderSubmissionListResponse, AtCoderUserProfile, ContestTypeSpecifier,
+};
+use chrono::Utc;
 use log::info;
-use sql_client::models::{Contest, ContestProblem, Problem, Submission};
+use sql_client::activity::ParseFailure;
+use sql_client::models::{Contest, ContestProblem, Problem, Submission, UserProfile};
+use std::sync::Mutex;
+
+use crate::metrics;
+
+/// The parse failures which are not saved yet, since the fetchers have no database.
+static PARSE_FAILURES: Mutex<Vec<ParseFailure>> = Mutex::new(Vec::new());
+
+/// Records the error if AtCoder served the page but it could not be parsed.
+fn record_parse_failure<T>(result: Result<T>) -> Result<T> {
+    if let Err(e) = &result {
+        if let Some(parse_error) = e.downcast_ref::<ParseError>() {
+            let failure = ParseFailure {
+                url: parse_error.url.clone(),
+                error: format!("{:#}", e),
+                epoch_second: Utc::now().timestamp(),
+            };
+            PARSE_FAILURES
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(failure);
+        }
+    }
+    result
+}
+
+/// Returns the parse failures recorded since the last call, to be saved in `parse_failures`.
+pub fn take_parse_failures() -> Vec<ParseFailure> {
+    let mut failures = PARSE_FAILURES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    std::mem::take(&mut *failures)
+}
 
 #[async_trait]
 pub trait AtCoderFetcher {
+    /// Returns an empty page if the page can not be fetched after the retries.
     async fn fetch_submissions(&self, contest_id: &str, page: u32) -> (Vec<Submission>, u32);
+    /// The same as `fetch_submissions`, but fails instead of returning an empty page.
+    async fn try_fetch_submissions(
+        &self,
+        contest_id: &str,
+        page: u32,
+    ) -> Result<(Vec<Submission>, u32)>;
+    async fn fetch_user_submissions(
+        &self,
+        contest_id: &str,
+        user_id: &str,
+        page: u32,
+    ) -> Result<(Vec<Submission>, u32)>;
     async fn fetch_contests(&self, spf: ContestTypeSpecifier) -> Result<Vec<Contest>>;
     async fn fetch_problems(&self, contest_id: &str)
         -> Result<(Vec<Problem>, Vec<ContestProblem>)>;
+    async fn fetch_standings(&self, contest_id: &str) -> Result<AtCoderStandings>;
+    async fn fetch_contest_detail(&self, contest_id: &str) -> Result<AtCoderContestDetail>;
+    /// Returns `None` if the user does not exist.
+    async fn fetch_user_profile(&self, user_id: &str) -> Result<Option<UserProfile>>;
 }
 
 #[async_trait]
 impl AtCoderFetcher for AtCoderClient {
     async fn fetch_submissions(&self, contest_id: &str, page: u32) -> (Vec<Submission>, u32) {
-        let (submissions, max_page) = retry_fetch_submissions(self, 9, contest_id, page).await;
-        let submissions = submissions
+        // The error is already logged on each retry.
+        self.try_fetch_submissions(contest_id, page)
+            .await
+            .unwrap_or_else(|_| (Vec::new(), 0))
+    }
+
+    async fn try_fetch_submissions(
+        &self,
+        contest_id: &str,
+        page: u32,
+    ) -> Result<(Vec<Submission>, u32)> {
+        let response =
+            record_parse_failure(retry_fetch_submissions(self, 9, contest_id, page).await)?;
+        metrics::add_page_fetched();
+        let submissions = response
+            .submissions
             .into_iter()
-            .map(|s| Submission {
-                id: s.id as i64,
-                epoch_second: s.epoch_second as i64,
-                problem_id: s.problem_id,
-                contest_id: s.contest_id,
-                user_id: s.user_id,
-                language: s.language,
-                point: s.point,
-                length: s.length as i32,
-                result: s.result,
-                execution_time: s.execution_time.map(|t| t as i32),
-            })
+            .map(convert_submission)
+            .collect();
+        Ok((submissions, response.max_page))
+    }
+
+    async fn fetch_user_submissions(
+        &self,
+        contest_id: &str,
+        user_id: &str,
+        page: u32,
+    ) -> Result<(Vec<Submission>, u32)> {
+        info!(
+            "Fetching submissions of {} in {} page-{}",
+            user_id, contest_id, page
+        );
+        let response = record_parse_failure(
+            self.fetch_atcoder_user_submission_list(contest_id, user_id, page)
+                .await,
+        )?;
+        metrics::add_page_fetched();
+        let submissions = response
+            .submissions
+            .into_iter()
+            .map(convert_submission)
             .collect();
-        (submissions, max_page)
+        Ok((submissions, response.max_page))
     }
 
     async fn fetch_contests(&self, spf: ContestTypeSpecifier) -> Result<Vec<Contest>> {
@@ -60,15 +151,18 @@ impl AtCoderFetcher for AtCoderClient {
             }
         };
 
-        let contests = self.fetch_atcoder_contests(spf).await?;
+        let contests = record_parse_failure(self.fetch_atcoder_contests(spf).await)?;
+        metrics::add_page_fetched();
         let contests = contests
             .into_iter()
-            .map(|c| Contest {
-                id: c.id,
-                start_epoch_second: c.start_epoch_second as i64,
-                duration_second: c.duration_second as i64,
-                title: c.title,
-                rate_change: c.rate_change,
+            .map(|c| {
+                Contest::new(
+                    c.id,
+                    c.start_epoch_second as i64,
+                    c.duration_second as i64,
+                    c.title,
+                    c.rate_change,
+                )
             })
             .collect::<Vec<_>>();
         Ok(contests)
@@ -79,7 +173,8 @@ impl AtCoderFetcher for AtCoderClient {
         contest_id: &str,
     ) -> Result<(Vec<Problem>, Vec<ContestProblem>)> {
         info!("Fetching problems from {} ...", contest_id);
-        let problems = self.fetch_problem_list(contest_id).await?;
+        let problems = record_parse_failure(self.fetch_problem_list(contest_id).await)?;
+        metrics::add_page_fetched();
         let problems = problems
             .into_iter()
             .map(convert_problem)
@@ -93,6 +188,27 @@ impl AtCoderFetcher for AtCoderClient {
             .collect::<Vec<_>>();
         Ok((problems, contest_problem))
     }
+
+    async fn fetch_standings(&self, contest_id: &str) -> Result<AtCoderStandings> {
+        info!("Fetching standings of {} ...", contest_id);
+        let standings = self.fetch_atcoder_standings(contest_id).await?;
+        metrics::add_page_fetched();
+        Ok(standings)
+    }
+
+    async fn fetch_contest_detail(&self, contest_id: &str) -> Result<AtCoderContestDetail> {
+        info!("Fetching the detail of {} ...", contest_id);
+        let detail = record_parse_failure(self.fetch_atcoder_contest_detail(contest_id).await)?;
+        metrics::add_page_fetched();
+        Ok(detail)
+    }
+
+    async fn fetch_user_profile(&self, user_id: &str) -> Result<Option<UserProfile>> {
+        info!("Fetching the profile of {} ...", user_id);
+        let profile = record_parse_failure(self.fetch_atcoder_user_profile(user_id).await)?;
+        metrics::add_page_fetched();
+        Ok(profile.map(convert_user_profile))
+    }
 }
 
 async fn retry_fetch_submissions(
@@ -100,15 +216,23 @@ async fn retry_fetch_submissions(
     retry_count: usize,
     contest_id: &str,
     page: u32,
-) -> (Vec<AtCoderSubmission>, u32) {
+) -> Result<AtCoderSubmissionListResponse> {
     let mut sleep_second = 1;
-    for _ in 0..retry_count {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
         match client
             .fetch_atcoder_submission_list(contest_id, Some(page))
             .await
         {
             Ok(response) => {
-                return (response.submissions, response.max_page);
+                return Ok(response);
+            }
+            Err(e) if attempts >= retry_count => {
+                return Err(e.context(format!(
+                    "Failed to fetch {} page-{} {} times",
+                    contest_id, page, retry_count
+                )));
             }
             Err(e) => {
                 log::error!("Error when fetching {} {}: {:?} ", contest_id, page, e);
@@ -118,14 +242,42 @@ async fn retry_fetch_submissions(
             }
         }
     }
-    (Vec::new(), 0)
+}
+
+fn convert_submission(s: AtCoderSubmission) -> Submission {
+    Submission {
+        id: s.id as i64,
+        epoch_second: s.epoch_second as i64,
+        problem_id: s.problem_id.into(),
+        contest_id: s.contest_id.into(),
+        user_id: s.user_id.into(),
+        language: s.language,
+        point: s.point,
+        length: s.length as i32,
+        result: s.result,
+        execution_time: s.execution_time.map(|t| t as i32),
+    }
+}
+
+fn convert_user_profile(p: AtCoderUserProfile) -> UserProfile {
+    UserProfile {
+        user_id: p.user_id,
+        rating: p.rating,
+        highest_rating: p.highest_rating,
+        affiliation: p.affiliation,
+        country: p.country,
+        birth_year_bucket: p.birth_year.map(|year| year - year.rem_euclid(10)),
+        rated_matches: p.rated_matches,
+    }
 }
 
 fn convert_problem(p: AtCoderProblem) -> Problem {
     Problem {
-        id: p.id,
-        contest_id: p.contest_id,
-        title: p.position + ". " + p.title.as_str(),
+        id: p.id.into(),
+        contest_id: p.contest_id.into(),
+        title: format!("{}. {}", p.position, p.title),
+        problem_index: p.position,
+        name: p.title,
     }
 }
 
@@ -133,6 +285,27 @@ fn convert_problem(p: AtCoderProblem) -> Problem {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_record_parse_failure() {
+        take_parse_failures();
+        let parse_error = ParseError {
+            url: "https://atcoder.jp/contests/abc001/tasks".to_owned(),
+        };
+        let result: Result<()> = Err(anyhow::anyhow!("no table").context(parse_error));
+        assert!(record_parse_failure(result).is_err());
+        assert!(record_parse_failure::<()>(Err(anyhow::anyhow!("timeout"))).is_err());
+        assert!(record_parse_failure(Ok(())).is_ok());
+
+        let failures = take_parse_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].url, "https://atcoder.jp/contests/abc001/tasks");
+        assert_eq!(
+            failures[0].error,
+            "Failed to parse https://atcoder.jp/contests/abc001/tasks: no table"
+        );
+        assert!(take_parse_failures().is_empty());
+    }
+
     #[test]
     fn test_convert_problem() {
         let p = AtCoderProblem {
@@ -142,8 +315,29 @@ mod tests {
             position: "A".to_owned(),
         };
         let p = convert_problem(p);
-        assert_eq!(p.id, "id".to_owned());
-        assert_eq!(p.contest_id, "contest_id".to_owned());
+        assert_eq!(p.id.as_str(), "id");
+        assert_eq!(p.contest_id.as_str(), "contest_id");
         assert_eq!(p.title, "A. title".to_owned());
+        assert_eq!(p.problem_index, "A".to_owned());
+        assert_eq!(p.name, "title".to_owned());
+    }
+
+    #[test]
+    fn test_convert_user_profile() {
+        let p = AtCoderUserProfile {
+            user_id: "user".to_owned(),
+            rating: Some(1200),
+            birth_year: Some(1995),
+            ..Default::default()
+        };
+        let p = convert_user_profile(p);
+        assert_eq!(p.rating, Some(1200));
+        assert_eq!(p.birth_year_bucket, Some(1990));
+
+        let p = convert_user_profile(AtCoderUserProfile {
+            birth_year: Some(2000),
+            ..Default::default()
+        });
+        assert_eq!(p.birth_year_bucket, Some(2000));
     }
 }
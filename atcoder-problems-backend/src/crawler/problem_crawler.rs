@@ -1,4 +1,5 @@
 use crate::crawler::AtCoderFetcher;
+use crate::metrics;
 use anyhow::Result;
 use atcoder_client::ContestTypeSpecifier;
 use sql_client::contest_problem::ContestProblemClient;
@@ -7,6 +8,10 @@ use sql_client::simple_client::SimpleClient;
 use std::collections::BTreeSet;
 use std::{thread, time};
 
+/// The number of the contests whose rules are crawled in a run, so that a run after the rules
+/// are added to many contests does not take too long.
+const MAX_CONTESTS_TO_CRAWL_RULES: usize = 50;
+
 pub struct ProblemCrawler<C, F> {
     db: C,
     fetcher: F,
@@ -24,19 +29,41 @@ where
         log::info!("Starting...");
         let mut contests = Vec::new();
 
-        match self.fetcher.fetch_contests(ContestTypeSpecifier::Permanent).await {
-            Ok(c) => { contests.extend(c); }
-            Err(e) => { log::error!("{:?}", e); }
+        match self
+            .fetcher
+            .fetch_contests(ContestTypeSpecifier::Permanent)
+            .await
+        {
+            Ok(c) => {
+                contests.extend(c);
+            }
+            Err(e) => {
+                log::error!("{:?}", e);
+                metrics::add_failure();
+            }
         }
         thread::sleep(time::Duration::from_millis(500));
 
-        match self.fetcher.fetch_contests(ContestTypeSpecifier::Hidden).await {
-            Ok(c) => { contests.extend(c); }
-            Err(e) => { log::error!("{:?}", e); }
+        match self
+            .fetcher
+            .fetch_contests(ContestTypeSpecifier::Hidden)
+            .await
+        {
+            Ok(c) => {
+                contests.extend(c);
+            }
+            Err(e) => {
+                log::error!("{:?}", e);
+                metrics::add_failure();
+            }
         }
-        
+
         for page in 1.. {
-            match self.fetcher.fetch_contests(ContestTypeSpecifier::Normal{ page }).await {
+            match self
+                .fetcher
+                .fetch_contests(ContestTypeSpecifier::Normal { page })
+                .await
+            {
                 Ok(c) => {
                     if c.is_empty() {
                         break;
@@ -45,6 +72,7 @@ where
                 }
                 Err(e) => {
                     log::error!("{:?}", e);
+                    metrics::add_failure();
                     break;
                 }
             }
@@ -52,7 +80,8 @@ where
         }
 
         log::info!("There are {} contests.", contests.len());
-        self.db.insert_contests(&contests).await?;
+        let rows = self.db.insert_contests(&contests).await?;
+        metrics::add_rows_written(rows);
 
         let contests = self.db.load_contests().await?;
         let problems = self.db.load_problems().await?;
@@ -63,13 +92,45 @@ where
 
         for contest in no_problem_contests.into_iter() {
             log::info!("Crawling problems of {}...", contest.id);
-            match self.fetcher.fetch_problems(&contest.id).await {
+            match self.fetcher.fetch_problems(contest.id.as_str()).await {
                 Ok((problems, contest_problem)) => {
-                    self.db.insert_problems(&problems).await?;
+                    let rows = self.db.insert_problems(&problems).await?;
+                    metrics::add_rows_written(rows);
                     self.db.insert_contest_problem(&contest_problem).await?;
                 }
                 Err(e) => {
                     log::error!("{:?}", e);
+                    metrics::add_failure();
+                }
+            }
+            thread::sleep(time::Duration::from_millis(500));
+        }
+
+        let mut no_rule_contests = contests
+            .iter()
+            .filter(|c| c.penalty_minutes.is_none())
+            .collect::<Vec<_>>();
+        no_rule_contests.sort_by_key(|c| std::cmp::Reverse(c.start_epoch_second));
+        for contest in no_rule_contests
+            .into_iter()
+            .take(MAX_CONTESTS_TO_CRAWL_RULES)
+        {
+            log::info!("Crawling the rules of {}...", contest.id);
+            match self.fetcher.fetch_contest_detail(contest.id.as_str()).await {
+                Ok(detail) => {
+                    // The contests whose pages do not show the penalty are saved with 0,
+                    // so that they are not crawled again.
+                    let penalty_minutes = detail.penalty_minutes.unwrap_or_else(|| {
+                        log::warn!("{} has no penalty in its page", contest.id);
+                        0
+                    });
+                    self.db
+                        .update_contest_rules(&contest.id, penalty_minutes, detail.allows_late_join)
+                        .await?;
+                }
+                Err(e) => {
+                    log::error!("{:?}", e);
+                    metrics::add_failure();
                 }
             }
             thread::sleep(time::Duration::from_millis(500));
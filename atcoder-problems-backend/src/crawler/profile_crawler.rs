@@ -0,0 +1,161 @@
+use crate::crawler::AtCoderFetcher;
+use crate::metrics;
+use anyhow::Result;
+use log::{info, warn};
+use sql_client::models::UserProfile;
+use sql_client::user_profile::UserProfileClient;
+use std::{thread, time};
+
+/// The number of the profiles inserted at once.
+const PROFILES_PER_INSERT: usize = 100;
+
+/// Crawls the profiles of the users whose profiles are missing or the oldest.
+pub struct ProfileCrawler<C, F> {
+    db: C,
+    fetcher: F,
+    count: i64,
+}
+
+impl<C, F> ProfileCrawler<C, F>
+where
+    C: UserProfileClient,
+    F: AtCoderFetcher,
+{
+    pub fn new(db: C, fetcher: F, count: i64) -> Self {
+        Self { db, fetcher, count }
+    }
+
+    pub async fn crawl(&self) -> Result<()> {
+        let user_ids = self.db.load_user_ids_to_crawl_profiles(self.count).await?;
+        info!("Crawling the profiles of {} users", user_ids.len());
+
+        let mut profiles = Vec::new();
+        for user_id in user_ids.iter() {
+            match self.fetcher.fetch_user_profile(user_id).await {
+                Ok(Some(profile)) => profiles.push(profile),
+                Ok(None) => {
+                    // The empty profile is stored, so that the user is crawled again later.
+                    warn!("{} does not exist", user_id);
+                    profiles.push(UserProfile {
+                        user_id: user_id.clone(),
+                        ..Default::default()
+                    });
+                }
+                Err(e) => {
+                    log::error!("Failed to fetch the profile of {}: {:?}", user_id, e);
+                    metrics::add_failure();
+                }
+            }
+            if profiles.len() >= PROFILES_PER_INSERT {
+                metrics::add_rows_written(self.db.insert_user_profiles(&profiles).await?);
+                profiles.clear();
+            }
+            thread::sleep(time::Duration::from_millis(500));
+        }
+        metrics::add_rows_written(self.db.insert_user_profiles(&profiles).await?);
+        info!("Finished crawling the profiles");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_std::task::block_on;
+    use async_trait::async_trait;
+    use atcoder_client::{AtCoderContestDetail, AtCoderStandings, ContestTypeSpecifier};
+    use sql_client::ids::UserId;
+    use sql_client::models::{Contest, ContestProblem, Problem, Submission};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockDB(Mutex<Vec<UserProfile>>);
+
+    #[async_trait]
+    impl UserProfileClient for MockDB {
+        async fn insert_user_profiles(&self, profiles: &[UserProfile]) -> Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(profiles);
+            Ok(profiles.len())
+        }
+
+        async fn get_user_profile(&self, _: &UserId) -> Result<Option<UserProfile>> {
+            unimplemented!()
+        }
+
+        async fn load_ratings_with_competitions(
+            &self,
+            _: &[UserId],
+        ) -> Result<Vec<(String, i64, i64)>> {
+            unimplemented!()
+        }
+
+        async fn load_user_ids_to_crawl_profiles(&self, count: i64) -> Result<Vec<String>> {
+            let user_ids = vec!["user1".to_owned(), "deleted".to_owned(), "error".to_owned()];
+            Ok(user_ids.into_iter().take(count as usize).collect())
+        }
+    }
+
+    struct MockFetcher;
+
+    #[async_trait]
+    impl AtCoderFetcher for MockFetcher {
+        async fn fetch_submissions(&self, _: &str, _: u32) -> (Vec<Submission>, u32) {
+            unimplemented!()
+        }
+
+        async fn try_fetch_submissions(&self, _: &str, _: u32) -> Result<(Vec<Submission>, u32)> {
+            unimplemented!()
+        }
+
+        async fn fetch_user_submissions(
+            &self,
+            _: &str,
+            _: &str,
+            _: u32,
+        ) -> Result<(Vec<Submission>, u32)> {
+            unimplemented!()
+        }
+
+        async fn fetch_contests(&self, _: ContestTypeSpecifier) -> Result<Vec<Contest>> {
+            unimplemented!()
+        }
+
+        async fn fetch_problems(&self, _: &str) -> Result<(Vec<Problem>, Vec<ContestProblem>)> {
+            unimplemented!()
+        }
+
+        async fn fetch_standings(&self, _: &str) -> Result<AtCoderStandings> {
+            unimplemented!()
+        }
+
+        async fn fetch_contest_detail(&self, _: &str) -> Result<AtCoderContestDetail> {
+            unimplemented!()
+        }
+
+        async fn fetch_user_profile(&self, user_id: &str) -> Result<Option<UserProfile>> {
+            match user_id {
+                "deleted" => Ok(None),
+                "error" => Err(anyhow::anyhow!("Failed to parse html.")),
+                _ => Ok(Some(UserProfile {
+                    user_id: user_id.to_owned(),
+                    rating: Some(1200),
+                    ..Default::default()
+                })),
+            }
+        }
+    }
+
+    #[test]
+    fn test_profile_crawler() {
+        let db = MockDB::default();
+        let crawler = ProfileCrawler::new(db, MockFetcher, 10);
+        block_on(crawler.crawl()).unwrap();
+
+        let profiles = crawler.db.0.lock().unwrap();
+        assert_eq!(profiles.len(), 2);
+        assert_eq!(profiles[0].user_id, "user1");
+        assert_eq!(profiles[0].rating, Some(1200));
+        assert_eq!(profiles[1].user_id, "deleted");
+        assert_eq!(profiles[1].rating, None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::crawler::AtCoderFetcher;
+use crate::metrics;
 use anyhow::Result;
 
 use log::info;
@@ -26,8 +27,10 @@ where
         for contest in contests.into_iter() {
             for page in 1.. {
                 info!("Crawling {}-{} ...", contest.id, page);
-                let (submissions, max_page) =
-                    self.fetcher.fetch_submissions(&contest.id, page).await;
+                let (submissions, max_page) = self
+                    .fetcher
+                    .fetch_submissions(contest.id.as_str(), page)
+                    .await;
                 if submissions.is_empty() {
                     info!("There is no submission on {}-{}", contest.id, page);
                     break;
@@ -35,7 +38,8 @@ where
 
                 let min_id = submissions.iter().map(|s| s.id).min().unwrap();
                 let exists = self.db.count_stored_submissions(&[min_id]).await? != 0;
-                self.db.update_submissions(&submissions).await?;
+                let rows = self.db.update_submissions(&submissions).await?;
+                metrics::add_rows_written(rows);
                 thread::sleep(time::Duration::from_millis(200));
 
                 if exists {
@@ -50,6 +54,7 @@ where
                     break;
                 }
             }
+            metrics::add_contest_processed(contest.id.as_str());
         }
 
         info!("Finished");
@@ -63,6 +68,7 @@ mod tests {
     use crate::crawler::utils::MockFetcher;
     use async_std::task::block_on;
     use async_trait::async_trait;
+    use sql_client::ids::{ContestId, UserId};
     use sql_client::models::{Contest, Problem, Submission};
     use sql_client::submission_client::SubmissionRequest;
 
@@ -100,7 +106,14 @@ mod tests {
                     _ => unimplemented!(),
                 }
             }
-            async fn get_user_submission_count(&self, _: &str) -> Result<i64> {
+            async fn get_user_submission_count(&self, _: &UserId) -> Result<i64> {
+                unimplemented!()
+            }
+
+            async fn get_contest_submission_stats(
+                &self,
+                _: &ContestId,
+            ) -> Result<(i64, Option<i64>)> {
                 unimplemented!()
             }
 
@@ -111,7 +124,7 @@ mod tests {
             async fn update_submission_count(&self) -> Result<()> {
                 unimplemented!()
             }
-            async fn update_user_submission_count(&self, _: &str) -> Result<()> {
+            async fn update_user_submission_count(&self, _: &UserId) -> Result<()> {
                 unimplemented!()
             }
 
@@ -132,10 +145,16 @@ mod tests {
             }
             async fn load_contests(&self) -> Result<Vec<Contest>> {
                 Ok(vec![Contest {
-                    id: "contest".to_string(),
+                    id: "contest".into(),
                     ..Default::default()
                 }])
             }
+            async fn update_derived_contest_columns(&self) -> Result<usize> {
+                unimplemented!()
+            }
+            async fn update_contest_rules(&self, _: &ContestId, _: i64, _: bool) -> Result<()> {
+                unimplemented!()
+            }
         }
 
         let crawler = RecentCrawler::new(MockDB, fetcher);
@@ -0,0 +1,173 @@
+use crate::crawler::AtCoderFetcher;
+use crate::metrics;
+use anyhow::Result;
+use atcoder_client::AtCoderStandings;
+use sql_client::contest_standings::ContestStandingsClient;
+use sql_client::models::{Contest, StandingsEntry};
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::ContestWindow;
+use std::collections::BTreeMap;
+use std::{thread, time};
+
+const MAX_CRAWLED_CONTEST_DURATION_SECOND: i64 = 24 * 3600;
+
+/// The standings can change after a contest ends, e.g. by the rejudges and the removal of cheaters.
+const FINAL_STANDINGS_CRAWL_SECOND: i64 = 24 * 3600;
+
+pub struct StandingsCrawler<C, F> {
+    db: C,
+    fetcher: F,
+    current_time_second: i64,
+}
+
+impl<C, F> StandingsCrawler<C, F>
+where
+    C: SimpleClient + ContestStandingsClient + Sync,
+    F: AtCoderFetcher,
+{
+    pub fn new(db: C, fetcher: F, current_time_second: i64) -> Self {
+        Self {
+            db,
+            fetcher,
+            current_time_second,
+        }
+    }
+
+    /// Fetches the standings of the running contests, and the ones which ended recently.
+    pub async fn crawl(&self) -> Result<()> {
+        let updated = self
+            .db
+            .load_standings_updated_epoch_seconds()
+            .await?
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        let contests = self
+            .db
+            .load_contests()
+            .await?
+            .into_iter()
+            .filter(|c| {
+                needs_update(
+                    c,
+                    updated.get(c.id.as_str()).copied(),
+                    self.current_time_second,
+                )
+            })
+            .collect::<Vec<_>>();
+        log::info!("Fetching standings of {} contests.", contests.len());
+
+        for contest in contests.into_iter() {
+            let standings = match self.fetcher.fetch_standings(contest.id.as_str()).await {
+                Ok(standings) => standings,
+                Err(e) => {
+                    log::error!("Failed to fetch standings of {}: {:?}", contest.id, e);
+                    metrics::add_failure();
+                    continue;
+                }
+            };
+            let standings = convert_standings(standings);
+            self.db
+                .update_contest_standings(&contest.id, &standings, self.current_time_second)
+                .await?;
+            metrics::add_rows_written(standings.len());
+            thread::sleep(time::Duration::from_millis(500));
+        }
+        Ok(())
+    }
+}
+
+/// Running contests are refreshed every time. Ended contests are refreshed until
+/// the standings are fetched `FINAL_STANDINGS_CRAWL_SECOND` seconds after the end.
+fn needs_update(contest: &Contest, updated_epoch_second: Option<i64>, now: i64) -> bool {
+    let window = ContestWindow::of(contest);
+    if contest.duration_second > MAX_CRAWLED_CONTEST_DURATION_SECOND
+        || !window.has_started(now)
+        || window.end_epoch_second + FINAL_STANDINGS_CRAWL_SECOND < now
+    {
+        return false;
+    }
+    !window.has_ended(now)
+        || updated_epoch_second.map_or(true, |updated| !window.has_ended(updated))
+}
+
+fn convert_standings(standings: AtCoderStandings) -> Vec<StandingsEntry> {
+    standings
+        .standings_data
+        .into_iter()
+        .map(|e| StandingsEntry {
+            user_id: e.user_screen_name,
+            rank: e.rank as i64,
+            is_rated: e.is_rated,
+            old_rating: e.old_rating,
+            solved_count: e.total_result.count as i64,
+            // AtCoder gives the score in hundredths of a point, and the elapsed time in nanoseconds.
+            point: e.total_result.score as f64 / 100.0,
+            elapsed_second: e.total_result.elapsed / 1_000_000_000,
+            penalty: e.total_result.penalty as i64,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use atcoder_client::{AtCoderStandingsEntry, AtCoderStandingsResult};
+
+    fn contest(start_epoch_second: i64, duration_second: i64) -> Contest {
+        Contest {
+            id: "abc200".into(),
+            start_epoch_second,
+            duration_second,
+            title: "".to_owned(),
+            rate_change: " ~ 1999".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_needs_update() {
+        let c = contest(1000, 6000);
+        assert!(!needs_update(&c, None, 999));
+        assert!(needs_update(&c, None, 1000));
+        assert!(needs_update(&c, Some(6000), 6999));
+        assert!(needs_update(&c, Some(6999), 7100));
+        assert!(!needs_update(&c, Some(7000), 7100));
+        assert!(!needs_update(&c, None, 7000 + 24 * 3600 + 1));
+
+        let c = contest(1000, 10 * 24 * 3600);
+        assert!(!needs_update(&c, None, 2000));
+    }
+
+    #[test]
+    fn test_convert_standings() {
+        let standings = AtCoderStandings {
+            standings_data: vec![AtCoderStandingsEntry {
+                rank: 1,
+                user_screen_name: "tourist".to_owned(),
+                is_rated: true,
+                old_rating: 3800,
+                competitions: 50,
+                total_result: AtCoderStandingsResult {
+                    count: 6,
+                    score: 210000,
+                    elapsed: 600_000_000_000,
+                    penalty: 1,
+                },
+            }],
+        };
+        let standings = convert_standings(standings);
+        assert_eq!(
+            standings,
+            vec![StandingsEntry {
+                user_id: "tourist".to_owned(),
+                rank: 1,
+                is_rated: true,
+                old_rating: 3800,
+                solved_count: 6,
+                point: 2100.0,
+                elapsed_second: 600,
+                penalty: 1,
+            }]
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use crate::crawler::AtCoderFetcher;
+use crate::metrics;
+use anyhow::{Context, Result};
+use log::info;
+use sql_client::submission_client::SubmissionClient;
+use std::{thread, time};
+
+/// Re-crawls the submissions of a user in the given contests, e.g. when the user reports
+/// that some of their submissions are missing.
+pub struct UserCrawler<C, F> {
+    db: C,
+    fetcher: F,
+    user_id: String,
+    contest_ids: Vec<String>,
+}
+
+impl<C, F> UserCrawler<C, F>
+where
+    C: SubmissionClient,
+    F: AtCoderFetcher,
+{
+    pub fn new(db: C, fetcher: F, user_id: &str, contest_ids: Vec<String>) -> Self {
+        Self {
+            db,
+            fetcher,
+            user_id: user_id.to_owned(),
+            contest_ids,
+        }
+    }
+
+    pub async fn crawl(&self) -> Result<()> {
+        for contest_id in self.contest_ids.iter() {
+            for page in 1.. {
+                let (submissions, max_page) = self
+                    .fetcher
+                    .fetch_user_submissions(contest_id, &self.user_id, page)
+                    .await
+                    .with_context(|| format!("Failed to fetch {}", contest_id))?;
+                if submissions.is_empty() {
+                    break;
+                }
+                let rows = self.db.update_submissions(&submissions).await?;
+                metrics::add_rows_written(rows);
+                thread::sleep(time::Duration::from_millis(200));
+                if page >= max_page {
+                    break;
+                }
+            }
+        }
+        info!("Finished crawling {}", self.user_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::utils::MockFetcher;
+    use async_std::task::block_on;
+    use async_trait::async_trait;
+    use sql_client::ids::{ContestId, UserId};
+    use sql_client::models::Submission;
+    use sql_client::submission_client::SubmissionRequest;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockDB(Mutex<Vec<Submission>>);
+
+    #[async_trait]
+    impl SubmissionClient for MockDB {
+        async fn get_submissions<'a>(&self, _: SubmissionRequest<'a>) -> Result<Vec<Submission>> {
+            unimplemented!()
+        }
+
+        async fn get_user_submission_count(&self, _: &UserId) -> Result<i64> {
+            unimplemented!()
+        }
+
+        async fn get_contest_submission_stats(&self, _: &ContestId) -> Result<(i64, Option<i64>)> {
+            unimplemented!()
+        }
+
+        async fn update_submissions(&self, submissions: &[Submission]) -> Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(submissions);
+            Ok(submissions.len())
+        }
+
+        async fn update_submission_count(&self) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn update_user_submission_count(&self, _: &UserId) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn update_delta_submission_count(&self, _: &[Submission]) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn count_stored_submissions(&self, _: &[i64]) -> Result<usize> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_user_crawler() {
+        let fetcher = MockFetcher(|contest_id, _| {
+            vec![
+                Submission {
+                    contest_id: contest_id.into(),
+                    user_id: "user1".into(),
+                    ..Default::default()
+                },
+                Submission {
+                    contest_id: contest_id.into(),
+                    user_id: "user2".into(),
+                    ..Default::default()
+                },
+            ]
+        });
+        let contest_ids = vec!["abc001".to_owned(), "abc002".to_owned()];
+        let crawler = UserCrawler::new(MockDB::default(), fetcher, "user1", contest_ids);
+        block_on(crawler.crawl()).unwrap();
+
+        let saved = crawler.db.0.lock().unwrap();
+        assert_eq!(saved.len(), 2);
+        assert!(saved.iter().all(|s| s.user_id.as_str() == "user1"));
+        assert_eq!(saved[0].contest_id.as_str(), "abc001");
+        assert_eq!(saved[1].contest_id.as_str(), "abc002");
+    }
+}
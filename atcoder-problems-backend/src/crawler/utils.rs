@@ -1,8 +1,8 @@
 use crate::crawler::AtCoderFetcher;
 use anyhow::Result;
 use async_trait::async_trait;
-use atcoder_client::ContestTypeSpecifier;
-use sql_client::models::{Contest, ContestProblem, Problem, Submission};
+use atcoder_client::{AtCoderContestDetail, AtCoderStandings, ContestTypeSpecifier};
+use sql_client::models::{Contest, ContestProblem, Problem, Submission, UserProfile};
 
 pub(crate) struct MockFetcher<F: Fn(&str, u32) -> Vec<Submission>>(pub(crate) F);
 
@@ -15,6 +15,27 @@ where
         ((self.0)(contest_id, page), 0)
     }
 
+    async fn try_fetch_submissions(
+        &self,
+        contest_id: &str,
+        page: u32,
+    ) -> Result<(Vec<Submission>, u32)> {
+        Ok(self.fetch_submissions(contest_id, page).await)
+    }
+
+    async fn fetch_user_submissions(
+        &self,
+        contest_id: &str,
+        user_id: &str,
+        page: u32,
+    ) -> Result<(Vec<Submission>, u32)> {
+        let submissions = (self.0)(contest_id, page)
+            .into_iter()
+            .filter(|s| s.user_id.as_str() == user_id)
+            .collect();
+        Ok((submissions, 0))
+    }
+
     async fn fetch_contests(&self, _: ContestTypeSpecifier) -> Result<Vec<Contest>> {
         unimplemented!()
     }
@@ -22,4 +43,16 @@ where
     async fn fetch_problems(&self, _: &str) -> Result<(Vec<Problem>, Vec<ContestProblem>)> {
         unimplemented!()
     }
+
+    async fn fetch_standings(&self, _: &str) -> Result<AtCoderStandings> {
+        unimplemented!()
+    }
+
+    async fn fetch_contest_detail(&self, _: &str) -> Result<AtCoderContestDetail> {
+        unimplemented!()
+    }
+
+    async fn fetch_user_profile(&self, _: &str) -> Result<Option<UserProfile>> {
+        unimplemented!()
+    }
 }
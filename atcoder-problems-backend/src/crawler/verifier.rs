@@ -0,0 +1,211 @@
+use crate::crawler::AtCoderFetcher;
+use anyhow::Result;
+
+use log::info;
+use sql_client::ids::ContestId;
+use sql_client::submission_client::SubmissionClient;
+use std::fmt;
+
+/// The number of the submissions on a page of the submission list of AtCoder.
+const SUBMISSIONS_PER_PAGE: usize = 20;
+
+/// A contest whose stored submissions differ from the ones on AtCoder.
+#[derive(Debug, PartialEq)]
+pub struct Drift {
+    pub contest_id: String,
+    pub stored_count: i64,
+    pub atcoder_count: i64,
+    pub stored_latest_id: Option<i64>,
+    pub atcoder_latest_id: Option<i64>,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} submissions up to {:?} are stored, but AtCoder has {} up to {:?}",
+            self.contest_id,
+            self.stored_count,
+            self.stored_latest_id,
+            self.atcoder_count,
+            self.atcoder_latest_id
+        )
+    }
+}
+
+/// Compares the number and the latest id of the stored submissions of a contest with AtCoder,
+/// fetching only the first and the last pages of the submission list.
+pub struct Verifier<C, F> {
+    db: C,
+    fetcher: F,
+}
+
+impl<C, F> Verifier<C, F>
+where
+    C: SubmissionClient,
+    F: AtCoderFetcher,
+{
+    pub fn new(db: C, fetcher: F) -> Self {
+        Self { db, fetcher }
+    }
+
+    /// Fails if the submissions can not be fetched, rather than reporting the empty pages as drifts.
+    pub async fn verify(&self, contest_id: &str) -> Result<Option<Drift>> {
+        info!("Verifying {} ...", contest_id);
+        let (submissions, max_page) = self.fetcher.try_fetch_submissions(contest_id, 1).await?;
+        let atcoder_latest_id = submissions.iter().map(|s| s.id).max();
+        let atcoder_count = if max_page <= 1 {
+            submissions.len()
+        } else {
+            let (last_page, _) = self
+                .fetcher
+                .try_fetch_submissions(contest_id, max_page)
+                .await?;
+            (max_page as usize - 1) * SUBMISSIONS_PER_PAGE + last_page.len()
+        } as i64;
+
+        let (stored_count, stored_latest_id) = self
+            .db
+            .get_contest_submission_stats(&ContestId::from(contest_id))
+            .await?;
+        if stored_count == atcoder_count && stored_latest_id == atcoder_latest_id {
+            return Ok(None);
+        }
+        Ok(Some(Drift {
+            contest_id: contest_id.to_owned(),
+            stored_count,
+            atcoder_count,
+            stored_latest_id,
+            atcoder_latest_id,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crawler::utils::MockFetcher;
+    use anyhow::anyhow;
+    use async_std::task::block_on;
+    use async_trait::async_trait;
+    use atcoder_client::{AtCoderContestDetail, AtCoderStandings, ContestTypeSpecifier};
+    use sql_client::ids::UserId;
+    use sql_client::models::{Contest, ContestProblem, Problem, Submission, UserProfile};
+    use sql_client::submission_client::SubmissionRequest;
+
+    struct MockDB;
+
+    #[async_trait]
+    impl SubmissionClient for MockDB {
+        async fn get_submissions<'a>(&self, _: SubmissionRequest<'a>) -> Result<Vec<Submission>> {
+            unimplemented!()
+        }
+
+        async fn get_user_submission_count(&self, _: &UserId) -> Result<i64> {
+            unimplemented!()
+        }
+
+        async fn get_contest_submission_stats(
+            &self,
+            contest_id: &ContestId,
+        ) -> Result<(i64, Option<i64>)> {
+            match contest_id.as_str() {
+                "stored" => Ok((2, Some(2))),
+                _ => Ok((1, Some(1))),
+            }
+        }
+
+        async fn update_submissions(&self, _: &[Submission]) -> Result<usize> {
+            unimplemented!()
+        }
+
+        async fn update_submission_count(&self) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn update_user_submission_count(&self, _: &UserId) -> Result<()> {
+            unimplemented!()
+        }
+
+        async fn update_delta_submission_count(&self, _: &[Submission]) -> Result<()> {
+            unimplemented!()
+        }
+    }
+
+    struct FailingFetcher;
+
+    #[async_trait]
+    impl AtCoderFetcher for FailingFetcher {
+        async fn fetch_submissions(&self, _: &str, _: u32) -> (Vec<Submission>, u32) {
+            (Vec::new(), 0)
+        }
+
+        async fn try_fetch_submissions(&self, _: &str, _: u32) -> Result<(Vec<Submission>, u32)> {
+            Err(anyhow!("503 Service Unavailable"))
+        }
+
+        async fn fetch_user_submissions(
+            &self,
+            _: &str,
+            _: &str,
+            _: u32,
+        ) -> Result<(Vec<Submission>, u32)> {
+            unimplemented!()
+        }
+
+        async fn fetch_contests(&self, _: ContestTypeSpecifier) -> Result<Vec<Contest>> {
+            unimplemented!()
+        }
+
+        async fn fetch_problems(&self, _: &str) -> Result<(Vec<Problem>, Vec<ContestProblem>)> {
+            unimplemented!()
+        }
+
+        async fn fetch_standings(&self, _: &str) -> Result<AtCoderStandings> {
+            unimplemented!()
+        }
+
+        async fn fetch_contest_detail(&self, _: &str) -> Result<AtCoderContestDetail> {
+            unimplemented!()
+        }
+
+        async fn fetch_user_profile(&self, _: &str) -> Result<Option<UserProfile>> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn test_verifier_fetch_error() {
+        let verifier = Verifier::new(MockDB, FailingFetcher);
+        assert!(block_on(verifier.verify("drifted")).is_err());
+    }
+
+    #[test]
+    fn test_verifier() {
+        let fetcher = MockFetcher(|_, page| {
+            assert_eq!(page, 1);
+            vec![
+                Submission {
+                    id: 2,
+                    ..Default::default()
+                },
+                Submission {
+                    id: 1,
+                    ..Default::default()
+                },
+            ]
+        });
+        let verifier = Verifier::new(MockDB, fetcher);
+        assert_eq!(block_on(verifier.verify("stored")).unwrap(), None);
+        assert_eq!(
+            block_on(verifier.verify("drifted")).unwrap(),
+            Some(Drift {
+                contest_id: "drifted".to_owned(),
+                stored_count: 1,
+                atcoder_count: 2,
+                stored_latest_id: Some(1),
+                atcoder_latest_id: Some(2),
+            })
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::crawler::AtCoderFetcher;
+use crate::metrics;
 use anyhow::Result;
 use chrono::Utc;
 use rand::distributions::Uniform;
@@ -56,7 +57,11 @@ where
 
         let contest_set = pairs
             .into_iter()
-            .filter(|pair| problem_ids.binary_search(&pair.problem_id).is_ok())
+            .filter(|pair| {
+                problem_ids
+                    .binary_search_by(|id| id.as_str().cmp(pair.problem_id.as_str()))
+                    .is_ok()
+            })
             .map(|pair| pair.contest_id)
             .collect::<BTreeSet<_>>();
         log::info!("Loaded {} contests", contest_set.len());
@@ -66,7 +71,8 @@ where
             let mut streak = 0;
             for page in 1.. {
                 log::info!("Fetching from {} {} ...", contest, page);
-                let (submissions, max_page) = self.fetcher.fetch_submissions(&contest, page).await;
+                let (submissions, max_page) =
+                    self.fetcher.fetch_submissions(contest.as_str(), page).await;
                 if submissions.is_empty() {
                     log::info!("No submission is fetched");
                     break;
@@ -83,7 +89,8 @@ where
                 }
 
                 log::info!("Updating submissions ...");
-                self.db_pool.update_submissions(&submissions).await?;
+                let rows = self.db_pool.update_submissions(&submissions).await?;
+                metrics::add_rows_written(rows);
                 log::info!("Updated");
 
                 if streak >= CRAWLED_STREAK || page == max_page {
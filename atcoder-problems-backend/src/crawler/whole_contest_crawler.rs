@@ -1,4 +1,5 @@
 use crate::crawler::AtCoderFetcher;
+use crate::metrics;
 use anyhow::Result;
 
 use log::info;
@@ -38,10 +39,12 @@ where
                 break;
             }
 
-            self.db.update_submissions(&submissions).await?;
+            let rows = self.db.update_submissions(&submissions).await?;
+            metrics::add_rows_written(rows);
             thread::sleep(time::Duration::from_millis(200));
         }
 
+        metrics::add_contest_processed(&self.contest_id);
         info!("Finished");
         Ok(())
     }
@@ -53,6 +56,7 @@ mod tests {
     use crate::crawler::utils::MockFetcher;
     use async_std::task::block_on;
     use async_trait::async_trait;
+    use sql_client::ids::{ContestId, UserId};
     use sql_client::models::Submission;
     use sql_client::submission_client::SubmissionRequest;
 
@@ -64,7 +68,11 @@ mod tests {
             unimplemented!()
         }
 
-        async fn get_user_submission_count(&self, _: &str) -> Result<i64> {
+        async fn get_user_submission_count(&self, _: &UserId) -> Result<i64> {
+            unimplemented!()
+        }
+
+        async fn get_contest_submission_stats(&self, _: &ContestId) -> Result<(i64, Option<i64>)> {
             unimplemented!()
         }
 
@@ -76,7 +84,7 @@ mod tests {
             unimplemented!()
         }
 
-        async fn update_user_submission_count(&self, _: &str) -> Result<()> {
+        async fn update_user_submission_count(&self, _: &UserId) -> Result<()> {
             unimplemented!()
         }
 
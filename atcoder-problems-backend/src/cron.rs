@@ -0,0 +1,198 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use std::str::FromStr;
+
+/// How far `next_after` looks ahead, which covers the schedules only matching on February 29.
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 8;
+
+/// A schedule in the cron syntax, `minute hour day-of-month month day-of-week`, evaluated in UTC.
+/// Each field is a comma-separated list of `*`, `a`, `a-b`, and any of them followed by `/step`.
+/// `@hourly`, `@daily`, `@weekly` and `@monthly` are also accepted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// As in the standard cron, a day matches either of the day fields if both are restricted.
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let expression = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            expression => expression,
+        };
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        if fields.len() != 5 {
+            bail!("Expected 5 fields in the cron expression: {}", s);
+        }
+        let parse = |index: usize, min: u32, max: u32| {
+            parse_field(fields[index], min, max)
+                .with_context(|| format!("Invalid cron expression: {}", s))
+        };
+
+        // Both 0 and 7 are Sunday.
+        let mut days_of_week = parse(4, 0, 7)?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+        Ok(Self {
+            minutes: parse(0, 0, 59)?,
+            hours: parse(1, 0, 23)?,
+            days_of_month: parse(2, 1, 31)?,
+            months: parse(3, 1, 12)?,
+            days_of_week,
+            days_of_month_restricted: fields[2] != "*",
+            days_of_week_restricted: fields[4] != "*",
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Returns the first time matching the schedule strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = time + Duration::days(MAX_LOOKAHEAD_DAYS);
+        while time < limit {
+            if !contains(self.months, time.month()) || !self.matches_day(&time) {
+                time = time.date().succ().and_hms(0, 0, 0);
+            } else if !contains(self.hours, time.hour()) {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if !contains(self.minutes, time.minute()) {
+                time = time + Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        let day_of_month = contains(self.days_of_month, time.day());
+        let day_of_week = contains(self.days_of_week, time.weekday().num_days_from_sunday());
+        if self.days_of_month_restricted && self.days_of_week_restricted {
+            day_of_month || day_of_week
+        } else {
+            day_of_month && day_of_week
+        }
+    }
+}
+
+fn contains(mask: u64, value: u32) -> bool {
+    mask & (1 << value) != 0
+}
+
+/// Parses a field into the bit mask of the values.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64> {
+    let parse_value = |value: &str| -> Result<u32> {
+        let value = value
+            .parse::<u32>()
+            .map_err(|_| anyhow!("Invalid value: {}", value))?;
+        if value < min || max < value {
+            bail!("{} is out of the range {}-{}", value, min, max);
+        }
+        Ok(value)
+    };
+
+    let mut mask = 0;
+    for part in field.split(',') {
+        let mut split = part.splitn(2, '/');
+        let range = split.next().unwrap_or_default();
+        let step = match split.next() {
+            Some(step) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => Some(step),
+                _ => bail!("Invalid step: {}", step),
+            },
+            None => None,
+        };
+
+        let mut bounds = range.splitn(2, '-');
+        let (start, end) = match (bounds.next().unwrap_or_default(), bounds.next()) {
+            ("*", None) => (min, max),
+            (start, Some(end)) => (parse_value(start)?, parse_value(end)?),
+            // `a/step` starts at `a` and continues to the maximum.
+            (start, None) if step.is_some() => (parse_value(start)?, max),
+            (value, None) => (parse_value(value)?, parse_value(value)?),
+        };
+        if start > end {
+            bail!("Invalid range: {}", range);
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.ymd(2021, month, day).and_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn test_next_after() {
+        // 2021-05-01 is Saturday.
+        let after = Utc.ymd(2021, 5, 1).and_hms(12, 34, 56);
+        let cases = [
+            ("* * * * *", utc(5, 1, 12, 35)),
+            ("*/15 * * * *", utc(5, 1, 12, 45)),
+            ("5 3 * * *", utc(5, 2, 3, 5)),
+            ("@hourly", utc(5, 1, 13, 0)),
+            ("0 12 * * 1-5", utc(5, 3, 12, 0)),
+            ("0 0 * * 7", utc(5, 2, 0, 0)),
+            ("0 0 1 1,7 *", utc(7, 1, 0, 0)),
+            ("0 0 29 2 *", Utc.ymd(2024, 2, 29).and_hms(0, 0, 0)),
+            // Either of the day fields matches if both are restricted.
+            ("0 0 15 * 0", utc(5, 2, 0, 0)),
+            // Strictly after the given time.
+            ("34 12 * * *", utc(5, 2, 12, 34)),
+        ];
+        for (expression, expected) in cases.iter() {
+            let schedule = expression.parse::<CronSchedule>().unwrap();
+            assert_eq!(
+                schedule.next_after(after),
+                Some(*expected),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for expression in &[
+            "* * * *",
+            "60 * * * *",
+            "* * 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+            "@yearly",
+        ] {
+            assert!(
+                expression.parse::<CronSchedule>().is_err(),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn test_never() {
+        let schedule = "0 0 31 2 *".parse::<CronSchedule>().unwrap();
+        assert_eq!(schedule.next_after(utc(5, 1, 0, 0)), None);
+    }
+}
@@ -0,0 +1,28 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+const PROBLEM_MODELS_URL: &str = "https://kenkoooo.com/atcoder/resources/problem-models.json";
+
+#[derive(Deserialize)]
+pub(crate) struct ProblemModel {
+    difficulty: Option<f64>,
+}
+
+/// The difficulties of the problems in `problem-models.json`, which are missing for some of them.
+pub(crate) fn difficulties(models: BTreeMap<String, ProblemModel>) -> BTreeMap<String, f64> {
+    models
+        .into_iter()
+        .filter_map(|(problem_id, model)| model.difficulty.map(|d| (problem_id, d)))
+        .collect()
+}
+
+/// Fetches the estimated difficulties of the problems, which are published by the time-estimator.
+pub async fn fetch_problem_difficulties() -> Result<BTreeMap<String, f64>> {
+    let models: BTreeMap<String, ProblemModel> = surf::get(PROBLEM_MODELS_URL)
+        .header("accept-encoding", "gzip")
+        .recv_json()
+        .await
+        .map_err(|e| anyhow!("Failed to get json from {}: {:?}", PROBLEM_MODELS_URL, e))?;
+    Ok(difficulties(models))
+}
@@ -0,0 +1,33 @@
+use anyhow::Error;
+use sentry::ClientInitGuard;
+
+/// Starts reporting the panics and the errors passed to `report` to Sentry.
+/// The reports are flushed when the returned guard is dropped.
+pub fn init(dsn: &str, environment: Option<&str>) -> ClientInitGuard {
+    sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: environment.map(|environment| environment.to_owned().into()),
+            ..Default::default()
+        },
+    ))
+}
+
+/// Attaches the tag to all the reports of this process, e.g. the name of the job.
+pub fn set_tag(key: &str, value: &str) {
+    sentry::configure_scope(|scope| scope.set_tag(key, value));
+}
+
+/// Reports the error with the context, e.g. the contest being crawled.
+/// This does nothing if `init` has not been called.
+pub fn report(error: &Error, context: &[(&str, &str)]) {
+    sentry::with_scope(
+        |scope| {
+            for (key, value) in context.iter() {
+                scope.set_tag(key, value);
+            }
+        },
+        || sentry::integrations::anyhow::capture_anyhow(error),
+    );
+}
@@ -0,0 +1,239 @@
+use anyhow::{anyhow, Result};
+use async_std::future::timeout;
+use async_std::task;
+use kafka::producer::{Producer, Record, RequiredAcks};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sql_client::ids::ContestId;
+use sql_client::models::{Contest, Submission};
+use sql_client::published_contest::PublishedContestClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_listener::SubmissionListener;
+use sql_client::PgPool;
+use std::collections::{BTreeSet, VecDeque};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const KAFKA_ACK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventBroker {
+    Kafka,
+    Nats,
+}
+
+impl FromStr for EventBroker {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "kafka" => Ok(EventBroker::Kafka),
+            "nats" => Ok(EventBroker::Nats),
+            _ => Err(anyhow!("Unknown event broker: {}", s)),
+        }
+    }
+}
+
+enum Connection {
+    Kafka(Producer),
+    Nats(nats::Connection),
+}
+
+impl Connection {
+    fn publish(&mut self, topic: &str, key: &str, payload: &[u8]) -> Result<()> {
+        match self {
+            Connection::Kafka(producer) => {
+                let record = Record::from_key_value(topic, key, payload);
+                producer
+                    .send(&record)
+                    .map_err(|e| anyhow!("Failed to send to {}: {}", topic, e))?;
+            }
+            Connection::Nats(connection) => connection.publish(topic, payload)?,
+        }
+        Ok(())
+    }
+}
+
+/// An event published to a topic with a key, which decides the partition of Kafka, so that the
+/// events of the same submission or contest are consumed in order. NATS does not use the key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub topic: String,
+    pub key: String,
+    pub payload: Vec<u8>,
+}
+
+/// Publishes the events to the topics of Kafka, or to the subjects of NATS.
+///
+/// The clients of them block, so they are used on the blocking threads, and the publisher can be
+/// cloned to share the connection.
+#[derive(Clone)]
+pub struct Publisher(Arc<Mutex<Connection>>);
+
+impl Publisher {
+    pub fn connect(broker: EventBroker, servers: &[String]) -> Result<Self> {
+        let connection = match broker {
+            EventBroker::Kafka => {
+                let producer = Producer::from_hosts(servers.to_vec())
+                    .with_ack_timeout(KAFKA_ACK_TIMEOUT)
+                    .with_required_acks(RequiredAcks::One)
+                    .create()
+                    .map_err(|e| anyhow!("Failed to connect to Kafka: {}", e))?;
+                Connection::Kafka(producer)
+            }
+            EventBroker::Nats => Connection::Nats(nats::connect(&servers.join(","))?),
+        };
+        Ok(Publisher(Arc::new(Mutex::new(connection))))
+    }
+
+    /// Publishes the events in order, and returns the number of the events published before an
+    /// error if any.
+    pub async fn publish(&self, events: Vec<Event>) -> (usize, Result<()>) {
+        let connection = self.0.clone();
+        task::spawn_blocking(move || {
+            let mut connection = connection.lock().unwrap();
+            for (i, event) in events.iter().enumerate() {
+                if let Err(e) = connection.publish(&event.topic, &event.key, &event.payload) {
+                    return (i, Err(e));
+                }
+            }
+            (events.len(), Ok(()))
+        })
+        .await
+    }
+}
+
+/// Publishes the pending events in order, and removes them as they are published, so that the
+/// ones which failed are published again by the next call.
+pub async fn publish_pending(publisher: &Publisher, pending: &mut VecDeque<Event>) -> Result<()> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let (published, result) = publisher.publish(pending.iter().cloned().collect()).await;
+    pending.drain(..published);
+    result
+}
+
+/// The contests which are not in `known_contest_ids`, unless it is `None` because the contests
+/// have not been checked yet.
+fn discovered_contests<'a>(
+    contests: &'a [Contest],
+    known_contest_ids: Option<&BTreeSet<ContestId>>,
+) -> Vec<&'a Contest> {
+    match known_contest_ids {
+        Some(ids) => contests.iter().filter(|c| !ids.contains(&c.id)).collect(),
+        None => Vec::new(),
+    }
+}
+
+fn submission_event(topic: &str, submission: &Submission) -> Event {
+    let payload = json!({ "type": "submission_updated", "submission": submission });
+    Event {
+        topic: topic.to_owned(),
+        key: submission.id.to_string(),
+        payload: payload.to_string().into_bytes(),
+    }
+}
+
+/// Publishes an event to `{topic_prefix}.submissions` per submission inserted or updated by the
+/// crawlers, and to `{topic_prefix}.contests` per contest found by checking the contests every
+/// `polling_interval`.
+///
+/// The published contests are saved, so that the contests found while it is not running are
+/// published after restarting, except for the first run, which only saves the existing ones.
+/// The events which failed to be published are left in `pending`, including the submissions
+/// received but not published yet, so that a retry after an error publishes them first. So an
+/// event is published at least once, and may be published again after an error.
+pub async fn publish_events(
+    pg_pool: PgPool,
+    publisher: &Publisher,
+    topic_prefix: &str,
+    polling_interval: Duration,
+    pending: &mut VecDeque<Event>,
+) -> Result<()> {
+    let submission_topic = format!("{}.submissions", topic_prefix);
+    let contest_topic = format!("{}.contests", topic_prefix);
+    let (receiver, listening) = SubmissionListener::connect(&pg_pool).await?.spawn();
+    publish_pending(publisher, pending).await?;
+
+    let mut known_contest_ids = pg_pool.load_published_contest_ids().await?;
+    let mut polled: Option<Instant> = None;
+    let result = async {
+        loop {
+            if polled.map_or(true, |polled| polled.elapsed() >= polling_interval) {
+                let contests = pg_pool.load_contests().await?;
+                if known_contest_ids.is_empty() {
+                    let contest_ids = contests.into_iter().map(|c| c.id).collect::<Vec<_>>();
+                    pg_pool.insert_published_contest_ids(&contest_ids).await?;
+                    known_contest_ids = contest_ids.into_iter().collect();
+                } else {
+                    for contest in discovered_contests(&contests, Some(&known_contest_ids)) {
+                        let payload = json!({ "type": "contest_discovered", "contest": contest });
+                        pending.push_back(Event {
+                            topic: contest_topic.clone(),
+                            key: contest.id.to_string(),
+                            payload: payload.to_string().into_bytes(),
+                        });
+                        publish_pending(publisher, pending).await?;
+                        pg_pool
+                            .insert_published_contest_ids(&[contest.id.clone()])
+                            .await?;
+                        known_contest_ids.insert(contest.id.clone());
+                        log::info!("Published the discovery of {}", contest.id);
+                    }
+                }
+                polled = Some(Instant::now());
+            }
+
+            let wait = polled
+                .and_then(|polled| polling_interval.checked_sub(polled.elapsed()))
+                .unwrap_or_default();
+            match timeout(wait, receiver.recv()).await {
+                Ok(Ok(submission)) => {
+                    pending.push_back(submission_event(&submission_topic, &submission));
+                    publish_pending(publisher, pending).await?;
+                }
+                Ok(Err(_)) => return Ok::<_, anyhow::Error>(()),
+                Err(_) => {}
+            }
+        }
+    }
+    .await;
+
+    while let Ok(submission) = receiver.try_recv() {
+        pending.push_back(submission_event(&submission_topic, &submission));
+    }
+    result?;
+    listening.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contest(id: &str) -> Contest {
+        Contest {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_discovered_contests() {
+        let contests = vec![contest("abc001"), contest("abc002")];
+        assert!(discovered_contests(&contests, None).is_empty());
+
+        let known = vec![ContestId::from("abc001")].into_iter().collect();
+        let discovered = discovered_contests(&contests, Some(&known));
+        assert_eq!(discovered, vec![&contests[1]]);
+    }
+
+    #[test]
+    fn test_event_broker() {
+        assert_eq!("kafka".parse::<EventBroker>().unwrap(), EventBroker::Kafka);
+        assert_eq!("nats".parse::<EventBroker>().unwrap(), EventBroker::Nats);
+        assert!("redis".parse::<EventBroker>().is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::server::middleware::constant_time_eq;
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::ids::UserId;
+use sql_client::models;
+use sql_client::rated_point_sum::RatedPointSumClient;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::PgPool;
+use std::ops::Range;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("atcoder_problems");
+}
+
+use proto::at_coder_problems_api_server::AtCoderProblemsApi;
+pub use proto::at_coder_problems_api_server::AtCoderProblemsApiServer;
+use proto::{
+    AcceptedCountEntry, AcceptedCountRanking, RankingRequest, RatedPointSumEntry,
+    RatedPointSumRanking, Submission, SyncUserSubmissionsRequest,
+};
+
+const SYNC_BATCH_SIZE: usize = 1_000;
+const MAX_RANKING_RANGE_LENGTH: usize = 1_000;
+
+impl From<models::Submission> for Submission {
+    fn from(s: models::Submission) -> Self {
+        Self {
+            id: s.id,
+            epoch_second: s.epoch_second,
+            problem_id: s.problem_id.into(),
+            contest_id: s.contest_id.into(),
+            user_id: s.user_id.into(),
+            language: s.language,
+            point: s.point,
+            length: s.length,
+            result: s.result,
+            execution_time: s.execution_time,
+        }
+    }
+}
+
+fn ranking_range(request: &RankingRequest) -> Result<Range<usize>, Status> {
+    let range = (request.from as usize)..(request.to as usize);
+    if range.len() > MAX_RANKING_RANGE_LENGTH {
+        return Err(Status::invalid_argument(format!(
+            "The range must be shorter than {}",
+            MAX_RANKING_RANGE_LENGTH
+        )));
+    }
+    Ok(range)
+}
+
+/// Rejects the requests without one of `ADMIN_API_KEYS` in `authorization: Bearer <key>`, the
+/// same as /admin-api. All the requests are accepted if no keys are given, e.g. on 127.0.0.1.
+#[derive(Clone)]
+pub struct ApiKeyInterceptor {
+    api_keys: Vec<String>,
+}
+
+impl ApiKeyInterceptor {
+    pub fn new(api_keys: Vec<String>) -> Self {
+        Self { api_keys }
+    }
+
+    pub fn check(&self, request: Request<()>) -> Result<Request<()>, Status> {
+        if self.api_keys.is_empty() {
+            return Ok(request);
+        }
+        let key = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or_else(|| Status::unauthenticated("The API key is required"))?;
+        if self
+            .api_keys
+            .iter()
+            .any(|api_key| constant_time_eq(api_key.as_bytes(), key.as_bytes()))
+        {
+            Ok(request)
+        } else {
+            Err(Status::permission_denied("The API key is invalid"))
+        }
+    }
+}
+
+pub struct GrpcService {
+    pg_pool: PgPool,
+}
+
+impl GrpcService {
+    pub fn new(pg_pool: PgPool) -> Self {
+        Self { pg_pool }
+    }
+}
+
+#[tonic::async_trait]
+impl AtCoderProblemsApi for GrpcService {
+    type SyncUserSubmissionsStream = ReceiverStream<Result<Submission, Status>>;
+
+    async fn sync_user_submissions(
+        &self,
+        request: Request<SyncUserSubmissionsRequest>,
+    ) -> Result<Response<Self::SyncUserSubmissionsStream>, Status> {
+        let request = request.into_inner();
+        let pg_pool = self.pg_pool.clone();
+        let (tx, rx) = mpsc::channel(SYNC_BATCH_SIZE);
+        tokio::spawn(async move {
+            let mut from_second = request.from_second;
+            let mut from_id = request.from_id;
+            let user_id = UserId::from(request.user_id);
+            loop {
+                let submissions = pg_pool
+                    .get_submissions(SubmissionRequest::FromUserAndCursor {
+                        user_id: &user_id,
+                        from_second,
+                        from_id,
+                        count: SYNC_BATCH_SIZE as i64,
+                    })
+                    .await;
+                let submissions = match submissions {
+                    Ok(submissions) => submissions,
+                    Err(e) => {
+                        log::error!("{:?}", e);
+                        let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                        return;
+                    }
+                };
+
+                let is_last_batch = submissions.len() < SYNC_BATCH_SIZE;
+                for submission in submissions.into_iter() {
+                    from_second = submission.epoch_second;
+                    from_id = submission.id;
+                    if tx.send(Ok(submission.into())).await.is_err() {
+                        // The client has disconnected.
+                        return;
+                    }
+                }
+                if is_last_batch {
+                    return;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn get_accepted_count_ranking(
+        &self,
+        request: Request<RankingRequest>,
+    ) -> Result<Response<AcceptedCountRanking>, Status> {
+        let range = ranking_range(request.get_ref())?;
+        let entries = self
+            .pg_pool
+            .load_accepted_count_in_range(range)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .into_iter()
+            .map(|e| AcceptedCountEntry {
+                user_id: e.user_id,
+                problem_count: e.problem_count,
+            })
+            .collect();
+        Ok(Response::new(AcceptedCountRanking { entries }))
+    }
+
+    async fn get_rated_point_sum_ranking(
+        &self,
+        request: Request<RankingRequest>,
+    ) -> Result<Response<RatedPointSumRanking>, Status> {
+        let range = ranking_range(request.get_ref())?;
+        let entries = self
+            .pg_pool
+            .load_rated_point_sum_in_range(range)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .into_iter()
+            .map(|e| RatedPointSumEntry {
+                user_id: e.user_id,
+                point_sum: e.point_sum,
+            })
+            .collect();
+        Ok(Response::new(RatedPointSumRanking { entries }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    fn request(authorization: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(authorization) = authorization {
+            request
+                .metadata_mut()
+                .insert("authorization", authorization.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn test_api_key_interceptor() {
+        let interceptor = ApiKeyInterceptor::new(vec!["key1".to_owned(), "key2".to_owned()]);
+        assert!(interceptor.check(request(Some("Bearer key2"))).is_ok());
+        assert_eq!(
+            interceptor.check(request(None)).unwrap_err().code(),
+            Code::Unauthenticated
+        );
+        assert_eq!(
+            interceptor.check(request(Some("key1"))).unwrap_err().code(),
+            Code::Unauthenticated
+        );
+        assert_eq!(
+            interceptor
+                .check(request(Some("Bearer key3")))
+                .unwrap_err()
+                .code(),
+            Code::PermissionDenied
+        );
+
+        let interceptor = ApiKeyInterceptor::new(Vec::new());
+        assert!(interceptor.check(request(None)).is_ok());
+    }
+
+    #[test]
+    fn test_ranking_range() {
+        let request = RankingRequest { from: 10, to: 20 };
+        assert_eq!(ranking_range(&request).unwrap(), 10..20);
+        let request = RankingRequest { from: 0, to: 1_001 };
+        assert_eq!(
+            ranking_range(&request).unwrap_err().code(),
+            Code::InvalidArgument
+        );
+    }
+}
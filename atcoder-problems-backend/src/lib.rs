@@ -1,5 +1,22 @@
+pub mod bigquery;
+pub mod clickhouse;
+pub mod cli;
+pub mod compression;
 pub mod crawler;
+pub mod cron;
+pub mod difficulty;
+pub mod error_report;
+pub mod events;
+pub mod grpc;
+pub mod materialize;
+pub mod metrics;
+pub mod mirror;
+pub mod notify;
+pub mod progress;
+pub mod rating;
 pub mod s3;
 pub mod server;
+pub mod systemd;
 pub mod utils;
-pub mod config;
\ No newline at end of file
+pub mod webhook;
+pub mod config;
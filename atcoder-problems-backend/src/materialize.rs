@@ -0,0 +1,173 @@
+use anyhow::Result;
+use sql_client::models::{RankingEntry, UserSummary};
+use sql_client::ranking::{RankingClient, RankingKind};
+use sql_client::user_summary::UserSummaryClient;
+use sql_client::PgPool;
+
+/// The number of the users whose hashes are written in a pipeline.
+const USER_CHUNK_SIZE: usize = 1000;
+
+const RANKINGS: [(&str, RankingKind<'static>); 3] = [
+    ("accepted_count", RankingKind::AcceptedCount),
+    ("rated_point_sum", RankingKind::RatedPointSum),
+    ("max_streak", RankingKind::Streak),
+];
+
+/// Writes the results of the aggregation to Redis, so that the hottest keys are read without
+/// querying the database:
+///
+/// - `{prefix}:ranking:{name}`: The sorted set of the top-N users of the ranking, e.g. read by
+///   `ZREVRANGE {prefix}:ranking:accepted_count 0 99 WITHSCORES`.
+/// - `{prefix}:user:{user_id}`: The hash of the summary of the user, the same as `/v3/user_info`,
+///   which expires unless it is written again, e.g. after the user is merged into another one.
+/// - `{prefix}:updated_epoch_second`: When they were written last.
+pub struct RedisWriter {
+    client: redis::Client,
+    key_prefix: String,
+    top_n: usize,
+    user_ttl_second: u64,
+}
+
+impl RedisWriter {
+    pub fn new(url: &str, key_prefix: &str, top_n: usize, user_ttl_second: u64) -> Result<Self> {
+        Ok(Self {
+            client: redis::Client::open(url)?,
+            key_prefix: key_prefix.to_owned(),
+            top_n,
+            user_ttl_second,
+        })
+    }
+
+    pub async fn write(&self, pg_pool: &PgPool) -> Result<()> {
+        let mut conn = self.client.get_async_std_connection().await?;
+        for (name, kind) in RANKINGS.iter() {
+            let entries = pg_pool.load_ranking(*kind, 0, self.top_n).await?;
+            ranking_pipeline(&self.ranking_key(name), &entries)
+                .query_async::<_, ()>(&mut conn)
+                .await?;
+        }
+
+        let summaries = pg_pool.load_user_summaries().await?;
+        for chunk in summaries.chunks(USER_CHUNK_SIZE) {
+            let mut pipeline = redis::pipe();
+            for summary in chunk {
+                let key = self.user_key(&summary.user_id);
+                pipeline.cmd("HSET").arg(&key);
+                for (field, value) in summary_fields(summary) {
+                    pipeline.arg(field).arg(value);
+                }
+                pipeline.ignore();
+                pipeline
+                    .cmd("EXPIRE")
+                    .arg(&key)
+                    .arg(self.user_ttl_second)
+                    .ignore();
+            }
+            pipeline.query_async::<_, ()>(&mut conn).await?;
+        }
+
+        redis::cmd("SET")
+            .arg(format!("{}:updated_epoch_second", self.key_prefix))
+            .arg(chrono::Utc::now().timestamp())
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        log::info!(
+            "Wrote {} rankings and {} users to Redis",
+            RANKINGS.len(),
+            summaries.len()
+        );
+        Ok(())
+    }
+
+    /// Removes the user from the rankings and deletes the hash of the user, e.g. after the user
+    /// is forgotten, without waiting for the next `write`.
+    pub async fn forget_user(&self, user_id: &str) -> Result<()> {
+        let mut conn = self.client.get_async_std_connection().await?;
+        let mut pipeline = redis::pipe();
+        pipeline.atomic();
+        for (name, _) in RANKINGS.iter() {
+            pipeline
+                .cmd("ZREM")
+                .arg(self.ranking_key(name))
+                .arg(user_id)
+                .ignore();
+        }
+        pipeline.cmd("DEL").arg(self.user_key(user_id)).ignore();
+        pipeline.query_async::<_, ()>(&mut conn).await?;
+        Ok(())
+    }
+
+    fn ranking_key(&self, name: &str) -> String {
+        format!("{}:ranking:{}", self.key_prefix, name)
+    }
+
+    fn user_key(&self, user_id: &str) -> String {
+        format!("{}:user:{}", self.key_prefix, user_id)
+    }
+}
+
+/// Replaces the sorted set in a transaction, so that the readers never see a half-written one.
+fn ranking_pipeline(key: &str, entries: &[RankingEntry]) -> redis::Pipeline {
+    let mut pipeline = redis::pipe();
+    pipeline.atomic().cmd("DEL").arg(key).ignore();
+    if !entries.is_empty() {
+        pipeline.cmd("ZADD").arg(key);
+        for entry in entries {
+            pipeline.arg(entry.value).arg(&entry.user_id);
+        }
+        pipeline.ignore();
+    }
+    pipeline
+}
+
+fn summary_fields(summary: &UserSummary) -> Vec<(&'static str, String)> {
+    vec![
+        ("accepted_count", summary.accepted_count.to_string()),
+        (
+            "accepted_count_rank",
+            summary.accepted_count_rank.to_string(),
+        ),
+        ("rated_point_sum", summary.rated_point_sum.to_string()),
+        (
+            "rated_point_sum_rank",
+            summary.rated_point_sum_rank.to_string(),
+        ),
+        ("max_streak", summary.max_streak.to_string()),
+        ("max_streak_rank", summary.max_streak_rank.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys() {
+        let writer =
+            RedisWriter::new("redis://localhost:6379", "atcoder-problems", 10, 3600).unwrap();
+        assert_eq!(
+            writer.ranking_key("accepted_count"),
+            "atcoder-problems:ranking:accepted_count"
+        );
+        assert_eq!(
+            writer.user_key("kenkoooo"),
+            "atcoder-problems:user:kenkoooo"
+        );
+    }
+
+    #[test]
+    fn test_summary_fields() {
+        let summary = UserSummary {
+            user_id: "kenkoooo".to_owned(),
+            accepted_count: 42,
+            accepted_count_rank: 3,
+            rated_point_sum: 1500.5,
+            ..Default::default()
+        };
+        let fields = summary_fields(&summary);
+        assert_eq!(fields[0], ("accepted_count", "42".to_owned()));
+        assert_eq!(fields[1], ("accepted_count_rank", "3".to_owned()));
+        assert_eq!(fields[2], ("rated_point_sum", "1500.5".to_owned()));
+        assert_eq!(fields[5], ("max_streak_rank", "0".to_owned()));
+    }
+}
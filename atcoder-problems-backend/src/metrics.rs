@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Result};
+use std::fmt::{self, Write};
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::progress;
+
+const METRIC_PREFIX: &str = "atcoder_problems_job";
+
+/// The counters of the current run. They are shared in the whole process, since the jobs spawn
+/// tasks which run on the other threads. The counts of the scheduled jobs which overlap in the
+/// daemon are mixed.
+struct Counters {
+    rows_written: AtomicU64,
+    failures: AtomicU64,
+    contests_processed: Mutex<Vec<String>>,
+    pages_fetched: AtomicU64,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            rows_written: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            contests_processed: Mutex::new(Vec::new()),
+            pages_fetched: AtomicU64::new(0),
+        }
+    }
+
+    fn contests(&self) -> MutexGuard<Vec<String>> {
+        // The list is still valid even if a thread panicked while pushing to it.
+        self.contests_processed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn counts(&self) -> Counts {
+        Counts {
+            contests_processed: self.contests().len() as u64,
+            pages_fetched: self.pages_fetched.load(Ordering::Relaxed),
+            rows_written: self.rows_written.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        self.rows_written.store(0, Ordering::Relaxed);
+        self.failures.store(0, Ordering::Relaxed);
+        self.contests().clear();
+        self.pages_fetched.store(0, Ordering::Relaxed);
+    }
+}
+
+static COUNTERS: Counters = Counters::new();
+
+/// Counts the rows which the job writes to the database.
+pub fn add_rows_written(rows: usize) {
+    COUNTERS
+        .rows_written
+        .fetch_add(rows as u64, Ordering::Relaxed);
+    progress::update();
+}
+
+pub fn rows_written() -> u64 {
+    COUNTERS.rows_written.load(Ordering::Relaxed)
+}
+
+/// Counts the items which the job skips because of errors, e.g. the contests which fail to be crawled.
+pub fn add_failure() {
+    COUNTERS.failures.fetch_add(1, Ordering::Relaxed);
+    progress::update();
+}
+
+pub fn failures() -> u64 {
+    COUNTERS.failures.load(Ordering::Relaxed)
+}
+
+/// Records the contests which the job has finished, e.g. crawled or verified.
+pub fn add_contest_processed(contest_id: &str) {
+    COUNTERS.contests().push(contest_id.to_owned());
+    progress::update();
+}
+
+/// The contests which the job has finished, in the order they are finished.
+pub fn contests_processed() -> Vec<String> {
+    COUNTERS.contests().clone()
+}
+
+/// Counts the pages fetched from AtCoder.
+pub fn add_page_fetched() {
+    COUNTERS.pages_fetched.fetch_add(1, Ordering::Relaxed);
+    progress::update();
+}
+
+/// The counters of the current run, which are reported as the progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Counts {
+    pub contests_processed: u64,
+    pub pages_fetched: u64,
+    pub rows_written: u64,
+    pub failures: u64,
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} contests, {} pages, {} rows written, {} failures",
+            self.contests_processed, self.pages_fetched, self.rows_written, self.failures
+        )
+    }
+}
+
+pub fn counts() -> Counts {
+    COUNTERS.counts()
+}
+
+/// Resets the counters before the next run of a scheduled job.
+pub fn reset() {
+    COUNTERS.reset();
+}
+
+/// The metrics of a batch job, which are pushed when the job finishes
+/// because the job does not live long enough to be scraped.
+#[derive(Debug, PartialEq)]
+pub struct JobMetrics {
+    pub job: String,
+    pub duration: Duration,
+    pub rows_written: u64,
+    pub failed: bool,
+    pub finished_epoch_second: i64,
+}
+
+impl JobMetrics {
+    /// Formats the metrics in the Prometheus text format.
+    fn to_prometheus_text(&self) -> String {
+        let metrics = [
+            ("duration_seconds", self.duration.as_secs_f64().to_string()),
+            ("rows_written", self.rows_written.to_string()),
+            ("failed", (self.failed as u8).to_string()),
+            (
+                "last_completion_timestamp_seconds",
+                self.finished_epoch_second.to_string(),
+            ),
+        ];
+        let mut text = String::new();
+        for (name, value) in metrics.iter() {
+            writeln!(text, "# TYPE {}_{} gauge", METRIC_PREFIX, name).unwrap();
+            writeln!(text, "{}_{} {}", METRIC_PREFIX, name, value).unwrap();
+        }
+        text
+    }
+
+    /// Formats the metrics in the StatsD format. The job name is a part of the metric names,
+    /// since StatsD does not have labels.
+    fn to_statsd_lines(&self) -> Vec<String> {
+        let prefix = format!("{}.{}", METRIC_PREFIX, self.job);
+        vec![
+            format!("{}.duration:{}|ms", prefix, self.duration.as_millis()),
+            format!("{}.rows_written:{}|c", prefix, self.rows_written),
+            format!("{}.failures:{}|c", prefix, self.failed as u8),
+        ]
+    }
+
+    /// Replaces the metrics of the job in the Pushgateway.
+    pub async fn push_to_pushgateway(&self, url: &str) -> Result<()> {
+        let url = format!("{}/metrics/job/{}", url.trim_end_matches('/'), self.job);
+        let response = surf::put(&url)
+            .content_type("text/plain; version=0.0.4")
+            .body(self.to_prometheus_text())
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to push the metrics to {}: {:?}", url, e))?;
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to push the metrics to {}: status={}",
+                url,
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn send_to_statsd(&self, address: &str) -> Result<()> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.send_to(self.to_statsd_lines().join("\n").as_bytes(), address)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> JobMetrics {
+        JobMetrics {
+            job: "crawl_problems".to_owned(),
+            duration: Duration::from_millis(1500),
+            rows_written: 42,
+            failed: true,
+            finished_epoch_second: 1_600_000_000,
+        }
+    }
+
+    #[test]
+    fn test_to_prometheus_text() {
+        assert_eq!(
+            metrics().to_prometheus_text(),
+            r"# TYPE atcoder_problems_job_duration_seconds gauge
+atcoder_problems_job_duration_seconds 1.5
+# TYPE atcoder_problems_job_rows_written gauge
+atcoder_problems_job_rows_written 42
+# TYPE atcoder_problems_job_failed gauge
+atcoder_problems_job_failed 1
+# TYPE atcoder_problems_job_last_completion_timestamp_seconds gauge
+atcoder_problems_job_last_completion_timestamp_seconds 1600000000
+"
+        );
+    }
+
+    #[test]
+    fn test_counts() {
+        // Uses its own counters, since the other tests running in parallel count to `COUNTERS`.
+        let counters = Counters::new();
+        counters.contests().push("abc001".to_owned());
+        counters.pages_fetched.fetch_add(2, Ordering::Relaxed);
+        counters.rows_written.fetch_add(3, Ordering::Relaxed);
+        assert_eq!(
+            counters.counts().to_string(),
+            "1 contests, 2 pages, 3 rows written, 0 failures"
+        );
+        assert_eq!(*counters.contests(), vec!["abc001"]);
+        counters.reset();
+        assert_eq!(counters.counts(), Counts::default());
+    }
+
+    #[test]
+    fn test_to_statsd_lines() {
+        assert_eq!(
+            metrics().to_statsd_lines(),
+            vec![
+                "atcoder_problems_job.crawl_problems.duration:1500|ms",
+                "atcoder_problems_job.crawl_problems.rows_written:42|c",
+                "atcoder_problems_job.crawl_problems.failures:1|c",
+            ]
+        );
+    }
+}
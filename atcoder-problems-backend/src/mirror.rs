@@ -0,0 +1,197 @@
+//! Mirrors the submissions to a secondary store by consuming the logical replication of the
+//! `submissions` table, so that the store follows the inserts, the updates and the deletions.
+
+use crate::events::{Event, EventBroker, Publisher};
+use anyhow::{anyhow, Context, Result};
+use async_std::task;
+use log::info;
+use sql_client::pgoutput::{parse_message, Message, SubmissionChange, SubmissionDecoder};
+use sql_client::replication::ReplicationClient;
+use sql_client::PgPool;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Where the submissions are mirrored to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MirrorTarget {
+    /// Another database with the same `submissions` table.
+    Postgres(String),
+    /// A file which the changes are appended to, as one JSON object per line.
+    File(PathBuf),
+    /// The changes are published to `{prefix}.submissions` with the ids as the keys.
+    Broker(EventBroker, Vec<String>),
+}
+
+impl FromStr for MirrorTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.starts_with("postgres://") || s.starts_with("postgresql://") {
+            return Ok(MirrorTarget::Postgres(s.to_owned()));
+        }
+        if let Some(path) = s.strip_prefix("file:") {
+            return Ok(MirrorTarget::File(PathBuf::from(path)));
+        }
+        for &(scheme, broker) in [
+            ("kafka://", EventBroker::Kafka),
+            ("nats://", EventBroker::Nats),
+        ]
+        .iter()
+        {
+            if let Some(servers) = s.strip_prefix(scheme) {
+                let servers = servers
+                    .split(',')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_owned())
+                    .collect::<Vec<_>>();
+                if servers.is_empty() {
+                    return Err(anyhow!("No servers are given: {}", s));
+                }
+                return Ok(MirrorTarget::Broker(broker, servers));
+            }
+        }
+        Err(anyhow!("Unknown mirror target: {}", s))
+    }
+}
+
+pub enum Mirror {
+    Postgres(PgPool),
+    File(BufWriter<File>),
+    Broker { publisher: Publisher, topic: String },
+}
+
+impl Mirror {
+    pub async fn connect(target: &MirrorTarget, topic_prefix: &str) -> Result<Self> {
+        let mirror = match target {
+            MirrorTarget::Postgres(url) => {
+                Mirror::Postgres(sql_client::initialize_pool(url).await?)
+            }
+            MirrorTarget::File(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open {:?}", path))?;
+                Mirror::File(BufWriter::new(file))
+            }
+            MirrorTarget::Broker(broker, servers) => Mirror::Broker {
+                publisher: Publisher::connect(*broker, servers)?,
+                topic: format!("{}.submissions", topic_prefix),
+            },
+        };
+        Ok(mirror)
+    }
+
+    /// Applies the changes in order, and returns after they are written to the target.
+    pub async fn apply(&mut self, changes: &[SubmissionChange]) -> Result<()> {
+        match self {
+            Mirror::Postgres(pool) => pool.apply_submission_changes(changes).await?,
+            Mirror::File(writer) => {
+                for change in changes.iter() {
+                    serde_json::to_writer(&mut *writer, change)?;
+                    writeln!(writer)?;
+                }
+                writer.flush()?;
+                writer.get_ref().sync_data()?;
+            }
+            Mirror::Broker { publisher, topic } => {
+                let events = changes
+                    .iter()
+                    .map(|change| {
+                        let key = match change {
+                            SubmissionChange::Upsert { submission } => submission.id.to_string(),
+                            SubmissionChange::Delete { id } => id.to_string(),
+                            SubmissionChange::Truncate => String::new(),
+                        };
+                        Ok(Event {
+                            topic: topic.clone(),
+                            key,
+                            payload: serde_json::to_vec(change)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                // The slot is not advanced on an error, so the whole batch is published again.
+                publisher.publish(events).await.1?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Mirrors the changes forever. The slot is advanced after each batch is applied, so the changes
+/// may be applied again after a restart, but none of them is lost.
+pub async fn mirror_submissions(
+    pool: &PgPool,
+    slot: &str,
+    publication: &str,
+    mirror: &mut Mirror,
+    batch_size: i32,
+    polling_interval: Duration,
+) -> Result<()> {
+    pool.create_replication_slot(slot, publication).await?;
+    loop {
+        let messages = pool
+            .peek_replication_changes(slot, publication, batch_size)
+            .await?;
+
+        // The relations are sent again in each session of the decoding, so a new decoder is used
+        // for each batch.
+        let mut decoder = SubmissionDecoder::default();
+        let mut changes = Vec::new();
+        let mut committed = None;
+        let mut committed_changes = 0;
+        for message in messages.iter() {
+            let parsed = parse_message(&message.data)
+                .with_context(|| format!("Failed to parse the message at {}", message.lsn))?;
+            if let Message::Commit { .. } = parsed {
+                committed = Some(message.lsn.as_str());
+                committed_changes = changes.len();
+            }
+            changes.extend(decoder.decode(parsed)?);
+        }
+
+        match committed {
+            Some(lsn) => {
+                mirror.apply(&changes[..committed_changes]).await?;
+                pool.advance_replication_slot(slot, lsn).await?;
+                info!("Mirrored {} changes up to {}", committed_changes, lsn);
+            }
+            None => task::sleep(polling_interval).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mirror_target() {
+        assert_eq!(
+            "postgresql://localhost/mirror"
+                .parse::<MirrorTarget>()
+                .unwrap(),
+            MirrorTarget::Postgres("postgresql://localhost/mirror".to_owned())
+        );
+        assert_eq!(
+            "file:/tmp/submissions.jsonl"
+                .parse::<MirrorTarget>()
+                .unwrap(),
+            MirrorTarget::File(PathBuf::from("/tmp/submissions.jsonl"))
+        );
+        assert_eq!(
+            "kafka://host1:9092,host2:9092"
+                .parse::<MirrorTarget>()
+                .unwrap(),
+            MirrorTarget::Broker(
+                EventBroker::Kafka,
+                vec!["host1:9092".to_owned(), "host2:9092".to_owned()]
+            )
+        );
+        assert!("nats://".parse::<MirrorTarget>().is_err());
+        assert!("mysql://localhost".parse::<MirrorTarget>().is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::str::FromStr;
+
+/// Slack and Discord limit the length of a message, so the long errors are cut.
+const MAX_MESSAGE_LENGTH: usize = 1500;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookFormat {
+    /// Also accepted by Mattermost and Rocket.Chat.
+    Slack,
+    Discord,
+}
+
+impl FromStr for WebhookFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "slack" => Ok(WebhookFormat::Slack),
+            "discord" => Ok(WebhookFormat::Discord),
+            _ => Err(anyhow!("Unknown webhook format: {}", s)),
+        }
+    }
+}
+
+/// Posts the failures of the jobs to the incoming webhook of a chat.
+pub struct Notifier {
+    url: String,
+    format: WebhookFormat,
+}
+
+impl Notifier {
+    pub fn new(url: &str, format: WebhookFormat) -> Self {
+        Self {
+            url: url.to_owned(),
+            format,
+        }
+    }
+
+    pub async fn send(&self, message: &str) -> Result<()> {
+        let payload = serde_json::to_vec(&payload(self.format, message))?;
+        let response = surf::post(&self.url)
+            .content_type(surf::http::mime::JSON)
+            .body(payload)
+            .await
+            .map_err(|e| anyhow!("Failed to post the notification: {:?}", e))?;
+        if !response.status().is_success() {
+            bail!("The notification webhook returned {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+fn payload(format: WebhookFormat, message: &str) -> Value {
+    let message = match message.char_indices().nth(MAX_MESSAGE_LENGTH) {
+        Some((end, _)) => format!("{}...", &message[..end]),
+        None => message.to_owned(),
+    };
+    match format {
+        WebhookFormat::Slack => json!({ "text": message }),
+        WebhookFormat::Discord => json!({ "content": message }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        assert_eq!(
+            payload(WebhookFormat::Slack, "failed"),
+            json!({"text": "failed"})
+        );
+        assert_eq!(
+            payload(WebhookFormat::Discord, "failed"),
+            json!({"content": "failed"})
+        );
+
+        let long = "あ".repeat(MAX_MESSAGE_LENGTH + 1);
+        let text = payload(WebhookFormat::Slack, &long)["text"]
+            .as_str()
+            .unwrap()
+            .to_owned();
+        assert_eq!(text.chars().count(), MAX_MESSAGE_LENGTH + 3);
+        assert!(text.ends_with("..."));
+    }
+}
@@ -0,0 +1,83 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::cell::RefCell;
+
+use crate::metrics::{self, Counts};
+
+/// Shows the progress of a job from the counters in `metrics`, which call `update`.
+pub trait ProgressReporter {
+    fn update(&self, counts: &Counts);
+    /// Reports the summary of the run when the command finishes.
+    fn finish(&self, summary: &str);
+}
+
+/// Leaves the progress to the logs, and logs the summary. The default for the daemon and
+/// the log collectors.
+pub struct LogReporter;
+
+impl ProgressReporter for LogReporter {
+    fn update(&self, _: &Counts) {}
+
+    fn finish(&self, summary: &str) {
+        log::info!("{}", summary);
+    }
+}
+
+/// Prints only the summary to stdout, e.g. for the mails of cron.
+pub struct QuietReporter;
+
+impl ProgressReporter for QuietReporter {
+    fn update(&self, _: &Counts) {}
+
+    fn finish(&self, summary: &str) {
+        println!("{}", summary);
+    }
+}
+
+/// Draws the counters on stderr for the interactive use.
+pub struct BarReporter {
+    bar: ProgressBar,
+}
+
+impl BarReporter {
+    pub fn new() -> Self {
+        let bar = ProgressBar::new_spinner();
+        bar.set_style(ProgressStyle::default_spinner().template("{spinner} [{elapsed}] {msg}"));
+        bar.enable_steady_tick(100);
+        Self { bar }
+    }
+}
+
+impl Default for BarReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProgressReporter for BarReporter {
+    fn update(&self, counts: &Counts) {
+        self.bar.set_message(&counts.to_string());
+    }
+
+    fn finish(&self, summary: &str) {
+        self.bar.finish_with_message(summary);
+    }
+}
+
+// Per thread as the counters, so that the scheduled jobs of the daemon only log.
+thread_local! {
+    static REPORTER: RefCell<Box<dyn ProgressReporter>> = RefCell::new(Box::new(LogReporter));
+}
+
+/// Replaces the reporter of the current thread.
+pub fn set_reporter(reporter: Box<dyn ProgressReporter>) {
+    REPORTER.with(|current| *current.borrow_mut() = reporter);
+}
+
+pub(crate) fn update() {
+    let counts = metrics::counts();
+    REPORTER.with(|reporter| reporter.borrow().update(&counts));
+}
+
+pub fn finish(summary: &str) {
+    REPORTER.with(|reporter| reporter.borrow().finish(summary));
+}
@@ -0,0 +1,305 @@
+use serde::Serialize;
+use sql_client::models::{Contest, Submission};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+pub use atcoder_client::rating::{
+    adjust_rating, estimate_performances, next_rating, unadjust_rating, RatingRule,
+};
+
+/// A rated participant in the final standings of a virtual contest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VirtualParticipant {
+    pub user_id: String,
+    /// The rank in the standings, which is shared by the tied participants.
+    pub rank: u64,
+    /// The rating on AtCoder, or 0 if the user has never joined a rated contest.
+    pub rating: i64,
+    /// The number of the rated contests the user has joined.
+    pub competitions: u64,
+}
+
+/// The rating change of a participant if a virtual contest were rated, which does not affect the
+/// actual rating.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulatedRatingChange {
+    pub user_id: String,
+    pub rank: i64,
+    pub performance: i64,
+    pub old_rating: i64,
+    pub new_rating: i64,
+    pub delta: i64,
+}
+
+/// Returns `None` if the contest is unrated.
+pub fn rating_rule(contest: &Contest) -> Option<RatingRule> {
+    let (_, rated_upper_bound) = contest.rated_range?;
+    Some(RatingRule::new(contest.id.as_str(), rated_upper_bound))
+}
+
+/// Returns the 1-origin ranks among the participants sorted by `ranks`, where the participants
+/// with the same rank in the standings share the same rank.
+pub fn rated_ranks(ranks: &[u64]) -> Vec<f64> {
+    let mut rated_ranks = Vec::with_capacity(ranks.len());
+    for (i, &rank) in ranks.iter().enumerate() {
+        if i > 0 && ranks[i - 1] == rank {
+            rated_ranks.push(rated_ranks[i - 1]);
+        } else {
+            rated_ranks.push((i + 1) as f64);
+        }
+    }
+    rated_ranks
+}
+
+/// The best submission of a user for a problem in a virtual contest.
+struct ProblemResult {
+    trials: i64,
+    /// The number of the submissions before the best one.
+    penalties: i64,
+    point: f64,
+    epoch_second: i64,
+}
+
+/// Returns the users who submitted in a virtual contest with their ranks in its standings, computed
+/// in the same way as the frontend does: the best submission of each problem counts, where the
+/// accepted ones score the points in `point_overrides` if any, and the users are ordered by the
+/// total score, the time of the last improvement plus the penalties, and the number of penalties.
+pub fn virtual_contest_ranks(
+    submissions: &[Submission],
+    point_overrides: &BTreeMap<&str, i64>,
+    penalty_second: i64,
+) -> Vec<(String, u64)> {
+    let mut submissions = submissions.iter().collect::<Vec<_>>();
+    submissions.sort_by_key(|s| s.id);
+    let mut results = BTreeMap::<&str, BTreeMap<&str, ProblemResult>>::new();
+    for s in submissions {
+        let point = match point_overrides.get(s.problem_id.as_str()) {
+            Some(&point) if s.result == "AC" => point as f64,
+            Some(_) => 0.0,
+            None => s.point,
+        };
+        let problems = results.entry(s.user_id.as_str()).or_default();
+        match problems.get_mut(s.problem_id.as_str()) {
+            Some(best) if best.point < point => {
+                best.penalties = best.trials;
+                best.trials += 1;
+                best.point = point;
+                best.epoch_second = s.epoch_second;
+            }
+            Some(best) => best.trials += 1,
+            None => {
+                problems.insert(
+                    s.problem_id.as_str(),
+                    ProblemResult {
+                        trials: 1,
+                        penalties: 0,
+                        point,
+                        epoch_second: s.epoch_second,
+                    },
+                );
+            }
+        }
+    }
+
+    let mut totals = results
+        .into_iter()
+        .map(|(user_id, problems)| {
+            let point = problems.values().map(|r| r.point).sum::<f64>();
+            let penalties = problems.values().map(|r| r.penalties).sum::<i64>();
+            let last_epoch_second = problems
+                .values()
+                .filter(|r| r.point != 0.0)
+                .map(|r| r.epoch_second)
+                .max()
+                .unwrap_or(0);
+            let time = last_epoch_second + penalties * penalty_second;
+            (user_id, point, time, penalties)
+        })
+        .collect::<Vec<_>>();
+    let compare = |a: &(&str, f64, i64, i64), b: &(&str, f64, i64, i64)| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then(a.2.cmp(&b.2))
+            .then(a.3.cmp(&b.3))
+    };
+    totals.sort_by(|a, b| compare(a, b).then_with(|| a.0.cmp(b.0)));
+
+    let mut ranks = Vec::with_capacity(totals.len());
+    for (i, total) in totals.iter().enumerate() {
+        let rank = match ranks.last() {
+            Some(&(_, rank)) if compare(&totals[i - 1], total) == Ordering::Equal => rank,
+            _ => (i + 1) as u64,
+        };
+        ranks.push((total.0.to_owned(), rank));
+    }
+    ranks
+}
+
+/// Simulates the rating update of AtCoder for the participants of a virtual contest, as if it were
+/// rated with `rule`. The participants above the rated range are not rated, as on AtCoder.
+pub fn simulate_rating_changes(
+    participants: &[VirtualParticipant],
+    rule: &RatingRule,
+) -> Vec<SimulatedRatingChange> {
+    let mut participants = participants
+        .iter()
+        .filter(|p| {
+            rule.rated_upper_bound
+                .map_or(true, |upper| p.rating <= upper)
+        })
+        .collect::<Vec<_>>();
+    participants.sort_by(|a, b| a.rank.cmp(&b.rank).then_with(|| a.user_id.cmp(&b.user_id)));
+
+    let average_performances = participants
+        .iter()
+        .map(|p| {
+            if p.competitions == 0 {
+                rule.default_performance
+            } else {
+                unadjust_rating(p.rating, p.competitions)
+            }
+        })
+        .collect::<Vec<_>>();
+    let ranks = rated_ranks(&participants.iter().map(|p| p.rank).collect::<Vec<_>>());
+    let performances = estimate_performances(&average_performances, &ranks, rule);
+    participants
+        .into_iter()
+        .zip(ranks)
+        .zip(performances)
+        .map(|((p, rank), performance)| {
+            let new_rating = next_rating(p.rating, p.competitions, performance).round() as i64;
+            SimulatedRatingChange {
+                user_id: p.user_id.clone(),
+                rank: rank as i64,
+                performance: performance.round() as i64,
+                old_rating: p.rating,
+                new_rating,
+                delta: new_rating - p.rating,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A contest held after AGC 001, since the contests before it are unrated.
+    fn contest(id: &str, rate_change: &str) -> Contest {
+        Contest::new(
+            id.to_owned(),
+            1_600_000_000,
+            6000,
+            String::new(),
+            rate_change.to_owned(),
+        )
+    }
+
+    #[test]
+    fn test_rating_rule() {
+        let rule = rating_rule(&contest("abc200", " ~ 1999")).unwrap();
+        assert_eq!(rule.rated_upper_bound, Some(1999));
+        assert_eq!(rule.performance_cap(), Some(2400.0));
+        assert_eq!(rule.default_performance, 800.0);
+
+        let rule = rating_rule(&contest("agc050", "All")).unwrap();
+        assert_eq!(rule.rated_upper_bound, None);
+        assert_eq!(rule.default_performance, 1600.0);
+
+        let rule = rating_rule(&contest("arc110", "1200 ~ ")).unwrap();
+        assert_eq!(rule.rated_upper_bound, None);
+
+        assert!(rating_rule(&contest("practice", "-")).is_none());
+    }
+
+    fn participant(user_id: &str, rank: u64, rating: i64, competitions: u64) -> VirtualParticipant {
+        VirtualParticipant {
+            user_id: user_id.to_owned(),
+            rank,
+            rating,
+            competitions,
+        }
+    }
+
+    fn submission(id: i64, user_id: &str, problem_id: &str, result: &str) -> Submission {
+        Submission {
+            id,
+            epoch_second: 1_600_000_000 + id * 60,
+            user_id: user_id.into(),
+            problem_id: problem_id.into(),
+            result: result.to_owned(),
+            point: if result == "AC" { 100.0 } else { 0.0 },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_virtual_contest_ranks() {
+        let submissions = vec![
+            submission(1, "user1", "a", "WA"),
+            submission(2, "user1", "a", "AC"),
+            submission(3, "user2", "a", "AC"),
+            submission(4, "user2", "b", "AC"),
+            submission(5, "user3", "a", "AC"),
+            submission(6, "user4", "a", "WA"),
+            submission(7, "user1", "a", "AC"),
+            submission(8, "user5", "b", "WA"),
+        ];
+        let overrides = BTreeMap::new();
+        assert_eq!(
+            virtual_contest_ranks(&submissions, &overrides, 0),
+            vec![
+                ("user2".to_owned(), 1),
+                ("user1".to_owned(), 2),
+                ("user3".to_owned(), 3),
+                ("user4".to_owned(), 4),
+                ("user5".to_owned(), 4),
+            ]
+        );
+
+        // The penalty of user1 catches up with user3, who has fewer penalties.
+        let overrides = vec![("b", 0)].into_iter().collect();
+        assert_eq!(
+            virtual_contest_ranks(&submissions, &overrides, 180),
+            vec![
+                ("user2".to_owned(), 1),
+                ("user3".to_owned(), 2),
+                ("user1".to_owned(), 3),
+                ("user4".to_owned(), 4),
+                ("user5".to_owned(), 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rated_ranks() {
+        assert_eq!(rated_ranks(&[1, 1, 3, 5, 5]), vec![1.0, 1.0, 3.0, 4.0, 4.0]);
+        assert!(rated_ranks(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_simulate_rating_changes() {
+        let rule = RatingRule::new("virtual", Some(1999));
+        let participants = vec![
+            participant("user3", 3, 1200, 10),
+            participant("user1", 1, 1200, 10),
+            participant("red", 2, 2800, 50),
+            participant("newcomer", 4, 0, 0),
+        ];
+        let changes = simulate_rating_changes(&participants, &rule);
+        let user_ids = changes
+            .iter()
+            .map(|c| c.user_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(user_ids, vec!["user1", "user3", "newcomer"]);
+        assert_eq!(changes[1].rank, 2);
+        assert!(changes[0].delta > 0);
+        assert!(changes[0].performance > changes[1].performance);
+        assert_eq!(
+            changes[0].new_rating - changes[0].old_rating,
+            changes[0].delta
+        );
+        assert_eq!(changes[2].old_rating, 0);
+        assert!(changes[2].new_rating > 0);
+    }
+}
@@ -1,24 +1,52 @@
-use anyhow::Result;
+use crate::compression::{Compression, CompressionFormat};
+use anyhow::{bail, Result};
 
 use s3::bucket::Bucket;
 use s3::credentials::Credentials;
+use s3::region::Region;
 
-const BUCKET_NAME: &str = "kenkoooo.com";
-const REGION: &str = "ap-northeast-1";
+const CONTENT_TYPE: &str = "application/json;charset=utf-8";
+/// The best compression of gzip, which all the browsers decode.
+pub const DEFAULT_COMPRESSION: Compression = Compression {
+    format: CompressionFormat::Gzip,
+    level: Some(9),
+};
 
+/// Uploads the resources compressed by gzip by default, so that the CDN serves them as they are
+/// with `Content-Encoding`. They can not be compressed by zstd, which not all the browsers decode.
 pub struct S3Client {
     bucket: Bucket,
+    compression: Compression,
 }
 
 impl S3Client {
-    pub fn new() -> Result<Self> {
-        let region = REGION.parse()?;
+    pub fn new(
+        bucket_name: &str,
+        region: Region,
+        cache_max_age_second: u64,
+        compression: Compression,
+    ) -> Result<Self> {
+        if compression.format == CompressionFormat::Zstd {
+            bail!("The resources uploaded to S3 can be compressed only by gzip");
+        }
         let credentials = Credentials::default();
-        let bucket = Bucket::new(BUCKET_NAME, region, credentials)?;
-        Ok(Self { bucket })
+        let mut bucket = Bucket::new(bucket_name, region, credentials)?;
+        if let Some(encoding) = compression.format.content_encoding() {
+            bucket.add_header("Content-Encoding", encoding);
+        }
+        bucket.add_header(
+            "Cache-Control",
+            &format!("public, max-age={}", cache_max_age_second),
+        );
+        Ok(Self {
+            bucket,
+            compression,
+        })
     }
 
     pub fn update(&self, data: Vec<u8>, path: &str) -> Result<bool> {
+        // The output is the same for the same data, so the uploaded data can be compared with it.
+        let data = self.compression.compress(&data)?;
         log::info!("Fetching old data ...");
         let old_data = self
             .bucket
@@ -30,9 +58,7 @@ impl S3Client {
             });
         if old_data != data {
             log::info!("Uploading new data to {} ...", path);
-            let (data, status) =
-                self.bucket
-                    .put_object(path, &data, "application/json;charset=utf-8")?;
+            let (data, status) = self.bucket.put_object(path, &data, CONTENT_TYPE)?;
             log::info!("data={:?}", data);
             log::info!("status={}", status);
             Ok(true)
@@ -42,3 +68,23 @@ impl S3Client {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_default_compression() {
+        let data = br#"[{"id":"abc001_a","contest_id":"abc001","title":"A. test"}]"#;
+        let compressed = DEFAULT_COMPRESSION.compress(data).unwrap();
+        assert_eq!(compressed, DEFAULT_COMPRESSION.compress(data).unwrap());
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data.to_vec());
+    }
+}
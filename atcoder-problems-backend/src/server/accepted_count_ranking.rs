@@ -1,4 +1,4 @@
-use crate::server::{AppData, CommonResponse};
+use crate::server::{csv, AppData};
 use serde::Deserialize;
 use sql_client::accepted_count::AcceptedCountClient;
 use tide::{Request, Response, Result};
@@ -18,6 +18,6 @@ pub(crate) async fn get_ac_ranking<A>(request: Request<AppData<A>>) -> Result<Re
         return Ok(Response::new(400));
     }
     let ranking = conn.load_accepted_count_in_range(query).await?;
-    let response = Response::json(&ranking)?;
+    let response = csv::negotiate(&request, &ranking, None)?;
     Ok(response)
 }
@@ -0,0 +1,17 @@
+use crate::server::{AppData, CommonResponse};
+use serde::Deserialize;
+use sql_client::achievement::AchievementClient;
+use sql_client::ids::UserId;
+use tide::{Request, Response, Result};
+
+pub(crate) async fn get_user_achievements<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        user: UserId,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    let achievements = conn.load_achievements(&query.user).await?;
+    let response = Response::json(&achievements)?.make_cors();
+    Ok(response)
+}
@@ -0,0 +1,41 @@
+use crate::server::{AppData, CommonResponse};
+use chrono::Utc;
+use serde::Deserialize;
+use sql_client::crawl_request::{CrawlRequest, CrawlRequestClient};
+use sql_client::data_version::{DataVersionClient, ALL_DATA};
+use tide::{Request, Response, Result, StatusCode};
+
+/// Invalidates the data which the clients have cached, e.g. after the database is fixed manually.
+pub(crate) async fn increment_data_version<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let name = request.param("name")?;
+    if !ALL_DATA.contains(&name) {
+        return Ok(Response::new(StatusCode::NotFound));
+    }
+    let conn = request.state().pg_pool.clone();
+    conn.increment_data_version(name).await?;
+    Ok(Response::ok())
+}
+
+/// Queues the contest to be re-crawled by `crawl requests`, e.g. when users report stale data.
+pub(crate) async fn enqueue_contest_crawl<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let contest_id = request.param("contest_id")?.to_owned();
+    enqueue_crawl(request, CrawlRequest::Contest(contest_id)).await
+}
+
+/// Queues the submissions of the user to be re-crawled by `crawl requests`.
+pub(crate) async fn enqueue_user_crawl<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let user_id = request.param("user_id")?.to_owned();
+    enqueue_crawl(request, CrawlRequest::User(user_id)).await
+}
+
+async fn enqueue_crawl<A>(request: Request<AppData<A>>, crawl: CrawlRequest) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        priority: Option<i32>,
+    }
+    let query = request.query::<Query>()?;
+    let conn = request.state().pg_pool.clone();
+    conn.enqueue_crawl(&crawl, query.priority.unwrap_or(0), Utc::now().timestamp())
+        .await?;
+    Ok(Response::new(StatusCode::Accepted))
+}
@@ -1,6 +1,6 @@
 use crate::server::AppData;
 use async_trait::async_trait;
-use cookie::Cookie;
+use cookie::{Cookie, SameSite};
 use serde::{Deserialize, Serialize};
 use sql_client::internal::user_manager::UserManager;
 use tide::http::headers::LOCATION;
@@ -91,7 +91,13 @@ pub(crate) async fn get_token<A: Authentication + Clone>(
     let internal_user_id = response.id.to_string();
     conn.register_user(&internal_user_id).await?;
 
-    let cookie = Cookie::build("token", token).path("/").finish();
+    // The token is only sent to the API server, and never read by the scripts in the browser.
+    let cookie = Cookie::build("token", token)
+        .path("/")
+        .http_only(true)
+        .secure(true)
+        .same_site(SameSite::Lax)
+        .finish();
     let redirect_fragment = query
         .redirect_to
         .unwrap_or_else(|| "/login/user".to_string());
@@ -102,3 +108,11 @@ pub(crate) async fn get_token<A: Authentication + Clone>(
     response.insert_cookie(cookie);
     Ok(response)
 }
+
+pub(crate) async fn logout<A>(_: Request<AppData<A>>) -> Result<Response> {
+    let mut response = Response::builder(StatusCode::Found)
+        .header(LOCATION, REDIRECT_URL)
+        .build();
+    response.remove_cookie(Cookie::build("token", "").path("/").finish());
+    Ok(response)
+}
@@ -0,0 +1,197 @@
+use crate::server::feed::escape_xml;
+use crate::server::{AppData, CommonResponse};
+use atcoder_client::rating::RatingColor;
+use chrono::Utc;
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::ids::UserId;
+use sql_client::rated_point_sum::RatedPointSumClient;
+use sql_client::streak::{current_streak_alive_since, StreakUpdater};
+use sql_client::user_profile::UserProfileClient;
+use tide::http::headers::CACHE_CONTROL;
+use tide::{Body, Request, Response, Result, StatusCode};
+
+const SVG_MIME: &str = "image/svg+xml; charset=utf-8";
+/// The badges are embedded in the profiles, which are served through the image proxies of GitHub,
+/// so they are cached longer than the API responses.
+const BADGE_MAX_AGE_SECOND: i64 = 6 * 3600;
+const LABEL_COLOR: &str = "#555";
+const AC_COLOR: &str = "#4c1";
+const POINT_SUM_COLOR: &str = "#007ec6";
+/// The approximate widths of a character of Verdana in 11px and the padding of a text.
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BadgeKind {
+    AcceptedCount,
+    Streak,
+    PointSum,
+}
+
+impl BadgeKind {
+    fn from_file_name(name: &str) -> Option<Self> {
+        match name {
+            "ac.svg" => Some(BadgeKind::AcceptedCount),
+            "streak.svg" => Some(BadgeKind::Streak),
+            "point_sum.svg" => Some(BadgeKind::PointSum),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            BadgeKind::AcceptedCount => "AC",
+            BadgeKind::Streak => "streak",
+            BadgeKind::PointSum => "point sum",
+        }
+    }
+}
+
+/// The color of the rating on AtCoder, or gray if the user has no rating.
+fn rating_color(rating: Option<i64>) -> &'static str {
+    match RatingColor::from_rating(rating.unwrap_or(0) as f64) {
+        RatingColor::Black => "#000000",
+        RatingColor::Grey => "#808080",
+        RatingColor::Brown => "#804000",
+        RatingColor::Green => "#008000",
+        RatingColor::Cyan => "#00c0c0",
+        RatingColor::Blue => "#0000ff",
+        RatingColor::Yellow => "#c0c000",
+        RatingColor::Orange => "#ff8000",
+        RatingColor::Red => "#ff0000",
+    }
+}
+
+fn text_width(text: &str) -> usize {
+    text.chars().count() * CHAR_WIDTH + PADDING
+}
+
+/// Renders a badge in the flat style of shields.io, whose label is on the left in gray and whose
+/// value is on the right in `color`.
+fn render_badge(label: &str, value: &str, color: &str) -> String {
+    let label_width = text_width(label);
+    let value_width = text_width(value);
+    let width = label_width + value_width;
+    let label = escape_xml(label);
+    let value = escape_xml(value);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20"
+ role="img" aria-label="{label}: {value}">
+<title>{label}: {value}</title>
+<linearGradient id="s" x2="0" y2="100%">
+<stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/>
+</linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)">
+<rect width="{label_width}" height="20" fill="{label_color}"/>
+<rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+<rect width="{width}" height="20" fill="url(#s)"/>
+</g>
+<g fill="#fff" text-anchor="middle" font-size="11"
+ font-family="Verdana,Geneva,DejaVu Sans,sans-serif">
+<text x="{label_x}" y="14">{label}</text><text x="{value_x}" y="14">{value}</text>
+</g>
+</svg>
+"##,
+        width = width,
+        label = label,
+        value = value,
+        label_width = label_width,
+        value_width = value_width,
+        label_color = LABEL_COLOR,
+        color = color,
+        label_x = label_width / 2,
+        value_x = label_width + value_width / 2,
+    )
+}
+
+/// Returns the badge of `/badge/:user/:file`, where the file is `ac.svg`, `streak.svg` or
+/// `point_sum.svg`. The streak is the current one, which is 0 if it is not alive, and is colored
+/// by the rating of the user.
+pub(crate) async fn get_badge<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let kind = match BadgeKind::from_file_name(request.param("file")?) {
+        Some(kind) => kind,
+        None => return Ok(Response::new(StatusCode::NotFound)),
+    };
+    let user_id = &UserId::from(request.param("user")?);
+    let conn = &request.state().pg_pool;
+    let (value, color) = match kind {
+        BadgeKind::AcceptedCount => {
+            let count = conn.get_users_accepted_count(user_id).await?.unwrap_or(0);
+            (count.to_string(), AC_COLOR)
+        }
+        BadgeKind::Streak => {
+            let alive_since = current_streak_alive_since(Utc::now().timestamp());
+            let streak = conn
+                .get_users_current_streak(user_id, alive_since)
+                .await?
+                .unwrap_or(0);
+            let rating = conn
+                .get_user_profile(user_id)
+                .await?
+                .and_then(|profile| profile.rating);
+            (format!("{} days", streak), rating_color(rating))
+        }
+        BadgeKind::PointSum => {
+            let point_sum = conn
+                .get_users_rated_point_sum(user_id)
+                .await?
+                .unwrap_or(0.0);
+            (format!("{:.0}", point_sum), POINT_SUM_COLOR)
+        }
+    };
+
+    let mut body = Body::from_string(render_badge(kind.label(), &value, color));
+    body.set_mime(SVG_MIME);
+    let mut response = Response::ok();
+    response.set_body(body);
+    response.insert_header(
+        CACHE_CONTROL,
+        format!("public, max-age={}", BADGE_MAX_AGE_SECOND),
+    );
+    Ok(response.make_cors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_kind() {
+        assert_eq!(
+            BadgeKind::from_file_name("ac.svg"),
+            Some(BadgeKind::AcceptedCount)
+        );
+        assert_eq!(
+            BadgeKind::from_file_name("streak.svg"),
+            Some(BadgeKind::Streak)
+        );
+        assert_eq!(
+            BadgeKind::from_file_name("point_sum.svg"),
+            Some(BadgeKind::PointSum)
+        );
+        assert_eq!(BadgeKind::from_file_name("ac.png"), None);
+    }
+
+    #[test]
+    fn test_rating_color() {
+        assert_eq!(rating_color(None), "#808080");
+        assert_eq!(rating_color(Some(399)), "#808080");
+        assert_eq!(rating_color(Some(400)), "#804000");
+        assert_eq!(rating_color(Some(1600)), "#0000ff");
+        assert_eq!(rating_color(Some(2799)), "#ff8000");
+        assert_eq!(rating_color(Some(3500)), "#ff0000");
+    }
+
+    #[test]
+    fn test_render_badge() {
+        let badge = render_badge("AC", "1234", AC_COLOR);
+        // "AC" is 24px and "1234" is 38px wide.
+        assert!(badge.contains(r#"width="62""#));
+        assert!(badge.contains(r##"<rect x="24" width="38" height="20" fill="#4c1"/>"##));
+        assert!(badge.contains("<title>AC: 1234</title>"));
+
+        let badge = render_badge("streak", "<1>", "#808080");
+        assert!(badge.contains("<title>streak: &lt;1&gt;</title>"));
+    }
+}
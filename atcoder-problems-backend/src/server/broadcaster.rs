@@ -0,0 +1,42 @@
+use async_std::channel::{bounded, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+const SUBSCRIBER_BUFFER_SIZE: usize = 1000;
+
+/// Delivers the items to all connected clients.
+pub(crate) struct Broadcaster<T> {
+    subscribers: Arc<Mutex<Vec<Sender<Arc<T>>>>>,
+}
+
+impl<T> Broadcaster<T> {
+    pub(crate) fn subscribe(&self) -> Receiver<Arc<T>> {
+        let (sender, receiver) = bounded(SUBSCRIBER_BUFFER_SIZE);
+        self.subscribers.lock().unwrap().push(sender);
+        receiver
+    }
+
+    pub(crate) fn broadcast(&self, item: T) {
+        let item = Arc::new(item);
+        // Clients which have disconnected or cannot keep up with the stream are dropped.
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.try_send(item.clone()).is_ok());
+    }
+}
+
+impl<T> Clone for Broadcaster<T> {
+    fn clone(&self) -> Self {
+        Self {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<T> Default for Broadcaster<T> {
+    fn default() -> Self {
+        Self {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
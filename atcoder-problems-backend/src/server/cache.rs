@@ -0,0 +1,290 @@
+use crate::server::csv::wants_csv;
+use crate::server::AppData;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sql_client::data_version::DataVersionClient;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tide::http::headers::IF_NONE_MATCH;
+use tide::http::Method;
+use tide::{Body, Response, StatusCode};
+
+const DEFAULT_CACHE_CAPACITY: usize = 1_000;
+const DEFAULT_CACHE_TTL_SECOND: u64 = 300;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(crate) struct CachedResponse {
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+#[async_trait]
+pub(crate) trait CacheStore: Send + Sync {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<CachedResponse>>;
+    async fn set(&self, key: &str, value: &CachedResponse, ttl: Duration) -> anyhow::Result<()>;
+}
+
+/// Uses Redis if `REDIS_URL` is given, so that the replicas of the server can share the cache.
+/// Otherwise, uses an in-process LRU cache which holds up to `CACHE_CAPACITY` responses.
+pub(crate) fn store_from_env() -> anyhow::Result<Arc<dyn CacheStore>> {
+    match env::var("REDIS_URL") {
+        Ok(url) => Ok(Arc::new(RedisCache::new(&url)?)),
+        Err(_) => {
+            let capacity = env::var("CACHE_CAPACITY")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_CACHE_CAPACITY);
+            Ok(Arc::new(MemoryCache::new(capacity)))
+        }
+    }
+}
+
+struct MemoryCacheEntry {
+    value: CachedResponse,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct LruEntries {
+    clock: u64,
+    entries: HashMap<String, MemoryCacheEntry>,
+}
+
+pub(crate) struct MemoryCache {
+    capacity: usize,
+    entries: Mutex<LruEntries>,
+}
+
+impl MemoryCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(LruEntries::default()),
+        }
+    }
+
+    fn get_at(&self, key: &str, now: Instant) -> Option<CachedResponse> {
+        let mut lru = self.entries.lock().unwrap();
+        lru.clock += 1;
+        let clock = lru.clock;
+        match lru.entries.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = clock;
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                lru.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn set_at(&self, key: &str, value: &CachedResponse, ttl: Duration, now: Instant) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lru = self.entries.lock().unwrap();
+        if lru.entries.len() >= self.capacity && !lru.entries.contains_key(key) {
+            lru.entries.retain(|_, entry| entry.expires_at > now);
+        }
+        if lru.entries.len() >= self.capacity && !lru.entries.contains_key(key) {
+            let least_recently_used = lru
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(least_recently_used) = least_recently_used {
+                lru.entries.remove(&least_recently_used);
+            }
+        }
+        lru.clock += 1;
+        let entry = MemoryCacheEntry {
+            value: value.clone(),
+            expires_at: now + ttl,
+            last_used: lru.clock,
+        };
+        lru.entries.insert(key.to_string(), entry);
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<CachedResponse>> {
+        Ok(self.get_at(key, Instant::now()))
+    }
+    async fn set(&self, key: &str, value: &CachedResponse, ttl: Duration) -> anyhow::Result<()> {
+        self.set_at(key, value, ttl, Instant::now());
+        Ok(())
+    }
+}
+
+pub(crate) struct RedisCache {
+    client: redis::Client,
+}
+
+impl RedisCache {
+    pub(crate) fn new(url: &str) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl CacheStore for RedisCache {
+    async fn get(&self, key: &str) -> anyhow::Result<Option<CachedResponse>> {
+        let mut conn = self.client.get_async_std_connection().await?;
+        let value: Option<String> = redis::cmd("GET").arg(key).query_async(&mut conn).await?;
+        match value {
+            Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+            None => Ok(None),
+        }
+    }
+    async fn set(&self, key: &str, value: &CachedResponse, ttl: Duration) -> anyhow::Result<()> {
+        let mut conn = self.client.get_async_std_connection().await?;
+        redis::cmd("SET")
+            .arg(key)
+            .arg(serde_json::to_string(value)?)
+            .arg("EX")
+            .arg(ttl.as_secs())
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Caches successful responses of GET requests for `CACHE_TTL_SECOND` seconds.
+/// The cache key contains the version of `data_name`, so the cached responses are invalidated
+/// as soon as the batch jobs increment the version after updating the data.
+pub(crate) struct CacheMiddleware {
+    store: Arc<dyn CacheStore>,
+    data_name: &'static str,
+    ttl: Duration,
+}
+
+impl CacheMiddleware {
+    pub(crate) fn new(store: Arc<dyn CacheStore>, data_name: &'static str) -> Self {
+        let ttl = env::var("CACHE_TTL_SECOND")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_TTL_SECOND);
+        Self {
+            store,
+            data_name,
+            ttl: Duration::from_secs(ttl),
+        }
+    }
+}
+
+#[async_trait]
+impl<A> tide::Middleware<AppData<A>> for CacheMiddleware
+where
+    A: Clone + Send + Sync + 'static,
+{
+    async fn handle(
+        &self,
+        request: tide::Request<AppData<A>>,
+        next: tide::Next<'_, AppData<A>>,
+    ) -> tide::Result {
+        // Conditional requests are answered by the endpoints without loading the data.
+        if request.method() != Method::Get || request.header(IF_NONE_MATCH).is_some() {
+            return Ok(next.run(request).await);
+        }
+
+        let version = request
+            .state()
+            .pg_pool
+            .load_data_version(self.data_name)
+            .await?;
+        let key = format!(
+            "response:{}:{}:{}:{}?{}",
+            self.data_name,
+            version,
+            if wants_csv(&request) { "csv" } else { "json" },
+            request.url().path(),
+            request.url().query().unwrap_or("")
+        );
+        match self.store.get(&key).await {
+            Ok(Some(cached)) => return Ok(restore(cached)),
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to load the cache {}: {:?}", key, e),
+        }
+
+        let mut response = next.run(request).await;
+        if response.status() != StatusCode::Ok {
+            return Ok(response);
+        }
+        let body = response.take_body().into_string().await?;
+        let headers = response
+            .iter()
+            .flat_map(|(name, values)| {
+                values
+                    .iter()
+                    .map(move |value| (name.as_str().to_string(), value.as_str().to_string()))
+            })
+            .collect();
+        let cached = CachedResponse { headers, body };
+        if let Err(e) = self.store.set(&key, &cached, self.ttl).await {
+            log::error!("Failed to save the cache {}: {:?}", key, e);
+        }
+        Ok(restore(cached))
+    }
+}
+
+fn restore(cached: CachedResponse) -> Response {
+    let mut response = Response::new(StatusCode::Ok);
+    for (name, value) in cached.headers.iter() {
+        response.append_header(name.as_str(), value.as_str());
+    }
+    response.set_body(Body::from_string(cached.body));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &str) -> CachedResponse {
+        CachedResponse {
+            headers: vec![("content-type".to_string(), "application/json".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_memory_cache_expiration() {
+        let cache = MemoryCache::new(10);
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        cache.set_at("a", &response("1"), ttl, now);
+        assert_eq!(
+            cache.get_at("a", now + Duration::from_secs(59)),
+            Some(response("1"))
+        );
+        assert_eq!(cache.get_at("a", now + Duration::from_secs(60)), None);
+        assert_eq!(cache.get_at("b", now), None);
+    }
+
+    #[test]
+    fn test_memory_cache_eviction() {
+        let cache = MemoryCache::new(2);
+        let now = Instant::now();
+        let ttl = Duration::from_secs(60);
+        cache.set_at("a", &response("1"), ttl, now);
+        cache.set_at("b", &response("2"), ttl, now);
+        assert!(cache.get_at("a", now).is_some());
+
+        // "b" is evicted because "a" has been used more recently.
+        cache.set_at("c", &response("3"), ttl, now);
+        assert_eq!(cache.get_at("a", now), Some(response("1")));
+        assert_eq!(cache.get_at("b", now), None);
+        assert_eq!(cache.get_at("c", now), Some(response("3")));
+
+        let cache = MemoryCache::new(0);
+        cache.set_at("a", &response("1"), ttl, now);
+        assert_eq!(cache.get_at("a", now), None);
+    }
+}
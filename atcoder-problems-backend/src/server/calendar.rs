@@ -0,0 +1,201 @@
+use crate::config::BLOCKED_CONTESTS;
+use crate::server::{AppData, CommonResponse};
+use chrono::{TimeZone, Utc};
+use sql_client::models::Contest;
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::ContestWindow;
+use tide::{Body, Request, Response, Result};
+
+const CALENDAR_MIME: &str = "text/calendar; charset=utf-8";
+/// The maximum length of a content line in octets, excluding the line break.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Escapes a text value, in which the backslashes, the commas, the semicolons and the line breaks
+/// are special.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ',' | ';' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends a content line with CRLF, folded into the lines of at most `MAX_LINE_OCTETS` octets
+/// without splitting the UTF-8 characters. The folded lines begin with a space.
+fn push_line(calendar: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            calendar.push_str("\r\n ");
+            octets = 1;
+        }
+        calendar.push(c);
+        octets += c.len_utf8();
+    }
+    calendar.push_str("\r\n");
+}
+
+fn format_time(epoch_second: i64) -> String {
+    Utc.timestamp(epoch_second, 0)
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+pub(crate) fn describe_rated_range(rated_range: Option<(i64, Option<i64>)>) -> String {
+    match rated_range {
+        None => "Unrated".to_owned(),
+        Some((0, None)) => "Rated: All".to_owned(),
+        Some((0, Some(upper))) => format!("Rated: ~ {}", upper),
+        Some((lower, None)) => format!("Rated: {} ~", lower),
+        Some((lower, Some(upper))) => format!("Rated: {} ~ {}", lower, upper),
+    }
+}
+
+/// Renders the contests which have not ended at `now_second` as an iCalendar, in the order of
+/// their start times.
+pub(crate) fn render_calendar(contests: &[Contest], now_second: i64) -> String {
+    let mut contests = contests
+        .iter()
+        .filter(|c| !ContestWindow::of(c).has_ended(now_second))
+        .collect::<Vec<_>>();
+    contests.sort_by(|a, b| {
+        a.start_epoch_second
+            .cmp(&b.start_epoch_second)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+
+    let mut calendar = String::new();
+    push_line(&mut calendar, "BEGIN:VCALENDAR");
+    push_line(&mut calendar, "VERSION:2.0");
+    push_line(&mut calendar, "PRODID:-//AtCoder Problems//Contests//EN");
+    push_line(&mut calendar, "CALSCALE:GREGORIAN");
+    push_line(&mut calendar, "X-WR-CALNAME:AtCoder");
+    let stamp = format_time(now_second);
+    for contest in contests {
+        let window = ContestWindow::of(contest);
+        let url = format!("https://atcoder.jp/contests/{}", contest.id);
+        let description = format!("{}\n{}", describe_rated_range(contest.rated_range), url);
+        push_line(&mut calendar, "BEGIN:VEVENT");
+        push_line(
+            &mut calendar,
+            &format!("UID:{}@kenkoooo.com", escape_text(contest.id.as_str())),
+        );
+        push_line(&mut calendar, &format!("DTSTAMP:{}", stamp));
+        push_line(
+            &mut calendar,
+            &format!("DTSTART:{}", format_time(window.start_epoch_second)),
+        );
+        push_line(
+            &mut calendar,
+            &format!("DTEND:{}", format_time(window.end_epoch_second)),
+        );
+        push_line(
+            &mut calendar,
+            &format!("SUMMARY:{}", escape_text(&contest.title)),
+        );
+        push_line(
+            &mut calendar,
+            &format!("DESCRIPTION:{}", escape_text(&description)),
+        );
+        push_line(&mut calendar, &format!("URL:{}", url));
+        push_line(&mut calendar, "END:VEVENT");
+    }
+    push_line(&mut calendar, "END:VCALENDAR");
+    calendar
+}
+
+/// Returns the upcoming and the running contests as an iCalendar feed, which the calendar apps
+/// can subscribe to.
+pub(crate) async fn get_calendar<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let contests = request
+        .state()
+        .pg_pool
+        .load_contests()
+        .await?
+        .into_iter()
+        .filter(|c| !BLOCKED_CONTESTS.contains(&c.id.as_str()))
+        .collect::<Vec<_>>();
+    let mut body = Body::from_string(render_calendar(&contests, Utc::now().timestamp()));
+    body.set_mime(CALENDAR_MIME);
+    let mut response = Response::ok();
+    response.set_body(body);
+    Ok(response.make_cors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contest(
+        id: &str,
+        start_epoch_second: i64,
+        rated_range: Option<(i64, Option<i64>)>,
+    ) -> Contest {
+        Contest {
+            id: id.into(),
+            start_epoch_second,
+            duration_second: 6000,
+            title: format!("Contest {}", id),
+            rated_range,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_calendar() {
+        let contests = vec![
+            contest("abc200", 1_620_475_200, Some((0, Some(1999)))),
+            contest("ended", 0, None),
+            contest("agc053", 1_620_000_000, Some((1200, None))),
+        ];
+        let calendar = render_calendar(&contests, 1_620_000_000);
+        let lines = calendar.split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.first(), Some(&"BEGIN:VCALENDAR"));
+        assert_eq!(lines[lines.len() - 2], "END:VCALENDAR");
+        assert!(!calendar.contains("UID:ended@"));
+
+        let events = lines
+            .iter()
+            .filter(|line| line.starts_with("UID:"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![&"UID:agc053@kenkoooo.com", &"UID:abc200@kenkoooo.com"]
+        );
+        assert!(lines.contains(&"DTSTART:20210508T120000Z"));
+        assert!(lines.contains(&"DTEND:20210508T134000Z"));
+        assert!(lines.contains(&"DESCRIPTION:Rated: ~ 1999\\nhttps://atcoder.jp/contests/abc200"));
+        assert!(lines.contains(&"DESCRIPTION:Rated: 1200 ~\\nhttps://atcoder.jp/contests/agc053"));
+    }
+
+    #[test]
+    fn test_escape_and_fold() {
+        assert_eq!(escape_text("a,b;c\\d\ne"), "a\\,b\\;c\\\\d\\ne");
+
+        let mut calendar = String::new();
+        let line = format!("SUMMARY:{}", "あ".repeat(30));
+        push_line(&mut calendar, &line);
+        let lines = calendar.trim_end().split("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|l| l.len() <= MAX_LINE_OCTETS));
+        assert_eq!(calendar.trim_end().replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn test_describe_rated_range() {
+        assert_eq!(describe_rated_range(None), "Unrated");
+        assert_eq!(describe_rated_range(Some((0, None))), "Rated: All");
+        assert_eq!(
+            describe_rated_range(Some((1200, Some(2799)))),
+            "Rated: 1200 ~ 2799"
+        );
+    }
+}
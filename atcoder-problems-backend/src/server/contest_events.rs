@@ -0,0 +1,179 @@
+use crate::server::broadcaster::Broadcaster;
+use crate::server::{AppData, CommonResponse};
+use async_std::task;
+use chrono::Utc;
+use sql_client::ids::ContestId;
+use sql_client::models::Contest;
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::ContestWindow;
+use sql_client::PgPool;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tide::http::headers::CACHE_CONTROL;
+use tide::sse::Sender;
+use tide::{Request, Response, Result};
+
+const POLLING_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum ContestEventKind {
+    Announced,
+    Started,
+    Ended,
+}
+
+impl ContestEventKind {
+    fn name(self) -> &'static str {
+        match self {
+            ContestEventKind::Announced => "announced",
+            ContestEventKind::Started => "started",
+            ContestEventKind::Ended => "ended",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct ContestEvent {
+    pub(crate) kind: ContestEventKind,
+    pub(crate) contest: Contest,
+}
+
+/// Finds the events which happened in `(last_checked_second, current_second]`.
+/// Contests which are not in `known_contest_ids` are regarded as newly announced,
+/// unless it is `None` because the contests have not been checked yet.
+fn find_contest_events(
+    contests: &[Contest],
+    known_contest_ids: Option<&BTreeSet<ContestId>>,
+    last_checked_second: i64,
+    current_second: i64,
+) -> Vec<ContestEvent> {
+    let happened =
+        |epoch_second: i64| last_checked_second < epoch_second && epoch_second <= current_second;
+
+    let mut events = Vec::new();
+    for contest in contests.iter() {
+        let window = ContestWindow::of(contest);
+        let kinds = [
+            (
+                ContestEventKind::Announced,
+                known_contest_ids.map_or(false, |ids| !ids.contains(&contest.id)),
+            ),
+            (
+                ContestEventKind::Started,
+                happened(window.start_epoch_second),
+            ),
+            (ContestEventKind::Ended, happened(window.end_epoch_second)),
+        ];
+        for &(kind, happened) in kinds.iter() {
+            if happened {
+                events.push(ContestEvent {
+                    kind,
+                    contest: contest.clone(),
+                });
+            }
+        }
+    }
+    events
+}
+
+/// Checks the contests periodically and broadcasts the events of them.
+pub(crate) async fn watch_contests(broadcaster: Broadcaster<ContestEvent>, pg_pool: PgPool) {
+    let mut known_contest_ids = None;
+    let mut last_checked_second = Utc::now().timestamp();
+    loop {
+        task::sleep(POLLING_INTERVAL).await;
+        let contests = match pg_pool.load_contests().await {
+            Ok(contests) => contests,
+            Err(e) => {
+                log::error!("Failed to load contests: {:?}", e);
+                continue;
+            }
+        };
+
+        let current_second = Utc::now().timestamp();
+        let events = find_contest_events(
+            &contests,
+            known_contest_ids.as_ref(),
+            last_checked_second,
+            current_second,
+        );
+        for event in events.into_iter() {
+            broadcaster.broadcast(event);
+        }
+        known_contest_ids = Some(contests.into_iter().map(|c| c.id).collect());
+        last_checked_second = current_second;
+    }
+}
+
+async fn stream_contest_events<A>(request: Request<AppData<A>>, sender: Sender) -> Result<()> {
+    let receiver = request.state().contest_event_broadcaster.subscribe();
+    while let Ok(event) = receiver.recv().await {
+        let name = event.kind.name();
+        let data = serde_json::to_string(&event.contest)?;
+        let id = format!("{}:{}", event.contest.id, name);
+        sender.send(name, data, Some(&id)).await?;
+    }
+    Ok(())
+}
+
+pub(crate) async fn get_contest_events<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Clone + Send + Sync + 'static,
+{
+    let mut response = tide::sse::upgrade(request, stream_contest_events);
+    // Prevents the compression middleware from buffering the events.
+    response.insert_header(CACHE_CONTROL, "no-transform");
+    Ok(response.make_cors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contest(id: &str, start_epoch_second: i64, duration_second: i64) -> Contest {
+        Contest {
+            id: id.into(),
+            start_epoch_second,
+            duration_second,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_contest_events() {
+        let contests = vec![
+            contest("finished", 0, 100),
+            contest("running", 150, 100),
+            contest("upcoming", 1000, 100),
+        ];
+
+        let events = find_contest_events(&contests, None, 90, 200);
+        let events = events
+            .iter()
+            .map(|e| (e.contest.id.as_str(), e.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ("finished", ContestEventKind::Ended),
+                ("running", ContestEventKind::Started),
+            ]
+        );
+
+        let known_contest_ids = vec![ContestId::from("finished"), ContestId::from("running")]
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        let events = find_contest_events(&contests, Some(&known_contest_ids), 200, 250);
+        let events = events
+            .iter()
+            .map(|e| (e.contest.id.as_str(), e.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                ("running", ContestEventKind::Ended),
+                ("upcoming", ContestEventKind::Announced),
+            ]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::server::{AppData, CommonResponse};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sql_client::contest_standings::ContestStandingsClient;
+use sql_client::ids::ContestId;
+use sql_client::models::{Contest, ContestStandings};
+use sql_client::simple_client::SimpleClient;
+use sql_client::time::ContestWindow;
+use tide::http::headers::CACHE_CONTROL;
+use tide::{Request, Response, Result, StatusCode};
+
+/// How often `crawl_standings` refreshes the standings of the running contests.
+const STANDINGS_REFRESH_SECOND: i64 = 60;
+const FINAL_STANDINGS_MAX_AGE_SECOND: i64 = 3600;
+
+#[derive(Serialize, Debug)]
+struct StandingsResponse {
+    #[serde(flatten)]
+    standings: ContestStandings,
+    is_running: bool,
+    /// True if the standings are fetched after the contest ended.
+    is_final: bool,
+}
+
+/// Returns how long the clients can cache the standings, which are refreshed every minute
+/// while the contest is running or until the standings after the end are fetched.
+fn max_age_second(contest: &Contest, updated_epoch_second: i64, now: i64) -> i64 {
+    if ContestWindow::of(contest).has_ended(updated_epoch_second) {
+        FINAL_STANDINGS_MAX_AGE_SECOND
+    } else {
+        (updated_epoch_second + STANDINGS_REFRESH_SECOND - now).clamp(0, STANDINGS_REFRESH_SECOND)
+    }
+}
+
+pub(crate) async fn get_contest_standings<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        contest: ContestId,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    let contest = conn
+        .load_contests()
+        .await?
+        .into_iter()
+        .find(|c| c.id == query.contest);
+    let standings = conn.load_contest_standings(&query.contest).await?;
+    let (contest, standings) = match (contest, standings) {
+        (Some(contest), Some(standings)) => (contest, standings),
+        _ => return Ok(Response::new(StatusCode::NotFound)),
+    };
+
+    let now = Utc::now().timestamp();
+    let window = ContestWindow::of(&contest);
+    let max_age = max_age_second(&contest, standings.updated_epoch_second, now);
+    let body = StandingsResponse {
+        is_running: window.is_running(now),
+        is_final: window.has_ended(standings.updated_epoch_second),
+        standings,
+    };
+    let mut response = Response::json(&body)?.make_cors();
+    response.insert_header(CACHE_CONTROL, format!("max-age={}", max_age));
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_age_second() {
+        let contest = Contest {
+            id: "abc200".into(),
+            start_epoch_second: 1000,
+            duration_second: 6000,
+            title: "".to_owned(),
+            rate_change: " ~ 1999".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(max_age_second(&contest, 2000, 2010), 50);
+        assert_eq!(max_age_second(&contest, 2000, 2100), 0);
+        assert_eq!(max_age_second(&contest, 2000, 1990), 60);
+        assert_eq!(max_age_second(&contest, 6990, 7010), 40);
+        assert_eq!(max_age_second(&contest, 7000, 7010), 3600);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::server::{AppData, CommonResponse};
+use serde::Deserialize;
+use sql_client::crawler_run::CrawlerRunClient;
+use sql_client::ids::ContestId;
+use tide::{Request, Response, Result};
+
+const DEFAULT_RUN_COUNT: i64 = 20;
+const MAX_RUN_COUNT: i64 = 1_000;
+
+/// Returns the latest runs of the jobs, e.g. `?contest_id=abc330&limit=1` tells
+/// when the submissions of abc330 were crawled last.
+pub(crate) async fn get_crawler_runs<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        job: Option<String>,
+        contest_id: Option<ContestId>,
+        limit: Option<i64>,
+    }
+    let query = request.query::<Query>()?;
+    let limit = query.limit.unwrap_or(DEFAULT_RUN_COUNT);
+    if limit <= 0 || limit > MAX_RUN_COUNT {
+        return Ok(Response::new(400));
+    }
+    let conn = request.state().pg_pool.clone();
+    let runs = conn
+        .load_crawler_runs(query.job.as_deref(), query.contest_id.as_ref(), limit)
+        .await?;
+    Ok(Response::json(&runs)?)
+}
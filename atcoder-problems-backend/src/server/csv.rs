@@ -0,0 +1,305 @@
+use crate::server::CommonResponse;
+use anyhow::anyhow;
+use async_std::channel::{self, Sender};
+use async_std::task;
+use futures::stream::TryStreamExt;
+use serde::Serialize;
+use serde_json::Value;
+use std::future::Future;
+use std::io;
+use tide::http::headers::{ACCEPT, VARY};
+use tide::{Body, Request, Response, Result};
+
+pub(crate) const CSV_MIME: &str = "text/csv; charset=utf-8";
+
+/// How many lines are buffered while the client reads slower than the rows arrive.
+const STREAM_BUFFER_LINES: usize = 1_000;
+
+/// Returns true if the client asks for CSV by `Accept: text/csv` or `format=csv`.
+pub(crate) fn wants_csv<A>(request: &Request<A>) -> bool {
+    let accepts_csv = request
+        .header(ACCEPT)
+        .map(|accept| accept.as_str().contains("text/csv"))
+        .unwrap_or(false);
+    let format_csv = request
+        .url()
+        .query_pairs()
+        .any(|(key, value)| key == "format" && value == "csv");
+    accepts_csv || format_csv
+}
+
+/// Responds with `rows` as CSV if the client asks for it, or as JSON otherwise.
+/// Only the comma-separated `fields` of each row are included if specified.
+pub(crate) fn negotiate<A, S: Serialize>(
+    request: &Request<A>,
+    rows: &S,
+    fields: Option<&str>,
+) -> Result<Response> {
+    let mut response = if wants_csv(request) {
+        let rows = serde_json::to_value(rows)?;
+        let mut body = Body::from_string(to_csv(&rows, fields));
+        body.set_mime(CSV_MIME);
+        let mut response = Response::ok();
+        response.set_body(body);
+        response
+    } else {
+        Response::json_with_fields(rows, fields)?
+    };
+    response.insert_header(VARY, "Accept");
+    Ok(response)
+}
+
+/// Responds with the rows which `send_rows` sends to the `CsvSender` as CSV. Each line is sent to
+/// the client as soon as the row is sent, e.g. as it arrives from a database cursor, so the rows
+/// are never held in memory all at once. The columns are decided as `to_csv` does.
+pub(crate) async fn stream_csv<F, Fut>(fields: Option<&str>, send_rows: F) -> Result<Response>
+where
+    F: FnOnce(CsvSender) -> Fut,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let (sender, receiver) = channel::bounded(STREAM_BUFFER_LINES);
+    let errors = sender.clone();
+    let mut csv = CsvSender {
+        columns: None,
+        sender,
+    };
+    if let Some(fields) = fields {
+        csv.send_header(parse_fields(fields)).await?;
+    }
+    let rows = send_rows(csv);
+    task::spawn(async move {
+        if let Err(e) = rows.await {
+            // The status has already been sent, so the response is aborted instead.
+            log::error!("Failed to stream the rows as CSV: {:?}", e);
+            let _ = errors
+                .send(Err(io::Error::new(io::ErrorKind::Other, e.to_string())))
+                .await;
+        }
+    });
+
+    let mut body = Body::from_reader(receiver.into_async_read(), None);
+    body.set_mime(CSV_MIME);
+    let mut response = Response::ok();
+    response.set_body(body);
+    response.insert_header(VARY, "Accept");
+    Ok(response)
+}
+
+/// Formats the rows sent to it as the lines of the response of `stream_csv`.
+pub(crate) struct CsvSender {
+    columns: Option<Vec<String>>,
+    sender: Sender<io::Result<Vec<u8>>>,
+}
+
+impl CsvSender {
+    /// Fails if the client has disconnected, so that the caller stops reading the rows.
+    pub(crate) async fn send<S: Serialize>(&mut self, row: &S) -> anyhow::Result<()> {
+        let row = serde_json::to_value(row)?;
+        if self.columns.is_none() {
+            self.send_header(columns_of(std::slice::from_ref(&row)))
+                .await?;
+        }
+        match self.columns.as_deref() {
+            Some(columns) if !columns.is_empty() => self.send_line(format_row(columns, &row)).await,
+            _ => Ok(()),
+        }
+    }
+
+    async fn send_header(&mut self, columns: Vec<String>) -> anyhow::Result<()> {
+        if !columns.is_empty() {
+            self.send_line(format_header(&columns)).await?;
+        }
+        self.columns = Some(columns);
+        Ok(())
+    }
+
+    async fn send_line(&self, line: String) -> anyhow::Result<()> {
+        self.sender
+            .send(Ok(line.into_bytes()))
+            .await
+            .map_err(|_| anyhow!("The client has disconnected"))
+    }
+}
+
+/// Formats an array of JSON objects as CSV with a header line.
+/// The columns are `fields` in the given order if specified, or the keys of the first row otherwise.
+pub(crate) fn to_csv(rows: &Value, fields: Option<&str>) -> String {
+    let rows = match rows {
+        Value::Array(rows) => rows.as_slice(),
+        row => std::slice::from_ref(row),
+    };
+    let columns = match fields {
+        Some(fields) => parse_fields(fields),
+        None => columns_of(rows),
+    };
+    if columns.is_empty() {
+        return String::new();
+    }
+
+    let mut csv = format_header(&columns);
+    for row in rows {
+        csv.push_str(&format_row(&columns, row));
+    }
+    csv
+}
+
+fn parse_fields(fields: &str) -> Vec<String> {
+    fields.split(',').map(|f| f.trim().to_string()).collect()
+}
+
+fn columns_of(rows: &[Value]) -> Vec<String> {
+    rows.first()
+        .and_then(|row| row.as_object())
+        .map(|row| row.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default()
+}
+
+fn format_header(columns: &[String]) -> String {
+    format_line(columns.iter().map(|column| escape(column)))
+}
+
+fn format_row(columns: &[String], row: &Value) -> String {
+    format_line(columns.iter().map(|column| match row.get(column) {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => escape(value),
+        Some(value) => escape(&value.to_string()),
+    }))
+}
+
+fn format_line<I: Iterator<Item = String>>(cells: I) -> String {
+    let mut line = cells.collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+fn escape(cell: &str) -> String {
+    if cell.contains(|c| c == ',' || c == '"' || c == '\r' || c == '\n') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Parses CSV into the records of the cells, which may be quoted as `to_csv` does. The empty
+/// lines and the byte order mark, which the spreadsheets often write, are skipped.
+pub(crate) fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => quoted = false,
+                _ => cell.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' => quoted = true,
+            ',' => record.push(std::mem::take(&mut cell)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut cell));
+                if record.len() == 1 && record[0].is_empty() {
+                    record.clear();
+                } else {
+                    records.push(std::mem::take(&mut record));
+                }
+            }
+            _ => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !record.is_empty() {
+        record.push(cell);
+        records.push(record);
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_to_csv() {
+        let rows = json!([
+            {"id": 1, "problem_id": "abc001_a", "result": "AC", "execution_time": 10},
+            {"id": 2, "problem_id": "abc001_b", "result": "WA", "execution_time": null}
+        ]);
+        assert_eq!(
+            to_csv(&rows, None),
+            "execution_time,id,problem_id,result\r\n10,1,abc001_a,AC\r\n,2,abc001_b,WA\r\n"
+        );
+        assert_eq!(
+            to_csv(&rows, Some("result,id")),
+            "result,id\r\nAC,1\r\nWA,2\r\n"
+        );
+        assert_eq!(to_csv(&json!([]), None), "");
+        assert_eq!(to_csv(&json!([]), Some("id")), "id\r\n");
+    }
+
+    #[test]
+    fn test_stream_csv() {
+        let stream = |rows: Value, fields: Option<&str>| {
+            task::block_on(async {
+                let mut response = stream_csv(fields, |mut csv| async move {
+                    for row in rows.as_array().unwrap() {
+                        csv.send(row).await?;
+                    }
+                    Ok(())
+                })
+                .await
+                .unwrap();
+                response.take_body().into_string().await.unwrap()
+            })
+        };
+        let rows = json!([
+            {"id": 1, "problem_id": "abc001_a", "result": "AC"},
+            {"id": 2, "problem_id": "abc001_b", "result": "WA"}
+        ]);
+        assert_eq!(stream(rows.clone(), None), to_csv(&rows, None));
+        assert_eq!(
+            stream(rows.clone(), Some("result,id")),
+            "result,id\r\nAC,1\r\nWA,2\r\n"
+        );
+        assert_eq!(stream(json!([]), None), "");
+        assert_eq!(stream(json!([]), Some("id")), "id\r\n");
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let rows = json!([
+            {"id": "a,b", "note": "say \"hi\"\nbye"},
+            {"id": "c", "note": ""}
+        ]);
+        let csv = to_csv(&rows, Some("id,note"));
+        assert_eq!(
+            parse_csv(&csv),
+            vec![
+                vec!["id", "note"],
+                vec!["a,b", "say \"hi\"\nbye"],
+                vec!["c", ""]
+            ]
+        );
+        assert_eq!(
+            parse_csv("\u{feff}id,note\n\nx,y"),
+            vec![vec!["id", "note"], vec!["x", "y"]]
+        );
+        assert!(parse_csv("").is_empty());
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Rust"), "Rust");
+        assert_eq!(escape("C++ (GCC 9.2.1)"), "C++ (GCC 9.2.1)");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,212 @@
+use crate::config::{BLOCKED_CONTESTS, BLOCKED_PROBLEMS};
+use crate::server::calendar::describe_rated_range;
+use crate::server::{AppData, CommonResponse};
+use chrono::{SecondsFormat, TimeZone, Utc};
+use sql_client::models::{Contest, Problem};
+use sql_client::simple_client::SimpleClient;
+use sql_client::PgPool;
+use std::collections::BTreeMap;
+use tide::{Body, Request, Response, Result};
+
+const ATOM_MIME: &str = "application/atom+xml; charset=utf-8";
+const FEED_URL: &str = "https://kenkoooo.com/atcoder/feed.atom";
+/// The number of the latest entries in the feed.
+const FEED_SIZE: usize = 50;
+
+/// An entry of the feed. The contests and the problems are dated by the start times of the
+/// contests, since the times when they were added are not stored.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    id: String,
+    title: String,
+    updated_epoch_second: i64,
+    summary: String,
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_time(epoch_second: i64) -> String {
+    Utc.timestamp(epoch_second, 0)
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Returns the latest `FEED_SIZE` entries of the contests and their problems, the newest first.
+fn latest_entries(contests: &[Contest], problems: &[Problem]) -> Vec<Entry> {
+    let contests_by_id = contests
+        .iter()
+        .map(|c| (c.id.as_str(), c))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut entries = Vec::with_capacity(contests.len() + problems.len());
+    for contest in contests.iter() {
+        entries.push(Entry {
+            id: format!("https://atcoder.jp/contests/{}", contest.id),
+            title: contest.title.clone(),
+            updated_epoch_second: contest.start_epoch_second,
+            summary: format!(
+                "Starts at {} for {} minutes. {}",
+                format_time(contest.start_epoch_second),
+                contest.duration_second / 60,
+                describe_rated_range(contest.rated_range)
+            ),
+        });
+    }
+    for problem in problems.iter() {
+        let contest = match contests_by_id.get(problem.contest_id.as_str()) {
+            Some(contest) => contest,
+            None => continue,
+        };
+        entries.push(Entry {
+            id: format!(
+                "https://atcoder.jp/contests/{}/tasks/{}",
+                problem.contest_id, problem.id
+            ),
+            title: format!("{}: {}", contest.title, problem.title),
+            updated_epoch_second: contest.start_epoch_second,
+            summary: format!("A problem of {}.", contest.title),
+        });
+    }
+    entries.sort_by(|a, b| {
+        b.updated_epoch_second
+            .cmp(&a.updated_epoch_second)
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    entries.truncate(FEED_SIZE);
+    entries
+}
+
+/// Renders the Atom feed of the new contests and problems. It depends only on the contests and
+/// the problems, so that `snapshot` does not rewrite the file until they change.
+pub(crate) fn render_feed(contests: &[Contest], problems: &[Problem]) -> String {
+    let entries = latest_entries(contests, problems);
+    let updated = entries.first().map_or(0, |e| e.updated_epoch_second);
+
+    let mut feed = String::new();
+    feed.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    feed.push_str("  <title>AtCoder Problems: New Contests and Problems</title>\n");
+    feed.push_str(&format!("  <id>{}</id>\n", FEED_URL));
+    feed.push_str(&format!("  <link rel=\"self\" href=\"{}\"/>\n", FEED_URL));
+    feed.push_str(&format!("  <updated>{}</updated>\n", format_time(updated)));
+    feed.push_str("  <author><name>AtCoder Problems</name></author>\n");
+    for entry in entries.iter() {
+        feed.push_str("  <entry>\n");
+        feed.push_str(&format!("    <id>{}</id>\n", escape_xml(&entry.id)));
+        feed.push_str(&format!(
+            "    <title>{}</title>\n",
+            escape_xml(&entry.title)
+        ));
+        feed.push_str(&format!("    <link href=\"{}\"/>\n", escape_xml(&entry.id)));
+        feed.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            format_time(entry.updated_epoch_second)
+        ));
+        feed.push_str(&format!(
+            "    <summary>{}</summary>\n",
+            escape_xml(&entry.summary)
+        ));
+        feed.push_str("  </entry>\n");
+    }
+    feed.push_str("</feed>\n");
+    feed
+}
+
+/// Loads the contests and the problems except the blocked ones, and renders the feed.
+pub(crate) async fn load_feed(pg_pool: &PgPool) -> anyhow::Result<String> {
+    let contests = pg_pool
+        .load_contests()
+        .await?
+        .into_iter()
+        .filter(|c| !BLOCKED_CONTESTS.contains(&c.id.as_str()))
+        .collect::<Vec<_>>();
+    let problems = pg_pool
+        .load_problems()
+        .await?
+        .into_iter()
+        .filter(|p| !BLOCKED_PROBLEMS.contains(&p.id.as_str()))
+        .collect::<Vec<_>>();
+    Ok(render_feed(&contests, &problems))
+}
+
+pub(crate) async fn get_feed<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let feed = load_feed(&request.state().pg_pool).await?;
+    let mut body = Body::from_string(feed);
+    body.set_mime(ATOM_MIME);
+    let mut response = Response::ok();
+    response.set_body(body);
+    Ok(response.make_cors())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contest(id: &str, title: &str, start_epoch_second: i64) -> Contest {
+        Contest {
+            id: id.into(),
+            title: title.to_owned(),
+            start_epoch_second,
+            duration_second: 6000,
+            ..Default::default()
+        }
+    }
+
+    fn problem(id: &str, contest_id: &str) -> Problem {
+        Problem {
+            id: id.into(),
+            contest_id: contest_id.into(),
+            title: format!("A. {}", id),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_latest_entries() {
+        let contests = vec![
+            contest("abc001", "ABC 001", 100),
+            contest("abc002", "ABC 002", 200),
+        ];
+        let problems = vec![
+            problem("abc001_a", "abc001"),
+            problem("unknown_a", "unknown"),
+        ];
+        let entries = latest_entries(&contests, &problems);
+        let ids = entries.iter().map(|e| e.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                "https://atcoder.jp/contests/abc002",
+                "https://atcoder.jp/contests/abc001",
+                "https://atcoder.jp/contests/abc001/tasks/abc001_a",
+            ]
+        );
+        assert_eq!(entries[2].title, "ABC 001: A. abc001_a");
+        assert_eq!(
+            entries[0].summary,
+            "Starts at 1970-01-01T00:03:20Z for 100 minutes. Unrated"
+        );
+    }
+
+    #[test]
+    fn test_render_feed() {
+        let contests = vec![contest("arc001", "AtCoder <Regular> Contest & 001", 1000)];
+        let feed = render_feed(&contests, &[]);
+        assert!(feed.contains("<updated>1970-01-01T00:16:40Z</updated>"));
+        assert!(feed.contains("<title>AtCoder &lt;Regular&gt; Contest &amp; 001</title>"));
+        assert_eq!(feed.matches("<entry>").count(), 1);
+        assert_eq!(render_feed(&contests, &[]), feed);
+    }
+}
@@ -0,0 +1,345 @@
+use crate::config::{BLOCKED_CONTESTS, BLOCKED_PROBLEMS};
+use anyhow::anyhow;
+use async_graphql::connection::{query, Connection, CursorType, Edge, EmptyFields};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Object, Result, Schema, SimpleObject,
+};
+use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::ids::UserId;
+use sql_client::merged_problem::MergedProblemClient;
+use sql_client::models;
+use sql_client::problem_difficulty::ProblemDifficultyClient;
+use sql_client::rated_point_sum::RatedPointSumClient;
+use sql_client::simple_client::SimpleClient;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::PgPool;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1_000;
+
+pub(crate) type AtCoderProblemsSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub(crate) fn build_schema(pg_pool: PgPool) -> AtCoderProblemsSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(pg_pool)
+        .finish()
+}
+
+#[derive(SimpleObject)]
+struct Contest {
+    id: String,
+    start_epoch_second: i64,
+    duration_second: i64,
+    title: String,
+    rate_change: String,
+    category: String,
+    /// `None` if the contest is unrated.
+    rated_lower_bound: Option<i64>,
+    /// `None` if the contest is unrated or has no upper bound.
+    rated_upper_bound: Option<i64>,
+    /// `None` if the rules are not crawled yet.
+    penalty_minutes: Option<i64>,
+    allows_late_join: Option<bool>,
+}
+
+#[derive(SimpleObject)]
+struct Problem {
+    id: String,
+    contest_id: String,
+    title: String,
+    problem_index: String,
+    name: String,
+    point: Option<f64>,
+    solver_count: Option<i32>,
+    difficulty: Option<f64>,
+}
+
+#[derive(SimpleObject)]
+struct Submission {
+    id: i64,
+    epoch_second: i64,
+    problem_id: String,
+    contest_id: String,
+    user_id: String,
+    language: String,
+    point: f64,
+    length: i32,
+    result: String,
+    execution_time: Option<i32>,
+}
+
+impl From<models::Submission> for Submission {
+    fn from(s: models::Submission) -> Self {
+        Self {
+            id: s.id,
+            epoch_second: s.epoch_second,
+            problem_id: s.problem_id.into(),
+            contest_id: s.contest_id.into(),
+            user_id: s.user_id.into(),
+            language: s.language,
+            point: s.point,
+            length: s.length,
+            result: s.result,
+            execution_time: s.execution_time,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct AcceptedCountEntry {
+    user_id: String,
+    problem_count: i32,
+}
+
+#[derive(SimpleObject)]
+struct RatedPointSumEntry {
+    user_id: String,
+    point_sum: f64,
+}
+
+/// Submissions of a user are paginated in the order of `(epoch_second, id)`.
+struct SubmissionCursor {
+    epoch_second: i64,
+    id: i64,
+}
+
+impl CursorType for SubmissionCursor {
+    type Error = anyhow::Error;
+
+    fn decode_cursor(s: &str) -> std::result::Result<Self, Self::Error> {
+        let mut parts = s.splitn(2, ':');
+        let epoch_second = parts.next().unwrap_or("").parse()?;
+        let id = parts
+            .next()
+            .ok_or_else(|| anyhow!("Invalid cursor: {}", s))?
+            .parse()?;
+        Ok(Self { epoch_second, id })
+    }
+
+    fn encode_cursor(&self) -> String {
+        format!("{}:{}", self.epoch_second, self.id)
+    }
+}
+
+struct User {
+    id: UserId,
+}
+
+#[Object]
+impl User {
+    async fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// 0 if the user has no accepted submissions.
+    async fn accepted_count(&self, ctx: &Context<'_>) -> Result<i32> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let accepted_count = pool.get_users_accepted_count(&self.id).await?;
+        Ok(accepted_count.unwrap_or(0))
+    }
+
+    async fn accepted_count_rank(&self, ctx: &Context<'_>) -> Result<i64> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let accepted_count = pool.get_users_accepted_count(&self.id).await?.unwrap_or(0);
+        let rank = pool.get_accepted_count_rank(accepted_count).await?;
+        Ok(rank)
+    }
+
+    /// 0 if the user has no accepted submissions of the rated problems.
+    async fn rated_point_sum(&self, ctx: &Context<'_>) -> Result<f64> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let rated_point_sum = pool.get_users_rated_point_sum(&self.id).await?;
+        Ok(rated_point_sum.unwrap_or(0.0))
+    }
+
+    async fn rated_point_sum_rank(&self, ctx: &Context<'_>) -> Result<i64> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let rated_point_sum = pool
+            .get_users_rated_point_sum(&self.id)
+            .await?
+            .unwrap_or(0.0);
+        let rank = pool.get_rated_point_sum_rank(rated_point_sum).await?;
+        Ok(rank)
+    }
+
+    async fn submissions(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Connection<SubmissionCursor, Submission, EmptyFields, EmptyFields>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<SubmissionCursor>,
+             _before: Option<SubmissionCursor>,
+             first: Option<usize>,
+             _last: Option<usize>| async move {
+                let count = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+                let request = match after.as_ref() {
+                    Some(cursor) => SubmissionRequest::FromUserAndCursor {
+                        user_id: &self.id,
+                        from_second: cursor.epoch_second,
+                        from_id: cursor.id,
+                        count: count as i64 + 1,
+                    },
+                    None => SubmissionRequest::FromUserAndTime {
+                        user_id: &self.id,
+                        from_second: 0,
+                        count: count as i64 + 1,
+                    },
+                };
+                let mut submissions = pool.get_submissions(request).await?;
+                let has_next_page = submissions.len() > count;
+                submissions.truncate(count);
+
+                let mut connection = Connection::new(after.is_some(), has_next_page);
+                connection.append(submissions.into_iter().map(|s| {
+                    let cursor = SubmissionCursor {
+                        epoch_second: s.epoch_second,
+                        id: s.id,
+                    };
+                    Edge::new(cursor, Submission::from(s))
+                }));
+                Ok(connection)
+            },
+        )
+        .await
+    }
+}
+
+pub(crate) struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn contests(&self, ctx: &Context<'_>) -> Result<Vec<Contest>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let mut contests = pool
+            .load_contests()
+            .await?
+            .into_iter()
+            .filter(|c| !BLOCKED_CONTESTS.contains(&c.id.as_str()))
+            .map(|c| Contest {
+                id: c.id.into(),
+                start_epoch_second: c.start_epoch_second,
+                duration_second: c.duration_second,
+                title: c.title,
+                rate_change: c.rate_change,
+                category: c.category.as_str().to_owned(),
+                rated_lower_bound: c.rated_range.map(|(lower, _)| lower),
+                rated_upper_bound: c.rated_range.and_then(|(_, upper)| upper),
+                penalty_minutes: c.penalty_minutes,
+                allows_late_join: c.allows_late_join,
+            })
+            .collect::<Vec<_>>();
+        contests.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(contests)
+    }
+
+    async fn problems(&self, ctx: &Context<'_>) -> Result<Vec<Problem>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        let difficulties = pool.load_problem_difficulties().await?;
+        let problems = pool
+            .load_merged_problems()
+            .await?
+            .into_iter()
+            .filter(|p| !BLOCKED_PROBLEMS.contains(&p.id.as_str()))
+            .map(|p| Problem {
+                difficulty: difficulties.get(p.id.as_str()).copied(),
+                id: p.id.into(),
+                contest_id: p.contest_id.into(),
+                title: p.title,
+                problem_index: p.problem_index,
+                name: p.name,
+                point: p.point,
+                solver_count: p.solver_count,
+            })
+            .collect();
+        Ok(problems)
+    }
+
+    async fn user(&self, id: String) -> User {
+        User {
+            id: UserId::from(id),
+        }
+    }
+
+    async fn accepted_count_ranking(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Connection<usize, AcceptedCountEntry, EmptyFields, EmptyFields>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<usize>,
+             _before: Option<usize>,
+             first: Option<usize>,
+             _last: Option<usize>| async move {
+                let start = after.map(|after| after + 1).unwrap_or(0);
+                let count = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+                let mut ranking = pool
+                    .load_accepted_count_in_range(start..(start + count + 1))
+                    .await?;
+                let has_next_page = ranking.len() > count;
+                ranking.truncate(count);
+
+                let mut connection = Connection::new(start > 0, has_next_page);
+                connection.append(ranking.into_iter().enumerate().map(|(i, entry)| {
+                    let entry = AcceptedCountEntry {
+                        user_id: entry.user_id,
+                        problem_count: entry.problem_count,
+                    };
+                    Edge::new(start + i, entry)
+                }));
+                Ok(connection)
+            },
+        )
+        .await
+    }
+
+    async fn rated_point_sum_ranking(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        first: Option<i32>,
+    ) -> Result<Connection<usize, RatedPointSumEntry, EmptyFields, EmptyFields>> {
+        let pool = ctx.data_unchecked::<PgPool>();
+        query(
+            after,
+            None,
+            first,
+            None,
+            |after: Option<usize>,
+             _before: Option<usize>,
+             first: Option<usize>,
+             _last: Option<usize>| async move {
+                let start = after.map(|after| after + 1).unwrap_or(0);
+                let count = first.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+                let mut ranking = pool
+                    .load_rated_point_sum_in_range(start..(start + count + 1))
+                    .await?;
+                let has_next_page = ranking.len() > count;
+                ranking.truncate(count);
+
+                let mut connection = Connection::new(start > 0, has_next_page);
+                connection.append(ranking.into_iter().enumerate().map(|(i, entry)| {
+                    let entry = RatedPointSumEntry {
+                        user_id: entry.user_id,
+                        point_sum: entry.point_sum,
+                    };
+                    Edge::new(start + i, entry)
+                }));
+                Ok(connection)
+            },
+        )
+        .await
+    }
+}
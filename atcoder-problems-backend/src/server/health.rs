@@ -0,0 +1,48 @@
+use crate::server::{AppData, CommonResponse};
+use chrono::Utc;
+use serde_json::json;
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use std::env;
+use tide::{Request, Response, Result, StatusCode};
+
+const DEFAULT_MAX_DATA_AGE_SECOND: i64 = 3600;
+
+/// Returns 200 if the database is reachable and the latest submission is not older than
+/// `READINESS_MAX_DATA_AGE_SECOND`, which means the crawlers are working. Otherwise returns 503.
+pub(crate) async fn get_readiness<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let max_data_age_second = env::var("READINESS_MAX_DATA_AGE_SECOND")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_MAX_DATA_AGE_SECOND);
+
+    let conn = request.state().pg_pool.clone();
+    let submissions = match conn
+        .get_submissions(SubmissionRequest::RecentAll { count: 1 })
+        .await
+    {
+        Ok(submissions) => submissions,
+        Err(e) => {
+            log::error!("Database is not reachable: {:?}", e);
+            let response = Response::json(&json!({"reason": "database is not reachable"}))?;
+            return Ok(with_status(response, StatusCode::ServiceUnavailable));
+        }
+    };
+
+    let latest_epoch_second = submissions.first().map(|s| s.epoch_second);
+    let data_age_second =
+        latest_epoch_second.map(|epoch_second| Utc::now().timestamp() - epoch_second);
+    let body = json!({
+        "latest_submission_epoch_second": latest_epoch_second,
+        "data_age_second": data_age_second,
+    });
+    let response = Response::json(&body)?;
+    match data_age_second {
+        Some(age) if age <= max_data_age_second => Ok(response),
+        _ => Ok(with_status(response, StatusCode::ServiceUnavailable)),
+    }
+}
+
+fn with_status(mut response: Response, status: StatusCode) -> Response {
+    response.set_status(status);
+    response
+}
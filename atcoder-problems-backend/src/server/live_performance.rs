@@ -0,0 +1,17 @@
+use crate::server::{AppData, CommonResponse};
+use serde::Deserialize;
+use sql_client::ids::ContestId;
+use sql_client::live_performance::LivePerformanceClient;
+use tide::{Request, Response, Result};
+
+pub(crate) async fn get_live_performances<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        contest: ContestId,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    let performances = conn.load_live_performances(&query.contest).await?;
+    let response = Response::json(&performances)?.make_cors();
+    Ok(response)
+}
@@ -1,6 +1,66 @@
 use async_trait::async_trait;
 use serde_json::json;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
+/// The header which carries the trace ID of a request.
+/// The clients can set it to correlate their logs, and the server returns it in every response.
+pub const TRACE_ID_HEADER: &str = "X-Request-Id";
+
+const MAX_TRACE_ID_LENGTH: usize = 64;
+
+async_std::task_local! {
+    static CURRENT_TRACE_ID: RefCell<Option<TraceId>> = RefCell::new(None);
+}
+
+/// The ID which identifies a request in the logs and the error responses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceId(pub String);
+
+impl TraceId {
+    /// The trace ID of the request which the current task is handling. The logger attaches it to
+    /// every record, so that the queries which sqlx logs can be matched to the request.
+    pub fn current() -> Option<Self> {
+        CURRENT_TRACE_ID
+            .try_with(|current| current.borrow().clone())
+            .ok()
+            .flatten()
+    }
+
+    /// Returns the previous one, which has to be restored when the request is handled,
+    /// since a connection handles the requests one by one in the same task.
+    fn set_current(trace_id: Option<Self>) -> Option<Self> {
+        CURRENT_TRACE_ID
+            .try_with(|current| current.replace(trace_id))
+            .ok()
+            .flatten()
+    }
+
+    fn generate() -> Self {
+        Self(format!("{:016x}", rand::random::<u64>()))
+    }
+
+    /// Accepts the trace ID given by the client only if it is safe to be written in the logs.
+    fn from_client(value: &str) -> Option<Self> {
+        let is_valid = !value.is_empty()
+            && value.len() <= MAX_TRACE_ID_LENGTH
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if is_valid {
+            Some(Self(value.to_string()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Logs every request with its trace ID, and returns the trace ID in the `X-Request-Id` header.
+/// The internal server errors are logged in detail, and the clients receive only the trace ID of them.
 #[derive(Debug, Default, Clone)]
 pub struct LogMiddleware;
 
@@ -20,16 +80,43 @@ impl LogMiddleware {
         }
         req.set_ext(LogMiddlewareHasBeenRun);
 
+        let trace_id = req
+            .header(TRACE_ID_HEADER)
+            .and_then(|values| TraceId::from_client(values.last().as_str()))
+            .unwrap_or_else(TraceId::generate);
+        req.set_ext(trace_id.clone());
+
         let url = req.url().to_string();
         let method = req.method().to_string();
         let start = std::time::Instant::now();
-        let response = next.run(req).await;
+        let previous = TraceId::set_current(Some(trace_id.clone()));
+        let mut response = next.run(req).await;
+        TraceId::set_current(previous);
         let duration_millis = (start.elapsed().as_millis() as f64) / 1000.0;
         let status = response.status();
 
+        let error = response
+            .error()
+            .filter(|_| status.is_server_error())
+            .map(|error| format!("{:?}", error));
+        if let Some(error) = error {
+            log::error!(
+                "{}",
+                json!({
+                    "trace_id": trace_id.0,
+                    "error": error,
+                })
+                .to_string()
+            );
+            response.set_body(json!({
+                "message": status.canonical_reason(),
+                "trace_id": trace_id.0,
+            }));
+        }
         log::info!(
             "{}",
             json!({
+                "trace_id": trace_id.0,
                 "method": method,
                 "url": url,
                 "status": status as u16,
@@ -37,6 +124,7 @@ impl LogMiddleware {
             })
             .to_string()
         );
+        response.insert_header(TRACE_ID_HEADER, trace_id.0.as_str());
         Ok(response)
     }
 }
@@ -50,3 +138,329 @@ where
         self.log(req, next).await
     }
 }
+
+const DEFAULT_RATE_LIMIT_CAPACITY: f64 = 60.0;
+const DEFAULT_RATE_LIMIT_PER_SECOND: f64 = 1.0;
+const MAX_TRACKED_CLIENTS: usize = 100_000;
+
+struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token buckets of the clients. Each client can send `capacity` requests at once,
+/// and the tokens are refilled at `per_second` tokens per second.
+struct TokenBuckets {
+    capacity: f64,
+    per_second: f64,
+    buckets: HashMap<String, TokenBucket>,
+}
+
+impl TokenBuckets {
+    fn new(capacity: f64, per_second: f64) -> Self {
+        Self {
+            capacity,
+            per_second,
+            buckets: HashMap::new(),
+        }
+    }
+
+    /// Consumes a token of the client, or returns how long the client should wait.
+    fn acquire(&mut self, client: &str, now: Instant) -> std::result::Result<(), Duration> {
+        if self.buckets.len() >= MAX_TRACKED_CLIENTS {
+            self.forget_full_buckets(now);
+        }
+
+        let capacity = self.capacity;
+        let per_second = self.per_second;
+        let bucket = self
+            .buckets
+            .entry(client.to_string())
+            .or_insert(TokenBucket {
+                tokens: capacity,
+                updated_at: now,
+            });
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated_at = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+
+    fn forget_full_buckets(&mut self, now: Instant) {
+        let capacity = self.capacity;
+        let per_second = self.per_second;
+        self.buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+            bucket.tokens + elapsed * per_second < capacity
+        });
+    }
+}
+
+/// Limits the number of requests from each IP address, except the ones in the allowlist.
+/// The IP address is the peer address of the connection, and `X-Forwarded-For` is read only
+/// when the peer is one of the trusted proxies, since any client can send the header.
+pub struct RateLimitMiddleware {
+    allowlist: Vec<String>,
+    trusted_proxies: Vec<String>,
+    buckets: Mutex<TokenBuckets>,
+}
+
+impl RateLimitMiddleware {
+    pub fn new(
+        capacity: f64,
+        per_second: f64,
+        allowlist: Vec<String>,
+        trusted_proxies: Vec<String>,
+    ) -> Self {
+        Self {
+            allowlist,
+            trusted_proxies,
+            buckets: Mutex::new(TokenBuckets::new(capacity, per_second)),
+        }
+    }
+
+    /// Reads `RATE_LIMIT_CAPACITY`, `RATE_LIMIT_PER_SECOND`, `RATE_LIMIT_ALLOWLIST` and
+    /// `RATE_LIMIT_TRUSTED_PROXIES`, the last two of which are comma-separated lists of IP addresses.
+    pub fn from_env() -> Self {
+        let capacity = env::var("RATE_LIMIT_CAPACITY")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RATE_LIMIT_CAPACITY);
+        let per_second = env::var("RATE_LIMIT_PER_SECOND")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_RATE_LIMIT_PER_SECOND);
+        let mut allowlist = vec!["127.0.0.1".to_string(), "::1".to_string()];
+        allowlist.extend(ip_list_from_env("RATE_LIMIT_ALLOWLIST"));
+        let trusted_proxies = ip_list_from_env("RATE_LIMIT_TRUSTED_PROXIES");
+        Self::new(capacity, per_second, allowlist, trusted_proxies)
+    }
+
+    fn client_ip<State>(&self, req: &tide::Request<State>) -> Option<String> {
+        let peer = parse_ip(req.peer_addr()?);
+        let forwarded_for = req
+            .header("X-Forwarded-For")
+            .map(|values| values.last().as_str());
+        Some(resolve_client_ip(
+            peer,
+            forwarded_for,
+            &self.trusted_proxies,
+        ))
+    }
+}
+
+fn ip_list_from_env(name: &str) -> Vec<String> {
+    env::var(name)
+        .map(|s| {
+            s.split(',')
+                .map(|ip| ip.trim())
+                .filter(|ip| !ip.is_empty())
+                .map(|ip| ip.to_string())
+                .collect()
+        })
+        .unwrap_or_else(|_| Vec::new())
+}
+
+fn parse_ip(addr: &str) -> String {
+    match addr.parse::<SocketAddr>() {
+        Ok(addr) => addr.ip().to_string(),
+        Err(_) => addr.trim_matches(|c| c == '[' || c == ']').to_string(),
+    }
+}
+
+/// Walks `X-Forwarded-For` from the right while the hops are trusted proxies,
+/// and returns the first address which is not, i.e. the one which the trusted proxies saw.
+fn resolve_client_ip(
+    peer: String,
+    forwarded_for: Option<&str>,
+    trusted_proxies: &[String],
+) -> String {
+    if !trusted_proxies.contains(&peer) {
+        return peer;
+    }
+    let mut client = peer;
+    if let Some(forwarded_for) = forwarded_for {
+        for hop in forwarded_for.rsplit(',').map(|hop| hop.trim()) {
+            if hop.is_empty() {
+                break;
+            }
+            client = parse_ip(hop);
+            if !trusted_proxies.contains(&client) {
+                break;
+            }
+        }
+    }
+    client
+}
+
+#[async_trait]
+impl<State> tide::Middleware<State> for RateLimitMiddleware
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn handle(&self, req: tide::Request<State>, next: tide::Next<'_, State>) -> tide::Result {
+        let ip = match self.client_ip(&req) {
+            Some(ip) => ip,
+            None => return Ok(next.run(req).await),
+        };
+        if self.allowlist.contains(&ip) {
+            return Ok(next.run(req).await);
+        }
+
+        let acquired = self.buckets.lock().unwrap().acquire(&ip, Instant::now());
+        match acquired {
+            Ok(()) => Ok(next.run(req).await),
+            Err(retry_after) => {
+                let retry_after = retry_after.as_secs_f64().ceil() as u64;
+                let mut response = tide::Response::new(tide::StatusCode::TooManyRequests);
+                response.insert_header(tide::http::headers::RETRY_AFTER, retry_after.to_string());
+                Ok(response)
+            }
+        }
+    }
+}
+
+/// Rejects the requests which do not have any of the API keys in `Authorization: Bearer {key}`.
+/// This protects the admin and write endpoints which are not used by the public.
+pub struct ApiKeyMiddleware {
+    api_keys: Vec<String>,
+}
+
+impl ApiKeyMiddleware {
+    pub fn new(api_keys: Vec<String>) -> Self {
+        Self { api_keys }
+    }
+
+    /// Reads `ADMIN_API_KEYS`, which is a comma-separated list of API keys.
+    /// If it is not set, all requests are rejected.
+    pub fn from_env() -> Self {
+        let api_keys = env::var("ADMIN_API_KEYS")
+            .map(|s| {
+                s.split(',')
+                    .map(|key| key.trim())
+                    .filter(|key| !key.is_empty())
+                    .map(|key| key.to_string())
+                    .collect()
+            })
+            .unwrap_or_else(|_| Vec::new());
+        Self::new(api_keys)
+    }
+
+    fn is_authorized(&self, authorization: Option<&str>) -> bool {
+        let key = match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(key) => key.trim(),
+            None => return false,
+        };
+        self.api_keys
+            .iter()
+            .any(|api_key| constant_time_eq(api_key.as_bytes(), key.as_bytes()))
+    }
+}
+
+/// Compares the byte strings in the time which does not depend on where they differ.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[async_trait]
+impl<State> tide::Middleware<State> for ApiKeyMiddleware
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn handle(&self, req: tide::Request<State>, next: tide::Next<'_, State>) -> tide::Result {
+        let authorization = req
+            .header(tide::http::headers::AUTHORIZATION)
+            .map(|values| values.last().as_str());
+        if self.is_authorized(authorization) {
+            Ok(next.run(req).await)
+        } else {
+            Ok(tide::Response::new(tide::StatusCode::Unauthorized))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_buckets() {
+        let mut buckets = TokenBuckets::new(2.0, 0.5);
+        let now = Instant::now();
+        assert!(buckets.acquire("a", now).is_ok());
+        assert!(buckets.acquire("a", now).is_ok());
+        assert_eq!(buckets.acquire("a", now), Err(Duration::from_secs(2)));
+        assert!(buckets.acquire("b", now).is_ok());
+
+        let now = now + Duration::from_secs(1);
+        assert_eq!(buckets.acquire("a", now), Err(Duration::from_secs(1)));
+        let now = now + Duration::from_secs(1);
+        assert!(buckets.acquire("a", now).is_ok());
+
+        let now = now + Duration::from_secs(10);
+        buckets.forget_full_buckets(now);
+        assert!(buckets.buckets.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let proxies = vec!["10.0.0.1".to_string(), "10.0.0.2".to_string()];
+        let resolve = |peer: &str, forwarded_for| {
+            resolve_client_ip(peer.to_string(), forwarded_for, &proxies)
+        };
+
+        assert_eq!(resolve("1.2.3.4", Some("127.0.0.1")), "1.2.3.4");
+        assert_eq!(resolve("10.0.0.1", None), "10.0.0.1");
+        assert_eq!(resolve("10.0.0.1", Some("1.2.3.4")), "1.2.3.4");
+        assert_eq!(
+            resolve("10.0.0.1", Some("127.0.0.1, 1.2.3.4, 10.0.0.2")),
+            "1.2.3.4"
+        );
+    }
+
+    #[test]
+    fn test_trace_id() {
+        assert_eq!(
+            TraceId::from_client("3f2a-b_9"),
+            Some(TraceId("3f2a-b_9".to_string()))
+        );
+        assert_eq!(TraceId::from_client(""), None);
+        assert_eq!(TraceId::from_client("a b"), None);
+        assert_eq!(TraceId::from_client("a\nb"), None);
+        assert_eq!(TraceId::from_client(&"a".repeat(65)), None);
+
+        let generated = TraceId::generate();
+        assert_eq!(TraceId::from_client(&generated.0), Some(generated));
+    }
+
+    #[test]
+    fn test_current_trace_id() {
+        assert_eq!(TraceId::current(), None);
+        async_std::task::block_on(async {
+            let trace_id = TraceId("abc".to_string());
+            assert_eq!(TraceId::set_current(Some(trace_id.clone())), None);
+            assert_eq!(TraceId::current(), Some(trace_id.clone()));
+            assert_eq!(TraceId::set_current(None), Some(trace_id));
+            assert_eq!(TraceId::current(), None);
+        });
+    }
+
+    #[test]
+    fn test_api_key() {
+        let middleware = ApiKeyMiddleware::new(vec!["key1".to_string(), "key2".to_string()]);
+        assert!(middleware.is_authorized(Some("Bearer key1")));
+        assert!(middleware.is_authorized(Some("Bearer key2")));
+        assert!(!middleware.is_authorized(Some("Bearer key3")));
+        assert!(!middleware.is_authorized(Some("Bearer key")));
+        assert!(!middleware.is_authorized(Some("key1")));
+        assert!(!middleware.is_authorized(None));
+
+        let middleware = ApiKeyMiddleware::new(vec![]);
+        assert!(!middleware.is_authorized(Some("Bearer ")));
+    }
+}
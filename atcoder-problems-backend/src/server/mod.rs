@@ -1,42 +1,129 @@
 use crate::server::accepted_count_ranking::get_ac_ranking;
+use crate::server::achievement::get_user_achievements;
+use crate::server::badge::get_badge;
+use crate::server::broadcaster::Broadcaster;
+use crate::server::cache::CacheMiddleware;
+use crate::server::calendar::get_calendar;
+use crate::server::contest_events::{get_contest_events, watch_contests, ContestEvent};
+use crate::server::contest_standings::get_contest_standings;
+use crate::server::crawler_runs::get_crawler_runs;
+use crate::server::feed::get_feed;
+use crate::server::health::get_readiness;
+use crate::server::live_performance::get_live_performances;
+use crate::server::problem_staleness::{
+    get_never_solved_problems, get_stale_problems, get_user_unsolved_attempts,
+};
+use crate::server::ranking::{get_current_streak_ranking, get_languages, get_ranking};
+use crate::server::rated_point_sum_ranking::get_rated_point_sum_ranking;
+use crate::server::resources::{
+    get_contest_problem, get_contests, get_merged_problems, get_problems, search_problems,
+};
+use crate::server::submission_stream::{listen_submissions, stream_submissions};
 use crate::server::time_submissions::get_time_submissions;
-use crate::server::user_info::get_user_info;
+use crate::server::training_velocity::get_user_training_velocity;
+use crate::server::user_info::{get_user_info, get_user_summary, get_users_summaries};
 use crate::server::user_submissions::{
-    get_recent_submissions, get_user_submissions, get_user_submissions_from_time,
+    get_recent_submissions, get_submission, get_user_submissions, get_user_submissions_from_time,
     get_users_time_submissions,
 };
 pub(crate) mod auth;
-use crate::server::middleware::LogMiddleware;
+use crate::server::middleware::{ApiKeyMiddleware, LogMiddleware, RateLimitMiddleware};
 use crate::server::problem_list::{
-    add_item, create_list, delete_item, delete_list, get_own_lists, get_single_list, update_item,
-    update_list,
+    add_item, create_list, delete_item, delete_list, export_list, get_own_lists, get_single_list,
+    import_list, update_item, update_list,
 };
-use auth::get_token;
+use async_std::prelude::FutureExt;
+use async_std::task;
+use auth::{get_token, logout};
 pub use auth::{Authentication, GitHubAuthentication, GitHubUserResponse};
+use shutdown::{shutdown_timeout_from_env, InFlightRequests};
+use sql_client::data_version::{MERGED_PROBLEMS_DATA, RANKINGS_DATA};
+use sql_client::models::Submission;
 use sql_client::PgPool;
+use std::future::{pending, Future};
 use tide::{Result, StatusCode};
+use tide_compress::CompressMiddleware;
+use tide_websockets::WebSocket;
 
 pub(crate) mod accepted_count_ranking;
+pub(crate) mod achievement;
+pub(crate) mod admin;
+pub(crate) mod badge;
+pub(crate) mod broadcaster;
+pub(crate) mod cache;
+pub(crate) mod calendar;
+pub(crate) mod contest_events;
+pub(crate) mod contest_standings;
+pub(crate) mod crawler_runs;
+pub(crate) mod csv;
+pub(crate) mod feed;
+pub(crate) mod graphql;
+pub(crate) mod health;
 pub(crate) mod internal_user;
+pub(crate) mod live_performance;
 pub(crate) mod middleware;
 pub(crate) mod problem_list;
+pub(crate) mod problem_staleness;
 pub(crate) mod progress_reset;
+pub(crate) mod ranking;
+pub(crate) mod rated_point_sum_ranking;
+pub(crate) mod resources;
+pub(crate) mod shutdown;
+pub(crate) mod submission_stream;
 pub(crate) mod time_submissions;
+pub(crate) mod training_velocity;
 pub(crate) mod user_info;
 pub(crate) mod user_submissions;
 pub(crate) mod utils;
 pub(crate) mod virtual_contest;
+pub(crate) mod webhook;
+
+/// The endpoints which stream the events until the clients leave, and so are not waited for
+/// on shutdown.
+const STREAMING_PATHS: [&str; 2] = ["/ws/submissions", "/atcoder-api/v3/contest_events"];
 
 pub async fn run_server<A>(pg_pool: PgPool, authentication: A, port: u16) -> Result<()>
 where
     A: Authentication + Send + Sync + 'static + Clone,
 {
+    run_server_with_shutdown(pg_pool, authentication, port, pending()).await
+}
+
+/// Runs the server until `shutdown` completes. Then, the server stops accepting new connections,
+/// waits for the in-flight requests except the streams up to `SHUTDOWN_TIMEOUT_SECOND` and closes
+/// the connection pool.
+pub async fn run_server_with_shutdown<A, F>(
+    pg_pool: PgPool,
+    authentication: A,
+    port: u16,
+    shutdown: F,
+) -> Result<()>
+where
+    A: Authentication + Send + Sync + 'static + Clone,
+    F: Future<Output = ()>,
+{
+    let in_flight = InFlightRequests::excluding(&STREAMING_PATHS);
     let app_data = AppData::new(pg_pool, authentication);
+    let cache_store = cache::store_from_env()?;
+    let background_tasks = vec![
+        task::spawn(listen_submissions(
+            app_data.submission_broadcaster.clone(),
+            app_data.pg_pool.clone(),
+        )),
+        task::spawn(watch_contests(
+            app_data.contest_event_broadcaster.clone(),
+            app_data.pg_pool.clone(),
+        )),
+    ];
     let mut api = tide::with_state(app_data.clone());
+    api.with(in_flight.clone());
     api.with(LogMiddleware);
+    api.with(RateLimitMiddleware::from_env());
+    api.with(CompressMiddleware::new());
     api.at("/internal-api").nest({
         let mut api = tide::with_state(app_data.clone());
         api.at("/authorize").get_ah(get_token);
+        api.at("/logout").get_ah(logout);
         api.at("/list").nest({
             let mut api = tide::with_state(app_data.clone());
             api.at("/my").get_ah(get_own_lists);
@@ -44,6 +131,8 @@ where
             api.at("/create").post_ah(create_list);
             api.at("/delete").post_ah(delete_list);
             api.at("/update").post_ah(update_list);
+            api.at("/export/:list_id").get_ah(export_list);
+            api.at("/import").post_ah(import_list);
             api.at("/item").nest({
                 let mut api = tide::with_state(app_data.clone());
                 api.at("/add").post_ah(add_item);
@@ -68,6 +157,8 @@ where
             api.at("/joined").get_ah(virtual_contest::get_participated);
             api.at("/recent")
                 .get_ah(virtual_contest::get_recent_contests);
+            api.at("/rating/:contest_id")
+                .post_ah(virtual_contest::simulate_rating);
             api
         });
 
@@ -86,12 +177,43 @@ where
                 .post_ah(progress_reset::add_progress_reset_item);
             api.at("/delete")
                 .post_ah(progress_reset::delete_progress_reset_item);
+            api.at("/solved")
+                .get_ah(progress_reset::get_solved_problems);
+            api
+        });
+
+        api.at("/webhook").nest({
+            let mut api = tide::with_state(app_data.clone());
+            api.at("/list").get_ah(webhook::get_webhooks);
+            api.at("/add").post_ah(webhook::add_webhook);
+            api.at("/delete").post_ah(webhook::delete_webhook);
             api
         });
         api
     });
+    api.at("/admin-api").nest({
+        let mut api = tide::with_state(app_data.clone());
+        api.with(ApiKeyMiddleware::from_env());
+        api.at("/data_version/:name/increment")
+            .post_ah(admin::increment_data_version);
+        api
+    });
+    api.at("/internal").nest({
+        let mut api = tide::with_state(app_data.clone());
+        api.with(ApiKeyMiddleware::from_env());
+        api.at("/runs").get_ah(get_crawler_runs);
+        api.at("/crawl/contest/:contest_id")
+            .post_ah(admin::enqueue_contest_crawl);
+        api.at("/crawl/user/:user_id")
+            .post_ah(admin::enqueue_user_crawl);
+        api
+    });
     api.at("/atcoder-api").nest({
         let mut api = tide::with_state(app_data.clone());
+        let schema = graphql::build_schema(app_data.pg_pool.clone());
+        api.at("/graphql")
+            .get(async_graphql_tide::endpoint(schema.clone()))
+            .post(async_graphql_tide::endpoint(schema));
         api.at("/results").get_ah(get_user_submissions);
         api.at("/v2").nest({
             let mut api = tide::with_state(app_data.clone());
@@ -100,24 +222,100 @@ where
         });
         api.at("/v3").nest({
             let mut api = tide::with_state(app_data.clone());
-            api.at("/ac_ranking").get_ah(get_ac_ranking);
+            api.at("/ac_ranking")
+                .with(CacheMiddleware::new(cache_store.clone(), RANKINGS_DATA))
+                .get_ah(get_ac_ranking);
+            api.at("/rated_point_sum_ranking")
+                .with(CacheMiddleware::new(cache_store.clone(), RANKINGS_DATA))
+                .get_ah(get_rated_point_sum_ranking);
+            api.at("/ranking/current_streak")
+                .get_ah(get_current_streak_ranking);
+            api.at("/ranking/:kind")
+                .with(CacheMiddleware::new(cache_store.clone(), RANKINGS_DATA))
+                .get_ah(get_ranking);
+            api.at("/languages")
+                .with(CacheMiddleware::new(cache_store.clone(), RANKINGS_DATA))
+                .get_ah(get_languages);
+            api.at("/contests").get_ah(get_contests);
+            api.at("/problems").get_ah(get_problems);
+            api.at("/problems/search").get_ah(search_problems);
+            api.at("/contest_events").get_ah(get_contest_events);
+            api.at("/contest-problem").get_ah(get_contest_problem);
+            api.at("/merged-problems")
+                .with(CacheMiddleware::new(
+                    cache_store.clone(),
+                    MERGED_PROBLEMS_DATA,
+                ))
+                .get_ah(get_merged_problems);
+            api.at("/live_performances").get_ah(get_live_performances);
+            api.at("/contest_standings").get_ah(get_contest_standings);
+            api.at("/never_solved_problems")
+                .get_ah(get_never_solved_problems);
+            api.at("/stale_problems").get_ah(get_stale_problems);
             api.at("/from/:from").get_ah(get_time_submissions);
             api.at("/recent").get_ah(get_recent_submissions);
+            api.at("/submission/:id").get_ah(get_submission);
             api.at("/users_and_time").get_ah(get_users_time_submissions);
+            api.at("/user/info").get_ah(get_user_summary);
+            api.at("/users/info").post_ah(get_users_summaries);
             api.at("/user/submissions")
                 .get_ah(get_user_submissions_from_time);
+            api.at("/user/achievements").get_ah(get_user_achievements);
+            api.at("/user/training_velocity")
+                .get_ah(get_user_training_velocity);
+            api.at("/user/unsolved_attempts")
+                .get_ah(get_user_unsolved_attempts);
             api
         });
         api
     });
+    api.at("/ws/submissions")
+        .get(WebSocket::new(stream_submissions));
+    api.at("/atcoder.ics").get_ah(get_calendar);
+    api.at("/feed.atom").get_ah(get_feed);
+    api.at("/badge/:user/:file").get_ah(get_badge);
     api.at("/healthcheck").get(|_| async move { Ok("") });
-    api.listen(format!("0.0.0.0:{}", port)).await?;
+    api.at("/healthz").get(|_| async move { Ok("") });
+    api.at("/readyz").get_ah(get_readiness);
+    let pg_pool = app_data.pg_pool.clone();
+    api.listen(format!("0.0.0.0:{}", port))
+        .race(async {
+            shutdown.await;
+            Ok(())
+        })
+        .await?;
+
+    log::info!(
+        "Shutting down with {} requests in flight",
+        in_flight.count()
+    );
+    let timeout = shutdown_timeout_from_env();
+    if !in_flight.wait_until_idle(timeout).await {
+        log::warn!(
+            "Gave up waiting for {} requests in flight",
+            in_flight.count()
+        );
+    }
+    // The listener of the submissions holds a connection until it is dropped.
+    for background_task in background_tasks {
+        background_task.cancel().await;
+    }
+    if async_std::future::timeout(timeout, pg_pool.close())
+        .await
+        .is_err()
+    {
+        log::warn!("Gave up closing the connection pool");
+    }
     Ok(())
 }
 
 pub(crate) trait CommonResponse {
     fn ok() -> Self;
     fn json<S: serde::Serialize>(body: &S) -> tide::Result<Self>
+    where
+        Self: Sized;
+    /// Same as `json`, but keeps only the comma-separated `fields` of the objects if specified.
+    fn json_with_fields<S: serde::Serialize>(body: &S, fields: Option<&str>) -> tide::Result<Self>
     where
         Self: Sized;
     fn empty_json() -> Self;
@@ -138,6 +336,18 @@ impl CommonResponse for tide::Response {
             .build();
         Ok(response)
     }
+    fn json_with_fields<S: serde::Serialize>(body: &S, fields: Option<&str>) -> tide::Result<Self>
+    where
+        Self: Sized,
+    {
+        match fields {
+            Some(fields) => {
+                let value = serde_json::to_value(body)?;
+                Self::json(&utils::select_fields(value, fields))
+            }
+            None => Self::json(body),
+        }
+    }
     fn empty_json() -> Self {
         Self::builder(tide::StatusCode::Ok)
             .content_type(tide::http::mime::JSON)
@@ -154,6 +364,8 @@ impl CommonResponse for tide::Response {
 pub(crate) struct AppData<A> {
     pub(crate) authentication: A,
     pub(crate) pg_pool: PgPool,
+    pub(crate) submission_broadcaster: Broadcaster<Submission>,
+    pub(crate) contest_event_broadcaster: Broadcaster<ContestEvent>,
 }
 
 impl<A: Clone> Clone for AppData<A> {
@@ -161,6 +373,8 @@ impl<A: Clone> Clone for AppData<A> {
         Self {
             pg_pool: self.pg_pool.clone(),
             authentication: self.authentication.clone(),
+            submission_broadcaster: self.submission_broadcaster.clone(),
+            contest_event_broadcaster: self.contest_event_broadcaster.clone(),
         }
     }
 }
@@ -170,6 +384,8 @@ impl<A> AppData<A> {
         Self {
             pg_pool,
             authentication,
+            submission_broadcaster: Broadcaster::default(),
+            contest_event_broadcaster: Broadcaster::default(),
         }
     }
 }
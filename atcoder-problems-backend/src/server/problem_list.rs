@@ -1,8 +1,64 @@
+use crate::server::csv::{parse_csv, to_csv, wants_csv, CSV_MIME};
 use crate::server::utils::RequestUnpack;
 use crate::server::{AppData, Authentication, CommonResponse};
+use anyhow::{anyhow, Context};
 use serde::Deserialize;
-use sql_client::internal::problem_list_manager::ProblemListManager;
-use tide::{Request, Response, Result};
+use sql_client::ids::ProblemId;
+use sql_client::internal::problem_list_manager::{
+    ProblemListDocument, ProblemListEntry, ProblemListManager, PROBLEM_LIST_FORMAT_VERSION,
+};
+use tide::{Body, Request, Response, Result, StatusCode};
+
+/// The columns of a problem list in CSV, for the spreadsheets.
+const LIST_CSV_FIELDS: &str = "problem_id,order,note";
+
+pub(crate) fn list_to_csv(document: &ProblemListDocument) -> anyhow::Result<String> {
+    let items = serde_json::to_value(&document.items)?;
+    Ok(to_csv(&items, Some(LIST_CSV_FIELDS)))
+}
+
+/// Reads the items of a list from CSV with the header of `problem_id` and optionally `order` and
+/// `note`, where the empty orders are allowed.
+pub(crate) fn list_from_csv(name: &str, csv: &str) -> anyhow::Result<ProblemListDocument> {
+    let mut records = parse_csv(csv).into_iter();
+    let header = records.next().unwrap_or_default();
+    let column = |name: &str| header.iter().position(|cell| cell.trim() == name);
+    let problem_id_column = column("problem_id").context("The CSV has no problem_id column")?;
+    let order_column = column("order");
+    let note_column = column("note");
+
+    let mut items = Vec::new();
+    for (i, record) in records.enumerate() {
+        let cell = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(|cell| cell.trim())
+                .unwrap_or("")
+        };
+        let problem_id = cell(Some(problem_id_column));
+        if problem_id.is_empty() {
+            continue;
+        }
+        let order = match cell(order_column) {
+            "" => None,
+            order => Some(
+                order
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid order at line {}: {}", i + 2, order))?,
+            ),
+        };
+        items.push(ProblemListEntry {
+            problem_id: problem_id.to_owned(),
+            order,
+            note: cell(note_column).to_owned(),
+        });
+    }
+    Ok(ProblemListDocument {
+        version: PROBLEM_LIST_FORMAT_VERSION,
+        name: name.to_owned(),
+        items,
+    })
+}
 
 pub(crate) async fn get_own_lists<A>(request: Request<AppData<A>>) -> Result<Response>
 where
@@ -26,6 +82,39 @@ where
     Ok(response)
 }
 
+/// Returns the list in the interchange format, or its items as CSV if the client asks for it.
+pub(crate) async fn export_list<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    let list_id = request.param("list_id")?;
+    let conn = request.state().pg_pool.clone();
+    let document = ProblemListDocument::from_list(conn.get_single_list(&list_id).await?);
+    if wants_csv(&request) {
+        let mut body = Body::from_string(list_to_csv(&document)?);
+        body.set_mime(CSV_MIME);
+        let mut response = Response::ok();
+        response.set_body(body);
+        Ok(response)
+    } else {
+        Ok(Response::json(&document)?)
+    }
+}
+
+/// Creates a new list of the user from a list in the interchange format.
+pub(crate) async fn import_list<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    let internal_user_id = request.get_authorized_id().await?;
+    let conn = request.state().pg_pool.clone();
+    let document = request.parse_body::<ProblemListDocument>().await?;
+    let internal_list_id = conn.import_list(&internal_user_id, &document).await?;
+    let body = serde_json::json!({ "internal_list_id": internal_list_id });
+    let response = Response::json(&body)?;
+    Ok(response)
+}
+
 pub(crate) async fn create_list<A>(request: Request<AppData<A>>) -> Result<Response>
 where
     A: Authentication + Clone + Send + Sync + 'static,
@@ -53,9 +142,15 @@ where
     struct Q {
         internal_list_id: String,
     }
-    request.get_authorized_id().await?;
+    let internal_user_id = request.get_authorized_id().await?;
     let conn = request.state().pg_pool.clone();
     let query = request.parse_body::<Q>().await?;
+    if !conn
+        .is_list_owner(&query.internal_list_id, &internal_user_id)
+        .await?
+    {
+        return Ok(Response::new(StatusCode::Forbidden));
+    }
     conn.delete_list(&query.internal_list_id).await?;
     let response = Response::empty_json();
     Ok(response)
@@ -70,9 +165,15 @@ where
         internal_list_id: String,
         name: String,
     }
-    request.get_authorized_id().await?;
+    let internal_user_id = request.get_authorized_id().await?;
     let conn = request.state().pg_pool.clone();
     let query = request.parse_body::<Q>().await?;
+    if !conn
+        .is_list_owner(&query.internal_list_id, &internal_user_id)
+        .await?
+    {
+        return Ok(Response::new(StatusCode::Forbidden));
+    }
     conn.update_list(&query.internal_list_id, &query.name)
         .await?;
     let response = Response::empty_json();
@@ -86,11 +187,17 @@ where
     #[derive(Deserialize)]
     struct Q {
         internal_list_id: String,
-        problem_id: String,
+        problem_id: ProblemId,
     }
-    request.get_authorized_id().await?;
+    let internal_user_id = request.get_authorized_id().await?;
     let conn = request.state().pg_pool.clone();
     let query = request.parse_body::<Q>().await?;
+    if !conn
+        .is_list_owner(&query.internal_list_id, &internal_user_id)
+        .await?
+    {
+        return Ok(Response::new(StatusCode::Forbidden));
+    }
     conn.add_item(&query.internal_list_id, &query.problem_id)
         .await?;
     let response = Response::empty_json();
@@ -104,12 +211,18 @@ where
     #[derive(Deserialize)]
     struct Q {
         internal_list_id: String,
-        problem_id: String,
+        problem_id: ProblemId,
         memo: String,
     }
-    request.get_authorized_id().await?;
+    let internal_user_id = request.get_authorized_id().await?;
     let conn = request.state().pg_pool.clone();
     let query = request.parse_body::<Q>().await?;
+    if !conn
+        .is_list_owner(&query.internal_list_id, &internal_user_id)
+        .await?
+    {
+        return Ok(Response::new(StatusCode::Forbidden));
+    }
     conn.update_item(&query.internal_list_id, &query.problem_id, &query.memo)
         .await?;
     let response = Response::empty_json();
@@ -123,13 +236,56 @@ where
     #[derive(Deserialize)]
     struct Q {
         internal_list_id: String,
-        problem_id: String,
+        problem_id: ProblemId,
     }
-    request.get_authorized_id().await?;
+    let internal_user_id = request.get_authorized_id().await?;
     let conn = request.state().pg_pool.clone();
     let query = request.parse_body::<Q>().await?;
+    if !conn
+        .is_list_owner(&query.internal_list_id, &internal_user_id)
+        .await?
+    {
+        return Ok(Response::new(StatusCode::Forbidden));
+    }
     conn.delete_item(&query.internal_list_id, &query.problem_id)
         .await?;
     let response = Response::empty_json();
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_csv() {
+        let document = list_from_csv(
+            "from spreadsheet",
+            "note,problem_id,order\r\n\"dp, hard\",abc100_d,2\r\n,abc100_a,\r\n,,\r\n",
+        )
+        .unwrap();
+        assert_eq!(document.name, "from spreadsheet");
+        assert_eq!(
+            document.items,
+            vec![
+                ProblemListEntry {
+                    problem_id: "abc100_d".to_owned(),
+                    order: Some(2),
+                    note: "dp, hard".to_owned(),
+                },
+                ProblemListEntry {
+                    problem_id: "abc100_a".to_owned(),
+                    order: None,
+                    note: "".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            list_to_csv(&document).unwrap(),
+            "problem_id,order,note\r\nabc100_d,2,\"dp, hard\"\r\nabc100_a,,\r\n"
+        );
+
+        assert!(list_from_csv("", "id,note\r\na,b\r\n").is_err());
+        assert!(list_from_csv("", "problem_id,order\r\na,first\r\n").is_err());
+    }
+}
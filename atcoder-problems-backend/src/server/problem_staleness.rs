@@ -0,0 +1,43 @@
+use crate::server::{AppData, CommonResponse};
+use serde::Deserialize;
+use sql_client::ids::UserId;
+use sql_client::problem_staleness::ProblemStalenessClient;
+use tide::{Request, Response, Result};
+
+const MAX_STALE_PROBLEM_COUNT: i64 = 1_000;
+
+pub(crate) async fn get_never_solved_problems<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let conn = request.state().pg_pool.clone();
+    let problems = conn.load_never_solved_problems().await?;
+    let response = Response::json(&problems)?.make_cors();
+    Ok(response)
+}
+
+pub(crate) async fn get_stale_problems<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        count: i64,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    if query.count <= 0 || query.count > MAX_STALE_PROBLEM_COUNT {
+        return Ok(Response::new(400));
+    }
+    let problems = conn.load_stale_problems(query.count).await?;
+    let response = Response::json(&problems)?.make_cors();
+    Ok(response)
+}
+
+pub(crate) async fn get_user_unsolved_attempts<A>(
+    request: Request<AppData<A>>,
+) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        user: UserId,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    let attempts = conn.load_unsolved_attempts(&query.user).await?;
+    let response = Response::json(&attempts)?.make_cors();
+    Ok(response)
+}
@@ -1,6 +1,7 @@
 use crate::server::utils::RequestUnpack;
 use crate::server::{AppData, Authentication, CommonResponse};
 use serde::Deserialize;
+use sql_client::ids::ProblemId;
 use sql_client::internal::progress_reset_manager::ProgressResetManager;
 use tide::{Request, Response, Result};
 
@@ -21,7 +22,7 @@ where
 {
     #[derive(Deserialize)]
     struct Query {
-        problem_id: String,
+        problem_id: ProblemId,
         reset_epoch_second: i64,
     }
     let internal_user_id = request.get_authorized_id().await?;
@@ -42,7 +43,7 @@ where
 {
     #[derive(Deserialize)]
     struct Query {
-        problem_id: String,
+        problem_id: ProblemId,
     }
     let internal_user_id = request.get_authorized_id().await?;
     let pool = request.state().pg_pool.clone();
@@ -51,3 +52,14 @@ where
         .await?;
     Ok(Response::ok())
 }
+
+pub(crate) async fn get_solved_problems<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    let user_id = request.get_authorized_id().await?;
+    let pool = request.state().pg_pool.clone();
+    let problem_ids = pool.get_solved_problems_after_reset(&user_id).await?;
+    let response = Response::json(&problem_ids)?;
+    Ok(response)
+}
@@ -0,0 +1,155 @@
+use crate::server::{csv, AppData, CommonResponse};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sql_client::ids::UserId;
+use sql_client::language_count::{resolve_language, LanguageCountClient};
+use sql_client::models::{RankingCursor, RankingEntry};
+use sql_client::ranking::{RankingClient, RankingKind};
+use sql_client::streak::current_streak_alive_since;
+use tide::http::headers::VARY;
+use tide::{Request, Response, Result, StatusCode};
+
+const DEFAULT_RANKING_LIMIT: usize = 100;
+const MAX_RANKING_LIMIT: usize = 1_000;
+
+#[derive(Serialize)]
+struct RankingPage {
+    offset: usize,
+    entries: Vec<RankingEntry>,
+    /// Omitted in the offset mode, and `null` on the last page in the cursor mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<Option<String>>,
+}
+
+fn encode_cursor(cursor: &RankingCursor) -> Result<String> {
+    let json = serde_json::to_vec(cursor)?;
+    Ok(base64::encode_config(json, base64::URL_SAFE_NO_PAD))
+}
+
+fn decode_cursor(cursor: &str) -> Option<RankingCursor> {
+    let json = base64::decode_config(cursor, base64::URL_SAFE_NO_PAD).ok()?;
+    serde_json::from_slice(&json).ok()
+}
+
+/// Returns a page of the ranking specified by the path, which is one of `ac`, `sum`, `lang` and `streak`.
+/// `language` is an alias of `lang`, and the `language` parameter also accepts the aliases of the languages.
+pub(crate) async fn get_ranking<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let kind = request.param("kind")?.to_owned();
+    ranking_page(request, &kind).await
+}
+
+/// Returns a page of the ranking of the streaks which are still alive at the time of the request.
+/// This is not cached, because the streaks which are not extended expire at midnight in JST.
+pub(crate) async fn get_current_streak_ranking<A>(
+    request: Request<AppData<A>>,
+) -> Result<Response> {
+    ranking_page(request, "current_streak").await
+}
+
+/// If `user` is given, the page containing the user is returned instead of the page at `offset`.
+/// If `cursor` is given, the page after the cursor is returned with the cursor of the next page,
+/// where an empty `cursor` means the first page.
+async fn ranking_page<A>(request: Request<AppData<A>>, kind: &str) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        offset: Option<usize>,
+        limit: Option<usize>,
+        user: Option<UserId>,
+        language: Option<String>,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    // Read from the URL, since an empty `cursor` would be deserialized as `None`.
+    let cursor = request
+        .url()
+        .query_pairs()
+        .find(|(key, _)| key == "cursor")
+        .map(|(_, value)| value.into_owned());
+    let kind = match kind {
+        "language" => "lang",
+        kind => kind,
+    };
+    let languages = match (kind, query.language.as_deref()) {
+        ("lang", Some(_)) => conn.load_languages().await?,
+        _ => Vec::new(),
+    };
+    let languages = languages
+        .into_iter()
+        .map(|l| l.language)
+        .collect::<Vec<_>>();
+    let kind = match (kind, query.language.as_deref()) {
+        ("ac", _) => RankingKind::AcceptedCount,
+        ("sum", _) => RankingKind::RatedPointSum,
+        ("streak", _) => RankingKind::Streak,
+        ("current_streak", _) => RankingKind::CurrentStreak {
+            alive_since: current_streak_alive_since(Utc::now().timestamp()),
+        },
+        ("lang", Some(language)) => match resolve_language(language, &languages) {
+            Some(language) => RankingKind::LanguageCount { language },
+            None => return Ok(Response::new(StatusCode::NotFound)),
+        },
+        ("lang", None) => return Ok(Response::new(StatusCode::BadRequest)),
+        _ => return Ok(Response::new(StatusCode::NotFound)),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_RANKING_LIMIT);
+    if limit == 0 || limit > MAX_RANKING_LIMIT {
+        return Ok(Response::new(StatusCode::BadRequest));
+    }
+
+    if let Some(cursor) = cursor {
+        let cursor = match cursor.as_str() {
+            "" => None,
+            cursor => match decode_cursor(cursor) {
+                Some(cursor) => Some(cursor),
+                None => return Ok(Response::new(StatusCode::BadRequest)),
+            },
+        };
+        let offset = cursor.as_ref().map(|cursor| cursor.position).unwrap_or(0);
+        let entries = conn
+            .load_ranking_after(kind, cursor.as_ref(), limit)
+            .await?;
+        let next_cursor = match RankingCursor::after(offset, &entries) {
+            Some(next_cursor) if entries.len() == limit => Some(encode_cursor(&next_cursor)?),
+            _ => None,
+        };
+        let page = RankingPage {
+            offset,
+            entries,
+            next_cursor: Some(next_cursor),
+        };
+        return ranking_response(&request, page);
+    }
+
+    let offset = match query.user {
+        Some(user_id) => match conn.get_ranking_position(kind, &user_id).await? {
+            Some(position) => position / limit * limit,
+            None => return Ok(Response::new(StatusCode::NotFound)),
+        },
+        None => query.offset.unwrap_or(0),
+    };
+    let entries = conn.load_ranking(kind, offset, limit).await?;
+    let page = RankingPage {
+        offset,
+        entries,
+        next_cursor: None,
+    };
+    ranking_response(&request, page)
+}
+
+fn ranking_response<A>(request: &Request<AppData<A>>, page: RankingPage) -> Result<Response> {
+    let mut response = if csv::wants_csv(request) {
+        csv::negotiate(request, &page.entries, None)?
+    } else {
+        Response::json(&page)?
+    };
+    response.insert_header(VARY, "Accept");
+    Ok(response.make_cors())
+}
+
+/// Returns the canonical names of the languages in the language ranking with the number of users.
+pub(crate) async fn get_languages<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let conn = request.state().pg_pool.clone();
+    let languages = conn.load_languages().await?;
+    let response = Response::json(&languages)?.make_cors();
+    Ok(response)
+}
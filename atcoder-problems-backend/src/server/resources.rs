@@ -0,0 +1,168 @@
+use crate::config::{BLOCKED_CONTESTS, BLOCKED_PROBLEMS};
+use crate::server::utils::normalize_fields;
+use crate::server::{AppData, CommonResponse};
+use serde::Deserialize;
+use sql_client::contest_category::ContestCategory;
+use sql_client::contest_problem::ContestProblemClient;
+use sql_client::data_version::{
+    DataVersionClient, CONTESTS_DATA, MERGED_PROBLEMS_DATA, PROBLEMS_DATA,
+};
+use sql_client::merged_problem::MergedProblemClient;
+use sql_client::simple_client::SimpleClient;
+use tide::http::headers::{ETAG, IF_NONE_MATCH};
+use tide::{Request, Response, Result, StatusCode};
+
+/// Returns the strong ETag of the data, and whether the client already has it. The responses
+/// which select only some `fields` of the data have their own ETags.
+async fn check_etag<A>(
+    request: &Request<AppData<A>>,
+    name: &str,
+    fields: Option<&str>,
+) -> Result<(String, bool)> {
+    let version = request.state().pg_pool.load_data_version(name).await?;
+    let etag = match fields {
+        Some(fields) => format!("\"{}-{}-{}\"", name, version, normalize_fields(fields)),
+        None => format!("\"{}-{}\"", name, version),
+    };
+    let not_modified = request
+        .header(IF_NONE_MATCH)
+        .map(|values| {
+            values.iter().any(|value| {
+                value
+                    .as_str()
+                    .split(',')
+                    .any(|tag| tag.trim() == etag || tag.trim() == "*")
+            })
+        })
+        .unwrap_or(false);
+    Ok((etag, not_modified))
+}
+
+fn not_modified_response(etag: &str) -> Response {
+    let mut response = Response::new(StatusCode::NotModified);
+    response.insert_header(ETAG, etag);
+    response.make_cors()
+}
+
+pub(crate) async fn get_contests<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let (etag, not_modified) = check_etag(&request, CONTESTS_DATA, None).await?;
+    if not_modified {
+        return Ok(not_modified_response(&etag));
+    }
+    let conn = request.state().pg_pool.clone();
+    let mut contests = conn
+        .load_contests()
+        .await?
+        .into_iter()
+        .filter(|c| !BLOCKED_CONTESTS.contains(&c.id.as_str()))
+        .collect::<Vec<_>>();
+    contests.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut response = Response::json(&contests)?.make_cors();
+    response.insert_header(ETAG, etag);
+    Ok(response)
+}
+
+pub(crate) async fn get_problems<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let (etag, not_modified) = check_etag(&request, PROBLEMS_DATA, None).await?;
+    if not_modified {
+        return Ok(not_modified_response(&etag));
+    }
+    let conn = request.state().pg_pool.clone();
+    let mut problems = conn
+        .load_problems()
+        .await?
+        .into_iter()
+        .filter(|p| !BLOCKED_PROBLEMS.contains(&p.id.as_str()))
+        .collect::<Vec<_>>();
+    problems.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut response = Response::json(&problems)?.make_cors();
+    response.insert_header(ETAG, etag);
+    Ok(response)
+}
+
+pub(crate) async fn get_contest_problem<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let conn = request.state().pg_pool.clone();
+    let mut contest_problem = conn.load_contest_problem().await?;
+    contest_problem.sort_by(|a, b| {
+        a.contest_id
+            .cmp(&b.contest_id)
+            .then_with(|| a.problem_id.cmp(&b.problem_id))
+    });
+    let response = Response::json(&contest_problem)?.make_cors();
+    Ok(response)
+}
+
+pub(crate) async fn get_merged_problems<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        fields: Option<String>,
+    }
+    let query = request.query::<Query>()?;
+    let (etag, not_modified) =
+        check_etag(&request, MERGED_PROBLEMS_DATA, query.fields.as_deref()).await?;
+    if not_modified {
+        return Ok(not_modified_response(&etag));
+    }
+    let conn = request.state().pg_pool.clone();
+    let merged_problems = conn
+        .load_merged_problems()
+        .await?
+        .into_iter()
+        .filter(|p| !BLOCKED_PROBLEMS.contains(&p.id.as_str()))
+        .collect::<Vec<_>>();
+    let mut response =
+        Response::json_with_fields(&merged_problems, query.fields.as_deref())?.make_cors();
+    response.insert_header(ETAG, etag);
+    Ok(response)
+}
+
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_SEARCH_LIMIT: usize = 1_000;
+
+/// Searches the problems by the title or the id, the range of the difficulty, and the category
+/// of the contest, e.g. `tag=ABC`.
+pub(crate) async fn search_problems<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        q: Option<String>,
+        difficulty_from: Option<f64>,
+        difficulty_to: Option<f64>,
+        tag: Option<String>,
+        limit: Option<usize>,
+        fields: Option<String>,
+    }
+    let query = request.query::<Query>()?;
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    if limit == 0 || limit > MAX_SEARCH_LIMIT {
+        return Ok(Response::new(StatusCode::BadRequest));
+    }
+    let category = match query.tag.as_deref() {
+        Some(tag) => match tag.parse::<ContestCategory>() {
+            Ok(category) => Some(category),
+            Err(_) => return Ok(Response::new(StatusCode::BadRequest)),
+        },
+        None => None,
+    };
+    let text = query
+        .q
+        .as_deref()
+        .map(|q| q.trim())
+        .filter(|q| !q.is_empty());
+    let conn = request.state().pg_pool.clone();
+    // The blocked problems are removed after the search, so it finds as many more problems.
+    let mut problems = conn
+        .search_merged_problems(
+            text,
+            query.difficulty_from,
+            query.difficulty_to,
+            category,
+            limit + BLOCKED_PROBLEMS.len(),
+        )
+        .await?
+        .into_iter()
+        .filter(|p| !BLOCKED_PROBLEMS.contains(&p.id.as_str()))
+        .collect::<Vec<_>>();
+    problems.truncate(limit);
+    let response = Response::json_with_fields(&problems, query.fields.as_deref())?.make_cors();
+    Ok(response)
+}
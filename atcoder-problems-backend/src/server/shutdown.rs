@@ -0,0 +1,126 @@
+use async_std::task;
+use async_trait::async_trait;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_SHUTDOWN_TIMEOUT_SECOND: u64 = 30;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Reads `SHUTDOWN_TIMEOUT_SECOND`, how long the server waits for the in-flight requests on shutdown.
+pub(crate) fn shutdown_timeout_from_env() -> Duration {
+    let timeout = env::var("SHUTDOWN_TIMEOUT_SECOND")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECOND);
+    Duration::from_secs(timeout)
+}
+
+/// Counts the requests being processed, so that the server can finish them before exiting.
+#[derive(Clone, Default)]
+pub(crate) struct InFlightRequests {
+    count: Arc<AtomicUsize>,
+    excluded_paths: &'static [&'static str],
+}
+
+/// Decrements the count when dropped, even if the request is cancelled on the way.
+struct InFlightGuard {
+    count: Arc<AtomicUsize>,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.count.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl InFlightRequests {
+    /// Does not count the requests to `excluded_paths`, e.g. SSE and WebSocket, which stay open
+    /// until the clients leave. They are closed without waiting when the server exits.
+    pub(crate) fn excluding(excluded_paths: &'static [&'static str]) -> Self {
+        Self {
+            count: Arc::default(),
+            excluded_paths,
+        }
+    }
+
+    fn is_excluded(&self, path: &str) -> bool {
+        self.excluded_paths.contains(&path)
+    }
+
+    fn enter(&self) -> InFlightGuard {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard {
+            count: self.count.clone(),
+        }
+    }
+
+    pub(crate) fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Waits until all the requests are finished, or `timeout` elapses.
+    /// Returns false if some requests are still in flight.
+    pub(crate) async fn wait_until_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while self.count() > 0 {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            task::sleep(POLL_INTERVAL).await;
+        }
+        true
+    }
+}
+
+#[async_trait]
+impl<State> tide::Middleware<State> for InFlightRequests
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn handle(&self, req: tide::Request<State>, next: tide::Next<'_, State>) -> tide::Result {
+        if self.is_excluded(req.url().path()) {
+            return Ok(next.run(req).await);
+        }
+        let _guard = self.enter();
+        Ok(next.run(req).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wait_until_idle() {
+        let in_flight = InFlightRequests::default();
+        let guard = in_flight.enter();
+        let other = in_flight.enter();
+        assert_eq!(in_flight.count(), 2);
+        drop(other);
+        assert_eq!(in_flight.count(), 1);
+
+        assert!(!task::block_on(
+            in_flight.wait_until_idle(Duration::from_millis(10))
+        ));
+
+        task::spawn(async move {
+            task::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        assert!(task::block_on(
+            in_flight.wait_until_idle(Duration::from_secs(10))
+        ));
+        assert_eq!(in_flight.count(), 0);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let in_flight = InFlightRequests::excluding(&["/ws/submissions"]);
+        assert!(in_flight.is_excluded("/ws/submissions"));
+        assert!(!in_flight.is_excluded("/ws/submissions/1"));
+        assert!(!in_flight.is_excluded("/atcoder-api/v3/recent"));
+        assert!(!InFlightRequests::default().is_excluded("/ws/submissions"));
+    }
+}
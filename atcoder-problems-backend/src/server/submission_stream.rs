@@ -0,0 +1,63 @@
+use crate::server::broadcaster::Broadcaster;
+use crate::server::AppData;
+use async_std::task;
+use serde::Deserialize;
+use sql_client::models::Submission;
+use sql_client::submission_listener::SubmissionListener;
+use sql_client::PgPool;
+use std::collections::BTreeSet;
+use std::time::Duration;
+use tide::{Request, Result};
+use tide_websockets::WebSocketConnection;
+
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Broadcasts the submissions notified by the database.
+pub(crate) async fn listen_submissions(broadcaster: Broadcaster<Submission>, pg_pool: PgPool) {
+    let mut listener = loop {
+        match SubmissionListener::connect(&pg_pool).await {
+            Ok(listener) => break listener,
+            Err(e) => {
+                log::error!("Failed to listen to submissions: {:?}", e);
+                task::sleep(RETRY_INTERVAL).await;
+            }
+        }
+    };
+    loop {
+        match listener.recv().await {
+            Ok(submission) => broadcaster.broadcast(submission),
+            Err(e) => {
+                log::error!("Failed to receive a submission: {:?}", e);
+                task::sleep(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+pub(crate) async fn stream_submissions<A>(
+    request: Request<AppData<A>>,
+    mut stream: WebSocketConnection,
+) -> Result<()> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        users: Option<String>,
+    }
+    let query = request.query::<Query>()?;
+    let users = query.users.map(|users| {
+        users
+            .split(',')
+            .map(|user| user.trim().to_lowercase())
+            .collect::<BTreeSet<_>>()
+    });
+
+    let receiver = request.state().submission_broadcaster.subscribe();
+    while let Ok(submission) = receiver.recv().await {
+        if let Some(users) = users.as_ref() {
+            if !users.contains(&submission.user_id.as_str().to_lowercase()) {
+                continue;
+            }
+        }
+        stream.send_json(submission.as_ref()).await?;
+    }
+    Ok(())
+}
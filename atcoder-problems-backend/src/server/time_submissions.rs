@@ -1,17 +1,32 @@
-use crate::server::{AppData, CommonResponse};
+use crate::server::{csv, AppData, CommonResponse};
+use serde::Deserialize;
 use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
 use tide::{Request, Response, Result};
 
+const TIME_SUBMISSION_LIMIT: i64 = 1000;
+
 pub(crate) async fn get_time_submissions<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        from_id: Option<i64>,
+        fields: Option<String>,
+    }
     let from = request.param("from")?;
     let from_epoch_second = from.parse::<i64>()?;
-    let conn = request.state().pg_pool.clone();
-    let submissions: Vec<_> = conn
-        .get_submissions(SubmissionRequest::FromTime {
+    let query = request.query::<Query>()?;
+    let submission_request = match query.from_id {
+        Some(from_id) => SubmissionRequest::FromCursor {
             from_second: from_epoch_second,
-            count: 1000,
-        })
-        .await?;
-    let response = Response::json(&submissions)?.make_cors();
+            from_id,
+            count: TIME_SUBMISSION_LIMIT,
+        },
+        None => SubmissionRequest::FromTime {
+            from_second: from_epoch_second,
+            count: TIME_SUBMISSION_LIMIT,
+        },
+    };
+    let conn = request.state().pg_pool.clone();
+    let submissions: Vec<_> = conn.get_submissions(submission_request).await?;
+    let response = csv::negotiate(&request, &submissions, query.fields.as_deref())?.make_cors();
     Ok(response)
 }
@@ -0,0 +1,19 @@
+use crate::server::{AppData, CommonResponse};
+use serde::Deserialize;
+use sql_client::ids::UserId;
+use sql_client::training_velocity::TrainingVelocityClient;
+use tide::{Request, Response, Result};
+
+pub(crate) async fn get_user_training_velocity<A>(
+    request: Request<AppData<A>>,
+) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        user: UserId,
+    }
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    let velocities = conn.load_training_velocity(&query.user).await?;
+    let response = Response::json(&velocities)?.make_cors();
+    Ok(response)
+}
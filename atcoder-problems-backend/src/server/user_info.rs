@@ -2,16 +2,22 @@ use crate::server::{AppData, CommonResponse};
 
 use serde::{Deserialize, Serialize};
 use sql_client::accepted_count::AcceptedCountClient;
+use sql_client::ids::UserId;
+use sql_client::language_count::LanguageCountClient;
 use sql_client::rated_point_sum::RatedPointSumClient;
-use tide::{Request, Response, Result};
+use sql_client::streak::StreakUpdater;
+use sql_client::PgPool;
+use tide::{Request, Response, Result, StatusCode};
+
+const MAX_BATCH_USER_COUNT: usize = 100;
 
 #[derive(Deserialize)]
 struct Query {
-    user: String,
+    user: UserId,
 }
 #[derive(Serialize)]
 struct UserInfo {
-    user_id: String,
+    user_id: UserId,
     accepted_count: i32,
     accepted_count_rank: i64,
     rated_point_sum: f64,
@@ -22,11 +28,11 @@ pub(crate) async fn get_user_info<A>(request: Request<AppData<A>>) -> Result<Res
     let conn = request.state().pg_pool.clone();
     let query = request.query::<Query>()?;
     let user_id = query.user;
-    let accepted_count = conn.get_users_accepted_count(&user_id).await.unwrap_or(0);
+    let accepted_count = conn.get_users_accepted_count(&user_id).await?.unwrap_or(0);
     let accepted_count_rank = conn.get_accepted_count_rank(accepted_count).await?;
     let rated_point_sum = conn
         .get_users_rated_point_sum(&user_id)
-        .await
+        .await?
         .unwrap_or(0.0);
     let rated_point_sum_rank = conn.get_rated_point_sum_rank(rated_point_sum).await?;
 
@@ -40,3 +46,85 @@ pub(crate) async fn get_user_info<A>(request: Request<AppData<A>>) -> Result<Res
     let response = Response::json(&user_info)?.make_cors();
     Ok(response)
 }
+
+#[derive(Serialize)]
+struct LanguageCount {
+    language: String,
+    count: i32,
+}
+
+#[derive(Serialize)]
+struct UserSummary {
+    user_id: UserId,
+    accepted_count: i32,
+    accepted_count_rank: i64,
+    rated_point_sum: f64,
+    rated_point_sum_rank: i64,
+    max_streak: i64,
+    max_streak_rank: i64,
+    language_counts: Vec<LanguageCount>,
+}
+
+async fn load_user_summary(conn: &PgPool, user_id: UserId) -> anyhow::Result<UserSummary> {
+    // All values are read from the aggregation tables, which are looked up concurrently.
+    let (accepted_count, rated_point_sum, max_streak, language_counts) = futures::join!(
+        conn.get_users_accepted_count(&user_id),
+        conn.get_users_rated_point_sum(&user_id),
+        conn.get_users_max_streak(&user_id),
+        conn.load_users_language_count(&user_id),
+    );
+    let accepted_count = accepted_count?.unwrap_or(0);
+    let rated_point_sum = rated_point_sum?.unwrap_or(0.0);
+    let max_streak = max_streak?.unwrap_or(0);
+    let (accepted_count_rank, rated_point_sum_rank, max_streak_rank) = futures::try_join!(
+        conn.get_accepted_count_rank(accepted_count),
+        conn.get_rated_point_sum_rank(rated_point_sum),
+        conn.get_max_streak_rank(max_streak),
+    )?;
+    let language_counts = language_counts?
+        .into_iter()
+        .map(|c| LanguageCount {
+            language: c.simplified_language,
+            count: c.problem_count,
+        })
+        .collect();
+
+    Ok(UserSummary {
+        user_id,
+        accepted_count,
+        accepted_count_rank,
+        rated_point_sum,
+        rated_point_sum_rank,
+        max_streak,
+        max_streak_rank,
+        language_counts,
+    })
+}
+
+pub(crate) async fn get_user_summary<A>(request: Request<AppData<A>>) -> Result<Response> {
+    let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
+    let user_summary = load_user_summary(&conn, query.user).await?;
+    let response = Response::json(&user_summary)?.make_cors();
+    Ok(response)
+}
+
+pub(crate) async fn get_users_summaries<A>(mut request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize)]
+    struct Body {
+        user_ids: Vec<UserId>,
+    }
+    let body: Body = request.body_json().await?;
+    if body.user_ids.len() > MAX_BATCH_USER_COUNT {
+        return Ok(Response::new(StatusCode::BadRequest));
+    }
+    let conn = request.state().pg_pool.clone();
+    let user_summaries = futures::future::try_join_all(
+        body.user_ids
+            .into_iter()
+            .map(|user_id| load_user_summary(&conn, user_id)),
+    )
+    .await?;
+    let response = Response::json(&user_summaries)?.make_cors();
+    Ok(response)
+}
@@ -1,23 +1,40 @@
-use crate::server::{AppData, CommonResponse};
+use crate::server::{csv, AppData, CommonResponse};
+use futures::stream::TryStreamExt;
 use serde::Deserialize;
-use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::compressed_text::{CompressedTextClient, TextTable};
+use sql_client::ids::{ProblemId, UserId};
+use sql_client::submission_client::{stream_user_submissions, SubmissionClient, SubmissionRequest};
 use tide::http::headers::CACHE_CONTROL;
-use tide::{Request, Response, Result};
+use tide::{Request, Response, Result, StatusCode};
 
-const USER_SUBMISSION_LIMIT: usize = 500;
+const USER_SUBMISSION_LIMIT: i64 = 500;
 
 pub(crate) async fn get_user_submissions<A>(request: Request<AppData<A>>) -> Result<Response> {
     #[derive(Deserialize, Debug)]
     struct Query {
-        user: String,
+        user: UserId,
+        fields: Option<String>,
     }
     let conn = request.state().pg_pool.clone();
     let query = request.query::<Query>()?;
-    let user_id = &query.user;
-    let submissions = conn
-        .get_submissions(SubmissionRequest::UserAll { user_id })
-        .await?;
-    let mut response = Response::json(&submissions)?.make_cors();
+    let user_id = query.user;
+    let mut response = if csv::wants_csv(&request) {
+        // Some users have too many submissions to be held in memory at once.
+        csv::stream_csv(query.fields.as_deref(), |mut csv| async move {
+            let mut submissions = stream_user_submissions(&conn, &user_id);
+            while let Some(submission) = submissions.try_next().await? {
+                csv.send(&submission).await?;
+            }
+            Ok(())
+        })
+        .await?
+    } else {
+        let submissions = conn
+            .get_submissions(SubmissionRequest::UserAll { user_id: &user_id })
+            .await?;
+        csv::negotiate(&request, &submissions, query.fields.as_deref())?
+    }
+    .make_cors();
     response.insert_header(CACHE_CONTROL, "max-age=300");
     Ok(response)
 }
@@ -27,29 +44,43 @@ pub(crate) async fn get_user_submissions_from_time<A>(
 ) -> Result<Response> {
     #[derive(Deserialize, Debug)]
     struct Query {
-        user: String,
+        user: UserId,
         from_second: i64,
+        from_id: Option<i64>,
+        fields: Option<String>,
     }
     let conn = request.state().pg_pool.clone();
     let query = request.query::<Query>()?;
     let user_id = &query.user;
-    let submissions = conn
-        .get_submissions(SubmissionRequest::FromUserAndTime {
+    let submission_request = match query.from_id {
+        Some(from_id) => SubmissionRequest::FromUserAndCursor {
             user_id,
             from_second: query.from_second,
+            from_id,
             count: USER_SUBMISSION_LIMIT,
-        })
-        .await?;
-    let response = Response::json(&submissions)?.make_cors();
+        },
+        None => SubmissionRequest::FromUserAndTime {
+            user_id,
+            from_second: query.from_second,
+            count: USER_SUBMISSION_LIMIT,
+        },
+    };
+    let submissions = conn.get_submissions(submission_request).await?;
+    let response = csv::negotiate(&request, &submissions, query.fields.as_deref())?.make_cors();
     Ok(response)
 }
 
 pub(crate) async fn get_recent_submissions<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        fields: Option<String>,
+    }
     let conn = request.state().pg_pool.clone();
+    let query = request.query::<Query>()?;
     let submissions = conn
         .get_submissions(SubmissionRequest::RecentAll { count: 1000 })
         .await?;
-    let response = Response::json(&submissions)?;
+    let response = csv::negotiate(&request, &submissions, query.fields.as_deref())?;
     Ok(response)
 }
 
@@ -62,15 +93,20 @@ pub(crate) async fn get_users_time_submissions<A>(
         problems: String,
         from: i64,
         to: i64,
+        fields: Option<String>,
     }
 
     let conn = request.state().pg_pool.clone();
     let query = request.query::<Query>()?;
-    let user_ids = query.users.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+    let user_ids = query
+        .users
+        .split(',')
+        .map(|s| UserId::from(s.trim()))
+        .collect::<Vec<_>>();
     let problem_ids = query
         .problems
         .split(',')
-        .map(|s| s.trim())
+        .map(|s| ProblemId::from(s.trim()))
         .collect::<Vec<_>>();
     let submissions = conn
         .get_submissions(SubmissionRequest::UsersProblemsTime {
@@ -80,6 +116,43 @@ pub(crate) async fn get_users_time_submissions<A>(
             to_second: query.to,
         })
         .await?;
-    let response = Response::json(&submissions)?;
+    let response = csv::negotiate(&request, &submissions, query.fields.as_deref())?;
     Ok(response)
 }
+
+/// Returns the submission, with its `source_code` if `source=true` and the source code is stored
+/// in `submission_source_codes`. The field is omitted if it is not stored.
+pub(crate) async fn get_submission<A>(request: Request<AppData<A>>) -> Result<Response> {
+    #[derive(Deserialize, Debug)]
+    struct Query {
+        fields: Option<String>,
+        #[serde(default)]
+        source: bool,
+    }
+    let query = request.query::<Query>()?;
+    let id = match request.param("id")?.parse::<i64>() {
+        Ok(id) => id,
+        Err(_) => return Ok(Response::new(StatusCode::NotFound)),
+    };
+    let conn = request.state().pg_pool.clone();
+    let submission = conn
+        .get_submissions(SubmissionRequest::ByIds { ids: &[id] })
+        .await?
+        .pop();
+    let submission = match submission {
+        Some(submission) => submission,
+        None => return Ok(Response::new(StatusCode::NotFound).make_cors()),
+    };
+    let mut value = serde_json::to_value(&submission)?;
+    if query.source {
+        let source_code = conn
+            .load_texts(TextTable::SourceCodes, &[id.to_string()])
+            .await?
+            .pop();
+        if let Some((_, source_code)) = source_code {
+            value["source_code"] = source_code.into();
+        }
+    }
+    let response = Response::json_with_fields(&value, query.fields.as_deref())?;
+    Ok(response.make_cors())
+}
@@ -2,6 +2,7 @@ use crate::server::{AppData, Authentication};
 use anyhow::Context;
 use async_trait::async_trait;
 use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
 use tide::{Request, Result};
 
 #[async_trait]
@@ -33,3 +34,77 @@ impl<A: Authentication + Clone + Send + Sync + 'static> RequestUnpack for Reques
         Ok(body)
     }
 }
+
+/// Keeps only the comma-separated `fields` of the JSON object, or of each object in the JSON array.
+pub(crate) fn select_fields(value: Value, fields: &str) -> Value {
+    let fields = fields.split(',').map(|f| f.trim()).collect::<Vec<_>>();
+    let select = |object: Map<String, Value>| {
+        object
+            .into_iter()
+            .filter(|(key, _)| fields.contains(&key.as_str()))
+            .collect::<Map<_, _>>()
+    };
+    match value {
+        Value::Object(object) => Value::Object(select(object)),
+        Value::Array(array) => Value::Array(
+            array
+                .into_iter()
+                .map(|value| match value {
+                    Value::Object(object) => Value::Object(select(object)),
+                    value => value,
+                })
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Returns the `fields` which select the same fields in a canonical form, which can be a part of
+/// an ETag. The names which are not of the fields of the responses are dropped, since they select
+/// nothing.
+pub(crate) fn normalize_fields(fields: &str) -> String {
+    let mut fields = fields
+        .split(',')
+        .map(|f| f.trim())
+        .filter(|f| !f.is_empty())
+        .filter(|f| f.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+        .collect::<Vec<_>>();
+    fields.sort_unstable();
+    fields.dedup();
+    fields.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_select_fields() {
+        let value = json!([
+            {"id": 1, "problem_id": "abc001_a", "result": "AC", "point": 100.0},
+            {"id": 2, "problem_id": "abc001_b", "result": "WA", "point": 0.0}
+        ]);
+        assert_eq!(
+            select_fields(value, "problem_id, result,unknown"),
+            json!([
+                {"problem_id": "abc001_a", "result": "AC"},
+                {"problem_id": "abc001_b", "result": "WA"}
+            ])
+        );
+
+        let value = json!({"id": 1, "problem_id": "abc001_a"});
+        assert_eq!(select_fields(value, "id"), json!({"id": 1}));
+        assert_eq!(select_fields(json!([1, 2]), "id"), json!([1, 2]));
+    }
+
+    #[test]
+    fn test_normalize_fields() {
+        assert_eq!(
+            normalize_fields("result, problem_id,result"),
+            "problem_id,result"
+        );
+        assert_eq!(normalize_fields("id,\"x\",,a b"), "id");
+        assert_eq!(normalize_fields(""), "");
+    }
+}
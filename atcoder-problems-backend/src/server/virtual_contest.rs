@@ -1,11 +1,18 @@
+use crate::rating::{
+    simulate_rating_changes, virtual_contest_ranks, RatingRule, VirtualParticipant,
+};
 use crate::server::utils::RequestUnpack;
 use crate::server::{AppData, Authentication, CommonResponse};
 
 use serde::{Deserialize, Serialize};
+use sql_client::ids::{ProblemId, UserId};
 use sql_client::internal::virtual_contest_manager::{
     VirtualContestInfo, VirtualContestItem, VirtualContestManager,
 };
-use tide::{Request, Response, Result};
+use sql_client::submission_client::{SubmissionClient, SubmissionRequest};
+use sql_client::user_profile::UserProfileClient;
+use std::collections::BTreeMap;
+use tide::{Request, Response, Result, StatusCode};
 
 pub(crate) async fn create_contest<A>(request: Request<AppData<A>>) -> Result<Response>
 where
@@ -140,6 +147,75 @@ where
     Ok(response)
 }
 
+/// Returns the rating changes of the participants as if the virtual contest were rated, given its
+/// final standings computed from their submissions in it. The ratings are taken from the crawled
+/// profiles, and the users whose profiles are not crawled yet are not rated.
+pub(crate) async fn simulate_rating<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    #[derive(Deserialize)]
+    struct Q {
+        /// The upper bound of the rated range, e.g. 1999 to simulate an ABC.
+        rated_upper_bound: Option<i64>,
+    }
+    let conn = request.state().pg_pool.clone();
+    let contest_id = request.param("contest_id")?.to_owned();
+    let query = request.parse_body::<Q>().await?;
+    let info = conn.get_single_contest_info(&contest_id).await?;
+    if info.mode.is_some() {
+        // The lockout and training contests have no standings to be rated.
+        return Ok(Response::new(StatusCode::BadRequest));
+    }
+    let items = conn.get_single_contest_problems(&contest_id).await?;
+    let user_ids = conn
+        .get_single_contest_participants(&contest_id)
+        .await?
+        .into_iter()
+        .map(UserId::from)
+        .collect::<Vec<_>>();
+    let problem_ids = items
+        .iter()
+        .map(|item| ProblemId::from(item.id.as_str()))
+        .collect::<Vec<_>>();
+    let submissions = conn
+        .get_submissions(SubmissionRequest::UsersProblemsTime {
+            user_ids: &user_ids,
+            problem_ids: &problem_ids,
+            from_second: info.start_epoch_second,
+            to_second: info.start_epoch_second + info.duration_second - 1,
+        })
+        .await?;
+    let point_overrides = items
+        .iter()
+        .filter_map(|item| item.point.map(|point| (item.id.as_str(), point)))
+        .collect::<BTreeMap<_, _>>();
+    let standings = virtual_contest_ranks(&submissions, &point_overrides, info.penalty_second);
+
+    let ratings = conn
+        .load_ratings_with_competitions(&user_ids)
+        .await?
+        .into_iter()
+        .map(|(user_id, rating, competitions)| (user_id, (rating, competitions as u64)))
+        .collect::<BTreeMap<_, _>>();
+    let participants = standings
+        .into_iter()
+        .filter_map(|(user_id, rank)| {
+            let &(rating, competitions) = ratings.get(&user_id)?;
+            Some(VirtualParticipant {
+                user_id,
+                rank,
+                rating,
+                competitions,
+            })
+        })
+        .collect::<Vec<_>>();
+    let rule = RatingRule::new(&contest_id, query.rated_upper_bound);
+    let changes = simulate_rating_changes(&participants, &rule);
+    let response = Response::json(&changes)?;
+    Ok(response)
+}
+
 pub(crate) async fn get_recent_contests<A>(request: Request<AppData<A>>) -> Result<Response> {
     let conn = request.state().pg_pool.clone();
     let contest = conn.get_recent_contest_info().await?;
@@ -0,0 +1,62 @@
+use crate::server::utils::RequestUnpack;
+use crate::server::{AppData, Authentication, CommonResponse};
+use crate::webhook::check_public_url;
+use serde::Deserialize;
+use sql_client::internal::webhook_manager::{WebhookFilter, WebhookManager};
+use tide::{Request, Response, Result, StatusCode};
+
+pub(crate) async fn get_webhooks<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    let internal_user_id = request.get_authorized_id().await?;
+    let conn = request.state().pg_pool.clone();
+    let webhooks = conn.get_webhooks(&internal_user_id).await?;
+    let response = Response::json(&webhooks)?;
+    Ok(response)
+}
+
+/// Registers a webhook which receives the submissions matching the filter.
+/// Either `user_id` or `contest_id` is required, so that a webhook doesn't receive all the submissions.
+/// The URL must be HTTPS and resolve only to public addresses.
+pub(crate) async fn add_webhook<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    #[derive(Deserialize)]
+    struct Query {
+        url: String,
+        #[serde(flatten)]
+        filter: WebhookFilter,
+    }
+    let internal_user_id = request.get_authorized_id().await?;
+    let conn = request.state().pg_pool.clone();
+    let query = request.parse_body::<Query>().await?;
+    if query.filter.user_id.is_none() && query.filter.contest_id.is_none() {
+        return Ok(Response::new(StatusCode::BadRequest));
+    }
+    if let Err(e) = check_public_url(&query.url).await {
+        log::info!("Rejected the webhook: {:?}", e);
+        return Ok(Response::new(StatusCode::BadRequest));
+    }
+    let webhook = conn
+        .add_webhook(&internal_user_id, &query.url, &query.filter)
+        .await?;
+    let response = Response::json(&webhook)?;
+    Ok(response)
+}
+
+pub(crate) async fn delete_webhook<A>(request: Request<AppData<A>>) -> Result<Response>
+where
+    A: Authentication + Clone + Send + Sync + 'static,
+{
+    #[derive(Deserialize)]
+    struct Query {
+        id: String,
+    }
+    let internal_user_id = request.get_authorized_id().await?;
+    let conn = request.state().pg_pool.clone();
+    let query = request.parse_body::<Query>().await?;
+    conn.delete_webhook(&internal_user_id, &query.id).await?;
+    Ok(Response::ok())
+}
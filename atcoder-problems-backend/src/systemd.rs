@@ -0,0 +1,49 @@
+use std::env;
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+/// Tells systemd that the service has started, when it runs with `Type=notify`.
+pub fn notify_ready() {
+    notify("READY=1");
+}
+
+/// Tells systemd that the service is alive, when it runs with `WatchdogSec`. The crawl loops call
+/// this whenever they make progress, so systemd restarts the crawler if it gets stuck.
+pub fn notify_watchdog() {
+    notify("WATCHDOG=1");
+}
+
+fn notify(state: &str) {
+    let socket = match env::var_os("NOTIFY_SOCKET") {
+        Some(socket) => socket,
+        None => return,
+    };
+    if let Err(e) = send(Path::new(&socket), state) {
+        log::warn!("Failed to notify systemd of {}: {:?}", state, e);
+    }
+}
+
+fn send(socket: &Path, state: &str) -> io::Result<()> {
+    let datagram = UnixDatagram::unbound()?;
+    datagram.send_to(state.as_bytes(), socket)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send() {
+        let path = env::temp_dir().join(format!("atcoder-problems-notify-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let receiver = UnixDatagram::bind(&path).unwrap();
+
+        send(&path, "WATCHDOG=1").unwrap();
+        let mut buf = [0; 64];
+        let size = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..size], b"WATCHDOG=1");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
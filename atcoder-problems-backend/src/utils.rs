@@ -1,14 +1,277 @@
-use log::LevelFilter;
+use crate::server::middleware::TraceId;
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
 use simple_logger::SimpleLogger;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
-pub fn init_log_config() -> Result<()> {
-    SimpleLogger::new()
-        .with_level(LevelFilter::Info)
-        .with_module_level("sqlx", LevelFilter::Warn)
-        .with_module_level("tide", LevelFilter::Warn)
-        .with_module_level("surf", LevelFilter::Warn)
-        .init()?;
+/// The dependencies which log too much below the warning level.
+const QUIET_MODULES: [&str; 3] = ["sqlx", "tide", "surf"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    Text,
+    /// One JSON object per line, which the log collectors can parse.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(anyhow!("Unknown log format: {}", s)),
+        }
+    }
+}
+
+/// Initializes the logger with the given level, except for the noisy dependencies.
+pub fn init_log_config(level: LevelFilter, format: LogFormat) -> Result<()> {
+    match format {
+        LogFormat::Text => {
+            let mut logger = SimpleLogger::new().with_level(level);
+            for module in QUIET_MODULES.iter() {
+                logger = logger.with_module_level(module, LevelFilter::Warn);
+            }
+            log::set_boxed_logger(Box::new(TraceIdLogger(logger)))?;
+            log::set_max_level(level.max(LevelFilter::Warn));
+        }
+        LogFormat::Json => {
+            log::set_boxed_logger(Box::new(JsonLogger { level }))?;
+            log::set_max_level(level);
+        }
+    }
+    Ok(())
+}
+
+/// Prefixes the records with the trace ID of the request which is being handled, if any.
+struct TraceIdLogger<L>(L);
+
+impl<L: Log> Log for TraceIdLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.0.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        match TraceId::current() {
+            Some(trace_id) => self.0.log(
+                &Record::builder()
+                    .args(format_args!("[{}] {}", trace_id.0, record.args()))
+                    .metadata(record.metadata().clone())
+                    .module_path(record.module_path())
+                    .file(record.file())
+                    .line(record.line())
+                    .build(),
+            ),
+            None => self.0.log(record),
+        }
+    }
+
+    fn flush(&self) {
+        self.0.flush()
+    }
+}
+
+struct JsonLogger {
+    level: LevelFilter,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let target = metadata.target();
+        let is_quiet = QUIET_MODULES
+            .iter()
+            .any(|module| target == *module || target.starts_with(&format!("{}::", module)));
+        let level = if is_quiet {
+            self.level.min(LevelFilter::Warn)
+        } else {
+            self.level
+        };
+        metadata.level() <= level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let event = to_json(record, Utc::now(), TraceId::current());
+        let stdout = io::stdout();
+        let _ = writeln!(stdout.lock(), "{}", event);
+    }
+
+    fn flush(&self) {
+        let _ = io::stdout().flush();
+    }
+}
+
+/// The messages which are JSON objects, e.g. the access logs of the server, are put in `fields`
+/// as they are, so that they do not have to be parsed again.
+fn to_json(record: &Record, timestamp: DateTime<Utc>, trace_id: Option<TraceId>) -> Value {
+    let message = record.args().to_string();
+    let mut event = json!({
+        "timestamp": timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "level": record.level().to_string(),
+        "target": record.target(),
+    });
+    match serde_json::from_str::<Value>(&message) {
+        Ok(fields @ Value::Object(_)) => event["fields"] = fields,
+        _ => event["message"] = Value::String(message),
+    }
+    if let Some(trace_id) = trace_id {
+        event["trace_id"] = Value::String(trace_id.0);
+    }
+    event
+}
+
+/// Writes to a temporary file first and renames it, so that the readers never see
+/// a half-written file.
+pub fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, data)?;
+    fs::rename(&temporary, path)?;
     Ok(())
 }
+
+/// A writer which computes the SHA-256 of the bytes written through it.
+pub struct HashingWriter<W> {
+    writer: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// Returns the inner writer and the digest in lowercase hex.
+    pub fn finish(self) -> (W, String) {
+        (self.writer, hex::encode(self.hasher.finalize()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let size = self.writer.write(buf)?;
+        self.hasher.update(&buf[..size]);
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use log::Level;
+
+    #[test]
+    fn test_to_json() {
+        let timestamp = Utc.timestamp(1_600_000_000, 0);
+        let event = to_json(
+            &Record::builder()
+                .args(format_args!("Crawling {}", "abc001"))
+                .level(Level::Info)
+                .target("atcoder_problems_backend::crawler")
+                .build(),
+            timestamp,
+            None,
+        );
+        assert_eq!(
+            event,
+            json!({
+                "timestamp": "2020-09-13T12:26:40.000Z",
+                "level": "INFO",
+                "target": "atcoder_problems_backend::crawler",
+                "message": "Crawling abc001",
+            })
+        );
+
+        let event = to_json(
+            &Record::builder()
+                .args(format_args!("{}", r#"{"trace_id":"abc","status":200}"#))
+                .level(Level::Error)
+                .target("atcoder_problems_backend::server::middleware")
+                .build(),
+            timestamp,
+            None,
+        );
+        assert_eq!(
+            event["fields"],
+            json!({
+                "trace_id": "abc",
+                "status": 200,
+            })
+        );
+        assert_eq!(event["level"], "ERROR");
+        assert!(event.get("message").is_none());
+
+        let event = to_json(
+            &Record::builder()
+                .args(format_args!("slow statement"))
+                .level(Level::Warn)
+                .target("sqlx::query")
+                .build(),
+            timestamp,
+            Some(TraceId("abc".to_string())),
+        );
+        assert_eq!(event["trace_id"], "abc");
+    }
+
+    #[test]
+    fn test_enabled() {
+        let logger = JsonLogger {
+            level: LevelFilter::Info,
+        };
+        fn metadata(level: Level, target: &str) -> Metadata<'_> {
+            Metadata::builder().level(level).target(target).build()
+        }
+        assert!(logger.enabled(&metadata(Level::Info, "atcoder_problems_backend")));
+        assert!(!logger.enabled(&metadata(Level::Debug, "atcoder_problems_backend")));
+        assert!(!logger.enabled(&metadata(Level::Info, "sqlx::query")));
+        assert!(logger.enabled(&metadata(Level::Warn, "sqlx::query")));
+        assert!(logger.enabled(&metadata(Level::Info, "sqlx_extra")));
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let path = std::env::temp_dir().join(format!(
+            "atcoder-problems-utils-{}.json",
+            std::process::id()
+        ));
+        write_atomically(&path, b"[1]").unwrap();
+        write_atomically(&path, b"[1,2]").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"[1,2]");
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        assert!(!Path::new(&temporary).exists());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_hashing_writer() {
+        let mut writer = HashingWriter::new(Vec::new());
+        writer.write_all(b"ab").unwrap();
+        writer.write_all(b"c").unwrap();
+        let (written, digest) = writer.finish();
+        assert_eq!(written, b"abc");
+        assert_eq!(
+            digest,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use anyhow::{anyhow, bail, Result};
+use async_std::channel::bounded;
+use async_std::net::ToSocketAddrs;
+use async_std::task;
+use hmac::{Hmac, Mac, NewMac};
+use isahc::config::{Configurable, Dialer, RedirectPolicy};
+use isahc::http::header::CONTENT_TYPE;
+use isahc::http::Request;
+use isahc::RequestExt;
+use sha2::Sha256;
+use sql_client::internal::webhook_manager::{Webhook, WebhookManager};
+use sql_client::models::Submission;
+use sql_client::submission_listener::SubmissionListener;
+use sql_client::PgPool;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use surf::http::url::{Host, Url};
+
+/// The header which contains the HMAC-SHA256 of the payload, signed with the secret of the webhook.
+pub const SIGNATURE_HEADER: &str = "X-AtCoder-Problems-Signature";
+
+const MAX_DELIVERY_ATTEMPTS: u32 = 4;
+
+/// The number of the deliveries in flight at once, so that slow webhooks can't pile up the tasks.
+const MAX_CONCURRENT_DELIVERIES: usize = 16;
+
+/// Returns the signature of the payload in the form of `sha256={hex digest}`.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Returns whether the address is reachable on the public internet, i.e. not in the loopback, the
+/// private, the link-local or the other special-purpose ranges.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        // The IPv4-mapped and the IPv4-compatible addresses, including `::` and `::1` in 0.0.0.0/8.
+        IpAddr::V6(ip) => match ip.to_ipv4() {
+            Some(ipv4) => is_public_ipv4(ipv4),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(a == 0
+        || ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_multicast()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        // The shared address space of the carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && (b & 0xc0) == 64)
+        // The IETF protocol assignments, 192.0.0.0/24.
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        // The benchmarking networks, 198.18.0.0/15.
+        || (a == 198 && (b & 0xfe) == 18)
+        // The reserved addresses, 240.0.0.0/4.
+        || a >= 240)
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // The unique local addresses, fc00::/7.
+        || (first & 0xfe00) == 0xfc00
+        // The link-local addresses, fe80::/10.
+        || (first & 0xffc0) == 0xfe80
+        // The documentation addresses, 2001:db8::/32.
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+/// Resolves the host of the HTTPS URL, and returns an error unless all of its addresses are public,
+/// so that the webhooks can't be used to reach the internal network of the server. Returns one of
+/// the addresses, which the request has to connect to instead of resolving the host again.
+pub async fn check_public_url(url: &str) -> Result<SocketAddr> {
+    let url = Url::parse(url)?;
+    if url.scheme() != "https" {
+        bail!("{} is not an HTTPS URL", url);
+    }
+    let port = url.port_or_known_default().unwrap_or(443);
+    let ips = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![IpAddr::V4(ip)],
+        Some(Host::Ipv6(ip)) => vec![IpAddr::V6(ip)],
+        Some(Host::Domain(domain)) => (domain, port)
+            .to_socket_addrs()
+            .await?
+            .map(|addr| addr.ip())
+            .collect(),
+        None => bail!("{} has no host", url),
+    };
+    if ips.is_empty() {
+        bail!("{} has no address", url);
+    }
+    match ips.iter().find(|&&ip| !is_public_ip(ip)) {
+        Some(ip) => Err(anyhow!("{} resolves to a non-public address {}", url, ip)),
+        None => Ok(SocketAddr::new(ips[0], port)),
+    }
+}
+
+/// Posts the submission to the webhook, retrying with exponential backoff until it succeeds.
+/// The host is checked again before each attempt, since it may have been resolved to another
+/// address after the webhook was registered, and the request connects to the checked address, so
+/// that the host can't be resolved to another address in between. The `Host` header and the SNI
+/// are still of the host of the URL. The redirects are not followed.
+pub async fn deliver(webhook: &Webhook, submission: &Submission) -> Result<()> {
+    let payload = serde_json::to_vec(submission)?;
+    let signature = sign(&webhook.secret, &payload);
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let addr = check_public_url(&webhook.url).await?;
+        let request = Request::post(webhook.url.as_str())
+            .header(SIGNATURE_HEADER, signature.as_str())
+            .header(CONTENT_TYPE, "application/json")
+            .dial(Dialer::ip_socket(addr))
+            .redirect_policy(RedirectPolicy::None)
+            .body(payload.clone())?;
+        let result = request.send_async().await;
+        match result {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if attempt >= MAX_DELIVERY_ATTEMPTS => {
+                bail!("{} returned {}", webhook.url, response.status())
+            }
+            Err(e) if attempt >= MAX_DELIVERY_ATTEMPTS => {
+                bail!("Failed to post to {}: {:?}", webhook.url, e)
+            }
+            _ => task::sleep(Duration::from_secs(1 << attempt)).await,
+        }
+    }
+}
+
+/// Delivers the submissions inserted or updated by the crawlers to the matching webhooks, with
+/// at most `MAX_CONCURRENT_DELIVERIES` deliveries at once. The listener waits while all the
+/// workers are busy, and the queued deliveries are finished before returning an error.
+pub async fn deliver_submissions(pg_pool: PgPool) -> Result<()> {
+    let (sender, receiver) = bounded::<(Webhook, Submission)>(MAX_CONCURRENT_DELIVERIES);
+    let workers = (0..MAX_CONCURRENT_DELIVERIES)
+        .map(|_| {
+            let receiver = receiver.clone();
+            task::spawn(async move {
+                while let Ok((webhook, submission)) = receiver.recv().await {
+                    if let Err(e) = deliver(&webhook, &submission).await {
+                        log::error!(
+                            "Failed to deliver {} to {}: {:?}",
+                            submission.id,
+                            webhook.id,
+                            e
+                        );
+                    }
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let result = async {
+        let mut listener = SubmissionListener::connect(&pg_pool).await?;
+        loop {
+            let submission = listener.recv().await?;
+            let webhooks = pg_pool.load_matching_webhooks(&submission).await?;
+            for webhook in webhooks {
+                sender.send((webhook, submission.clone())).await?;
+            }
+        }
+    }
+    .await;
+    drop(sender);
+    for worker in workers {
+        worker.await;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231, Test Case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in &["8.8.8.8", "1.1.1.1", "2001:4860:4860::8888"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in &[
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[test]
+    fn test_check_public_url() {
+        task::block_on(async {
+            assert_eq!(
+                check_public_url("https://1.1.1.1/hook").await.unwrap(),
+                "1.1.1.1:443".parse().unwrap()
+            );
+            assert_eq!(
+                check_public_url("https://[2001:4860:4860::8888]:8443/hook")
+                    .await
+                    .unwrap(),
+                "[2001:4860:4860::8888]:8443".parse().unwrap()
+            );
+            assert!(check_public_url("https://127.0.0.1/hook").await.is_err());
+            assert!(check_public_url("https://[::1]:8080/hook").await.is_err());
+            assert!(check_public_url("https://localhost/hook").await.is_err());
+            assert!(check_public_url("http://1.1.1.1/hook").await.is_err());
+        });
+    }
+}
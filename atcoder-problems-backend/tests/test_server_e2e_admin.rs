@@ -0,0 +1,106 @@
+use async_std::future::ready;
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{run_server, Authentication, GitHubUserResponse};
+use rand::Rng;
+use sql_client::crawl_request::{CrawlRequest, CrawlRequestClient};
+use sql_client::data_version::{DataVersionClient, CONTESTS_DATA};
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        unimplemented!()
+    }
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        unimplemented!()
+    }
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+async fn setup() -> u16 {
+    utils::initialize_and_connect_to_test_sql().await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+#[async_std::test]
+async fn test_admin_api_key() {
+    let port = setup().await;
+    std::env::set_var("ADMIN_API_KEYS", "key1,key2");
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let path = "/admin-api/data_version/contests/increment";
+    let response = surf::post(url(path, port)).await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let response = surf::post(url(path, port))
+        .header("Authorization", "Bearer key3")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    let response = surf::post(url(path, port))
+        .header("Authorization", "Bearer key2")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    let version = pg_pool.load_data_version(CONTESTS_DATA).await.unwrap();
+    assert_eq!(version, 1);
+
+    let response = surf::post(url("/admin-api/data_version/unknown/increment", port))
+        .header("Authorization", "Bearer key1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = surf::post(url("/internal/crawl/contest/abc001", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    let response = surf::post(url("/internal/crawl/contest/abc001?priority=1", port))
+        .header("Authorization", "Bearer key1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+    let response = surf::post(url("/internal/crawl/user/user1", port))
+        .header("Authorization", "Bearer key1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+    assert_eq!(
+        pg_pool.pop_crawl().await.unwrap(),
+        Some((CrawlRequest::Contest("abc001".to_owned()), 0))
+    );
+    assert_eq!(
+        pg_pool.pop_crawl().await.unwrap(),
+        Some((CrawlRequest::User("user1".to_owned()), 0))
+    );
+
+    // Read endpoints stay public.
+    let response = surf::get(url("/atcoder-api/v3/contests", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    server.race(ready(())).await;
+}
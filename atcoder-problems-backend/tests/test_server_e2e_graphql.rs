@@ -0,0 +1,161 @@
+use async_std::future::ready;
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{run_server, Authentication, GitHubUserResponse};
+use rand::Rng;
+use serde_json::{json, Value};
+use sql_client::PgPool;
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        unimplemented!()
+    }
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        unimplemented!()
+    }
+}
+
+async fn prepare_data_set(conn: &PgPool) {
+    sql_client::query(
+        r"INSERT INTO accepted_count (user_id, problem_count) VALUES ('u1', 1), ('u2', 2), ('u3', 1)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"
+    INSERT INTO
+        submissions (epoch_second, problem_id, contest_id, user_id, result, id, language, point, length)
+        VALUES
+            (0,  'p1',   'c1',   'u1',   'WA',   1,  'Rust',    0.0,    0),
+            (1,  'p1',   'c1',   'u1',   'AC',   2,  'Rust',    0.0,    0),
+            (1,  'p2',   'c1',   'u1',   'AC',   3,  'Rust',    0.0,    0)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+async fn setup() -> u16 {
+    prepare_data_set(&utils::initialize_and_connect_to_test_sql().await).await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+async fn graphql(query: &str, port: u16) -> Value {
+    surf::post(url("/atcoder-api/graphql", port))
+        .body(json!({ "query": query }))
+        .recv_json::<Value>()
+        .await
+        .unwrap()
+}
+
+#[async_std::test]
+async fn test_graphql() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = graphql(
+        r"{
+            acceptedCountRanking(first: 2) {
+                edges { cursor node { userId problemCount } }
+                pageInfo { hasNextPage }
+            }
+        }",
+        port,
+    )
+    .await;
+    assert_eq!(
+        response,
+        json!({
+            "data": {
+                "acceptedCountRanking": {
+                    "edges": [
+                        {"cursor": "0", "node": {"userId": "u2", "problemCount": 2}},
+                        {"cursor": "1", "node": {"userId": "u1", "problemCount": 1}}
+                    ],
+                    "pageInfo": {"hasNextPage": true}
+                }
+            }
+        })
+    );
+
+    let response = graphql(
+        r#"{
+            user(id: "u1") {
+                acceptedCount
+                acceptedCountRank
+                submissions(first: 2) {
+                    edges { cursor node { id result } }
+                    pageInfo { hasNextPage }
+                }
+            }
+        }"#,
+        port,
+    )
+    .await;
+    assert_eq!(
+        response,
+        json!({
+            "data": {
+                "user": {
+                    "acceptedCount": 1,
+                    "acceptedCountRank": 1,
+                    "submissions": {
+                        "edges": [
+                            {"cursor": "0:1", "node": {"id": 1, "result": "WA"}},
+                            {"cursor": "1:2", "node": {"id": 2, "result": "AC"}}
+                        ],
+                        "pageInfo": {"hasNextPage": true}
+                    }
+                }
+            }
+        })
+    );
+
+    let response = graphql(
+        r#"{
+            user(id: "u1") {
+                submissions(after: "1:2") {
+                    edges { node { id } }
+                    pageInfo { hasNextPage }
+                }
+            }
+        }"#,
+        port,
+    )
+    .await;
+    assert_eq!(
+        response,
+        json!({
+            "data": {
+                "user": {
+                    "submissions": {
+                        "edges": [{"node": {"id": 3}}],
+                        "pageInfo": {"hasNextPage": false}
+                    }
+                }
+            }
+        })
+    );
+
+    server.race(ready(())).await;
+}
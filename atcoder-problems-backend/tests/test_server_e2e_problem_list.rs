@@ -72,6 +72,7 @@ async fn test_list() {
         .next()
         .unwrap();
     assert_eq!(token, VALID_TOKEN);
+    assert!(cookie.as_str().contains("HttpOnly"));
 
     let response = surf::get(url("/internal-api/list/my", port))
         .header("Cookie", format!("token={}", token))
@@ -392,3 +393,91 @@ async fn test_register_twice() {
     assert_eq!(response.status(), 302);
     server.race(ready(())).await;
 }
+
+#[async_std::test]
+async fn test_logout() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/internal-api/logout", port))
+        .header("Cookie", format!("token={}", VALID_TOKEN))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 302);
+    let cookie = response.header("set-cookie").unwrap();
+    assert!(cookie.as_str().starts_with("token=;"));
+    assert!(cookie.as_str().contains("Max-Age=0"));
+    server.race(ready(())).await;
+}
+
+#[async_std::test]
+async fn test_others_list() {
+    let port = setup().await;
+    let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    sql_client::query("INSERT INTO internal_users (internal_user_id) VALUES ('another_user')")
+        .execute(&pg_pool)
+        .await
+        .unwrap();
+    sql_client::query(
+        r"INSERT INTO internal_problem_lists (internal_list_id, internal_user_id, internal_list_name)
+        VALUES ('another_list', 'another_user', 'b')",
+    )
+    .execute(&pg_pool)
+    .await
+    .unwrap();
+    let server = task::spawn(async move {
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    surf::get(url(
+        &format!("/internal-api/authorize?code={}", VALID_CODE),
+        port,
+    ))
+    .await
+    .unwrap();
+    let cookie_header = format!("token={}", VALID_TOKEN);
+
+    let requests = vec![
+        (
+            "/internal-api/list/update",
+            json!({"internal_list_id": "another_list", "name": "c"}),
+        ),
+        (
+            "/internal-api/list/delete",
+            json!({"internal_list_id": "another_list"}),
+        ),
+        (
+            "/internal-api/list/item/add",
+            json!({"internal_list_id": "another_list", "problem_id": "problem_1"}),
+        ),
+        (
+            "/internal-api/list/item/add",
+            json!({"internal_list_id": "unknown_list", "problem_id": "problem_1"}),
+        ),
+    ];
+    for (path, body) in requests {
+        let response = surf::post(url(path, port))
+            .header("Cookie", cookie_header.as_str())
+            .body(body)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403, "{}", path);
+    }
+
+    let list = surf::get(url("/internal-api/list/get/another_list", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(list["internal_list_name"], json!("b"));
+    assert_eq!(list["items"], json!([]));
+    server.race(ready(())).await;
+}
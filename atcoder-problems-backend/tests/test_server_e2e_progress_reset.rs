@@ -129,5 +129,13 @@ async fn test_progress_reset() {
         })
     );
 
+    // The user has not linked the AtCoder account yet.
+    let response = surf::get(url("/internal-api/progress_reset/solved", port))
+        .header("Cookie", "token=a")
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response, json!([]));
+
     server.race(async_std::future::ready(())).await;
 }
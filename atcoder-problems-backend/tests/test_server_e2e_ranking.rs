@@ -0,0 +1,325 @@
+use async_std::future::ready;
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{run_server, Authentication, GitHubUserResponse};
+use rand::Rng;
+use serde_json::{json, Value};
+use sql_client::data_version::{DataVersionClient, RANKINGS_DATA};
+use sql_client::PgPool;
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        unimplemented!()
+    }
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        unimplemented!()
+    }
+}
+
+async fn prepare_data_set(conn: &PgPool) {
+    sql_client::query(
+        r"INSERT INTO accepted_count (user_id, problem_count) VALUES ('u1', 1), ('u2', 2), ('u3', 1), ('u4', 1)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"INSERT INTO language_count (user_id, simplified_language, problem_count) VALUES ('u1', 'Rust', 1), ('u2', 'C++', 2)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+async fn setup() -> u16 {
+    prepare_data_set(&utils::initialize_and_connect_to_test_sql().await).await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+#[async_std::test]
+async fn test_ranking() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 0,
+            "entries": [
+                {"rank": 1, "user_id": "u2", "value": 2.0},
+                {"rank": 2, "user_id": "u1", "value": 1.0},
+                {"rank": 2, "user_id": "u3", "value": 1.0},
+                {"rank": 2, "user_id": "u4", "value": 1.0}
+            ]
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac?offset=1&limit=2", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 1,
+            "entries": [
+                {"rank": 2, "user_id": "u1", "value": 1.0},
+                {"rank": 2, "user_id": "u3", "value": 1.0}
+            ]
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac?user=u4&limit=2", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 2,
+            "entries": [
+                {"rank": 2, "user_id": "u3", "value": 1.0},
+                {"rank": 2, "user_id": "u4", "value": 1.0}
+            ]
+        })
+    );
+
+    let csv = surf::get(url("/atcoder-api/v3/ranking/ac?limit=2", port))
+        .header("Accept", "text/csv")
+        .recv_string()
+        .await
+        .unwrap();
+    assert_eq!(csv, "rank,user_id,value\r\n1,u2,2.0\r\n2,u1,1.0\r\n");
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/lang?language=Rust", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 0,
+            "entries": [{"rank": 1, "user_id": "u1", "value": 1.0}]
+        })
+    );
+
+    let response = surf::get(url(
+        "/atcoder-api/v3/ranking/language?language=Rust%20(1.42.0)",
+        port,
+    ))
+    .recv_json::<Value>()
+    .await
+    .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 0,
+            "entries": [{"rank": 1, "user_id": "u1", "value": 1.0}]
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/language?language=cpp", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response["entries"],
+        json!([{"rank": 1, "user_id": "u2", "value": 2.0}])
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/lang?language=Go", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = surf::get(url("/atcoder-api/v3/languages", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!([
+            {"language": "C++", "user_count": 1},
+            {"language": "Rust", "user_count": 1}
+        ])
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac?cursor=&limit=3", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response["offset"], json!(0));
+    assert_eq!(response["entries"].as_array().unwrap().len(), 3);
+    let cursor = response["next_cursor"].as_str().unwrap().to_owned();
+
+    let path = format!("/atcoder-api/v3/ranking/ac?cursor={}&limit=3", cursor);
+    let response = surf::get(url(&path, port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 3,
+            "entries": [{"rank": 2, "user_id": "u4", "value": 1.0}],
+            "next_cursor": null
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac?cursor=invalid", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac?user=u5", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/lang", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/ac?limit=2000", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/unknown", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    server.race(ready(())).await;
+}
+
+#[async_std::test]
+async fn test_ranking_cache() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let path = "/atcoder-api/v3/ranking/ac?limit=1";
+    let expected = json!({
+        "offset": 0,
+        "entries": [{"rank": 1, "user_id": "u2", "value": 2.0}]
+    });
+    let response = surf::get(url(path, port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response, expected);
+
+    // The cached response is returned until the version of the rankings is incremented.
+    let conn = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    sql_client::query(r"UPDATE accepted_count SET problem_count = 3 WHERE user_id = 'u1'")
+        .execute(&conn)
+        .await
+        .unwrap();
+    let response = surf::get(url(path, port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response, expected);
+
+    conn.increment_data_version(RANKINGS_DATA).await.unwrap();
+    let response = surf::get(url(path, port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 0,
+            "entries": [{"rank": 1, "user_id": "u1", "value": 3.0}]
+        })
+    );
+
+    server.race(ready(())).await;
+}
+
+#[async_std::test]
+async fn test_current_streak_ranking() {
+    let port = setup().await;
+    let conn = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    let now = chrono::Utc::now().timestamp();
+    sql_client::query(
+        r"INSERT INTO current_streaks (user_id, streak, last_epoch_second) VALUES ('u1', 3, $1), ('u2', 5, 0)",
+    )
+    .bind(now)
+    .execute(&conn)
+    .await
+    .unwrap();
+    sql_client::query(r"INSERT INTO max_streaks (user_id, streak) VALUES ('u1', 3), ('u2', 5)")
+        .execute(&conn)
+        .await
+        .unwrap();
+
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    // The streak of u2 has been broken, while it is still the longest one in the history.
+    let response = surf::get(url("/atcoder-api/v3/ranking/current_streak", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "offset": 0,
+            "entries": [{"rank": 1, "user_id": "u1", "value": 3.0}]
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/ranking/streak", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response["entries"],
+        json!([
+            {"rank": 1, "user_id": "u2", "value": 5.0},
+            {"rank": 2, "user_id": "u1", "value": 3.0}
+        ])
+    );
+
+    server.race(ready(())).await;
+}
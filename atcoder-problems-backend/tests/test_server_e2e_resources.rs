@@ -0,0 +1,362 @@
+use async_std::future::ready;
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{run_server, Authentication, GitHubUserResponse};
+use rand::Rng;
+use serde_json::{json, Value};
+use sql_client::contest_standings::ContestStandingsClient;
+use sql_client::ids::ContestId;
+use sql_client::models::{Contest, StandingsEntry};
+use sql_client::simple_client::SimpleClient;
+use sql_client::PgPool;
+use std::collections::BTreeSet;
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        unimplemented!()
+    }
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        unimplemented!()
+    }
+}
+
+async fn prepare_data_set(conn: &PgPool) {
+    sql_client::query(
+        r"
+        INSERT INTO contests (id, start_epoch_second, duration_second, title, rate_change, category) VALUES
+        ('abc002', 100, 6000, 'ABC 002', '-', 'ABC'),
+        ('abc001', 0, 6000, 'ABC 001', '-', 'ABC'),
+        ('practice', 0, 6000, 'Practice', '-', 'Other Contests')
+        ",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"
+        INSERT INTO problems (id, contest_id, title, problem_index, name) VALUES
+        ('abc001_b', 'abc001', 'B. B', 'B', 'B'),
+        ('abc001_a', 'abc001', 'A. A', 'A', 'A'),
+        ('APG4b_b', 'APG4b', 'B. B', 'B', 'B')
+        ",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"INSERT INTO contest_problem (contest_id, problem_id) VALUES ('abc001', 'abc001_b'), ('abc001', 'abc001_a')",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"INSERT INTO problem_difficulties (problem_id, difficulty) VALUES ('abc001_a', 100.0), ('abc001_b', 500.0)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(r"INSERT INTO solver (problem_id, user_count) VALUES ('abc001_a', 10)")
+        .execute(conn)
+        .await
+        .unwrap();
+    sql_client::query(
+        r"INSERT INTO rated_point_sum (user_id, point_sum) VALUES ('u1', 100), ('u2', 200), ('u3', 100)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+fn set(ids: &[&str]) -> BTreeSet<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+async fn setup() -> u16 {
+    prepare_data_set(&utils::initialize_and_connect_to_test_sql().await).await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+#[async_std::test]
+async fn test_resources() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/atcoder-api/v3/contests", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!([
+            {"id": "abc001", "start_epoch_second": 0, "duration_second": 6000, "title": "ABC 001", "rate_change": "-", "category": "ABC", "rated_range": null, "penalty_minutes": null, "allows_late_join": null},
+            {"id": "abc002", "start_epoch_second": 100, "duration_second": 6000, "title": "ABC 002", "rate_change": "-", "category": "ABC", "rated_range": null, "penalty_minutes": null, "allows_late_join": null}
+        ])
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/problems", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!([
+            {"id": "abc001_a", "contest_id": "abc001", "title": "A. A", "problem_index": "A", "name": "A"},
+            {"id": "abc001_b", "contest_id": "abc001", "title": "B. B", "problem_index": "B", "name": "B"}
+        ])
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/contest-problem", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!([
+            {"contest_id": "abc001", "problem_id": "abc001_a"},
+            {"contest_id": "abc001", "problem_id": "abc001_b"}
+        ])
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/merged-problems", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    let merged_problems = response.as_array().unwrap();
+    assert_eq!(merged_problems.len(), 2);
+    assert_eq!(merged_problems[0]["id"], json!("abc001_a"));
+    assert_eq!(merged_problems[0]["solver_count"], json!(10));
+    assert_eq!(merged_problems[1]["solver_count"], Value::Null);
+
+    let search = move |path: &'static str| async move {
+        let response = surf::get(url(path, port))
+            .recv_json::<Value>()
+            .await
+            .unwrap();
+        response
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|problem| problem["id"].as_str().unwrap().to_owned())
+            .collect::<BTreeSet<_>>()
+    };
+    // APG4b_b is one of the blocked problems.
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?q=B.").await,
+        set(&["abc001_b"])
+    );
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?q=abc001&difficulty_from=200").await,
+        set(&["abc001_b"])
+    );
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?difficulty_to=200").await,
+        set(&["abc001_a"])
+    );
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?q=%25").await,
+        set(&[])
+    );
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?q=B.&tag=ABC").await,
+        set(&["abc001_b"])
+    );
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?tag=Other%20Contests").await,
+        set(&[])
+    );
+    let response = surf::get(url("/atcoder-api/v3/problems/search?tag=XYZ", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(
+        search("/atcoder-api/v3/problems/search?limit=1")
+            .await
+            .len(),
+        1
+    );
+
+    let response = surf::get(url(
+        "/atcoder-api/v3/rated_point_sum_ranking?from=0&to=10",
+        port,
+    ))
+    .recv_json::<Value>()
+    .await
+    .unwrap();
+    assert_eq!(
+        response,
+        json!([
+            {"user_id": "u2", "point_sum": 200.0},
+            {"user_id": "u1", "point_sum": 100.0},
+            {"user_id": "u3", "point_sum": 100.0}
+        ])
+    );
+
+    let response = surf::get(url(
+        "/atcoder-api/v3/rated_point_sum_ranking?from=0&to=2000",
+        port,
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 400);
+
+    server.race(ready(())).await;
+}
+
+#[async_std::test]
+async fn test_resources_etag() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/atcoder-api/v3/contests", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let etag = response.header("etag").unwrap().as_str().to_owned();
+
+    let response = surf::get(url("/atcoder-api/v3/contests", port))
+        .header("if-none-match", etag.as_str())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 304);
+    assert_eq!(response.header("etag").unwrap().as_str(), etag);
+
+    // Inserting a new contest changes the version of the contests.
+    let conn = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    conn.insert_contests(&[Contest::new(
+        "abc003".to_owned(),
+        200,
+        6000,
+        "ABC 003".to_owned(),
+        "-".to_owned(),
+    )])
+    .await
+    .unwrap();
+    let mut response = surf::get(url("/atcoder-api/v3/contests", port))
+        .header("if-none-match", etag.as_str())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_ne!(response.header("etag").unwrap().as_str(), etag);
+    let contests: Value = response.body_json().await.unwrap();
+    assert_eq!(contests.as_array().unwrap().len(), 3);
+
+    // The responses with only some fields do not match the ETag of the whole ones.
+    let response = surf::get(url("/atcoder-api/v3/merged-problems", port))
+        .await
+        .unwrap();
+    let etag = response.header("etag").unwrap().as_str().to_owned();
+    let response = surf::get(url("/atcoder-api/v3/merged-problems?fields=id", port))
+        .header("if-none-match", etag.as_str())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let fields_etag = response.header("etag").unwrap().as_str().to_owned();
+    assert_ne!(fields_etag, etag);
+    let response = surf::get(url("/atcoder-api/v3/merged-problems?fields=id,id", port))
+        .header("if-none-match", fields_etag.as_str())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 304);
+
+    server.race(ready(())).await;
+}
+
+#[async_std::test]
+async fn test_contest_standings() {
+    let port = setup().await;
+    let conn = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    conn.update_contest_standings(
+        &ContestId::from("abc001"),
+        &[StandingsEntry {
+            user_id: "u1".to_owned(),
+            rank: 1,
+            is_rated: false,
+            old_rating: 0,
+            solved_count: 2,
+            point: 200.0,
+            elapsed_second: 600,
+            penalty: 0,
+        }],
+        7000,
+    )
+    .await
+    .unwrap();
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/contest_standings?contest=abc001",
+        port,
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.header("cache-control").unwrap().as_str(),
+        "max-age=3600"
+    );
+    let standings: Value = response.body_json().await.unwrap();
+    assert_eq!(
+        standings,
+        json!({
+            "contest_id": "abc001",
+            "updated_epoch_second": 7000,
+            "is_running": false,
+            "is_final": true,
+            "standings": [{
+                "user_id": "u1",
+                "rank": 1,
+                "is_rated": false,
+                "old_rating": 0,
+                "solved_count": 2,
+                "point": 200.0,
+                "elapsed_second": 600,
+                "penalty": 0
+            }]
+        })
+    );
+
+    let response = surf::get(url(
+        "/atcoder-api/v3/contest_standings?contest=abc002",
+        port,
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 404);
+
+    server.race(ready(())).await;
+}
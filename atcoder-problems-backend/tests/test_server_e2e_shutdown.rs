@@ -0,0 +1,64 @@
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{
+    run_server_with_shutdown, Authentication, GitHubUserResponse,
+};
+use futures::channel::oneshot;
+use rand::Rng;
+use std::time::Duration;
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        unimplemented!()
+    }
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        unimplemented!()
+    }
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+async fn setup() -> u16 {
+    utils::initialize_and_connect_to_test_sql().await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+#[async_std::test]
+async fn test_graceful_shutdown() {
+    let port = setup().await;
+    let (sender, receiver) = oneshot::channel::<()>();
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        let shutdown = async {
+            receiver.await.ok();
+        };
+        run_server_with_shutdown(pg_pool, MockAuth, port, shutdown).await
+    });
+    task::sleep(Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/healthz", port)).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    sender.send(()).unwrap();
+    let result = server.timeout(Duration::from_secs(10)).await;
+    assert!(matches!(result, Ok(Ok(()))));
+
+    // A new client, because the connection kept alive by the global client is still served.
+    assert!(surf::Client::new()
+        .get(url("/healthz", port))
+        .await
+        .is_err());
+}
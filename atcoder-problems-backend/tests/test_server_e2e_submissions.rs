@@ -5,6 +5,8 @@ use async_trait::async_trait;
 use atcoder_problems_backend::server::GitHubUserResponse;
 use atcoder_problems_backend::server::{run_server, Authentication};
 use rand::Rng;
+use serde_json::Value;
+use sql_client::compressed_text::{CompressedTextClient, TextTable};
 use sql_client::models::Submission;
 use sql_client::PgPool;
 use tide::Result;
@@ -109,18 +111,24 @@ async fn test_user_submissions_fromtime() {
     });
     task::sleep(std::time::Duration::from_millis(1000)).await;
 
-    let submissions: Vec<Submission> = surf::get(url("/atcoder-api/v3/user/submissions?user=u1&from_second=3", port))
-        .await
-        .unwrap()
-        .body_json()
-        .await
-        .unwrap();
+    let submissions: Vec<Submission> = surf::get(url(
+        "/atcoder-api/v3/user/submissions?user=u1&from_second=3",
+        port,
+    ))
+    .await
+    .unwrap()
+    .body_json()
+    .await
+    .unwrap();
     assert_eq!(submissions.len(), 2);
     assert!(submissions.iter().all(|s| s.user_id.as_str() == "u1"));
 
-    let mut response = surf::get(url("/atcoder-api/v3/user/submissions?user=u2&from_second=6", port))
-        .await
-        .unwrap();
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/user/submissions?user=u2&from_second=6",
+        port,
+    ))
+    .await
+    .unwrap();
     let submissions: Vec<Submission> = response.body_json().await.unwrap();
     assert_eq!(submissions.len(), 3);
     assert!(submissions.iter().all(|s| s.user_id.as_str() == "u2"));
@@ -128,21 +136,41 @@ async fn test_user_submissions_fromtime() {
     assert_eq!(submissions[1].epoch_second, 7);
     assert_eq!(submissions[2].epoch_second, 200);
 
-    let mut response = surf::get(url("/atcoder-api/v3/user/submissions?user=u3&from_second=0", port))
-        .await
-        .unwrap();
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/user/submissions?user=u2&from_second=6&from_id=8",
+        port,
+    ))
+    .await
+    .unwrap();
+    let submissions: Vec<Submission> = response.body_json().await.unwrap();
+    assert_eq!(submissions.len(), 2);
+    assert_eq!(submissions[0].id, 9);
+    assert_eq!(submissions[1].id, 10);
+
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/user/submissions?user=u3&from_second=0",
+        port,
+    ))
+    .await
+    .unwrap();
     let submissions: Vec<Submission> = response.body_json().await.unwrap();
     assert_eq!(submissions.len(), 0);
 
-    let mut response = surf::get(url("/atcoder-api/v3/user/submissions?user=u1&from_second=-30", port))
-        .await
-        .unwrap();
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/user/submissions?user=u1&from_second=-30",
+        port,
+    ))
+    .await
+    .unwrap();
     let submissions: Vec<Submission> = response.body_json().await.unwrap();
     assert_eq!(submissions.len(), 5);
 
-    let mut response = surf::get(url("/atcoder-api/v3/user/submissions?user=u2&from_second=3000", port))
-        .await
-        .unwrap();
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/user/submissions?user=u2&from_second=3000",
+        port,
+    ))
+    .await
+    .unwrap();
     let submissions: Vec<Submission> = response.body_json().await.unwrap();
     assert_eq!(submissions.len(), 0);
 
@@ -169,6 +197,100 @@ async fn test_time_submissions() {
     assert_eq!(submissions.len(), 2);
     assert!(submissions.iter().all(|s| s.epoch_second >= 100));
 
+    let submissions: Vec<Submission> = surf::get(url("/atcoder-api/v3/from/100?from_id=5", port))
+        .await
+        .unwrap()
+        .body_json()
+        .await
+        .unwrap();
+    assert_eq!(submissions.len(), 1);
+    assert_eq!(submissions[0].id, 10);
+
+    let submissions: Vec<serde_json::Value> =
+        surf::get(url("/atcoder-api/v3/from/100?fields=id,result", port))
+            .await
+            .unwrap()
+            .body_json()
+            .await
+            .unwrap();
+    assert_eq!(submissions.len(), 2);
+    for submission in submissions.iter() {
+        let keys = submission.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, vec!["id", "result"]);
+    }
+
+    let mut response = surf::get(url(
+        "/atcoder-api/v3/from/100?fields=id,result&format=csv",
+        port,
+    ))
+    .await
+    .unwrap();
+    assert_eq!(
+        response.header("Content-Type").unwrap().as_str(),
+        "text/csv;charset=utf-8"
+    );
+    let csv = response.body_string().await.unwrap();
+    assert_eq!(csv.lines().next(), Some("id,result"));
+    assert_eq!(csv.lines().count(), 3);
+
+    server.race(ready(())).await;
+}
+
+#[async_std::test]
+async fn test_submission() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let submission: Submission = surf::get(url("/atcoder-api/v3/submission/3", port))
+        .recv_json()
+        .await
+        .unwrap();
+    assert_eq!(submission.id, 3);
+    assert_eq!(submission.user_id.as_str(), "u1");
+    assert_eq!(submission.result, "AC");
+
+    // The source code is returned only if it is asked for and stored.
+    let submission: Value = surf::get(url("/atcoder-api/v3/submission/3?source=true", port))
+        .recv_json()
+        .await
+        .unwrap();
+    assert!(submission.get("source_code").is_none());
+    let conn = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    conn.save_texts(
+        TextTable::SourceCodes,
+        &[("3".to_owned(), "fn main() {}".to_owned())],
+    )
+    .await
+    .unwrap();
+    let submission: Value = surf::get(url("/atcoder-api/v3/submission/3?source=true", port))
+        .recv_json()
+        .await
+        .unwrap();
+    assert_eq!(submission["source_code"], "fn main() {}");
+    assert_eq!(submission["id"], 3);
+    let submission: Value = surf::get(url("/atcoder-api/v3/submission/3", port))
+        .recv_json()
+        .await
+        .unwrap();
+    assert!(submission.get("source_code").is_none());
+
+    let response = surf::get(url("/atcoder-api/v3/submission/100", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = surf::get(url("/atcoder-api/v3/submission/abc", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
     server.race(ready(())).await;
 }
 
@@ -195,6 +317,41 @@ async fn test_invalid_path() {
     server.race(ready(())).await;
 }
 
+#[async_std::test]
+async fn test_trace_id() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/healthz", port)).await.unwrap();
+    let trace_id = response.header("X-Request-Id").unwrap().as_str();
+    assert_eq!(trace_id.len(), 16);
+
+    let response = surf::get(url("/atcoder-api/results", port))
+        .header("X-Request-Id", "client-trace-1")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(
+        response.header("X-Request-Id").unwrap().as_str(),
+        "client-trace-1"
+    );
+
+    // An unsafe trace ID is replaced with a new one.
+    let response = surf::get(url("/healthz", port))
+        .header("X-Request-Id", "a b")
+        .await
+        .unwrap();
+    assert_ne!(response.header("X-Request-Id").unwrap().as_str(), "a b");
+
+    server.race(ready(())).await;
+}
+
 #[async_std::test]
 async fn test_health_check() {
     let port = setup().await;
@@ -208,6 +365,29 @@ async fn test_health_check() {
 
     let response = surf::get(url("/healthcheck", port)).await.unwrap();
     assert_eq!(response.status(), 200);
+    let response = surf::get(url("/healthz", port)).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    // The latest submission in the data set is too old.
+    let response = surf::get(url("/readyz", port)).await.unwrap();
+    assert_eq!(response.status(), 503);
+
+    let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+        .await
+        .unwrap();
+    sql_client::query(
+        r"
+        INSERT INTO submissions (epoch_second, problem_id, contest_id, user_id, result, id, language, point, length)
+        VALUES ($1, 'p1', 'c1', 'u1', 'AC', 11, 'Rust', 0.0, 0)
+        ",
+    )
+    .bind(chrono::Utc::now().timestamp())
+    .execute(&pg_pool)
+    .await
+    .unwrap();
+    let response = surf::get(url("/readyz", port)).await.unwrap();
+    assert_eq!(response.status(), 200);
+
     server.race(ready(())).await;
 }
 
@@ -269,8 +449,20 @@ async fn test_users_and_time() {
     .await
     .unwrap();
     assert_eq!(submissions.len(), 2);
-    assert_eq!(submissions.iter().filter(|s| &s.user_id == "u1").count(), 1);
-    assert_eq!(submissions.iter().filter(|s| &s.user_id == "u2").count(), 1);
+    assert_eq!(
+        submissions
+            .iter()
+            .filter(|s| s.user_id.as_str() == "u1")
+            .count(),
+        1
+    );
+    assert_eq!(
+        submissions
+            .iter()
+            .filter(|s| s.user_id.as_str() == "u2")
+            .count(),
+        1
+    );
 
     server.race(ready(())).await;
 }
@@ -0,0 +1,146 @@
+use async_std::future::ready;
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{run_server, Authentication, GitHubUserResponse};
+use rand::Rng;
+use serde_json::{json, Value};
+use sql_client::PgPool;
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        unimplemented!()
+    }
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        unimplemented!()
+    }
+}
+
+async fn prepare_data_set(conn: &PgPool) {
+    sql_client::query(
+        r"INSERT INTO accepted_count (user_id, problem_count) VALUES ('u1', 10), ('u2', 20), ('u3', 30)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(
+        r"INSERT INTO rated_point_sum (user_id, point_sum) VALUES ('u1', 2000), ('u2', 1000)",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+    sql_client::query(r"INSERT INTO max_streaks (user_id, streak) VALUES ('u1', 3), ('u2', 5)")
+        .execute(conn)
+        .await
+        .unwrap();
+    sql_client::query(
+        r"
+        INSERT INTO language_count (user_id, simplified_language, problem_count) VALUES
+        ('u1', 'Rust', 8),
+        ('u1', 'C++', 5),
+        ('u2', 'Python', 20)
+        ",
+    )
+    .execute(conn)
+    .await
+    .unwrap();
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+async fn setup() -> u16 {
+    prepare_data_set(&utils::initialize_and_connect_to_test_sql().await).await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+#[async_std::test]
+async fn test_user_summary() {
+    let port = setup().await;
+    let server = task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(std::time::Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/atcoder-api/v3/user/info?user=u1", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "user_id": "u1",
+            "accepted_count": 10,
+            "accepted_count_rank": 2,
+            "rated_point_sum": 2000.0,
+            "rated_point_sum_rank": 0,
+            "max_streak": 3,
+            "max_streak_rank": 1,
+            "language_counts": [
+                {"language": "C++", "count": 5},
+                {"language": "Rust", "count": 8}
+            ]
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/user/info?user=u3", port))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(
+        response,
+        json!({
+            "user_id": "u3",
+            "accepted_count": 30,
+            "accepted_count_rank": 0,
+            "rated_point_sum": 0.0,
+            "rated_point_sum_rank": 2,
+            "max_streak": 0,
+            "max_streak_rank": 2,
+            "language_counts": []
+        })
+    );
+
+    let response = surf::get(url("/atcoder-api/v3/user/info", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = surf::post(url("/atcoder-api/v3/users/info", port))
+        .body(json!({"user_ids": ["u2", "u3"]}))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    let summaries = response.as_array().unwrap();
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0]["user_id"], "u2");
+    assert_eq!(summaries[0]["accepted_count"], 20);
+    assert_eq!(summaries[0]["rated_point_sum_rank"], 1);
+    assert_eq!(
+        summaries[0]["language_counts"],
+        json!([{"language": "Python", "count": 20}])
+    );
+    assert_eq!(summaries[1]["user_id"], "u3");
+    assert_eq!(summaries[1]["accepted_count_rank"], 0);
+
+    let user_ids = (0..101).map(|i| format!("u{}", i)).collect::<Vec<_>>();
+    let response = surf::post(url("/atcoder-api/v3/users/info", port))
+        .body(json!({ "user_ids": user_ids }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    server.race(ready(())).await;
+}
@@ -0,0 +1,112 @@
+use async_std::prelude::*;
+use async_std::task;
+use async_trait::async_trait;
+use atcoder_problems_backend::server::{run_server, Authentication, GitHubUserResponse};
+use rand::Rng;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tide::Result;
+
+pub mod utils;
+
+#[derive(Clone)]
+struct MockAuth;
+#[async_trait]
+impl Authentication for MockAuth {
+    async fn get_token(&self, _: &str) -> Result<String> {
+        Ok(String::new())
+    }
+
+    async fn get_user_id(&self, _: &str) -> Result<GitHubUserResponse> {
+        Ok(GitHubUserResponse::default())
+    }
+}
+
+async fn setup() -> u16 {
+    utils::initialize_and_connect_to_test_sql().await;
+    let mut rng = rand::thread_rng();
+    rng.gen::<u16>() % 30000 + 30000
+}
+
+fn url(path: &str, port: u16) -> String {
+    format!("http://localhost:{}{}", port, path)
+}
+
+#[async_std::test]
+async fn test_webhook() {
+    let port = setup().await;
+    let server = async_std::task::spawn(async move {
+        let pg_pool = sql_client::initialize_pool(utils::get_sql_url_from_env())
+            .await
+            .unwrap();
+        run_server(pg_pool, MockAuth, port).await.unwrap();
+    });
+    task::sleep(Duration::from_millis(1000)).await;
+
+    let response = surf::get(url("/internal-api/authorize?code=a", port))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 302);
+
+    let response = surf::post(url("/internal-api/webhook/add", port))
+        .header("Cookie", "token=a")
+        .body(json!({"url": "https://1.1.1.1/hook", "user_id": "chokudai", "result": "AC"}))
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response["url"], json!("https://1.1.1.1/hook"));
+    assert_eq!(response["user_id"], json!("chokudai"));
+    assert_eq!(response["result"], json!("AC"));
+    assert_eq!(response["contest_id"], json!(null));
+    let id = response["id"].as_str().unwrap().to_owned();
+    assert!(!response["secret"].as_str().unwrap().is_empty());
+
+    let response = surf::get(url("/internal-api/webhook/list", port))
+        .header("Cookie", "token=a")
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response.as_array().unwrap().len(), 1);
+    assert_eq!(response[0]["id"], json!(id));
+
+    // A webhook needs a public HTTPS URL and either the user or the contest.
+    for hook in &[
+        "http://1.1.1.1/hook",
+        "https://127.0.0.1/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://localhost/hook",
+    ] {
+        let response = surf::post(url("/internal-api/webhook/add", port))
+            .header("Cookie", "token=a")
+            .body(json!({"url": hook, "user_id": "chokudai"}))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 400);
+    }
+    let response = surf::post(url("/internal-api/webhook/add", port))
+        .header("Cookie", "token=a")
+        .body(json!({"url": "https://1.1.1.1/hook", "result": "AC"}))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = surf::post(url("/internal-api/webhook/delete", port))
+        .header("Cookie", "token=a")
+        .body(json!({ "id": id }))
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let response = surf::get(url("/internal-api/webhook/list", port))
+        .header("Cookie", "token=a")
+        .recv_json::<Value>()
+        .await
+        .unwrap();
+    assert_eq!(response, json!([]));
+
+    let response = surf::get(url("/internal-api/webhook/list", port))
+        .await
+        .unwrap();
+    assert!(!response.status().is_success());
+
+    server.race(async_std::future::ready(())).await;
+}
@@ -1,4 +1,4 @@
-use sql_client::{initialize_pool, PgPool};
+use sql_client::{execute_script, initialize_pool, PgPool};
 use std::fs::read_to_string;
 
 const SQL_FILE: &str = "../config/database-definition.sql";
@@ -13,8 +13,8 @@ pub fn get_sql_url_from_env() -> String {
 pub async fn initialize_and_connect_to_test_sql() -> PgPool {
     let conn = initialize_pool(get_sql_url_from_env()).await.unwrap();
 
-    for query_str in read_to_string(SQL_FILE).unwrap().split(";") {
-        sql_client::query(query_str).execute(&conn).await.unwrap();
-    }
+    // The definition has functions whose bodies contain `;`, so it is executed as a whole.
+    let script = read_to_string(SQL_FILE).unwrap();
+    execute_script(&conn, &script).await.unwrap();
     conn
 }
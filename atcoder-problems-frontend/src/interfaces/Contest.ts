@@ -5,6 +5,14 @@ export default interface Contest {
   readonly id: string;
   readonly duration_second: number;
   readonly title: string;
+  /** Derived by the backend. Missing in the responses of the older backends. */
+  readonly category?: string;
+  /** The lowest and the highest ratings which are rated, or null if unrated. */
+  readonly rated_range?: [number, number | null] | null;
+  /** The penalty minutes for each wrong submission, or null if not crawled yet. */
+  readonly penalty_minutes?: number | null;
+  /** Whether users can register after the start, or null if not crawled yet. */
+  readonly allows_late_join?: boolean | null;
 }
 
 // eslint-disable-next-line @typescript-eslint/no-explicit-any,@typescript-eslint/explicit-module-boundary-types
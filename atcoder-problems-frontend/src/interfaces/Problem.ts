@@ -4,6 +4,10 @@ export default interface Problem {
   readonly id: string;
   readonly contest_id: string;
   readonly title: string;
+  /** The index in the contest, e.g. "A" or "Ex". Missing in the older responses. */
+  readonly problem_index?: string;
+  /** The title without the index. Missing in the older responses. */
+  readonly name?: string;
 }
 
 // eslint-disable-next-line @typescript-eslint/no-explicit-any,@typescript-eslint/explicit-module-boundary-types
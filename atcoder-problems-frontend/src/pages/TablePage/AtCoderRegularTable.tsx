@@ -37,6 +37,9 @@ interface Props {
 }
 
 const getProblemHeaderAlphabetFromTitle = (problem: Problem) => {
+  if (problem.problem_index) {
+    return problem.problem_index;
+  }
   const list = problem.title.split(".");
   return list.length === 0 ? "" : list[0];
 };
@@ -39,6 +39,11 @@ const classifyOtherRatedContest = (contest: Contest): ContestCategory => {
 };
 
 export const classifyContest = (contest: Contest): ContestCategory => {
+  const category = ContestCategories.find((c) => c === contest.category);
+  if (category) {
+    return category;
+  }
+
   if (/^abc\d{3}$/.exec(contest.id)) {
     return "ABC";
   }
@@ -2,6 +2,8 @@
 -- https://github.com/launchbadge/sqlx/issues/484
 -- SET client_encoding = 'UTF8';
 
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
 DROP TABLE IF EXISTS submissions;
 CREATE TABLE submissions (
   id            BIGINT NOT NULL,
@@ -14,19 +16,85 @@ CREATE TABLE submissions (
   length        INT NOT NULL,
   result        VARCHAR(255) NOT NULL,
   execution_time  INT,
+  crawled_epoch_second  BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW()),
   PRIMARY KEY (id)
 );
 CREATE INDEX ON submissions (user_id);
 CREATE INDEX ON submissions (LOWER(user_id));
-CREATE INDEX ON submissions (epoch_second);
+CREATE INDEX ON submissions (epoch_second, id);
+CREATE INDEX ON submissions (contest_id, id);
+CREATE INDEX ON submissions (crawled_epoch_second);
+
+-- Notifies the API server of new and updated submissions, which are streamed to the clients.
+CREATE OR REPLACE FUNCTION notify_submission() RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_notify('submissions', row_to_json(NEW)::TEXT);
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE TRIGGER notify_inserted_submission AFTER INSERT ON submissions
+  FOR EACH ROW EXECUTE PROCEDURE notify_submission();
+CREATE TRIGGER notify_updated_submission AFTER UPDATE ON submissions
+  FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*) EXECUTE PROCEDURE notify_submission();
 
 DROP TABLE IF EXISTS problems;
 CREATE TABLE problems (
   id            VARCHAR(255) NOT NULL,
   contest_id    VARCHAR(255) NOT NULL,
   title         VARCHAR(255) NOT NULL,
+  problem_index VARCHAR(255) NOT NULL DEFAULT '',
+  name          VARCHAR(255) NOT NULL DEFAULT '',
   PRIMARY KEY (id)
 );
+CREATE INDEX ON problems USING GIN (title gin_trgm_ops);
+CREATE INDEX ON problems USING GIN (id gin_trgm_ops);
+
+DROP TABLE IF EXISTS crawl_jobs;
+DROP TABLE IF EXISTS crawl_requests;
+CREATE TABLE crawl_requests (
+  kind                    VARCHAR(255) NOT NULL CHECK (kind IN ('contest', 'user')),
+  target_id               VARCHAR(255) NOT NULL,
+  priority                INT NOT NULL DEFAULT 0,
+  requested_epoch_second  BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW()),
+  failed_attempts         INT NOT NULL DEFAULT 0,
+  PRIMARY KEY (kind, target_id)
+);
+
+-- Wakes up `crawl requests`, so that the requests inserted by the API server or by hand are crawled immediately.
+CREATE OR REPLACE FUNCTION notify_crawl_request() RETURNS TRIGGER AS $$
+BEGIN
+  PERFORM pg_notify('crawl_requests', NEW.kind);
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE TRIGGER notify_crawl_request AFTER INSERT OR UPDATE ON crawl_requests
+  FOR EACH ROW EXECUTE PROCEDURE notify_crawl_request();
+
+-- The history of the job runs, e.g. to find when the submissions of a contest were crawled last.
+DROP TABLE IF EXISTS crawler_runs;
+CREATE TABLE crawler_runs (
+  run_id                  VARCHAR(255) NOT NULL,
+  job                     VARCHAR(255) NOT NULL,
+  contest_ids             VARCHAR(255)[] NOT NULL DEFAULT '{}',
+  status                  VARCHAR(255) NOT NULL,
+  error                   TEXT DEFAULT NULL,
+  started_epoch_second    BIGINT NOT NULL,
+  finished_epoch_second   BIGINT NOT NULL,
+  rows_written            BIGINT NOT NULL,
+  failures                BIGINT NOT NULL,
+  PRIMARY KEY (run_id)
+);
+CREATE INDEX ON crawler_runs (job, finished_epoch_second);
+CREATE INDEX ON crawler_runs USING GIN (contest_ids);
+
+-- The pages of AtCoder which the crawlers failed to parse, e.g. after AtCoder changed them.
+DROP TABLE IF EXISTS parse_failures;
+CREATE TABLE parse_failures (
+  url           TEXT NOT NULL,
+  error         TEXT NOT NULL,
+  epoch_second  BIGINT NOT NULL
+);
+CREATE INDEX ON parse_failures (epoch_second);
 
 DROP TABLE IF EXISTS contests;
 CREATE TABLE contests (
@@ -35,6 +103,11 @@ CREATE TABLE contests (
   duration_second       BIGINT       NOT NULL,
   title                 VARCHAR(255) NOT NULL,
   rate_change           VARCHAR(255) NOT NULL,
+  category              VARCHAR(255) NOT NULL DEFAULT 'Other Contests',
+  rated_lower_bound     BIGINT,
+  rated_upper_bound     BIGINT,
+  penalty_minutes       BIGINT,
+  allows_late_join      BOOLEAN,
   PRIMARY KEY (id)
 );
 
@@ -45,6 +118,13 @@ CREATE TABLE solver (
   PRIMARY KEY (problem_id)
 );
 
+DROP TABLE IF EXISTS last_accepted;
+CREATE TABLE last_accepted (
+  problem_id            VARCHAR(255) NOT NULL,
+  epoch_second          BIGINT NOT NULL,
+  PRIMARY KEY (problem_id)
+);
+
 DROP TABLE IF EXISTS shortest;
 CREATE TABLE shortest (
   contest_id    VARCHAR(255)  NOT NULL,
@@ -75,6 +155,7 @@ CREATE TABLE accepted_count (
   problem_count INT           NOT NULL,
   PRIMARY KEY (user_id)
 );
+CREATE INDEX ON accepted_count (problem_count DESC, user_id);
 
 DROP TABLE IF EXISTS points;
 CREATE TABLE points (
@@ -84,12 +165,20 @@ CREATE TABLE points (
   PRIMARY KEY (problem_id)
 );
 
+DROP TABLE IF EXISTS problem_difficulties;
+CREATE TABLE problem_difficulties (
+  problem_id            VARCHAR(255) NOT NULL,
+  difficulty            DOUBLE PRECISION NOT NULL,
+  PRIMARY KEY (problem_id)
+);
+
 DROP TABLE IF EXISTS rated_point_sum;
 CREATE TABLE rated_point_sum (
   user_id         VARCHAR(255) NOT NULL,
   point_sum       DOUBLE PRECISION NOT NULL,
   PRIMARY KEY (user_id)
 );
+CREATE INDEX ON rated_point_sum (point_sum DESC, user_id);
 
 DROP TABLE IF EXISTS language_count;
 CREATE TABLE language_count (
@@ -98,6 +187,7 @@ CREATE TABLE language_count (
   problem_count         INT NOT NULL,
   PRIMARY KEY (user_id, simplified_language)
 );
+CREATE INDEX ON language_count (simplified_language, problem_count DESC, user_id);
 
 DROP TABLE IF EXISTS predicted_rating;
 CREATE TABLE predicted_rating (
@@ -119,6 +209,16 @@ CREATE TABLE max_streaks (
   streak                BIGINT NOT NULL,
   PRIMARY KEY (user_id)
 );
+CREATE INDEX ON max_streaks (streak DESC, user_id);
+
+DROP TABLE IF EXISTS current_streaks;
+CREATE TABLE current_streaks (
+  user_id               VARCHAR(255) NOT NULL,
+  streak                BIGINT NOT NULL,
+  last_epoch_second     BIGINT NOT NULL,
+  PRIMARY KEY (user_id)
+);
+CREATE INDEX ON current_streaks (streak DESC, user_id);
 
 DROP TABLE IF EXISTS submission_count;
 CREATE TABLE submission_count (
@@ -127,6 +227,166 @@ CREATE TABLE submission_count (
   PRIMARY KEY (user_id)
 );
 
+DROP TABLE IF EXISTS data_versions;
+CREATE TABLE data_versions (
+  name                  VARCHAR(255) NOT NULL,
+  version               BIGINT NOT NULL,
+  PRIMARY KEY (name)
+);
+
+-- The last submissions sent by `sink-bigquery` and so on, which they resume from after restarting.
+DROP TABLE IF EXISTS sink_cursors;
+CREATE TABLE sink_cursors (
+  name                  VARCHAR(255) NOT NULL,
+  epoch_second          BIGINT NOT NULL,
+  id                    BIGINT NOT NULL,
+  PRIMARY KEY (name)
+);
+
+-- The contests whose discoveries `publish-events` has published.
+DROP TABLE IF EXISTS published_contests;
+CREATE TABLE published_contests (
+  contest_id            VARCHAR(255) NOT NULL,
+  PRIMARY KEY (contest_id)
+);
+
+DROP TABLE IF EXISTS achievements;
+CREATE TABLE achievements (
+  user_id               VARCHAR(255) NOT NULL,
+  achievement_id        VARCHAR(255) NOT NULL,
+  achieved_epoch_second BIGINT NOT NULL,
+  PRIMARY KEY (user_id, achievement_id)
+);
+
+DROP TABLE IF EXISTS training_velocity;
+CREATE TABLE training_velocity (
+  user_id               VARCHAR(255) NOT NULL,
+  period_days           BIGINT NOT NULL,
+  accepted_count        BIGINT NOT NULL,
+  rated_point_sum       DOUBLE PRECISION NOT NULL,
+  PRIMARY KEY (user_id, period_days)
+);
+
+DROP TABLE IF EXISTS recent_difficulty_count;
+CREATE TABLE recent_difficulty_count (
+  user_id               VARCHAR(255) NOT NULL,
+  period_days           BIGINT NOT NULL,
+  difficulty_level      INT NOT NULL,
+  problem_count         BIGINT NOT NULL,
+  PRIMARY KEY (user_id, period_days, difficulty_level)
+);
+
+DROP TABLE IF EXISTS live_performances;
+CREATE TABLE live_performances (
+  contest_id            VARCHAR(255) NOT NULL,
+  user_id               VARCHAR(255) NOT NULL,
+  rank                  BIGINT NOT NULL,
+  performance           BIGINT NOT NULL,
+  old_rating            BIGINT NOT NULL,
+  new_rating            BIGINT NOT NULL,
+  PRIMARY KEY (contest_id, user_id)
+);
+
+DROP TABLE IF EXISTS contest_standings;
+CREATE TABLE contest_standings (
+  contest_id            VARCHAR(255) NOT NULL,
+  user_id               VARCHAR(255) NOT NULL,
+  rank                  BIGINT NOT NULL,
+  is_rated              BOOLEAN NOT NULL,
+  old_rating            BIGINT NOT NULL,
+  solved_count          BIGINT NOT NULL,
+  point                 DOUBLE PRECISION NOT NULL,
+  elapsed_second        BIGINT NOT NULL,
+  penalty               BIGINT NOT NULL,
+  PRIMARY KEY (contest_id, user_id)
+);
+
+DROP TABLE IF EXISTS contest_standings_updates;
+CREATE TABLE contest_standings_updates (
+  contest_id            VARCHAR(255) NOT NULL,
+  updated_epoch_second  BIGINT NOT NULL,
+  PRIMARY KEY (contest_id)
+);
+
+-- The profiles of the users crawled from AtCoder.
+DROP TABLE IF EXISTS users;
+CREATE TABLE users (
+  user_id               VARCHAR(255) NOT NULL,
+  rating                BIGINT,
+  highest_rating        BIGINT,
+  affiliation           VARCHAR(255),
+  country               VARCHAR(255),
+  birth_year_bucket     INT,
+  rated_matches         BIGINT,
+  updated_epoch_second  BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW()),
+  PRIMARY KEY (user_id)
+);
+CREATE INDEX ON users (country);
+CREATE INDEX ON users (updated_epoch_second);
+
+-- The users who asked to be forgotten by `forget-user`, whose submissions and profiles are not inserted again.
+DROP TABLE IF EXISTS forgotten_users;
+CREATE TABLE forgotten_users (
+  user_id               VARCHAR(255) NOT NULL,
+  forgotten_epoch_second BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW()),
+  PRIMARY KEY (user_id)
+);
+
+CREATE OR REPLACE FUNCTION skip_forgotten_user() RETURNS TRIGGER AS $$
+BEGIN
+  IF EXISTS (SELECT 1 FROM forgotten_users WHERE LOWER(user_id) = LOWER(NEW.user_id)) THEN
+    RETURN NULL;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE TRIGGER skip_forgotten_submission BEFORE INSERT OR UPDATE ON submissions
+  FOR EACH ROW EXECUTE PROCEDURE skip_forgotten_user();
+CREATE TRIGGER skip_forgotten_user_profile BEFORE INSERT OR UPDATE ON users
+  FOR EACH ROW EXECUTE PROCEDURE skip_forgotten_user();
+
+-- The audit log of `forget-user`. The submissions of an anonymized user are renamed to `pseudonym`.
+DROP TABLE IF EXISTS user_deletions;
+CREATE TABLE user_deletions (
+  id                    SERIAL NOT NULL,
+  user_id               VARCHAR(255) NOT NULL,
+  mode                  VARCHAR(255) NOT NULL,
+  pseudonym             VARCHAR(255),
+  reason                VARCHAR(255),
+  rows_affected         BIGINT NOT NULL DEFAULT 0,
+  epoch_second          BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW()),
+  PRIMARY KEY (id)
+);
+
+-- The texts are compressed by zstd with the dictionary `dictionary_id`, or without a dictionary if it is null.
+DROP TABLE IF EXISTS compression_dictionaries;
+CREATE TABLE compression_dictionaries (
+  id                    SERIAL NOT NULL,
+  table_name            VARCHAR(255) NOT NULL,
+  dictionary            BYTEA NOT NULL,
+  created_epoch_second  BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW()),
+  PRIMARY KEY (id)
+);
+CREATE INDEX ON compression_dictionaries (table_name, id);
+
+DROP TABLE IF EXISTS submission_source_codes;
+CREATE TABLE submission_source_codes (
+  submission_id         BIGINT NOT NULL,
+  dictionary_id         INT,
+  compressed            BYTEA NOT NULL,
+  PRIMARY KEY (submission_id)
+);
+CREATE INDEX ON submission_source_codes (dictionary_id);
+
+DROP TABLE IF EXISTS problem_statements;
+CREATE TABLE problem_statements (
+  problem_id            VARCHAR(255) NOT NULL,
+  dictionary_id         INT,
+  compressed            BYTEA NOT NULL,
+  PRIMARY KEY (problem_id)
+);
+CREATE INDEX ON problem_statements (dictionary_id);
+
 -- For internal services:
 DROP TABLE IF EXISTS internal_problem_list_items;
 DROP TABLE IF EXISTS internal_problem_lists;
@@ -137,6 +397,8 @@ DROP TABLE IF EXISTS internal_virtual_contests;
 
 DROP TABLE IF EXISTS internal_progress_reset;
 
+DROP TABLE IF EXISTS internal_webhooks;
+
 DROP TABLE IF EXISTS internal_users;
 
 CREATE TABLE internal_users (
@@ -157,6 +419,7 @@ CREATE TABLE internal_problem_list_items (
   internal_list_id      VARCHAR(255) REFERENCES internal_problem_lists ON DELETE CASCADE ON UPDATE CASCADE,
   problem_id            VARCHAR(255) NOT NULL,
   memo                  VARCHAR(255) DEFAULT '',
+  item_order            INTEGER NOT NULL DEFAULT 0,
   PRIMARY KEY (internal_list_id, problem_id)
 );
 CREATE INDEX ON internal_problem_list_items (internal_list_id);
@@ -199,3 +462,15 @@ CREATE TABLE internal_progress_reset (
   PRIMARY KEY (internal_user_id, problem_id)
 );
 CREATE INDEX ON internal_progress_reset (internal_user_id);
+
+CREATE TABLE internal_webhooks (
+  id                  VARCHAR(255) NOT NULL,
+  internal_user_id    VARCHAR(255) REFERENCES internal_users ON DELETE CASCADE ON UPDATE CASCADE,
+  url                 VARCHAR(2048) NOT NULL,
+  secret              VARCHAR(255) NOT NULL,
+  user_id             VARCHAR(255) DEFAULT NULL,
+  result              VARCHAR(255) DEFAULT NULL,
+  contest_id          VARCHAR(255) DEFAULT NULL,
+  PRIMARY KEY (id)
+);
+CREATE INDEX ON internal_webhooks (internal_user_id);
@@ -4,9 +4,9 @@ import math
 import random
 import statistics
 from collections import defaultdict
+from dataclasses import dataclass
 from html.parser import HTMLParser
 
-import boto3
 import requests
 
 from rating import RatingSystem, ContestType
@@ -65,6 +65,16 @@ prohibited_problem_ids = {
 }
 
 
+@dataclass
+class ModelParameters:
+    # the minimum number of the users who got AC to estimate the time model
+    min_time_model_users: int = 5
+    # the minimum number of the users to estimate the difficulty model
+    min_difficulty_model_users: int = 40
+    # the estimations harder than this are rejected
+    max_difficulty: float = 6000
+
+
 class AtCoderCSRFExtractor(HTMLParser):
     def __init__(self):
         super(AtCoderCSRFExtractor, self).__init__()
@@ -193,7 +203,7 @@ def is_agc_easiest_problem(task_screen_name):
     return task_screen_name.startswith("agc") and task_screen_name.endswith("_a")
 
 
-def fit_problem_model(user_results, task_screen_name):
+def fit_problem_model(user_results, task_screen_name, params=ModelParameters()):
     max_score = max(
         task_result[task_screen_name + ".score"] for task_result in user_results
     )
@@ -227,7 +237,7 @@ def fit_problem_model(user_results, task_screen_name):
         and task_result[task_screen_name + ".ac"] == 1.0
     ]
     model = {}
-    if len(time_model_sample_users) < 5:
+    if len(time_model_sample_users) < params.min_time_model_users:
         print(
             f"{task_screen_name}: insufficient data ({len(time_model_sample_users)} users). skip estimating time model."
         )
@@ -273,7 +283,7 @@ def fit_problem_model(user_results, task_screen_name):
             for task_result in recurring_users
             if not task_result["retreated"]
         ]
-    if len(difficulty_dataset) < 40:
+    if len(difficulty_dataset) < params.min_difficulty_model_users:
         print(
             f"{task_screen_name}: insufficient data ({len(difficulty_dataset)} users). skip estimating difficulty model."
         )
@@ -301,7 +311,7 @@ def fit_problem_model(user_results, task_screen_name):
         print(f"difficulty: {difficulty}, discrimination: {discrimination}")
         if discrimination < 0:
             print("discrimination is negative. ignoring unreliable estimation.")
-        elif difficulty > 6000:
+        elif difficulty > params.max_difficulty:
             print("extreme difficulty. rejecting this estimation.")
         else:
             model["difficulty"] = difficulty
@@ -323,15 +333,22 @@ def fit_problem_model(user_results, task_screen_name):
     return model
 
 
-def fetch_dataset_for_contest(
-    contest_name, contest_type, existing_problem, session, skip_if_no_user_has_rating
-):
+def fetch_standings(contest_name, session):
     try:
-        results = session.get(
+        return session.get(
             f"https://atcoder.jp/contests/{contest_name}/standings/json"
         ).json()
     except json.JSONDecodeError as e:
-        print(f"Failed to decode standings of {contest_id}: {e}")
+        print(f"Failed to decode standings of {contest_name}: {e}")
+        return None
+
+
+def dataset_from_standings(
+    results, contest_name, contest_type, existing_problem, skip_if_no_user_has_rating
+):
+    # the standings-dumper writes an empty list if it failed to fetch the standings.
+    if not isinstance(results, dict):
+        print(f"There are no standings of {contest_name}. Ignoring.")
         return {}, []
     task_names = {
         task["TaskScreenName"]: task["TaskName"] for task in results["TaskInfo"]
@@ -438,12 +455,16 @@ def infer_contest_type(contest) -> ContestType:
 
 
 def all_rated_contests():
-    # Gets all contest IDs and their contest type
-    # The result is ordered by the start time.
     contests = requests.get(
         "https://kenkoooo.com/atcoder/resources/contests.json"
     ).json()
-    contests.sort(key=lambda contest: contest["start_epoch_second"])
+    return rated_contests(contests)
+
+
+def rated_contests(contests):
+    # Gets all contest IDs and their contest type from contests.json
+    # The result is ordered by the start time.
+    contests = sorted(contests, key=lambda contest: contest["start_epoch_second"])
     contests_and_types = [
         (contest["id"], infer_contest_type(contest)) for contest in contests
     ]
@@ -458,6 +479,11 @@ def all_contest_problems():
     problems = requests.get(
         "https://kenkoooo.com/atcoder/resources/problems.json"
     ).json()
+    return contest_problems_of(problems)
+
+
+def contest_problems_of(problems):
+    # Groups the problem IDs in problems.json by the contests
     # exclude marathon-like problems
     problems = [
         problem for problem in problems if problem["id"] not in prohibited_problem_ids
@@ -470,16 +496,36 @@ def all_contest_problems():
     }
 
 
-def run(target, overwrite, session):
+def run(target, overwrite, session, params=ModelParameters()):
+    return estimate(
+        all_rated_contests(),
+        all_contest_problems(),
+        get_current_models(),
+        lambda contest: fetch_standings(contest, session),
+        target,
+        overwrite,
+        params,
+    )
+
+
+def estimate(
+    all_contests,
+    contest_problems,
+    current_models,
+    load_standings,
+    target,
+    overwrite,
+    params=ModelParameters(),
+):
+    # Estimates the problem models of the target contests, whose standings are loaded by
+    # load_standings(contest_id). It does not access the network by itself, so that the models
+    # can be reproduced from the dumped files by offline_generate.py.
     recompute_history = target is None and overwrite
     if target is None:
-        target = all_rated_contests()
+        target = all_contests
     else:
-        all_contests = all_rated_contests()
         target = [contest for contest in all_contests if contest[0] in target]
-    current_models = get_current_models()
     existing_problems = current_models.keys() if not overwrite else set()
-    contest_problems = all_contest_problems()
 
     print(f"Fetching dataset from {len(target)} contests.")
     dataset_by_problem = defaultdict(list)
@@ -495,8 +541,12 @@ def run(target, overwrite, session):
             )
             continue
         is_old_contest = not contest_type.is_rated
-        user_results_by_problem, standings = fetch_dataset_for_contest(
-            contest, contest_type, existing_problems, session, not recompute_history
+        user_results_by_problem, standings = dataset_from_standings(
+            load_standings(contest),
+            contest,
+            contest_type,
+            existing_problems,
+            not recompute_history,
         )
         for problem, data_points in user_results_by_problem.items():
             if recompute_history:
@@ -537,7 +587,7 @@ def run(target, overwrite, session):
     print(f"Estimating time models of {len(dataset_by_problem)} problems.")
     results = current_models
     for problem, data_points in dataset_by_problem.items():
-        model = fit_problem_model(data_points, problem)
+        model = fit_problem_model(data_points, problem, params)
         model["is_experimental"] = problem in experimental_problems
         results[problem] = model
     return results
@@ -570,6 +620,9 @@ def handler(event, context):
     session = login(atcoder_user, atcoder_pass)
     results = run(target, overwrite, session)
     print("Estimation completed. Saving results in S3")
+    # boto3 is provided by the runtime of Lambda, and not required to run the estimator locally.
+    import boto3
+
     s3 = boto3.resource("s3")
     s3.Object(bucket, object_key).put(
         Body=json.dumps(results), ContentType="application/json"
@@ -0,0 +1,94 @@
+# Estimates the problem models entirely from the dumped files, without AtCoder or the database.
+#
+#   python offline_generate.py --contests contests.json --problems problems.json \
+#       --standings-dir ../standings-dumper/standings --output problem-models.json
+#
+# contests.json and problems.json are the ones in https://kenkoooo.com/atcoder/resources/, and the
+# standings are the files written by standings-dumper/local_generate.py. The parameters of the
+# model can be changed by the options to see how the estimations change.
+import argparse
+import json
+from pathlib import Path
+
+from function import ModelParameters, contest_problems_of, estimate, rated_contests
+
+
+def standings_loader(standings_dir):
+    def load_standings(contest_id):
+        path = Path(standings_dir) / f"{contest_id}.json"
+        if not path.exists():
+            print(f"{path} does not exist.")
+            return None
+        with path.open() as f:
+            return json.load(f)
+
+    return load_standings
+
+
+def main():
+    defaults = ModelParameters()
+    parser = argparse.ArgumentParser(
+        description="Estimates the problem models from the dumped standings."
+    )
+    parser.add_argument("--contests", required=True, help="the path of contests.json")
+    parser.add_argument("--problems", required=True, help="the path of problems.json")
+    parser.add_argument(
+        "--standings-dir",
+        required=True,
+        help="the directory of the standings dumped as {contest_id}.json",
+    )
+    parser.add_argument(
+        "--models",
+        help="the existing problem-models.json, whose models are kept unless --overwrite",
+    )
+    parser.add_argument("--output", default="problem-models.json")
+    parser.add_argument(
+        "--target", nargs="*", help="the contest IDs to estimate (default: all)"
+    )
+    parser.add_argument(
+        "--overwrite",
+        action="store_true",
+        help="estimate the existing models again. With all the contests, the ratings in "
+        "the contests before the official rating system are emulated as well.",
+    )
+    parser.add_argument(
+        "--min-time-model-users", type=int, default=defaults.min_time_model_users
+    )
+    parser.add_argument(
+        "--min-difficulty-model-users",
+        type=int,
+        default=defaults.min_difficulty_model_users,
+    )
+    parser.add_argument("--max-difficulty", type=float, default=defaults.max_difficulty)
+    args = parser.parse_args()
+
+    with open(args.contests) as f:
+        contests = rated_contests(json.load(f))
+    with open(args.problems) as f:
+        contest_problems = contest_problems_of(json.load(f))
+    current_models = {}
+    if args.models is not None:
+        with open(args.models) as f:
+            current_models = json.load(f)
+    params = ModelParameters(
+        min_time_model_users=args.min_time_model_users,
+        min_difficulty_model_users=args.min_difficulty_model_users,
+        max_difficulty=args.max_difficulty,
+    )
+
+    results = estimate(
+        contests,
+        contest_problems,
+        current_models,
+        standings_loader(args.standings_dir),
+        args.target or None,
+        args.overwrite,
+        params,
+    )
+    with open(args.output, "w") as f:
+        json.dump(results, f)
+    print(f"Saved {len(results)} problem models in {args.output}")
+
+
+if __name__ == "__main__":
+    main()